@@ -17,6 +17,10 @@ pushd migration
 cargo build
 popd
 
+pushd das_all
+cargo build
+popd
+
 # Not used by Helius
 # pushd metaplex-rpc-proxy
 # cargo build
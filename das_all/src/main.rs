@@ -0,0 +1,76 @@
+use {
+    anyhow::Context,
+    das_api::{
+        config::{load_config_from as load_api_config_from, Config as ApiConfig},
+        error::DasApiError,
+        start_server,
+    },
+    figment::{
+        providers::{Env, Format, Toml},
+        Figment,
+    },
+    log::info,
+    nft_ingester::{
+        config::{init_logger, load_config_from as load_ingester_config_from, IngesterConfig},
+        error::IngesterError,
+        metrics::{flush_metrics, setup_metrics},
+        service,
+    },
+    std::env,
+};
+
+const CONFIG_FILE_ENV: &str = "DAS_CONFIG_FILE";
+
+// The `[api]` or `[ingester]` table of the config file, along with the keys of `[default]` which
+// both share. `APP_` and `INGESTER_` env vars override the file.
+fn config_file(section: &str) -> Figment {
+    let figment = Figment::new();
+    match env::var(CONFIG_FILE_ENV) {
+        Ok(path) => figment.merge(Toml::file(path).nested()).select(section),
+        Err(_) => figment.select(section),
+    }
+}
+
+fn load_api_config() -> Result<ApiConfig, DasApiError> {
+    load_api_config_from(config_file("api").merge(Env::prefixed("APP_").profile("api")))
+}
+
+// Also what the ingester reloads on SIGHUP, so changes to its table apply without a restart.
+fn load_ingester_config() -> Result<IngesterConfig, IngesterError> {
+    load_ingester_config_from(
+        config_file("ingester").merge(Env::prefixed("INGESTER_").profile("ingester")),
+    )
+}
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() -> anyhow::Result<()> {
+    let log_filter = init_logger();
+    info!("Starting das-all");
+
+    let ingester_config = load_ingester_config().context("invalid ingester config")?;
+    let api_config = load_api_config().context("invalid api config")?;
+
+    // A process has a single statsd client, the API's metrics are sent with the ingester's
+    // prefix and tags.
+    setup_metrics(&ingester_config);
+
+    let server_handle = start_server(api_config)
+        .await
+        .context("failed to start the api server")?;
+    info!("Server started");
+
+    let res = service::run(
+        ingester_config,
+        log_filter,
+        load_ingester_config,
+        service::shutdown_signal(),
+    )
+    .await;
+
+    info!("Shutting down server");
+    if server_handle.stop().is_ok() {
+        server_handle.stopped().await;
+    }
+    flush_metrics();
+    res.context("ingester failed")
+}
@@ -1,28 +1,49 @@
 use digital_asset_types::{
     dao::{
-        scopes::asset::get_grouping,
+        cursor::AssetCursor,
+        schema::{check_schema_version, is_valid_schema_name, SchemaCompatibility, SCHEMA_VERSION},
+        scopes::asset::{get_by_id, get_grouping, get_id_by_leaf},
         sea_orm_active_enums::{
             OwnerType, RoyaltyTargetType, SpecificationAssetClass, SpecificationVersions,
         },
-        SearchAssetsQuery,
+        AssetLeafAddress, SearchAssetsQuery, COLLECTION_GROUP_KEY,
     },
     dapi::{
-        get_asset, get_assets_by_authority, get_assets_by_creator, get_assets_by_group,
-        get_assets_by_owner, get_proof_for_asset, get_signatures_for_asset, search_assets,
+        add_escrow_treatment, add_full_metadata, add_lock_state, add_mutated_flag,
+        add_parent_assets, add_price_info, add_token_info, add_tree_freshness, get_asset,
+        get_asset_at_slot, get_asset_authority_history, get_asset_id_by_leaf,
+        get_asset_id_by_leaf_hash, get_asset_metadata_history, get_assets_by_authority,
+        get_assets_by_creator, get_assets_by_group, get_assets_by_leaf_delegate,
+        get_assets_by_owner, get_assets_by_owner_grouped, get_assets_by_parent_asset,
+        get_canopy_depth, get_collection_activity_stats, get_collection_traits, get_missing_proof,
+        get_nft_editions, get_owner_diff, get_proof_for_asset, get_signatures_for_asset,
+        get_signatures_for_asset_batch, get_token_balance_history, get_tree_audit,
+        is_group_key_indexed, is_hidden, proof_matches_root, search_assets, trim_canopy,
+        MissingProof,
     },
     rpc::{
-        filter::{AssetSortBy, SearchConditionType},
+        filter::{AssetSortBy, AssetSorting, Commitment, SearchConditionType},
         response::GetGroupingResponse,
-        transform::AssetTransform,
+        transform::{AssetTransform, UrlRewrite},
     },
-    rpc::{OwnershipModel, RoyaltyModel},
 };
+use log::warn;
+use mpl_token_metadata::pda::find_master_edition_account;
 use open_rpc_derive::document_rpc;
 use sea_orm::{sea_query::ConditionType, ConnectionTrait, DbBackend, Statement};
+use solana_sdk::pubkey::Pubkey;
 
 use crate::{
     feature_flag::{get_feature_flags, FeatureFlags},
-    validation::validate_opt_pubkey,
+    index_advisor::IndexAdvisor,
+    limits::MethodLimits,
+    native_balance::NativeBalanceCache,
+    private_assets::PrivateAssets,
+    query_plans::QueryPlanSampler,
+    query_watchdog::QueryWatchdog,
+    transfer_simulator::{CnftTransfer, TransferSimulator},
+    tree_roots::TreeRootCache,
+    validation::{validate_hash, validate_network, validate_opt_pubkey},
 };
 use open_rpc_schema::document::OpenrpcDocument;
 use {
@@ -32,54 +53,186 @@ use {
     crate::DasApiError,
     async_trait::async_trait,
     digital_asset_types::rpc::{
-        response::AssetList, response::TransactionSignatureList, Asset, AssetProof,
+        response::AssetAtSlot, response::AssetList, response::AuthorityHistory,
+        response::CnftTransferSimulation, response::CollectionActivityStats,
+        response::CollectionTraits, response::MetadataHistory, response::NativeBalance,
+        response::NftEditions, response::OwnerDiff, response::TokenBalanceHistory,
+        response::TransactionSignatureList, response::TreeAudit, Asset, AssetProof, OwnershipModel,
     },
     sea_orm::{DatabaseConnection, DbErr, SqlxPostgresConnector},
-    sqlx::postgres::PgPoolOptions,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+    std::str::FromStr,
 };
 
 pub struct DasApi {
     db_connection: DatabaseConnection,
     cdn_prefix: Option<String>,
+    url_rewrites: Vec<UrlRewrite>,
     feature_flags: FeatureFlags,
+    limits: MethodLimits,
+    tree_roots: Option<TreeRootCache>,
+    native_balances: Option<NativeBalanceCache>,
+    transfer_simulator: Option<TransferSimulator>,
+    default_network: Option<String>,
+    private_assets: PrivateAssets,
+}
+
+// The API only reads, so a database migrated past this build still serves everything the build
+// knows about. One that isn't migrated far enough is missing tables or columns the queries use.
+async fn check_schema(config: &Config, conn: &DatabaseConnection) -> Result<(), DasApiError> {
+    let msg = match check_schema_version(conn).await? {
+        SchemaCompatibility::Current => return Ok(()),
+        SchemaCompatibility::Ahead { applied } => {
+            warn!(
+                "Database is at migration {}, newer than {} this build was written for",
+                applied, SCHEMA_VERSION
+            );
+            return Ok(());
+        }
+        SchemaCompatibility::Behind { applied } => format!(
+            "database is at migration {}, this build needs {}, run the migrations first",
+            applied.as_deref().unwrap_or("none"),
+            SCHEMA_VERSION
+        ),
+    };
+    if config.allow_schema_mismatch.unwrap_or(false) {
+        warn!("Schema mismatch allowed: {}", msg);
+        return Ok(());
+    }
+    Err(DasApiError::SchemaMismatch(msg))
+}
+
+// Cursors resume after the last asset of a page by a key the asset never changes, which the
+// update and recent action sorts aren't.
+fn validate_cursor_sort(
+    cursor: &Option<String>,
+    sort_by: &AssetSorting,
+) -> Result<(), DasApiError> {
+    match (cursor, &sort_by.sort_by) {
+        (Some(_), AssetSortBy::Updated | AssetSortBy::RecentAction) => {
+            Err(DasApiError::PaginationInvalid(
+                "cursors only page through assets sorted by id or creation".to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
 }
 
 impl DasApi {
     pub async fn from_config(config: Config) -> Result<Self, DasApiError> {
+        let limits = MethodLimits::from_config(&config);
+        let mut options = PgConnectOptions::from_str(&config.database_url)?;
+        if let Some(timeout) = limits.max_timeout() {
+            options = options.options([("statement_timeout", timeout.as_millis().to_string())]);
+        }
+        if let Some(watchdog_config) = &config.query_watchdog_config {
+            options = options.application_name(&watchdog_config.get_application_name());
+        }
+        if let Some(capacity) = config.statement_cache_capacity {
+            options = options.statement_cache_capacity(capacity);
+        }
+        if let Some(schema) = &config.database_schema {
+            if !is_valid_schema_name(schema) {
+                return Err(DasApiError::ConfigurationError(format!(
+                    "invalid database schema {}",
+                    schema
+                )));
+            }
+            options = options.options([("search_path", schema)]);
+        }
         let pool = PgPoolOptions::new()
             .max_connections(250)
-            .connect(&config.database_url)
+            .connect_with(options)
             .await?;
         let feature_flags = get_feature_flags(&config);
-        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let mut conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
+        let sampler = config.query_plan_config.as_ref().map(|plan_config| {
+            QueryPlanSampler::new(
+                plan_config,
+                SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone()),
+            )
+        });
+        let advisor = config.index_advisor_config.as_ref().map(|advisor_config| {
+            let advisor = IndexAdvisor::new(
+                advisor_config,
+                SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone()),
+            );
+            advisor.spawn_reporter();
+            advisor
+        });
+        if let Some(watchdog_config) = &config.query_watchdog_config {
+            QueryWatchdog::new(
+                watchdog_config,
+                SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone()),
+            )
+            .spawn();
+        }
+        if sampler.is_some() || advisor.is_some() {
+            conn.set_metric_callback(move |info| {
+                if let Some(sampler) = &sampler {
+                    sampler.on_query(info);
+                }
+                if let Some(advisor) = &advisor {
+                    advisor.on_query(info);
+                }
+            });
+        }
+        check_schema(&config, &conn).await?;
+        if let Some(network) = &config.default_network {
+            validate_network(network).map_err(|_| {
+                DasApiError::ConfigurationError(format!("invalid default network {}", network))
+            })?;
+        }
+        let private_assets =
+            PrivateAssets::from_config(config.private_assets.clone().unwrap_or_default())?;
         Ok(DasApi {
             db_connection: conn,
             cdn_prefix: config.cdn_prefix,
+            url_rewrites: config.content_url_rewrites.unwrap_or_default(),
             feature_flags,
+            limits,
+            tree_roots: config.rpc_url.clone().map(TreeRootCache::new),
+            native_balances: config.rpc_url.clone().map(NativeBalanceCache::new),
+            transfer_simulator: config.rpc_url.map(TransferSimulator::new),
+            default_network: config.default_network,
+            private_assets,
         })
     }
 
     fn validate_pagination(
         &self,
+        method: &str,
         limit: &Option<u32>,
         page: &Option<u32>,
         before: &Option<String>,
         after: &Option<String>,
+        cursor: &Option<String>,
     ) -> Result<(), DasApiError> {
-        if page.is_none() && before.is_none() && after.is_none() {
+        if let Some(cursor) = cursor {
+            if page.is_some() || before.is_some() || after.is_some() {
+                return Err(DasApiError::PaginationError);
+            }
+            AssetCursor::parse(cursor)
+                .map_err(|_| DasApiError::PaginationInvalid("invalid cursor".to_string()))?;
+        } else if page.is_none() && before.is_none() && after.is_none() {
             return Err(DasApiError::PaginationEmptyError);
         }
 
         if let Some(limit) = limit {
-            // make config item
-            if *limit > 1000 {
-                return Err(DasApiError::PaginationError);
+            let max = self.limits.max_page_size(method);
+            if *limit > max {
+                return Err(DasApiError::QueryTooComplex(format!(
+                    "limit {} is above the maximum page size of {}",
+                    limit, max
+                )));
             }
         }
 
         if let Some(page) = page {
             if *page == 0 {
-                return Err(DasApiError::PaginationEmptyError);
+                return Err(DasApiError::PaginationInvalid(
+                    "pages start at 1".to_string(),
+                ));
             }
 
             // make config item
@@ -99,6 +252,230 @@ impl DasApi {
         Ok(())
     }
 
+    async fn add_ownership_info(&self, assets: &mut [Asset]) -> Result<(), DasApiError> {
+        if self.feature_flags.enable_escrow_treatment {
+            add_escrow_treatment(&self.db_connection, assets).await?;
+        }
+        if self.feature_flags.enable_parent_assets {
+            add_parent_assets(&self.db_connection, assets).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_price_info(
+        &self,
+        assets: &mut [Asset],
+        options: &Option<DisplayOptions>,
+    ) -> Result<(), DasApiError> {
+        if options.as_ref().map(|o| o.show_price_info).unwrap_or(false) {
+            add_price_info(&self.db_connection, assets).await?;
+        }
+        Ok(())
+    }
+
+    // Counting every match costs as much as the page again, so it takes both the server and the
+    // request asking for it.
+    fn grand_total(&self, options: &Option<DisplayOptions>) -> bool {
+        self.feature_flags.enable_grand_total_query
+            && options
+                .as_ref()
+                .map(|o| o.show_grand_total)
+                .unwrap_or(false)
+    }
+
+    async fn add_native_balance(
+        &self,
+        assets: &mut AssetList,
+        owner: Option<&Pubkey>,
+        options: &Option<DisplayOptions>,
+    ) -> Result<(), DasApiError> {
+        if !options
+            .as_ref()
+            .map(|o| o.show_native_balance)
+            .unwrap_or(false)
+        {
+            return Ok(());
+        }
+        let owner = owner.ok_or_else(|| {
+            DasApiError::ValidationError("showNativeBalance needs an ownerAddress".to_string())
+        })?;
+        let native_balances = self.native_balances.as_ref().ok_or_else(|| {
+            DasApiError::ConfigurationError("showNativeBalance needs APP_RPC_URL".to_string())
+        })?;
+        // The assets are served without the balance rather than failing with the RPC node.
+        match native_balances.get(owner).await {
+            Ok(lamports) => assets.native_balance = Some(NativeBalance { lamports }),
+            Err(e) => warn!("Failed to read the native balance of {}: {}", owner, e),
+        }
+        Ok(())
+    }
+
+    // Reads the mints and the owners' token accounts of the page, under the method's limits.
+    async fn add_token_info(
+        &self,
+        method: &str,
+        assets: &mut [Asset],
+        options: &Option<DisplayOptions>,
+    ) -> Result<(), DasApiError> {
+        if options.as_ref().map(|o| o.show_token_info).unwrap_or(false) {
+            self.limits
+                .run(method, add_token_info(&self.db_connection, assets))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn add_full_metadata(
+        &self,
+        assets: &mut [Asset],
+        options: &Option<DisplayOptions>,
+        transform: &AssetTransform,
+    ) -> Result<(), DasApiError> {
+        if options.as_ref().map(|o| o.full_metadata).unwrap_or(false) {
+            add_full_metadata(&self.db_connection, assets, transform).await?;
+        }
+        Ok(())
+    }
+
+    fn asset_transform(
+        &self,
+        options: &Option<DisplayOptions>,
+    ) -> Result<AssetTransform, DasApiError> {
+        let network = self.request_network(options.as_ref().and_then(|o| o.network.clone()))?;
+        Ok(AssetTransform {
+            cdn_prefix: self.cdn_prefix.clone(),
+            url_rewrites: self.url_rewrites.clone(),
+            require_verified_collection: options
+                .as_ref()
+                .map(|o| o.require_verified_collection)
+                .unwrap_or(false),
+            network,
+            hidden: self.private_assets.hidden(),
+        })
+    }
+
+    // The network a request is served from, the one it names or the server's default.
+    fn request_network(&self, requested: Option<String>) -> Result<Option<String>, DasApiError> {
+        let network = requested.or_else(|| self.default_network.clone());
+        if let Some(network) = &network {
+            validate_network(network)?;
+        }
+        Ok(network)
+    }
+
+    // Answers for the assets of other networks than the request's as if they weren't indexed.
+    // The addresses of assets not indexed yet are left to the method.
+    async fn check_network(
+        &self,
+        address: &AssetLeafAddress,
+        network: &Option<String>,
+    ) -> Result<(), DasApiError> {
+        let network = match network {
+            Some(network) => network,
+            None => return Ok(()),
+        };
+        let (id, name) = match address {
+            AssetLeafAddress::Id(id) => (id.clone(), bs58::encode(id).into_string()),
+            AssetLeafAddress::Leaf { tree, leaf_idx } => {
+                let name = format!("{}:{}", bs58::encode(tree).into_string(), leaf_idx);
+                match get_id_by_leaf(&self.db_connection, tree.clone(), *leaf_idx).await? {
+                    Some(id) => (id, name),
+                    None => return Ok(()),
+                }
+            }
+        };
+        match get_by_id(&self.db_connection, id, false).await {
+            Ok(asset) if asset.asset.network != *network => Err(DasApiError::AssetNotFound(name)),
+            Ok(_) | Err(DbErr::RecordNotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Answers for the assets hidden from the request as if they weren't indexed.
+    async fn check_visible(&self, id: &str, id_bytes: Vec<u8>) -> Result<(), DasApiError> {
+        let hidden = self.private_assets.hidden();
+        if hidden.is_empty() {
+            return Ok(());
+        }
+        match get_by_id(&self.db_connection, id_bytes, false).await {
+            Ok(asset) if is_hidden(&asset, &hidden) => {
+                Err(DasApiError::AssetNotFound(id.to_string()))
+            }
+            Ok(_) | Err(DbErr::RecordNotFound(_)) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn check_address_visible(&self, address: &AssetLeafAddress) -> Result<(), DasApiError> {
+        match address {
+            AssetLeafAddress::Id(id) => {
+                self.check_visible(&bs58::encode(id).into_string(), id.clone())
+                    .await
+            }
+            AssetLeafAddress::Leaf { tree, leaf_idx } => {
+                if self.private_assets.hidden().hides_tree(tree) {
+                    return Err(DasApiError::AssetNotFound(format!(
+                        "{}:{}",
+                        bs58::encode(tree).into_string(),
+                        leaf_idx
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn hides_collection(&self, collection: &str) -> bool {
+        self.private_assets.hidden().hides_collection(collection)
+    }
+
+    // Why the asset has no proof, for clients to tell a missing asset from one still indexing.
+    async fn missing_proof(
+        &self,
+        id: &str,
+        id_bytes: Vec<u8>,
+        commitment: Commitment,
+    ) -> DasApiError {
+        match get_missing_proof(&self.db_connection, id_bytes).await {
+            Ok(MissingProof::AssetNotFound) => DasApiError::AssetNotFound(id.to_string()),
+            Ok(MissingProof::NotCompressed) => DasApiError::ProofUnavailable(
+                id.to_string(),
+                "the asset isn't compressed".to_string(),
+            ),
+            Ok(MissingProof::TreeNotIndexed(tree)) => {
+                DasApiError::TreeNotIndexed(id.to_string(), bs58::encode(tree).into_string())
+            }
+            Ok(MissingProof::LeafNotIndexed(_)) if commitment == Commitment::Finalized => {
+                DasApiError::ProofUnavailable(
+                    id.to_string(),
+                    "its leaf isn't finalized yet".to_string(),
+                )
+            }
+            Ok(MissingProof::LeafNotIndexed(_)) => DasApiError::ProofUnavailable(
+                id.to_string(),
+                "its leaf isn't indexed yet".to_string(),
+            ),
+            Err(e) => e.into(),
+        }
+    }
+
+    // Without an RPC, or when the tree can't be read, only the path is checked.
+    async fn validate_proof(&self, proof: &mut AssetProof) {
+        let mut valid = proof_matches_root(proof);
+        let tree = Pubkey::from_str(&proof.tree_id);
+        if let (Some(tree_roots), Ok(tree)) = (&self.tree_roots, tree) {
+            match tree_roots.get(&tree).await {
+                Ok(roots) => {
+                    let root = bs58::decode(&proof.root).into_vec().unwrap_or_default();
+                    valid = valid && roots.recent.iter().any(|r| r[..] == root[..]);
+                    proof.on_chain_root = Some(bs58::encode(roots.current).into_string());
+                }
+                Err(e) => warn!("Failed to read the on-chain roots of {}: {}", tree, e),
+            }
+        }
+        proof.valid = Some(valid);
+    }
+
     fn validate_sorting_for_collection(
         &self,
         group: &String,
@@ -126,7 +503,7 @@ impl DasApi {
             "WoMbiTtXKwUtf4wosoffv45khVF8yA2mPkinGosCFQ4",
         ];
 
-        if group == "collection" && collections.contains(&collection.as_str()) {
+        if group == COLLECTION_GROUP_KEY && collections.contains(&collection.as_str()) {
             if sort_by.clone().map(|s| s.sort_by) == Some(AssetSortBy::None) {
                 return Ok(());
             } else {
@@ -139,9 +516,32 @@ impl DasApi {
     }
 }
 
-pub fn not_found(asset_id: &String) -> DbErr {
-    DbErr::RecordNotFound(format!("Asset Proof for {} Not Found", asset_id))
-}
+const GET_ASSET_PROOF: &str = "getAssetProof";
+const GET_ASSET: &str = "getAsset";
+const GET_ASSET_BY_LEAF: &str = "getAssetByLeaf";
+const GET_ASSET_BY_LEAF_HASH: &str = "getAssetByLeafHash";
+const GET_ASSETS_BY_OWNER: &str = "getAssetsByOwner";
+const GET_ASSETS_BY_GROUP: &str = "getAssetsByGroup";
+const GET_ASSETS_BY_CREATOR: &str = "getAssetsByCreator";
+const GET_ASSETS_BY_AUTHORITY: &str = "getAssetsByAuthority";
+const GET_ASSETS_BY_LEAF_DELEGATE: &str = "getAssetsByLeafDelegate";
+const GET_ASSETS_BY_PARENT_ASSET: &str = "getAssetsByParentAsset";
+const SEARCH_ASSETS: &str = "searchAssets";
+const GET_GROUPING: &str = "getGrouping";
+const GET_SIGNATURES_FOR_ASSET: &str = "getSignaturesForAsset";
+const GET_SIGNATURES_FOR_ASSET_BATCH: &str = "getSignaturesForAssetBatch";
+// Most assets a getSignaturesForAssetBatch request can address.
+const MAX_SIGNATURE_BATCH_SIZE: usize = 100;
+const GET_TOKEN_BALANCE_HISTORY: &str = "getTokenBalanceHistory";
+const GET_ASSET_AUTHORITY_HISTORY: &str = "getAssetAuthorityHistory";
+const GET_ASSET_METADATA_HISTORY: &str = "getAssetMetadataHistory";
+const GET_OWNER_DIFF: &str = "getOwnerDiff";
+const GET_COLLECTION_TRAITS: &str = "getCollectionTraits";
+const GET_COLLECTION_ACTIVITY_STATS: &str = "getCollectionActivityStats";
+const GET_ASSET_AT_SLOT: &str = "getAssetAtSlot";
+const SIMULATE_CNFT_TRANSFER: &str = "simulateCnftTransfer";
+const GET_NFT_EDITIONS: &str = "getNftEditions";
+const GET_TREE_AUDIT: &str = "getTreeAudit";
 
 #[document_rpc]
 #[async_trait]
@@ -162,26 +562,127 @@ impl ApiContract for DasApi {
     ) -> Result<AssetProof, DasApiError> {
         let id = validate_pubkey(payload.id.clone())?;
         let id_bytes = id.to_bytes().to_vec();
-        get_proof_for_asset(&self.db_connection, id_bytes)
-            .await
-            .and_then(|p| {
-                if p.proof.is_empty() {
-                    return Err(not_found(&payload.id));
-                }
-                Ok(p)
-            })
-            .map_err(Into::into)
+        let options = payload.options.unwrap_or_default();
+        self.check_visible(&payload.id, id_bytes.clone()).await?;
+        let network = self.request_network(options.network.clone())?;
+        self.check_network(&AssetLeafAddress::Id(id_bytes.clone()), &network)
+            .await?;
+        let proof = self
+            .limits
+            .run(
+                GET_ASSET_PROOF,
+                get_proof_for_asset(&self.db_connection, id_bytes.clone(), options.commitment),
+            )
+            .await;
+        let mut proof = match proof {
+            Ok(proof) if !proof.proof.is_empty() => proof,
+            Ok(_) | Err(DasApiError::DatabaseError(DbErr::RecordNotFound(_))) => {
+                return Err(self
+                    .missing_proof(&payload.id, id_bytes, options.commitment)
+                    .await);
+            }
+            Err(e) => return Err(e),
+        };
+        // Validation needs the whole proof, trimming comes after.
+        if options.validate {
+            self.validate_proof(&mut proof).await;
+        }
+        if options.trim_canopy {
+            let tree = bs58::decode(&proof.tree_id).into_vec().unwrap_or_default();
+            if let Some(canopy_depth) = get_canopy_depth(&self.db_connection, tree).await? {
+                trim_canopy(&mut proof, canopy_depth);
+            }
+        }
+        Ok(proof)
     }
 
     async fn get_asset(self: &DasApi, payload: GetAsset) -> Result<Asset, DasApiError> {
         let id = validate_pubkey(payload.id.clone())?;
         let id_bytes = id.to_bytes().to_vec();
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
-        get_asset(&self.db_connection, id_bytes, &transform, payload.raw_data)
+        let transform = self.asset_transform(&payload.options)?;
+        let mut asset = self
+            .limits
+            .run(
+                GET_ASSET,
+                get_asset(&self.db_connection, id_bytes, &transform, payload.raw_data),
+            )
+            .await
+            .map_err(|e| match e {
+                DasApiError::DatabaseError(DbErr::RecordNotFound(_)) => {
+                    DasApiError::AssetNotFound(payload.id.clone())
+                }
+                e => e,
+            })?;
+        self.add_ownership_info(std::slice::from_mut(&mut asset))
+            .await?;
+        self.add_price_info(std::slice::from_mut(&mut asset), &payload.options)
+            .await?;
+        self.add_full_metadata(
+            std::slice::from_mut(&mut asset),
+            &payload.options,
+            &transform,
+        )
+        .await?;
+        add_mutated_flag(&self.db_connection, std::slice::from_mut(&mut asset)).await?;
+        add_lock_state(&self.db_connection, std::slice::from_mut(&mut asset)).await?;
+        add_tree_freshness(&self.db_connection, std::slice::from_mut(&mut asset)).await?;
+        Ok(asset)
+    }
+
+    async fn get_asset_by_leaf(
+        self: &DasApi,
+        payload: GetAssetByLeaf,
+    ) -> Result<Asset, DasApiError> {
+        let tree = validate_pubkey(payload.tree.clone())?;
+        let id = self
+            .limits
+            .run(
+                GET_ASSET_BY_LEAF,
+                get_asset_id_by_leaf(
+                    &self.db_connection,
+                    tree.to_bytes().to_vec(),
+                    payload.leaf_index,
+                ),
+            )
+            .await
+            .map_err(|e| match e {
+                DasApiError::DatabaseError(DbErr::RecordNotFound(_)) => {
+                    DasApiError::AssetNotFound(format!("{}:{}", payload.tree, payload.leaf_index))
+                }
+                e => e,
+            })?;
+        self.get_asset(GetAsset {
+            id: bs58::encode(id).into_string(),
+            raw_data: payload.raw_data,
+            options: payload.options,
+        })
+        .await
+    }
+
+    async fn get_asset_by_leaf_hash(
+        self: &DasApi,
+        payload: GetAssetByLeafHash,
+    ) -> Result<Asset, DasApiError> {
+        let hash = validate_hash(&payload.hash)?;
+        let id = self
+            .limits
+            .run(
+                GET_ASSET_BY_LEAF_HASH,
+                get_asset_id_by_leaf_hash(&self.db_connection, hash),
+            )
             .await
-            .map_err(Into::into)
+            .map_err(|e| match e {
+                DasApiError::DatabaseError(DbErr::RecordNotFound(_)) => {
+                    DasApiError::AssetNotFound(payload.hash.clone())
+                }
+                e => e,
+            })?;
+        self.get_asset(GetAsset {
+            id: bs58::encode(id).into_string(),
+            raw_data: payload.raw_data,
+            options: payload.options,
+        })
+        .await
     }
 
     async fn get_assets_by_owner(
@@ -195,29 +696,78 @@ impl ApiContract for DasApi {
             page,
             before,
             after,
+            cursor,
+            group_by_collection,
+            options,
         } = payload;
         let before: Option<String> = before.filter(|before| !before.is_empty());
         let after: Option<String> = after.filter(|after| !after.is_empty());
         let owner_address = validate_pubkey(owner_address.clone())?;
         let owner_address_bytes = owner_address.to_bytes().to_vec();
         let sort_by = sort_by.unwrap_or_default();
-        self.validate_pagination(&limit, &page, &before, &after)?;
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
-        get_assets_by_owner(
-            &self.db_connection,
-            owner_address_bytes,
-            sort_by,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            &transform,
-            self.feature_flags.enable_grand_total_query,
-        )
-        .await
-        .map_err(Into::into)
+        self.validate_pagination(GET_ASSETS_BY_OWNER, &limit, &page, &before, &after, &cursor)?;
+        validate_cursor_sort(&cursor, &sort_by)?;
+        let transform = self.asset_transform(&options)?;
+        if group_by_collection.unwrap_or(false) {
+            if before.is_some() || after.is_some() || cursor.is_some() {
+                return Err(DasApiError::ValidationError(
+                    "groupByCollection only supports page pagination".to_string(),
+                ));
+            }
+            let mut assets = self
+                .limits
+                .run(
+                    GET_ASSETS_BY_OWNER,
+                    get_assets_by_owner_grouped(
+                        &self.db_connection,
+                        owner_address_bytes,
+                        self.limits.page_size(GET_ASSETS_BY_OWNER, limit),
+                        page.unwrap_or(1) as u64,
+                        &transform,
+                    ),
+                )
+                .await?;
+            if let Some(collections) = assets.collections.as_mut() {
+                let mut samples: Vec<_> = collections.iter().map(|c| c.sample.clone()).collect();
+                self.add_ownership_info(&mut samples).await?;
+                self.add_price_info(&mut samples, &options).await?;
+                self.add_full_metadata(&mut samples, &options, &transform)
+                    .await?;
+                for (collection, sample) in collections.iter_mut().zip(samples) {
+                    collection.sample = sample;
+                }
+            }
+            self.add_native_balance(&mut assets, Some(&owner_address), &options)
+                .await?;
+            return Ok(assets);
+        }
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_OWNER,
+                get_assets_by_owner(
+                    &self.db_connection,
+                    owner_address_bytes,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_OWNER, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        self.add_token_info(GET_ASSETS_BY_OWNER, &mut assets.items, &options)
+            .await?;
+        self.add_native_balance(&mut assets, Some(&owner_address), &options)
+            .await?;
+        Ok(assets)
     }
 
     async fn get_assets_by_group(
@@ -232,29 +782,46 @@ impl ApiContract for DasApi {
             page,
             before,
             after,
+            cursor,
+            options,
         } = payload;
         self.validate_sorting_for_collection(&group_key, &group_value, &sort_by)?;
         let sort_by = sort_by.unwrap_or_default();
         let before: Option<String> = before.filter(|before| !before.is_empty());
         let after: Option<String> = after.filter(|after| !after.is_empty());
-        self.validate_pagination(&limit, &page, &before, &after)?;
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
-        get_assets_by_group(
-            &self.db_connection,
-            group_key,
-            group_value,
-            sort_by,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            &transform,
-            self.feature_flags.enable_grand_total_query,
-        )
-        .await
-        .map_err(Into::into)
+        self.validate_pagination(GET_ASSETS_BY_GROUP, &limit, &page, &before, &after, &cursor)?;
+        validate_cursor_sort(&cursor, &sort_by)?;
+        if !is_group_key_indexed(&self.db_connection, group_key.clone()).await? {
+            return Err(DasApiError::ValidationError(format!(
+                "groupKey {} isn't indexed",
+                group_key
+            )));
+        }
+        let transform = self.asset_transform(&options)?;
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_GROUP,
+                get_assets_by_group(
+                    &self.db_connection,
+                    group_key,
+                    group_value,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_GROUP, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        Ok(assets)
     }
 
     async fn get_assets_by_creator(
@@ -269,30 +836,48 @@ impl ApiContract for DasApi {
             page,
             before,
             after,
+            cursor,
+            options,
         } = payload;
         let creator_address = validate_pubkey(creator_address.clone())?;
         let creator_address_bytes = creator_address.to_bytes().to_vec();
 
-        self.validate_pagination(&limit, &page, &before, &after)?;
+        self.validate_pagination(
+            GET_ASSETS_BY_CREATOR,
+            &limit,
+            &page,
+            &before,
+            &after,
+            &cursor,
+        )?;
         let sort_by = sort_by.unwrap_or_default();
+        validate_cursor_sort(&cursor, &sort_by)?;
         let only_verified = only_verified.unwrap_or_default();
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
-        get_assets_by_creator(
-            &self.db_connection,
-            creator_address_bytes,
-            only_verified,
-            sort_by,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            &transform,
-            self.feature_flags.enable_grand_total_query,
-        )
-        .await
-        .map_err(Into::into)
+        let transform = self.asset_transform(&options)?;
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_CREATOR,
+                get_assets_by_creator(
+                    &self.db_connection,
+                    creator_address_bytes,
+                    only_verified,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_CREATOR, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        Ok(assets)
     }
 
     async fn get_assets_by_authority(
@@ -306,27 +891,149 @@ impl ApiContract for DasApi {
             page,
             before,
             after,
+            cursor,
+            options,
         } = payload;
         let sort_by = sort_by.unwrap_or_default();
         let authority_address = validate_pubkey(authority_address.clone())?;
         let authority_address_bytes = authority_address.to_bytes().to_vec();
-        self.validate_pagination(&limit, &page, &before, &after)?;
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
-        get_assets_by_authority(
-            &self.db_connection,
-            authority_address_bytes,
+        self.validate_pagination(
+            GET_ASSETS_BY_AUTHORITY,
+            &limit,
+            &page,
+            &before,
+            &after,
+            &cursor,
+        )?;
+        validate_cursor_sort(&cursor, &sort_by)?;
+        let transform = self.asset_transform(&options)?;
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_AUTHORITY,
+                get_assets_by_authority(
+                    &self.db_connection,
+                    authority_address_bytes,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_AUTHORITY, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        Ok(assets)
+    }
+
+    async fn get_assets_by_leaf_delegate(
+        self: &DasApi,
+        payload: GetAssetsByLeafDelegate,
+    ) -> Result<AssetList, DasApiError> {
+        let GetAssetsByLeafDelegate {
+            delegate_address,
             sort_by,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            &transform,
-            self.feature_flags.enable_grand_total_query,
-        )
-        .await
-        .map_err(Into::into)
+            limit,
+            page,
+            before,
+            after,
+            cursor,
+            options,
+        } = payload;
+        let sort_by = sort_by.unwrap_or_default();
+        let delegate_address = validate_pubkey(delegate_address.clone())?;
+        let delegate_address_bytes = delegate_address.to_bytes().to_vec();
+        self.validate_pagination(
+            GET_ASSETS_BY_LEAF_DELEGATE,
+            &limit,
+            &page,
+            &before,
+            &after,
+            &cursor,
+        )?;
+        validate_cursor_sort(&cursor, &sort_by)?;
+        let transform = self.asset_transform(&options)?;
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_LEAF_DELEGATE,
+                get_assets_by_leaf_delegate(
+                    &self.db_connection,
+                    delegate_address_bytes,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_LEAF_DELEGATE, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        Ok(assets)
+    }
+
+    async fn get_assets_by_parent_asset(
+        self: &DasApi,
+        payload: GetAssetsByParentAsset,
+    ) -> Result<AssetList, DasApiError> {
+        let GetAssetsByParentAsset {
+            parent_asset,
+            sort_by,
+            limit,
+            page,
+            before,
+            after,
+            cursor,
+            options,
+        } = payload;
+        let sort_by = sort_by.unwrap_or_default();
+        let parent_asset = validate_pubkey(parent_asset.clone())?;
+        let parent_asset_bytes = parent_asset.to_bytes().to_vec();
+        self.validate_pagination(
+            GET_ASSETS_BY_PARENT_ASSET,
+            &limit,
+            &page,
+            &before,
+            &after,
+            &cursor,
+        )?;
+        validate_cursor_sort(&cursor, &sort_by)?;
+        let transform = self.asset_transform(&options)?;
+        let mut assets = self
+            .limits
+            .run(
+                GET_ASSETS_BY_PARENT_ASSET,
+                get_assets_by_parent_asset(
+                    &self.db_connection,
+                    parent_asset_bytes,
+                    sort_by,
+                    self.limits.page_size(GET_ASSETS_BY_PARENT_ASSET, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        Ok(assets)
     }
 
     async fn search_assets(&self, payload: SearchAssets) -> Result<AssetList, DasApiError> {
@@ -356,11 +1063,20 @@ impl ApiContract for DasApi {
             page,
             before,
             after,
+            cursor,
             json_uri,
             show_collection_metadata,
+            escrowed,
+            royalty_amount_min,
+            royalty_amount_max,
+            royalty_recipient,
+            creator_share_min,
+            creator_share_max,
+            options,
         } = payload;
         // Deserialize search assets query
-        self.validate_pagination(&limit, &page, &before, &after)?;
+        self.validate_pagination(SEARCH_ASSETS, &limit, &page, &before, &after, &cursor)?;
+        validate_cursor_sort(&cursor, &sort_by.clone().unwrap_or_default())?;
         let spec: Option<(SpecificationVersions, SpecificationAssetClass)> =
             interface.map(|x| x.into());
         let specification_version = spec.clone().map(|x| x.0);
@@ -370,22 +1086,45 @@ impl ApiContract for DasApi {
             SearchConditionType::All => ConditionType::All,
         });
         let owner_address = validate_opt_pubkey(&owner_address)?;
+        let balance_owner = owner_address
+            .as_deref()
+            .and_then(|owner| Pubkey::try_from(owner).ok());
         let creator_address = validate_opt_pubkey(&creator_address)?;
         let delegate = validate_opt_pubkey(&delegate)?;
 
         let authority_address = validate_opt_pubkey(&authority_address)?;
         let supply_mint = validate_opt_pubkey(&supply_mint)?;
         let royalty_target = validate_opt_pubkey(&royalty_target)?;
+        let royalty_recipient = validate_opt_pubkey(&royalty_recipient)?;
+        if let (Some(min), Some(max)) = (royalty_amount_min, royalty_amount_max) {
+            if min > max {
+                return Err(DasApiError::ValidationError(
+                    "royaltyAmountMin must not be greater than royaltyAmountMax".to_string(),
+                ));
+            }
+        }
+        if creator_share_min
+            .max(creator_share_max)
+            .map_or(false, |share| share > 100)
+        {
+            return Err(DasApiError::ValidationError(
+                "creatorShareMin and creatorShareMax must be at most 100".to_string(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (creator_share_min, creator_share_max) {
+            if min > max {
+                return Err(DasApiError::ValidationError(
+                    "creatorShareMin must not be greater than creatorShareMax".to_string(),
+                ));
+            }
+        }
 
-        let owner_type = owner_type.map(|x| match x {
-            OwnershipModel::Single => OwnerType::Single,
-            OwnershipModel::Token => OwnerType::Token,
-        });
-        let royalty_target_type = royalty_target_type.map(|x| match x {
-            RoyaltyModel::Creators => RoyaltyTargetType::Creators,
-            RoyaltyModel::Fanout => RoyaltyTargetType::Fanout,
-            RoyaltyModel::Single => RoyaltyTargetType::Single,
-        });
+        // Assets owned by an asset are recorded with their owner PDA as a single owner.
+        let owned_by_asset = (owner_type == Some(OwnershipModel::Asset)).then_some(true);
+        let owner_type: Option<OwnerType> = owner_type
+            .filter(|t| *t != OwnershipModel::Asset)
+            .map(Into::into);
+        let royalty_target_type: Option<RoyaltyTargetType> = royalty_target_type.map(Into::into);
         let saq = SearchAssetsQuery {
             negate,
             condition_type,
@@ -395,6 +1134,8 @@ impl ApiContract for DasApi {
             owner_type,
             creator_address,
             creator_verified,
+            creator_share_min,
+            creator_share_max,
             authority_address,
             grouping,
             delegate,
@@ -406,29 +1147,50 @@ impl ApiContract for DasApi {
             royalty_target_type,
             royalty_target,
             royalty_amount,
+            royalty_amount_min,
+            royalty_amount_max,
+            royalty_recipient,
             burnt,
             json_uri,
+            escrowed,
+            owned_by_asset,
         };
+        self.limits
+            .validate_conditions(SEARCH_ASSETS, saq.count_conditions())?;
         let sort_by = sort_by.unwrap_or_default();
-        let transform = AssetTransform {
-            cdn_prefix: self.cdn_prefix.clone(),
-        };
+        let transform = self.asset_transform(&options)?;
+        let shape = format!("{} by {:?}", saq.shape(), sort_by.sort_by);
         // Execute query
-        search_assets(
-            &self.db_connection,
-            saq,
-            sort_by,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            &transform,
-            self.feature_flags.enable_grand_total_query,
-            self.feature_flags.enable_collection_metadata
-                && show_collection_metadata.unwrap_or(false),
-        )
-        .await
-        .map_err(Into::into)
+        let mut assets = self
+            .limits
+            .run_shaped(
+                SEARCH_ASSETS,
+                Some(shape),
+                search_assets(
+                    &self.db_connection,
+                    saq,
+                    sort_by,
+                    self.limits.page_size(SEARCH_ASSETS, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    cursor,
+                    &transform,
+                    self.grand_total(&options),
+                    self.feature_flags.enable_collection_metadata
+                        && show_collection_metadata.unwrap_or(false),
+                ),
+            )
+            .await?;
+        self.add_ownership_info(&mut assets.items).await?;
+        self.add_price_info(&mut assets.items, &options).await?;
+        self.add_full_metadata(&mut assets.items, &options, &transform)
+            .await?;
+        self.add_token_info(SEARCH_ASSETS, &mut assets.items, &options)
+            .await?;
+        self.add_native_balance(&mut assets, balance_owner.as_ref(), &options)
+            .await?;
+        Ok(assets)
     }
 
     async fn get_grouping(
@@ -439,7 +1201,20 @@ impl ApiContract for DasApi {
             group_key,
             group_value,
         } = payload;
-        let gs = get_grouping(&self.db_connection, group_key.clone(), group_value.clone()).await?;
+        if group_key == COLLECTION_GROUP_KEY && self.hides_collection(&group_value) {
+            return Ok(GetGroupingResponse {
+                group_key,
+                group_name: group_value,
+                group_size: 0,
+            });
+        }
+        let gs = self
+            .limits
+            .run(
+                GET_GROUPING,
+                get_grouping(&self.db_connection, group_key.clone(), group_value.clone()),
+            )
+            .await?;
         Ok(GetGroupingResponse {
             group_key,
             group_name: group_value,
@@ -459,6 +1234,7 @@ impl ApiContract for DasApi {
             after,
             tree,
             leaf_index,
+            network,
         } = payload;
 
         if !((id.is_some() && tree.is_none() && leaf_index.is_none())
@@ -471,19 +1247,533 @@ impl ApiContract for DasApi {
         let id = validate_opt_pubkey(&id)?;
         let tree = validate_opt_pubkey(&tree)?;
 
-        self.validate_pagination(&limit, &page, &before, &after)?;
+        self.validate_pagination(
+            GET_SIGNATURES_FOR_ASSET,
+            &limit,
+            &page,
+            &before,
+            &after,
+            &None,
+        )?;
+        let address = match (&id, &tree, leaf_index) {
+            (Some(id), _, _) => AssetLeafAddress::Id(id.clone()),
+            (_, Some(tree), Some(leaf_idx)) => AssetLeafAddress::Leaf {
+                tree: tree.clone(),
+                leaf_idx,
+            },
+            _ => unreachable!("validated above"),
+        };
+        self.check_address_visible(&address).await?;
+        let network = self.request_network(network)?;
+        self.check_network(&address, &network).await?;
 
-        get_signatures_for_asset(
-            &self.db_connection,
-            id,
+        self.limits
+            .run(
+                GET_SIGNATURES_FOR_ASSET,
+                get_signatures_for_asset(
+                    &self.db_connection,
+                    id,
+                    tree,
+                    leaf_index,
+                    self.limits.page_size(GET_SIGNATURES_FOR_ASSET, limit),
+                    page.map(|x| x as u64),
+                    before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                    after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
+                ),
+            )
+            .await
+    }
+
+    async fn get_signatures_for_asset_batch(
+        self: &DasApi,
+        payload: GetSignaturesForAssetBatch,
+    ) -> Result<Vec<TransactionSignatureList>, DasApiError> {
+        let GetSignaturesForAssetBatch { assets, limit } = payload;
+        if assets.len() > MAX_SIGNATURE_BATCH_SIZE {
+            return Err(DasApiError::QueryTooComplex(format!(
+                "{} assets given, at most {} are allowed",
+                assets.len(),
+                MAX_SIGNATURE_BATCH_SIZE
+            )));
+        }
+        let addresses = assets
+            .into_iter()
+            .map(|address| match address {
+                AssetSignaturesAddress {
+                    id: Some(id),
+                    tree: None,
+                    leaf_index: None,
+                } => Ok(AssetLeafAddress::Id(
+                    validate_pubkey(id)?.to_bytes().to_vec(),
+                )),
+                AssetSignaturesAddress {
+                    id: None,
+                    tree: Some(tree),
+                    leaf_index: Some(leaf_idx),
+                } => Ok(AssetLeafAddress::Leaf {
+                    tree: validate_pubkey(tree)?.to_bytes().to_vec(),
+                    leaf_idx,
+                }),
+                _ => Err(DasApiError::ValidationError(
+                    "Must provide either 'id' or both 'tree' and 'leafIndex'".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // The batch is a single page.
+        self.validate_pagination(
+            GET_SIGNATURES_FOR_ASSET_BATCH,
+            &limit,
+            &Some(1),
+            &None,
+            &None,
+            &None,
+        )?;
+
+        for address in &addresses {
+            self.check_address_visible(address).await?;
+        }
+
+        self.limits
+            .run(
+                GET_SIGNATURES_FOR_ASSET_BATCH,
+                get_signatures_for_asset_batch(
+                    &self.db_connection,
+                    addresses,
+                    self.limits.page_size(GET_SIGNATURES_FOR_ASSET_BATCH, limit),
+                ),
+            )
+            .await
+    }
+
+    async fn get_token_balance_history(
+        self: &DasApi,
+        payload: GetTokenBalanceHistory,
+    ) -> Result<TokenBalanceHistory, DasApiError> {
+        let GetTokenBalanceHistory {
+            owner_address,
+            mint,
+            limit,
+            page,
+            before_slot,
+            after_slot,
+        } = payload;
+        let owner_address = validate_pubkey(owner_address)?;
+        let mint = validate_pubkey(mint)?;
+        if page.is_some() && (before_slot.is_some() || after_slot.is_some()) {
+            return Err(DasApiError::PaginationError);
+        }
+        // The slots aren't keys, so are checked above. Without a page or slots, the first page is
+        // returned.
+        self.validate_pagination(
+            GET_TOKEN_BALANCE_HISTORY,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        self.check_visible(&mint.to_string(), mint.to_bytes().to_vec())
+            .await?;
+
+        self.limits
+            .run(
+                GET_TOKEN_BALANCE_HISTORY,
+                get_token_balance_history(
+                    &self.db_connection,
+                    owner_address.to_bytes().to_vec(),
+                    mint.to_bytes().to_vec(),
+                    self.limits.page_size(GET_TOKEN_BALANCE_HISTORY, limit),
+                    page.map(|x| x as u64),
+                    before_slot,
+                    after_slot,
+                ),
+            )
+            .await
+    }
+
+    async fn get_asset_authority_history(
+        self: &DasApi,
+        payload: GetAssetAuthorityHistory,
+    ) -> Result<AuthorityHistory, DasApiError> {
+        let GetAssetAuthorityHistory { id, limit, page } = payload;
+        let id = validate_pubkey(id)?;
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_ASSET_AUTHORITY_HISTORY,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        self.check_visible(&id.to_string(), id.to_bytes().to_vec())
+            .await?;
+
+        self.limits
+            .run(
+                GET_ASSET_AUTHORITY_HISTORY,
+                get_asset_authority_history(
+                    &self.db_connection,
+                    id.to_bytes().to_vec(),
+                    self.limits.page_size(GET_ASSET_AUTHORITY_HISTORY, limit),
+                    page.map(|x| x as u64),
+                ),
+            )
+            .await
+    }
+
+    async fn get_asset_metadata_history(
+        self: &DasApi,
+        payload: GetAssetMetadataHistory,
+    ) -> Result<MetadataHistory, DasApiError> {
+        let GetAssetMetadataHistory { id, limit, page } = payload;
+        let id = validate_pubkey(id)?;
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_ASSET_METADATA_HISTORY,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        self.check_visible(&id.to_string(), id.to_bytes().to_vec())
+            .await?;
+
+        self.limits
+            .run(
+                GET_ASSET_METADATA_HISTORY,
+                get_asset_metadata_history(
+                    &self.db_connection,
+                    id.to_bytes().to_vec(),
+                    self.limits.page_size(GET_ASSET_METADATA_HISTORY, limit),
+                    page.map(|x| x as u64),
+                ),
+            )
+            .await
+    }
+
+    async fn get_owner_diff(
+        self: &DasApi,
+        payload: GetOwnerDiff,
+    ) -> Result<OwnerDiff, DasApiError> {
+        let GetOwnerDiff {
+            owner,
+            since_slot,
+            limit,
+            page,
+        } = payload;
+        let owner = validate_pubkey(owner)?;
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_OWNER_DIFF,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+
+        self.limits
+            .run(
+                GET_OWNER_DIFF,
+                get_owner_diff(
+                    &self.db_connection,
+                    owner.to_bytes().to_vec(),
+                    since_slot,
+                    self.limits.page_size(GET_OWNER_DIFF, limit),
+                    page.map(|x| x as u64),
+                    &self.private_assets.hidden(),
+                ),
+            )
+            .await
+    }
+
+    async fn get_collection_traits(
+        self: &DasApi,
+        payload: GetCollectionTraits,
+    ) -> Result<CollectionTraits, DasApiError> {
+        let GetCollectionTraits {
+            collection,
+            trait_type,
+            limit,
+            page,
+        } = payload;
+        let collection = validate_pubkey(collection)?;
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_COLLECTION_TRAITS,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        let limit = self.limits.page_size(GET_COLLECTION_TRAITS, limit);
+        if self.hides_collection(&collection.to_string()) {
+            return Ok(CollectionTraits {
+                collection: collection.to_string(),
+                limit: limit as u32,
+                page,
+                ..Default::default()
+            });
+        }
+
+        self.limits
+            .run(
+                GET_COLLECTION_TRAITS,
+                get_collection_traits(
+                    &self.db_connection,
+                    collection.to_string(),
+                    trait_type,
+                    limit,
+                    page.map(|x| x as u64),
+                ),
+            )
+            .await
+    }
+
+    async fn get_collection_activity_stats(
+        self: &DasApi,
+        payload: GetCollectionActivityStats,
+    ) -> Result<CollectionActivityStats, DasApiError> {
+        let collection = validate_pubkey(payload.collection)?;
+        if self.hides_collection(&collection.to_string()) {
+            return Ok(CollectionActivityStats {
+                collection: collection.to_string(),
+                ..Default::default()
+            });
+        }
+        self.limits
+            .run(
+                GET_COLLECTION_ACTIVITY_STATS,
+                get_collection_activity_stats(&self.db_connection, collection.to_string()),
+            )
+            .await
+    }
+
+    async fn get_asset_at_slot(
+        self: &DasApi,
+        payload: GetAssetAtSlot,
+    ) -> Result<AssetAtSlot, DasApiError> {
+        let id = validate_pubkey(payload.id.clone())?;
+        let id_bytes = id.to_bytes().to_vec();
+        self.check_visible(&payload.id, id_bytes.clone()).await?;
+        self.limits
+            .run(
+                GET_ASSET_AT_SLOT,
+                get_asset_at_slot(&self.db_connection, id_bytes, payload.slot),
+            )
+            .await
+            .map_err(|e| match e {
+                DasApiError::DatabaseError(DbErr::RecordNotFound(_)) => {
+                    DasApiError::AssetNotFound(payload.id.clone())
+                }
+                e => e,
+            })
+    }
+
+    async fn simulate_cnft_transfer(
+        self: &DasApi,
+        payload: SimulateCnftTransfer,
+    ) -> Result<CnftTransferSimulation, DasApiError> {
+        let simulator = self.transfer_simulator.as_ref().ok_or_else(|| {
+            DasApiError::ConfigurationError("simulateCnftTransfer needs APP_RPC_URL".to_string())
+        })?;
+        let id = validate_pubkey(payload.asset_id.clone())?;
+        let new_owner = validate_pubkey(payload.new_owner.clone())?;
+        let fee_payer = payload.fee_payer.clone().map(validate_pubkey).transpose()?;
+        let id_bytes = id.to_bytes().to_vec();
+        self.check_visible(&payload.asset_id, id_bytes.clone())
+            .await?;
+        let asset = self
+            .limits
+            .run(
+                SIMULATE_CNFT_TRANSFER,
+                get_by_id(&self.db_connection, id_bytes.clone(), false),
+            )
+            .await
+            .map_err(|e| match e {
+                DasApiError::DatabaseError(DbErr::RecordNotFound(_)) => {
+                    DasApiError::AssetNotFound(payload.asset_id.clone())
+                }
+                e => e,
+            })?
+            .asset;
+        if asset.burnt {
+            return Err(DasApiError::ValidationError(format!(
+                "asset {} is burnt",
+                payload.asset_id
+            )));
+        }
+        let proof = self
+            .limits
+            .run(
+                SIMULATE_CNFT_TRANSFER,
+                get_proof_for_asset(&self.db_connection, id_bytes.clone(), Commitment::Confirmed),
+            )
+            .await;
+        let mut proof = match proof {
+            Ok(proof) if !proof.proof.is_empty() => proof,
+            Ok(_) | Err(DasApiError::DatabaseError(DbErr::RecordNotFound(_))) => {
+                return Err(self
+                    .missing_proof(&payload.asset_id, id_bytes, Commitment::Confirmed)
+                    .await);
+            }
+            Err(e) => return Err(e),
+        };
+        // The whole proof rarely fits in a transaction.
+        let tree = bs58::decode(&proof.tree_id).into_vec().unwrap_or_default();
+        let canopy_depth = self
+            .limits
+            .run(
+                SIMULATE_CNFT_TRANSFER,
+                get_canopy_depth(&self.db_connection, tree),
+            )
+            .await?;
+        if let Some(canopy_depth) = canopy_depth {
+            trim_canopy(&mut proof, canopy_depth);
+        }
+
+        let invalid = |what: &str| {
+            DasApiError::ProofUnavailable(
+                payload.asset_id.clone(),
+                format!("its indexed {} is invalid", what),
+            )
+        };
+        let pubkey = |bytes: &Option<Vec<u8>>, what: &str| {
+            bytes
+                .as_deref()
+                .and_then(|b| Pubkey::try_from(b).ok())
+                .ok_or_else(|| invalid(what))
+        };
+        let hash = |encoded: &str, what: &str| {
+            bs58::decode(encoded.trim())
+                .into_vec()
+                .ok()
+                .and_then(|h| <[u8; 32]>::try_from(h).ok())
+                .ok_or_else(|| invalid(what))
+        };
+        let owner = pubkey(&asset.owner, "owner")?;
+        let transfer = CnftTransfer {
+            tree: Pubkey::from_str(&proof.tree_id).map_err(|_| invalid("tree"))?,
+            owner,
+            delegate: match &asset.leaf_delegate {
+                Some(_) => pubkey(&asset.leaf_delegate, "leaf delegate")?,
+                None => owner,
+            },
+            new_owner,
+            root: hash(&proof.root, "root")?,
+            data_hash: hash(asset.data_hash.as_deref().unwrap_or_default(), "data hash")?,
+            creator_hash: hash(
+                asset.creator_hash.as_deref().unwrap_or_default(),
+                "creator hash",
+            )?,
+            nonce: asset.nonce.ok_or_else(|| invalid("leaf index"))? as u64,
+            proof: proof
+                .proof
+                .iter()
+                .map(|node| Pubkey::from_str(node).map_err(|_| invalid("proof")))
+                .collect::<Result<_, _>>()?,
+        };
+        let result = self
+            .limits
+            .run(
+                SIMULATE_CNFT_TRANSFER,
+                simulator.simulate(&transfer, &fee_payer.unwrap_or(owner)),
+            )
+            .await?;
+        Ok(CnftTransferSimulation {
+            id: payload.asset_id,
+            new_owner: payload.new_owner,
+            valid: result.error.is_none(),
+            root: proof.root,
+            slot: result.slot,
+            error: result.error,
+            logs: result.logs,
+        })
+    }
+
+    async fn get_nft_editions(
+        self: &DasApi,
+        payload: GetNftEditions,
+    ) -> Result<NftEditions, DasApiError> {
+        let GetNftEditions { mint, limit, page } = payload;
+        let mint = validate_pubkey(mint)?;
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_NFT_EDITIONS,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        self.check_visible(&mint.to_string(), mint.to_bytes().to_vec())
+            .await?;
+        let (master_edition, _) = find_master_edition_account(&mint);
+
+        self.limits
+            .run(
+                GET_NFT_EDITIONS,
+                get_nft_editions(
+                    &self.db_connection,
+                    master_edition.to_bytes().to_vec(),
+                    self.limits.page_size(GET_NFT_EDITIONS, limit),
+                    page.map(|x| x as u64),
+                ),
+            )
+            .await
+    }
+
+    async fn get_tree_audit(
+        self: &DasApi,
+        payload: GetTreeAudit,
+    ) -> Result<TreeAudit, DasApiError> {
+        let GetTreeAudit {
             tree,
-            leaf_index,
-            limit.map(|x| x as u64).unwrap_or(1000),
-            page.map(|x| x as u64),
-            before.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-            after.map(|x| bs58::decode(x).into_vec().unwrap_or_default()),
-        )
-        .await
-        .map_err(Into::into)
+            seq_range,
+            limit,
+            page,
+        } = payload;
+        let tree = validate_pubkey(tree)?;
+        if seq_range.start > seq_range.end {
+            return Err(DasApiError::ValidationError(format!(
+                "seqRange start {} is after end {}",
+                seq_range.start, seq_range.end
+            )));
+        }
+        // Without a page, the first one is returned.
+        self.validate_pagination(
+            GET_TREE_AUDIT,
+            &limit,
+            &page.or(Some(1)),
+            &None,
+            &None,
+            &None,
+        )?;
+        let limit = self.limits.page_size(GET_TREE_AUDIT, limit);
+        if self.private_assets.hidden().hides_tree(&tree.to_bytes()) {
+            return Ok(TreeAudit {
+                tree: tree.to_string(),
+                limit: limit as u32,
+                page,
+                ..Default::default()
+            });
+        }
+
+        self.limits
+            .run(
+                GET_TREE_AUDIT,
+                get_tree_audit(
+                    &self.db_connection,
+                    tree.to_bytes().to_vec(),
+                    seq_range.start,
+                    seq_range.end,
+                    limit,
+                    page.map(|x| x as u64),
+                ),
+            )
+            .await
     }
 }
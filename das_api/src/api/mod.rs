@@ -1,128 +1,18 @@
 use crate::DasApiError;
 use async_trait::async_trait;
-use digital_asset_types::rpc::filter::SearchConditionType;
-use digital_asset_types::rpc::response::{AssetList, TransactionSignatureList};
-use digital_asset_types::rpc::{filter::AssetSorting, response::GetGroupingResponse};
-use digital_asset_types::rpc::{Asset, AssetProof, Interface, OwnershipModel, RoyaltyModel};
+use digital_asset_types::rpc::response::GetGroupingResponse;
+use digital_asset_types::rpc::response::{
+    AssetAtSlot, AssetList, AuthorityHistory, CnftTransferSimulation, CollectionActivityStats,
+    CollectionTraits, MetadataHistory, NftEditions, OwnerDiff, TokenBalanceHistory,
+    TransactionSignatureList, TreeAudit,
+};
+use digital_asset_types::rpc::{Asset, AssetProof};
 use open_rpc_derive::{document_rpc, rpc};
-use open_rpc_schema::schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
 
 mod api_impl;
 pub use api_impl::*;
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetAssetsByGroup {
-    pub group_key: String,
-    pub group_value: String,
-    pub sort_by: Option<AssetSorting>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetAssetsByOwner {
-    pub owner_address: String,
-    pub sort_by: Option<AssetSorting>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetAsset {
-    pub id: String,
-    #[serde(default)]
-    pub raw_data: Option<bool>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetAssetProof {
-    pub id: String,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetAssetsByCreator {
-    pub creator_address: String,
-    pub only_verified: Option<bool>,
-    pub sort_by: Option<AssetSorting>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct SearchAssets {
-    pub negate: Option<bool>,
-    pub condition_type: Option<SearchConditionType>,
-    pub interface: Option<Interface>,
-    pub owner_address: Option<String>,
-    pub owner_type: Option<OwnershipModel>,
-    pub creator_address: Option<String>,
-    pub creator_verified: Option<bool>,
-    pub authority_address: Option<String>,
-    pub grouping: Option<(String, String)>,
-    pub delegate: Option<String>,
-    pub frozen: Option<bool>,
-    pub supply: Option<u64>,
-    pub supply_mint: Option<String>,
-    pub compressed: Option<bool>,
-    pub compressible: Option<bool>,
-    pub royalty_target_type: Option<RoyaltyModel>,
-    pub royalty_target: Option<String>,
-    pub royalty_amount: Option<u32>,
-    pub burnt: Option<bool>,
-    pub sort_by: Option<AssetSorting>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-    #[serde(default)]
-    pub json_uri: Option<String>,
-    #[serde(default)]
-    pub show_collection_metadata: Option<bool>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-
-pub struct GetAssetsByAuthority {
-    pub authority_address: String,
-    pub sort_by: Option<AssetSorting>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetGrouping {
-    pub group_key: String,
-    pub group_value: String,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct GetSignaturesForAsset {
-    pub id: Option<String>,
-    pub limit: Option<u32>,
-    pub page: Option<u32>,
-    pub before: Option<String>,
-    pub after: Option<String>,
-    pub tree: Option<String>,
-    pub leaf_index: Option<i64>,
-}
+// The params are shared with the API's client.
+pub use digital_asset_types::rpc::request::*;
 
 #[document_rpc]
 #[async_trait]
@@ -140,6 +30,21 @@ pub trait ApiContract: Send + Sync + 'static {
         summary = "Get an asset by its ID"
     )]
     async fn get_asset(&self, payload: GetAsset) -> Result<Asset, DasApiError>;
+    #[rpc(
+        name = "getAssetByLeaf",
+        params = "named",
+        summary = "Get the compressed asset at a leaf of a tree"
+    )]
+    async fn get_asset_by_leaf(&self, payload: GetAssetByLeaf) -> Result<Asset, DasApiError>;
+    #[rpc(
+        name = "getAssetByLeafHash",
+        params = "named",
+        summary = "Get the compressed asset whose leaf has a hash"
+    )]
+    async fn get_asset_by_leaf_hash(
+        &self,
+        payload: GetAssetByLeafHash,
+    ) -> Result<Asset, DasApiError>;
     #[rpc(
         name = "getAssetsByOwner",
         params = "named",
@@ -176,6 +81,24 @@ pub trait ApiContract: Send + Sync + 'static {
         &self,
         payload: GetAssetsByAuthority,
     ) -> Result<AssetList, DasApiError>;
+    #[rpc(
+        name = "getAssetsByLeafDelegate",
+        params = "named",
+        summary = "Get a list of compressed assets delegated to an address"
+    )]
+    async fn get_assets_by_leaf_delegate(
+        &self,
+        payload: GetAssetsByLeafDelegate,
+    ) -> Result<AssetList, DasApiError>;
+    #[rpc(
+        name = "getAssetsByParentAsset",
+        params = "named",
+        summary = "Get a list of assets owned by another asset"
+    )]
+    async fn get_assets_by_parent_asset(
+        &self,
+        payload: GetAssetsByParentAsset,
+    ) -> Result<AssetList, DasApiError>;
     #[rpc(
         name = "searchAssets",
         params = "named",
@@ -197,4 +120,91 @@ pub trait ApiContract: Send + Sync + 'static {
         &self,
         payload: GetSignaturesForAsset,
     ) -> Result<TransactionSignatureList, DasApiError>;
+    #[rpc(
+        name = "getSignaturesForAssetBatch",
+        params = "named",
+        summary = "Get the latest transaction signatures of several assets"
+    )]
+    async fn get_signatures_for_asset_batch(
+        &self,
+        payload: GetSignaturesForAssetBatch,
+    ) -> Result<Vec<TransactionSignatureList>, DasApiError>;
+    #[rpc(
+        name = "getTokenBalanceHistory",
+        params = "named",
+        summary = "Get the balance history of an owner's token accounts for a mint"
+    )]
+    async fn get_token_balance_history(
+        &self,
+        payload: GetTokenBalanceHistory,
+    ) -> Result<TokenBalanceHistory, DasApiError>;
+    #[rpc(
+        name = "getAssetAuthorityHistory",
+        params = "named",
+        summary = "Get the update authorities an asset has had and what kind of account each is"
+    )]
+    async fn get_asset_authority_history(
+        &self,
+        payload: GetAssetAuthorityHistory,
+    ) -> Result<AuthorityHistory, DasApiError>;
+    #[rpc(
+        name = "getAssetMetadataHistory",
+        params = "named",
+        summary = "Get the versions of an asset's on-chain name, symbol and metadata uri"
+    )]
+    async fn get_asset_metadata_history(
+        &self,
+        payload: GetAssetMetadataHistory,
+    ) -> Result<MetadataHistory, DasApiError>;
+    #[rpc(
+        name = "getOwnerDiff",
+        params = "named",
+        summary = "Get the assets an owner gained and lost since a slot"
+    )]
+    async fn get_owner_diff(&self, payload: GetOwnerDiff) -> Result<OwnerDiff, DasApiError>;
+    #[rpc(
+        name = "getCollectionTraits",
+        params = "named",
+        summary = "Get how many assets of a collection have each trait"
+    )]
+    async fn get_collection_traits(
+        &self,
+        payload: GetCollectionTraits,
+    ) -> Result<CollectionTraits, DasApiError>;
+    #[rpc(
+        name = "getCollectionActivityStats",
+        params = "named",
+        summary = "Get how many assets of a collection were minted, transferred and burnt"
+    )]
+    async fn get_collection_activity_stats(
+        &self,
+        payload: GetCollectionActivityStats,
+    ) -> Result<CollectionActivityStats, DasApiError>;
+    #[rpc(
+        name = "getAssetAtSlot",
+        params = "named",
+        summary = "Get the owner, delegate and burn state of an asset as of a slot"
+    )]
+    async fn get_asset_at_slot(&self, payload: GetAssetAtSlot) -> Result<AssetAtSlot, DasApiError>;
+    #[rpc(
+        name = "simulateCnftTransfer",
+        params = "named",
+        summary = "Simulate a transfer of a compressed asset with its indexed proof"
+    )]
+    async fn simulate_cnft_transfer(
+        &self,
+        payload: SimulateCnftTransfer,
+    ) -> Result<CnftTransferSimulation, DasApiError>;
+    #[rpc(
+        name = "getNftEditions",
+        params = "named",
+        summary = "Get the print editions of a master edition with their mints and owners"
+    )]
+    async fn get_nft_editions(&self, payload: GetNftEditions) -> Result<NftEditions, DasApiError>;
+    #[rpc(
+        name = "getTreeAudit",
+        params = "named",
+        summary = "Debug: get the change log audit rows of a tree for a seq range"
+    )]
+    async fn get_tree_audit(&self, payload: GetTreeAudit) -> Result<TreeAudit, DasApiError>;
 }
@@ -6,6 +6,7 @@ pub struct RpcApiBuilder;
 impl RpcApiBuilder {
     pub fn build(
         contract: Box<dyn ApiContract>,
+        enable_debug_methods: bool,
     ) -> Result<RpcModule<Box<dyn ApiContract>>, DasApiError> {
         let mut module = RpcModule::new(contract);
         module.register_async_method("healthz", |_rpc_params, rpc_context| async move {
@@ -28,6 +29,30 @@ impl RpcApiBuilder {
         })?;
         module.register_alias("getAsset", "get_asset")?;
 
+        module.register_async_method(
+            "get_asset_by_leaf",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetByLeaf>()?;
+                rpc_context
+                    .get_asset_by_leaf(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetByLeaf", "get_asset_by_leaf")?;
+
+        module.register_async_method(
+            "get_asset_by_leaf_hash",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetByLeafHash>()?;
+                rpc_context
+                    .get_asset_by_leaf_hash(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetByLeafHash", "get_asset_by_leaf_hash")?;
+
         module.register_async_method(
             "get_assets_by_owner",
             |rpc_params, rpc_context| async move {
@@ -63,6 +88,30 @@ impl RpcApiBuilder {
             },
         )?;
 
+        module.register_async_method(
+            "get_assets_by_leaf_delegate",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetsByLeafDelegate>()?;
+                rpc_context
+                    .get_assets_by_leaf_delegate(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetsByLeafDelegate", "get_assets_by_leaf_delegate")?;
+
+        module.register_async_method(
+            "get_assets_by_parent_asset",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetsByParentAsset>()?;
+                rpc_context
+                    .get_assets_by_parent_asset(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetsByParentAsset", "get_assets_by_parent_asset")?;
+
         module.register_async_method(
             "get_assets_by_group",
             |rpc_params, rpc_context| async move {
@@ -85,6 +134,21 @@ impl RpcApiBuilder {
             },
         )?;
 
+        module.register_async_method(
+            "get_signatures_for_asset_batch",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetSignaturesForAssetBatch>()?;
+                rpc_context
+                    .get_signatures_for_asset_batch(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias(
+            "getSignaturesForAssetBatch",
+            "get_signatures_for_asset_batch",
+        )?;
+
         module.register_alias("getAssetsByGroup", "get_assets_by_group")?;
 
         module.register_async_method("search_assets", |rpc_params, rpc_context| async move {
@@ -93,6 +157,126 @@ impl RpcApiBuilder {
         })?;
         module.register_alias("searchAssets", "search_assets")?;
 
+        module.register_async_method(
+            "get_token_balance_history",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetTokenBalanceHistory>()?;
+                rpc_context
+                    .get_token_balance_history(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getTokenBalanceHistory", "get_token_balance_history")?;
+
+        module.register_async_method(
+            "get_asset_authority_history",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetAuthorityHistory>()?;
+                rpc_context
+                    .get_asset_authority_history(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetAuthorityHistory", "get_asset_authority_history")?;
+
+        module.register_async_method(
+            "get_asset_metadata_history",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetMetadataHistory>()?;
+                rpc_context
+                    .get_asset_metadata_history(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetMetadataHistory", "get_asset_metadata_history")?;
+
+        module.register_async_method("get_owner_diff", |rpc_params, rpc_context| async move {
+            let payload = rpc_params.parse::<GetOwnerDiff>()?;
+            rpc_context
+                .get_owner_diff(payload)
+                .await
+                .map_err(Into::into)
+        })?;
+        module.register_alias("getOwnerDiff", "get_owner_diff")?;
+
+        module.register_async_method(
+            "get_collection_traits",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetCollectionTraits>()?;
+                rpc_context
+                    .get_collection_traits(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getCollectionTraits", "get_collection_traits")?;
+
+        module.register_async_method(
+            "get_collection_activity_stats",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetCollectionActivityStats>()?;
+                rpc_context
+                    .get_collection_activity_stats(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias(
+            "getCollectionActivityStats",
+            "get_collection_activity_stats",
+        )?;
+
+        module.register_async_method(
+            "get_asset_at_slot",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<GetAssetAtSlot>()?;
+                rpc_context
+                    .get_asset_at_slot(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("getAssetAtSlot", "get_asset_at_slot")?;
+
+        module.register_async_method(
+            "simulate_cnft_transfer",
+            |rpc_params, rpc_context| async move {
+                let payload = rpc_params.parse::<SimulateCnftTransfer>()?;
+                rpc_context
+                    .simulate_cnft_transfer(payload)
+                    .await
+                    .map_err(Into::into)
+            },
+        )?;
+        module.register_alias("simulateCnftTransfer", "simulate_cnft_transfer")?;
+
+        module.register_async_method("get_nft_editions", |rpc_params, rpc_context| async move {
+            let payload = rpc_params.parse::<GetNftEditions>()?;
+            rpc_context
+                .get_nft_editions(payload)
+                .await
+                .map_err(Into::into)
+        })?;
+        module.register_alias("getNftEditions", "get_nft_editions")?;
+
+        // Debug methods are only served when an api key is configured to gate them with.
+        if enable_debug_methods {
+            module.register_async_method(
+                "get_tree_audit",
+                |rpc_params, rpc_context| async move {
+                    let payload = rpc_params.parse::<GetTreeAudit>()?;
+                    rpc_context
+                        .get_tree_audit(payload)
+                        .await
+                        .map_err(Into::into)
+                },
+            )?;
+            module.register_alias("getTreeAudit", "get_tree_audit")?;
+        }
+
         module.register_async_method("schema", |_, rpc_context| async move {
             Ok(rpc_context.schema())
         })?;
@@ -1,7 +1,13 @@
-use crate::error::DasApiError;
+use crate::{
+    error::DasApiError, index_advisor::IndexAdvisorConfig, limits::MethodLimitConfig,
+    private_assets::PrivateAssetsConfig, query_plans::QueryPlanConfig,
+    query_watchdog::QueryWatchdogConfig,
+};
 use {
+    digital_asset_types::rpc::transform::UrlRewrite,
     figment::{providers::Env, Figment},
     serde::Deserialize,
+    std::collections::HashMap,
 };
 
 #[derive(Deserialize)]
@@ -12,13 +18,42 @@ pub struct Config {
     pub server_port: u16,
     pub env: Option<String>,
     pub cdn_prefix: Option<String>,
+    pub content_url_rewrites: Option<Vec<UrlRewrite>>,
     pub enable_grand_total_query: Option<bool>,
     pub enable_collection_metadata: Option<bool>,
+    pub enable_escrow_treatment: Option<bool>,
+    pub enable_parent_assets: Option<bool>,
+    pub query_timeout_ms: Option<u64>,
+    pub max_page_size: Option<u32>,
+    pub max_search_conditions: Option<usize>,
+    pub method_limits: Option<HashMap<String, MethodLimitConfig>>,
+    pub debug_api_key: Option<String>,
+    pub rpc_url: Option<String>,
+    pub cors_allowed_origins: Option<Vec<String>>,
+    pub enable_compression: Option<bool>,
+    pub max_request_body_size: Option<u32>,
+    pub enable_http2: Option<bool>,
+    pub allow_schema_mismatch: Option<bool>,
+    pub query_plan_config: Option<QueryPlanConfig>,
+    pub index_advisor_config: Option<IndexAdvisorConfig>,
+    pub query_watchdog_config: Option<QueryWatchdogConfig>,
+    /// Prepared statements each database connection keeps, 100 by default.
+    pub statement_cache_capacity: Option<usize>,
+    /// The network served to requests that don't ask for one, every network when unset.
+    pub default_network: Option<String>,
+    /// Collections and trees only served to the requests presenting one of their API keys.
+    pub private_assets: Option<Vec<PrivateAssetsConfig>>,
+    /// Postgres schema the index lives in, `public` when unset.
+    pub database_schema: Option<String>,
 }
 
 pub fn load_config() -> Result<Config, DasApiError> {
-    Figment::new()
-        .join(Env::prefixed("APP_"))
+    load_config_from(Figment::new().join(Env::prefixed("APP_")))
+}
+
+/// Extracts the config from `figment`, for binaries that read it from their own sources.
+pub fn load_config_from(figment: Figment) -> Result<Config, DasApiError> {
+    figment
         .extract()
         .map_err(|config_error| DasApiError::ConfigurationError(config_error.to_string()))
 }
@@ -0,0 +1,121 @@
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use hyper::{header::AUTHORIZATION, Body, Method, Request, Response, StatusCode};
+use tower::{Layer, Service};
+
+pub const DEBUG_METHODS: [&str; 2] = ["getTreeAudit", "get_tree_audit"];
+
+/// Rejects calls to the debug methods unless the request carries
+/// `Authorization: Bearer <APP_DEBUG_API_KEY>`. Batches are rejected as a whole when any of
+/// their calls is a debug method. Without a key the debug methods aren't registered at all and
+/// requests pass straight through.
+#[derive(Debug, Clone)]
+pub struct DebugAuthLayer {
+    api_key: Option<Arc<String>>,
+}
+
+impl DebugAuthLayer {
+    pub fn new(api_key: Option<String>) -> Self {
+        DebugAuthLayer {
+            api_key: api_key.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for DebugAuthLayer {
+    type Service = DebugAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DebugAuth {
+            inner,
+            api_key: self.api_key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugAuth<S> {
+    inner: S,
+    api_key: Option<Arc<String>>,
+}
+
+impl<S> Service<Request<Body>> for DebugAuth<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn Error + Send + Sync + 'static>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The ready service is the one that must handle the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let api_key = self.api_key.clone();
+
+        Box::pin(async move {
+            let api_key = match api_key {
+                Some(api_key) if req.method() == Method::POST => api_key,
+                _ => return inner.call(req).await.map_err(Into::into),
+            };
+
+            let authorized = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+                .map(|key| keys_match(key.trim(), &api_key))
+                .unwrap_or(false);
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+
+            if !authorized && calls_debug_method(&body) {
+                let mut unauthorized = Response::new(Body::from("Unauthorized"));
+                *unauthorized.status_mut() = StatusCode::UNAUTHORIZED;
+                return Ok(unauthorized);
+            }
+
+            inner
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+                .map_err(Into::into)
+        })
+    }
+}
+
+fn calls_debug_method(body: &[u8]) -> bool {
+    let is_debug = |call: &serde_json::Value| {
+        call.get("method")
+            .and_then(|m| m.as_str())
+            .map(|m| DEBUG_METHODS.contains(&m))
+            .unwrap_or(false)
+    };
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Array(calls)) => calls.iter().any(is_debug),
+        Ok(call) => is_debug(&call),
+        Err(_) => false,
+    }
+}
+
+// Compares every byte so the time taken doesn't leak how much of the key was right.
+pub(crate) fn keys_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
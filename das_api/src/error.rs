@@ -1,6 +1,22 @@
 use log::{debug, error};
 
-use {jsonrpsee::core::Error as RpcError, jsonrpsee::types::error::CallError, thiserror::Error};
+use {
+    jsonrpsee::core::Error as RpcError,
+    jsonrpsee::types::error::{CallError, ErrorObject},
+    serde_json::{json, Value},
+    thiserror::Error,
+};
+
+// Stable codes of the errors clients can act on. They are sent with a `data` object whose
+// `error` names the code, next to the error's details.
+pub const RATE_LIMITED_CODE: i32 = -32001;
+pub const QUERY_TIMEOUT_CODE: i32 = -32002;
+pub const QUERY_TOO_COMPLEX_CODE: i32 = -32003;
+pub const ASSET_NOT_FOUND_CODE: i32 = -32004;
+pub const TREE_NOT_INDEXED_CODE: i32 = -32005;
+pub const PROOF_UNAVAILABLE_CODE: i32 = -32006;
+pub const PAGINATION_INVALID_CODE: i32 = -32007;
+pub const QUERY_TOO_EXPENSIVE_CODE: i32 = -32008;
 
 #[derive(Error, Debug)]
 pub enum DasApiError {
@@ -22,18 +38,103 @@ pub enum DasApiError {
     PaginationEmptyError,
     #[error("Deserialization error: {0}")]
     DeserializationError(#[from] serde_json::Error),
+    #[error("Rate Limited: too many concurrent {0} requests, retry later")]
+    RateLimited(String),
+    #[error("Query Timeout: {0} took longer than {1}ms")]
+    QueryTimeout(String, u64),
+    #[error("Query Too Complex: {0}")]
+    QueryTooComplex(String),
+    #[error("RPC Error: {0}")]
+    RpcError(String),
+    #[error("Schema Mismatch: {0}")]
+    SchemaMismatch(String),
+    #[error("Asset Not Found: {0}")]
+    AssetNotFound(String),
+    #[error("Tree Not Indexed: tree {1} of asset {0} isn't indexed yet")]
+    TreeNotIndexed(String, String),
+    #[error("Proof Unavailable: no proof for {0}, {1}")]
+    ProofUnavailable(String, String),
+    #[error("Pagination Error: {0}")]
+    PaginationInvalid(String),
+    #[error("Query Too Expensive: a {0} query ran too long and was cancelled")]
+    QueryTooExpensive(String, Option<String>),
+}
+
+impl DasApiError {
+    /// The stable code and `data` of the errors clients can act on.
+    fn code(&self) -> Option<(i32, Value)> {
+        let (code, name, details) = match self {
+            Self::RateLimited(method) => (
+                RATE_LIMITED_CODE,
+                "RATE_LIMITED",
+                json!({ "method": method }),
+            ),
+            Self::QueryTimeout(method, timeout_ms) => (
+                QUERY_TIMEOUT_CODE,
+                "QUERY_TIMEOUT",
+                json!({ "method": method, "timeoutMs": timeout_ms }),
+            ),
+            Self::QueryTooComplex(reason) => (
+                QUERY_TOO_COMPLEX_CODE,
+                "QUERY_TOO_COMPLEX",
+                json!({ "reason": reason }),
+            ),
+            Self::AssetNotFound(id) => {
+                (ASSET_NOT_FOUND_CODE, "ASSET_NOT_FOUND", json!({ "id": id }))
+            }
+            Self::TreeNotIndexed(id, tree) => (
+                TREE_NOT_INDEXED_CODE,
+                "TREE_NOT_INDEXED",
+                json!({ "id": id, "tree": tree }),
+            ),
+            Self::ProofUnavailable(id, reason) => (
+                PROOF_UNAVAILABLE_CODE,
+                "PROOF_UNAVAILABLE",
+                json!({ "id": id, "reason": reason }),
+            ),
+            Self::PaginationError | Self::PaginationEmptyError | Self::PaginationInvalid(_) => (
+                PAGINATION_INVALID_CODE,
+                "PAGINATION_INVALID",
+                json!({ "reason": self.to_string() }),
+            ),
+            Self::QueryTooExpensive(method, shape) => (
+                QUERY_TOO_EXPENSIVE_CODE,
+                "QUERY_TOO_EXPENSIVE",
+                json!({ "method": method, "shape": shape }),
+            ),
+            _ => return None,
+        };
+        let mut data = details;
+        data["error"] = Value::from(name);
+        Some((code, data))
+    }
 }
 
 impl Into<RpcError> for DasApiError {
     fn into(self) -> RpcError {
         match self {
-            Self::ValidationError(_) => {
+            Self::ValidationError(_)
+            | Self::QueryTooComplex(_)
+            | Self::AssetNotFound(_)
+            | Self::TreeNotIndexed(_, _)
+            | Self::ProofUnavailable(_, _)
+            | Self::PaginationError
+            | Self::PaginationEmptyError
+            | Self::PaginationInvalid(_) => {
                 debug!("{}", self);
             }
             _ => {
                 error!("{}", self);
             }
         }
-        RpcError::Call(CallError::from_std_error(self))
+        let (code, data) = match self.code() {
+            Some(code) => code,
+            None => return RpcError::Call(CallError::from_std_error(self)),
+        };
+        RpcError::Call(CallError::Custom(ErrorObject::owned(
+            code,
+            self.to_string(),
+            Some(data),
+        )))
     }
 }
@@ -0,0 +1,222 @@
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use hyper::{
+    header::{HeaderValue, ETAG, IF_NONE_MATCH},
+    Body, Method, Request, Response, StatusCode,
+};
+use tower::{Layer, Service};
+
+const CACHEABLE_METHODS: [&str; 2] = ["getAsset", "get_asset"];
+
+/// Adds a content hash `ETag` to `getAsset` responses and answers `304 Not Modified` when the
+/// request's `If-None-Match` already matches, so pollers can skip re-downloading unchanged assets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EtagLayer;
+
+impl<S> Layer<S> for EtagLayer {
+    type Service = Etag<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Etag { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Etag<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for Etag<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn Error + Send + Sync + 'static>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        // The ready service is the one that must handle the call.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if req.method() != Method::POST {
+                return inner.call(req).await.map_err(Into::into);
+            }
+
+            let if_none_match = req.headers().get(IF_NONE_MATCH).cloned();
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let cacheable = is_cacheable_call(&body);
+            let res = inner
+                .call(Request::from_parts(parts, Body::from(body)))
+                .await
+                .map_err(Into::into)?;
+
+            if !cacheable || res.status() != StatusCode::OK {
+                return Ok(res);
+            }
+
+            let (mut parts, body) = res.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let etag = match result_etag(&body) {
+                Some(etag) => etag,
+                None => return Ok(Response::from_parts(parts, Body::from(body))),
+            };
+
+            if if_none_match
+                .as_ref()
+                .and_then(|v| v.to_str().ok())
+                .map(|v| etag_matches(v, &etag))
+                .unwrap_or(false)
+            {
+                let mut not_modified = Response::new(Body::empty());
+                *not_modified.status_mut() = StatusCode::NOT_MODIFIED;
+                not_modified
+                    .headers_mut()
+                    .insert(ETAG, HeaderValue::from_str(&etag)?);
+                return Ok(not_modified);
+            }
+
+            parts.headers.insert(ETAG, HeaderValue::from_str(&etag)?);
+            Ok(Response::from_parts(parts, Body::from(body)))
+        })
+    }
+}
+
+/// Only single (non batched) calls to the cacheable methods get an ETag.
+fn is_cacheable_call(body: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("method").and_then(|m| m.as_str()).map(str::to_owned))
+        .map(|m| CACHEABLE_METHODS.contains(&m.as_str()))
+        .unwrap_or(false)
+}
+
+/// Hashes the `result` member only, the envelope carries the caller's request id which would
+/// otherwise make every response unique. Errors are never tagged.
+fn result_etag(body: &[u8]) -> Option<String> {
+    let response: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let result = response.get("result")?;
+    let bytes = serde_json::to_vec(result).ok()?;
+    Some(format!("\"{}\"", solana_sdk::hash::hash(&bytes)))
+}
+
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    const ASSET: &str = r#"{"jsonrpc":"2.0","result":{"id":"asset"},"id":1}"#;
+
+    // Sends `body` to a server answering every call with `ASSET`.
+    async fn call(body: &str, if_none_match: Option<&str>) -> Response<Body> {
+        let service = EtagLayer.layer(service_fn(|_: Request<Body>| async {
+            Ok::<_, Infallible>(Response::new(Body::from(ASSET)))
+        }));
+        let mut req = Request::post("/")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        if let Some(if_none_match) = if_none_match {
+            req.headers_mut()
+                .insert(IF_NONE_MATCH, HeaderValue::from_str(if_none_match).unwrap());
+        }
+        service.oneshot(req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn matching_requests_are_not_modified() {
+        let get_asset = r#"{"jsonrpc":"2.0","method":"getAsset","params":{"id":"asset"},"id":1}"#;
+        let res = call(get_asset, None).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers()[ETAG].to_str().unwrap().to_string();
+
+        for if_none_match in [
+            etag.clone(),
+            format!("W/{}", etag),
+            format!("\"other\", {}", etag),
+            "*".to_string(),
+        ] {
+            let res = call(get_asset, Some(&if_none_match)).await;
+            assert_eq!(res.status(), StatusCode::NOT_MODIFIED, "{}", if_none_match);
+            assert_eq!(res.headers()[ETAG], etag.as_str());
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert!(body.is_empty());
+        }
+
+        let res = call(get_asset, Some("\"other\"")).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[ETAG], etag.as_str());
+    }
+
+    #[tokio::test]
+    async fn batches_and_other_methods_are_left_alone() {
+        for body in [
+            r#"[{"jsonrpc":"2.0","method":"getAsset","params":{"id":"asset"},"id":1}]"#,
+            r#"{"jsonrpc":"2.0","method":"getAssetProof","params":{"id":"asset"},"id":1}"#,
+        ] {
+            let res = call(body, Some("*")).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", body);
+            assert!(res.headers().get(ETAG).is_none(), "{}", body);
+            let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            assert_eq!(body, ASSET);
+        }
+    }
+
+    #[test]
+    fn only_single_calls_to_cacheable_methods_are_cacheable() {
+        assert!(is_cacheable_call(br#"{"method":"getAsset"}"#));
+        assert!(is_cacheable_call(br#"{"method":"get_asset"}"#));
+        assert!(!is_cacheable_call(br#"{"method":"getAssetsByOwner"}"#));
+        assert!(!is_cacheable_call(br#"[{"method":"getAsset"}]"#));
+        assert!(!is_cacheable_call(b"getAsset"));
+    }
+
+    #[test]
+    fn etags_only_hash_the_result() {
+        let etag = result_etag(br#"{"jsonrpc":"2.0","result":{"id":"asset"},"id":1}"#).unwrap();
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_eq!(
+            result_etag(br#"{"jsonrpc":"2.0","result":{"id":"asset"},"id":"other"}"#),
+            Some(etag.clone())
+        );
+        assert_ne!(
+            result_etag(br#"{"jsonrpc":"2.0","result":{"id":"other"},"id":1}"#),
+            Some(etag)
+        );
+        assert_eq!(
+            result_etag(br#"{"jsonrpc":"2.0","error":{"code":-32000},"id":1}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn weak_and_wildcard_etags_match() {
+        assert!(etag_matches("\"a\"", "\"a\""));
+        assert!(etag_matches("W/\"a\"", "\"a\""));
+        assert!(etag_matches("\"b\", W/\"a\"", "\"a\""));
+        assert!(etag_matches("*", "\"a\""));
+        assert!(!etag_matches("\"b\"", "\"a\""));
+        assert!(!etag_matches("a", "\"a\""));
+    }
+}
@@ -3,11 +3,15 @@ use crate::config::Config;
 pub struct FeatureFlags {
     pub enable_grand_total_query: bool,
     pub enable_collection_metadata: bool,
+    pub enable_escrow_treatment: bool,
+    pub enable_parent_assets: bool,
 }
 
 pub fn get_feature_flags(config: &Config) -> FeatureFlags {
     FeatureFlags {
         enable_grand_total_query: config.enable_grand_total_query.unwrap_or(false),
         enable_collection_metadata: config.enable_collection_metadata.unwrap_or(false),
+        enable_escrow_treatment: config.enable_escrow_treatment.unwrap_or(false),
+        enable_parent_assets: config.enable_parent_assets.unwrap_or(false),
     }
 }
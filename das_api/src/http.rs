@@ -0,0 +1,123 @@
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use hyper::{
+    body::{Bytes, HttpBody},
+    header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_NONE_MATCH},
+    Body, Method, Request, Response, StatusCode, Version,
+};
+use tower::{Layer, Service};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, Any, CorsLayer},
+};
+
+use crate::{config::Config, error::DasApiError};
+
+/// Allows every origin unless `APP_CORS_ALLOWED_ORIGINS` lists them, `*` in the list allows
+/// every origin too.
+pub fn cors_layer(config: &Config) -> Result<CorsLayer, DasApiError> {
+    let cors = CorsLayer::new()
+        .allow_methods([Method::POST, Method::GET])
+        .allow_headers([CONTENT_TYPE, IF_NONE_MATCH, AUTHORIZATION])
+        .expose_headers([ETAG]);
+    let origins = match &config.cors_allowed_origins {
+        Some(origins) if !origins.iter().any(|o| o == "*") => origins,
+        _ => return Ok(cors.allow_origin(Any)),
+    };
+    let origins = origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin.trim_end_matches('/')).map_err(|_| {
+                DasApiError::ConfigurationError(format!("Invalid CORS origin: {}", origin))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(cors.allow_origin(AllowOrigin::list(origins)))
+}
+
+/// Compresses responses with gzip or brotli, whichever the client accepts, when
+/// `APP_ENABLE_COMPRESSION` is set. Otherwise responses pass through as they are.
+pub fn compression_layer(config: &Config) -> CompressionLayer {
+    let layer = CompressionLayer::new().no_deflate();
+    if config.enable_compression.unwrap_or(false) {
+        layer
+    } else {
+        layer.no_gzip().no_br()
+    }
+}
+
+/// The server only takes `hyper::Body` responses back from its middleware.
+pub fn into_hyper_body<B>(res: Response<B>) -> Response<Body>
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    res.map(|body| {
+        Body::wrap_stream(futures::stream::unfold(
+            Box::pin(body),
+            |mut body| async move { body.data().await.map(|chunk| (chunk, body)) },
+        ))
+    })
+}
+
+/// The server speaks cleartext HTTP/2 (prior knowledge, as used by proxies and gRPC style
+/// clients) next to HTTP/1.1. With `APP_ENABLE_HTTP2=false` HTTP/2 requests are refused with
+/// `505 HTTP Version Not Supported`, for deployments behind proxies that mishandle it.
+#[derive(Debug, Clone, Copy)]
+pub struct Http2Layer {
+    enabled: bool,
+}
+
+impl Http2Layer {
+    pub fn new(enabled: bool) -> Self {
+        Http2Layer { enabled }
+    }
+}
+
+impl<S> Layer<S> for Http2Layer {
+    type Service = Http2<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Http2 {
+            inner,
+            enabled: self.enabled,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Http2<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S> Service<Request<Body>> for Http2<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn Error + Send + Sync + 'static>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        if !self.enabled && req.version() == Version::HTTP_2 {
+            let mut unsupported = Response::new(Body::from("HTTP/2 is disabled"));
+            *unsupported.status_mut() = StatusCode::HTTP_VERSION_NOT_SUPPORTED;
+            return Box::pin(async move { Ok(unsupported) });
+        }
+        let fut = self.inner.call(req);
+        Box::pin(async move { fut.await.map_err(Into::into) })
+    }
+}
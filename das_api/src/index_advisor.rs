@@ -0,0 +1,423 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cadence_macros::statsd_count;
+use digital_asset_types::dao::raw_query::RawQuery;
+use log::{error, info, warn};
+use sea_orm::{metric::Info, ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult};
+use serde::Deserialize;
+
+use crate::{query_plans::is_read, safe_metric};
+
+const DEFAULT_REPORT_INTERVAL_SECS: u64 = 3600;
+const DEFAULT_MIN_CALLS: u64 = 100;
+// Queries bind their values, so a deployment runs a few hundred shapes at most.
+const DEFAULT_MAX_SHAPES: usize = 1000;
+
+const EXISTING_INDEXES: &str =
+    "SELECT tablename, indexdef FROM pg_indexes WHERE schemaname = current_schema()";
+
+const UPSERT_SUGGESTION: &str = "
+INSERT INTO index_suggestions
+    (table_name, index_columns, suggested_index, example_query, calls, total_ms, updated_at)
+VALUES ($1, $2, $3, $4, $5, $6, now())
+ON CONFLICT (table_name, index_columns) DO UPDATE SET
+    suggested_index = EXCLUDED.suggested_index,
+    example_query = EXCLUDED.example_query,
+    calls = index_suggestions.calls + EXCLUDED.calls,
+    total_ms = index_suggestions.total_ms + EXCLUDED.total_ms,
+    updated_at = now()
+";
+
+/// Counts the shapes of the queries the API runs and suggests the indexes they miss, e.g.
+/// `APP_INDEX_ADVISOR_CONFIG='{report_interval_secs=3600, min_calls=100}'`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct IndexAdvisorConfig {
+    pub report_interval_secs: Option<u64>,
+    pub min_calls: Option<u64>,
+    pub max_shapes: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct ShapeStats {
+    calls: u64,
+    total: Duration,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ExistingIndex {
+    tablename: String,
+    indexdef: String,
+}
+
+/// An index the queries of a shape would use, its columns ordered equality, sort then range.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexCandidate {
+    pub table: String,
+    pub columns: Vec<String>,
+    // The leading columns compared for equality, any order of them serves the query.
+    equality: usize,
+}
+
+impl IndexCandidate {
+    /// Whether an index on `index` already serves the shape: it leads with the equality
+    /// columns, or with the first column when there are none.
+    pub fn covered_by(&self, index: &[String]) -> bool {
+        let lead = self.equality.max(1);
+        if index.len() < lead {
+            return false;
+        }
+        let wanted: HashSet<&String> = self.columns[..lead].iter().collect();
+        let leading: HashSet<&String> = index[..lead].iter().collect();
+        wanted == leading
+    }
+
+    pub fn create_statement(&self) -> String {
+        format!(
+            "CREATE INDEX CONCURRENTLY ON {} ({})",
+            self.table,
+            self.columns.join(", ")
+        )
+    }
+}
+
+/// Each interval, reports the indexes that the query shapes called at least `min_calls` times
+/// would use and no existing index leads with. Suggestions are logged and added up in the
+/// `index_suggestions` table, which is the only thing the API ever writes.
+#[derive(Clone)]
+pub struct IndexAdvisor {
+    interval: Duration,
+    min_calls: u64,
+    max_shapes: usize,
+    shapes: Arc<Mutex<HashMap<String, ShapeStats>>>,
+    // A connection without the advisor's callback, so its own queries aren't counted.
+    conn: DatabaseConnection,
+}
+
+impl IndexAdvisor {
+    pub fn new(config: &IndexAdvisorConfig, conn: DatabaseConnection) -> Self {
+        IndexAdvisor {
+            interval: Duration::from_secs(
+                config
+                    .report_interval_secs
+                    .unwrap_or(DEFAULT_REPORT_INTERVAL_SECS)
+                    .max(1),
+            ),
+            min_calls: config.min_calls.unwrap_or(DEFAULT_MIN_CALLS),
+            max_shapes: config.max_shapes.unwrap_or(DEFAULT_MAX_SHAPES),
+            shapes: Arc::new(Mutex::new(HashMap::new())),
+            conn,
+        }
+    }
+
+    /// The connection's metric callback, called after each query.
+    pub fn on_query(&self, info: &Info<'_>) {
+        if !is_read(&info.statement.sql) {
+            return;
+        }
+        let shape = normalize(&info.statement.sql);
+        let mut shapes = self.shapes.lock().unwrap();
+        if shapes.len() >= self.max_shapes && !shapes.contains_key(&shape) {
+            return;
+        }
+        let stats = shapes.entry(shape).or_default();
+        stats.calls += 1;
+        stats.total += info.elapsed;
+    }
+
+    pub fn spawn_reporter(&self) {
+        let advisor = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(advisor.interval);
+            // The first tick completes immediately, before anything was counted.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = advisor.report().await {
+                    error!("Failed to report index suggestions: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn report(&self) -> Result<(), DbErr> {
+        let shapes = mem::take(&mut *self.shapes.lock().unwrap());
+        // The same index often serves several shapes, their calls add up.
+        let mut candidates: HashMap<IndexCandidate, (u64, Duration, String)> = HashMap::new();
+        for (sql, stats) in shapes {
+            if stats.calls < self.min_calls {
+                continue;
+            }
+            for candidate in index_candidates(&sql) {
+                let entry = candidates
+                    .entry(candidate)
+                    .or_insert_with(|| (0, Duration::ZERO, sql.clone()));
+                entry.0 += stats.calls;
+                entry.1 += stats.total;
+            }
+        }
+        if candidates.is_empty() {
+            return Ok(());
+        }
+
+        let existing = ExistingIndex::find_by_statement(RawQuery::new(EXISTING_INDEXES).build())
+            .all(&self.conn)
+            .await?;
+        let mut indexes: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for index in existing {
+            indexes
+                .entry(index.tablename)
+                .or_default()
+                .push(index_columns(&index.indexdef));
+        }
+
+        let mut suggested: i64 = 0;
+        for (candidate, (calls, total, sql)) in candidates {
+            let covered = indexes
+                .get(&candidate.table)
+                .map_or(false, |list| list.iter().any(|i| candidate.covered_by(i)));
+            if covered {
+                continue;
+            }
+            suggested += 1;
+            warn!(
+                "Index advisor: {} calls ({} ms in total) would use `{}`, e.g. {}",
+                calls,
+                total.as_millis(),
+                candidate.create_statement(),
+                sql
+            );
+            let upsert = RawQuery::new(UPSERT_SUGGESTION)
+                .bind(candidate.table.clone())
+                .bind(candidate.columns.join(","))
+                .bind(candidate.create_statement())
+                .bind(sql)
+                .bind(calls as i64)
+                .bind(total.as_secs_f64() * 1000.0)
+                .build();
+            self.conn.execute(upsert).await?;
+        }
+        safe_metric(|| {
+            statsd_count!("api_index_suggestion", suggested);
+        });
+        info!("Index advisor suggested {} indexes", suggested);
+        Ok(())
+    }
+}
+
+/// `sql` with every placeholder as `$` and lists of placeholders as one, so queries that only
+/// differ in the length of an `IN` list share a shape.
+pub fn normalize(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '$' && chars.peek().map_or(false, char::is_ascii_digit) {
+            while chars.peek().map_or(false, char::is_ascii_digit) {
+                chars.next();
+            }
+            if !out.ends_with("$, ") {
+                out.push('$');
+            } else {
+                out.truncate(out.len() - 2);
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Predicate {
+    Equality,
+    Range,
+}
+
+const KEYWORDS: &[&str] = &[
+    "ON", "WHERE", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "JOIN", "GROUP", "ORDER", "LIMIT",
+    "OFFSET", "UNION", "AS", "USING", "LATERAL", "AND", "OR",
+];
+
+fn trim_token(token: &str) -> &str {
+    token.trim_matches(|c| c == '(' || c == ')' || c == ',' || c == ';')
+}
+
+/// The tables of `FROM` and `JOIN` clauses, by their alias and by their name.
+fn table_aliases(tokens: &[&str]) -> HashMap<String, String> {
+    let mut aliases = HashMap::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.eq_ignore_ascii_case("FROM") && !token.eq_ignore_ascii_case("JOIN") {
+            continue;
+        }
+        let table = match tokens.get(i + 1) {
+            Some(t) if !t.starts_with('(') => trim_token(t).replace('"', ""),
+            _ => continue,
+        };
+        let table = table.rsplit('.').next().unwrap_or_default().to_string();
+        let mut next = i + 2;
+        if tokens
+            .get(next)
+            .map_or(false, |t| t.eq_ignore_ascii_case("AS"))
+        {
+            next += 1;
+        }
+        if let Some(alias) = tokens.get(next) {
+            let alias = trim_token(alias).replace('"', "");
+            if !alias.is_empty() && !KEYWORDS.iter().any(|k| alias.eq_ignore_ascii_case(k)) {
+                aliases.insert(alias, table.clone());
+            }
+        }
+        aliases.insert(table.clone(), table);
+    }
+    aliases
+}
+
+/// The table and column of a `"table"."column"` or `alias.column` reference.
+fn column_ref(token: &str, aliases: &HashMap<String, String>) -> Option<(String, String)> {
+    let token = trim_token(token).replace('"', "");
+    let (qualifier, column) = token.split_once('.')?;
+    let table = aliases.get(qualifier)?;
+    let valid = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid(column).then(|| (table.clone(), column.to_string()))
+}
+
+fn predicate(op: &str) -> Option<Predicate> {
+    match op.to_ascii_uppercase().as_str() {
+        "=" | "IN" | "IS" | "@>" => Some(Predicate::Equality),
+        ">" | "<" | ">=" | "<=" | "BETWEEN" | "LIKE" => Some(Predicate::Range),
+        _ => None,
+    }
+}
+
+// A predicate against another column is a join condition, not a filter.
+fn is_value(token: &str) -> bool {
+    let token = token.trim_start_matches('(');
+    token.starts_with('$')
+        || token.starts_with('\'')
+        || token.starts_with(|c: char| c.is_ascii_digit())
+        || ["TRUE", "FALSE", "NULL"]
+            .iter()
+            .any(|k| trim_token(token).eq_ignore_ascii_case(k))
+}
+
+/// The indexes the filters and the sort of a query would use, one per table. Columns follow the
+/// equality, sort, range rule: the leading equality columns narrow the scan to rows already in
+/// the sort order, and a range column only helps last.
+pub fn index_candidates(sql: &str) -> Vec<IndexCandidate> {
+    let tokens: Vec<&str> = sql.split_whitespace().collect();
+    let aliases = table_aliases(&tokens);
+    let order_by = tokens
+        .windows(2)
+        .rposition(|w| w[0].eq_ignore_ascii_case("ORDER") && w[1].eq_ignore_ascii_case("BY"));
+
+    let mut filters: Vec<(String, String, Predicate)> = Vec::new();
+    for (i, window) in tokens.windows(3).enumerate() {
+        if order_by.map_or(false, |o| i >= o) {
+            break;
+        }
+        let (column, op, value) = (window[0], window[1], window[2]);
+        if let (Some((table, column)), Some(predicate)) =
+            (column_ref(column, &aliases), predicate(op))
+        {
+            if is_value(value) {
+                filters.push((table, column, predicate));
+            }
+        }
+    }
+    let mut sort: Vec<(String, String)> = Vec::new();
+    if let Some(o) = order_by {
+        for token in tokens[o + 2..].iter() {
+            if ["LIMIT", "OFFSET", "FOR"]
+                .iter()
+                .any(|k| token.eq_ignore_ascii_case(k))
+            {
+                break;
+            }
+            if let Some(column) = column_ref(token, &aliases) {
+                sort.push(column);
+            }
+        }
+    }
+
+    let mut tables: Vec<String> = Vec::new();
+    for (table, _, _) in filters.iter() {
+        if !tables.contains(table) {
+            tables.push(table.clone());
+        }
+    }
+    tables
+        .into_iter()
+        .filter_map(|table| {
+            let mut columns: Vec<String> = Vec::new();
+            for (_, column, _) in filters
+                .iter()
+                .filter(|(t, _, p)| *t == table && *p == Predicate::Equality)
+            {
+                push_unique(&mut columns, column);
+            }
+            let equality = columns.len();
+            for (_, column) in sort.iter().filter(|(t, _)| *t == table) {
+                push_unique(&mut columns, column);
+            }
+            if let Some((_, column, _)) = filters
+                .iter()
+                .find(|(t, _, p)| *t == table && *p == Predicate::Range)
+            {
+                push_unique(&mut columns, column);
+            }
+            (!columns.is_empty()).then(|| IndexCandidate {
+                table,
+                columns,
+                equality,
+            })
+        })
+        .collect()
+}
+
+fn push_unique(columns: &mut Vec<String>, column: &str) {
+    if !columns.iter().any(|c| c == column) {
+        columns.push(column.to_string());
+    }
+}
+
+/// The columns of a `pg_indexes.indexdef`, e.g. `CREATE INDEX x ON public.asset USING btree
+/// (owner, id DESC) WHERE ...`.
+pub fn index_columns(indexdef: &str) -> Vec<String> {
+    let columns = match indexdef.split_once(" USING ") {
+        Some((_, rest)) => rest,
+        None => return Vec::new(),
+    };
+    let start = match columns.find('(') {
+        Some(start) => start + 1,
+        None => return Vec::new(),
+    };
+    let mut depth = 1;
+    let mut end = columns.len();
+    for (i, c) in columns[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = start + i;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    columns[start..end]
+        .split(',')
+        .map(|column| {
+            column
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .replace('"', "")
+        })
+        .collect()
+}
@@ -0,0 +1,29 @@
+pub mod api;
+mod builder;
+pub mod config;
+mod debug_auth;
+pub mod error;
+mod etag;
+mod feature_flag;
+mod http;
+pub mod index_advisor;
+mod limits;
+mod native_balance;
+pub mod private_assets;
+mod query_plans;
+mod query_watchdog;
+mod server;
+pub mod transfer_simulator;
+mod tree_roots;
+mod validation;
+
+pub use server::{setup_metrics, start_server};
+
+use crate::error::DasApiError;
+use cadence_macros::is_global_default_set;
+
+pub fn safe_metric<F: Fn()>(f: F) {
+    if is_global_default_set() {
+        f()
+    }
+}
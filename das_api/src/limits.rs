@@ -0,0 +1,309 @@
+use std::{collections::HashMap, future::Future, sync::Arc, time::Duration};
+
+use cadence_macros::statsd_count;
+use log::warn;
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+
+use crate::{
+    config::Config, error::DasApiError, query_plans::in_request, query_watchdog::is_cancelled,
+    safe_metric,
+};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 1000;
+
+/// Overrides for a single RPC method, keyed by its public (camelCase) name in the config,
+/// e.g. `APP_METHOD_LIMITS='{searchAssets={timeout_ms=2000,max_concurrency=20}}'`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MethodLimitConfig {
+    pub timeout_ms: Option<u64>,
+    pub max_page_size: Option<u32>,
+    pub max_concurrency: Option<usize>,
+    pub max_conditions: Option<usize>,
+}
+
+struct MethodLimit {
+    timeout: Option<Duration>,
+    max_page_size: u32,
+    max_conditions: Option<usize>,
+    permits: Option<Arc<Semaphore>>,
+}
+
+/// Per method query timeouts, page size and filter limits, and concurrency caps. Requests over
+/// a method's concurrency cap are rejected straight away rather than queued, so one slow method
+/// can't tie up every database connection.
+pub struct MethodLimits {
+    default: MethodLimit,
+    methods: HashMap<String, MethodLimit>,
+}
+
+impl MethodLimits {
+    pub fn from_config(config: &Config) -> Self {
+        let default = MethodLimit {
+            timeout: config.query_timeout_ms.map(Duration::from_millis),
+            max_page_size: config.max_page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+            max_conditions: config.max_search_conditions,
+            permits: None,
+        };
+        let methods = config
+            .method_limits
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(method, limit)| {
+                (
+                    method,
+                    MethodLimit {
+                        timeout: limit
+                            .timeout_ms
+                            .map(Duration::from_millis)
+                            .or(default.timeout),
+                        max_page_size: limit.max_page_size.unwrap_or(default.max_page_size),
+                        max_conditions: limit.max_conditions.or(default.max_conditions),
+                        permits: limit.max_concurrency.map(|n| Arc::new(Semaphore::new(n))),
+                    },
+                )
+            })
+            .collect();
+        MethodLimits { default, methods }
+    }
+
+    /// Longest configured timeout, used as the server side `statement_timeout` backstop so
+    /// abandoned queries don't keep running in Postgres.
+    pub fn max_timeout(&self) -> Option<Duration> {
+        self.methods
+            .values()
+            .chain(std::iter::once(&self.default))
+            .filter_map(|l| l.timeout)
+            .max()
+    }
+
+    fn get(&self, method: &str) -> &MethodLimit {
+        self.methods.get(method).unwrap_or(&self.default)
+    }
+
+    pub fn max_page_size(&self, method: &str) -> u32 {
+        self.get(method).max_page_size
+    }
+
+    /// The page size to query with, defaulting to the largest page allowed.
+    pub fn page_size(&self, method: &str, limit: Option<u32>) -> u64 {
+        limit.unwrap_or_else(|| self.max_page_size(method).min(DEFAULT_PAGE_SIZE)) as u64
+    }
+
+    pub fn validate_conditions(&self, method: &str, conditions: usize) -> Result<(), DasApiError> {
+        match self.get(method).max_conditions {
+            Some(max) if conditions > max => Err(DasApiError::QueryTooComplex(format!(
+                "{} filters given, at most {} are allowed",
+                conditions, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs `fut` under the method's concurrency cap and timeout.
+    pub async fn run<T, E, F>(&self, method: &str, fut: F) -> Result<T, DasApiError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<DasApiError>,
+    {
+        self.run_shaped(method, None, fut).await
+    }
+
+    /// [Self::run] for a query of the filter `shape`, which is recorded when Postgres cancels
+    /// the query for running too long.
+    pub async fn run_shaped<T, E, F>(
+        &self,
+        method: &str,
+        shape: Option<String>,
+        fut: F,
+    ) -> Result<T, DasApiError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<DasApiError>,
+    {
+        match self.run_limited(method, fut).await {
+            Err(DasApiError::DatabaseError(e)) if is_cancelled(&e) => {
+                warn!(
+                    "Cancelled an expensive {} query of shape {}",
+                    method,
+                    shape.as_deref().unwrap_or("-")
+                );
+                safe_metric(|| {
+                    statsd_count!("api_query_too_expensive", 1, "method" => method);
+                });
+                Err(DasApiError::QueryTooExpensive(method.to_string(), shape))
+            }
+            result => result,
+        }
+    }
+
+    async fn run_limited<T, E, F>(&self, method: &str, fut: F) -> Result<T, DasApiError>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Into<DasApiError>,
+    {
+        let fut = in_request(method, fut);
+        let limit = self.get(method);
+        let _permit = match &limit.permits {
+            Some(permits) => match permits.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    safe_metric(|| {
+                        statsd_count!("api_call_shed", 1, "method" => method);
+                    });
+                    return Err(DasApiError::RateLimited(method.to_string()));
+                }
+            },
+            None => None,
+        };
+        match limit.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, fut).await {
+                Ok(res) => res.map_err(Into::into),
+                Err(_) => {
+                    safe_metric(|| {
+                        statsd_count!("api_call_timeout", 1, "method" => method);
+                    });
+                    Err(DasApiError::QueryTimeout(
+                        method.to_string(),
+                        timeout.as_millis() as u64,
+                    ))
+                }
+            },
+            None => fut.await.map_err(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::load_config_from;
+    use crate::error::{QUERY_TIMEOUT_CODE, QUERY_TOO_COMPLEX_CODE, RATE_LIMITED_CODE};
+    use figment::{providers::Serialized, Figment};
+    use jsonrpsee::{core::Error as RpcError, types::error::CallError};
+    use serde_json::json;
+
+    fn method_limits(overrides: serde_json::Value) -> MethodLimits {
+        let mut config = json!({ "database_url": "postgres://localhost/das", "server_port": 9090 });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(overrides.as_object().unwrap().clone());
+        MethodLimits::from_config(
+            &load_config_from(Figment::from(Serialized::defaults(config))).unwrap(),
+        )
+    }
+
+    fn code(error: DasApiError) -> i32 {
+        match Into::<RpcError>::into(error) {
+            RpcError::Call(CallError::Custom(error)) => error.code(),
+            error => panic!("{:?}", error),
+        }
+    }
+
+    #[test]
+    fn page_sizes_default_to_the_largest_page_allowed() {
+        let unset = method_limits(json!({}));
+        assert_eq!(unset.max_page_size("getAssetsByOwner"), DEFAULT_PAGE_SIZE);
+        assert_eq!(
+            unset.page_size("getAssetsByOwner", None),
+            DEFAULT_PAGE_SIZE as u64
+        );
+        assert_eq!(unset.max_timeout(), None);
+
+        let limits = method_limits(json!({
+            "max_page_size": 500,
+            "query_timeout_ms": 1000,
+            "method_limits": {
+                "searchAssets": { "max_page_size": 50, "timeout_ms": 2000 },
+                "getAssetsByGroup": { "max_page_size": 5000 },
+            },
+        }));
+        assert_eq!(limits.max_page_size("getAssetsByOwner"), 500);
+        assert_eq!(limits.page_size("getAssetsByOwner", None), 500);
+        assert_eq!(limits.page_size("getAssetsByOwner", Some(20)), 20);
+        assert_eq!(limits.max_page_size("searchAssets"), 50);
+        assert_eq!(limits.page_size("searchAssets", None), 50);
+        // Larger pages are allowed when asked for, not by default.
+        assert_eq!(limits.max_page_size("getAssetsByGroup"), 5000);
+        assert_eq!(
+            limits.page_size("getAssetsByGroup", None),
+            DEFAULT_PAGE_SIZE as u64
+        );
+        assert_eq!(limits.max_timeout(), Some(Duration::from_millis(2000)));
+    }
+
+    #[test]
+    fn conditions_over_the_method_limit_are_too_complex() {
+        let limits = method_limits(json!({
+            "max_search_conditions": 3,
+            "method_limits": { "searchAssets": { "max_conditions": 1 } },
+        }));
+        assert!(limits.validate_conditions("searchAssets", 1).is_ok());
+        let error = limits.validate_conditions("searchAssets", 2).unwrap_err();
+        assert!(matches!(error, DasApiError::QueryTooComplex(_)));
+        assert_eq!(code(error), QUERY_TOO_COMPLEX_CODE);
+        assert!(limits.validate_conditions("getAssetsByOwner", 3).is_ok());
+        assert!(limits.validate_conditions("getAssetsByOwner", 4).is_err());
+
+        assert!(method_limits(json!({}))
+            .validate_conditions("searchAssets", 100)
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn calls_time_out_after_their_method_timeout() {
+        let limits = method_limits(json!({
+            "query_timeout_ms": 5000,
+            "method_limits": { "searchAssets": { "timeout_ms": 10 } },
+        }));
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, DasApiError>(())
+        };
+
+        let error = limits.run("searchAssets", slow).await.unwrap_err();
+        assert!(matches!(
+            &error,
+            DasApiError::QueryTimeout(method, 10) if method == "searchAssets"
+        ));
+        assert_eq!(code(error), QUERY_TIMEOUT_CODE);
+        let fast = async { Ok::<_, DasApiError>(1) };
+        assert_eq!(limits.run("getAssetsByOwner", fast).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn calls_over_the_concurrency_cap_are_shed() {
+        let limits = method_limits(json!({
+            "method_limits": { "getAssetProof": { "max_concurrency": 1 } },
+        }));
+        let (done, finish) = tokio::sync::oneshot::channel::<()>();
+        let running = limits.run("getAssetProof", async {
+            finish
+                .await
+                .map_err(|e| DasApiError::RpcError(e.to_string()))
+        });
+        let shed = async {
+            // Lets the running call take the method's only permit.
+            tokio::task::yield_now().await;
+            let shed = limits
+                .run("getAssetProof", async { Ok::<_, DasApiError>(()) })
+                .await;
+            done.send(()).unwrap();
+            shed
+        };
+
+        let (running, shed) = tokio::join!(running, shed);
+        assert!(running.is_ok());
+        let error = shed.unwrap_err();
+        assert!(matches!(&error, DasApiError::RateLimited(method) if method == "getAssetProof"));
+        assert_eq!(code(error), RATE_LIMITED_CODE);
+        // The permit is released with the call.
+        let next = async { Ok::<_, DasApiError>(()) };
+        assert!(limits.run("getAssetProof", next).await.is_ok());
+        // Other methods have no cap.
+        let other = async { Ok::<_, DasApiError>(()) };
+        assert!(limits.run("getAsset", other).await.is_ok());
+    }
+}
@@ -0,0 +1,48 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+
+use crate::DasApiError;
+
+// Wallets paging through their assets ask for the same balance once per page.
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Reads owners' SOL balances from chain, caching them briefly so paging through an owner's
+/// assets doesn't cost an RPC call per page.
+pub struct NativeBalanceCache {
+    client: RpcClient,
+    cache: Mutex<HashMap<Pubkey, (Instant, u64)>>,
+}
+
+impl NativeBalanceCache {
+    pub fn new(rpc_url: String) -> Self {
+        NativeBalanceCache {
+            client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, owner: &Pubkey) -> Result<u64, DasApiError> {
+        if let Some((fetched_at, lamports)) = self.cache.lock().unwrap().get(owner) {
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Ok(*lamports);
+            }
+        }
+
+        let lamports = self
+            .client
+            .get_balance(owner)
+            .await
+            .map_err(|e| DasApiError::RpcError(e.to_string()))?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < CACHE_TTL);
+        cache.insert(*owner, (Instant::now(), lamports));
+        Ok(lamports)
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    error::Error,
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use digital_asset_types::rpc::transform::HiddenAssets;
+use hyper::{header::AUTHORIZATION, Body, Request, Response};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use tower::{Layer, Service};
+
+use crate::{debug_auth::keys_match, error::DasApiError};
+
+tokio::task_local! {
+    static API_KEY: Option<String>;
+}
+
+/// Collections and trees only served to the requests presenting `api_key`, e.g.
+/// `APP_PRIVATE_ASSETS='[{api_key="...", collections=["..."], trees=["..."]}]'`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PrivateAssetsConfig {
+    pub api_key: String,
+    #[serde(default)]
+    pub collections: Vec<String>,
+    #[serde(default)]
+    pub trees: Vec<String>,
+}
+
+#[derive(Debug)]
+struct PrivateGroup {
+    api_key: String,
+    collections: Vec<String>,
+    trees: Vec<Vec<u8>>,
+}
+
+/// Hides the private collections and trees from every request but the ones presenting one of
+/// their keys. A collection or tree listed under several keys is served to each of them.
+#[derive(Debug, Default)]
+pub struct PrivateAssets {
+    groups: Vec<PrivateGroup>,
+}
+
+impl PrivateAssets {
+    pub fn from_config(configs: Vec<PrivateAssetsConfig>) -> Result<Self, DasApiError> {
+        let pubkey = |address: &str| {
+            Pubkey::from_str(address).map_err(|_| {
+                DasApiError::ConfigurationError(format!(
+                    "invalid private asset address {}",
+                    address
+                ))
+            })
+        };
+        let groups = configs
+            .into_iter()
+            .map(|config| {
+                if config.api_key.is_empty() {
+                    return Err(DasApiError::ConfigurationError(
+                        "private assets need an api key".to_string(),
+                    ));
+                }
+                Ok(PrivateGroup {
+                    collections: config
+                        .collections
+                        .iter()
+                        .map(|c| pubkey(c).map(|c| c.to_string()))
+                        .collect::<Result<_, _>>()?,
+                    trees: config
+                        .trees
+                        .iter()
+                        .map(|t| pubkey(t).map(|t| t.to_bytes().to_vec()))
+                        .collect::<Result<_, _>>()?,
+                    api_key: config.api_key,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(PrivateAssets { groups })
+    }
+
+    /// The assets hidden from the current request, given the key it presented.
+    pub fn hidden(&self) -> HiddenAssets {
+        if self.groups.is_empty() {
+            return HiddenAssets::default();
+        }
+        let api_key = API_KEY.try_with(|key| key.clone()).ok().flatten();
+        let (granted, hidden): (Vec<_>, Vec<_>) = self
+            .groups
+            .iter()
+            .partition(|group| matches!(&api_key, Some(key) if keys_match(key, &group.api_key)));
+        let mut assets = HiddenAssets::default();
+        for group in hidden {
+            for collection in &group.collections {
+                if !granted.iter().any(|g| g.collections.contains(collection))
+                    && !assets.hides_collection(collection)
+                {
+                    assets.collections.push(collection.clone());
+                }
+            }
+            for tree in &group.trees {
+                if !granted.iter().any(|g| g.trees.contains(tree)) && !assets.hides_tree(tree) {
+                    assets.trees.push(tree.clone());
+                }
+            }
+        }
+        assets
+    }
+}
+
+/// Runs `fut` as a request presenting `api_key`.
+pub async fn with_api_key<F: Future>(api_key: Option<String>, fut: F) -> F::Output {
+    API_KEY.scope(api_key, fut).await
+}
+
+/// Makes the `Authorization: Bearer <key>` of a request available to [PrivateAssets::hidden]
+/// while the request is handled.
+#[derive(Debug, Clone)]
+pub struct PrivateAssetsLayer;
+
+impl<S> Layer<S> for PrivateAssetsLayer {
+    type Service = PrivateAssetsKey<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PrivateAssetsKey { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PrivateAssetsKey<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for PrivateAssetsKey<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = Box<dyn Error + Send + Sync + 'static>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let api_key = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|key| key.trim().to_string());
+        let fut = self.inner.call(req);
+        Box::pin(async move { with_api_key(api_key, fut).await.map_err(Into::into) })
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    future::Future,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cadence_macros::statsd_count;
+use log::{error, warn};
+use rand::Rng;
+use sea_orm::{metric::Info, ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::Semaphore;
+
+use crate::safe_metric;
+
+const DEFAULT_SLOW_QUERY_MS: u64 = 1000;
+// Explaining runs the query again, so only a few run at once.
+const MAX_CONCURRENT_EXPLAINS: usize = 2;
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+tokio::task_local! {
+    static REQUEST: RequestContext;
+}
+
+/// Samples slow queries to log their plans, e.g.
+/// `APP_QUERY_PLAN_CONFIG='{slow_query_ms=500, sample_rate=0.05, plan_dir="/var/tmp/plans"}'`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct QueryPlanConfig {
+    pub slow_query_ms: Option<u64>,
+    pub sample_rate: Option<f64>,
+    pub plan_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct RequestContext {
+    id: u64,
+    method: String,
+}
+
+/// Runs `fut` as a request of `method`, so the plans of its queries are tagged with it.
+pub async fn in_request<F: Future>(method: &str, fut: F) -> F::Output {
+    let request = RequestContext {
+        id: NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+        method: method.to_string(),
+    };
+    REQUEST.scope(request, fut).await
+}
+
+/// Runs `EXPLAIN ANALYZE` on a sample of the queries of API requests that took longer than
+/// `slow_query_ms`, logging each plan with the request's id and method and the query's
+/// parameters, and writing it to `plan_dir` when set.
+#[derive(Clone)]
+pub struct QueryPlanSampler {
+    threshold: Duration,
+    sample_rate: f64,
+    plan_dir: Option<PathBuf>,
+    // A connection without the sampler's callback, so explaining doesn't sample itself.
+    conn: DatabaseConnection,
+    explains: Arc<Semaphore>,
+}
+
+impl QueryPlanSampler {
+    pub fn new(config: &QueryPlanConfig, conn: DatabaseConnection) -> Self {
+        QueryPlanSampler {
+            threshold: Duration::from_millis(config.slow_query_ms.unwrap_or(DEFAULT_SLOW_QUERY_MS)),
+            sample_rate: config.sample_rate.unwrap_or(1.0).clamp(0.0, 1.0),
+            plan_dir: config.plan_dir.clone(),
+            conn,
+            explains: Arc::new(Semaphore::new(MAX_CONCURRENT_EXPLAINS)),
+        }
+    }
+
+    /// The connection's metric callback, called after each query.
+    pub fn on_query(&self, info: &Info<'_>) {
+        if info.elapsed < self.threshold {
+            return;
+        }
+        let request = match REQUEST.try_with(|r| r.clone()) {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+        if !is_read(&info.statement.sql) || rand::thread_rng().gen::<f64>() >= self.sample_rate {
+            return;
+        }
+        // Slow queries come in bursts, explains over the limit are dropped rather than queued.
+        let permit = match self.explains.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+        let (sampler, statement, elapsed) = (self.clone(), info.statement.clone(), info.elapsed);
+        tokio::spawn(async move {
+            let _permit = permit;
+            sampler.capture(request, statement, elapsed).await;
+        });
+    }
+
+    async fn capture(&self, request: RequestContext, statement: Statement, elapsed: Duration) {
+        let values = statement.values.clone().map(|v| v.0).unwrap_or_default();
+        let explain = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", statement.sql),
+            values.clone(),
+        );
+        let plan = match self.conn.query_one(explain).await {
+            Ok(Some(row)) => row.try_get::<JsonValue>("", "QUERY PLAN"),
+            Ok(None) => return,
+            Err(e) => Err(e),
+        };
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(e) => {
+                error!("Failed to explain a slow {} query: {}", request.method, e);
+                return;
+            }
+        };
+        safe_metric(|| {
+            statsd_count!("api_query_plan_captured", 1, "method" => &request.method);
+        });
+        let entry = json!({
+            "request_id": request.id,
+            "method": request.method,
+            "elapsed_ms": elapsed.as_millis() as u64,
+            "sql": statement.sql,
+            "params": values.iter().map(|v| format!("{:?}", v)).collect::<Vec<_>>(),
+            "plan": plan,
+        });
+        warn!("Slow query plan: {}", entry);
+        if let Some(dir) = &self.plan_dir {
+            let path = dir.join(format!("plan-{}-{}.json", request.method, request.id));
+            if let Err(e) = tokio::fs::write(&path, entry.to_string()).await {
+                error!("Failed to write query plan to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+// `EXPLAIN ANALYZE` executes the statement, which is only harmless for reads.
+pub(crate) fn is_read(sql: &str) -> bool {
+    let sql = sql.trim_start().to_ascii_uppercase();
+    sql.starts_with("SELECT") || sql.starts_with("WITH")
+}
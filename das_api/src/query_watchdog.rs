@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use cadence_macros::statsd_count;
+use log::{error, warn};
+use sea_orm::{DatabaseConnection, DbBackend, DbErr, FromQueryResult, Statement};
+use serde::Deserialize;
+
+use crate::safe_metric;
+
+const DEFAULT_THRESHOLD_MS: u64 = 10_000;
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+pub const DEFAULT_APPLICATION_NAME: &str = "das-api";
+
+// Only this API's queries, not the watchdog's own, and not the ones waiting on a client.
+const CANCEL_LONG_QUERIES: &str = "
+SELECT pid,
+    (EXTRACT(EPOCH FROM now() - query_start) * 1000)::float8 AS elapsed_ms,
+    left(query, 500) AS query,
+    pg_cancel_backend(pid) AS cancelled
+FROM pg_stat_activity
+WHERE application_name = $1
+    AND state = 'active'
+    AND pid <> pg_backend_pid()
+    AND query_start < now() - make_interval(secs => $2)
+";
+
+/// Cancels the API's queries that run for longer than `threshold_ms`, e.g.
+/// `APP_QUERY_WATCHDOG_CONFIG='{threshold_ms=5000, interval_ms=1000}'`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct QueryWatchdogConfig {
+    pub threshold_ms: Option<u64>,
+    pub interval_ms: Option<u64>,
+    /// Set on the API's connections so the watchdog tells its queries apart from other clients'.
+    pub application_name: Option<String>,
+}
+
+impl QueryWatchdogConfig {
+    pub fn get_application_name(&self) -> String {
+        self.application_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_APPLICATION_NAME.to_string())
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct LongQuery {
+    pid: i32,
+    elapsed_ms: f64,
+    query: String,
+    cancelled: bool,
+}
+
+/// Polls `pg_stat_activity` for the API's queries running past the threshold and cancels them
+/// with `pg_cancel_backend`. A timed out request stops waiting on its query, but Postgres keeps
+/// running it until `statement_timeout`, if there is one. The request of a cancelled query fails
+/// with [crate::DasApiError::QueryTooExpensive].
+pub struct QueryWatchdog {
+    conn: DatabaseConnection,
+    application_name: String,
+    threshold: Duration,
+    interval: Duration,
+}
+
+impl QueryWatchdog {
+    pub fn new(config: &QueryWatchdogConfig, conn: DatabaseConnection) -> Self {
+        QueryWatchdog {
+            conn,
+            application_name: config.get_application_name(),
+            threshold: Duration::from_millis(config.threshold_ms.unwrap_or(DEFAULT_THRESHOLD_MS)),
+            interval: Duration::from_millis(
+                config.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).max(1),
+            ),
+        }
+    }
+
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.cancel_long_queries().await {
+                    error!("Failed to check for long running queries: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn cancel_long_queries(&self) -> Result<(), DbErr> {
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            CANCEL_LONG_QUERIES,
+            vec![
+                self.application_name.clone().into(),
+                self.threshold.as_secs_f64().into(),
+            ],
+        );
+        for query in LongQuery::find_by_statement(statement)
+            .all(&self.conn)
+            .await?
+        {
+            if !query.cancelled {
+                continue;
+            }
+            warn!(
+                "Cancelled query of backend {} after {}ms: {}",
+                query.pid, query.elapsed_ms as u64, query.query
+            );
+            safe_metric(|| {
+                statsd_count!("api_query_cancelled", 1);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether `err` is Postgres cancelling a query, by the watchdog or `statement_timeout`. sea-orm
+/// only passes the database's message on, so it is matched on that.
+pub fn is_cancelled(err: &DbErr) -> bool {
+    err.to_string().contains("canceling statement due to")
+}
@@ -0,0 +1,137 @@
+use std::{net::SocketAddr, time::Instant};
+
+use cadence::StatsdClient;
+use cadence_macros::{set_global_default, statsd_time};
+use digital_asset_types::metrics_sink::{FlushingMetricSink, LogMetricSink};
+use jsonrpsee::server::{
+    logger::{Logger, TransportProtocol},
+    middleware::proxy_get_request::ProxyGetRequestLayer,
+    ServerBuilder, ServerHandle,
+};
+use log::{debug, warn};
+
+use crate::{
+    api::DasApi,
+    builder::RpcApiBuilder,
+    config::Config,
+    debug_auth::DebugAuthLayer,
+    error::DasApiError,
+    etag::EtagLayer,
+    http::{compression_layer, cors_layer, into_hyper_body, Http2Layer},
+    private_assets::PrivateAssetsLayer,
+    safe_metric,
+};
+
+pub fn setup_metrics(config: &Config) {
+    let uri = config.metrics_host.clone();
+    let port = config.metrics_port;
+    let env = config.env.clone().unwrap_or_else(|| "dev".to_string());
+    if uri.is_some() || port.is_some() {
+        let host = (uri.unwrap(), port.unwrap());
+        let tags = format!("|#env:{}", env);
+        let builder = match FlushingMetricSink::start(host.clone(), "das_api", tags) {
+            Ok(sink) => StatsdClient::builder("das_api", sink),
+            Err(err) => {
+                warn!(
+                    "Metrics can't be sent to {}:{}, writing them to the log instead: {}",
+                    host.0, host.1, err
+                );
+                StatsdClient::builder("das_api", LogMetricSink)
+            }
+        };
+        let client = builder.with_tag("env", env).build();
+        set_global_default(client);
+    }
+}
+
+#[derive(Clone)]
+struct MetricMiddleware;
+
+impl Logger for MetricMiddleware {
+    type Instant = Instant;
+
+    fn on_request(&self, _t: TransportProtocol) -> Self::Instant {
+        Instant::now()
+    }
+
+    fn on_result(
+        &self,
+        name: &str,
+        success: bool,
+        started_at: Self::Instant,
+        _t: TransportProtocol,
+    ) {
+        let stat = match success {
+            true => "success",
+            false => "failure",
+        };
+        debug!(
+            "Call to '{}' {} took {:?}",
+            name,
+            stat,
+            started_at.elapsed()
+        );
+        safe_metric(|| {
+            let success = success.to_string();
+            statsd_time!("api_call", started_at.elapsed(), "method" => name, "success" => &success);
+        });
+    }
+
+    fn on_connect(
+        &self,
+        remote_addr: SocketAddr,
+        _request: &jsonrpsee::server::logger::HttpRequest,
+        _t: TransportProtocol,
+    ) {
+        debug!("Connecting from {}", remote_addr)
+    }
+
+    fn on_call(
+        &self,
+        method_name: &str,
+        params: jsonrpsee::types::Params,
+        _kind: jsonrpsee::server::logger::MethodKind,
+        _transport: TransportProtocol,
+    ) {
+        debug!("Call: {} {:?}", method_name, params);
+    }
+
+    fn on_response(&self, result: &str, _started_at: Self::Instant, _transport: TransportProtocol) {
+        debug!("Response: {}", result);
+    }
+
+    fn on_disconnect(&self, remote_addr: SocketAddr, _transport: TransportProtocol) {
+        debug!("Disconnecting from {}", remote_addr);
+    }
+}
+
+/// Starts serving the API on `APP_SERVER_PORT`. The server runs until the returned handle is
+/// stopped.
+pub async fn start_server(config: Config) -> Result<ServerHandle, DasApiError> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.server_port));
+    let cors = cors_layer(&config)?;
+    let middleware = tower::ServiceBuilder::new()
+        .layer(cors)
+        .layer(Http2Layer::new(config.enable_http2.unwrap_or(true)))
+        .map_response(into_hyper_body)
+        .layer(compression_layer(&config))
+        .layer(ProxyGetRequestLayer::new("/health", "healthz")?)
+        .layer(DebugAuthLayer::new(config.debug_api_key.clone()))
+        .layer(PrivateAssetsLayer)
+        .layer(EtagLayer);
+
+    let mut server = ServerBuilder::default();
+    if let Some(max_request_body_size) = config.max_request_body_size {
+        server = server.max_request_body_size(max_request_body_size);
+    }
+    let server = server
+        .set_middleware(middleware)
+        .set_logger(MetricMiddleware)
+        .build(addr)
+        .await?;
+
+    let enable_debug_methods = config.debug_api_key.is_some();
+    let api = DasApi::from_config(config).await?;
+    let rpc = RpcApiBuilder::build(Box::new(api), enable_debug_methods)?;
+    Ok(server.start(rpc)?)
+}
@@ -0,0 +1,107 @@
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction,
+};
+
+use crate::DasApiError;
+
+/// A Bubblegum transfer of a compressed asset, as indexed.
+#[derive(Debug, Clone)]
+pub struct CnftTransfer {
+    pub tree: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub new_owner: Pubkey,
+    pub root: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    /// The proof without the nodes the tree's canopy holds.
+    pub proof: Vec<Pubkey>,
+}
+
+impl CnftTransfer {
+    /// The transfer signed by the owner, with the proof as the remaining accounts.
+    pub fn instruction(&self) -> Instruction {
+        // Anchor instructions start with the first bytes of the hash of their name.
+        let mut data = hash(b"global:transfer").to_bytes()[..8].to_vec();
+        data.extend_from_slice(&self.root);
+        data.extend_from_slice(&self.data_hash);
+        data.extend_from_slice(&self.creator_hash);
+        data.extend_from_slice(&self.nonce.to_le_bytes());
+        data.extend_from_slice(&(self.nonce as u32).to_le_bytes());
+        let tree_authority =
+            Pubkey::find_program_address(&[self.tree.as_ref()], &mpl_bubblegum::id()).0;
+        let mut accounts = vec![
+            AccountMeta::new_readonly(tree_authority, false),
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(self.delegate, false),
+            AccountMeta::new_readonly(self.new_owner, false),
+            AccountMeta::new(self.tree, false),
+            AccountMeta::new_readonly(spl_noop::id(), false),
+            AccountMeta::new_readonly(spl_account_compression::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        accounts.extend(
+            self.proof
+                .iter()
+                .map(|node| AccountMeta::new_readonly(*node, false)),
+        );
+        Instruction {
+            program_id: mpl_bubblegum::id(),
+            accounts,
+            data,
+        }
+    }
+}
+
+/// What the RPC made of a transfer.
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub slot: u64,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+}
+
+/// Runs transfers of compressed assets through `simulateTransaction`, without signatures.
+pub struct TransferSimulator {
+    client: RpcClient,
+}
+
+impl TransferSimulator {
+    pub fn new(rpc_url: String) -> Self {
+        TransferSimulator {
+            client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+        }
+    }
+
+    pub async fn simulate(
+        &self,
+        transfer: &CnftTransfer,
+        fee_payer: &Pubkey,
+    ) -> Result<SimulationResult, DasApiError> {
+        let message = Message::new(&[transfer.instruction()], Some(fee_payer));
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        };
+        let response = self
+            .client
+            .simulate_transaction_with_config(&Transaction::new_unsigned(message), config)
+            .await
+            .map_err(|e| DasApiError::RpcError(e.to_string()))?;
+        Ok(SimulationResult {
+            slot: response.context.slot,
+            error: response.value.err.map(|e| e.to_string()),
+            logs: response.value.logs.unwrap_or_default(),
+        })
+    }
+}
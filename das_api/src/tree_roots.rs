@@ -0,0 +1,107 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anchor_lang::AnchorDeserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use spl_account_compression::state::{
+    merkle_tree_get_size, ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
+};
+
+use crate::DasApiError;
+
+// Roots change with every update of a busy tree, so they are only reused for a moment.
+const CACHE_TTL: Duration = Duration::from_secs(2);
+
+// Sequence number, active index and buffer size come before the change logs.
+const TREE_PREFIX_SIZE: usize = 24;
+
+#[derive(Debug, Clone)]
+pub struct TreeRoots {
+    /// Root after the latest update.
+    pub current: [u8; 32],
+    /// Roots still held in the tree's change log buffer, which the program accepts proofs for.
+    pub recent: Vec<[u8; 32]>,
+}
+
+/// Reads tree roots from chain, caching them briefly so validating the proofs of one tree's
+/// assets doesn't cost an RPC call each.
+pub struct TreeRootCache {
+    client: RpcClient,
+    cache: Mutex<HashMap<Pubkey, (Instant, TreeRoots)>>,
+}
+
+impl TreeRootCache {
+    pub fn new(rpc_url: String) -> Self {
+        TreeRootCache {
+            client: RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed()),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, tree: &Pubkey) -> Result<TreeRoots, DasApiError> {
+        if let Some((fetched_at, roots)) = self.cache.lock().unwrap().get(tree) {
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Ok(roots.clone());
+            }
+        }
+
+        let data = self
+            .client
+            .get_account_data(tree)
+            .await
+            .map_err(|e| DasApiError::RpcError(e.to_string()))?;
+        let roots = parse_tree_roots(&data)?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < CACHE_TTL);
+        cache.insert(*tree, (Instant::now(), roots.clone()));
+        Ok(roots)
+    }
+}
+
+fn parse_tree_roots(data: &[u8]) -> Result<TreeRoots, DasApiError> {
+    let invalid =
+        |msg: &str| DasApiError::RpcError(format!("Invalid merkle tree account: {}", msg));
+    if data.len() < CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1 {
+        return Err(invalid("account too small"));
+    }
+    let (mut header_bytes, tree_bytes) = data.split_at(CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1);
+    let header = ConcurrentMerkleTreeHeader::deserialize(&mut header_bytes)
+        .map_err(|e| invalid(&format!("{:?}", e)))?;
+    let tree_size = merkle_tree_get_size(&header).map_err(|e| invalid(&format!("{:?}", e)))?;
+    if tree_bytes.len() < tree_size {
+        return Err(invalid("account too small"));
+    }
+
+    let read_u64 = |offset: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&tree_bytes[offset..offset + 8]);
+        u64::from_le_bytes(bytes) as usize
+    };
+    let max_buffer_size = header.get_max_buffer_size() as usize;
+    let active_index = read_u64(8);
+    let buffer_size = read_u64(16).min(max_buffer_size);
+    if active_index >= max_buffer_size {
+        return Err(invalid("active index out of bounds"));
+    }
+
+    // Each change log is its root, the path of the leaf it updated and the leaf's index.
+    let change_log_size = 32 * (header.get_max_depth() as usize + 1) + 8;
+    let root_at = |i: usize| {
+        let offset = TREE_PREFIX_SIZE + i * change_log_size;
+        let mut root = [0u8; 32];
+        root.copy_from_slice(&tree_bytes[offset..offset + 32]);
+        root
+    };
+    let recent = (0..buffer_size.max(1))
+        .map(|age| root_at((active_index + max_buffer_size - age) % max_buffer_size))
+        .collect();
+    Ok(TreeRoots {
+        current: root_at(active_index),
+        recent,
+    })
+}
@@ -1,4 +1,5 @@
 use crate::DasApiError;
+use digital_asset_types::dao::is_valid_network;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
@@ -6,6 +7,25 @@ pub fn validate_pubkey(str_pubkey: String) -> Result<Pubkey, DasApiError> {
     Pubkey::from_str(&str_pubkey).map_err(|_| DasApiError::PubkeyValidationError(str_pubkey))
 }
 
+pub fn validate_hash(str_hash: &str) -> Result<Vec<u8>, DasApiError> {
+    bs58::decode(str_hash)
+        .into_vec()
+        .ok()
+        .filter(|hash| hash.len() == 32)
+        .ok_or_else(|| DasApiError::ValidationError(format!("Invalid hash {}", str_hash)))
+}
+
+pub fn validate_network(network: &str) -> Result<(), DasApiError> {
+    if is_valid_network(network) {
+        Ok(())
+    } else {
+        Err(DasApiError::ValidationError(format!(
+            "Invalid network {}",
+            network
+        )))
+    }
+}
+
 pub fn validate_opt_pubkey(pubkey: &Option<String>) -> Result<Option<Vec<u8>>, DasApiError> {
     let opt_bytes = if let Some(pubkey) = pubkey {
         let pubkey = Pubkey::from_str(pubkey)
@@ -0,0 +1,98 @@
+use das_api::index_advisor::{index_candidates, index_columns, normalize};
+
+fn columns(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+fn candidates(sql: &str) -> Vec<(String, Vec<String>)> {
+    index_candidates(sql)
+        .into_iter()
+        .map(|candidate| (candidate.table, candidate.columns))
+        .collect()
+}
+
+#[test]
+fn in_lists_of_any_length_share_a_shape() {
+    let short = normalize("SELECT * FROM asset WHERE id IN ($1) AND owner = $2");
+    let long = normalize("SELECT *\n  FROM asset WHERE id IN ($1, $2, $3) AND owner = $4");
+    assert_eq!(short, "SELECT * FROM asset WHERE id IN ($) AND owner = $");
+    assert_eq!(short, long);
+}
+
+#[test]
+fn columns_are_ordered_equality_sort_then_range() {
+    let sql = r#"SELECT "asset"."id" FROM "asset" WHERE "asset"."slot_updated" > $1 AND "asset"."owner" = $2 ORDER BY "asset"."id" ASC LIMIT $3"#;
+    assert_eq!(
+        candidates(sql),
+        vec![(
+            "asset".to_string(),
+            columns(&["owner", "id", "slot_updated"])
+        )]
+    );
+    assert_eq!(
+        index_candidates(sql)[0].create_statement(),
+        "CREATE INDEX CONCURRENTLY ON asset (owner, id, slot_updated)"
+    );
+}
+
+#[test]
+fn aliases_resolve_and_join_conditions_are_not_filters() {
+    let sql = "SELECT a.id FROM asset a INNER JOIN asset_grouping AS ag ON ag.asset_id = a.id \
+               WHERE ag.group_key = $1 AND ag.group_value = $2 AND a.burnt = false \
+               AND a.delegate IS NULL ORDER BY a.id";
+    assert_eq!(
+        candidates(sql),
+        vec![
+            (
+                "asset_grouping".to_string(),
+                columns(&["group_key", "group_value"])
+            ),
+            ("asset".to_string(), columns(&["burnt", "delegate", "id"])),
+        ]
+    );
+}
+
+#[test]
+fn a_query_without_filters_has_no_candidates() {
+    assert!(candidates("SELECT id FROM asset ORDER BY asset.id LIMIT $1").is_empty());
+    // Columns of unknown tables or qualifiers are ignored.
+    assert!(candidates("SELECT 1 FROM asset WHERE other.owner = $1").is_empty());
+}
+
+#[test]
+fn existing_index_columns_are_parsed_from_their_definition() {
+    assert_eq!(
+        index_columns(
+            "CREATE INDEX asset_owner_idx ON public.asset USING btree (owner, id DESC) WHERE (burnt = false)"
+        ),
+        columns(&["owner", "id"])
+    );
+    assert_eq!(
+        index_columns(
+            "CREATE INDEX asset_data_name ON public.asset_data USING btree (lower((name)::text), id)"
+        ),
+        columns(&["lower((name)::text)", "id"])
+    );
+    assert!(index_columns("not an index").is_empty());
+}
+
+#[test]
+fn an_index_leading_with_the_equality_columns_covers_a_candidate() {
+    let by_owner =
+        &index_candidates("SELECT 1 FROM asset WHERE asset.owner = $1 ORDER BY asset.id")[0];
+    assert!(by_owner.covered_by(&columns(&["owner", "id"])));
+    assert!(by_owner.covered_by(&columns(&["owner"])));
+    assert!(!by_owner.covered_by(&columns(&["id", "owner"])));
+
+    let by_group = &index_candidates(
+        "SELECT 1 FROM asset_grouping WHERE asset_grouping.group_key = $1 AND asset_grouping.group_value = $2",
+    )[0];
+    // The equality columns can lead in any order.
+    assert!(by_group.covered_by(&columns(&["group_value", "group_key", "slot_updated"])));
+    assert!(!by_group.covered_by(&columns(&["group_key"])));
+
+    // Without equality columns, the first sort or range column has to lead.
+    let by_slot = &index_candidates("SELECT 1 FROM asset WHERE asset.slot_updated > $1")[0];
+    assert!(by_slot.covered_by(&columns(&["slot_updated"])));
+    assert!(!by_slot.covered_by(&columns(&["owner", "slot_updated"])));
+}
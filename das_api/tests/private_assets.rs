@@ -0,0 +1,41 @@
+use das_api::private_assets::{with_api_key, PrivateAssets, PrivateAssetsConfig};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+fn group(api_key: &str, collections: &[String], trees: &[String]) -> PrivateAssetsConfig {
+    PrivateAssetsConfig {
+        api_key: api_key.to_string(),
+        collections: collections.to_vec(),
+        trees: trees.to_vec(),
+    }
+}
+
+#[tokio::test]
+async fn private_assets_are_only_served_to_their_keys() {
+    let shared = Keypair::new().pubkey().to_string();
+    let first_only = Keypair::new().pubkey().to_string();
+    let second_only = Keypair::new().pubkey();
+    let assets = PrivateAssets::from_config(vec![
+        group("first", &[shared.clone(), first_only.clone()], &[]),
+        group("second", &[shared.clone()], &[second_only.to_string()]),
+    ])
+    .unwrap();
+    let second_tree = second_only.to_bytes().to_vec();
+
+    // Without a key, everything private is hidden, each address once.
+    let hidden = with_api_key(None, async { assets.hidden() }).await;
+    assert_eq!(hidden.collections, vec![shared.clone(), first_only.clone()]);
+    assert_eq!(hidden.trees, vec![second_tree.clone()]);
+
+    // A collection granted to one of the keys is served to it even though another group hides it.
+    let hidden = with_api_key(Some("first".to_string()), async { assets.hidden() }).await;
+    assert!(hidden.collections.is_empty());
+    assert!(hidden.hides_tree(&second_tree));
+
+    let hidden = with_api_key(Some("second".to_string()), async { assets.hidden() }).await;
+    assert_eq!(hidden.collections, vec![first_only]);
+    assert!(hidden.trees.is_empty());
+
+    let hidden = with_api_key(Some("other".to_string()), async { assets.hidden() }).await;
+    assert_eq!(hidden.collections.len(), 2);
+    assert!(hidden.hides_collection(&shared));
+}
@@ -0,0 +1,76 @@
+use anchor_lang::{InstructionData, ToAccountMetas};
+use das_api::transfer_simulator::CnftTransfer;
+use solana_sdk::{hash::hash, pubkey::Pubkey, system_program};
+
+fn transfer(proof: Vec<Pubkey>) -> CnftTransfer {
+    CnftTransfer {
+        tree: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        delegate: Pubkey::new_unique(),
+        new_owner: Pubkey::new_unique(),
+        root: [1; 32],
+        data_hash: [2; 32],
+        creator_hash: [3; 32],
+        nonce: 0x0102_0304_0506,
+        proof,
+    }
+}
+
+#[test]
+fn transfer_data_matches_bubblegum() {
+    let transfer = transfer(vec![]);
+    let instruction = transfer.instruction();
+
+    assert_eq!(instruction.program_id, mpl_bubblegum::id());
+    assert_eq!(
+        instruction.data[..8],
+        hash(b"global:transfer").to_bytes()[..8]
+    );
+    // The discriminator, the root and both hashes, the nonce and the leaf index.
+    assert_eq!(instruction.data.len(), 8 + 32 * 3 + 8 + 4);
+    let expected = mpl_bubblegum::instruction::Transfer {
+        root: transfer.root,
+        data_hash: transfer.data_hash,
+        creator_hash: transfer.creator_hash,
+        nonce: transfer.nonce,
+        index: transfer.nonce as u32,
+    };
+    assert_eq!(instruction.data, expected.data());
+}
+
+#[test]
+fn transfer_accounts_match_bubblegum() {
+    let proof = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+    let transfer = transfer(proof.clone());
+    let instruction = transfer.instruction();
+
+    let tree_authority =
+        Pubkey::find_program_address(&[transfer.tree.as_ref()], &mpl_bubblegum::id()).0;
+    let expected = mpl_bubblegum::accounts::Transfer {
+        tree_authority,
+        leaf_owner: transfer.owner,
+        leaf_delegate: transfer.delegate,
+        new_leaf_owner: transfer.new_owner,
+        merkle_tree: transfer.tree,
+        log_wrapper: spl_noop::id(),
+        compression_program: spl_account_compression::id(),
+        system_program: system_program::id(),
+    }
+    .to_account_metas(None);
+    let (accounts, remaining) = instruction.accounts.split_at(expected.len());
+    for (account, expected) in accounts.iter().zip(&expected) {
+        assert_eq!(account.pubkey, expected.pubkey);
+        assert_eq!(account.is_writable, expected.is_writable);
+    }
+    // Bubblegum checks the owner's signature itself, so only the owner signs.
+    let signers: Vec<_> = accounts.iter().filter(|a| a.is_signer).collect();
+    assert_eq!(signers.len(), 1);
+    assert_eq!(signers[0].pubkey, transfer.owner);
+
+    // The proof follows, read only and in order.
+    assert_eq!(
+        remaining.iter().map(|a| a.pubkey).collect::<Vec<_>>(),
+        proof
+    );
+    assert!(remaining.iter().all(|a| !a.is_writable && !a.is_signer));
+}
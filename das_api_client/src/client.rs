@@ -0,0 +1,286 @@
+use {
+    crate::{error::ClientError, retry::RetryPolicy},
+    digital_asset_types::rpc::request::*,
+    digital_asset_types::rpc::{
+        response::{
+            AssetAtSlot, AssetList, AuthorityHistory, CnftTransferSimulation,
+            CollectionActivityStats, CollectionTraits, GetGroupingResponse, MetadataHistory,
+            NftEditions, OwnerDiff, TokenBalanceHistory, TransactionSignatureList, TreeAudit,
+        },
+        Asset, AssetProof,
+    },
+    log::warn,
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{json, Value},
+    std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    },
+    tokio::time::sleep,
+};
+
+/// Calls the methods of a DAS API endpoint. One client can be shared by many tasks, requests
+/// reuse its connections.
+pub struct DasClient {
+    http: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    retry: RetryPolicy,
+    id: AtomicU64,
+}
+
+impl DasClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        DasClient {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            api_key: None,
+            timeout: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+            id: AtomicU64::new(0),
+        }
+    }
+
+    /// Sent as `Authorization: Bearer <key>`, for the private assets the key is allowed to see.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Timeout of each attempt of a call, 30 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Calls `method` with named `params`, retrying the errors that may go away.
+    pub async fn call<P, R>(&self, method: &str, params: &P) -> Result<R, ClientError>
+    where
+        P: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)
+            .map_err(|e| ClientError::Serialization(method.to_string(), e))?;
+        let mut retry = 0;
+        loop {
+            match self.call_once(method, &params).await {
+                Ok(result) => {
+                    return serde_json::from_value(result)
+                        .map_err(|e| ClientError::Deserialization(method.to_string(), e))
+                }
+                Err(e) if e.is_retryable() && retry < self.retry.max_retries => {
+                    let backoff = self.retry.backoff(retry);
+                    warn!("{} failed, retrying in {:?}: {}", method, backoff, e);
+                    sleep(backoff).await;
+                    retry += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn call_once(&self, method: &str, params: &Value) -> Result<Value, ClientError> {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let mut request = self
+            .http
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params,
+            }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(ClientError::Status(status.as_u16()));
+        }
+        let mut response: Value = response
+            .json()
+            .await
+            .map_err(|_| ClientError::InvalidResponse(method.to_string()))?;
+        if let Some(error) = response.get("error") {
+            let code = error
+                .get("code")
+                .and_then(Value::as_i64)
+                .ok_or_else(|| ClientError::InvalidResponse(method.to_string()))?;
+            return Err(ClientError::Rpc {
+                method: method.to_string(),
+                code,
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                data: error.get("data").cloned(),
+            });
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(ClientError::InvalidResponse(method.to_string())),
+        }
+    }
+
+    pub async fn get_asset(&self, request: &GetAsset) -> Result<Asset, ClientError> {
+        self.call("getAsset", request).await
+    }
+
+    pub async fn get_asset_by_leaf(&self, request: &GetAssetByLeaf) -> Result<Asset, ClientError> {
+        self.call("getAssetByLeaf", request).await
+    }
+
+    pub async fn get_asset_by_leaf_hash(
+        &self,
+        request: &GetAssetByLeafHash,
+    ) -> Result<Asset, ClientError> {
+        self.call("getAssetByLeafHash", request).await
+    }
+
+    pub async fn get_asset_proof(
+        &self,
+        request: &GetAssetProof,
+    ) -> Result<AssetProof, ClientError> {
+        self.call("getAssetProof", request).await
+    }
+
+    pub async fn get_assets_by_owner(
+        &self,
+        request: &GetAssetsByOwner,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByOwner", request).await
+    }
+
+    pub async fn get_assets_by_group(
+        &self,
+        request: &GetAssetsByGroup,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByGroup", request).await
+    }
+
+    pub async fn get_assets_by_creator(
+        &self,
+        request: &GetAssetsByCreator,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByCreator", request).await
+    }
+
+    pub async fn get_assets_by_authority(
+        &self,
+        request: &GetAssetsByAuthority,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByAuthority", request).await
+    }
+
+    pub async fn get_assets_by_leaf_delegate(
+        &self,
+        request: &GetAssetsByLeafDelegate,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByLeafDelegate", request).await
+    }
+
+    pub async fn get_assets_by_parent_asset(
+        &self,
+        request: &GetAssetsByParentAsset,
+    ) -> Result<AssetList, ClientError> {
+        self.call("getAssetsByParentAsset", request).await
+    }
+
+    pub async fn search_assets(&self, request: &SearchAssets) -> Result<AssetList, ClientError> {
+        self.call("searchAssets", request).await
+    }
+
+    pub async fn get_grouping(
+        &self,
+        request: &GetGrouping,
+    ) -> Result<GetGroupingResponse, ClientError> {
+        self.call("getGrouping", request).await
+    }
+
+    pub async fn get_signatures_for_asset(
+        &self,
+        request: &GetSignaturesForAsset,
+    ) -> Result<TransactionSignatureList, ClientError> {
+        self.call("getSignaturesForAsset", request).await
+    }
+
+    pub async fn get_signatures_for_asset_batch(
+        &self,
+        request: &GetSignaturesForAssetBatch,
+    ) -> Result<Vec<TransactionSignatureList>, ClientError> {
+        self.call("getSignaturesForAssetBatch", request).await
+    }
+
+    pub async fn get_token_balance_history(
+        &self,
+        request: &GetTokenBalanceHistory,
+    ) -> Result<TokenBalanceHistory, ClientError> {
+        self.call("getTokenBalanceHistory", request).await
+    }
+
+    pub async fn get_asset_authority_history(
+        &self,
+        request: &GetAssetAuthorityHistory,
+    ) -> Result<AuthorityHistory, ClientError> {
+        self.call("getAssetAuthorityHistory", request).await
+    }
+
+    pub async fn get_asset_metadata_history(
+        &self,
+        request: &GetAssetMetadataHistory,
+    ) -> Result<MetadataHistory, ClientError> {
+        self.call("getAssetMetadataHistory", request).await
+    }
+
+    pub async fn get_owner_diff(&self, request: &GetOwnerDiff) -> Result<OwnerDiff, ClientError> {
+        self.call("getOwnerDiff", request).await
+    }
+
+    pub async fn get_collection_traits(
+        &self,
+        request: &GetCollectionTraits,
+    ) -> Result<CollectionTraits, ClientError> {
+        self.call("getCollectionTraits", request).await
+    }
+
+    pub async fn get_collection_activity_stats(
+        &self,
+        request: &GetCollectionActivityStats,
+    ) -> Result<CollectionActivityStats, ClientError> {
+        self.call("getCollectionActivityStats", request).await
+    }
+
+    pub async fn get_asset_at_slot(
+        &self,
+        request: &GetAssetAtSlot,
+    ) -> Result<AssetAtSlot, ClientError> {
+        self.call("getAssetAtSlot", request).await
+    }
+
+    pub async fn simulate_cnft_transfer(
+        &self,
+        request: &SimulateCnftTransfer,
+    ) -> Result<CnftTransferSimulation, ClientError> {
+        self.call("simulateCnftTransfer", request).await
+    }
+
+    pub async fn get_nft_editions(
+        &self,
+        request: &GetNftEditions,
+    ) -> Result<NftEditions, ClientError> {
+        self.call("getNftEditions", request).await
+    }
+
+    pub async fn get_tree_audit(&self, request: &GetTreeAudit) -> Result<TreeAudit, ClientError> {
+        self.call("getTreeAudit", request).await
+    }
+}
@@ -0,0 +1,59 @@
+use serde_json::Value;
+
+// The stable codes of the API's errors clients can act on, as the server sends them.
+pub const RATE_LIMITED_CODE: i64 = -32001;
+pub const QUERY_TIMEOUT_CODE: i64 = -32002;
+pub const QUERY_TOO_COMPLEX_CODE: i64 = -32003;
+pub const ASSET_NOT_FOUND_CODE: i64 = -32004;
+pub const TREE_NOT_INDEXED_CODE: i64 = -32005;
+pub const PROOF_UNAVAILABLE_CODE: i64 = -32006;
+pub const PAGINATION_INVALID_CODE: i64 = -32007;
+pub const QUERY_TOO_EXPENSIVE_CODE: i64 = -32008;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("server answered with HTTP {0}")]
+    Status(u16),
+    #[error("{method} failed with error {code}: {message}")]
+    Rpc {
+        method: String,
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    #[error("{0} returned a response that isn't JSON-RPC")]
+    InvalidResponse(String),
+    #[error("{0} returned a result that doesn't match its type: {1}")]
+    Deserialization(String, serde_json::Error),
+    #[error("failed to serialize the params of {0}: {1}")]
+    Serialization(String, serde_json::Error),
+}
+
+impl ClientError {
+    /// The JSON-RPC error code, for errors the server returned.
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            ClientError::Rpc { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        self.code() == Some(ASSET_NOT_FOUND_CODE)
+    }
+
+    /// Whether the same call may succeed later: connection failures, overloaded servers, and
+    /// the rate limit and timeout errors of the API.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Http(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            ClientError::Status(status) => *status == 429 || *status >= 500,
+            ClientError::Rpc { code, .. } => {
+                *code == RATE_LIMITED_CODE || *code == QUERY_TIMEOUT_CODE
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Typed client for the DAS API. The requests are the `digital_asset_types::rpc::request` params
+//! the API parses, the responses the `digital_asset_types::rpc` types it serves, so Rust consumers
+//! don't build JSON-RPC calls by hand. Failed calls are retried with backoff when the server asks to retry later, and
+//! asset lists can be read page by page with their cursor.
+
+mod client;
+mod error;
+mod pagination;
+mod retry;
+
+pub use client::DasClient;
+pub use digital_asset_types::rpc::request::*;
+pub use error::{
+    ClientError, ASSET_NOT_FOUND_CODE, PAGINATION_INVALID_CODE, PROOF_UNAVAILABLE_CODE,
+    QUERY_TIMEOUT_CODE, QUERY_TOO_COMPLEX_CODE, QUERY_TOO_EXPENSIVE_CODE, RATE_LIMITED_CODE,
+    TREE_NOT_INDEXED_CODE,
+};
+pub use pagination::AssetListRequest;
+pub use retry::RetryPolicy;
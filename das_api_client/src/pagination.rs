@@ -0,0 +1,84 @@
+use {
+    crate::{client::DasClient, error::ClientError},
+    digital_asset_types::rpc::{request::*, response::AssetList, Asset},
+    futures::{stream, Stream, TryStreamExt},
+    serde::Serialize,
+};
+
+/// The requests of the methods returning an [`AssetList`], which can be paged by cursor.
+pub trait AssetListRequest: Serialize + Clone + Send + Sync {
+    const METHOD: &'static str;
+
+    fn cursor(&self) -> Option<&str>;
+
+    fn set_cursor(&mut self, cursor: String);
+}
+
+macro_rules! asset_list_request {
+    ($request:ty, $method:literal) => {
+        impl AssetListRequest for $request {
+            const METHOD: &'static str = $method;
+
+            fn cursor(&self) -> Option<&str> {
+                self.cursor.as_deref()
+            }
+
+            fn set_cursor(&mut self, cursor: String) {
+                self.cursor = Some(cursor);
+            }
+        }
+    };
+}
+
+asset_list_request!(GetAssetsByOwner, "getAssetsByOwner");
+asset_list_request!(GetAssetsByGroup, "getAssetsByGroup");
+asset_list_request!(GetAssetsByCreator, "getAssetsByCreator");
+asset_list_request!(GetAssetsByAuthority, "getAssetsByAuthority");
+asset_list_request!(GetAssetsByLeafDelegate, "getAssetsByLeafDelegate");
+asset_list_request!(GetAssetsByParentAsset, "getAssetsByParentAsset");
+asset_list_request!(SearchAssets, "searchAssets");
+
+impl DasClient {
+    /// The pages of `request`, each requested with the cursor of the one before, from the
+    /// request's own cursor or the first page. Unlike `page`, the pages don't shift when assets
+    /// are written in between. The server only pages lists sorted by id or creation by cursor,
+    /// and not together with `page`, `before` or `after`.
+    pub fn pages<'a, R>(
+        &'a self,
+        request: R,
+    ) -> impl Stream<Item = Result<AssetList, ClientError>> + 'a
+    where
+        R: AssetListRequest + 'a,
+    {
+        let mut first = request;
+        first.set_cursor(first.cursor().unwrap_or_default().to_string());
+        stream::try_unfold(Some(first), move |request| async move {
+            let mut request = match request {
+                Some(request) => request,
+                None => return Ok(None),
+            };
+            let list: AssetList = self.call(R::METHOD, &request).await?;
+            // The last page has no cursor, or no assets for lists ending on a full page.
+            let next = match &list.cursor {
+                Some(cursor) if !list.items.is_empty() => {
+                    request.set_cursor(cursor.clone());
+                    Some(request)
+                }
+                _ => None,
+            };
+            Ok(Some((list, next)))
+        })
+    }
+
+    /// Every asset of `request`, read page by page with [`DasClient::pages`].
+    pub async fn all_assets<R>(&self, request: R) -> Result<Vec<Asset>, ClientError>
+    where
+        R: AssetListRequest,
+    {
+        self.pages(request)
+            .map_ok(|list| stream::iter(list.items.into_iter().map(Ok)))
+            .try_flatten()
+            .try_collect()
+            .await
+    }
+}
@@ -0,0 +1,164 @@
+use das_api_client::{
+    ClientError, DasClient, GetAssetsByOwner, RetryPolicy, ASSET_NOT_FOUND_CODE,
+    QUERY_TIMEOUT_CODE, QUERY_TOO_EXPENSIVE_CODE, RATE_LIMITED_CODE,
+};
+use futures::TryStreamExt;
+use serde_json::{json, Value};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+fn rpc_error(code: i64) -> ClientError {
+    ClientError::Rpc {
+        method: "getAsset".to_string(),
+        code,
+        message: String::new(),
+        data: None,
+    }
+}
+
+fn asset(id: &str) -> Value {
+    json!({
+        "interface": "V1_NFT",
+        "id": id,
+        "ownership": {
+            "frozen": false,
+            "delegated": false,
+            "delegate": null,
+            "ownership_model": "single",
+            "owner": "owner",
+        },
+        "supply": null,
+        "mutable": true,
+        "burnt": false,
+    })
+}
+
+// Answers the calls it receives with `results` in order, and records their params.
+async fn serve(results: Vec<Value>) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let results = Arc::new(Mutex::new(results.into_iter()));
+    let recorded = received.clone();
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (received, results) = (recorded.clone(), results.clone());
+            tokio::spawn(async move {
+                let (reader, mut writer) = socket.into_split();
+                let mut reader = BufReader::new(reader);
+                loop {
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let line = line.trim_end().to_ascii_lowercase();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some(value) = line.strip_prefix("content-length:") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).await.unwrap();
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    received.lock().unwrap().push(request["params"].clone());
+                    let result = results.lock().unwrap().next().unwrap();
+                    let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": result})
+                        .to_string();
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+                        response.len()
+                    );
+                    writer.write_all(head.as_bytes()).await.unwrap();
+                    writer.write_all(response.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    });
+    (url, received)
+}
+
+fn by_owner(limit: u32) -> GetAssetsByOwner {
+    GetAssetsByOwner {
+        owner_address: "owner".to_string(),
+        limit: Some(limit),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn backoff_doubles_up_to_the_max() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        min_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+    };
+    assert_eq!(policy.backoff(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(800));
+    assert_eq!(policy.backoff(4), Duration::from_secs(1));
+    // Doesn't overflow however many retries.
+    assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    assert_eq!(RetryPolicy::none().max_retries, 0);
+}
+
+#[test]
+fn only_transient_errors_are_retryable() {
+    assert!(ClientError::Status(429).is_retryable());
+    assert!(ClientError::Status(503).is_retryable());
+    assert!(!ClientError::Status(404).is_retryable());
+    assert!(rpc_error(RATE_LIMITED_CODE).is_retryable());
+    assert!(rpc_error(QUERY_TIMEOUT_CODE).is_retryable());
+    // The same query costs as much the next time.
+    assert!(!rpc_error(QUERY_TOO_EXPENSIVE_CODE).is_retryable());
+    assert!(!rpc_error(ASSET_NOT_FOUND_CODE).is_retryable());
+    assert!(rpc_error(ASSET_NOT_FOUND_CODE).is_not_found());
+    assert!(!ClientError::InvalidResponse("getAsset".to_string()).is_retryable());
+}
+
+#[tokio::test]
+async fn pages_follow_the_cursor_until_the_last_page() {
+    let (url, received) = serve(vec![
+        json!({"total": 2, "limit": 2, "cursor": "b", "items": [asset("a"), asset("b")]}),
+        json!({"total": 1, "limit": 2, "items": [asset("c")]}),
+    ])
+    .await;
+    let client = DasClient::new(url).with_retry(RetryPolicy::none());
+
+    let assets = client.all_assets(by_owner(2)).await.unwrap();
+    let ids: Vec<_> = assets.iter().map(|asset| asset.id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c"]);
+    // The first page is requested with an empty cursor, the next with the cursor of the first.
+    let cursors: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|params| params["cursor"].clone())
+        .collect();
+    assert_eq!(cursors, vec![json!(""), json!("b")]);
+}
+
+#[tokio::test]
+async fn pages_end_on_an_empty_page() {
+    // A list ending on a full page still has a cursor, the page after it is empty.
+    let (url, received) = serve(vec![
+        json!({"total": 1, "limit": 1, "cursor": "a", "items": [asset("a")]}),
+        json!({"total": 0, "limit": 1, "cursor": "a", "items": []}),
+    ])
+    .await;
+    let client = DasClient::new(url).with_retry(RetryPolicy::none());
+
+    let pages: Vec<_> = client.pages(by_owner(1)).try_collect().await.unwrap();
+    assert_eq!(pages.len(), 2);
+    assert_eq!(received.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,572 @@
+//! Latency of the query shapes of the API over the tables the fixtures fill, against generated
+//! fixtures of increasing size, in a throwaway database per size. Needs `DAS_BENCH_PG_URL` to
+//! point at a Postgres server the user can create databases on; the sizes default to
+//! `DAS_BENCH_SIZES=1000,10000,100000` assets.
+//!
+//! Criterion keeps its usual reports, for comparisons against a saved `--baseline`. The p50 and
+//! p99 of every shape and size printed at the end are measured once criterion is done with the
+//! shape, so its warm-up calls don't count.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use das_fixture::{Fixture, FixtureConfig};
+use digital_asset_types::{
+    dao::{SearchAssetsQuery, COLLECTION_GROUP_KEY},
+    dapi::{
+        get_asset, get_asset_id_by_leaf, get_asset_id_by_leaf_hash, get_assets_by_authority,
+        get_assets_by_creator, get_assets_by_group, get_assets_by_leaf_delegate,
+        get_assets_by_owner, get_assets_by_owner_grouped, get_proof_for_asset, search_assets,
+    },
+    rpc::{
+        filter::{AssetSortBy, AssetSorting, Commitment},
+        transform::AssetTransform,
+    },
+};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, SqlxPostgresConnector, Statement};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use std::{
+    env,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
+
+const PAGE_LIMIT: u64 = 100;
+// Calls timed for the percentiles of each shape, after criterion warmed the database up.
+const PERCENTILE_SAMPLES: usize = 200;
+
+fn sizes() -> Vec<usize> {
+    env::var("DAS_BENCH_SIZES")
+        .unwrap_or_else(|_| "1000,10000,100000".to_string())
+        .split(',')
+        .map(|size| {
+            size.trim()
+                .parse()
+                .expect("DAS_BENCH_SIZES is a list of sizes")
+        })
+        .collect()
+}
+
+/// Half of the assets compressed into two trees, the other half uncompressed.
+fn fixture_config(size: usize) -> FixtureConfig {
+    let leaves_per_tree = (size / 4).max(1);
+    FixtureConfig {
+        seed: 0,
+        trees: 2,
+        tree_depth: (leaves_per_tree as f64).log2().ceil().max(5.0) as u32,
+        leaves_per_tree,
+        uncompressed_assets: size - 2 * leaves_per_tree,
+        collections: (size / 500).max(5),
+        owners: (size / 20).max(10),
+        ..Default::default()
+    }
+}
+
+/// A database of its own for one fixture, dropped when the benchmarks are done with it.
+struct BenchDatabase {
+    admin: DatabaseConnection,
+    name: String,
+    pool: PgPool,
+    conn: DatabaseConnection,
+}
+
+impl BenchDatabase {
+    async fn create(pg_url: &str, size: usize) -> BenchDatabase {
+        let options: PgConnectOptions = pg_url.parse().expect("invalid DAS_BENCH_PG_URL");
+        let admin = connect(options.clone()).await;
+        let admin = SqlxPostgresConnector::from_sqlx_postgres_pool(admin);
+        let name = format!("das_bench_{}_{}", size, std::process::id());
+        execute(&admin, &format!("DROP DATABASE IF EXISTS {}", name)).await;
+        execute(&admin, &format!("CREATE DATABASE {}", name)).await;
+        let pool = connect(options.database(&name)).await;
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
+        Migrator::up(&conn, None).await.expect("migrating failed");
+        BenchDatabase {
+            admin,
+            name,
+            pool,
+            conn,
+        }
+    }
+
+    async fn remove(self) {
+        // Open connections would keep the database from being dropped.
+        self.pool.close().await;
+        execute(
+            &self.admin,
+            &format!("DROP DATABASE IF EXISTS {}", self.name),
+        )
+        .await;
+    }
+}
+
+async fn connect(options: PgConnectOptions) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(4)
+        .connect_with(options)
+        .await
+        .expect("connecting to Postgres failed")
+}
+
+async fn execute(conn: &DatabaseConnection, sql: &str) {
+    conn.execute(Statement::from_string(DbBackend::Postgres, sql.to_string()))
+        .await
+        .unwrap_or_else(|e| panic!("{} failed: {}", sql, e));
+}
+
+/// Latencies of one query shape at one size.
+struct Latencies {
+    shape: &'static str,
+    size: usize,
+    samples: Vec<Duration>,
+}
+
+impl Latencies {
+    fn percentile(&self, p: f64) -> Duration {
+        let mut samples = self.samples.clone();
+        samples.sort();
+        let rank = ((samples.len() as f64 * p).ceil() as usize).clamp(1, samples.len());
+        samples[rank - 1]
+    }
+}
+
+/// Benchmarks `query` as `shape`, then times `PERCENTILE_SAMPLES` more calls for its percentiles.
+fn bench_query<F, Fut>(
+    c: &mut Criterion,
+    rt: &Runtime,
+    latencies: &Mutex<Vec<Latencies>>,
+    shape: &'static str,
+    size: usize,
+    query: F,
+) where
+    F: Fn() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut group = c.benchmark_group(shape);
+    group.bench_function(BenchmarkId::from_parameter(size), |b| {
+        b.to_async(rt).iter(|| query())
+    });
+    group.finish();
+    // Criterion doesn't tell its warm-up calls from the measured ones, so the percentiles are
+    // timed separately.
+    let samples = rt.block_on(async {
+        let mut samples = Vec::with_capacity(PERCENTILE_SAMPLES);
+        for _ in 0..PERCENTILE_SAMPLES {
+            let start = Instant::now();
+            query().await;
+            samples.push(start.elapsed());
+        }
+        samples
+    });
+    latencies.lock().unwrap().push(Latencies {
+        shape,
+        size,
+        samples,
+    });
+}
+
+fn dapi_queries(c: &mut Criterion) {
+    let pg_url = env::var("DAS_BENCH_PG_URL").expect("DAS_BENCH_PG_URL is required");
+    let rt = Runtime::new().unwrap();
+    let latencies = Mutex::new(Vec::new());
+    let transform = AssetTransform::default();
+
+    for size in sizes() {
+        let fixture = Fixture::generate(&fixture_config(size));
+        let db = rt.block_on(async {
+            let db = BenchDatabase::create(&pg_url, size).await;
+            fixture
+                .insert(&db.conn)
+                .await
+                .expect("inserting fixture failed");
+            execute(&db.conn, "ANALYZE").await;
+            db
+        });
+        let (conn, transform) = (&db.conn, &transform);
+
+        // The largest owner, collection, creator and authority, the slowest pages to serve.
+        let (whale, _) = fixture.owners_by_holdings()[0];
+        let collection = fixture
+            .collections
+            .iter()
+            .max_by_key(|c| fixture.in_collection(&c.mint).len())
+            .expect("fixture has collections");
+        let asset = fixture.owned_by(&whale)[0].asset.id.clone();
+        let compressed = &fixture
+            .assets
+            .iter()
+            .find(|a| a.asset.compressed)
+            .expect("fixture has compressed assets")
+            .asset;
+        let (tree, leaf_index) = (
+            compressed
+                .tree_id
+                .clone()
+                .expect("compressed assets have a tree"),
+            compressed.nonce.expect("compressed assets have a nonce") as u64,
+        );
+        let leaf_hash = compressed
+            .leaf
+            .clone()
+            .expect("compressed assets have a leaf");
+        let compressed = compressed.id.clone();
+        let (asset, compressed, tree, leaf_hash) = (&asset, &compressed, &tree, &leaf_hash);
+        // The cursor of the second page of the collection, the first when it has only one.
+        let group_cursor = &rt
+            .block_on(get_assets_by_group(
+                conn,
+                COLLECTION_GROUP_KEY.to_string(),
+                collection.mint.to_string(),
+                AssetSorting::default(),
+                PAGE_LIMIT,
+                None,
+                None,
+                None,
+                Some(String::new()),
+                transform,
+                false,
+            ))
+            .unwrap()
+            .cursor
+            .unwrap_or_default();
+
+        bench_query(c, &rt, &latencies, "getAsset", size, move || async move {
+            get_asset(conn, asset.clone(), transform, None)
+                .await
+                .unwrap();
+        });
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetByLeaf",
+            size,
+            move || async move {
+                let id = get_asset_id_by_leaf(conn, tree.clone(), leaf_index)
+                    .await
+                    .unwrap();
+                get_asset(conn, id, transform, None).await.unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetByLeafHash",
+            size,
+            move || async move {
+                let id = get_asset_id_by_leaf_hash(conn, leaf_hash.clone())
+                    .await
+                    .unwrap();
+                get_asset(conn, id, transform, None).await.unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetProof",
+            size,
+            move || async move {
+                get_proof_for_asset(conn, compressed.clone(), Commitment::Confirmed)
+                    .await
+                    .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByOwner",
+            size,
+            move || async move {
+                get_assets_by_owner(
+                    conn,
+                    whale.to_bytes().to_vec(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        for (shape, sort_by) in [
+            ("getAssetsByOwner/updated", AssetSortBy::Updated),
+            ("getAssetsByOwner/recentAction", AssetSortBy::RecentAction),
+        ] {
+            bench_query(c, &rt, &latencies, shape, size, move || {
+                let sort_by = sort_by.clone();
+                async move {
+                    let sorting = AssetSorting {
+                        sort_by,
+                        sort_direction: None,
+                    };
+                    get_assets_by_owner(
+                        conn,
+                        whale.to_bytes().to_vec(),
+                        sorting,
+                        PAGE_LIMIT,
+                        Some(1),
+                        None,
+                        None,
+                        None,
+                        transform,
+                        false,
+                    )
+                    .await
+                    .unwrap();
+                }
+            });
+        }
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByOwner/cursor",
+            size,
+            move || async move {
+                get_assets_by_owner(
+                    conn,
+                    whale.to_bytes().to_vec(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    None,
+                    None,
+                    None,
+                    Some(String::new()),
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByOwner/groupByCollection",
+            size,
+            move || async move {
+                get_assets_by_owner_grouped(
+                    conn,
+                    whale.to_bytes().to_vec(),
+                    PAGE_LIMIT,
+                    1,
+                    transform,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByGroup",
+            size,
+            move || async move {
+                get_assets_by_group(
+                    conn,
+                    COLLECTION_GROUP_KEY.to_string(),
+                    collection.mint.to_string(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByGroup/cursor",
+            size,
+            move || async move {
+                get_assets_by_group(
+                    conn,
+                    COLLECTION_GROUP_KEY.to_string(),
+                    collection.mint.to_string(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    None,
+                    None,
+                    None,
+                    Some(group_cursor.clone()),
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByCreator",
+            size,
+            move || async move {
+                get_assets_by_creator(
+                    conn,
+                    collection.creators[0].0.to_bytes().to_vec(),
+                    false,
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByAuthority",
+            size,
+            move || async move {
+                get_assets_by_authority(
+                    conn,
+                    collection.update_authority.to_bytes().to_vec(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "getAssetsByLeafDelegate",
+            size,
+            move || async move {
+                // The fixture's compressed assets are delegated to their owner.
+                get_assets_by_leaf_delegate(
+                    conn,
+                    whale.to_bytes().to_vec(),
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "searchAssets/collectionCreator",
+            size,
+            move || async move {
+                let query = SearchAssetsQuery {
+                    creator_address: Some(collection.creators[0].0.to_bytes().to_vec()),
+                    grouping: Some((
+                        COLLECTION_GROUP_KEY.to_string(),
+                        collection.mint.to_string(),
+                    )),
+                    ..Default::default()
+                };
+                search_assets(
+                    conn,
+                    query,
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+        bench_query(
+            c,
+            &rt,
+            &latencies,
+            "searchAssets",
+            size,
+            move || async move {
+                let query = SearchAssetsQuery {
+                    owner_address: Some(whale.to_bytes().to_vec()),
+                    compressed: Some(true),
+                    ..Default::default()
+                };
+                search_assets(
+                    conn,
+                    query,
+                    AssetSorting::default(),
+                    PAGE_LIMIT,
+                    Some(1),
+                    None,
+                    None,
+                    None,
+                    transform,
+                    false,
+                    false,
+                )
+                .await
+                .unwrap();
+            },
+        );
+
+        rt.block_on(db.remove());
+    }
+
+    println!(
+        "\n{:<36} {:>8} {:>12} {:>12}",
+        "query", "assets", "p50", "p99"
+    );
+    for l in latencies.into_inner().unwrap() {
+        println!(
+            "{:<36} {:>8} {:>12?} {:>12?}",
+            l.shape,
+            l.size,
+            l.percentile(0.5),
+            l.percentile(0.99)
+        );
+    }
+}
+
+criterion_group!(benches, dapi_queries);
+criterion_main!(benches);
@@ -0,0 +1,109 @@
+use crate::generate::Fixture;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping, cl_items, FullAsset,
+};
+use sea_orm::{
+    ActiveValue::NotSet, ConnectionTrait, DatabaseBackend, DbErr, EntityTrait, MockDatabase,
+    Statement,
+};
+
+// Rows per insert, well below the bind parameter limit of Postgres for the widest table.
+const BATCH_SIZE: usize = 1000;
+
+impl Fixture {
+    /// Writes the fixture to a migrated database. The serial ids are left to the database, so
+    /// the ingester can keep writing to it afterwards.
+    pub async fn insert(&self, conn: &impl ConnectionTrait) -> Result<(), DbErr> {
+        for tree in &self.trees {
+            for table in ["cl_items", "cl_audits"] {
+                conn.execute(Statement::from_sql_and_values(
+                    DatabaseBackend::Postgres,
+                    "SELECT create_tree_partition($1, $2)",
+                    vec![table.into(), tree.to_bytes().to_vec().into()],
+                ))
+                .await?;
+            }
+        }
+        insert_all::<asset_data::Entity, _>(
+            conn,
+            self.assets.iter().map(|a| a.data.clone().into()),
+        )
+        .await?;
+        insert_all::<asset::Entity, _>(conn, self.assets.iter().map(|a| a.asset.clone().into()))
+            .await?;
+        insert_all::<asset_authority::Entity, _>(
+            conn,
+            self.assets.iter().flat_map(|a| &a.authorities).map(|a| {
+                let mut row: asset_authority::ActiveModel = a.clone().into();
+                row.id = NotSet;
+                row
+            }),
+        )
+        .await?;
+        insert_all::<asset_creators::Entity, _>(
+            conn,
+            self.assets.iter().flat_map(|a| &a.creators).map(|c| {
+                let mut row: asset_creators::ActiveModel = c.clone().into();
+                row.id = NotSet;
+                row
+            }),
+        )
+        .await?;
+        insert_all::<asset_grouping::Entity, _>(
+            conn,
+            self.assets.iter().flat_map(|a| &a.groups).map(|g| {
+                let mut row: asset_grouping::ActiveModel = g.clone().into();
+                row.id = NotSet;
+                row
+            }),
+        )
+        .await?;
+        insert_all::<cl_items::Entity, _>(
+            conn,
+            self.cl_items.iter().map(|node| {
+                let mut row: cl_items::ActiveModel = node.clone().into();
+                row.id = NotSet;
+                row
+            }),
+        )
+        .await
+    }
+}
+
+async fn insert_all<E, I>(conn: &impl ConnectionTrait, rows: I) -> Result<(), DbErr>
+where
+    E: EntityTrait,
+    I: Iterator<Item = E::ActiveModel>,
+{
+    let rows: Vec<E::ActiveModel> = rows.collect();
+    for batch in rows.chunks(BATCH_SIZE) {
+        E::insert_many(batch.to_vec()).exec(conn).await?;
+    }
+    Ok(())
+}
+
+/// A mock database answering the queries an asset list reads `assets` with: the page of assets,
+/// then their data, authorities, creators and groupings.
+pub fn mock_asset_list(assets: &[&FullAsset]) -> MockDatabase {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![assets
+            .iter()
+            .map(|a| a.asset.clone())
+            .collect::<Vec<_>>()])
+        .append_query_results(vec![assets
+            .iter()
+            .map(|a| a.data.clone())
+            .collect::<Vec<_>>()])
+        .append_query_results(vec![assets
+            .iter()
+            .flat_map(|a| a.authorities.clone())
+            .collect::<Vec<_>>()])
+        .append_query_results(vec![assets
+            .iter()
+            .flat_map(|a| a.creators.clone())
+            .collect::<Vec<_>>()])
+        .append_query_results(vec![assets
+            .iter()
+            .flat_map(|a| a.groups.clone())
+            .collect::<Vec<_>>()])
+}
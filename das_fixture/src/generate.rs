@@ -0,0 +1,408 @@
+use crate::tree::{tree_nodes, Leaf};
+use blockbuster::token_metadata::state::TokenStandard;
+use chrono::{Duration, TimeZone, Utc};
+use digital_asset_types::{
+    dao::{
+        asset, asset_authority, asset_creators, asset_data, asset_grouping, cl_items,
+        sea_orm_active_enums::{
+            ChainMutability, Mutability, OwnerType, RoyaltyTargetType, SpecificationAssetClass,
+            SpecificationVersions,
+        },
+        FullAsset, COLLECTION_GROUP_KEY,
+    },
+    dapi::common::hash_creators,
+    json::ChainDataV1,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde_json::json;
+use solana_sdk::{keccak::hashv, pubkey::Pubkey};
+use std::collections::BTreeMap;
+
+const BUBBLEGUM_PROGRAM_ID: Pubkey =
+    solana_sdk::pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+
+/// What to generate. The same config always generates the same fixture.
+#[derive(Debug, Clone)]
+pub struct FixtureConfig {
+    pub seed: u64,
+    pub trees: usize,
+    pub tree_depth: u32,
+    /// Compressed assets minted into each tree, at most 2^`tree_depth`.
+    pub leaves_per_tree: usize,
+    pub uncompressed_assets: usize,
+    pub collections: usize,
+    /// Share of the assets in a verified collection, the rest are in none.
+    pub collection_ratio: f64,
+    /// Owners the assets are spread over, a few of them holding most of the assets like on
+    /// mainnet.
+    pub owners: usize,
+    pub max_creators: usize,
+    pub network: String,
+}
+
+impl Default for FixtureConfig {
+    fn default() -> Self {
+        FixtureConfig {
+            seed: 0,
+            trees: 2,
+            tree_depth: 10,
+            leaves_per_tree: 100,
+            uncompressed_assets: 100,
+            collections: 5,
+            collection_ratio: 0.8,
+            owners: 50,
+            max_creators: 3,
+            network: "mainnet".to_string(),
+        }
+    }
+}
+
+pub struct Collection {
+    pub mint: Pubkey,
+    pub update_authority: Pubkey,
+    pub creators: Vec<(Pubkey, i32)>,
+}
+
+/// The rows of the generated assets and trees, ready to be inserted or served from a mock
+/// database.
+pub struct Fixture {
+    pub assets: Vec<FullAsset>,
+    pub cl_items: Vec<cl_items::Model>,
+    pub trees: Vec<Pubkey>,
+    pub collections: Vec<Collection>,
+    pub owners: Vec<Pubkey>,
+}
+
+impl Fixture {
+    pub fn generate(config: &FixtureConfig) -> Fixture {
+        assert!(
+            config.leaves_per_tree <= 1 << config.tree_depth,
+            "a tree of depth {} holds at most {} leaves",
+            config.tree_depth,
+            1u64 << config.tree_depth
+        );
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let owners: Vec<Pubkey> = (0..config.owners.max(1))
+            .map(|_| Pubkey::new_from_array(rng.gen()))
+            .collect();
+        let collections: Vec<Collection> = (0..config.collections)
+            .map(|_| Collection {
+                mint: Pubkey::new_from_array(rng.gen()),
+                update_authority: Pubkey::new_from_array(rng.gen()),
+                creators: creators(&mut rng, config.max_creators),
+            })
+            .collect();
+        let mut generator = Generator {
+            config,
+            rng,
+            owners: &owners,
+            collections: &collections,
+            assets: Vec::new(),
+        };
+
+        let mut trees = Vec::new();
+        let mut nodes = Vec::new();
+        for _ in 0..config.trees {
+            let tree = Pubkey::new_from_array(generator.rng.gen());
+            let leaves: Vec<[u8; 32]> = (0..config.leaves_per_tree as u64)
+                .map(|nonce| generator.compressed(tree, nonce))
+                .collect();
+            nodes.extend(tree_nodes(&tree, config.tree_depth, &leaves));
+            trees.push(tree);
+        }
+        for _ in 0..config.uncompressed_assets {
+            generator.uncompressed();
+        }
+
+        let mut assets = generator.assets;
+        // Ids of the rows of serial tables, unique across the fixture.
+        for (i, asset) in assets.iter_mut().enumerate() {
+            let id = i as i64 * 8;
+            asset.authorities.iter_mut().for_each(|a| a.id = id);
+            for (j, creator) in asset.creators.iter_mut().enumerate() {
+                creator.id = id + j as i64;
+            }
+            asset.groups.iter_mut().for_each(|g| g.id = id);
+        }
+        for (i, node) in nodes.iter_mut().enumerate() {
+            node.id = i as i64 + 1;
+        }
+        Fixture {
+            assets,
+            cl_items: nodes,
+            trees,
+            collections,
+            owners,
+        }
+    }
+
+    /// The assets of `owner`, in the order they were generated.
+    pub fn owned_by(&self, owner: &Pubkey) -> Vec<&FullAsset> {
+        let owner = owner.to_bytes();
+        self.assets
+            .iter()
+            .filter(|a| a.asset.owner.as_deref() == Some(owner.as_slice()))
+            .collect()
+    }
+
+    /// The assets in the collection of `mint`.
+    pub fn in_collection(&self, mint: &Pubkey) -> Vec<&FullAsset> {
+        let mint = mint.to_string();
+        self.assets
+            .iter()
+            .filter(|a| {
+                a.groups
+                    .iter()
+                    .any(|g| g.group_value.as_deref() == Some(mint.as_str()))
+            })
+            .collect()
+    }
+
+    /// The owners by the number of assets they hold, the largest first.
+    pub fn owners_by_holdings(&self) -> Vec<(Pubkey, usize)> {
+        let mut holdings: BTreeMap<Vec<u8>, usize> = BTreeMap::new();
+        for asset in &self.assets {
+            if let Some(owner) = &asset.asset.owner {
+                *holdings.entry(owner.clone()).or_default() += 1;
+            }
+        }
+        let mut holdings: Vec<(Pubkey, usize)> = holdings
+            .into_iter()
+            .map(|(owner, count)| (Pubkey::new(&owner), count))
+            .collect();
+        holdings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        holdings
+    }
+}
+
+/// Creators with shares adding up to 100, the first one verified. Metadata has at most 5.
+fn creators(rng: &mut StdRng, max_creators: usize) -> Vec<(Pubkey, i32)> {
+    let count = rng.gen_range(1..=max_creators.clamp(1, 5));
+    let mut left = 100;
+    (0..count)
+        .map(|i| {
+            let share = if i + 1 == count {
+                left
+            } else {
+                rng.gen_range(0..=left)
+            };
+            left -= share;
+            (Pubkey::new_from_array(rng.gen()), share)
+        })
+        .collect()
+}
+
+struct Generator<'a> {
+    config: &'a FixtureConfig,
+    rng: StdRng,
+    owners: &'a [Pubkey],
+    collections: &'a [Collection],
+    assets: Vec<FullAsset>,
+}
+
+impl<'a> Generator<'a> {
+    // Cubing skews the pick towards the first owners, so a few of them hold most assets.
+    fn owner(&mut self) -> Pubkey {
+        let skewed = self.rng.gen::<f64>().powi(3);
+        self.owners[((self.owners.len() as f64 * skewed) as usize).min(self.owners.len() - 1)]
+    }
+
+    fn collection(&mut self) -> Option<&'a Collection> {
+        if self
+            .rng
+            .gen_bool(self.config.collection_ratio.clamp(0.0, 1.0))
+        {
+            self.collections.choose(&mut self.rng)
+        } else {
+            None
+        }
+    }
+
+    /// Mints the compressed asset at `nonce` of `tree`, returning its leaf.
+    fn compressed(&mut self, tree: Pubkey, nonce: u64) -> [u8; 32] {
+        let (id, _) = Pubkey::find_program_address(
+            &[b"asset", tree.as_ref(), &nonce.to_le_bytes()],
+            &BUBBLEGUM_PROGRAM_ID,
+        );
+        let owner = self.owner();
+        let mut asset = self.asset(id, owner, SpecificationAssetClass::Nft);
+        let data_hash = hashv(&[
+            asset.data.metadata_url.as_bytes(),
+            &asset.asset.royalty_amount.to_le_bytes(),
+        ])
+        .to_bytes();
+        let creator_hash = hash_creators(&asset.creators);
+        let leaf = Leaf {
+            id,
+            owner,
+            delegate: owner,
+            nonce,
+            data_hash,
+            creator_hash,
+        }
+        .hash();
+        let seq = nonce as i64 + 1;
+        let model = &mut asset.asset;
+        model.compressed = true;
+        model.tree_id = Some(tree.to_bytes().to_vec());
+        model.nonce = Some(nonce as i64);
+        model.leaf = Some(leaf.to_vec());
+        model.data_hash = Some(bs58::encode(data_hash).into_string());
+        model.creator_hash = Some(bs58::encode(creator_hash).into_string());
+        model.seq = Some(seq);
+        model.leaf_seq = Some(seq);
+        model.owner_delegate_seq = Some(seq);
+        model.leaf_delegate = Some(owner.to_bytes().to_vec());
+        self.assets.push(asset);
+        leaf
+    }
+
+    fn uncompressed(&mut self) {
+        let id = Pubkey::new_from_array(self.rng.gen());
+        let owner = self.owner();
+        let class = if self.rng.gen_bool(0.3) {
+            SpecificationAssetClass::ProgrammableNft
+        } else {
+            SpecificationAssetClass::Nft
+        };
+        let asset = self.asset(id, owner, class);
+        self.assets.push(asset);
+    }
+
+    fn asset(&mut self, id: Pubkey, owner: Pubkey, class: SpecificationAssetClass) -> FullAsset {
+        let number = self.assets.len();
+        let slot = 200_000_000 + number as i64;
+        let collection = self.collection();
+        let (name, symbol) = match collection {
+            Some(c) => (
+                format!("{} #{}", &c.mint.to_string()[..6], number),
+                "COLL".to_string(),
+            ),
+            None => (format!("Asset #{}", number), "ASSET".to_string()),
+        };
+        let uri = format!(
+            "https://arweave.net/{}",
+            bs58::encode(self.rng.gen::<[u8; 32]>()).into_string()
+        );
+        let royalty_amount = *[0, 250, 500, 1000].choose(&mut self.rng).unwrap();
+        let update_authority = collection
+            .map(|c| c.update_authority)
+            .unwrap_or_else(|| Pubkey::new_from_array(self.rng.gen()));
+        let creators = match collection {
+            Some(c) => c.creators.clone(),
+            None => creators(&mut self.rng, self.config.max_creators),
+        };
+        let token_standard = match class {
+            SpecificationAssetClass::ProgrammableNft => TokenStandard::ProgrammableNonFungible,
+            _ => TokenStandard::NonFungible,
+        };
+        let created_at =
+            Utc.timestamp_opt(1_672_531_200, 0).unwrap() + Duration::seconds(number as i64);
+
+        let id_bytes = id.to_bytes().to_vec();
+        FullAsset {
+            asset: asset::Model {
+                id: id_bytes.clone(),
+                alt_id: None,
+                specification_version: Some(SpecificationVersions::V1),
+                specification_asset_class: Some(class),
+                owner: Some(owner.to_bytes().to_vec()),
+                owner_type: OwnerType::Single,
+                delegate: None,
+                frozen: false,
+                supply: 1,
+                supply_mint: None,
+                compressed: false,
+                compressible: false,
+                seq: Some(0),
+                tree_id: None,
+                leaf: None,
+                nonce: None,
+                royalty_target_type: RoyaltyTargetType::Creators,
+                royalty_target: None,
+                royalty_amount,
+                asset_data: Some(id_bytes.clone()),
+                created_at: Some(created_at.into()),
+                burnt: false,
+                slot_updated: Some(slot),
+                data_hash: None,
+                creator_hash: None,
+                owner_delegate_seq: None,
+                was_decompressed: false,
+                leaf_seq: None,
+                leaf_delegate: None,
+                network: self.config.network.clone(),
+            },
+            data: asset_data::Model {
+                id: id_bytes.clone(),
+                chain_data_mutability: ChainMutability::Mutable,
+                chain_data: serde_json::to_value(ChainDataV1 {
+                    name: name.clone(),
+                    symbol: symbol.clone(),
+                    edition_nonce: None,
+                    primary_sale_happened: self.rng.gen_bool(0.5),
+                    token_standard: Some(token_standard),
+                    uses: None,
+                })
+                .unwrap(),
+                metadata_url: uri.clone(),
+                metadata_mutability: Mutability::Mutable,
+                metadata: json!({
+                    "name": name,
+                    "symbol": symbol,
+                    "description": format!("Generated asset {}", number),
+                    "image": format!("{}/image.png", uri),
+                    "attributes": [
+                        {"trait_type": "Background", "value": *["Red", "Green", "Blue"].choose(&mut self.rng).unwrap()},
+                        {"trait_type": "Level", "value": self.rng.gen_range(1..100)},
+                    ],
+                    "properties": {
+                        "files": [{"uri": format!("{}/image.png", uri), "type": "image/png"}],
+                        "category": "image",
+                    },
+                }),
+                slot_updated: slot,
+                reindex: Some(false),
+                raw_name: Some(name.into_bytes()),
+                raw_symbol: Some(symbol.into_bytes()),
+                files: None,
+                metadata_blob: None,
+            },
+            authorities: vec![asset_authority::Model {
+                id: 0,
+                asset_id: id_bytes.clone(),
+                scopes: None,
+                authority: update_authority.to_bytes().to_vec(),
+                seq: 0,
+                slot_updated: slot,
+            }],
+            creators: creators
+                .iter()
+                .enumerate()
+                .map(|(position, (creator, share))| asset_creators::Model {
+                    id: 0,
+                    asset_id: id_bytes.clone(),
+                    creator: creator.to_bytes().to_vec(),
+                    share: *share,
+                    verified: position == 0,
+                    seq: Some(0),
+                    slot_updated: Some(slot),
+                    position: position as i16,
+                })
+                .collect(),
+            groups: collection
+                .map(|c| asset_grouping::Model {
+                    id: 0,
+                    asset_id: id_bytes.clone(),
+                    group_key: COLLECTION_GROUP_KEY.to_string(),
+                    group_value: Some(c.mint.to_string()),
+                    seq: Some(0),
+                    slot_updated: Some(slot),
+                    verified: Some(true),
+                    group_info_seq: Some(0),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
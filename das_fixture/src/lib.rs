@@ -0,0 +1,12 @@
+//! Generates realistic database fixtures for tests of the API's queries: trees with their leaves
+//! and nodes, collections, and owners holding a mix of compressed and uncompressed assets. The
+//! same config always generates the same rows, which can be inserted into a migrated database for
+//! load tests or served from a mock database in unit tests.
+
+mod db;
+mod generate;
+mod tree;
+
+pub use db::mock_asset_list;
+pub use generate::{Collection, Fixture, FixtureConfig};
+pub use tree::{tree_nodes, Leaf};
@@ -0,0 +1,93 @@
+use {
+    anyhow::Context,
+    clap::Parser,
+    das_fixture::{Fixture, FixtureConfig},
+    log::info,
+    sea_orm::SqlxPostgresConnector,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+    std::env,
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Postgres connection string of a migrated database.
+    #[arg(long, short)]
+    pg_url: String,
+
+    /// Generates the same fixture for the same seed and sizes.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    #[arg(long, default_value_t = 4)]
+    trees: usize,
+
+    #[arg(long, default_value_t = 14)]
+    tree_depth: u32,
+
+    #[arg(long, default_value_t = 10_000)]
+    leaves_per_tree: usize,
+
+    #[arg(long, default_value_t = 10_000)]
+    uncompressed_assets: usize,
+
+    #[arg(long, default_value_t = 20)]
+    collections: usize,
+
+    #[arg(long, default_value_t = 1_000)]
+    owners: usize,
+
+    #[arg(long, default_value = "mainnet")]
+    network: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let options: PgConnectOptions = args.pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with(options)
+        .await?;
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+
+    let fixture = Fixture::generate(&FixtureConfig {
+        seed: args.seed,
+        trees: args.trees,
+        tree_depth: args.tree_depth,
+        leaves_per_tree: args.leaves_per_tree,
+        uncompressed_assets: args.uncompressed_assets,
+        collections: args.collections,
+        owners: args.owners,
+        network: args.network,
+        ..Default::default()
+    });
+    info!(
+        "Inserting {} assets and {} tree nodes",
+        fixture.assets.len(),
+        fixture.cl_items.len()
+    );
+    fixture.insert(&conn).await?;
+
+    // Addresses to point load tests at.
+    for (owner, count) in fixture.owners_by_holdings().iter().take(5) {
+        info!("Owner {} holds {} assets", owner, count);
+    }
+    for collection in fixture.collections.iter().take(5) {
+        info!(
+            "Collection {} has {} assets",
+            collection.mint,
+            fixture.in_collection(&collection.mint).len()
+        );
+    }
+    for tree in &fixture.trees {
+        info!("Tree {}", tree);
+    }
+    Ok(())
+}
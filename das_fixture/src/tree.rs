@@ -0,0 +1,62 @@
+use digital_asset_types::dao::cl_items;
+use solana_sdk::{keccak::hashv, pubkey::Pubkey};
+use spl_concurrent_merkle_tree::node::empty_node;
+
+/// The fields of a Bubblegum V1 leaf schema.
+pub struct Leaf {
+    pub id: Pubkey,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub nonce: u64,
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+}
+
+impl Leaf {
+    /// The node Bubblegum writes to the tree for the leaf.
+    pub fn hash(&self) -> [u8; 32] {
+        hashv(&[
+            &[1u8],
+            self.id.as_ref(),
+            self.owner.as_ref(),
+            self.delegate.as_ref(),
+            &self.nonce.to_le_bytes(),
+            &self.data_hash,
+            &self.creator_hash,
+        ])
+        .to_bytes()
+    }
+}
+
+/// The `cl_items` rows of a tree of `depth` whose leaves were appended in order, the leaf at
+/// index `i` by the change log of seq `i + 1`. Like the ingester, only the nodes some leaf was
+/// written under have a row, and a node carries the seq of the last leaf written under it.
+pub fn tree_nodes(tree: &Pubkey, depth: u32, leaves: &[[u8; 32]]) -> Vec<cl_items::Model> {
+    let mut nodes = Vec::new();
+    let mut level: Vec<[u8; 32]> = leaves.to_vec();
+    for height in 0..=depth {
+        // Node indexes count from the root at 1, so this level starts at 2^(depth - height).
+        let first = 1i64 << (depth - height);
+        for (i, hash) in level.iter().enumerate() {
+            let last_leaf = ((i as i64 + 1) << height).min(leaves.len() as i64);
+            nodes.push(cl_items::Model {
+                id: 0,
+                tree: tree.to_bytes().to_vec(),
+                node_idx: first + i as i64,
+                leaf_idx: (height == 0).then_some(i as i64),
+                seq: last_leaf,
+                level: height as i64,
+                hash: hash.to_vec(),
+                slot: None,
+            });
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| {
+                let right = pair.get(1).copied().unwrap_or_else(|| empty_node(height));
+                hashv(&[&pair[0], &right]).to_bytes()
+            })
+            .collect();
+    }
+    nodes
+}
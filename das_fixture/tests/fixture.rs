@@ -0,0 +1,141 @@
+use das_fixture::{mock_asset_list, Fixture, FixtureConfig};
+use digital_asset_types::{
+    dao::cl_items,
+    dapi::{common::hash_creators, get_assets_by_owner, proof_matches_root},
+    rpc::{filter::AssetSorting, transform::AssetTransform, AssetProof},
+};
+use sea_orm::DbErr;
+use spl_concurrent_merkle_tree::node::empty_node;
+use std::collections::HashMap;
+
+fn config() -> FixtureConfig {
+    FixtureConfig {
+        seed: 7,
+        trees: 2,
+        tree_depth: 5,
+        leaves_per_tree: 21,
+        uncompressed_assets: 30,
+        collections: 3,
+        owners: 10,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fixtures_are_reproducible_from_their_seed() {
+    let (a, b) = (Fixture::generate(&config()), Fixture::generate(&config()));
+    assert_eq!(a.assets.len(), 2 * 21 + 30);
+    assert_eq!(a.trees, b.trees);
+    assert_eq!(a.cl_items, b.cl_items);
+    for (a, b) in a.assets.iter().zip(b.assets.iter()) {
+        assert_eq!(a.asset, b.asset);
+        assert_eq!(a.creators, b.creators);
+    }
+
+    let other = Fixture::generate(&FixtureConfig {
+        seed: 8,
+        ..config()
+    });
+    assert_ne!(other.trees, Fixture::generate(&config()).trees);
+}
+
+#[test]
+fn compressed_leaves_prove_against_the_tree_root() {
+    let fixture = Fixture::generate(&config());
+    let depth = config().tree_depth;
+    for tree in &fixture.trees {
+        let nodes: HashMap<i64, &cl_items::Model> = fixture
+            .cl_items
+            .iter()
+            .filter(|node| node.tree == tree.to_bytes())
+            .map(|node| (node.node_idx, node))
+            .collect();
+        let root = nodes[&1];
+        assert_eq!(root.seq, 21);
+
+        for asset in fixture
+            .assets
+            .iter()
+            .filter(|a| a.asset.tree_id.as_deref() == Some(tree.as_ref()))
+        {
+            let leaf_idx = asset.asset.nonce.unwrap();
+            let node_idx = (1 << depth) + leaf_idx;
+            assert_eq!(nodes[&node_idx].hash, asset.asset.leaf.clone().unwrap());
+            let mut proof = Vec::new();
+            let mut idx = node_idx;
+            for level in 0..depth {
+                let sibling = nodes
+                    .get(&(idx ^ 1))
+                    .map(|node| node.hash.clone())
+                    .unwrap_or_else(|| empty_node(level).to_vec());
+                proof.push(bs58::encode(sibling).into_string());
+                idx >>= 1;
+            }
+            assert!(proof_matches_root(&AssetProof {
+                root: bs58::encode(&root.hash).into_string(),
+                proof,
+                node_index: node_idx,
+                leaf: bs58::encode(asset.asset.leaf.clone().unwrap()).into_string(),
+                tree_id: tree.to_string(),
+                valid: None,
+                on_chain_root: None,
+            }));
+            assert_eq!(
+                asset.asset.creator_hash,
+                Some(bs58::encode(hash_creators(&asset.creators)).into_string())
+            );
+        }
+    }
+}
+
+#[test]
+fn owners_hold_mixed_skewed_holdings() {
+    let fixture = Fixture::generate(&config());
+    let holdings = fixture.owners_by_holdings();
+    let (whale, count) = holdings[0];
+    assert!(count > fixture.assets.len() / 10);
+    let owned = fixture.owned_by(&whale);
+    assert!(owned.iter().any(|a| a.asset.compressed));
+    assert!(owned.iter().any(|a| !a.asset.compressed));
+    for asset in &fixture.assets {
+        assert_eq!(asset.creators.iter().map(|c| c.share).sum::<i32>(), 100);
+    }
+    let grouped: usize = fixture
+        .collections
+        .iter()
+        .map(|c| fixture.in_collection(&c.mint).len())
+        .sum();
+    assert!(grouped > 0 && grouped < fixture.assets.len());
+}
+
+#[tokio::test]
+async fn fixtures_serve_the_asset_list_queries() -> Result<(), DbErr> {
+    let fixture = Fixture::generate(&config());
+    let (whale, _) = fixture.owners_by_holdings()[0];
+    let owned = fixture.owned_by(&whale);
+    let db = mock_asset_list(&owned).into_connection();
+
+    let list = get_assets_by_owner(
+        &db,
+        whale.to_bytes().to_vec(),
+        AssetSorting::default(),
+        1000,
+        Some(1),
+        None,
+        None,
+        None,
+        &AssetTransform::default(),
+        false,
+    )
+    .await?;
+    assert_eq!(list.items.len(), owned.len());
+    for (item, asset) in list.items.iter().zip(owned.iter()) {
+        assert_eq!(item.id, bs58::encode(&asset.asset.id).into_string());
+        assert_eq!(item.ownership.owner, whale.to_string());
+        assert_eq!(
+            item.compression.as_ref().map(|c| c.compressed),
+            Some(asset.asset.compressed)
+        );
+    }
+    Ok(())
+}
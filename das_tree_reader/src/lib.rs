@@ -0,0 +1,19 @@
+//! Reads the history of concurrent merkle trees from RPC: the signatures of an address, the
+//! changelog events of a transaction, and the `(seq, leaf, signature)` updates of a whole tree.
+//! The accounts transactions load from lookup tables are resolved when their meta leaves them out.
+//! Shared by the backfiller and the tools so they read trees the same way.
+
+mod lookup_tables;
+mod parse;
+mod reader;
+mod signatures;
+mod throttle;
+
+pub use lookup_tables::{missing_lookups, LookupTableCache, LookupTableError};
+pub use parse::{node_idx_to_leaf_idx, parse_tx_sequence, LeafNode, ParseError, SeqUpdate};
+pub use reader::{fetch_transaction, read_tree, ReadTreeError, TreeTransaction, RPC_TXN_CONFIG};
+pub use signatures::{
+    find_signature_slots, find_signatures, find_signatures_in_window, rpc_tx_with_retries,
+    FindSignaturesError, SignatureWindow, WindowPosition,
+};
+pub use throttle::{is_rate_limited, RpcPermit, RpcThrottle};
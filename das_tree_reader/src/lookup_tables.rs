@@ -0,0 +1,144 @@
+use {
+    crate::throttle::RpcThrottle,
+    solana_address_lookup_table_program::state::AddressLookupTable,
+    solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient},
+    solana_sdk::{
+        commitment_config::CommitmentConfig, message::v0::MessageAddressTableLookup, pubkey::Pubkey,
+    },
+    solana_transaction_status::{
+        option_serializer::OptionSerializer, EncodedTransactionWithStatusMeta, UiLoadedAddresses,
+    },
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LookupTableError {
+    #[error("failed to fetch lookup table {0}: {1}")]
+    Fetch(Pubkey, ClientError),
+    #[error("lookup table {0} doesn't exist")]
+    NotFound(Pubkey),
+    #[error("failed to deserialize lookup table {0}")]
+    Deserialize(Pubkey),
+    #[error("lookup table {0} has no address at index {1}")]
+    Index(Pubkey, u8),
+}
+
+/// The addresses of the lookup tables transactions loaded accounts from, for transactions whose
+/// meta doesn't list the loaded addresses, as some encodings and older nodes leave them out.
+///
+/// Tables are append-only and their addresses never move, so a cached table only has to be
+/// fetched again when a transaction reads past its end.
+#[derive(Default)]
+pub struct LookupTableCache {
+    tables: Mutex<HashMap<Pubkey, Arc<Vec<Pubkey>>>>,
+}
+
+impl LookupTableCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fills in the loaded addresses of `tx` when [missing_lookups] finds its meta leaves them
+    /// out. Returns whether they were filled in.
+    pub async fn fill_loaded_addresses(
+        &self,
+        client: &RpcClient,
+        throttle: &RpcThrottle,
+        tx: &mut EncodedTransactionWithStatusMeta,
+    ) -> Result<bool, LookupTableError> {
+        let lookups = match missing_lookups(tx) {
+            Some(lookups) => lookups,
+            None => return Ok(false),
+        };
+        let (writable, readonly) = self.resolve(client, throttle, &lookups).await?;
+        if let Some(meta) = tx.meta.as_mut() {
+            meta.loaded_addresses = OptionSerializer::Some(UiLoadedAddresses {
+                writable: writable.iter().map(Pubkey::to_string).collect(),
+                readonly: readonly.iter().map(Pubkey::to_string).collect(),
+            });
+        }
+        Ok(true)
+    }
+
+    /// The writable and readonly addresses `lookups` load, in the order the runtime appends
+    /// them to the account keys: the writable ones of every table, then the readonly ones.
+    pub async fn resolve(
+        &self,
+        client: &RpcClient,
+        throttle: &RpcThrottle,
+        lookups: &[MessageAddressTableLookup],
+    ) -> Result<(Vec<Pubkey>, Vec<Pubkey>), LookupTableError> {
+        let mut writable = Vec::new();
+        let mut readonly = Vec::new();
+        for lookup in lookups {
+            let highest = lookup
+                .writable_indexes
+                .iter()
+                .chain(lookup.readonly_indexes.iter())
+                .max()
+                .copied();
+            let table = match self.cached(&lookup.account_key) {
+                Some(table) if highest.map_or(true, |i| (i as usize) < table.len()) => table,
+                _ => self.fetch(client, throttle, lookup.account_key).await?,
+            };
+            let address = |index: &u8| {
+                table
+                    .get(*index as usize)
+                    .copied()
+                    .ok_or(LookupTableError::Index(lookup.account_key, *index))
+            };
+            for index in &lookup.writable_indexes {
+                writable.push(address(index)?);
+            }
+            for index in &lookup.readonly_indexes {
+                readonly.push(address(index)?);
+            }
+        }
+        Ok((writable, readonly))
+    }
+
+    fn cached(&self, key: &Pubkey) -> Option<Arc<Vec<Pubkey>>> {
+        self.tables.lock().unwrap().get(key).cloned()
+    }
+
+    async fn fetch(
+        &self,
+        client: &RpcClient,
+        throttle: &RpcThrottle,
+        key: Pubkey,
+    ) -> Result<Arc<Vec<Pubkey>>, LookupTableError> {
+        let account = throttle
+            .run(|| client.get_account_with_commitment(&key, CommitmentConfig::confirmed()))
+            .await
+            .map_err(|error| LookupTableError::Fetch(key, error))?
+            .value
+            .ok_or(LookupTableError::NotFound(key))?;
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|_| LookupTableError::Deserialize(key))?;
+        let addresses = Arc::new(table.addresses.to_vec());
+        self.tables.lock().unwrap().insert(key, addresses.clone());
+        Ok(addresses)
+    }
+}
+
+/// The lookup tables `tx` loads accounts from when its meta lists fewer loaded addresses than
+/// they load, none when the meta lists them all or the transaction uses no lookup table.
+pub fn missing_lookups(
+    tx: &EncodedTransactionWithStatusMeta,
+) -> Option<Vec<MessageAddressTableLookup>> {
+    let meta = tx.meta.as_ref()?;
+    let decoded = tx.transaction.decode()?;
+    let lookups = decoded.message.address_table_lookups()?;
+    let expected: usize = lookups
+        .iter()
+        .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+        .sum();
+    let listed = match &meta.loaded_addresses {
+        OptionSerializer::Some(loaded) => loaded.writable.len() + loaded.readonly.len(),
+        _ => 0,
+    };
+    (expected > listed).then(|| lookups.to_vec())
+}
@@ -0,0 +1,113 @@
+use {
+    borsh::BorshDeserialize,
+    solana_sdk::{
+        pubkey::{ParsePubkeyError, Pubkey},
+        transaction::VersionedTransaction,
+    },
+    solana_transaction_status::{
+        option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+        UiInstruction, UiTransactionStatusMeta,
+    },
+    spl_account_compression::{AccountCompressionEvent, ChangeLogEvent},
+    std::{collections::HashMap, str::FromStr},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseError {
+    #[error("failed to load Transaction Meta")]
+    TransactionMeta,
+    #[error("failed to decode Transaction")]
+    Transaction,
+    #[error("failed to decode instruction data: {0}")]
+    Instruction(#[from] bs58::decode::Error),
+    #[error("failed to parse pubkey: {0}")]
+    Pubkey(#[from] ParsePubkeyError),
+}
+
+/// The leaf a changelog event wrote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafNode {
+    pub leaf: Vec<u8>,
+    pub index: i64,
+}
+
+/// A tree's sequence number as of a changelog event. Events without a path have no leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeqUpdate {
+    pub seq: u64,
+    pub leaf: Option<LeafNode>,
+}
+
+/// The changelog events a transaction logged through the noop program, per tree, in the order
+/// they were logged. Failed transactions and those without their meta have none.
+pub fn parse_tx_sequence(
+    tx: EncodedConfirmedTransactionWithStatusMeta,
+) -> Result<HashMap<Pubkey, Vec<SeqUpdate>>, ParseError> {
+    let mut seq_updates = HashMap::<Pubkey, Vec<SeqUpdate>>::new();
+
+    // ignore if tx failed or meta is missed
+    let meta = tx.transaction.meta.as_ref();
+    if meta.map(|meta| meta.status.is_err()).unwrap_or(true) {
+        return Ok(seq_updates);
+    }
+
+    // Get `UiTransaction` out of `EncodedTransactionWithStatusMeta`.
+    let meta: UiTransactionStatusMeta = tx.transaction.meta.ok_or(ParseError::TransactionMeta)?;
+
+    // See https://github.com/ngundotra/spl-ac-seq-parse/blob/main/src/main.rs
+    if let OptionSerializer::Some(inner_instructions_vec) = meta.inner_instructions.as_ref() {
+        let transaction: VersionedTransaction = tx
+            .transaction
+            .transaction
+            .decode()
+            .ok_or(ParseError::Transaction)?;
+
+        // Add the account lookup stuff
+        let mut account_keys = transaction.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded_addresses) = meta.loaded_addresses {
+            for pubkey in loaded_addresses.writable.iter() {
+                account_keys.push(Pubkey::from_str(pubkey)?);
+            }
+            for pubkey in loaded_addresses.readonly.iter() {
+                account_keys.push(Pubkey::from_str(pubkey)?);
+            }
+        }
+
+        for inner_ixs in inner_instructions_vec.iter() {
+            for inner_ix in inner_ixs.instructions.iter() {
+                if let UiInstruction::Compiled(instr) = inner_ix {
+                    if let Some(program) = account_keys.get(instr.program_id_index as usize) {
+                        if *program == spl_noop::id() {
+                            let data = bs58::decode(&instr.data)
+                                .into_vec()
+                                .map_err(ParseError::Instruction)?;
+
+                            if let Ok(AccountCompressionEvent::ChangeLog(cl_data)) =
+                                AccountCompressionEvent::try_from_slice(&data)
+                            {
+                                let ChangeLogEvent::V1(cl_data) = cl_data;
+                                let leaf = cl_data.path.get(0).map(|node| LeafNode {
+                                    leaf: node.node.to_vec(),
+                                    index: node_idx_to_leaf_idx(
+                                        node.index as i64,
+                                        cl_data.path.len() as u32 - 1,
+                                    ),
+                                });
+                                seq_updates.entry(cl_data.id).or_default().push(SeqUpdate {
+                                    seq: cl_data.seq,
+                                    leaf,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(seq_updates)
+}
+
+/// The leaf index of a node index of the bottom level of a tree of `tree_height`.
+pub fn node_idx_to_leaf_idx(index: i64, tree_height: u32) -> i64 {
+    index - 2i64.pow(tree_height)
+}
@@ -0,0 +1,130 @@
+use {
+    crate::{
+        lookup_tables::{LookupTableCache, LookupTableError},
+        parse::{parse_tx_sequence, ParseError, SeqUpdate},
+        signatures::{find_signatures, rpc_tx_with_retries, FindSignaturesError},
+        throttle::RpcThrottle,
+    },
+    futures::stream::{BoxStream, StreamExt},
+    solana_client::{
+        client_error::ClientError, nonblocking::rpc_client::RpcClient,
+        rpc_config::RpcTransactionConfig, rpc_request::RpcRequest,
+    },
+    solana_sdk::{
+        commitment_config::{CommitmentConfig, CommitmentLevel},
+        pubkey::Pubkey,
+        signature::Signature,
+    },
+    solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding},
+    std::{num::NonZeroUsize, sync::Arc},
+    tokio_stream::wrappers::ReceiverStream,
+};
+
+// Signatures found ahead of the transaction fetches.
+const SIGNATURE_BUFFER: usize = 2_000;
+
+pub const RPC_TXN_CONFIG: RpcTransactionConfig = RpcTransactionConfig {
+    encoding: Some(UiTransactionEncoding::Base64),
+    commitment: Some(CommitmentConfig {
+        commitment: CommitmentLevel::Finalized,
+    }),
+    max_supported_transaction_version: Some(0),
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReadTreeError {
+    #[error(transparent)]
+    Signatures(#[from] FindSignaturesError),
+    #[error("failed to fetch transaction: {0}")]
+    Fetch(#[from] ClientError),
+    #[error("failed to parse transaction {0}: {1}")]
+    Parse(Signature, ParseError),
+    #[error("failed to resolve lookup tables of transaction {0}: {1}")]
+    LookupTable(Signature, LookupTableError),
+}
+
+/// A transaction of a tree and the tree's updates it made, none if it failed.
+#[derive(Debug, Clone)]
+pub struct TreeTransaction {
+    /// The order its signature was found in, newest first.
+    pub id: usize,
+    pub signature: Signature,
+    pub updates: Vec<SeqUpdate>,
+}
+
+/// Fetches a finalized transaction with the encoding [parse_tx_sequence] reads.
+pub async fn fetch_transaction(
+    client: &RpcClient,
+    throttle: &RpcThrottle,
+    signature: Signature,
+    max_retries: u8,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta, ClientError> {
+    rpc_tx_with_retries(
+        client,
+        throttle,
+        RpcRequest::GetTransaction,
+        serde_json::json!([signature.to_string(), RPC_TXN_CONFIG]),
+        max_retries,
+        signature,
+    )
+    .await
+}
+
+/// Streams every transaction of `tree` with its updates, fetching up to `concurrency` at once.
+/// They arrive in the order their fetches finish; [TreeTransaction::id] restores the signature
+/// order. Lookup tables are read when a transaction's meta leaves out the addresses it loaded.
+/// The stream ends after the first error.
+pub fn read_tree(
+    tree: Pubkey,
+    client_url: &str,
+    throttle: &RpcThrottle,
+    concurrency: NonZeroUsize,
+    max_retries: u8,
+) -> BoxStream<'static, Result<TreeTransaction, ReadTreeError>> {
+    let signatures = find_signatures(
+        tree,
+        RpcClient::new(client_url.to_owned()),
+        throttle.clone(),
+        None,
+        None,
+        SIGNATURE_BUFFER,
+        false,
+    );
+    let client = Arc::new(RpcClient::new(client_url.to_owned()));
+    let throttle = throttle.clone();
+    let lookup_tables = Arc::new(LookupTableCache::new());
+
+    ReceiverStream::new(signatures)
+        .enumerate()
+        .map(move |(id, signature)| {
+            let client = Arc::clone(&client);
+            let throttle = throttle.clone();
+            let lookup_tables = Arc::clone(&lookup_tables);
+            async move {
+                let signature = signature?;
+                let mut tx = fetch_transaction(&client, &throttle, signature, max_retries).await?;
+                lookup_tables
+                    .fill_loaded_addresses(&client, &throttle, &mut tx.transaction)
+                    .await
+                    .map_err(|error| ReadTreeError::LookupTable(signature, error))?;
+                let updates = parse_tx_sequence(tx)
+                    .map_err(|error| ReadTreeError::Parse(signature, error))?
+                    .remove(&tree)
+                    .unwrap_or_default();
+                Ok(TreeTransaction {
+                    id,
+                    signature,
+                    updates,
+                })
+            }
+        })
+        .buffer_unordered(concurrency.get())
+        .scan(false, |failed, result| {
+            let item = (!*failed).then(|| {
+                *failed = result.is_err();
+                result
+            });
+            futures::future::ready(item)
+        })
+        .boxed()
+}
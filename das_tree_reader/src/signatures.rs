@@ -0,0 +1,277 @@
+use {
+    crate::throttle::RpcThrottle,
+    log::{debug, error, info},
+    serde::de::DeserializeOwned,
+    solana_client::{
+        client_error::{ClientError, Result as RpcClientResult},
+        nonblocking::rpc_client::RpcClient,
+        rpc_client::GetConfirmedSignaturesForAddress2Config,
+        rpc_request::RpcRequest,
+        rpc_response::RpcConfirmedTransactionStatusWithSignature,
+    },
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{ParseSignatureError, Signature},
+        slot_history::Slot,
+    },
+    std::{fmt, str::FromStr},
+    tokio::{
+        sync::mpsc,
+        time::{sleep, Duration},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum FindSignaturesError {
+    #[error("Failed to fetch signatures: {0}")]
+    Fetch(#[from] ClientError),
+    #[error("Failed to parse signature: {0}")]
+    Parse(#[from] ParseSignatureError),
+}
+
+/// Bounds on the transactions a scan sends, by slot and by block time in unix seconds. Like
+/// `before` and `after` they are exclusive. Transactions without a block time are only bounded by
+/// their slot.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignatureWindow {
+    pub before_slot: Option<Slot>,
+    pub after_slot: Option<Slot>,
+    pub before_time: Option<i64>,
+    pub after_time: Option<i64>,
+}
+
+/// Where a transaction falls relative to a [SignatureWindow].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowPosition {
+    Newer,
+    Inside,
+    Older,
+}
+
+impl SignatureWindow {
+    /// Where `status` falls, older when it is past both an after and a before bound.
+    pub fn position(&self, status: &RpcConfirmedTransactionStatusWithSignature) -> WindowPosition {
+        let time = status.block_time;
+        if self.after_slot.map_or(false, |slot| status.slot <= slot)
+            || matches!((self.after_time, time), (Some(after), Some(time)) if time <= after)
+        {
+            WindowPosition::Older
+        } else if self.before_slot.map_or(false, |slot| status.slot >= slot)
+            || matches!((self.before_time, time), (Some(before), Some(time)) if time >= before)
+        {
+            WindowPosition::Newer
+        } else {
+            WindowPosition::Inside
+        }
+    }
+}
+
+/// Sends the signatures of the transactions that reference `address`, newest first unless
+/// `replay_forward`, which holds them all back to send them oldest first. A fetch error is sent
+/// last and ends the scan.
+pub fn find_signatures(
+    address: Pubkey,
+    client: RpcClient,
+    throttle: RpcThrottle,
+    before: Option<Signature>,
+    after: Option<Signature>,
+    buffer: usize,
+    replay_forward: bool,
+) -> mpsc::Receiver<Result<Signature, FindSignaturesError>> {
+    scan_signatures(
+        address,
+        client,
+        throttle,
+        before,
+        after,
+        buffer,
+        SignatureWindow::default(),
+        replay_forward,
+        |_, signature| signature,
+    )
+}
+
+/// Like [find_signatures], only for the transactions inside `window`. Signatures are listed
+/// newest first, so the newer ones are still paged through and the scan ends at the first older
+/// one.
+#[allow(clippy::too_many_arguments)]
+pub fn find_signatures_in_window(
+    address: Pubkey,
+    client: RpcClient,
+    throttle: RpcThrottle,
+    before: Option<Signature>,
+    after: Option<Signature>,
+    window: SignatureWindow,
+    buffer: usize,
+    replay_forward: bool,
+) -> mpsc::Receiver<Result<Signature, FindSignaturesError>> {
+    scan_signatures(
+        address,
+        client,
+        throttle,
+        before,
+        after,
+        buffer,
+        window,
+        replay_forward,
+        |_, signature| signature,
+    )
+}
+
+/// Like [find_signatures], with the slot each transaction landed in.
+pub fn find_signature_slots(
+    address: Pubkey,
+    client: RpcClient,
+    throttle: RpcThrottle,
+    before: Option<Signature>,
+    after: Option<Signature>,
+    buffer: usize,
+    replay_forward: bool,
+) -> mpsc::Receiver<Result<(Signature, Slot), FindSignaturesError>> {
+    scan_signatures(
+        address,
+        client,
+        throttle,
+        before,
+        after,
+        buffer,
+        SignatureWindow::default(),
+        replay_forward,
+        |status, signature| (signature, status.slot),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn scan_signatures<T, F>(
+    address: Pubkey,
+    client: RpcClient,
+    throttle: RpcThrottle,
+    before: Option<Signature>,
+    after: Option<Signature>,
+    buffer: usize,
+    window: SignatureWindow,
+    replay_forward: bool,
+    item: F,
+) -> mpsc::Receiver<Result<T, FindSignaturesError>>
+where
+    T: Send + 'static,
+    F: Fn(&RpcConfirmedTransactionStatusWithSignature, Signature) -> T + Send + 'static,
+{
+    let (chan, rx) = mpsc::channel(buffer);
+    tokio::spawn(async move {
+        let mut last_signature = before;
+        let mut all_items: Vec<T> = Vec::new();
+
+        loop {
+            debug!(
+                "fetching signatures for {} before {:?}",
+                address, last_signature
+            );
+            let batch = match throttle
+                .run(|| {
+                    let config = GetConfirmedSignaturesForAddress2Config {
+                        before: last_signature,
+                        until: after,
+                        ..Default::default()
+                    };
+                    client.get_signatures_for_address_with_config(&address, config)
+                })
+                .await
+            {
+                Ok(vec) => {
+                    info!(
+                        "fetched {} signatures for address {:?} before {:?}",
+                        vec.len(),
+                        address,
+                        last_signature
+                    );
+                    vec
+                }
+                Err(error) => {
+                    chan.send(Err(error.into())).await.map_err(|_| ())?;
+                    break;
+                }
+            };
+
+            // Collect all the signatures in the batch
+            let statuses: Vec<(Signature, &RpcConfirmedTransactionStatusWithSignature)> = batch
+                .iter()
+                .filter_map(|status| Some((Signature::from_str(&status.signature).ok()?, status)))
+                .collect();
+
+            if statuses.is_empty() {
+                break;
+            }
+
+            last_signature = statuses.last().map(|(signature, _)| *signature);
+            let mut past_window = false;
+            let mut items = Vec::with_capacity(statuses.len());
+            for (signature, status) in statuses {
+                match window.position(status) {
+                    WindowPosition::Newer => continue,
+                    WindowPosition::Inside => items.push(item(status, signature)),
+                    WindowPosition::Older => {
+                        past_window = true;
+                        break;
+                    }
+                }
+            }
+            if replay_forward {
+                all_items.extend(items);
+            } else {
+                for item in items.into_iter() {
+                    chan.send(Ok(item)).await.map_err(|_| ())?;
+                }
+            }
+            if past_window {
+                break;
+            }
+        }
+        info!(
+            "sending {} signatures for address {:?}",
+            all_items.len(),
+            address
+        );
+
+        if replay_forward {
+            for item in all_items.into_iter().rev() {
+                chan.send(Ok(item)).await.map_err(|_| ())?;
+            }
+        }
+
+        Ok::<(), ()>(())
+    });
+
+    rx
+}
+
+pub async fn rpc_tx_with_retries<T, E>(
+    client: &RpcClient,
+    throttle: &RpcThrottle,
+    request: RpcRequest,
+    value: serde_json::Value,
+    max_retries: u8,
+    error_key: E,
+) -> RpcClientResult<T>
+where
+    T: DeserializeOwned,
+    E: fmt::Debug,
+{
+    let mut retries = 0;
+    let mut delay = Duration::from_millis(500);
+    loop {
+        match throttle.run(|| client.send(request, value.clone())).await {
+            Ok(value) => return Ok(value),
+            Err(error) => {
+                if retries < max_retries {
+                    error!("retrying {request} {error_key:?}: {error}");
+                    sleep(delay).await;
+                    delay *= 2;
+                    retries += 1;
+                } else {
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use {
+    log::{info, warn},
+    solana_client::{
+        client_error::{
+            reqwest::StatusCode, ClientError, ClientErrorKind, Result as RpcClientResult,
+        },
+        rpc_request::RpcError,
+    },
+    std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    },
+    tokio::{
+        sync::Notify,
+        task::JoinHandle,
+        time::{interval, sleep, Duration, Instant},
+    },
+};
+
+const MIN_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// Rate limited responses this soon after a decrease are from the same burst and don't lower the
+// limit again.
+const DECREASE_COOLDOWN: Duration = Duration::from_secs(1);
+// Retries of a rate limited request, a few minutes of backoff, before giving up on it.
+const DEFAULT_MAX_RETRIES: usize = 10;
+
+/// Whether the provider rejected the request for going over its rate limit, either with an
+/// HTTP 429 or a JSON-RPC error saying so.
+pub fn is_rate_limited(error: &ClientError) -> bool {
+    fn mentions_rate_limit(message: &str) -> bool {
+        let message = message.to_lowercase();
+        message.contains("429")
+            || message.contains("too many requests")
+            || message.contains("rate limit")
+    }
+
+    match error.kind() {
+        ClientErrorKind::Reqwest(error) => error.status() == Some(StatusCode::TOO_MANY_REQUESTS),
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, message, .. }) => {
+            *code == 429 || *code == -32429 || mentions_rate_limit(message)
+        }
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(message))
+        | ClientErrorKind::Custom(message) => mentions_rate_limit(message),
+        _ => false,
+    }
+}
+
+/// Adaptive cap on the requests in flight to an RPC provider, shared by all of a tool's
+/// workers. The cap grows by one for every round of successful requests and halves when the
+/// provider rate limits (AIMD), so long replays settle just under the provider's limit rather
+/// than failing on 429s.
+#[derive(Clone)]
+pub struct RpcThrottle {
+    inner: Arc<Inner>,
+    max_retries: usize,
+}
+
+struct Inner {
+    max: usize,
+    state: Mutex<State>,
+    released: Notify,
+    requests: AtomicU64,
+    rate_limited: AtomicU64,
+}
+
+struct State {
+    limit: f64,
+    in_flight: usize,
+    backoff: Duration,
+    last_decrease: Option<Instant>,
+}
+
+impl RpcThrottle {
+    /// Starts at `max_concurrency`, the most requests ever in flight.
+    pub fn new(max_concurrency: usize) -> Self {
+        let max = max_concurrency.max(1);
+        Self {
+            inner: Arc::new(Inner {
+                max,
+                state: Mutex::new(State {
+                    limit: max as f64,
+                    in_flight: 0,
+                    backoff: MIN_BACKOFF,
+                    last_decrease: None,
+                }),
+                released: Notify::new(),
+                requests: AtomicU64::new(0),
+                rate_limited: AtomicU64::new(0),
+            }),
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Gives up on a request once the provider rate limited `max_retries` retries of it, 10 by
+    /// default.
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Current cap on the requests in flight.
+    pub fn limit(&self) -> usize {
+        self.inner.state.lock().unwrap().limit as usize
+    }
+
+    /// Waits until another request fits under the cap.
+    pub async fn acquire(&self) -> RpcPermit {
+        loop {
+            // Created before checking so a release in between isn't missed.
+            let released = self.inner.released.notified();
+            {
+                let mut state = self.inner.state.lock().unwrap();
+                if state.in_flight < state.limit as usize {
+                    state.in_flight += 1;
+                    return RpcPermit {
+                        throttle: self.clone(),
+                    };
+                }
+            }
+            released.await;
+        }
+    }
+
+    /// Runs `request` under the cap, retrying it after a backoff while the provider rate limits
+    /// it, up to the throttle's `max_retries`. The last rate limited error, and any other error,
+    /// is returned for the caller to retry or not.
+    pub async fn run<T, F, Fut>(&self, mut request: F) -> RpcClientResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = RpcClientResult<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let permit = self.acquire().await;
+            match request().await {
+                Ok(value) => {
+                    permit.success();
+                    return Ok(value);
+                }
+                Err(error) if is_rate_limited(&error) => {
+                    let backoff = permit.rate_limited();
+                    if retries >= self.max_retries {
+                        warn!("rpc request still rate limited after {} retries", retries);
+                        return Err(error);
+                    }
+                    retries += 1;
+                    sleep(backoff).await;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Logs the effective requests per second and the current cap every `period`.
+    pub fn spawn_reporter(&self, period: Duration) -> JoinHandle<()> {
+        let throttle = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = interval(period);
+            ticker.tick().await; // ticks immediately
+            let mut last = (Instant::now(), 0);
+            loop {
+                ticker.tick().await;
+                let now = Instant::now();
+                let requests = throttle.inner.requests.load(Ordering::Relaxed);
+                let rps = (requests - last.1) as f64 / now.duration_since(last.0).as_secs_f64();
+                info!(
+                    "rpc: {:.1} req/s, concurrency {} of {}, {} rate limited responses",
+                    rps,
+                    throttle.limit(),
+                    throttle.inner.max,
+                    throttle.inner.rate_limited.load(Ordering::Relaxed)
+                );
+                last = (now, requests);
+            }
+        })
+    }
+}
+
+/// A request slot under the cap, freed on drop. Requests that neither succeed nor get rate
+/// limited leave the cap as it is.
+pub struct RpcPermit {
+    throttle: RpcThrottle,
+}
+
+impl RpcPermit {
+    pub fn success(self) {
+        let mut state = self.throttle.inner.state.lock().unwrap();
+        state.limit = (state.limit + 1.0 / state.limit).min(self.throttle.inner.max as f64);
+        state.backoff = MIN_BACKOFF;
+    }
+
+    /// Halves the cap, once per burst of rate limited responses, and returns how long to wait
+    /// before retrying.
+    pub fn rate_limited(self) -> Duration {
+        self.throttle
+            .inner
+            .rate_limited
+            .fetch_add(1, Ordering::Relaxed);
+        let mut state = self.throttle.inner.state.lock().unwrap();
+        let now = Instant::now();
+        if state
+            .last_decrease
+            .map_or(true, |last| now.duration_since(last) >= DECREASE_COOLDOWN)
+        {
+            state.limit = (state.limit / 2.0).max(1.0);
+            state.backoff = (state.backoff * 2).min(MAX_BACKOFF);
+            state.last_decrease = Some(now);
+            warn!(
+                "rpc rate limited, lowering concurrency to {}",
+                state.limit as usize
+            );
+        }
+        state.backoff
+    }
+}
+
+impl Drop for RpcPermit {
+    fn drop(&mut self) {
+        self.throttle.inner.state.lock().unwrap().in_flight -= 1;
+        self.throttle.inner.requests.fetch_add(1, Ordering::Relaxed);
+        self.throttle.inner.released.notify_waiters();
+    }
+}
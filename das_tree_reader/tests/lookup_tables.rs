@@ -0,0 +1,131 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use das_tree_reader::{missing_lookups, LookupTableCache, RpcThrottle};
+use serde_json::json;
+use solana_address_lookup_table_program::state::{AddressLookupTable, LookupTableMeta};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{
+    hash::Hash,
+    message::{
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        MessageHeader, VersionedMessage,
+    },
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    EncodedTransaction, EncodedTransactionWithStatusMeta, TransactionBinaryEncoding,
+    TransactionStatusMeta, UiTransactionStatusMeta,
+};
+
+fn lookup(table: Pubkey, writable: &[u8], readonly: &[u8]) -> MessageAddressTableLookup {
+    MessageAddressTableLookup {
+        account_key: table,
+        writable_indexes: writable.to_vec(),
+        readonly_indexes: readonly.to_vec(),
+    }
+}
+
+fn transaction(
+    lookups: Vec<MessageAddressTableLookup>,
+    loaded: LoadedAddresses,
+) -> EncodedTransactionWithStatusMeta {
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 0,
+        },
+        account_keys: vec![Pubkey::new_unique()],
+        recent_blockhash: Hash::default(),
+        instructions: vec![],
+        address_table_lookups: lookups,
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::V0(message),
+    };
+    let meta = TransactionStatusMeta {
+        loaded_addresses: loaded,
+        ..Default::default()
+    };
+    EncodedTransactionWithStatusMeta {
+        transaction: EncodedTransaction::Binary(
+            STANDARD.encode(bincode::serialize(&transaction).unwrap()),
+            TransactionBinaryEncoding::Base64,
+        ),
+        meta: Some(UiTransactionStatusMeta::from(meta)),
+        version: None,
+    }
+}
+
+#[test]
+fn lookups_are_missing_when_the_meta_lists_fewer_addresses() {
+    let table = Pubkey::new_unique();
+    let lookups = vec![lookup(table, &[0, 1], &[2])];
+
+    let tx = transaction(lookups.clone(), LoadedAddresses::default());
+    assert_eq!(missing_lookups(&tx), Some(lookups.clone()));
+
+    let partial = LoadedAddresses {
+        writable: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        readonly: vec![],
+    };
+    assert_eq!(
+        missing_lookups(&transaction(lookups.clone(), partial)),
+        Some(lookups.clone())
+    );
+
+    let complete = LoadedAddresses {
+        writable: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+        readonly: vec![Pubkey::new_unique()],
+    };
+    assert_eq!(missing_lookups(&transaction(lookups, complete)), None);
+
+    // Nothing is missing from a transaction without lookup tables.
+    assert_eq!(
+        missing_lookups(&transaction(vec![], LoadedAddresses::default())),
+        None
+    );
+}
+
+#[tokio::test]
+async fn resolve_lists_writable_addresses_before_readonly_ones() {
+    let table = Pubkey::new_unique();
+    let addresses: Vec<_> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let data = AddressLookupTable {
+        meta: LookupTableMeta::default(),
+        addresses: Cow::Owned(addresses.clone()),
+    }
+    .serialize_for_tests()
+    .unwrap();
+    // The mock answers the first fetch only, later ones must be served from the cache.
+    let mocks = HashMap::from([(
+        RpcRequest::GetAccountInfo,
+        json!({
+            "context": {"slot": 1},
+            "value": {
+                "lamports": 1,
+                "data": [STANDARD.encode(data), "base64"],
+                "owner": solana_address_lookup_table_program::id().to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+            },
+        }),
+    )]);
+    let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+    let throttle = RpcThrottle::new(1);
+    let cache = LookupTableCache::new();
+
+    let lookups = vec![lookup(table, &[2], &[0]), lookup(table, &[1], &[3])];
+    let (writable, readonly) = cache.resolve(&client, &throttle, &lookups).await.unwrap();
+    assert_eq!(writable, vec![addresses[2], addresses[1]]);
+    assert_eq!(readonly, vec![addresses[0], addresses[3]]);
+
+    let lookups = vec![lookup(table, &[3, 0], &[])];
+    let (writable, readonly) = cache.resolve(&client, &throttle, &lookups).await.unwrap();
+    assert_eq!(writable, vec![addresses[3], addresses[0]]);
+    assert!(readonly.is_empty());
+}
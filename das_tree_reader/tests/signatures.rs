@@ -0,0 +1,80 @@
+use das_tree_reader::{SignatureWindow, WindowPosition};
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+
+fn status(slot: u64, block_time: Option<i64>) -> RpcConfirmedTransactionStatusWithSignature {
+    RpcConfirmedTransactionStatusWithSignature {
+        signature: String::new(),
+        slot,
+        err: None,
+        memo: None,
+        block_time,
+        confirmation_status: None,
+    }
+}
+
+#[test]
+fn slot_bounds_are_exclusive() {
+    let window = SignatureWindow {
+        before_slot: Some(200),
+        after_slot: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(window.position(&status(200, None)), WindowPosition::Newer);
+    assert_eq!(window.position(&status(199, None)), WindowPosition::Inside);
+    assert_eq!(window.position(&status(101, None)), WindowPosition::Inside);
+    assert_eq!(window.position(&status(100, None)), WindowPosition::Older);
+}
+
+#[test]
+fn time_bounds_skip_transactions_without_a_block_time() {
+    let window = SignatureWindow {
+        before_time: Some(2_000),
+        after_time: Some(1_000),
+        ..Default::default()
+    };
+    assert_eq!(
+        window.position(&status(1, Some(2_000))),
+        WindowPosition::Newer
+    );
+    assert_eq!(
+        window.position(&status(1, Some(1_500))),
+        WindowPosition::Inside
+    );
+    assert_eq!(
+        window.position(&status(1, Some(1_000))),
+        WindowPosition::Older
+    );
+    assert_eq!(window.position(&status(1, None)), WindowPosition::Inside);
+}
+
+#[test]
+fn an_unbounded_window_holds_everything() {
+    let window = SignatureWindow::default();
+    assert_eq!(window.position(&status(0, None)), WindowPosition::Inside);
+    assert_eq!(
+        window.position(&status(u64::MAX, Some(i64::MAX))),
+        WindowPosition::Inside
+    );
+}
+
+#[test]
+fn older_wins_over_newer() {
+    // Bounds by slot and by time disagreeing, the scan ends rather than paging on.
+    let window = SignatureWindow {
+        before_slot: Some(100),
+        after_time: Some(1_000),
+        ..Default::default()
+    };
+    assert_eq!(
+        window.position(&status(150, Some(500))),
+        WindowPosition::Older
+    );
+    assert_eq!(
+        window.position(&status(150, Some(1_500))),
+        WindowPosition::Newer
+    );
+    assert_eq!(
+        window.position(&status(50, Some(1_500))),
+        WindowPosition::Inside
+    );
+}
@@ -0,0 +1,106 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use das_tree_reader::{is_rate_limited, RpcThrottle};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use tokio::time::{advance, Duration};
+
+fn too_many_requests() -> ClientError {
+    ClientErrorKind::Custom("429 Too Many Requests".to_string()).into()
+}
+
+#[tokio::test(start_paused = true)]
+async fn backoff_doubles_per_burst_up_to_a_cap_and_resets_on_success() {
+    let throttle = RpcThrottle::new(8);
+
+    assert_eq!(
+        throttle.acquire().await.rate_limited(),
+        Duration::from_millis(500)
+    );
+    assert_eq!(throttle.limit(), 4);
+    // The same burst neither lowers the cap nor grows the backoff again.
+    assert_eq!(
+        throttle.acquire().await.rate_limited(),
+        Duration::from_millis(500)
+    );
+    assert_eq!(throttle.limit(), 4);
+
+    let mut backoffs = Vec::new();
+    for _ in 0..8 {
+        advance(Duration::from_secs(1)).await;
+        backoffs.push(throttle.acquire().await.rate_limited().as_millis());
+    }
+    assert_eq!(
+        backoffs,
+        vec![1000, 2000, 4000, 8000, 16000, 30000, 30000, 30000]
+    );
+    assert_eq!(throttle.limit(), 1);
+
+    throttle.acquire().await.success();
+    advance(Duration::from_secs(1)).await;
+    assert_eq!(
+        throttle.acquire().await.rate_limited(),
+        Duration::from_millis(500)
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_gives_up_after_max_retries() {
+    let throttle = RpcThrottle::new(4).with_max_retries(3);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let result: Result<(), _> = throttle
+        .run(|| {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(too_many_requests())
+            }
+        })
+        .await;
+
+    assert!(is_rate_limited(&result.unwrap_err()));
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_retries_rate_limited_requests_until_they_succeed() {
+    let throttle = RpcThrottle::new(4);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let result = throttle
+        .run(|| {
+            let calls = calls.clone();
+            async move {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(too_many_requests()),
+                    _ => Ok("done"),
+                }
+            }
+        })
+        .await;
+
+    assert_eq!(result.unwrap(), "done");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn run_returns_other_errors_without_retrying() {
+    let throttle = RpcThrottle::new(4);
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let result: Result<(), _> = throttle
+        .run(|| {
+            let calls = calls.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(ClientErrorKind::Custom("node is behind".to_string()).into())
+            }
+        })
+        .await;
+
+    assert!(!is_rate_limited(&result.unwrap_err()));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,121 @@
+//! Cursors page through asset lists by keys an asset keeps for good once it is indexed: its id
+//! and `created_at`, the time it was first indexed. A page resumes after the last asset of the
+//! previous one rather than at an offset, so assets inserted, updated or burnt between two page
+//! fetches never make a page skip or repeat the assets that were already there.
+
+use crate::dao::asset;
+use sea_orm::{entity::prelude::DateTimeWithTimeZone, ColumnTrait, Condition, DbErr, Order};
+
+/// The immutable keys a cursor can page by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorKey {
+    Id,
+    /// `created_at`, then the id among assets indexed at the same time.
+    Created,
+}
+
+impl CursorKey {
+    /// The key for a list sorted by `sort_by`. Other sort columns, such as `slot_updated`, change
+    /// when the asset does and can't be paged through with a cursor.
+    pub fn for_sort(sort_by: Option<&asset::Column>) -> Result<CursorKey, DbErr> {
+        match sort_by {
+            None | Some(asset::Column::Id) => Ok(CursorKey::Id),
+            Some(asset::Column::CreatedAt) => Ok(CursorKey::Created),
+            Some(_) => Err(DbErr::Custom(
+                "cursors only page through assets sorted by id or creation".to_string(),
+            )),
+        }
+    }
+}
+
+/// The position of an asset in a list, handed out as an opaque string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetCursor {
+    pub id: Vec<u8>,
+    pub created_at: Option<DateTimeWithTimeZone>,
+}
+
+impl AssetCursor {
+    pub fn of(asset: &asset::Model) -> AssetCursor {
+        AssetCursor {
+            id: asset.id.clone(),
+            created_at: asset.created_at,
+        }
+    }
+
+    /// The cursor of a request, an empty one starting at the first page.
+    pub fn parse(cursor: &str) -> Result<Option<AssetCursor>, DbErr> {
+        if cursor.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || DbErr::Custom("Invalid Cursor".to_string());
+        let bytes = bs58::decode(cursor).into_vec().map_err(|_| invalid())?;
+        let (id_len, rest) = bytes.split_first().ok_or_else(invalid)?;
+        if rest.len() < *id_len as usize {
+            return Err(invalid());
+        }
+        let (id, created_at) = rest.split_at(*id_len as usize);
+        let created_at = match created_at {
+            [] => None,
+            created_at => {
+                let created_at = std::str::from_utf8(created_at).map_err(|_| invalid())?;
+                Some(DateTimeWithTimeZone::parse_from_rfc3339(created_at).map_err(|_| invalid())?)
+            }
+        };
+        Ok(Some(AssetCursor {
+            id: id.to_vec(),
+            created_at,
+        }))
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(1 + self.id.len() + 35);
+        bytes.push(self.id.len() as u8);
+        bytes.extend_from_slice(&self.id);
+        if let Some(created_at) = &self.created_at {
+            bytes.extend_from_slice(created_at.to_rfc3339().as_bytes());
+        }
+        bs58::encode(bytes).into_string()
+    }
+
+    /// Keeps the assets after this one in a list sorted by `key` in `direction`.
+    pub fn condition(&self, key: CursorKey, direction: &Order) -> Result<Condition, DbErr> {
+        let after = |column: asset::Column, value: sea_orm::Value| match direction {
+            Order::Desc => column.lt(value),
+            _ => column.gt(value),
+        };
+        let id_after = after(asset::Column::Id, self.id.clone().into());
+        match key {
+            CursorKey::Id => Ok(Condition::all().add(id_after)),
+            CursorKey::Created => {
+                let created_at = self.created_at.ok_or_else(|| {
+                    DbErr::Custom("the cursor's asset has no creation time".to_string())
+                })?;
+                Ok(Condition::any()
+                    .add(after(asset::Column::CreatedAt, created_at.into()))
+                    .add(
+                        Condition::all()
+                            .add(asset::Column::CreatedAt.eq(created_at))
+                            .add(id_after),
+                    ))
+            }
+        }
+    }
+
+    /// Whether `asset` comes after this one in a list sorted by `key` in `direction`, like
+    /// [AssetCursor::condition] decides in the database.
+    pub fn precedes(&self, asset: &asset::Model, key: CursorKey, direction: &Order) -> bool {
+        let ordering = match key {
+            CursorKey::Id => asset.id.cmp(&self.id),
+            CursorKey::Created => match (asset.created_at, self.created_at) {
+                (Some(a), Some(c)) => a.cmp(&c).then_with(|| asset.id.cmp(&self.id)),
+                // Assets without a creation time are left out like SQL leaves out NULLs.
+                _ => return false,
+            },
+        };
+        match direction {
+            Order::Desc => ordering.is_lt(),
+            _ => ordering.is_gt(),
+        }
+    }
+}
@@ -46,6 +46,8 @@ pub struct Model {
     pub owner_delegate_seq: Option<i64>,
     pub was_decompressed: bool,
     pub leaf_seq: Option<i64>,
+    pub leaf_delegate: Option<Vec<u8>>,
+    pub network: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -78,6 +80,8 @@ pub enum Column {
     OwnerDelegateSeq,
     WasDecompressed,
     LeafSeq,
+    LeafDelegate,
+    Network,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -133,6 +137,8 @@ impl ColumnTrait for Column {
             Self::OwnerDelegateSeq => ColumnType::BigInteger.def().null(),
             Self::WasDecompressed => ColumnType::Boolean.def(),
             Self::LeafSeq => ColumnType::BigInteger.def().null(),
+            Self::LeafDelegate => ColumnType::Binary.def().null(),
+            Self::Network => ColumnType::Text.def(),
         }
     }
 }
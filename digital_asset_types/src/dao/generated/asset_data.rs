@@ -26,6 +26,8 @@ pub struct Model {
     pub reindex: Option<bool>,
     pub raw_name: Option<Vec<u8>>,
     pub raw_symbol: Option<Vec<u8>>,
+    pub files: Option<Json>,
+    pub metadata_blob: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -40,6 +42,8 @@ pub enum Column {
     Reindex,
     RawName,
     RawSymbol,
+    Files,
+    MetadataBlob,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -73,6 +77,8 @@ impl ColumnTrait for Column {
             Self::Reindex => ColumnType::Boolean.def(),
             Self::RawName => ColumnType::Binary.def(),
             Self::RawSymbol => ColumnType::Binary.def(),
+            Self::Files => ColumnType::JsonBinary.def().null(),
+            Self::MetadataBlob => ColumnType::Binary.def().null(),
         }
     }
 }
@@ -21,6 +21,7 @@ pub struct Model {
     pub seq: i64,
     pub level: i64,
     pub hash: Vec<u8>,
+    pub slot: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -32,6 +33,7 @@ pub enum Column {
     Seq,
     Level,
     Hash,
+    Slot,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -60,6 +62,7 @@ impl ColumnTrait for Column {
             Self::Seq => ColumnType::BigInteger.def(),
             Self::Level => ColumnType::BigInteger.def(),
             Self::Hash => ColumnType::Binary.def(),
+            Self::Slot => ColumnType::BigInteger.def().null(),
         }
     }
 }
@@ -4,15 +4,40 @@ pub mod prelude;
 
 pub mod asset;
 pub mod asset_authority;
+pub mod asset_authority_history;
+pub mod asset_burns;
+pub mod asset_change_outbox;
 pub mod asset_creators;
 pub mod asset_data;
+pub mod asset_data_full_metadata;
+pub mod asset_data_history;
+pub mod asset_delegate_history;
+pub mod asset_editions;
 pub mod asset_grouping;
+pub mod asset_owner_history;
+pub mod asset_owner_pdas;
+pub mod asset_pricing;
 pub mod asset_v1_account_attachments;
+pub mod authority_accounts;
 pub mod backfill_items;
+pub mod backfill_queue;
 pub mod cl_audits;
 pub mod cl_items;
+pub mod collection_stats;
+pub mod collection_traits;
+pub mod escrow_owners;
+pub mod failed_slots;
+pub mod hot_owner_assets;
+pub mod hot_owners;
+pub mod imported_assets;
+pub mod merkle_tree;
+pub mod merkle_tree_canopy;
+pub mod metadata_blob;
+pub mod pending_creator_verifications;
 pub mod raw_txn;
 pub mod sea_orm_active_enums;
 pub mod tasks;
+pub mod token_account_balances;
 pub mod token_accounts;
+pub mod token_records;
 pub mod tokens;
@@ -2,14 +2,39 @@
 
 pub use super::asset::Entity as Asset;
 pub use super::asset_authority::Entity as AssetAuthority;
+pub use super::asset_authority_history::Entity as AssetAuthorityHistory;
+pub use super::asset_burns::Entity as AssetBurns;
+pub use super::asset_change_outbox::Entity as AssetChangeOutbox;
 pub use super::asset_creators::Entity as AssetCreators;
 pub use super::asset_data::Entity as AssetData;
+pub use super::asset_data_full_metadata::Entity as AssetDataFullMetadata;
+pub use super::asset_data_history::Entity as AssetDataHistory;
+pub use super::asset_delegate_history::Entity as AssetDelegateHistory;
+pub use super::asset_editions::Entity as AssetEditions;
 pub use super::asset_grouping::Entity as AssetGrouping;
+pub use super::asset_owner_history::Entity as AssetOwnerHistory;
+pub use super::asset_owner_pdas::Entity as AssetOwnerPdas;
+pub use super::asset_pricing::Entity as AssetPricing;
 pub use super::asset_v1_account_attachments::Entity as AssetV1AccountAttachments;
+pub use super::authority_accounts::Entity as AuthorityAccounts;
 pub use super::backfill_items::Entity as BackfillItems;
+pub use super::backfill_queue::Entity as BackfillQueue;
 pub use super::cl_audits::Entity as ClAudits;
 pub use super::cl_items::Entity as ClItems;
+pub use super::collection_stats::Entity as CollectionStats;
+pub use super::collection_traits::Entity as CollectionTraits;
+pub use super::escrow_owners::Entity as EscrowOwners;
+pub use super::failed_slots::Entity as FailedSlots;
+pub use super::hot_owner_assets::Entity as HotOwnerAssets;
+pub use super::hot_owners::Entity as HotOwners;
+pub use super::imported_assets::Entity as ImportedAssets;
+pub use super::merkle_tree::Entity as MerkleTree;
+pub use super::merkle_tree_canopy::Entity as MerkleTreeCanopy;
+pub use super::metadata_blob::Entity as MetadataBlob;
+pub use super::pending_creator_verifications::Entity as PendingCreatorVerifications;
 pub use super::raw_txn::Entity as RawTxn;
 pub use super::tasks::Entity as Tasks;
+pub use super::token_account_balances::Entity as TokenAccountBalances;
 pub use super::token_accounts::Entity as TokenAccounts;
+pub use super::token_records::Entity as TokenRecords;
 pub use super::tokens::Entity as Tokens;
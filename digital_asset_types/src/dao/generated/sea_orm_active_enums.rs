@@ -123,3 +123,29 @@ pub enum OwnerType {
     #[sea_orm(string_value = "unknown")]
     Unknown,
 }
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "authority_kind")]
+pub enum AuthorityKind {
+    #[sea_orm(string_value = "multisig")]
+    Multisig,
+    #[sea_orm(string_value = "pda")]
+    Pda,
+    #[sea_orm(string_value = "program")]
+    Program,
+    #[sea_orm(string_value = "wallet")]
+    Wallet,
+}
+#[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "token_record_state"
+)]
+pub enum TokenRecordState {
+    #[sea_orm(string_value = "listed")]
+    Listed,
+    #[sea_orm(string_value = "locked")]
+    Locked,
+    #[sea_orm(string_value = "unlocked")]
+    Unlocked,
+}
@@ -26,6 +26,7 @@ pub struct Model {
     pub attempts: i16,
     pub duration: Option<i32>,
     pub errors: Option<String>,
+    pub heartbeat_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
@@ -41,6 +42,7 @@ pub enum Column {
     Attempts,
     Duration,
     Errors,
+    HeartbeatAt,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DerivePrimaryKey)]
@@ -73,6 +75,7 @@ impl ColumnTrait for Column {
             Self::Attempts => ColumnType::SmallInteger.def(),
             Self::Duration => ColumnType::Integer.def().null(),
             Self::Errors => ColumnType::Text.def().null(),
+            Self::HeartbeatAt => ColumnType::DateTime.def().null(),
         }
     }
 }
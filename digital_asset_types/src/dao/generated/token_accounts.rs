@@ -16,12 +16,12 @@ impl EntityName for Entity {
 pub struct Model {
     pub pubkey: Vec<u8>,
     pub mint: Vec<u8>,
-    pub amount: i64,
+    pub amount: Decimal,
     pub owner: Vec<u8>,
     pub frozen: bool,
     pub close_authority: Option<Vec<u8>>,
     pub delegate: Option<Vec<u8>>,
-    pub delegated_amount: i64,
+    pub delegated_amount: Decimal,
     pub slot_updated: i64,
     pub token_program: Vec<u8>,
 }
@@ -61,12 +61,12 @@ impl ColumnTrait for Column {
         match self {
             Self::Pubkey => ColumnType::Binary.def(),
             Self::Mint => ColumnType::Binary.def(),
-            Self::Amount => ColumnType::BigInteger.def(),
+            Self::Amount => ColumnType::Decimal(Some((20, 0))).def(),
             Self::Owner => ColumnType::Binary.def(),
             Self::Frozen => ColumnType::Boolean.def(),
             Self::CloseAuthority => ColumnType::Binary.def().null(),
             Self::Delegate => ColumnType::Binary.def().null(),
-            Self::DelegatedAmount => ColumnType::BigInteger.def(),
+            Self::DelegatedAmount => ColumnType::Decimal(Some((20, 0))).def(),
             Self::SlotUpdated => ColumnType::BigInteger.def(),
             Self::TokenProgram => ColumnType::Binary.def(),
         }
@@ -15,7 +15,7 @@ impl EntityName for Entity {
 #[derive(Clone, Debug, PartialEq, DeriveModel, DeriveActiveModel, Serialize, Deserialize)]
 pub struct Model {
     pub mint: Vec<u8>,
-    pub supply: i64,
+    pub supply: Decimal,
     pub decimals: i32,
     pub token_program: Vec<u8>,
     pub mint_authority: Option<Vec<u8>>,
@@ -58,7 +58,7 @@ impl ColumnTrait for Column {
     fn def(&self) -> ColumnDef {
         match self {
             Self::Mint => ColumnType::Binary.def(),
-            Self::Supply => ColumnType::BigInteger.def(),
+            Self::Supply => ColumnType::Decimal(Some((20, 0))).def(),
             Self::Decimals => ColumnType::Integer.def(),
             Self::TokenProgram => ColumnType::Binary.def(),
             Self::MintAuthority => ColumnType::Binary.def().null(),
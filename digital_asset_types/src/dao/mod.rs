@@ -1,5 +1,9 @@
+pub mod cursor;
 mod full_asset;
 mod generated;
+pub mod numeric;
+pub mod raw_query;
+pub mod schema;
 pub mod scopes;
 pub use full_asset::*;
 #[allow(ambiguous_glob_reexports)]
@@ -11,14 +15,57 @@ use self::sea_orm_active_enums::{
 use sea_orm::{
     entity::*,
     sea_query::Expr,
-    sea_query::{ConditionType, IntoCondition},
-    Condition, DbErr, RelationDef,
+    sea_query::{ConditionType, IntoCondition, Query},
+    Condition, DbErr, FromQueryResult, RelationDef,
 };
 
+/// The `group_key` of a Metaplex certified collection, valued with the collection mint.
+pub const COLLECTION_GROUP_KEY: &str = "collection";
+/// The `group_key` of a print edition, valued with the mint of its master edition.
+pub const EDITION_PARENT_GROUP_KEY: &str = "edition_parent";
+/// The `group_key` of an MPL Core asset in a collection, valued with the collection account.
+pub const MPL_CORE_COLLECTION_GROUP_KEY: &str = "mpl_core_collection";
+/// The network of the assets indexed without one configured.
+pub const DEFAULT_NETWORK: &str = "mainnet";
+
+/// Network names are short lowercase identifiers, e.g. `devnet` or `my-svm-l2`.
+pub fn is_valid_network(network: &str) -> bool {
+    !network.is_empty()
+        && network.len() <= 32
+        && network
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
 pub struct GroupingSize {
     pub size: u64,
 }
 
+/// How many assets of an owner are in a collection, with the lowest asset id as a sample.
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct OwnerCollection {
+    /// `None` for the assets without a verified collection.
+    pub collection: Option<String>,
+    pub count: i64,
+    pub sample_id: Vec<u8>,
+}
+
+/// An asset by its id, or by its leaf, which is known before the asset row exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetLeafAddress {
+    Id(Vec<u8>),
+    Leaf { tree: Vec<u8>, leaf_idx: i64 },
+}
+
+/// A transaction that changed a leaf, from `cl_audits`.
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct LeafSignature {
+    pub tree: Vec<u8>,
+    pub leaf_idx: i64,
+    pub tx: String,
+    pub instruction: String,
+}
+
 pub enum Pagination {
     Keyset {
         before: Option<Vec<u8>>,
@@ -27,9 +74,14 @@ pub enum Pagination {
     Page {
         page: u64,
     },
+    /// Resumes after the cursor's asset, at the first page without one. Unlike pages, assets
+    /// written between two fetches never shift the pages, see [cursor].
+    Cursor {
+        cursor: Option<cursor::AssetCursor>,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SearchAssetsQuery {
     // Conditions
     pub negate: Option<bool>,
@@ -41,6 +93,9 @@ pub struct SearchAssetsQuery {
     pub owner_type: Option<OwnerType>,
     pub creator_address: Option<Vec<u8>>,
     pub creator_verified: Option<bool>,
+    /// Share range of the matched creator in percent, both ends inclusive.
+    pub creator_share_min: Option<u8>,
+    pub creator_share_max: Option<u8>,
     pub authority_address: Option<Vec<u8>>,
     pub grouping: Option<(String, String)>,
     pub delegate: Option<Vec<u8>>,
@@ -52,8 +107,17 @@ pub struct SearchAssetsQuery {
     pub royalty_target_type: Option<RoyaltyTargetType>,
     pub royalty_target: Option<Vec<u8>>,
     pub royalty_amount: Option<u32>,
+    /// Royalty basis points range, both ends inclusive.
+    pub royalty_amount_min: Option<u32>,
+    pub royalty_amount_max: Option<u32>,
+    /// The royalty target, or a creator with a share for assets of the creators royalty model.
+    pub royalty_recipient: Option<Vec<u8>>,
     pub burnt: Option<bool>,
     pub json_uri: Option<String>,
+    /// Only assets owned by (or not owned by) a registered marketplace escrow.
+    pub escrowed: Option<bool>,
+    /// Only assets owned by (or not owned by) another asset's PDA.
+    pub owned_by_asset: Option<bool>,
 }
 
 impl SearchAssetsQuery {
@@ -100,6 +164,15 @@ impl SearchAssetsQuery {
         if self.royalty_amount.is_some() {
             num_conditions += 1;
         }
+        if self.royalty_amount_min.is_some() {
+            num_conditions += 1;
+        }
+        if self.royalty_amount_max.is_some() {
+            num_conditions += 1;
+        }
+        if self.royalty_recipient.is_some() {
+            num_conditions += 1;
+        }
         if self.burnt.is_some() {
             num_conditions += 1;
         }
@@ -109,16 +182,76 @@ impl SearchAssetsQuery {
         if self.creator_address.is_some() {
             num_conditions += 1;
         }
+        if self.creator_share_min.is_some() {
+            num_conditions += 1;
+        }
+        if self.creator_share_max.is_some() {
+            num_conditions += 1;
+        }
         if self.grouping.is_some() {
             num_conditions += 1;
         }
         if self.json_uri.is_some() {
             num_conditions += 1;
         }
+        if self.escrowed.is_some() {
+            num_conditions += 1;
+        }
+        if self.owned_by_asset.is_some() {
+            num_conditions += 1;
+        }
 
         num_conditions
     }
 
+    /// The filters set, by their API names, without their values, e.g.
+    /// `all(ownerAddress, compressed)`. Queries of the same shape tend to cost the same.
+    pub fn shape(&self) -> String {
+        let filters = [
+            ("interface", self.specification_version.is_some()),
+            ("interface", self.specification_asset_class.is_some()),
+            ("ownerAddress", self.owner_address.is_some()),
+            ("ownerType", self.owner_type.is_some() || self.owned_by_asset.is_some()),
+            ("creatorAddress", self.creator_address.is_some()),
+            ("creatorVerified", self.creator_verified.is_some()),
+            ("creatorShareMin", self.creator_share_min.is_some()),
+            ("creatorShareMax", self.creator_share_max.is_some()),
+            ("authorityAddress", self.authority_address.is_some()),
+            ("grouping", self.grouping.is_some()),
+            ("delegate", self.delegate.is_some()),
+            ("frozen", self.frozen.is_some()),
+            ("supply", self.supply.is_some()),
+            ("supplyMint", self.supply_mint.is_some()),
+            ("compressed", self.compressed.is_some()),
+            ("compressible", self.compressible.is_some()),
+            ("royaltyTargetType", self.royalty_target_type.is_some()),
+            ("royaltyTarget", self.royalty_target.is_some()),
+            ("royaltyAmount", self.royalty_amount.is_some()),
+            ("royaltyAmountMin", self.royalty_amount_min.is_some()),
+            ("royaltyAmountMax", self.royalty_amount_max.is_some()),
+            ("royaltyRecipient", self.royalty_recipient.is_some()),
+            ("burnt", self.burnt.is_some()),
+            ("jsonUri", self.json_uri.is_some()),
+            ("escrowed", self.escrowed.is_some()),
+        ];
+        let mut names: Vec<&str> = filters
+            .iter()
+            .filter(|(_, set)| *set)
+            .map(|(name, _)| *name)
+            .collect();
+        names.dedup();
+        let condition_type = match self.condition_type {
+            Some(ConditionType::Any) => "any",
+            _ => "all",
+        };
+        let negate = if self.negate.unwrap_or(false) {
+            "not "
+        } else {
+            ""
+        };
+        format!("{}{}({})", negate, condition_type, names.join(", "))
+    }
+
     pub fn conditions(&self) -> Result<(Condition, Vec<RelationDef>), DbErr> {
         let mut conditions = match self.condition_type {
             // None --> default to all when no option is provided
@@ -177,7 +310,48 @@ impl SearchAssetsQuery {
                 self.royalty_amount
                     .map(|x| asset::Column::RoyaltyAmount.eq(x)),
             )
-            .add_option(self.burnt.map(|x| asset::Column::Burnt.eq(x)));
+            .add_option(
+                self.royalty_amount_min
+                    .map(|x| asset::Column::RoyaltyAmount.gte(x)),
+            )
+            .add_option(
+                self.royalty_amount_max
+                    .map(|x| asset::Column::RoyaltyAmount.lte(x)),
+            )
+            .add_option(self.royalty_recipient.to_owned().map(|x| {
+                let creator_assets = Query::select()
+                    .column(asset_creators::Column::AssetId)
+                    .from(asset_creators::Entity)
+                    .and_where(asset_creators::Column::Creator.eq(x.clone()))
+                    .and_where(asset_creators::Column::Share.gt(0))
+                    .to_owned();
+                Condition::any()
+                    .add(asset::Column::RoyaltyTarget.eq(x))
+                    .add(asset::Column::Id.in_subquery(creator_assets))
+            }))
+            .add_option(self.burnt.map(|x| asset::Column::Burnt.eq(x)))
+            .add_option(self.escrowed.map(|x| {
+                let escrow_owners = Query::select()
+                    .column(escrow_owners::Column::Owner)
+                    .from(escrow_owners::Entity)
+                    .to_owned();
+                if x {
+                    asset::Column::Owner.in_subquery(escrow_owners)
+                } else {
+                    asset::Column::Owner.not_in_subquery(escrow_owners)
+                }
+            }))
+            .add_option(self.owned_by_asset.map(|x| {
+                let asset_pdas = Query::select()
+                    .column(asset_owner_pdas::Column::Pda)
+                    .from(asset_owner_pdas::Entity)
+                    .to_owned();
+                if x {
+                    asset::Column::Owner.in_subquery(asset_pdas)
+                } else {
+                    asset::Column::Owner.not_in_subquery(asset_pdas)
+                }
+            }));
 
         if let Some(c) = self.creator_address.to_owned() {
             conditions = conditions.add(asset_creators::Column::Creator.eq(c));
@@ -189,8 +363,19 @@ impl SearchAssetsQuery {
             conditions = conditions.add(asset_creators::Column::Verified.eq(cv));
         }
 
-        // If creator_address or creator_verified is set, join with asset_creators
-        if self.creator_address.is_some() || self.creator_verified.is_some() {
+        if let Some(min) = self.creator_share_min {
+            conditions = conditions.add(asset_creators::Column::Share.gte(min as i32));
+        }
+        if let Some(max) = self.creator_share_max {
+            conditions = conditions.add(asset_creators::Column::Share.lte(max as i32));
+        }
+
+        // If any creator condition is set, join with asset_creators
+        if self.creator_address.is_some()
+            || self.creator_verified.is_some()
+            || self.creator_share_min.is_some()
+            || self.creator_share_max.is_some()
+        {
             let rel = asset_creators::Relation::Asset
                 .def()
                 .rev()
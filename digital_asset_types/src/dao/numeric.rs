@@ -0,0 +1,67 @@
+//! Conversions between the unsigned integers of Solana and the columns storing them.
+//!
+//! Postgres has no unsigned integers, so seqs, slots and indexes are stored in `bigint` and
+//! token amounts, which use the full range of a `u64`, in `numeric(20, 0)`. The conversions
+//! below fail on values their column can't hold, where an `as` cast would silently wrap them
+//! into negative numbers that then sort before every other row. A seq grows by one with every
+//! change of its tree and stays far below `i64::MAX`, so out of range seqs come from malformed
+//! events rather than busy trees, and are rejected with them. The supply of an asset is the
+//! exception: it is still a `bigint`, and the rare fungible supply above `i64::MAX` is capped
+//! there while `tokens.supply` keeps the whole amount.
+
+use log::warn;
+use sea_orm::{prelude::Decimal, DbErr};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("{column} {value} is out of range")]
+pub struct NumericError {
+    pub column: &'static str,
+    pub value: String,
+}
+
+impl NumericError {
+    fn new(column: &'static str, value: impl ToString) -> Self {
+        NumericError {
+            column,
+            value: value.to_string(),
+        }
+    }
+}
+
+impl From<NumericError> for DbErr {
+    fn from(err: NumericError) -> Self {
+        DbErr::Custom(err.to_string())
+    }
+}
+
+/// `value` as stored in the `bigint` column `column`.
+pub fn to_i64(value: u64, column: &'static str) -> Result<i64, NumericError> {
+    i64::try_from(value).map_err(|_| NumericError::new(column, value))
+}
+
+/// `value` as stored in the `bigint` column `column`, capped at `i64::MAX`.
+pub fn saturating_to_i64(value: u64, column: &'static str) -> i64 {
+    i64::try_from(value).unwrap_or_else(|_| {
+        warn!("{} {} is out of range, storing {}", column, value, i64::MAX);
+        i64::MAX
+    })
+}
+
+/// The `u64` stored in the `bigint` column `column`.
+pub fn to_u64(value: i64, column: &'static str) -> Result<u64, NumericError> {
+    u64::try_from(value).map_err(|_| NumericError::new(column, value))
+}
+
+/// `value` as stored in a `numeric(20, 0)` column, which holds every `u64`.
+pub fn to_decimal(value: u64) -> Decimal {
+    Decimal::from(value)
+}
+
+/// The `u64` stored in the `numeric(20, 0)` column `column`.
+pub fn decimal_to_u64(value: Decimal, column: &'static str) -> Result<u64, NumericError> {
+    if !value.fract().is_zero() {
+        return Err(NumericError::new(column, value));
+    }
+    u64::try_from(value).map_err(|_| NumericError::new(column, value))
+}
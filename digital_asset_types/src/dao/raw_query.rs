@@ -0,0 +1,71 @@
+//! Builds the statements sea-orm's query builder can't express. Values are always bound as
+//! parameters and never formatted into the SQL, so a query's text is the same on every call and
+//! the prepared statement sqlx caches for it on each connection is reused.
+
+use sea_orm::{DbBackend, Statement, Value};
+
+#[derive(Debug, Clone, Default)]
+pub struct RawQuery {
+    sql: String,
+    values: Vec<Value>,
+}
+
+impl RawQuery {
+    /// Starts a query from `sql`, whose `$1`, `$2`... placeholders are bound in order with
+    /// [RawQuery::bind].
+    pub fn new(sql: &str) -> Self {
+        RawQuery {
+            sql: sql.to_string(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Binds the value of the next placeholder.
+    pub fn bind(mut self, value: impl Into<Value>) -> Self {
+        self.values.push(value.into());
+        self
+    }
+
+    /// Appends `sql` to the query.
+    pub fn push(mut self, sql: &str) -> Self {
+        self.sql.push_str(sql);
+        self
+    }
+
+    /// Binds `value` and appends its placeholder to the query.
+    pub fn push_bind(mut self, value: impl Into<Value>) -> Self {
+        self.values.push(value.into());
+        self.sql.push_str(&format!("${}", self.values.len()));
+        self
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    pub fn build(self) -> Statement {
+        debug_assert_eq!(
+            highest_placeholder(&self.sql),
+            self.values.len(),
+            "every placeholder of {} needs a value",
+            self.sql
+        );
+        Statement::from_sql_and_values(DbBackend::Postgres, &self.sql, self.values)
+    }
+}
+
+/// The highest `$n` placeholder of `sql`.
+fn highest_placeholder(sql: &str) -> usize {
+    sql.split('$')
+        .skip(1)
+        .filter_map(|rest| {
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })
+        .max()
+        .unwrap_or(0)
+}
@@ -0,0 +1,81 @@
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement};
+
+/// The latest migration this build was written against. Bump it along with every new
+/// migration.
+pub const SCHEMA_VERSION: &str = "m20230903_120101_add_tasks_unfinished_index";
+
+/// Postgres schemas an instance's tables can live in, so several instances can share a database.
+/// The name is used as is in the connections' `search_path`, so it's limited to lowercase
+/// identifiers, e.g. `das_devnet`.
+pub fn is_valid_schema_name(schema: &str) -> bool {
+    !schema.is_empty()
+        && schema.len() <= 63
+        && !schema.starts_with("pg_")
+        && schema
+            .bytes()
+            .next()
+            .map(|b| b.is_ascii_lowercase() || b == b'_')
+            .unwrap_or(false)
+        && schema
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// How the database's applied migrations compare to [SCHEMA_VERSION].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaCompatibility {
+    /// The expected migration is the latest one applied.
+    Current,
+    /// The expected migration isn't applied yet, `applied` is the latest one that is.
+    Behind { applied: Option<String> },
+    /// Migrations newer than this build are applied, the latest of them is `applied`.
+    Ahead { applied: String },
+}
+
+#[derive(Debug, FromQueryResult)]
+struct MigrationsTable {
+    exists: bool,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AppliedMigration {
+    version: String,
+}
+
+/// Reads the migrations applied by the migrator. A database that was never migrated is
+/// behind.
+pub async fn check_schema_version(
+    conn: &impl ConnectionTrait,
+) -> Result<SchemaCompatibility, DbErr> {
+    let table = MigrationsTable::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "SELECT to_regclass('seaql_migrations') IS NOT NULL AS exists".to_string(),
+    ))
+    .one(conn)
+    .await?;
+    if !table.map(|t| t.exists).unwrap_or(false) {
+        return Ok(SchemaCompatibility::Behind { applied: None });
+    }
+
+    let applied: Vec<String> = AppliedMigration::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "SELECT version FROM seaql_migrations ORDER BY version".to_string(),
+    ))
+    .all(conn)
+    .await?
+    .into_iter()
+    .map(|m| m.version)
+    .collect();
+    // Migration names start with their creation time, so they sort in the order they were
+    // written.
+    let latest = applied.last().cloned();
+    Ok(match latest {
+        _ if !applied.iter().any(|v| v == SCHEMA_VERSION) => {
+            SchemaCompatibility::Behind { applied: latest }
+        }
+        Some(latest) if latest.as_str() > SCHEMA_VERSION => {
+            SchemaCompatibility::Ahead { applied: latest }
+        }
+        _ => SchemaCompatibility::Current,
+    })
+}
@@ -1,15 +1,19 @@
 use crate::{
     dao::{
         asset::{self, Entity},
-        asset_authority, asset_creators, asset_data, asset_grouping, cl_audits, FullAsset,
-        GroupingSize, Pagination,
+        asset_authority, asset_creators, asset_data, asset_grouping, asset_owner_pdas, cl_audits,
+        cl_items,
+        cursor::CursorKey,
+        raw_query::RawQuery,
+        scopes::metadata_blob::resolve_metadata_blobs,
+        FullAsset, GroupingSize, LeafSignature, OwnerCollection, Pagination, COLLECTION_GROUP_KEY,
     },
     dapi::common::safe_select,
-    rpc::{response::AssetList, CollectionMetadata},
+    rpc::{response::AssetList, transform::HiddenAssets, CollectionMetadata},
 };
 
 use indexmap::IndexMap;
-use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+use sea_orm::{entity::*, query::*, sea_query::Query, ConnectionTrait, DbErr, Order, Value};
 use std::collections::{HashMap, HashSet};
 use tokio::try_join;
 
@@ -32,10 +36,74 @@ where
                 stmt = stmt.offset((page - 1) * limit)
             }
         }
+        // Applied with the sort by [sort_assets].
+        Pagination::Cursor { .. } => {}
     }
     stmt.limit(limit)
 }
 
+/// Orders assets by `sort_by`, then by id among assets with the same value. Cursor pagination
+/// needs a sort key that never changes, and keeps the assets after its cursor.
+pub fn sort_assets(
+    stmt: Select<Entity>,
+    sort_by: Option<asset::Column>,
+    sort_direction: Order,
+    pagination: &Pagination,
+) -> Result<Select<Entity>, DbErr> {
+    let cursor = match pagination {
+        Pagination::Cursor { cursor } => cursor,
+        _ => {
+            return Ok(match sort_by {
+                Some(col) => stmt
+                    .order_by(col, sort_direction.clone())
+                    .order_by(asset::Column::Id, sort_direction),
+                None => stmt,
+            })
+        }
+    };
+    let key = CursorKey::for_sort(sort_by.as_ref())?;
+    let mut stmt = stmt;
+    if let Some(cursor) = cursor {
+        stmt = stmt.filter(cursor.condition(key, &sort_direction)?);
+    }
+    if key == CursorKey::Created {
+        stmt = stmt.order_by(asset::Column::CreatedAt, sort_direction.clone());
+    }
+    Ok(stmt.order_by(asset::Column::Id, sort_direction))
+}
+
+/// Restricts `condition` to the assets of `network`, or leaves it as is for every network.
+pub fn on_network(condition: Condition, network: Option<String>) -> Condition {
+    match network {
+        Some(network) => condition.add(asset::Column::Network.eq(network)),
+        None => condition,
+    }
+}
+
+/// Leaves the assets `hidden` out of `condition`: the ones with a grouping in a hidden
+/// collection, verified or not, and the ones in a hidden tree.
+pub fn visible(condition: Condition, hidden: &HiddenAssets) -> Condition {
+    let mut condition = condition;
+    if !hidden.collections.is_empty() {
+        let hidden_ids = Query::select()
+            .column(asset_grouping::Column::AssetId)
+            .from(asset_grouping::Entity)
+            .and_where(asset_grouping::Column::GroupKey.eq(COLLECTION_GROUP_KEY))
+            .and_where(asset_grouping::Column::GroupValue.is_in(hidden.collections.clone()))
+            .to_owned();
+        condition = condition.add(asset::Column::Id.not_in_subquery(hidden_ids));
+    }
+    if !hidden.trees.is_empty() {
+        condition = condition.add(
+            asset::Column::TreeId
+                .is_null()
+                .or(asset::Column::TreeId.is_not_in(hidden.trees.clone())),
+        );
+    }
+    condition
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_by_creator(
     conn: &impl ConnectionTrait,
     creator: Vec<u8>,
@@ -45,6 +113,8 @@ pub async fn get_by_creator(
     pagination: &Pagination,
     limit: u64,
     enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
 ) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
     let mut condition = Condition::all()
         .add(asset_creators::Column::Creator.eq(creator))
@@ -54,7 +124,7 @@ pub async fn get_by_creator(
     }
     get_by_related_condition(
         conn,
-        condition,
+        visible(on_network(condition, network), hidden),
         asset::Relation::AssetCreators,
         sort_by,
         sort_direction,
@@ -86,29 +156,44 @@ pub async fn get_grouping(
     Ok(GroupingSize { size })
 }
 
+/// Assets of a group. With `require_verified`, assets whose membership of the group isn't
+/// verified, or predates the verified flag, are left out.
+#[allow(clippy::too_many_arguments)]
 pub async fn get_by_grouping(
     conn: &impl ConnectionTrait,
     group_key: String,
     group_value: String,
+    require_verified: bool,
     sort_by: Option<asset::Column>,
     sort_direction: Order,
     pagination: &Pagination,
     limit: u64,
     enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
 ) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
+    let verified = if require_verified {
+        asset_grouping::Column::Verified.eq(true)
+    } else {
+        asset_grouping::Column::Verified
+            .eq(true)
+            .or(asset_grouping::Column::Verified.is_null())
+    };
     let condition = asset_grouping::Column::GroupKey
         .eq(group_key)
         .and(asset_grouping::Column::GroupValue.eq(group_value))
-        .and(
-            asset_grouping::Column::Verified
-                .eq(true)
-                .or(asset_grouping::Column::Verified.is_null()),
-        );
+        .and(verified);
     get_by_related_condition(
         conn,
-        Condition::all()
-            .add(condition)
-            .add(asset::Column::Supply.gt(0)),
+        visible(
+            on_network(
+                Condition::all()
+                    .add(condition)
+                    .add(asset::Column::Supply.gt(0)),
+                network,
+            ),
+            hidden,
+        ),
         asset::Relation::AssetGrouping,
         sort_by,
         sort_direction,
@@ -119,6 +204,18 @@ pub async fn get_by_grouping(
     .await
 }
 
+pub async fn group_key_exists(
+    conn: &impl ConnectionTrait,
+    group_key: String,
+) -> Result<bool, DbErr> {
+    Ok(asset_grouping::Entity::find()
+        .filter(asset_grouping::Column::GroupKey.eq(group_key))
+        .one(conn)
+        .await?
+        .is_some())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_assets_by_owner(
     conn: &impl ConnectionTrait,
     owner: Vec<u8>,
@@ -127,13 +224,79 @@ pub async fn get_assets_by_owner(
     pagination: &Pagination,
     limit: u64,
     enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
 ) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
     let cond = Condition::all()
         .add(asset::Column::Owner.eq(owner))
         .add(asset::Column::Supply.gt(0));
     get_assets_by_condition(
         conn,
-        cond,
+        visible(on_network(cond, network), hidden),
+        vec![],
+        sort_by,
+        sort_direction,
+        pagination,
+        limit,
+        enable_grand_total_query,
+    )
+    .await
+}
+
+/// Compressed assets whose leaf delegate is `delegate`, unlike `delegate` which is also the
+/// token delegate of uncompressed assets.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_by_leaf_delegate(
+    conn: &impl ConnectionTrait,
+    delegate: Vec<u8>,
+    sort_by: Option<asset::Column>,
+    sort_direction: Order,
+    pagination: &Pagination,
+    limit: u64,
+    enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
+) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
+    let cond = Condition::all()
+        .add(asset::Column::LeafDelegate.eq(delegate))
+        .add(asset::Column::Supply.gt(0));
+    get_assets_by_condition(
+        conn,
+        visible(on_network(cond, network), hidden),
+        vec![],
+        sort_by,
+        sort_direction,
+        pagination,
+        limit,
+        enable_grand_total_query,
+    )
+    .await
+}
+
+/// Assets owned by one of the PDAs of `parent`, such as its token owned escrow.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_by_parent_asset(
+    conn: &impl ConnectionTrait,
+    parent: Vec<u8>,
+    sort_by: Option<asset::Column>,
+    sort_direction: Order,
+    pagination: &Pagination,
+    limit: u64,
+    enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
+) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
+    let parent_pdas = Query::select()
+        .column(asset_owner_pdas::Column::Pda)
+        .from(asset_owner_pdas::Entity)
+        .and_where(asset_owner_pdas::Column::AssetId.eq(parent))
+        .to_owned();
+    let cond = Condition::all()
+        .add(asset::Column::Owner.in_subquery(parent_pdas))
+        .add(asset::Column::Supply.gt(0));
+    get_assets_by_condition(
+        conn,
+        visible(on_network(cond, network), hidden),
         vec![],
         sort_by,
         sort_direction,
@@ -144,6 +307,85 @@ pub async fn get_assets_by_owner(
     .await
 }
 
+pub async fn get_owner_collections(
+    conn: &impl ConnectionTrait,
+    owner: Vec<u8>,
+    limit: u64,
+    page: u64,
+    network: Option<String>,
+    hidden: &HiddenAssets,
+) -> Result<Vec<OwnerCollection>, DbErr> {
+    // The same groupings as the ones shown on the assets, verified or from before verification
+    // was indexed.
+    let mut query = RawQuery::new(
+        "
+SELECT g.group_value AS collection, count(*) AS count, (array_agg(a.id ORDER BY a.id))[1] AS sample_id
+FROM asset a
+LEFT JOIN asset_grouping g ON g.asset_id = a.id
+    AND g.group_key = $4
+    AND g.group_value IS NOT NULL
+    AND (g.verified OR g.verified IS NULL)
+WHERE a.owner = $1 AND a.supply > 0 AND ($5::text IS NULL OR a.network = $5)
+",
+    )
+    .bind(owner)
+    .bind(limit as i64)
+    .bind((page.saturating_sub(1) * limit) as i64)
+    .bind(COLLECTION_GROUP_KEY)
+    .bind(network);
+    if !hidden.collections.is_empty() {
+        query = push_list(
+            query.push(
+                "AND NOT EXISTS (SELECT 1 FROM asset_grouping h WHERE h.asset_id = a.id AND h.group_key = $4 AND h.group_value IN (",
+            ),
+            hidden.collections.iter().cloned(),
+        )
+        .push("))\n");
+    }
+    if !hidden.trees.is_empty() {
+        query = push_list(
+            query.push("AND (a.tree_id IS NULL OR a.tree_id NOT IN ("),
+            hidden.trees.iter().cloned(),
+        )
+        .push("))\n");
+    }
+    OwnerCollection::find_by_statement(
+        query
+            .push(
+                "GROUP BY g.group_value
+ORDER BY count DESC, collection NULLS LAST
+LIMIT $2 OFFSET $3
+",
+            )
+            .build(),
+    )
+    .all(conn)
+    .await
+}
+
+/// Appends the placeholders of `values`, separated by commas, binding each of them.
+pub(crate) fn push_list<V: Into<Value>>(
+    query: RawQuery,
+    values: impl Iterator<Item = V>,
+) -> RawQuery {
+    values.enumerate().fold(query, |query, (i, value)| {
+        let query = if i > 0 { query.push(", ") } else { query };
+        query.push_bind(value)
+    })
+}
+
+pub async fn get_by_ids(
+    conn: &impl ConnectionTrait,
+    ids: Vec<Vec<u8>>,
+) -> Result<Vec<FullAsset>, DbErr> {
+    let assets = asset::Entity::find()
+        .filter(asset::Column::Id.is_in(ids))
+        .all(conn)
+        .await?;
+    get_related_for_assets(conn, assets).await
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn get_by_authority(
     conn: &impl ConnectionTrait,
     authority: Vec<u8>,
@@ -152,13 +394,15 @@ pub async fn get_by_authority(
     pagination: &Pagination,
     limit: u64,
     enable_grand_total_query: bool,
+    network: Option<String>,
+    hidden: &HiddenAssets,
 ) -> Result<(Vec<FullAsset>, Option<u64>), DbErr> {
     let cond = Condition::all()
         .add(asset_authority::Column::Authority.eq(authority))
         .add(asset::Column::Supply.gt(0));
     get_by_related_condition(
         conn,
-        cond,
+        visible(on_network(cond, network), hidden),
         asset::Relation::AssetAuthority,
         sort_by,
         sort_direction,
@@ -202,15 +446,10 @@ async fn get_by_related_condition<E>(
 where
     E: RelationTrait,
 {
-    let mut stmt = asset::Entity::find()
+    let stmt = asset::Entity::find()
         .filter(condition)
         .join(JoinType::LeftJoin, relation.def());
-
-    if let Some(col) = sort_by {
-        stmt = stmt
-            .order_by(col, sort_direction.clone())
-            .order_by(asset::Column::Id, sort_direction);
-    }
+    let stmt = sort_assets(stmt, sort_by, sort_direction, pagination)?;
 
     let (assets, grand_total) =
         get_full_response(conn, stmt, pagination, limit, enable_grand_total_query).await?;
@@ -223,10 +462,11 @@ pub async fn get_related_for_assets(
 ) -> Result<Vec<FullAsset>, DbErr> {
     let asset_ids = assets.iter().map(|a| a.id.clone()).collect::<Vec<_>>();
 
-    let asset_data: Vec<asset_data::Model> = asset_data::Entity::find()
+    let mut asset_data: Vec<asset_data::Model> = asset_data::Entity::find()
         .filter(asset_data::Column::Id.is_in(asset_ids))
         .all(conn)
         .await?;
+    resolve_metadata_blobs(conn, &mut asset_data).await?;
     let asset_data_map = asset_data.into_iter().fold(HashMap::new(), |mut acc, ad| {
         acc.insert(ad.id.clone(), ad);
         acc
@@ -312,11 +552,7 @@ pub async fn get_assets_by_condition(
         stmt = stmt.join(JoinType::LeftJoin, def);
     }
     stmt = stmt.filter(condition);
-    if let Some(col) = sort_by {
-        stmt = stmt
-            .order_by(col, sort_direction.clone())
-            .order_by(asset::Column::Id, sort_direction);
-    }
+    let stmt = sort_assets(stmt, sort_by, sort_direction, pagination)?;
 
     let (assets, grand_total) =
         get_full_response(conn, stmt, pagination, limit, enable_grand_total_query).await?;
@@ -339,7 +575,8 @@ pub async fn get_by_id(
             _ => Err(DbErr::RecordNotFound("Asset Not Found".to_string())),
         })?;
 
-    let (asset, data) = asset_data;
+    let (asset, mut data) = asset_data;
+    resolve_metadata_blobs(conn, std::slice::from_mut(&mut data)).await?;
     let authorities: Vec<asset_authority::Model> = asset_authority::Entity::find()
         .filter(asset_authority::Column::AssetId.eq(asset.id.clone()))
         .order_by_asc(asset_authority::Column::AssetId)
@@ -438,6 +675,127 @@ pub async fn get_signatures_for_asset(
     }
 }
 
+/// The tree and leaf index of each of the compressed assets among `asset_ids`.
+pub async fn get_asset_leaves(
+    conn: &impl ConnectionTrait,
+    asset_ids: Vec<Vec<u8>>,
+) -> Result<HashMap<Vec<u8>, (Vec<u8>, i64)>, DbErr> {
+    if asset_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let assets = asset::Entity::find()
+        .filter(asset::Column::Id.is_in(asset_ids))
+        .filter(asset::Column::TreeId.is_not_null())
+        .all(conn)
+        .await?;
+    Ok(assets
+        .into_iter()
+        .filter_map(|a| match (a.tree_id, a.nonce) {
+            (Some(tree), Some(nonce)) if !tree.is_empty() => Some((a.id, (tree, nonce))),
+            _ => None,
+        })
+        .collect())
+}
+
+/// The id of the compressed asset at `leaf_index` of `tree`.
+pub async fn get_id_by_leaf(
+    conn: &impl ConnectionTrait,
+    tree: Vec<u8>,
+    leaf_index: i64,
+) -> Result<Option<Vec<u8>>, DbErr> {
+    Ok(asset::Entity::find()
+        .filter(asset::Column::TreeId.eq(tree))
+        .filter(asset::Column::Nonce.eq(leaf_index))
+        .one(conn)
+        .await?
+        .map(|a| a.id))
+}
+
+/// The id of the compressed asset whose leaf hashes to `hash`. The asset's own leaf is looked up
+/// first, then the trees' change logs, whose leaves can be ahead of an asset still waiting on an
+/// earlier update. An empty leaf doesn't belong to any asset.
+pub async fn get_id_by_leaf_hash(
+    conn: &impl ConnectionTrait,
+    hash: Vec<u8>,
+) -> Result<Option<Vec<u8>>, DbErr> {
+    if hash.iter().all(|b| *b == 0) {
+        return Ok(None);
+    }
+    let by_asset = asset::Entity::find()
+        .filter(asset::Column::Leaf.eq(hash.clone()))
+        .one(conn)
+        .await?;
+    if let Some(asset) = by_asset {
+        return Ok(Some(asset.id));
+    }
+    let by_change_log = cl_items::Entity::find()
+        .filter(cl_items::Column::Hash.eq(hash))
+        .filter(cl_items::Column::Level.eq(0))
+        .one(conn)
+        .await?;
+    match by_change_log {
+        Some(cl_items::Model {
+            tree,
+            leaf_idx: Some(leaf_index),
+            ..
+        }) => get_id_by_leaf(conn, tree, leaf_index).await,
+        _ => Ok(None),
+    }
+}
+
+/// The latest `limit` transactions of each of `leaves`, newest first, read from `cl_audits` in a
+/// single statement that walks the `(tree, leaf_idx, created_at)` index once per leaf. The leaves
+/// are bound as one JSON array, so batches of any size share the statement.
+pub async fn get_signatures_for_leaves(
+    conn: &impl ConnectionTrait,
+    leaves: &[(Vec<u8>, i64)],
+    limit: u64,
+) -> Result<HashMap<(Vec<u8>, i64), Vec<Vec<String>>>, DbErr> {
+    let leaves: HashSet<&(Vec<u8>, i64)> = leaves.iter().collect();
+    if leaves.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let leaves: Vec<serde_json::Value> = leaves
+        .into_iter()
+        .map(|(tree, leaf_idx)| {
+            let tree: String = tree.iter().map(|b| format!("{:02x}", b)).collect();
+            serde_json::json!({ "tree": tree, "leaf_idx": leaf_idx })
+        })
+        .collect();
+    let signatures = LeafSignature::find_by_statement(
+        RawQuery::new(
+            "
+SELECT l.tree, l.leaf_idx, a.tx, a.instruction
+FROM (
+    SELECT decode(r.tree, 'hex') AS tree, r.leaf_idx
+    FROM jsonb_to_recordset($2::jsonb) AS r (tree text, leaf_idx bigint)
+) l
+CROSS JOIN LATERAL (
+    SELECT tx, instruction, created_at, id FROM cl_audits
+    WHERE cl_audits.tree = l.tree AND cl_audits.leaf_idx = l.leaf_idx
+    ORDER BY created_at DESC, id DESC
+    LIMIT $1
+) a
+ORDER BY l.tree, l.leaf_idx, a.created_at DESC, a.id DESC
+",
+        )
+        .bind(limit as i64)
+        .bind(serde_json::Value::Array(leaves))
+        .build(),
+    )
+    .all(conn)
+    .await?;
+
+    let mut by_leaf: HashMap<(Vec<u8>, i64), Vec<Vec<String>>> = HashMap::new();
+    for s in signatures {
+        by_leaf
+            .entry((s.tree, s.leaf_idx))
+            .or_default()
+            .push(vec![s.tx, s.instruction]);
+    }
+    Ok(by_leaf)
+}
+
 async fn get_full_response(
     conn: &impl ConnectionTrait,
     stmt: Select<Entity>,
@@ -493,11 +851,12 @@ pub async fn add_collection_metadata(
         .collect();
 
     // make a query to fetch all the metadata
-    let asset_data = asset_data::Entity::find()
+    let mut asset_data = asset_data::Entity::find()
         .filter(asset_data::Column::Id.is_in(bytea_group_values))
         .limit(group_values.len() as u64)
         .all(conn)
         .await?;
+    resolve_metadata_blobs(conn, &mut asset_data).await?;
 
     // create a mapping of id -> collection_metadata
     let mut hashmap: HashMap<String, CollectionMetadata> = HashMap::new();
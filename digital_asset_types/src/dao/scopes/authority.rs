@@ -0,0 +1,57 @@
+use crate::dao::{asset_authority_history, authority_accounts};
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+use std::collections::HashMap;
+
+/// Update authority changes of `asset_id`, oldest first, each with what the authority account
+/// was resolved to, if it has been yet.
+pub async fn get_asset_authority_history(
+    conn: &impl ConnectionTrait,
+    asset_id: Vec<u8>,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<
+    Vec<(
+        asset_authority_history::Model,
+        Option<authority_accounts::Model>,
+    )>,
+    DbErr,
+> {
+    let mut stmt = asset_authority_history::Entity::find()
+        .filter(asset_authority_history::Column::AssetId.eq(asset_id));
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    let history = stmt
+        .order_by(asset_authority_history::Column::SlotUpdated, Order::Asc)
+        .order_by(asset_authority_history::Column::Seq, Order::Asc)
+        .order_by(asset_authority_history::Column::Id, Order::Asc)
+        .limit(limit)
+        .all(conn)
+        .await?;
+    if history.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let authorities = history
+        .iter()
+        .map(|h| h.authority.clone())
+        .collect::<Vec<_>>();
+    let resolved = authority_accounts::Entity::find()
+        .filter(authority_accounts::Column::Authority.is_in(authorities))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|a| (a.authority.clone(), a))
+        .collect::<HashMap<_, _>>();
+
+    Ok(history
+        .into_iter()
+        .map(|h| {
+            let account = resolved.get(&h.authority).cloned();
+            (h, account)
+        })
+        .collect())
+}
@@ -0,0 +1,53 @@
+use crate::dao::{asset_grouping, collection_stats, collection_traits, COLLECTION_GROUP_KEY};
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+
+/// Trait counts of a collection by trait type, most common value first, optionally of a single
+/// trait type.
+pub async fn get_collection_traits(
+    conn: &impl ConnectionTrait,
+    collection: String,
+    trait_type: Option<String>,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<Vec<collection_traits::Model>, DbErr> {
+    let mut stmt = collection_traits::Entity::find()
+        .filter(collection_traits::Column::Collection.eq(collection));
+    if let Some(trait_type) = trait_type {
+        stmt = stmt.filter(collection_traits::Column::TraitType.eq(trait_type));
+    }
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    stmt.order_by(collection_traits::Column::TraitType, Order::Asc)
+        .order_by(collection_traits::Column::Count, Order::Desc)
+        .order_by(collection_traits::Column::Value, Order::Asc)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+/// Number of assets verified as part of `collection`, the base for trait frequencies.
+pub async fn get_collection_size(
+    conn: &impl ConnectionTrait,
+    collection: String,
+) -> Result<u64, DbErr> {
+    asset_grouping::Entity::find()
+        .filter(asset_grouping::Column::GroupKey.eq(COLLECTION_GROUP_KEY))
+        .filter(asset_grouping::Column::GroupValue.eq(collection))
+        .filter(asset_grouping::Column::Verified.eq(true))
+        .count(conn)
+        .await
+}
+
+/// Activity counters of `collection`, missing for collections without indexed activity.
+pub async fn get_collection_stats(
+    conn: &impl ConnectionTrait,
+    collection: String,
+) -> Result<Option<collection_stats::Model>, DbErr> {
+    collection_stats::Entity::find_by_id(collection)
+        .one(conn)
+        .await
+}
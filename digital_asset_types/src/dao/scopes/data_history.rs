@@ -0,0 +1,49 @@
+use crate::dao::asset_data_history;
+
+use sea_orm::{entity::*, query::*, sea_query::Expr, ConnectionTrait, DbErr, FromQueryResult};
+
+#[derive(Debug, FromQueryResult)]
+struct MutatedAsset {
+    asset_id: Vec<u8>,
+}
+
+/// Versions of the on-chain name, symbol and metadata uri of `asset_id`, oldest first.
+pub async fn get_asset_data_history(
+    conn: &impl ConnectionTrait,
+    asset_id: Vec<u8>,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<Vec<asset_data_history::Model>, DbErr> {
+    let mut stmt =
+        asset_data_history::Entity::find().filter(asset_data_history::Column::AssetId.eq(asset_id));
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    stmt.order_by(asset_data_history::Column::SlotUpdated, Order::Asc)
+        .order_by(asset_data_history::Column::Id, Order::Asc)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+/// The assets of `ids` whose data changed after it was first indexed.
+pub async fn get_mutated(
+    conn: &impl ConnectionTrait,
+    ids: Vec<Vec<u8>>,
+) -> Result<Vec<Vec<u8>>, DbErr> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    let mutated = asset_data_history::Entity::find()
+        .select_only()
+        .column(asset_data_history::Column::AssetId)
+        .filter(asset_data_history::Column::AssetId.is_in(ids))
+        .group_by(asset_data_history::Column::AssetId)
+        .having(Expr::cust("count(*) > 1"))
+        .into_model::<MutatedAsset>()
+        .all(conn)
+        .await?;
+    Ok(mutated.into_iter().map(|m| m.asset_id).collect())
+}
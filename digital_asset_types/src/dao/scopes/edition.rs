@@ -0,0 +1,81 @@
+use crate::dao::{asset, asset_editions, asset_v1_account_attachments};
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+use std::collections::HashMap;
+
+/// A print edition with the mint it was linked to by its metadata account and that mint's
+/// owner, either of which may not be indexed yet.
+pub struct EditionWithMint {
+    pub edition: asset_editions::Model,
+    pub mint: Option<Vec<u8>>,
+    pub owner: Option<Vec<u8>>,
+}
+
+/// The master edition account attachment at `master_edition`, if indexed.
+pub async fn get_master_edition(
+    conn: &impl ConnectionTrait,
+    master_edition: Vec<u8>,
+) -> Result<Option<asset_v1_account_attachments::Model>, DbErr> {
+    asset_v1_account_attachments::Entity::find_by_id(master_edition)
+        .one(conn)
+        .await
+}
+
+/// Print editions of `master_edition` by edition number.
+pub async fn get_editions(
+    conn: &impl ConnectionTrait,
+    master_edition: Vec<u8>,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<Vec<EditionWithMint>, DbErr> {
+    let mut stmt =
+        asset_editions::Entity::find().filter(asset_editions::Column::Parent.eq(master_edition));
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    let editions = stmt
+        .order_by(asset_editions::Column::Edition, Order::Asc)
+        .order_by(asset_editions::Column::Id, Order::Asc)
+        .limit(limit)
+        .all(conn)
+        .await?;
+    if editions.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ids = editions.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+    let mints = asset_v1_account_attachments::Entity::find()
+        .filter(asset_v1_account_attachments::Column::Id.is_in(ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|a| a.asset_id.map(|mint| (a.id, mint)))
+        .collect::<HashMap<_, _>>();
+
+    let owners = if mints.is_empty() {
+        HashMap::new()
+    } else {
+        asset::Entity::find()
+            .filter(asset::Column::Id.is_in(mints.values().cloned().collect::<Vec<_>>()))
+            .all(conn)
+            .await?
+            .into_iter()
+            .filter_map(|a| a.owner.map(|owner| (a.id, owner)))
+            .collect::<HashMap<_, _>>()
+    };
+
+    Ok(editions
+        .into_iter()
+        .map(|edition| {
+            let mint = mints.get(&edition.id).cloned();
+            let owner = mint.as_ref().and_then(|m| owners.get(m).cloned());
+            EditionWithMint {
+                edition,
+                mint,
+                owner,
+            }
+        })
+        .collect())
+}
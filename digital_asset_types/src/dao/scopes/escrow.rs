@@ -0,0 +1,21 @@
+use crate::dao::escrow_owners;
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+use std::collections::HashMap;
+
+/// The marketplace of each of `owners` that is in the escrow registry.
+pub async fn get_escrow_marketplaces(
+    conn: &impl ConnectionTrait,
+    owners: Vec<Vec<u8>>,
+) -> Result<HashMap<Vec<u8>, String>, DbErr> {
+    if owners.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(escrow_owners::Entity::find()
+        .filter(escrow_owners::Column::Owner.is_in(owners))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|e| (e.owner, e.marketplace))
+        .collect())
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+
+use crate::dao::{asset, hot_owner_assets, hot_owners, scopes, FullAsset, Pagination};
+
+/// Whether `owner` is a hot owner whose assets were all copied to `hot_owner_assets`.
+pub async fn is_hot_owner(conn: &impl ConnectionTrait, owner: Vec<u8>) -> Result<bool, DbErr> {
+    Ok(hot_owners::Entity::find_by_id(owner)
+        .one(conn)
+        .await?
+        .map_or(false, |hot| hot.ready))
+}
+
+/// A page of the assets of a hot owner, sorted and paged on `hot_owner_assets` rather than on
+/// the shared `asset` table, which only serves the page's assets by id. Returns `None` for owners
+/// that aren't hot yet and for the requests the copy can't serve: sorting by update and cursor
+/// pagination. Unsorted pages are in asset id order.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_hot_owner_assets(
+    conn: &impl ConnectionTrait,
+    owner: Vec<u8>,
+    sort_by: Option<asset::Column>,
+    sort_direction: Order,
+    pagination: &Pagination,
+    limit: u64,
+    enable_grand_total_query: bool,
+    network: Option<String>,
+) -> Result<Option<(Vec<FullAsset>, Option<u64>)>, DbErr> {
+    let by_created = match sort_by {
+        Some(asset::Column::CreatedAt) => true,
+        None => false,
+        Some(_) => return Ok(None),
+    };
+    if matches!(pagination, Pagination::Cursor { .. }) || !is_hot_owner(conn, owner.clone()).await?
+    {
+        return Ok(None);
+    }
+
+    let mut stmt =
+        hot_owner_assets::Entity::find().filter(hot_owner_assets::Column::Owner.eq(owner));
+    if let Some(network) = network {
+        stmt = stmt.filter(hot_owner_assets::Column::Network.eq(network));
+    }
+    let grand_total = if enable_grand_total_query {
+        Some(stmt.clone().count(conn).await?)
+    } else {
+        None
+    };
+    match pagination {
+        Pagination::Keyset { before, after } => {
+            if let Some(before) = before {
+                stmt = stmt.filter(hot_owner_assets::Column::AssetId.lt(before.clone()));
+            }
+            if let Some(after) = after {
+                stmt = stmt.filter(hot_owner_assets::Column::AssetId.gt(after.clone()));
+            }
+        }
+        Pagination::Page { page } => {
+            if *page > 0 {
+                stmt = stmt.offset((page - 1) * limit);
+            }
+        }
+        Pagination::Cursor { .. } => unreachable!("cursor pagination isn't served from the copy"),
+    }
+    if by_created {
+        stmt = stmt.order_by(hot_owner_assets::Column::CreatedAt, sort_direction.clone());
+    }
+    let ids: Vec<Vec<u8>> = stmt
+        .order_by(hot_owner_assets::Column::AssetId, sort_direction)
+        .limit(limit)
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|hot| hot.asset_id)
+        .collect();
+
+    let position: HashMap<&Vec<u8>, usize> =
+        ids.iter().enumerate().map(|(i, id)| (id, i)).collect();
+    let mut assets = asset::Entity::find()
+        .filter(asset::Column::Id.is_in(ids.clone()))
+        .all(conn)
+        .await?;
+    assets.sort_by_key(|asset| position.get(&asset.id).copied());
+    let assets = scopes::asset::get_related_for_assets(conn, assets).await?;
+    Ok(Some((assets, grand_total)))
+}
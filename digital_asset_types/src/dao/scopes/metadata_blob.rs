@@ -0,0 +1,52 @@
+use crate::dao::{asset_data, metadata_blob};
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, JsonValue};
+use solana_sdk::hash::hash;
+use std::collections::HashMap;
+
+/// The key of a URI's metadata in `metadata_blob`, the sha256 of the URI.
+pub fn uri_hash(uri: &str) -> Vec<u8> {
+    hash(uri.as_bytes()).to_bytes().to_vec()
+}
+
+/// The sha256 of the serialized metadata, to tell whether a download changed it.
+pub fn content_hash(metadata: &JsonValue) -> Vec<u8> {
+    hash(&serde_json::to_vec(metadata).unwrap_or_default())
+        .to_bytes()
+        .to_vec()
+}
+
+pub async fn get_metadata_blob(
+    conn: &impl ConnectionTrait,
+    uri_hash: Vec<u8>,
+) -> Result<Option<metadata_blob::Model>, DbErr> {
+    metadata_blob::Entity::find_by_id(uri_hash).one(conn).await
+}
+
+/// Fills in the metadata of the rows referencing a blob, which don't hold a copy of it. Rows
+/// whose blob is missing keep their own metadata.
+pub async fn resolve_metadata_blobs(
+    conn: &impl ConnectionTrait,
+    data: &mut [asset_data::Model],
+) -> Result<(), DbErr> {
+    let hashes = data
+        .iter()
+        .filter_map(|d| d.metadata_blob.clone())
+        .collect::<Vec<_>>();
+    if hashes.is_empty() {
+        return Ok(());
+    }
+    let blobs: HashMap<Vec<u8>, JsonValue> = metadata_blob::Entity::find()
+        .filter(metadata_blob::Column::UriHash.is_in(hashes))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|blob| (blob.uri_hash, blob.metadata))
+        .collect();
+    for d in data.iter_mut() {
+        if let Some(metadata) = d.metadata_blob.as_ref().and_then(|h| blobs.get(h)) {
+            d.metadata = metadata.clone();
+        }
+    }
+    Ok(())
+}
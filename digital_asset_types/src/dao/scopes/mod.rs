@@ -1 +1,13 @@
 pub mod asset;
+pub mod authority;
+pub mod collection;
+pub mod data_history;
+pub mod edition;
+pub mod escrow;
+pub mod hot_owner;
+pub mod metadata_blob;
+pub mod owner_history;
+pub mod parent;
+pub mod pricing;
+pub mod token;
+pub mod tree;
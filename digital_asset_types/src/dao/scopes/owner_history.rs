@@ -0,0 +1,138 @@
+use crate::dao::raw_query::RawQuery;
+use crate::dao::scopes::asset::push_list;
+use crate::dao::COLLECTION_GROUP_KEY;
+use crate::rpc::transform::HiddenAssets;
+use sea_orm::{ConnectionTrait, DbErr, FromQueryResult};
+
+/// An asset that moved to or away from an owner.
+#[derive(Debug, Clone, PartialEq, Eq, FromQueryResult)]
+pub struct OwnerChange {
+    pub asset_id: Vec<u8>,
+    /// Whether the owner holds the asset now rather than having held it before.
+    pub gained: bool,
+    /// Slot of the asset's latest owner change.
+    pub slot_updated: i64,
+}
+
+/// Assets whose owner was `owner` as of `since_slot` and isn't anymore, or the other way around,
+/// by asset id. An asset that left and came back since is neither gained nor lost. The assets
+/// `hidden` are left out.
+pub async fn get_owner_changes(
+    conn: &impl ConnectionTrait,
+    owner: Vec<u8>,
+    since_slot: i64,
+    page: Option<u64>,
+    limit: u64,
+    hidden: &HiddenAssets,
+) -> Result<Vec<OwnerChange>, DbErr> {
+    let offset = match page {
+        Some(page) if page > 0 => (page - 1) * limit,
+        _ => 0,
+    };
+    let mut query = RawQuery::new(
+        "
+SELECT d.asset_id, d.current_owner = $1 AS gained, d.slot_updated
+FROM (
+    SELECT c.asset_id, latest.owner AS current_owner, latest.slot_updated, (
+        SELECT h.owner FROM asset_owner_history h
+        WHERE h.asset_id = c.asset_id AND h.slot_updated <= $2
+        ORDER BY h.slot_updated DESC, h.seq DESC, h.id DESC
+        LIMIT 1
+    ) AS previous_owner
+    FROM (
+        SELECT DISTINCT o.asset_id FROM asset_owner_history o WHERE o.owner = $1
+",
+    )
+    .bind(owner)
+    .bind(since_slot)
+    .bind(limit as i64)
+    .bind(offset as i64);
+    if !hidden.collections.is_empty() {
+        query = push_list(
+            query
+                .push("AND NOT EXISTS (SELECT 1 FROM asset_grouping g WHERE g.asset_id = o.asset_id AND g.group_key = ")
+                .push_bind(COLLECTION_GROUP_KEY)
+                .push(" AND g.group_value IN ("),
+            hidden.collections.iter().cloned(),
+        )
+        .push("))\n");
+    }
+    if !hidden.trees.is_empty() {
+        query = push_list(
+            query.push(
+                "AND NOT EXISTS (SELECT 1 FROM asset a WHERE a.id = o.asset_id AND a.tree_id IN (",
+            ),
+            hidden.trees.iter().cloned(),
+        )
+        .push("))\n");
+    }
+    OwnerChange::find_by_statement(
+        query
+            .push(
+                "    ) c
+    CROSS JOIN LATERAL (
+        SELECT h.owner, h.slot_updated FROM asset_owner_history h
+        WHERE h.asset_id = c.asset_id
+        ORDER BY h.slot_updated DESC, h.seq DESC, h.id DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.slot_updated > $2
+) d
+WHERE (d.current_owner = $1) <> COALESCE(d.previous_owner = $1, false)
+ORDER BY d.asset_id
+LIMIT $3 OFFSET $4
+",
+            )
+            .build(),
+    )
+    .all(conn)
+    .await
+}
+
+/// The owner, delegate and burn of an asset as of a slot, each with the slot it was recorded
+/// at. Missing parts have no history up to the slot.
+#[derive(Debug, Clone, PartialEq, Eq, FromQueryResult)]
+pub struct AssetState {
+    pub owner: Option<Vec<u8>>,
+    pub owner_slot: Option<i64>,
+    pub delegate: Option<Vec<u8>>,
+    pub delegate_slot: Option<i64>,
+    pub burnt_slot: Option<i64>,
+}
+
+/// The state of `asset_id` after the last change recorded at or before `slot`. Changes within
+/// a slot are ordered by seq, and by the order they were indexed in for uncompressed assets.
+pub async fn get_state_at_slot(
+    conn: &impl ConnectionTrait,
+    asset_id: Vec<u8>,
+    slot: i64,
+) -> Result<AssetState, DbErr> {
+    let state = AssetState::find_by_statement(
+        RawQuery::new(
+            "
+SELECT o.owner, o.slot_updated AS owner_slot, d.delegate, d.slot_updated AS delegate_slot,
+    b.slot AS burnt_slot
+FROM (SELECT 1) s
+LEFT JOIN LATERAL (
+    SELECT owner, slot_updated FROM asset_owner_history
+    WHERE asset_id = $1 AND slot_updated <= $2
+    ORDER BY slot_updated DESC, seq DESC, id DESC
+    LIMIT 1
+) o ON true
+LEFT JOIN LATERAL (
+    SELECT delegate, slot_updated FROM asset_delegate_history
+    WHERE asset_id = $1 AND slot_updated <= $2
+    ORDER BY slot_updated DESC, seq DESC, id DESC
+    LIMIT 1
+) d ON true
+LEFT JOIN asset_burns b ON b.asset_id = $1 AND b.slot <= $2
+",
+        )
+        .bind(asset_id)
+        .bind(slot)
+        .build(),
+    )
+    .one(conn)
+    .await?;
+    state.ok_or_else(|| DbErr::Custom("the asset state query returned no row".to_string()))
+}
@@ -0,0 +1,21 @@
+use crate::dao::asset_owner_pdas;
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+use std::collections::HashMap;
+
+/// The asset each of `owners` is derived from, for the owners that are an asset's PDA.
+pub async fn get_parent_assets(
+    conn: &impl ConnectionTrait,
+    owners: Vec<Vec<u8>>,
+) -> Result<HashMap<Vec<u8>, Vec<u8>>, DbErr> {
+    if owners.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(asset_owner_pdas::Entity::find()
+        .filter(asset_owner_pdas::Column::Pda.is_in(owners))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|p| (p.pda, p.asset_id))
+        .collect())
+}
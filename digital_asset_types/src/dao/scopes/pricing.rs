@@ -0,0 +1,18 @@
+use crate::dao::asset_pricing;
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr};
+
+/// Every provider's price estimate for `ids`, most recently updated first.
+pub async fn get_prices(
+    conn: &impl ConnectionTrait,
+    ids: Vec<Vec<u8>>,
+) -> Result<Vec<asset_pricing::Model>, DbErr> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+    asset_pricing::Entity::find()
+        .filter(asset_pricing::Column::AssetId.is_in(ids))
+        .order_by_desc(asset_pricing::Column::UpdatedAt)
+        .all(conn)
+        .await
+}
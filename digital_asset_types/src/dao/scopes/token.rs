@@ -0,0 +1,104 @@
+use crate::dao::{token_account_balances, token_accounts, token_records, tokens};
+
+use blockbuster::token_metadata::pda::find_token_record_account;
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, Order};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Balance ledger entries for all of `owner`'s token accounts of `mint`, newest first.
+pub async fn get_token_balance_history(
+    conn: &impl ConnectionTrait,
+    owner: Vec<u8>,
+    mint: Vec<u8>,
+    before_slot: Option<i64>,
+    after_slot: Option<i64>,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<Vec<token_account_balances::Model>, DbErr> {
+    let mut stmt = token_account_balances::Entity::find()
+        .filter(token_account_balances::Column::Owner.eq(owner))
+        .filter(token_account_balances::Column::Mint.eq(mint));
+    if let Some(before) = before_slot {
+        stmt = stmt.filter(token_account_balances::Column::Slot.lt(before));
+    }
+    if let Some(after) = after_slot {
+        stmt = stmt.filter(token_account_balances::Column::Slot.gt(after));
+    }
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    stmt.order_by(token_account_balances::Column::Slot, Order::Desc)
+        .order_by(token_account_balances::Column::Id, Order::Desc)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+/// The token record of the token account holding each of `mints`, for programmable NFTs. Mints
+/// without a holder, or whose holder has no record, are left out.
+pub async fn get_token_records(
+    conn: &impl ConnectionTrait,
+    mints: Vec<Vec<u8>>,
+) -> Result<HashMap<Vec<u8>, token_records::Model>, DbErr> {
+    if mints.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let holders = token_accounts::Entity::find()
+        .filter(token_accounts::Column::Mint.is_in(mints))
+        .filter(token_accounts::Column::Amount.gt(0))
+        .all(conn)
+        .await?;
+    let records = holders
+        .iter()
+        .filter_map(|holder| {
+            let mint = Pubkey::try_from(holder.mint.as_slice()).ok()?;
+            let token = Pubkey::try_from(holder.pubkey.as_slice()).ok()?;
+            let (record, _) = find_token_record_account(&mint, &token);
+            Some((record.to_bytes().to_vec(), holder.mint.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+    if records.is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(token_records::Entity::find()
+        .filter(token_records::Column::Pubkey.is_in(records.keys().cloned()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .filter_map(|record| Some((records.get(&record.pubkey)?.clone(), record)))
+        .collect())
+}
+
+/// The indexed mints among `mints`.
+pub async fn get_tokens(
+    conn: &impl ConnectionTrait,
+    mints: Vec<Vec<u8>>,
+) -> Result<Vec<tokens::Model>, DbErr> {
+    if mints.is_empty() {
+        return Ok(vec![]);
+    }
+    tokens::Entity::find()
+        .filter(tokens::Column::Mint.is_in(mints))
+        .all(conn)
+        .await
+}
+
+/// The token accounts of `owners` holding a balance of any of `mints`, largest balance first.
+pub async fn get_owner_token_accounts(
+    conn: &impl ConnectionTrait,
+    mints: Vec<Vec<u8>>,
+    owners: Vec<Vec<u8>>,
+) -> Result<Vec<token_accounts::Model>, DbErr> {
+    if mints.is_empty() || owners.is_empty() {
+        return Ok(vec![]);
+    }
+    token_accounts::Entity::find()
+        .filter(token_accounts::Column::Mint.is_in(mints))
+        .filter(token_accounts::Column::Owner.is_in(owners))
+        .filter(token_accounts::Column::Amount.gt(0))
+        .order_by_desc(token_accounts::Column::Amount)
+        .all(conn)
+        .await
+}
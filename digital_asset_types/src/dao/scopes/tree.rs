@@ -0,0 +1,63 @@
+use crate::dao::{cl_audits, raw_query::RawQuery};
+
+use sea_orm::{entity::*, query::*, ConnectionTrait, DbErr, FromQueryResult, Order};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, FromQueryResult)]
+pub struct LeafFreshness {
+    pub tree_seq: Option<i64>,
+    pub leaf_seq: Option<i64>,
+    pub leaf_hash: Option<Vec<u8>>,
+}
+
+/// Leaf level audit rows of `tree` with a seq between `seq_start` and `seq_end` (inclusive), one
+/// per change log, in seq order.
+pub async fn get_tree_audit(
+    conn: &impl ConnectionTrait,
+    tree: Vec<u8>,
+    seq_start: i64,
+    seq_end: i64,
+    page: Option<u64>,
+    limit: u64,
+) -> Result<Vec<cl_audits::Model>, DbErr> {
+    let mut stmt = cl_audits::Entity::find()
+        .filter(cl_audits::Column::Tree.eq(tree))
+        .filter(cl_audits::Column::Level.eq(0i64))
+        .filter(cl_audits::Column::Seq.between(seq_start, seq_end));
+    if let Some(page) = page {
+        if page > 0 {
+            stmt = stmt.offset((page - 1) * limit);
+        }
+    }
+    stmt.order_by(cl_audits::Column::Seq, Order::Asc)
+        .order_by(cl_audits::Column::Id, Order::Asc)
+        .limit(limit)
+        .all(conn)
+        .await
+}
+
+/// The latest seq indexed for `tree`, read from its root which every change log rewrites, and the
+/// seq and hash the leaf at `leaf_idx` was last indexed with.
+pub async fn get_leaf_freshness(
+    conn: &impl ConnectionTrait,
+    tree: Vec<u8>,
+    leaf_idx: i64,
+) -> Result<LeafFreshness, DbErr> {
+    let freshness = LeafFreshness::find_by_statement(
+        RawQuery::new(
+            "
+SELECT
+    (SELECT seq FROM cl_items WHERE tree = $1 AND node_idx = 1) AS tree_seq,
+    leaf.seq AS leaf_seq,
+    leaf.hash AS leaf_hash
+FROM (SELECT 1) AS one
+LEFT JOIN cl_items leaf ON leaf.tree = $1 AND leaf.leaf_idx = $2 AND leaf.level = 0
+",
+        )
+        .bind(tree)
+        .bind(leaf_idx)
+        .build(),
+    )
+    .one(conn)
+    .await?;
+    Ok(freshness.unwrap_or_default())
+}
@@ -0,0 +1,36 @@
+use crate::dao::numeric::{to_i64, to_u64, NumericError};
+use crate::dao::{asset, scopes};
+use crate::rpc::response::AssetAtSlot;
+
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait};
+
+/// The owner, delegate and burn of an indexed asset as of `slot`, rebuilt from its history.
+pub async fn get_asset_at_slot(
+    conn: &impl ConnectionTrait,
+    id: Vec<u8>,
+    slot: u64,
+) -> Result<AssetAtSlot, DbErr> {
+    if asset::Entity::find_by_id(id.clone())
+        .one(conn)
+        .await?
+        .is_none()
+    {
+        return Err(DbErr::RecordNotFound("Asset Not Found".to_string()));
+    }
+    let state =
+        scopes::owner_history::get_state_at_slot(conn, id.clone(), to_i64(slot, "slot")?).await?;
+    let address = |key: Option<Vec<u8>>| key.map(|k| bs58::encode(k).into_string());
+    let slot_of = |slot: Option<i64>, column| -> Result<Option<u64>, NumericError> {
+        slot.map(|s| to_u64(s, column)).transpose()
+    };
+    Ok(AssetAtSlot {
+        id: bs58::encode(id).into_string(),
+        slot,
+        owner: address(state.owner),
+        owner_slot: slot_of(state.owner_slot, "owner_slot")?,
+        delegate: address(state.delegate),
+        delegate_slot: slot_of(state.delegate_slot, "delegate_slot")?,
+        burnt: state.burnt_slot.is_some(),
+        burnt_slot: slot_of(state.burnt_slot, "burnt_slot")?,
+    })
+}
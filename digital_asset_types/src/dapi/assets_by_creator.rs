@@ -17,10 +17,11 @@ pub async fn get_assets_by_creator(
     page: Option<u64>,
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
+    cursor: Option<String>,
     transform: &AssetTransform,
     enable_grand_total_query: bool,
 ) -> Result<AssetList, DbErr> {
-    let pagination = create_pagination(before, after, page)?;
+    let pagination = create_pagination(before, after, page, cursor)?;
     let (sort_direction, sort_column) = create_sorting(sorting);
     let (assets, grand_total) = scopes::asset::get_by_creator(
         db,
@@ -31,6 +32,8 @@ pub async fn get_assets_by_creator(
         &pagination,
         limit,
         enable_grand_total_query,
+        transform.network.clone(),
+        &transform.hidden,
     )
     .await?;
     Ok(build_asset_response(
@@ -1,13 +1,24 @@
-use crate::dao::scopes;
+use crate::dao::{scopes, COLLECTION_GROUP_KEY};
 use crate::rpc::filter::AssetSorting;
 use crate::rpc::response::AssetList;
 
 use crate::rpc::transform::AssetTransform;
+use sea_orm::ConnectionTrait;
 use sea_orm::DatabaseConnection;
 use sea_orm::DbErr;
 
 use super::common::create_sorting;
 use super::common::{build_asset_response, create_pagination};
+
+/// Whether any asset is grouped under `group_key`, telling a key nothing indexes apart from a
+/// group that is empty.
+pub async fn is_group_key_indexed(
+    db: &impl ConnectionTrait,
+    group_key: String,
+) -> Result<bool, DbErr> {
+    scopes::asset::group_key_exists(db, group_key).await
+}
+
 pub async fn get_assets_by_group(
     db: &DatabaseConnection,
     group_key: String,
@@ -17,21 +28,27 @@ pub async fn get_assets_by_group(
     page: Option<u64>,
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
+    cursor: Option<String>,
     transform: &AssetTransform,
     enable_grand_total_query: bool,
 ) -> Result<AssetList, DbErr> {
     // TODO: Explore further optimizing the unsorted query
-    let pagination = create_pagination(before, after, page)?;
+    let pagination = create_pagination(before, after, page, cursor)?;
     let (sort_direction, sort_column) = create_sorting(sorting);
+    let require_verified =
+        transform.require_verified_collection && group_key == COLLECTION_GROUP_KEY;
     let (assets, grand_total) = scopes::asset::get_by_grouping(
         db,
         group_key.clone(),
         group_value.clone(),
+        require_verified,
         sort_column,
         sort_direction,
         &pagination,
         limit,
         enable_grand_total_query,
+        transform.network.clone(),
+        &transform.hidden,
     )
     .await?;
 
@@ -0,0 +1,44 @@
+use crate::dao::scopes;
+use crate::rpc::filter::AssetSorting;
+use crate::rpc::response::AssetList;
+
+use crate::rpc::transform::AssetTransform;
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+use super::common::{build_asset_response, create_pagination, create_sorting};
+
+pub async fn get_assets_by_leaf_delegate(
+    db: &DatabaseConnection,
+    delegate: Vec<u8>,
+    sorting: AssetSorting,
+    limit: u64,
+    page: Option<u64>,
+    before: Option<Vec<u8>>,
+    after: Option<Vec<u8>>,
+    cursor: Option<String>,
+    transform: &AssetTransform,
+    enable_grand_total_query: bool,
+) -> Result<AssetList, DbErr> {
+    let pagination = create_pagination(before, after, page, cursor)?;
+    let (sort_direction, sort_column) = create_sorting(sorting);
+    let (assets, grand_total) = scopes::asset::get_by_leaf_delegate(
+        db,
+        delegate,
+        sort_column,
+        sort_direction,
+        &pagination,
+        limit,
+        enable_grand_total_query,
+        transform.network.clone(),
+        &transform.hidden,
+    )
+    .await?;
+    Ok(build_asset_response(
+        assets,
+        limit,
+        grand_total,
+        &pagination,
+        transform,
+    ))
+}
@@ -1,13 +1,14 @@
 use crate::dao::scopes;
 
 use crate::rpc::filter::AssetSorting;
-use crate::rpc::response::AssetList;
+use crate::rpc::response::{AssetError, AssetList, CollectionSummary};
 use crate::rpc::transform::AssetTransform;
 
 use sea_orm::DatabaseConnection;
 use sea_orm::DbErr;
+use std::collections::HashMap;
 
-use super::common::{build_asset_response, create_pagination, create_sorting};
+use super::common::{asset_to_rpc, build_asset_response, create_pagination, create_sorting};
 
 pub async fn get_assets_by_owner(
     db: &DatabaseConnection,
@@ -17,21 +18,46 @@ pub async fn get_assets_by_owner(
     page: Option<u64>,
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
+    cursor: Option<String>,
     transform: &AssetTransform,
     enable_grand_total_query: bool,
 ) -> Result<AssetList, DbErr> {
-    let pagination = create_pagination(before, after, page)?;
+    let pagination = create_pagination(before, after, page, cursor)?;
     let (sort_direction, sort_column) = create_sorting(sort_by);
-    let (assets, grand_total) = scopes::asset::get_assets_by_owner(
-        db,
-        owner_address,
-        sort_column,
-        sort_direction,
-        &pagination,
-        limit,
-        enable_grand_total_query,
-    )
-    .await?;
+    // Hot owners are served from their copy of their assets, unless assets are hidden from the
+    // request, which only the shared table can leave out.
+    let hot = if transform.hidden.is_empty() {
+        scopes::hot_owner::get_hot_owner_assets(
+            db,
+            owner_address.clone(),
+            sort_column,
+            sort_direction.clone(),
+            &pagination,
+            limit,
+            enable_grand_total_query,
+            transform.network.clone(),
+        )
+        .await?
+    } else {
+        None
+    };
+    let (assets, grand_total) = match hot {
+        Some(hot) => hot,
+        None => {
+            scopes::asset::get_assets_by_owner(
+                db,
+                owner_address,
+                sort_column,
+                sort_direction,
+                &pagination,
+                limit,
+                enable_grand_total_query,
+                transform.network.clone(),
+                &transform.hidden,
+            )
+            .await?
+        }
+    };
     Ok(build_asset_response(
         assets,
         limit,
@@ -40,3 +66,58 @@ pub async fn get_assets_by_owner(
         transform,
     ))
 }
+
+/// The owner's assets grouped by collection, largest collections first, each with a sample
+/// asset. Collections are paged by `page`.
+pub async fn get_assets_by_owner_grouped(
+    db: &DatabaseConnection,
+    owner_address: Vec<u8>,
+    limit: u64,
+    page: u64,
+    transform: &AssetTransform,
+) -> Result<AssetList, DbErr> {
+    let groups = scopes::asset::get_owner_collections(
+        db,
+        owner_address,
+        limit,
+        page,
+        transform.network.clone(),
+        &transform.hidden,
+    )
+    .await?;
+    let sample_ids = groups.iter().map(|g| g.sample_id.clone()).collect();
+    let mut samples: HashMap<_, _> = scopes::asset::get_by_ids(db, sample_ids)
+        .await?
+        .into_iter()
+        .map(|a| (a.asset.id.clone(), a))
+        .collect();
+
+    let mut collections = Vec::with_capacity(groups.len());
+    let mut errors = Vec::new();
+    for group in groups {
+        let sample = match samples.remove(&group.sample_id) {
+            Some(sample) => sample,
+            None => continue,
+        };
+        let id = bs58::encode(&group.sample_id).into_string();
+        match asset_to_rpc(sample, transform, None) {
+            Ok(sample) => collections.push(CollectionSummary {
+                collection: group.collection,
+                count: group.count as u64,
+                sample,
+            }),
+            Err(e) => errors.push(AssetError {
+                id,
+                error: e.to_string(),
+            }),
+        }
+    }
+    Ok(AssetList {
+        total: collections.len() as u32,
+        limit: limit as u32,
+        page: Some(page as u32),
+        errors,
+        collections: Some(collections),
+        ..Default::default()
+    })
+}
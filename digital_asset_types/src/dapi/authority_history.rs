@@ -0,0 +1,46 @@
+use crate::dao::numeric::{to_u64, NumericError};
+use crate::dao::scopes;
+use crate::dao::sea_orm_active_enums::AuthorityKind;
+use crate::rpc::response::{AuthorityChange, AuthorityHistory};
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_asset_authority_history(
+    db: &DatabaseConnection,
+    id: Vec<u8>,
+    limit: u64,
+    page: Option<u64>,
+) -> Result<AuthorityHistory, DbErr> {
+    let rows = scopes::authority::get_asset_authority_history(db, id.clone(), page, limit).await?;
+    let items = rows
+        .into_iter()
+        .map(
+            |(change, account)| -> Result<AuthorityChange, NumericError> {
+                let kind = match account.as_ref().map(|a| &a.kind) {
+                    Some(AuthorityKind::Wallet) => "wallet",
+                    Some(AuthorityKind::Pda) => "pda",
+                    Some(AuthorityKind::Multisig) => "multisig",
+                    Some(AuthorityKind::Program) => "program",
+                    None => "unresolved",
+                };
+                Ok(AuthorityChange {
+                    authority: bs58::encode(change.authority).into_string(),
+                    kind: kind.to_string(),
+                    owner_program: account
+                        .and_then(|a| a.owner)
+                        .map(|o| bs58::encode(o).into_string()),
+                    slot: to_u64(change.slot_updated, "slot_updated")?,
+                    seq: to_u64(change.seq, "seq")?,
+                })
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(AuthorityHistory {
+        id: bs58::encode(id).into_string(),
+        total: items.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        items,
+    })
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, FromQueryResult};
+use spl_concurrent_merkle_tree::node::empty_node;
+
+use crate::{
+    dao::{merkle_tree, raw_query::RawQuery},
+    rpc::AssetProof,
+};
+
+#[derive(FromQueryResult, Debug)]
+struct CanopyRow {
+    canopy_depth: i32,
+    nodes: Vec<u8>,
+    slot_updated: i64,
+    max_depth: Option<i32>,
+}
+
+#[derive(FromQueryResult, Debug)]
+struct IndexedNode {
+    node_idx: i64,
+    hash: Vec<u8>,
+}
+
+/// How a tree's indexed canopy compares with its indexed nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanopyCheck {
+    pub canopy_depth: u32,
+    /// The slot of the account read the canopy is from.
+    pub slot_updated: i64,
+    pub nodes: usize,
+    /// The node indexes whose canopy hash differs from the indexed node's.
+    pub mismatched: Vec<i64>,
+}
+
+/// The canopy depth of a tree as read from its account, `None` until the tree's account has
+/// been read by discovery or the transformer.
+pub async fn get_canopy_depth(
+    conn: &impl ConnectionTrait,
+    tree: Vec<u8>,
+) -> Result<Option<u32>, DbErr> {
+    let tree = merkle_tree::Entity::find_by_id(tree).one(conn).await?;
+    Ok(tree
+        .and_then(|tree| tree.canopy_depth)
+        .and_then(|depth| u32::try_from(depth).ok()))
+}
+
+/// Drops the top `canopy_depth` nodes of the proof, which the program reads from the tree's
+/// canopy, so the proof takes fewer accounts of a transaction.
+pub fn trim_canopy(proof: &mut AssetProof, canopy_depth: u32) {
+    let keep = proof.proof.len().saturating_sub(canopy_depth as usize);
+    proof.proof.truncate(keep);
+}
+
+// Node indexes count from the root at 1, so the node at index i is floor(log2(i)) levels below
+// the root.
+fn node_level(max_depth: u32, node_idx: i64) -> u32 {
+    max_depth.saturating_sub(63 - node_idx.leading_zeros())
+}
+
+/// Compares the canopy last read from a tree's account with the tree's indexed nodes, without
+/// reading the chain. The canopy holds the nodes from index 2 on, and both the canopy's zeroed
+/// nodes and the nodes never indexed are empty nodes. Returns `None` for trees without an
+/// indexed canopy.
+pub async fn check_canopy(
+    conn: &impl ConnectionTrait,
+    tree: Vec<u8>,
+) -> Result<Option<CanopyCheck>, DbErr> {
+    let canopy = CanopyRow::find_by_statement(
+        RawQuery::new(
+            "
+SELECT canopy.canopy_depth, canopy.nodes, canopy.slot_updated, merkle_tree.max_depth
+FROM merkle_tree_canopy canopy
+LEFT JOIN merkle_tree ON merkle_tree.tree = canopy.tree
+WHERE canopy.tree = $1
+",
+        )
+        .bind(tree.clone())
+        .build(),
+    )
+    .one(conn)
+    .await?;
+    let canopy = match canopy {
+        Some(canopy) => canopy,
+        None => return Ok(None),
+    };
+    let max_depth = canopy
+        .max_depth
+        .and_then(|depth| u32::try_from(depth).ok())
+        .ok_or_else(|| DbErr::Custom("tree has a canopy but no max depth".to_string()))?;
+    let count = canopy.nodes.len() / 32;
+    let indexed: HashMap<i64, Vec<u8>> = IndexedNode::find_by_statement(
+        RawQuery::new(
+            "SELECT node_idx, hash FROM cl_items WHERE tree = $1 AND node_idx >= 2 AND node_idx < $2",
+        )
+        .bind(tree)
+        .bind(count as i64 + 2)
+        .build(),
+    )
+    .all(conn)
+    .await?
+    .into_iter()
+    .map(|node| (node.node_idx, node.hash))
+    .collect();
+    let mismatched = canopy
+        .nodes
+        .chunks_exact(32)
+        .enumerate()
+        .filter_map(|(i, node)| {
+            let node_idx = i as i64 + 2;
+            let empty = empty_node(node_level(max_depth, node_idx));
+            let canopy_hash = if node.iter().all(|b| *b == 0) {
+                &empty[..]
+            } else {
+                node
+            };
+            let indexed_hash = indexed.get(&node_idx).map_or(&empty[..], Vec::as_slice);
+            (canopy_hash != indexed_hash).then_some(node_idx)
+        })
+        .collect();
+    Ok(Some(CanopyCheck {
+        canopy_depth: u32::try_from(canopy.canopy_depth).unwrap_or_default(),
+        slot_updated: canopy.slot_updated,
+        nodes: count,
+        mismatched,
+    }))
+}
@@ -1,12 +1,15 @@
 use log::debug;
-use sea_orm::sea_query::Expr;
-use sea_orm::{DatabaseConnection, DbBackend};
+use sea_orm::{ConnectionTrait, DatabaseConnection};
 use {
-    crate::dao::asset,
-    crate::dao::cl_items,
-    crate::rpc::AssetProof,
-    sea_orm::{entity::*, query::*, DbErr, FromQueryResult},
-    spl_concurrent_merkle_tree::node::empty_node,
+    crate::{
+        dao::raw_query::RawQuery,
+        rpc::{filter::Commitment, AssetProof},
+    },
+    sea_orm::{DbErr, FromQueryResult},
+    spl_concurrent_merkle_tree::{
+        hash::recompute,
+        node::{empty_node, Node},
+    },
 };
 
 #[derive(FromQueryResult, Debug, Default, Clone, Eq, PartialEq)]
@@ -17,65 +20,162 @@ struct SimpleChangeLog {
     seq: i64,
 }
 
+#[derive(FromQueryResult, Debug)]
+struct ProofNode {
+    tree: Vec<u8>,
+    leaf_node_idx: i64,
+    leaf_hash: Vec<u8>,
+    level: i64,
+    node_idx: i64,
+    hash: Option<Vec<u8>>,
+    seq: Option<i64>,
+}
+
+#[derive(FromQueryResult, Debug)]
+struct ProofAsset {
+    compressed: bool,
+    tree_id: Option<Vec<u8>>,
+    tree_indexed: bool,
+}
+
+/// Why an asset has no proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingProof {
+    AssetNotFound,
+    NotCompressed,
+    /// The asset's tree has no indexed nodes yet.
+    TreeNotIndexed(Vec<u8>),
+    /// The tree is indexed but the asset's leaf or part of its path is missing.
+    LeafNotIndexed(Vec<u8>),
+}
+
+/// Tells apart the reasons [get_proof_for_asset] can find no proof, for callers to report.
+pub async fn get_missing_proof(
+    db: &impl ConnectionTrait,
+    asset_id: Vec<u8>,
+) -> Result<MissingProof, DbErr> {
+    let asset = ProofAsset::find_by_statement(
+        RawQuery::new(
+            "
+SELECT compressed, tree_id,
+    EXISTS (SELECT 1 FROM cl_items WHERE cl_items.tree = asset.tree_id) AS tree_indexed
+FROM asset
+WHERE id = $1
+",
+        )
+        .bind(asset_id)
+        .build(),
+    )
+    .one(db)
+    .await?;
+    Ok(match asset {
+        None => MissingProof::AssetNotFound,
+        Some(ProofAsset {
+            compressed: true,
+            tree_id: Some(tree),
+            tree_indexed,
+        }) => {
+            if tree_indexed {
+                MissingProof::LeafNotIndexed(tree)
+            } else {
+                MissingProof::TreeNotIndexed(tree)
+            }
+        }
+        Some(_) => MissingProof::NotCompressed,
+    })
+}
+
+const CONFIRMED_PROOF_QUERY: &str = "
+SELECT leaf.tree, leaf.node_idx AS leaf_node_idx, leaf.hash AS leaf_hash,
+    path.level - 1 AS level, path.node_idx, node.hash, node.seq
+FROM asset
+JOIN cl_items leaf
+    ON leaf.tree = asset.tree_id AND leaf.leaf_idx = asset.nonce AND leaf.level = 0
+CROSS JOIN LATERAL unnest(leaf.proof_path) WITH ORDINALITY AS path (node_idx, level)
+LEFT JOIN cl_items node ON node.tree = leaf.tree AND node.node_idx = path.node_idx
+WHERE asset.id = $1
+ORDER BY path.level
+";
+
+// Every node is the latest of its versions in `cl_items` and `cl_items_history` written at or
+// before the finalized slot. Nodes without a slot were written before slots were recorded and
+// are finalized, and without a finalized slot every node is.
+const FINALIZED_PROOF_QUERY: &str = "
+WITH finalized AS (
+    SELECT COALESCE((SELECT slot FROM finalized_slot), 9223372036854775807) AS slot
+)
+SELECT leaf.tree, leaf.node_idx AS leaf_node_idx, leaf_version.hash AS leaf_hash,
+    path.level - 1 AS level, path.node_idx, node.hash, node.seq
+FROM asset
+JOIN cl_items leaf
+    ON leaf.tree = asset.tree_id AND leaf.leaf_idx = asset.nonce AND leaf.level = 0
+CROSS JOIN finalized
+CROSS JOIN LATERAL (
+    SELECT v.hash FROM (
+        SELECT hash, seq, slot FROM cl_items
+        WHERE tree = leaf.tree AND node_idx = leaf.node_idx
+        UNION ALL
+        SELECT hash, seq, slot FROM cl_items_history
+        WHERE tree = leaf.tree AND node_idx = leaf.node_idx
+    ) v
+    WHERE v.slot IS NULL OR v.slot <= finalized.slot
+    ORDER BY v.seq DESC
+    LIMIT 1
+) leaf_version
+CROSS JOIN LATERAL unnest(leaf.proof_path) WITH ORDINALITY AS path (node_idx, level)
+LEFT JOIN LATERAL (
+    SELECT v.hash, v.seq FROM (
+        SELECT hash, seq, slot FROM cl_items
+        WHERE tree = leaf.tree AND node_idx = path.node_idx
+        UNION ALL
+        SELECT hash, seq, slot FROM cl_items_history
+        WHERE tree = leaf.tree AND node_idx = path.node_idx
+    ) v
+    WHERE v.slot IS NULL OR v.slot <= finalized.slot
+    ORDER BY v.seq DESC
+    LIMIT 1
+) node ON true
+WHERE asset.id = $1
+ORDER BY path.level
+";
+
+/// Reads the asset's leaf and the nodes of its proof in one query, following the leaf's
+/// `proof_path` of node indexes. Nodes that were never written are empty nodes. At finalized
+/// commitment the nodes are read as of the finalized slot, and a leaf that wasn't written by
+/// then has no proof.
 pub async fn get_proof_for_asset(
     db: &DatabaseConnection,
     asset_id: Vec<u8>,
+    commitment: Commitment,
 ) -> Result<AssetProof, DbErr> {
-    let sel = cl_items::Entity::find()
-        .join_rev(
-            JoinType::InnerJoin,
-            asset::Entity::belongs_to(cl_items::Entity)
-                .from(asset::Column::Nonce)
-                .to(cl_items::Column::LeafIdx)
-                .into(),
-        )
-        .order_by_desc(cl_items::Column::Seq)
-        .filter(Expr::cust("asset.tree_id = cl_items.tree"))
-        .filter(Expr::cust_with_values(
-            "asset.id = $1::bytea",
-            vec![asset_id],
-        ))
-        .filter(cl_items::Column::Level.eq(0i64));
-    let leaf: Option<cl_items::Model> = sel.one(db).await?;
-    if leaf.is_none() {
-        return Err(DbErr::RecordNotFound("Asset Proof Not Found".to_string()));
-    }
-    let leaf = leaf.unwrap();
-    let req_indexes = get_required_nodes_for_proof(leaf.node_idx);
-    let expected_proof_size = req_indexes.len();
-    let mut final_node_list: Vec<SimpleChangeLog> =
-        vec![SimpleChangeLog::default(); expected_proof_size];
-    let mut query = cl_items::Entity::find()
-        .select_only()
-        .column(cl_items::Column::NodeIdx)
-        .column(cl_items::Column::Hash)
-        .column(cl_items::Column::Level)
-        .column(cl_items::Column::Seq)
-        .column(cl_items::Column::Tree)
-        .filter(cl_items::Column::NodeIdx.is_in(req_indexes.clone()))
-        .filter(cl_items::Column::Tree.eq(leaf.tree.clone()))
-        .order_by_desc(cl_items::Column::NodeIdx)
-        .order_by_desc(cl_items::Column::Id)
-        .order_by_desc(cl_items::Column::Seq)
-        .build(DbBackend::Postgres);
-    query.sql = query
-        .sql
-        .replace("SELECT", "SELECT DISTINCT ON (cl_items.node_idx)");
-    let nodes: Vec<SimpleChangeLog> = db.query_all(query).await.map(|qr| {
-        qr.iter()
-            .map(|q| SimpleChangeLog::from_query_result(q, "").unwrap())
-            .collect()
-    })?;
-    for node in nodes.iter() {
-        if node.level < final_node_list.len().try_into().unwrap() {
-            final_node_list[node.level as usize] = node.to_owned();
-        }
-    }
-    for (i, (n, nin)) in final_node_list.iter_mut().zip(req_indexes).enumerate() {
-        if *n == SimpleChangeLog::default() {
-            *n = make_empty_node(i as i64, nin);
-        }
-    }
+    let query = match commitment {
+        Commitment::Confirmed => CONFIRMED_PROOF_QUERY,
+        Commitment::Finalized => FINALIZED_PROOF_QUERY,
+    };
+    let nodes = ProofNode::find_by_statement(RawQuery::new(query).bind(asset_id).build())
+        .all(db)
+        .await?;
+    // Every row carries the leaf.
+    let (tree, leaf_node_idx, leaf_hash) = match nodes.first() {
+        Some(node) => (
+            node.tree.clone(),
+            node.leaf_node_idx,
+            node.leaf_hash.clone(),
+        ),
+        None => return Err(DbErr::RecordNotFound("Asset Proof Not Found".to_string())),
+    };
+    let mut final_node_list: Vec<SimpleChangeLog> = nodes
+        .into_iter()
+        .map(|node| match (node.hash, node.seq) {
+            (Some(hash), Some(seq)) => SimpleChangeLog {
+                hash,
+                level: node.level,
+                node_idx: node.node_idx,
+                seq,
+            },
+            _ => make_empty_node(node.level, node.node_idx),
+        })
+        .collect();
     for n in final_node_list.iter() {
         debug!(
             "level {} index {} seq {} hash {}",
@@ -87,16 +187,41 @@ pub async fn get_proof_for_asset(
     }
     Ok(AssetProof {
         root: bs58::encode(final_node_list.pop().unwrap().hash).into_string(),
-        leaf: bs58::encode(&leaf.hash).into_string(),
+        leaf: bs58::encode(&leaf_hash).into_string(),
         proof: final_node_list
             .iter()
             .map(|model| bs58::encode(&model.hash).into_string())
             .collect(),
-        node_index: leaf.node_idx,
-        tree_id: bs58::encode(&leaf.tree).into_string(),
+        node_index: leaf_node_idx,
+        tree_id: bs58::encode(&tree).into_string(),
+        valid: None,
+        on_chain_root: None,
     })
 }
 
+/// Whether hashing the leaf up through the proof gives the proof's root.
+pub fn proof_matches_root(proof: &AssetProof) -> bool {
+    fn decode(node: &str) -> Option<Node> {
+        bs58::decode(node).into_vec().ok()?.try_into().ok()
+    }
+
+    let siblings: Option<Vec<Node>> = proof.proof.iter().map(|node| decode(node)).collect();
+    let (leaf, root, siblings) = match (decode(&proof.leaf), decode(&proof.root), siblings) {
+        (Some(leaf), Some(root), Some(siblings)) => (leaf, root, siblings),
+        _ => return false,
+    };
+    // Node indexes count from the root at 1, so leaves start at 2^depth.
+    let depth = siblings.len() as u32;
+    let leaf_index = match u32::try_from(proof.node_index)
+        .ok()
+        .and_then(|idx| idx.checked_sub(1u32.checked_shl(depth)?))
+    {
+        Some(idx) => idx,
+        None => return false,
+    };
+    recompute(leaf, &siblings, leaf_index) == root
+}
+
 fn make_empty_node(lvl: i64, node_index: i64) -> SimpleChangeLog {
     SimpleChangeLog {
         node_idx: node_index,
@@ -0,0 +1,28 @@
+use crate::dao::numeric::to_u64;
+use crate::dao::scopes;
+use crate::rpc::response::CollectionActivityStats;
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_collection_activity_stats(
+    db: &DatabaseConnection,
+    collection: String,
+) -> Result<CollectionActivityStats, DbErr> {
+    let stats = match scopes::collection::get_collection_stats(db, collection.clone()).await? {
+        Some(stats) => stats,
+        None => {
+            return Ok(CollectionActivityStats {
+                collection,
+                ..Default::default()
+            })
+        }
+    };
+    Ok(CollectionActivityStats {
+        collection,
+        mints: to_u64(stats.mints, "mints")?,
+        transfers: to_u64(stats.transfers, "transfers")?,
+        burns: to_u64(stats.burns, "burns")?,
+        last_activity_slot: Some(to_u64(stats.last_activity_slot, "last_activity_slot")?),
+    })
+}
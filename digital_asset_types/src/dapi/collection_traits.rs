@@ -0,0 +1,39 @@
+use crate::dao::scopes;
+use crate::rpc::response::{CollectionTrait, CollectionTraits};
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_collection_traits(
+    db: &DatabaseConnection,
+    collection: String,
+    trait_type: Option<String>,
+    limit: u64,
+    page: Option<u64>,
+) -> Result<CollectionTraits, DbErr> {
+    let asset_count = scopes::collection::get_collection_size(db, collection.clone()).await?;
+    let rows =
+        scopes::collection::get_collection_traits(db, collection.clone(), trait_type, page, limit)
+            .await?;
+    let items: Vec<CollectionTrait> = rows
+        .into_iter()
+        .map(|r| CollectionTrait {
+            trait_type: r.trait_type,
+            value: r.value,
+            count: r.count as u64,
+            frequency: if asset_count > 0 {
+                r.count as f64 / asset_count as f64
+            } else {
+                0.0
+            },
+        })
+        .collect();
+    Ok(CollectionTraits {
+        collection,
+        asset_count,
+        total: items.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        items,
+    })
+}
@@ -1,13 +1,15 @@
-use crate::dao::sea_orm_active_enums::SpecificationVersions;
+use crate::dao::sea_orm_active_enums::{RoyaltyTargetType, SpecificationVersions};
 use crate::dao::FullAsset;
-use crate::dao::Pagination;
+use crate::dao::COLLECTION_GROUP_KEY;
 use crate::dao::{asset, asset_authority, asset_creators, asset_data, asset_grouping};
+use crate::dao::{cursor::AssetCursor, Pagination};
+use crate::json::{truncation_marker, ProbedFile};
 use crate::rpc::filter::{AssetSortBy, AssetSortDirection, AssetSorting};
 use crate::rpc::response::{AssetError, AssetList, TransactionSignatureList};
 use crate::rpc::transform::AssetTransform;
 use crate::rpc::{
-    Asset as RpcAsset, Authority, Compression, Content, Creator, File, Group, Interface,
-    MetadataMap, Ownership, Royalty, Scope, Supply, Uses,
+    Asset as RpcAsset, Authority, Compression, Content, Creator, CreatorRoyalty, File, Group,
+    Interface, MetadataMap, Ownership, Royalty, Scope, Supply, Uses,
 };
 use jsonpath_lib::JsonPathError;
 use log::warn;
@@ -15,8 +17,9 @@ use mime_guess::Mime;
 
 use sea_orm::DbErr;
 use serde_json::Value;
+use solana_sdk::keccak::hashv;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use url::Url;
 
@@ -40,6 +43,7 @@ pub fn file_from_str(str: String) -> File {
     File {
         uri: Some(str),
         cdn_uri: None,
+        preview_uri: None,
         mime: Some(mime),
         quality: None,
         contexts: None,
@@ -61,17 +65,26 @@ pub fn build_asset_response(
             (None, bef, aft)
         }
         Pagination::Page { page } => (Some(*page), None, None),
+        Pagination::Cursor { .. } => (None, None, None),
+    };
+    // The last page is the first one without assets.
+    let cursor = match pagination {
+        Pagination::Cursor { .. } => assets.last().map(|a| AssetCursor::of(&a.asset).encode()),
+        _ => None,
     };
     let (items, errors) = asset_list_to_rpc(assets, transform);
     AssetList {
-        // grand_total: grand_total.unwrap(), // TODO
+        grand_total,
         total,
         limit: limit as u32,
         page: page.map(|x| x as u32),
         before,
         after,
+        cursor,
         items,
         errors,
+        collections: None,
+        native_balance: None,
     }
 }
 
@@ -88,6 +101,7 @@ pub fn build_transaction_signatures_response(
             (None, bef, aft)
         }
         Pagination::Page { page } => (Some(*page), None, None),
+        Pagination::Cursor { .. } => (None, None, None),
     };
     TransactionSignatureList {
         total,
@@ -117,7 +131,16 @@ pub fn create_pagination(
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
     page: Option<u64>,
+    cursor: Option<String>,
 ) -> Result<Pagination, DbErr> {
+    if let Some(cursor) = cursor {
+        if before.is_some() || after.is_some() || page.is_some() {
+            return Err(DbErr::Custom("Invalid Pagination".to_string()));
+        }
+        return Ok(Pagination::Cursor {
+            cursor: AssetCursor::parse(&cursor)?,
+        });
+    }
     match (&before, &after, &page) {
         (_, _, None) => Ok(Pagination::Keyset {
             before: before.map(|x| x.into()),
@@ -143,6 +166,29 @@ pub fn track_top_level_file(
     }
 }
 
+/// Replaces the declared or guessed mime of the files the ingester probed with the one their host
+/// served, and adds the preview the thumbnailer rendered for them.
+pub fn apply_probed_files(files: &mut [File], probed: Option<&Value>) {
+    let probed: Vec<ProbedFile> = match probed.and_then(|p| serde_json::from_value(p.clone()).ok())
+    {
+        Some(probed) => probed,
+        None => return,
+    };
+    for file in files.iter_mut() {
+        let found = probed
+            .iter()
+            .find(|p| file.uri.as_deref() == Some(p.uri.as_str()));
+        if let Some(p) = found {
+            if p.mime.is_some() {
+                file.mime = p.mime.clone();
+            }
+            if p.preview_uri.is_some() {
+                file.preview_uri = p.preview_uri.clone();
+            }
+        }
+    }
+}
+
 pub fn safe_select<'a>(
     selector: &mut impl FnMut(&str) -> Result<Vec<&'a Value>, JsonPathError>,
     expr: &str,
@@ -235,6 +281,7 @@ pub fn v1_content_from_json(
                                 File {
                                     uri: Some(str_uri.to_string()),
                                     cdn_uri: None,
+                                    preview_uri: None,
                                     mime: Some(str_mime.to_string()),
                                     quality: None,
                                     contexts: None,
@@ -278,6 +325,8 @@ pub fn v1_content_from_json(
         _ => Ordering::Equal,
     });
 
+    apply_probed_files(&mut files, asset_data.files.as_ref());
+
     // Enrich files with CDN for images (optional).
     if let Some(cdn_prefix) = &cdn_prefix {
         // Use default options for now.
@@ -303,6 +352,7 @@ pub fn v1_content_from_json(
         files: Some(files),
         metadata: meta,
         links: Some(links),
+        metadata_truncated: truncation_marker(metadata).map(|_| true),
     })
 }
 
@@ -321,6 +371,32 @@ pub fn get_content(
     }
 }
 
+/// Rewrites the file URIs and links of `content` with the transform's URL rewrites. The CDN URIs
+/// of images keep pointing at the original files.
+pub fn rewrite_content_urls(content: &mut Content, transform: &AssetTransform) {
+    if transform.url_rewrites.is_empty() {
+        return;
+    }
+    if let Some(files) = content.files.as_mut() {
+        for file in files.iter_mut() {
+            if let Some(uri) = file
+                .uri
+                .as_deref()
+                .and_then(|uri| transform.rewrite_url(uri))
+            {
+                file.uri = Some(uri);
+            }
+        }
+    }
+    if let Some(links) = content.links.as_mut() {
+        for link in links.values_mut() {
+            if let Some(uri) = link.as_str().and_then(|uri| transform.rewrite_url(uri)) {
+                *link = Value::String(uri);
+            }
+        }
+    }
+}
+
 pub fn to_authority(authority: Vec<asset_authority::Model>) -> Vec<Authority> {
     authority
         .iter()
@@ -342,6 +418,66 @@ pub fn to_creators(creators: Vec<asset_creators::Model>) -> Vec<Creator> {
         .collect()
 }
 
+/// Whether `creators` are a valid split of the royalty: shares between 0 and 100 adding up to
+/// 100, over distinct addresses. For a compressed asset they must also hash to the leaf's
+/// `creator_hash`, the way Bubblegum hashes the creators of the metadata args.
+pub fn creator_shares_valid(
+    creators: &[asset_creators::Model],
+    creator_hash: Option<&str>,
+) -> bool {
+    let mut addresses = HashSet::new();
+    let mut total = 0;
+    for creator in creators {
+        if !(0..=100).contains(&creator.share) || !addresses.insert(&creator.creator) {
+            return false;
+        }
+        total += creator.share;
+    }
+    if total != 100 {
+        return false;
+    }
+    match creator_hash.map(str::trim).filter(|h| !h.is_empty()) {
+        Some(creator_hash) => bs58::encode(hash_creators(creators)).into_string() == creator_hash,
+        None => true,
+    }
+}
+
+/// The hash Bubblegum stores in a leaf for the creators of its metadata args.
+pub fn hash_creators(creators: &[asset_creators::Model]) -> [u8; 32] {
+    let creator_data: Vec<Vec<u8>> = creators
+        .iter()
+        .map(|c| [c.creator.as_slice(), &[c.verified as u8], &[c.share as u8]].concat())
+        .collect();
+    let creator_data: Vec<&[u8]> = creator_data.iter().map(Vec::as_slice).collect();
+    hashv(&creator_data).to_bytes()
+}
+
+/// Splits `basis_points` between the creators by share. Only the creators royalty model pays the
+/// creators, and only valid shares can be split.
+pub fn royalty_breakdown(
+    royalty_target_type: &RoyaltyTargetType,
+    basis_points: u32,
+    creators: &[asset_creators::Model],
+    shares_valid: bool,
+) -> Vec<CreatorRoyalty> {
+    if *royalty_target_type != RoyaltyTargetType::Creators || !shares_valid {
+        return Vec::new();
+    }
+    creators
+        .iter()
+        .map(|c| {
+            let basis_points = basis_points * c.share as u32 / 100;
+            CreatorRoyalty {
+                address: bs58::encode(&c.creator).into_string(),
+                share: c.share,
+                verified: c.verified,
+                basis_points,
+                percent: (basis_points as f64) * 0.0001,
+            }
+        })
+        .collect()
+}
+
 pub fn to_grouping(groups: Vec<asset_grouping::Model>) -> Result<Vec<Group>, DbErr> {
     fn find_group(model: &asset_grouping::Model) -> Result<Group, DbErr> {
         Ok(Group {
@@ -388,10 +524,27 @@ pub fn asset_to_rpc(
         groups,
     } = asset;
     let rpc_authorities = to_authority(authorities);
+    let creator_shares_valid = (!creators.is_empty())
+        .then(|| creator_shares_valid(&creators, asset.creator_hash.as_deref()));
+    let breakdown = royalty_breakdown(
+        &asset.royalty_target_type,
+        asset.royalty_amount as u32,
+        &creators,
+        creator_shares_valid.unwrap_or(false),
+    );
     let rpc_creators = to_creators(creators);
+    let groups = if transform.require_verified_collection {
+        groups
+            .into_iter()
+            .filter(|g| g.group_key != COLLECTION_GROUP_KEY || g.verified == Some(true))
+            .collect()
+    } else {
+        groups
+    };
     let rpc_groups = to_grouping(groups)?;
     let interface = get_interface(&asset)?;
-    let content = get_content(&asset, &data, transform.cdn_prefix.clone(), raw_data)?;
+    let mut content = get_content(&asset, &data, transform.cdn_prefix.clone(), raw_data)?;
+    rewrite_content_urls(&mut content, transform);
     let mut chain_data_selector_fn = jsonpath_lib::selector(&data.chain_data);
     let chain_data_selector = &mut chain_data_selector_fn;
     let basis_points = safe_select(chain_data_selector, "$.primary_sale_happened")
@@ -426,6 +579,9 @@ pub fn asset_to_rpc(
                 .creator_hash
                 .map(|e| e.trim().to_string())
                 .unwrap_or_default(),
+            tree_seq: None,
+            leaf_seq: None,
+            leaf_consistent: None,
         }),
         grouping: Some(rpc_groups),
         royalty: Some(Royalty {
@@ -435,6 +591,8 @@ pub fn asset_to_rpc(
             basis_points: asset.royalty_amount as u32,
             primary_sale_happened: basis_points,
             locked: false,
+            breakdown,
+            creator_shares_valid,
         }),
         creators: Some(rpc_creators),
         ownership: Ownership {
@@ -446,6 +604,12 @@ pub fn asset_to_rpc(
                 .owner
                 .map(|o| bs58::encode(o).into_string())
                 .unwrap_or("".to_string()),
+            treatment: None,
+            marketplace: None,
+            locked: None,
+            lock_delegate: None,
+            lock_delegate_role: None,
+            parent_asset: None,
         },
         supply: match interface {
             Interface::V1NFT => Some(Supply {
@@ -466,6 +630,9 @@ pub fn asset_to_rpc(
             remaining: u.get("remaining").and_then(|t| t.as_u64()).unwrap_or(0),
         }),
         burnt: asset.burnt,
+        price_info: None,
+        mutated: None,
+        token_info: None,
     })
 }
 
@@ -0,0 +1,52 @@
+use crate::dao::numeric::{to_u64, NumericError};
+use crate::dao::scopes;
+use crate::rpc::response::{MetadataChange, MetadataHistory};
+use crate::rpc::Asset;
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+
+pub async fn get_asset_metadata_history(
+    conn: &impl ConnectionTrait,
+    id: Vec<u8>,
+    limit: u64,
+    page: Option<u64>,
+) -> Result<MetadataHistory, DbErr> {
+    let rows = scopes::data_history::get_asset_data_history(conn, id.clone(), page, limit).await?;
+    let items = rows
+        .into_iter()
+        .map(|version| -> Result<MetadataChange, NumericError> {
+            Ok(MetadataChange {
+                name: version.name,
+                symbol: version.symbol,
+                uri: version.metadata_url,
+                slot: to_u64(version.slot_updated, "slot_updated")?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(MetadataHistory {
+        id: bs58::encode(id).into_string(),
+        total: items.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        items,
+    })
+}
+
+/// Sets `mutated` on the assets, true for those whose name, symbol or metadata uri changed
+/// after they were first indexed.
+pub async fn add_mutated_flag(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let ids = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.id).into_vec().ok())
+        .collect();
+    let mutated = scopes::data_history::get_mutated(conn, ids).await?;
+    for asset in assets.iter_mut() {
+        let id = bs58::decode(&asset.id).into_vec().unwrap_or_default();
+        asset.mutated = Some(mutated.contains(&id));
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::dao::numeric::{to_u64, NumericError};
+use crate::dao::scopes;
+use crate::rpc::response::{NftEdition, NftEditions};
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_nft_editions(
+    db: &DatabaseConnection,
+    master_edition: Vec<u8>,
+    limit: u64,
+    page: Option<u64>,
+) -> Result<NftEditions, DbErr> {
+    let master = scopes::edition::get_master_edition(db, master_edition.clone())
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound("Master Edition Not Found".to_string()))?;
+    let data = master.data.unwrap_or_default();
+    let supply = data.get("supply").and_then(|s| s.as_u64()).unwrap_or(0);
+    let max_supply = data.get("max_supply").and_then(|s| s.as_u64());
+
+    let rows = scopes::edition::get_editions(db, master_edition.clone(), page, limit).await?;
+    let editions = rows
+        .into_iter()
+        .map(|row| -> Result<NftEdition, NumericError> {
+            Ok(NftEdition {
+                mint: row.mint.map(|m| bs58::encode(m).into_string()),
+                edition_address: bs58::encode(row.edition.id).into_string(),
+                edition: to_u64(row.edition.edition, "edition")?,
+                owner: row.owner.map(|o| bs58::encode(o).into_string()),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(NftEditions {
+        master_edition_address: bs58::encode(master_edition).into_string(),
+        supply,
+        max_supply,
+        total: editions.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        editions,
+    })
+}
@@ -0,0 +1,33 @@
+use crate::dao::scopes;
+use crate::rpc::{Asset, OwnershipTreatment};
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+use std::collections::HashSet;
+
+/// Marks the assets owned by a registered marketplace escrow as escrowed, so the owner shown is
+/// known to be holding the asset for a listing rather than having bought it.
+pub async fn add_escrow_treatment(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let owners = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.ownership.owner).into_vec().ok())
+        .collect::<HashSet<_>>();
+    let marketplaces =
+        scopes::escrow::get_escrow_marketplaces(conn, owners.into_iter().collect()).await?;
+    if marketplaces.is_empty() {
+        return Ok(());
+    }
+    for asset in assets.iter_mut() {
+        let owner = bs58::decode(&asset.ownership.owner)
+            .into_vec()
+            .unwrap_or_default();
+        if let Some(marketplace) = marketplaces.get(&owner) {
+            asset.ownership.treatment = Some(OwnershipTreatment::Escrowed);
+            asset.ownership.marketplace = Some(marketplace.clone());
+        }
+    }
+    Ok(())
+}
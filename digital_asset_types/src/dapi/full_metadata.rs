@@ -0,0 +1,58 @@
+use crate::dao::{asset_data, asset_data_full_metadata};
+use crate::dapi::common::{rewrite_content_urls, v1_content_from_json};
+use crate::rpc::transform::AssetTransform;
+use crate::rpc::Asset;
+
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use std::collections::HashMap;
+
+/// Rebuilds the content of the assets served with truncated metadata from the metadata as it
+/// was downloaded, kept aside by the ingester.
+pub async fn add_full_metadata(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+    transform: &AssetTransform,
+) -> Result<(), DbErr> {
+    let ids = assets
+        .iter()
+        .filter(|a| {
+            a.content
+                .as_ref()
+                .and_then(|c| c.metadata_truncated)
+                .unwrap_or(false)
+        })
+        .filter_map(|a| bs58::decode(&a.id).into_vec().ok())
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let full: HashMap<Vec<u8>, serde_json::Value> = asset_data_full_metadata::Entity::find()
+        .filter(asset_data_full_metadata::Column::Id.is_in(ids))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|m| (m.id, m.metadata))
+        .collect();
+    if full.is_empty() {
+        return Ok(());
+    }
+    let data = asset_data::Entity::find()
+        .filter(asset_data::Column::Id.is_in(full.keys().cloned().collect::<Vec<_>>()))
+        .all(conn)
+        .await?;
+    let mut contents = HashMap::new();
+    for mut data in data {
+        if let Some(metadata) = full.get(&data.id) {
+            data.metadata = metadata.clone();
+            let mut content = v1_content_from_json(&data, transform.cdn_prefix.clone(), None)?;
+            rewrite_content_urls(&mut content, transform);
+            contents.insert(bs58::encode(&data.id).into_string(), content);
+        }
+    }
+    for asset in assets.iter_mut() {
+        if let Some(content) = contents.remove(&asset.id) {
+            asset.content = Some(content);
+        }
+    }
+    Ok(())
+}
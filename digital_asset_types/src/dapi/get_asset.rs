@@ -1,8 +1,11 @@
 use sea_orm::{DatabaseConnection, DbErr};
 
 use crate::{
-    dao::scopes,
-    rpc::{transform::AssetTransform, Asset},
+    dao::{numeric::to_i64, scopes, FullAsset, COLLECTION_GROUP_KEY},
+    rpc::{
+        transform::{AssetTransform, HiddenAssets},
+        Asset,
+    },
 };
 
 use super::common::asset_to_rpc;
@@ -14,5 +17,42 @@ pub async fn get_asset(
     raw_data: Option<bool>,
 ) -> Result<Asset, DbErr> {
     let asset = scopes::asset::get_by_id(db, id, false).await?;
+    let off_network =
+        matches!(&transform.network, Some(network) if asset.asset.network != *network);
+    if off_network || is_hidden(&asset, &transform.hidden) {
+        return Err(DbErr::RecordNotFound("Asset Not Found".to_string()));
+    }
     asset_to_rpc(asset, transform, raw_data)
 }
+
+/// The id of the asset at `leaf_index` of `tree`, which `getAsset` then serves.
+pub async fn get_asset_id_by_leaf(
+    db: &DatabaseConnection,
+    tree: Vec<u8>,
+    leaf_index: u64,
+) -> Result<Vec<u8>, DbErr> {
+    scopes::asset::get_id_by_leaf(db, tree, to_i64(leaf_index, "leaf_index")?)
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound("Asset Not Found".to_string()))
+}
+
+/// The id of the asset whose leaf hashes to `hash`, which `getAsset` then serves.
+pub async fn get_asset_id_by_leaf_hash(
+    db: &DatabaseConnection,
+    hash: Vec<u8>,
+) -> Result<Vec<u8>, DbErr> {
+    scopes::asset::get_id_by_leaf_hash(db, hash)
+        .await?
+        .ok_or_else(|| DbErr::RecordNotFound("Asset Not Found".to_string()))
+}
+
+/// Whether `asset` is in one of the collections or trees `hidden`, which are answered as if they
+/// didn't exist.
+pub fn is_hidden(asset: &FullAsset, hidden: &HiddenAssets) -> bool {
+    let in_tree = matches!(&asset.asset.tree_id, Some(tree) if hidden.hides_tree(tree));
+    in_tree
+        || asset.groups.iter().any(|g| {
+            g.group_key == COLLECTION_GROUP_KEY
+                && matches!(&g.group_value, Some(c) if hidden.hides_collection(c))
+        })
+}
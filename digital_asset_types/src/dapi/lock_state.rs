@@ -0,0 +1,38 @@
+use crate::dao::{scopes, sea_orm_active_enums::TokenRecordState};
+use crate::rpc::{Asset, Interface};
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+
+/// Sets the lock state of the programmable NFTs among the assets from the token record of the
+/// token account holding them, with the delegate that locked them. Assets whose record isn't
+/// indexed are left unset.
+pub async fn add_lock_state(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let mints = assets
+        .iter()
+        .filter(|a| a.interface == Interface::ProgrammableNFT)
+        .filter_map(|a| bs58::decode(&a.id).into_vec().ok())
+        .collect::<Vec<_>>();
+    let records = scopes::token::get_token_records(conn, mints).await?;
+    if records.is_empty() {
+        return Ok(());
+    }
+    for asset in assets.iter_mut() {
+        let id = bs58::decode(&asset.id).into_vec().unwrap_or_default();
+        if let Some(record) = records.get(&id) {
+            let locked = record.state == TokenRecordState::Locked;
+            asset.ownership.locked = Some(locked);
+            if locked {
+                asset.ownership.lock_delegate = record
+                    .delegate
+                    .as_ref()
+                    .map(|d| bs58::encode(d).into_string());
+                asset.ownership.lock_delegate_role = record.delegate_role.clone();
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,17 +1,55 @@
+mod asset_at_slot;
 mod assets_by_authority;
 mod assets_by_creator;
 mod assets_by_group;
+mod assets_by_leaf_delegate;
 mod assets_by_owner;
+mod assets_by_parent_asset;
+mod authority_history;
+mod canopy;
 mod change_logs;
+mod collection_stats;
+mod collection_traits;
 pub mod common;
+mod data_history;
+mod editions;
+mod escrow;
+mod full_metadata;
 mod get_asset;
+mod lock_state;
+mod owner_diff;
+mod parent_assets;
+mod pricing;
 mod search_assets;
 mod signatures_for_asset;
+mod token_balance_history;
+mod token_info;
+mod tree_audit;
+mod tree_freshness;
+pub use asset_at_slot::*;
 pub use assets_by_authority::*;
 pub use assets_by_creator::*;
 pub use assets_by_group::*;
+pub use assets_by_leaf_delegate::*;
 pub use assets_by_owner::*;
+pub use assets_by_parent_asset::*;
+pub use authority_history::*;
+pub use canopy::*;
 pub use change_logs::*;
+pub use collection_stats::*;
+pub use collection_traits::*;
+pub use data_history::*;
+pub use editions::*;
+pub use escrow::*;
+pub use full_metadata::*;
 pub use get_asset::*;
+pub use lock_state::*;
+pub use owner_diff::*;
+pub use parent_assets::*;
+pub use pricing::*;
 pub use search_assets::*;
 pub use signatures_for_asset::*;
+pub use token_balance_history::*;
+pub use token_info::*;
+pub use tree_audit::*;
+pub use tree_freshness::*;
@@ -0,0 +1,48 @@
+use crate::dao::numeric::{to_i64, to_u64, NumericError};
+use crate::dao::scopes;
+use crate::rpc::response::{OwnerDiff, OwnerDiffItem};
+use crate::rpc::transform::HiddenAssets;
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+
+pub async fn get_owner_diff(
+    conn: &impl ConnectionTrait,
+    owner: Vec<u8>,
+    since_slot: u64,
+    limit: u64,
+    page: Option<u64>,
+    hidden: &HiddenAssets,
+) -> Result<OwnerDiff, DbErr> {
+    let changes = scopes::owner_history::get_owner_changes(
+        conn,
+        owner.clone(),
+        to_i64(since_slot, "since_slot")?,
+        page,
+        limit,
+        hidden,
+    )
+    .await?;
+    let total = changes.len() as u32;
+    let (gained, lost): (Vec<_>, Vec<_>) = changes.into_iter().partition(|c| c.gained);
+    let items = |changes: Vec<scopes::owner_history::OwnerChange>| {
+        changes
+            .into_iter()
+            .map(|c| -> Result<OwnerDiffItem, NumericError> {
+                Ok(OwnerDiffItem {
+                    id: bs58::encode(c.asset_id).into_string(),
+                    slot: to_u64(c.slot_updated, "slot_updated")?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+    Ok(OwnerDiff {
+        owner: bs58::encode(owner).into_string(),
+        since_slot,
+        total,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        gained: items(gained)?,
+        lost: items(lost)?,
+    })
+}
@@ -0,0 +1,32 @@
+use crate::dao::scopes;
+use crate::rpc::{Asset, OwnershipModel};
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+use std::collections::HashSet;
+
+/// Marks the assets owned by another asset's PDA as owned by that asset, naming it in
+/// `ownership.parent_asset`. The owner stays the PDA, which is what signs for the asset.
+pub async fn add_parent_assets(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let owners = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.ownership.owner).into_vec().ok())
+        .collect::<HashSet<_>>();
+    let parents = scopes::parent::get_parent_assets(conn, owners.into_iter().collect()).await?;
+    if parents.is_empty() {
+        return Ok(());
+    }
+    for asset in assets.iter_mut() {
+        let owner = bs58::decode(&asset.ownership.owner)
+            .into_vec()
+            .unwrap_or_default();
+        if let Some(parent) = parents.get(&owner) {
+            asset.ownership.ownership_model = OwnershipModel::Asset;
+            asset.ownership.parent_asset = Some(bs58::encode(parent).into_string());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::dao::scopes;
+use crate::rpc::{Asset, PriceInfo};
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+
+/// Attaches the price estimates of the pricing providers to the assets. Assets no provider
+/// has priced are left without price info.
+pub async fn add_price_info(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let ids = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.id).into_vec().ok())
+        .collect();
+    let prices = scopes::pricing::get_prices(conn, ids).await?;
+    if prices.is_empty() {
+        return Ok(());
+    }
+    for asset in assets.iter_mut() {
+        let id = bs58::decode(&asset.id).into_vec().unwrap_or_default();
+        let info: Vec<PriceInfo> = prices
+            .iter()
+            .filter(|p| p.asset_id == id)
+            .map(|p| PriceInfo {
+                provider: p.provider.clone(),
+                price: p.price,
+                currency: p.currency.clone(),
+                updated_at: p.updated_at.to_string(),
+            })
+            .collect();
+        if !info.is_empty() {
+            asset.price_info = Some(info);
+        }
+    }
+    Ok(())
+}
@@ -1,12 +1,15 @@
 use super::common::{build_asset_response, create_pagination, create_sorting};
 use crate::{
     dao::{
-        scopes::{self, asset::add_collection_metadata},
+        scopes::{
+            self,
+            asset::{add_collection_metadata, on_network, visible},
+        },
         SearchAssetsQuery,
     },
     rpc::{filter::AssetSorting, response::AssetList, transform::AssetTransform},
 };
-use sea_orm::{DatabaseConnection, DbErr};
+use sea_orm::{Condition, DatabaseConnection, DbErr};
 
 pub async fn search_assets(
     db: &DatabaseConnection,
@@ -16,13 +19,20 @@ pub async fn search_assets(
     page: Option<u64>,
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
+    cursor: Option<String>,
     transform: &AssetTransform,
     enable_grand_total_query: bool,
     enable_collection_metadata: bool,
 ) -> Result<AssetList, DbErr> {
-    let pagination = create_pagination(before, after, page)?;
+    let pagination = create_pagination(before, after, page, cursor)?;
     let (sort_direction, sort_column) = create_sorting(sorting);
     let (condition, joins) = search_assets_query.conditions()?;
+    // The network and hidden assets apply whatever the condition type, they aren't search
+    // conditions.
+    let condition = visible(
+        on_network(Condition::all().add(condition), transform.network.clone()),
+        &transform.hidden,
+    );
     let (assets, grand_total) = scopes::asset::get_assets_by_condition(
         db,
         condition,
@@ -1,4 +1,4 @@
-use crate::dao::scopes;
+use crate::dao::{scopes, AssetLeafAddress, Pagination};
 
 use crate::rpc::response::TransactionSignatureList;
 use sea_orm::DatabaseConnection;
@@ -16,7 +16,7 @@ pub async fn get_signatures_for_asset(
     before: Option<Vec<u8>>,
     after: Option<Vec<u8>>,
 ) -> Result<TransactionSignatureList, DbErr> {
-    let pagination = create_pagination(before, after, page)?;
+    let pagination = create_pagination(before, after, page, None)?;
     let transactions =
         scopes::asset::get_signatures_for_asset(db, asset_id, tree, leaf_idx, &pagination, limit)
             .await?;
@@ -26,3 +26,39 @@ pub async fn get_signatures_for_asset(
         &pagination,
     ))
 }
+
+/// The latest `limit` signatures of each of `addresses`, in the same order. Assets that aren't
+/// compressed, or aren't indexed yet when addressed by id, have none.
+pub async fn get_signatures_for_asset_batch(
+    db: &DatabaseConnection,
+    addresses: Vec<AssetLeafAddress>,
+    limit: u64,
+) -> Result<Vec<TransactionSignatureList>, DbErr> {
+    let ids = addresses
+        .iter()
+        .filter_map(|address| match address {
+            AssetLeafAddress::Id(id) => Some(id.clone()),
+            AssetLeafAddress::Leaf { .. } => None,
+        })
+        .collect();
+    let asset_leaves = scopes::asset::get_asset_leaves(db, ids).await?;
+    let leaves: Vec<Option<(Vec<u8>, i64)>> = addresses
+        .into_iter()
+        .map(|address| match address {
+            AssetLeafAddress::Id(id) => asset_leaves.get(&id).cloned(),
+            AssetLeafAddress::Leaf { tree, leaf_idx } => Some((tree, leaf_idx)),
+        })
+        .collect();
+    let known: Vec<(Vec<u8>, i64)> = leaves.iter().flatten().cloned().collect();
+    let signatures = scopes::asset::get_signatures_for_leaves(db, &known, limit).await?;
+    let pagination = Pagination::Page { page: 1 };
+    Ok(leaves
+        .into_iter()
+        .map(|leaf| {
+            let items = leaf
+                .and_then(|leaf| signatures.get(&leaf).cloned())
+                .unwrap_or_default();
+            build_transaction_signatures_response(items, limit, &pagination)
+        })
+        .collect())
+}
@@ -0,0 +1,45 @@
+use crate::dao::numeric::{decimal_to_u64, to_i64, to_u64, NumericError};
+use crate::dao::scopes;
+use crate::rpc::response::{TokenBalance, TokenBalanceHistory};
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_token_balance_history(
+    db: &DatabaseConnection,
+    owner: Vec<u8>,
+    mint: Vec<u8>,
+    limit: u64,
+    page: Option<u64>,
+    before_slot: Option<u64>,
+    after_slot: Option<u64>,
+) -> Result<TokenBalanceHistory, DbErr> {
+    let entries = scopes::token::get_token_balance_history(
+        db,
+        owner.clone(),
+        mint.clone(),
+        before_slot.map(|s| to_i64(s, "before_slot")).transpose()?,
+        after_slot.map(|s| to_i64(s, "after_slot")).transpose()?,
+        page,
+        limit,
+    )
+    .await?;
+    let items = entries
+        .into_iter()
+        .map(|e| -> Result<TokenBalance, NumericError> {
+            Ok(TokenBalance {
+                token_account: bs58::encode(e.token_account).into_string(),
+                amount: decimal_to_u64(e.amount, "amount")?,
+                slot: to_u64(e.slot, "slot")?,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TokenBalanceHistory {
+        owner: bs58::encode(owner).into_string(),
+        mint: bs58::encode(mint).into_string(),
+        total: items.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        items,
+    })
+}
@@ -0,0 +1,65 @@
+use crate::dao::{numeric::decimal_to_u64, scopes};
+use crate::rpc::{Asset, TokenInfo};
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+use std::collections::HashMap;
+
+/// Attaches each asset's mint and its owner's balance of it, from the largest of the owner's
+/// token accounts of the mint. Compressed assets and assets whose mint isn't indexed are left
+/// without token info.
+pub async fn add_token_info(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    let mints: Vec<Vec<u8>> = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.id).into_vec().ok())
+        .collect();
+    let tokens = scopes::token::get_tokens(conn, mints.clone()).await?;
+    if tokens.is_empty() {
+        return Ok(());
+    }
+    let owners: Vec<Vec<u8>> = assets
+        .iter()
+        .filter_map(|a| bs58::decode(&a.ownership.owner).into_vec().ok())
+        .collect();
+    let mut accounts = HashMap::new();
+    for account in scopes::token::get_owner_token_accounts(conn, mints, owners).await? {
+        accounts
+            .entry((account.mint.clone(), account.owner.clone()))
+            .or_insert(account);
+    }
+    let tokens: HashMap<Vec<u8>, _> = tokens.into_iter().map(|t| (t.mint.clone(), t)).collect();
+
+    for asset in assets.iter_mut() {
+        let mint = bs58::decode(&asset.id).into_vec().unwrap_or_default();
+        let token = match tokens.get(&mint) {
+            Some(token) => token,
+            None => continue,
+        };
+        let owner = bs58::decode(&asset.ownership.owner)
+            .into_vec()
+            .unwrap_or_default();
+        let account = accounts.get(&(mint, owner));
+        asset.token_info = Some(TokenInfo {
+            balance: match account {
+                Some(account) => decimal_to_u64(account.amount, "amount")?,
+                None => 0,
+            },
+            supply: decimal_to_u64(token.supply, "supply")?,
+            decimals: token.decimals as u8,
+            token_program: bs58::encode(&token.token_program).into_string(),
+            associated_token_address: account.map(|a| bs58::encode(&a.pubkey).into_string()),
+            mint_authority: token
+                .mint_authority
+                .as_ref()
+                .map(|a| bs58::encode(a).into_string()),
+            freeze_authority: token
+                .freeze_authority
+                .as_ref()
+                .map(|a| bs58::encode(a).into_string()),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::dao::numeric::{to_i64, to_u64, NumericError};
+use crate::dao::scopes;
+use crate::rpc::response::{TreeAudit, TreeAuditEntry};
+
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+
+pub async fn get_tree_audit(
+    db: &DatabaseConnection,
+    tree: Vec<u8>,
+    seq_start: u64,
+    seq_end: u64,
+    limit: u64,
+    page: Option<u64>,
+) -> Result<TreeAudit, DbErr> {
+    let rows = scopes::tree::get_tree_audit(
+        db,
+        tree.clone(),
+        to_i64(seq_start, "seq_start")?,
+        to_i64(seq_end, "seq_end")?,
+        page,
+        limit,
+    )
+    .await?;
+    let items = rows
+        .into_iter()
+        .map(|r| -> Result<TreeAuditEntry, NumericError> {
+            Ok(TreeAuditEntry {
+                seq: to_u64(r.seq, "seq")?,
+                leaf_index: r.leaf_idx.map(|i| to_u64(i, "leaf_idx")).transpose()?,
+                tx: r.tx,
+                instruction: r.instruction,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TreeAudit {
+        tree: bs58::encode(tree).into_string(),
+        total: items.len() as u32,
+        limit: limit as u32,
+        page: page.map(|x| x as u32),
+        items,
+    })
+}
@@ -0,0 +1,30 @@
+use crate::dao::scopes;
+use crate::rpc::Asset;
+
+use sea_orm::ConnectionTrait;
+use sea_orm::DbErr;
+
+/// Sets how fresh the compression info of the compressed assets is against their tree: the
+/// tree's latest indexed seq, and the seq and hash of the asset's leaf in the indexed tree.
+pub async fn add_tree_freshness(
+    conn: &impl ConnectionTrait,
+    assets: &mut [Asset],
+) -> Result<(), DbErr> {
+    for asset in assets.iter_mut() {
+        let compression = match asset.compression.as_mut() {
+            Some(compression) if compression.compressed => compression,
+            _ => continue,
+        };
+        let tree = match bs58::decode(&compression.tree).into_vec() {
+            Ok(tree) if !tree.is_empty() => tree,
+            _ => continue,
+        };
+        let freshness = scopes::tree::get_leaf_freshness(conn, tree, compression.leaf_id).await?;
+        compression.tree_seq = freshness.tree_seq;
+        compression.leaf_seq = freshness.leaf_seq;
+        compression.leaf_consistent = freshness
+            .leaf_hash
+            .map(|hash| bs58::encode(hash).into_string() == compression.asset_hash);
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// What the ingester learned about a file of the off-chain metadata by requesting it, kept in
+/// `asset_data.files`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbedFile {
+    pub uri: String,
+    /// The content type the host served, left out when it was missing or generic.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_uri: Option<String>,
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// The key of the marker left in metadata stored truncated.
+pub const TRUNCATED_KEY: &str = "_truncated";
+
+/// The fields `essentials` keeps, the ones the API's content is built from.
+const ESSENTIAL_FIELDS: [&str; 8] = [
+    "name",
+    "symbol",
+    "description",
+    "image",
+    "animation_url",
+    "external_url",
+    "attributes",
+    "properties",
+];
+
+/// How metadata over the size cap is cut down before it's stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationPolicy {
+    /// Drop the largest top level fields until the metadata fits.
+    #[default]
+    DropLargest,
+    /// Only keep the fields content is built from, then drop the largest of them if that's not
+    /// enough.
+    Essentials,
+}
+
+/// The marker left under [TRUNCATED_KEY] in metadata stored truncated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TruncationMarker {
+    pub original_bytes: usize,
+    pub dropped_fields: Vec<String>,
+}
+
+fn json_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|v| v.len()).unwrap_or(0)
+}
+
+/// Cuts `metadata` down to `max_bytes` of serialized JSON following `policy`, or returns `None`
+/// when it already fits. The result carries a [TruncationMarker] under [TRUNCATED_KEY].
+/// Metadata that isn't an object is replaced by the marker alone.
+pub fn truncate_metadata(
+    metadata: &Value,
+    max_bytes: usize,
+    policy: TruncationPolicy,
+) -> Option<Value> {
+    let original_bytes = json_len(metadata);
+    if original_bytes <= max_bytes {
+        return None;
+    }
+    let fields = match metadata {
+        Value::Object(fields) => fields,
+        _ => {
+            let marker = TruncationMarker {
+                original_bytes,
+                dropped_fields: vec![],
+            };
+            return Some(json!({ TRUNCATED_KEY: marker }));
+        }
+    };
+    let mut dropped_fields = vec![];
+    let mut kept = Map::new();
+    for (key, value) in fields {
+        if key == TRUNCATED_KEY
+            || (policy == TruncationPolicy::Essentials && !ESSENTIAL_FIELDS.contains(&key.as_str()))
+        {
+            dropped_fields.push(key.clone());
+        } else {
+            kept.insert(key.clone(), value.clone());
+        }
+    }
+    let mut largest: Vec<(String, usize)> = kept
+        .iter()
+        .map(|(key, value)| (key.clone(), json_len(value)))
+        .collect();
+    largest.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut largest = largest.into_iter();
+    loop {
+        let mut truncated = kept.clone();
+        let marker = TruncationMarker {
+            original_bytes,
+            dropped_fields: dropped_fields.clone(),
+        };
+        truncated.insert(TRUNCATED_KEY.to_string(), json!(marker));
+        let truncated = Value::Object(truncated);
+        match largest.next() {
+            Some((key, _)) if json_len(&truncated) > max_bytes => {
+                // Rebuilt rather than removed in place, removing would reorder the fields.
+                kept = kept.into_iter().filter(|(k, _)| *k != key).collect();
+                dropped_fields.push(key);
+            }
+            _ => return Some(truncated),
+        }
+    }
+}
+
+/// The marker of metadata stored truncated, if it was.
+pub fn truncation_marker(metadata: &Value) -> Option<TruncationMarker> {
+    metadata
+        .get(TRUNCATED_KEY)
+        .and_then(|marker| serde_json::from_value(marker.clone()).ok())
+}
@@ -1,3 +1,7 @@
 mod chain_data;
+mod files;
+mod metadata_cap;
 
 pub use chain_data::*;
+pub use files::*;
+pub use metadata_cap::*;
@@ -4,5 +4,6 @@ pub mod dao;
 pub mod dapi;
 #[cfg(feature = "json_types")]
 pub mod json;
+pub mod metrics_sink;
 #[cfg(feature = "json_types")]
 pub mod rpc;
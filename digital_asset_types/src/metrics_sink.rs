@@ -0,0 +1,210 @@
+use std::{
+    io,
+    net::UdpSocket,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use cadence::MetricSink;
+use log::{info, warn};
+
+// Metrics waiting for the sink's worker, past which they are dropped rather than blocking.
+const QUEUE_CAPACITY: usize = 8192;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+// The largest datagram metrics are batched into, as cadence's buffered sink does.
+const MAX_DATAGRAM_SIZE: usize = 512;
+
+// The queue of the sink started last, the one `flush_metrics` flushes.
+static FLUSHED_QUEUE: Mutex<Option<SyncSender<SinkMessage>>> = Mutex::new(None);
+
+enum SinkMessage {
+    Metric(String),
+    Flush(SyncSender<()>),
+}
+
+/// What the sink lost since its worker last reported, which it reports as metrics of its own.
+#[derive(Default)]
+struct SinkStats {
+    // Metrics dropped because the queue was full.
+    dropped: AtomicU64,
+    // Metrics written to the log because sending their batch failed.
+    logged: AtomicU64,
+}
+
+/// Queues metrics for a worker thread sending them over UDP in batches, like cadence's
+/// `QueuingMetricSink` over a `BufferedUdpMetricSink`, except the queue is bounded, can be
+/// flushed on shutdown, and what it loses is counted. A batch that fails to send is written to
+/// the log, one metric per line, instead. Shared by the API, the ingester and the tools.
+pub struct FlushingMetricSink {
+    queue: Mutex<SyncSender<SinkMessage>>,
+    stats: Arc<SinkStats>,
+}
+
+impl FlushingMetricSink {
+    /// Starts the worker sending to `host`. It reports its losses as `<prefix>.metrics.dropped`
+    /// and `<prefix>.metrics.logged`, with `tags` in statsd's format, e.g. `|#env:prod`.
+    pub fn start(host: (String, u16), prefix: &str, tags: String) -> io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect((host.0.as_str(), host.1))?;
+        socket.set_nonblocking(true)?;
+        let (queue, receiver) = sync_channel(QUEUE_CAPACITY);
+        let stats = Arc::new(SinkStats::default());
+        let worker = SinkWorker {
+            socket,
+            batch: String::new(),
+            stats: stats.clone(),
+            prefix: prefix.to_string(),
+            tags,
+        };
+        thread::Builder::new()
+            .name("metrics-sink".to_string())
+            .spawn(move || worker.run(receiver))?;
+        *FLUSHED_QUEUE.lock().unwrap() = Some(queue.clone());
+        Ok(FlushingMetricSink {
+            queue: Mutex::new(queue),
+            stats,
+        })
+    }
+
+    /// Sends the metrics still queued, waiting for at most `timeout`. Returns whether they were.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let queue = self.queue.lock().unwrap().clone();
+        flush_queue(&queue, timeout)
+    }
+}
+
+impl MetricSink for FlushingMetricSink {
+    fn emit(&self, metric: &str) -> io::Result<usize> {
+        let queued = match self.queue.lock() {
+            Ok(queue) => queue.try_send(SinkMessage::Metric(metric.to_string())),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "metrics queue poisoned",
+                ))
+            }
+        };
+        match queued {
+            Ok(()) => Ok(metric.len()),
+            Err(_) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "metrics queue is full",
+                ))
+            }
+        }
+    }
+}
+
+/// Sends the metrics still queued by the sink started last, waiting for at most `timeout`.
+/// Called on shutdown, as the sink's worker thread doesn't outlive the process.
+pub fn flush_metrics(timeout: Duration) {
+    let queue = match FLUSHED_QUEUE.lock().unwrap().clone() {
+        Some(queue) => queue,
+        None => return,
+    };
+    if !flush_queue(&queue, timeout) {
+        warn!("Timed out flushing metrics on shutdown, the queued metrics are lost");
+    }
+}
+
+fn flush_queue(queue: &SyncSender<SinkMessage>, timeout: Duration) -> bool {
+    let (done, flushed) = sync_channel(1);
+    // Blocks while the queue is full, so every metric queued before is sent first.
+    if queue.send(SinkMessage::Flush(done)).is_err() {
+        return false;
+    }
+    flushed.recv_timeout(timeout).is_ok()
+}
+
+struct SinkWorker {
+    socket: UdpSocket,
+    // Newline separated metrics waiting to be sent as one datagram.
+    batch: String,
+    stats: Arc<SinkStats>,
+    prefix: String,
+    tags: String,
+}
+
+impl SinkWorker {
+    fn run(mut self, receiver: Receiver<SinkMessage>) {
+        let mut last_flush = Instant::now();
+        loop {
+            match receiver.recv_timeout(FLUSH_INTERVAL) {
+                Ok(SinkMessage::Metric(metric)) => self.push(&metric),
+                Ok(SinkMessage::Flush(done)) => {
+                    self.flush();
+                    last_flush = Instant::now();
+                    let _ = done.send(());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
+            if last_flush.elapsed() >= FLUSH_INTERVAL {
+                self.flush();
+                last_flush = Instant::now();
+            }
+        }
+    }
+
+    fn push(&mut self, metric: &str) {
+        if !self.batch.is_empty() && self.batch.len() + 1 + metric.len() > MAX_DATAGRAM_SIZE {
+            self.send_batch();
+        }
+        if !self.batch.is_empty() {
+            self.batch.push('\n');
+        }
+        self.batch.push_str(metric);
+    }
+
+    fn send_batch(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        if let Err(err) = self.socket.send(self.batch.as_bytes()) {
+            warn!(
+                "Error sending metrics, writing them to the log instead: {}",
+                err
+            );
+            for metric in self.batch.lines() {
+                self.stats.logged.fetch_add(1, Ordering::Relaxed);
+                info!(target: "metrics", "{}", metric);
+            }
+        }
+        self.batch.clear();
+    }
+
+    // Reports what was lost since the last flush and sends the batch.
+    fn flush(&mut self) {
+        for (name, count) in [
+            ("dropped", self.stats.dropped.swap(0, Ordering::Relaxed)),
+            ("logged", self.stats.logged.swap(0, Ordering::Relaxed)),
+        ] {
+            if count > 0 {
+                warn!("Metrics sink {}: {}", name, count);
+                let metric = format!("{}.metrics.{}:{}|c{}", self.prefix, name, count, self.tags);
+                self.push(&metric);
+            }
+        }
+        self.send_batch();
+    }
+}
+
+/// Writes metrics to the log, for when no UDP sink could be set up at all.
+pub struct LogMetricSink;
+
+impl MetricSink for LogMetricSink {
+    fn emit(&self, metric: &str) -> io::Result<usize> {
+        info!(target: "metrics", "{}", metric);
+        Ok(metric.len())
+    }
+}
@@ -18,6 +18,12 @@ pub struct AssetProof {
     pub node_index: i64,
     pub leaf: String,
     pub tree_id: String,
+    /// Set when validation is requested: whether the proof hashes up to `root` and, when the
+    /// on-chain tree could be read, `root` is still one of its recent roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_chain_root: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, JsonSchema)]
@@ -109,6 +115,8 @@ pub struct File {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cdn_uri: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub mime: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quality: Option<Quality>,
@@ -153,6 +161,10 @@ pub struct Content {
     pub metadata: MetadataMap,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub links: Option<Links>,
+    /// Set when the off-chain metadata was over the ingester's size cap and is served
+    /// truncated, `fullMetadata` serves it whole.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata_truncated: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -194,6 +206,16 @@ pub struct Compression {
     pub tree: String,
     pub seq: i64,
     pub leaf_id: i64,
+    /// The latest seq indexed for `tree`. Past `seq`, the tree changed after the asset did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tree_seq: Option<i64>,
+    /// The seq of the latest change log indexed for the asset's leaf.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_seq: Option<i64>,
+    /// Whether the tree's indexed leaf at `leaf_id` is `asset_hash`. When it isn't, the asset and
+    /// its tree were indexed at different points and its proof won't verify.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leaf_consistent: Option<bool>,
 }
 
 pub type GroupKey = String;
@@ -247,6 +269,17 @@ impl From<RoyaltyTargetType> for RoyaltyModel {
     }
 }
 
+#[cfg(feature = "sql_types")]
+impl From<RoyaltyModel> for RoyaltyTargetType {
+    fn from(m: RoyaltyModel) -> Self {
+        match m {
+            RoyaltyModel::Creators => RoyaltyTargetType::Creators,
+            RoyaltyModel::Fanout => RoyaltyTargetType::Fanout,
+            RoyaltyModel::Single => RoyaltyTargetType::Single,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Royalty {
     pub royalty_model: RoyaltyModel,
@@ -255,6 +288,24 @@ pub struct Royalty {
     pub basis_points: u32,
     pub primary_sale_happened: bool,
     pub locked: bool,
+    /// Each creator's cut, for assets of the creators royalty model with valid shares.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub breakdown: Vec<CreatorRoyalty>,
+    /// Whether the creators' shares add up to 100 and, for a compressed asset, match the leaf's
+    /// creator hash. Absent for assets without creators.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_shares_valid: Option<bool>,
+}
+
+/// A creator's cut of the royalty, `share` percent of the asset's basis points rounded down as
+/// marketplaces pay it out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreatorRoyalty {
+    pub address: String,
+    pub share: i32,
+    pub verified: bool,
+    pub basis_points: u32,
+    pub percent: f64,
 }
 
 pub type Address = String;
@@ -274,6 +325,9 @@ pub enum OwnershipModel {
     Single,
     #[serde(rename = "token")]
     Token,
+    /// Owned by a PDA of another asset, named in `parent_asset`.
+    #[serde(rename = "asset")]
+    Asset,
 }
 
 impl From<String> for OwnershipModel {
@@ -281,6 +335,7 @@ impl From<String> for OwnershipModel {
         match &*s {
             "single" => OwnershipModel::Single,
             "token" => OwnershipModel::Token,
+            "asset" => OwnershipModel::Asset,
             _ => OwnershipModel::Single,
         }
     }
@@ -297,6 +352,17 @@ impl From<OwnerType> for OwnershipModel {
     }
 }
 
+#[cfg(feature = "sql_types")]
+impl From<OwnershipModel> for OwnerType {
+    fn from(m: OwnershipModel) -> Self {
+        match m {
+            // The owner of record is the parent's PDA, a single owner.
+            OwnershipModel::Single | OwnershipModel::Asset => OwnerType::Single,
+            OwnershipModel::Token => OwnerType::Token,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Ownership {
     pub frozen: bool,
@@ -304,6 +370,28 @@ pub struct Ownership {
     pub delegate: Option<String>,
     pub ownership_model: OwnershipModel,
     pub owner: String,
+    /// Set when the owner is a known marketplace escrow holding the asset for a listing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub treatment: Option<OwnershipTreatment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub marketplace: Option<String>,
+    /// Whether a delegate locked the token of a programmable NFT, e.g. to stake it, only set by
+    /// `getAsset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_delegate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_delegate_role: Option<String>,
+    /// The asset whose PDA owns this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_asset: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OwnershipTreatment {
+    Escrowed,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -372,4 +460,39 @@ pub struct Asset {
     pub supply: Option<Supply>,
     pub mutable: bool,
     pub burnt: bool,
+    /// Price estimates of the pricing providers, only with `showPriceInfo`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_info: Option<Vec<PriceInfo>>,
+    /// Whether the on-chain name, symbol or metadata uri changed after the asset was first
+    /// indexed, only set by `getAsset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mutated: Option<bool>,
+    /// The asset's mint and the owner's balance of it, only set by `searchAssets` and
+    /// `getAssetsByOwner` for assets with an indexed mint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_info: Option<TokenInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenInfo {
+    /// The owner's balance, in base units.
+    pub balance: u64,
+    pub supply: u64,
+    pub decimals: u8,
+    pub token_program: String,
+    /// The owner's token account holding the balance, when it holds any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub associated_token_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint_authority: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freeze_authority: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceInfo {
+    pub provider: String,
+    pub price: f64,
+    pub currency: String,
+    pub updated_at: String,
 }
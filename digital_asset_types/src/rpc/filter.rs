@@ -52,3 +52,13 @@ pub enum SearchConditionType {
     #[serde(rename = "any")]
     Any,
 }
+
+/// The commitment a proof is read at, like the commitment of an RPC request. Proofs at
+/// `finalized` are made of the nodes the tree had as of the last finalized slot.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    #[default]
+    Confirmed,
+    Finalized,
+}
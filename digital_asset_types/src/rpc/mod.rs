@@ -1,6 +1,7 @@
 mod asset;
 
 pub mod filter;
+pub mod request;
 pub mod response;
 pub mod transform;
 
@@ -0,0 +1,380 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::filter::{AssetSorting, Commitment, SearchConditionType};
+use super::{Interface, OwnershipModel, RoyaltyModel};
+
+// The params of the API's methods, as named params. Shared by the API and its client.
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByGroup {
+    pub group_key: String,
+    pub group_value: String,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// Pages by the `cursor` of the previous page, `""` for the first page. Unlike `page`, the
+    /// pages don't shift when assets are written in between. Only for lists sorted by id or
+    /// creation.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByOwner {
+    pub owner_address: String,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Returns a summary per collection instead of the assets, paged by `page`.
+    #[serde(default)]
+    pub group_by_collection: Option<bool>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByParentAsset {
+    /// The asset whose PDAs, such as its token owned escrow, own the assets listed.
+    pub parent_asset: String,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByLeafDelegate {
+    /// The leaf delegate of compressed assets, such as a staking program's authority.
+    pub delegate_address: String,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAsset {
+    pub id: String,
+    #[serde(default)]
+    pub raw_data: Option<bool>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetByLeaf {
+    pub tree: String,
+    pub leaf_index: u64,
+    #[serde(default)]
+    pub raw_data: Option<bool>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetByLeafHash {
+    /// The leaf's hash, base58 encoded as in `getAssetProof`.
+    pub hash: String,
+    #[serde(default)]
+    pub raw_data: Option<bool>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct DisplayOptions {
+    /// Attach the pricing providers' price estimates to the assets as `price_info`.
+    #[serde(default)]
+    pub show_price_info: bool,
+    /// Only show verified collections in the grouping, and leave the assets whose collection
+    /// isn't verified out of `getAssetsByGroup` for a collection.
+    #[serde(default)]
+    pub require_verified_collection: bool,
+    /// Only serve the assets indexed from this network, e.g. `devnet`, instead of the server's
+    /// default.
+    #[serde(default)]
+    pub network: Option<String>,
+    /// Serve the off-chain metadata of the assets whose metadata was over the ingester's size cap
+    /// as it was downloaded, instead of truncated.
+    #[serde(default)]
+    pub full_metadata: bool,
+    /// Count every asset matching the request across all pages as `grand_total`, when the server
+    /// enables the count.
+    #[serde(default)]
+    pub show_grand_total: bool,
+    /// Attach the owner's SOL balance as `nativeBalance`. Only for requests by owner, and only
+    /// when the server has an RPC node to read balances from.
+    #[serde(default)]
+    pub show_native_balance: bool,
+    /// Attach `token_info` to the assets whose mint is indexed, from the mint and the owner's
+    /// token accounts of it.
+    #[serde(default)]
+    pub show_token_info: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetProof {
+    pub id: String,
+    #[serde(default)]
+    pub options: Option<GetAssetProofOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetProofOptions {
+    /// Recompute the root from the proof and compare it with the tree's recent on-chain roots.
+    #[serde(default)]
+    pub validate: bool,
+    /// `finalized` reads the proof as of the last finalized slot, `confirmed` by default.
+    #[serde(default)]
+    pub commitment: Commitment,
+    /// Leave out the top nodes of the proof held by the tree's canopy, as indexed from its
+    /// account. Proofs of trees whose canopy isn't known yet are returned whole.
+    #[serde(default)]
+    pub trim_canopy: bool,
+    /// Only serve the proof of an asset indexed from this network, instead of the server's
+    /// default.
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByCreator {
+    pub creator_address: String,
+    pub only_verified: Option<bool>,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct SearchAssets {
+    pub negate: Option<bool>,
+    pub condition_type: Option<SearchConditionType>,
+    pub interface: Option<Interface>,
+    pub owner_address: Option<String>,
+    pub owner_type: Option<OwnershipModel>,
+    pub creator_address: Option<String>,
+    pub creator_verified: Option<bool>,
+    pub authority_address: Option<String>,
+    pub grouping: Option<(String, String)>,
+    pub delegate: Option<String>,
+    pub frozen: Option<bool>,
+    pub supply: Option<u64>,
+    pub supply_mint: Option<String>,
+    pub compressed: Option<bool>,
+    pub compressible: Option<bool>,
+    pub royalty_target_type: Option<RoyaltyModel>,
+    pub royalty_target: Option<String>,
+    pub royalty_amount: Option<u32>,
+    pub burnt: Option<bool>,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub json_uri: Option<String>,
+    #[serde(default)]
+    pub show_collection_metadata: Option<bool>,
+    #[serde(default)]
+    pub escrowed: Option<bool>,
+    #[serde(default)]
+    pub royalty_amount_min: Option<u32>,
+    #[serde(default)]
+    pub royalty_amount_max: Option<u32>,
+    #[serde(default)]
+    pub royalty_recipient: Option<String>,
+    #[serde(default)]
+    pub creator_share_min: Option<u8>,
+    #[serde(default)]
+    pub creator_share_max: Option<u8>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetsByAuthority {
+    pub authority_address: String,
+    pub sort_by: Option<AssetSorting>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub options: Option<DisplayOptions>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetGrouping {
+    pub group_key: String,
+    pub group_value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetSignaturesForAsset {
+    pub id: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub tree: Option<String>,
+    pub leaf_index: Option<i64>,
+    /// Only serve the signatures of an asset indexed from this network, instead of the server's
+    /// default.
+    #[serde(default)]
+    pub network: Option<String>,
+}
+
+/// An asset by `id`, or by `tree` and `leafIndex`, which work before the asset is indexed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct AssetSignaturesAddress {
+    pub id: Option<String>,
+    pub tree: Option<String>,
+    pub leaf_index: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetSignaturesForAssetBatch {
+    pub assets: Vec<AssetSignaturesAddress>,
+    /// Most recent signatures returned per asset.
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetTokenBalanceHistory {
+    pub owner_address: String,
+    pub mint: String,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub before_slot: Option<u64>,
+    pub after_slot: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetAuthorityHistory {
+    pub id: String,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetMetadataHistory {
+    pub id: String,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetOwnerDiff {
+    pub owner: String,
+    pub since_slot: u64,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetCollectionTraits {
+    pub collection: String,
+    pub trait_type: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetCollectionActivityStats {
+    pub collection: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetAssetAtSlot {
+    pub id: String,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct SimulateCnftTransfer {
+    pub asset_id: String,
+    pub new_owner: String,
+    /// Pays the simulated transaction's fee instead of the owner, for owners without SOL.
+    #[serde(default)]
+    pub fee_payer: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetNftEditions {
+    /// Mint of the master edition.
+    pub mint: String,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct SeqRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct GetTreeAudit {
+    pub tree: String,
+    pub seq_range: SeqRange,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+}
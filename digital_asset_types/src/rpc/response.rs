@@ -22,7 +22,10 @@ pub struct GetGroupingResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
 #[serde(default)]
 pub struct AssetList {
-    // pub grand_total: u64, // TODO: not adding this for now because we aren't committing to the schema
+    /// Every asset matching the request across all pages, only with `showGrandTotal` when the
+    /// server enables the count.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grand_total: Option<u64>,
     pub total: u32,
     pub limit: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -31,9 +34,31 @@ pub struct AssetList {
     pub before: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub after: Option<String>,
+    /// With cursor pagination, the cursor to fetch the page after this one with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
     pub items: Vec<Asset>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub errors: Vec<AssetError>,
+    /// Set instead of `items` when the assets are grouped by collection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collections: Option<Vec<CollectionSummary>>,
+    /// The owner's SOL balance, only with `showNativeBalance`.
+    #[serde(rename = "nativeBalance", skip_serializing_if = "Option::is_none")]
+    pub native_balance: Option<NativeBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+pub struct NativeBalance {
+    pub lamports: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollectionSummary {
+    /// `None` for the assets without a verified collection.
+    pub collection: Option<String>,
+    pub count: u64,
+    pub sample: Asset,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
@@ -49,3 +74,209 @@ pub struct TransactionSignatureList {
     pub after: Option<String>,
     pub items: Vec<Vec<String>>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct TokenBalance {
+    pub token_account: String,
+    pub amount: u64,
+    pub slot: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct TokenBalanceHistory {
+    pub owner: String,
+    pub mint: String,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub items: Vec<TokenBalance>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct TreeAuditEntry {
+    pub seq: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leaf_index: Option<u64>,
+    pub tx: String,
+    pub instruction: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct TreeAudit {
+    pub tree: String,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub items: Vec<TreeAuditEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct AuthorityChange {
+    pub authority: String,
+    /// `wallet`, `pda`, `multisig` or `program`, `unresolved` until the account has been looked at.
+    pub kind: String,
+    /// Program owning the authority account, e.g. the multisig program.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_program: Option<String>,
+    pub slot: u64,
+    pub seq: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct AuthorityHistory {
+    pub id: String,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub items: Vec<AuthorityChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct OwnerDiffItem {
+    pub id: String,
+    /// Slot of the asset's latest owner change.
+    pub slot: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct OwnerDiff {
+    pub owner: String,
+    pub since_slot: u64,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub gained: Vec<OwnerDiffItem>,
+    pub lost: Vec<OwnerDiffItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct MetadataChange {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub slot: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct MetadataHistory {
+    pub id: String,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub items: Vec<MetadataChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct CollectionTrait {
+    pub trait_type: String,
+    pub value: String,
+    pub count: u64,
+    /// Share of the collection's assets with this trait, between 0 and 1.
+    pub frequency: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct CollectionTraits {
+    pub collection: String,
+    pub asset_count: u64,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub items: Vec<CollectionTrait>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct CollectionActivityStats {
+    pub collection: String,
+    pub mints: u64,
+    pub transfers: u64,
+    pub burns: u64,
+    /// Missing until activity of the collection is indexed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity_slot: Option<u64>,
+}
+
+/// An asset as of `slot`. Each part is missing when none of its changes were indexed by then,
+/// e.g. `owner` before the asset was minted and `delegate` before it was first delegated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default, JsonSchema)]
+#[serde(default)]
+pub struct AssetAtSlot {
+    pub id: String,
+    pub slot: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Slot of the transfer to `owner`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_slot: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<String>,
+    /// Slot `delegate` was set at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delegate_slot: Option<u64>,
+    pub burnt: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burnt_slot: Option<u64>,
+}
+
+/// A Bubblegum transfer of a compressed asset to `new_owner`, built with its indexed proof and
+/// simulated against the RPC without signatures.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default, JsonSchema)]
+#[serde(default)]
+pub struct CnftTransferSimulation {
+    pub id: String,
+    pub new_owner: String,
+    /// Whether the transfer went through, in which case the indexed proof is valid against the
+    /// tree as of `slot`.
+    pub valid: bool,
+    /// The root the proof was indexed for.
+    pub root: String,
+    pub slot: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct NftEdition {
+    /// Mint of the print, missing until its metadata account is indexed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint: Option<String>,
+    pub edition_address: String,
+    pub edition: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct NftEditions {
+    pub master_edition_address: String,
+    pub supply: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_supply: Option<u64>,
+    pub total: u32,
+    pub limit: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,
+    pub editions: Vec<NftEdition>,
+}
@@ -1,4 +1,54 @@
+use serde::Deserialize;
+
+/// Serves the URIs starting with `prefix` from `replacement` instead, e.g. to map
+/// `https://arweave.net/` media to a CDN.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct UrlRewrite {
+    pub prefix: String,
+    pub replacement: String,
+}
+
+/// Assets left out of the responses to a request, the ones of private collections and trees the
+/// request isn't authorized for. The default hides nothing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HiddenAssets {
+    /// Base58 collection addresses, as stored in the collection groupings.
+    pub collections: Vec<String>,
+    pub trees: Vec<Vec<u8>>,
+}
+
+impl HiddenAssets {
+    pub fn is_empty(&self) -> bool {
+        self.collections.is_empty() && self.trees.is_empty()
+    }
+
+    pub fn hides_collection(&self, collection: &str) -> bool {
+        self.collections.iter().any(|c| c == collection)
+    }
+
+    pub fn hides_tree(&self, tree: &[u8]) -> bool {
+        self.trees.iter().any(|t| t == tree)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct AssetTransform {
     pub cdn_prefix: Option<String>,
+    /// Applied to the file URIs and links of an asset's content, the first matching prefix wins.
+    pub url_rewrites: Vec<UrlRewrite>,
+    /// Leave collections that aren't verified out of the grouping, including the ones indexed
+    /// before the verified flag was, which can't be told apart from spoofed ones.
+    pub require_verified_collection: bool,
+    /// Only serve the assets indexed from this network, or from every network when `None`.
+    pub network: Option<String>,
+    pub hidden: HiddenAssets,
+}
+
+impl AssetTransform {
+    pub fn rewrite_url(&self, uri: &str) -> Option<String> {
+        self.url_rewrites.iter().find_map(|rewrite| {
+            uri.strip_prefix(&rewrite.prefix)
+                .map(|rest| format!("{}{}", rewrite.replacement, rest))
+        })
+    }
 }
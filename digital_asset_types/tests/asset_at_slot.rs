@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod common;
+
+use std::collections::BTreeMap;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::asset;
+use digital_asset_types::dapi::get_asset_at_slot;
+
+fn state_row(
+    owner: Option<(Pubkey, i64)>,
+    delegate: Option<(Pubkey, i64)>,
+    burnt_slot: Option<i64>,
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("owner", owner.map(|(o, _)| o.to_bytes().to_vec()).into()),
+        ("owner_slot", owner.map(|(_, s)| s).into()),
+        (
+            "delegate",
+            delegate.map(|(d, _)| d.to_bytes().to_vec()).into(),
+        ),
+        ("delegate_slot", delegate.map(|(_, s)| s).into()),
+        ("burnt_slot", burnt_slot.into()),
+    ])
+}
+
+#[tokio::test]
+async fn asset_state_is_rebuilt_from_its_history() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey();
+    let owner = Keypair::new().pubkey();
+    let delegate = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            create_full_asset(id, Keypair::new().pubkey()).asset,
+        ]])
+        .append_query_results(vec![vec![state_row(
+            Some((owner, 120)),
+            Some((delegate, 140)),
+            Some(150),
+        )]])
+        .into_connection();
+
+    let state = get_asset_at_slot(&db, id.to_bytes().to_vec(), 160).await?;
+
+    assert_eq!(state.id, id.to_string());
+    assert_eq!(state.slot, 160);
+    assert_eq!(state.owner, Some(owner.to_string()));
+    assert_eq!(state.owner_slot, Some(120));
+    assert_eq!(state.delegate, Some(delegate.to_string()));
+    assert_eq!(state.delegate_slot, Some(140));
+    assert!(state.burnt);
+    assert_eq!(state.burnt_slot, Some(150));
+
+    let log = format!("{:?}", db.into_transaction_log()[1]);
+    assert!(log.contains("slot_updated <= $2"));
+    assert!(log.contains("BigInt(Some(160))"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn assets_before_their_first_change_have_no_state() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            create_full_asset(id, Keypair::new().pubkey()).asset,
+        ]])
+        .append_query_results(vec![vec![state_row(None, None, None)]])
+        .into_connection();
+
+    let state = get_asset_at_slot(&db, id.to_bytes().to_vec(), 10).await?;
+
+    assert_eq!(state.owner, None);
+    assert_eq!(state.delegate, None);
+    assert!(!state.burnt);
+    assert_eq!(state.burnt_slot, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_assets_are_not_found() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .into_connection();
+
+    let result = get_asset_at_slot(&db, Keypair::new().pubkey().to_bytes().to_vec(), 10).await;
+
+    assert!(matches!(result, Err(DbErr::RecordNotFound(_))));
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{asset, cl_items};
+use digital_asset_types::dapi::{get_asset_id_by_leaf, get_asset_id_by_leaf_hash};
+
+#[tokio::test]
+async fn asset_by_leaf_index() -> Result<(), DbErr> {
+    let (id, tree) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    let asset = asset::Model {
+        tree_id: Some(tree.to_bytes().to_vec()),
+        nonce: Some(7),
+        ..create_full_asset(id, Keypair::new().pubkey()).asset
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset]])
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .into_connection();
+
+    assert_eq!(
+        get_asset_id_by_leaf(&db, tree.to_bytes().to_vec(), 7).await?,
+        id.to_bytes().to_vec()
+    );
+    assert!(matches!(
+        get_asset_id_by_leaf(&db, tree.to_bytes().to_vec(), 8).await,
+        Err(DbErr::RecordNotFound(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn asset_by_leaf_hash_falls_back_to_the_change_logs() -> Result<(), DbErr> {
+    let (id, tree) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    // The asset's leaf is still the one before the change log's.
+    let asset = asset::Model {
+        tree_id: Some(tree.to_bytes().to_vec()),
+        nonce: Some(3),
+        leaf: Some(vec![1; 32]),
+        ..create_full_asset(id, Keypair::new().pubkey()).asset
+    };
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset.clone()]])
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .append_query_results(vec![vec![cl_items::Model {
+            id: 1,
+            tree: tree.to_bytes().to_vec(),
+            node_idx: (1 << 14) + 3,
+            leaf_idx: Some(3),
+            seq: 12,
+            level: 0,
+            hash: vec![2; 32],
+            slot: Some(100),
+        }]])
+        .append_query_results(vec![vec![asset]])
+        .into_connection();
+
+    assert_eq!(
+        get_asset_id_by_leaf_hash(&db, vec![1; 32]).await?,
+        id.to_bytes().to_vec()
+    );
+    assert_eq!(
+        get_asset_id_by_leaf_hash(&db, vec![2; 32]).await?,
+        id.to_bytes().to_vec()
+    );
+
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 4);
+    assert!(format!("{:?}", log[2]).contains("cl_items"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_leaves_belong_to_no_asset() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    assert!(matches!(
+        get_asset_id_by_leaf_hash(&db, vec![0; 32]).await,
+        Err(DbErr::RecordNotFound(_))
+    ));
+    assert!(db.into_transaction_log().is_empty());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::{
+    dapi::{check_canopy, trim_canopy},
+    rpc::AssetProof,
+};
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use spl_concurrent_merkle_tree::node::empty_node;
+
+fn canopy_row(nodes: &[[u8; 32]]) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("canopy_depth", 1i32.into()),
+        ("nodes", nodes.concat().into()),
+        ("slot_updated", 100i64.into()),
+        ("max_depth", Some(2i32).into()),
+    ])
+}
+
+fn node_row(node_idx: i64, hash: [u8; 32]) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("node_idx", node_idx.into()),
+        ("hash", hash.to_vec().into()),
+    ])
+}
+
+#[test]
+fn trimming_drops_the_nodes_held_by_the_canopy() {
+    let mut proof = AssetProof {
+        root: "root".to_string(),
+        proof: vec!["l0".to_string(), "l1".to_string(), "l2".to_string()],
+        node_index: 8,
+        leaf: "leaf".to_string(),
+        tree_id: "tree".to_string(),
+        valid: None,
+        on_chain_root: None,
+    };
+
+    trim_canopy(&mut proof, 2);
+    assert_eq!(proof.proof, vec!["l0".to_string()]);
+
+    // A canopy deeper than the proof leaves nothing to send.
+    trim_canopy(&mut proof, 5);
+    assert!(proof.proof.is_empty());
+}
+
+// A depth 2 tree with a canopy of depth 1, nodes 2 and 3. Node 3 was never written, the
+// canopy holds zeroes for it and the index has no row.
+#[tokio::test]
+async fn canopy_matching_the_indexed_nodes_has_no_mismatches() -> Result<(), DbErr> {
+    let left = [7; 32];
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![canopy_row(&[left, [0; 32]])]])
+        .append_query_results(vec![vec![node_row(2, left)]])
+        .into_connection();
+
+    let check = check_canopy(&db, vec![1; 32]).await?.unwrap();
+
+    assert_eq!(check.canopy_depth, 1);
+    assert_eq!(check.slot_updated, 100);
+    assert_eq!(check.nodes, 2);
+    assert!(check.mismatched.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn canopy_nodes_differing_from_the_index_are_reported() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![canopy_row(&[[7; 32], empty_node(1)])]])
+        .append_query_results(vec![vec![node_row(2, [8; 32]), node_row(3, [9; 32])]])
+        .into_connection();
+
+    let check = check_canopy(&db, vec![1; 32]).await?.unwrap();
+
+    assert_eq!(check.mismatched, vec![2, 3]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn trees_without_a_canopy_have_nothing_to_check() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<BTreeMap<&'static str, Value>>::new()])
+        .into_connection();
+
+    assert_eq!(check_canopy(&db, vec![1; 32]).await?, None);
+    Ok(())
+}
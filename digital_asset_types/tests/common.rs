@@ -6,10 +6,11 @@ use digital_asset_types::{
     dao::{
         asset, asset_authority, asset_creators, asset_data, asset_grouping,
         sea_orm_active_enums::{ChainMutability, Mutability, OwnerType, RoyaltyTargetType},
+        FullAsset,
     },
     json::ChainDataV1,
 };
-use sea_orm::{JsonValue, Set};
+use sea_orm::{DatabaseConnection, JsonValue, Set};
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
 
 #[derive(Clone)]
@@ -85,6 +86,8 @@ pub fn create_asset_data(
             reindex: None,
             raw_name: Some(metadata.name.into_bytes().to_vec().clone()),
             raw_symbol: Some(metadata.symbol.into_bytes().to_vec().clone()),
+            files: None,
+            metadata_blob: None,
         },
     )
 }
@@ -108,6 +111,8 @@ pub fn create_asset(
     royalty_target: Option<Vec<u8>>,
     royalty_amount: i32,
 ) -> (asset::ActiveModel, asset::Model) {
+    // Compressed assets carry their delegate as the leaf delegate too.
+    let leaf_delegate = if compressed { delegate.clone() } else { None };
     (
         asset::ActiveModel {
             id: Set(id.clone()),
@@ -157,10 +162,57 @@ pub fn create_asset(
             owner_delegate_seq: Some(0),
             was_decompressed: false,
             leaf_seq: Some(0),
+            leaf_delegate,
+            network: "mainnet".to_string(),
         },
     )
 }
 
+/// An uncompressed NFT owned by `owner`, with its data and without authorities, creators or
+/// groups. Tests set the fields they check on the returned asset.
+#[allow(dead_code)]
+pub fn create_full_asset(id: Pubkey, owner: Pubkey) -> FullAsset {
+    let metadata = MockMetadataArgs {
+        name: String::from("Test #1"),
+        symbol: String::from("BUBBLE"),
+        uri: Keypair::new().pubkey().to_string(),
+        primary_sale_happened: true,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        creators: vec![],
+        seller_fee_basis_points: 100,
+    };
+    let (_, data) = create_asset_data(metadata, id.to_bytes().to_vec());
+    let (_, asset) = create_asset(
+        id.to_bytes().to_vec(),
+        owner.to_bytes().to_vec(),
+        OwnerType::Single,
+        None,
+        false,
+        1,
+        None,
+        false,
+        false,
+        None,
+        Some(SpecificationVersions::V1),
+        None,
+        None,
+        RoyaltyTargetType::Creators,
+        None,
+        100,
+    );
+    FullAsset {
+        asset,
+        data,
+        authorities: vec![],
+        creators: vec![],
+        groups: vec![],
+    }
+}
+
 pub fn create_asset_creator(
     asset_id: Vec<u8>,
     creator: Vec<u8>,
@@ -231,8 +283,18 @@ pub fn create_asset_grouping(
             id: row_num,
             group_key: "collection".to_string(),
             slot_updated: Some(0),
-            verified: false,
+            verified: Some(false),
             group_info_seq: Some(0),
         },
     )
 }
+
+/// The statements run against a mock `db`, with their values inlined.
+#[allow(dead_code)]
+pub fn logged_sql(db: DatabaseConnection) -> Vec<String> {
+    db.into_transaction_log()
+        .iter()
+        .flat_map(|transaction| transaction.statements())
+        .map(|statement| statement.to_string())
+        .collect()
+}
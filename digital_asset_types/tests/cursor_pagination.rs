@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, DatabaseConnection, MockDatabase, Order};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping,
+    cursor::{AssetCursor, CursorKey},
+    scopes, Pagination,
+};
+use digital_asset_types::rpc::transform::HiddenAssets;
+
+fn asset_at(seconds: i64) -> asset::Model {
+    let created_at = format!(
+        "2023-07-22T04:{:02}:{:02}+00:00",
+        seconds / 60,
+        seconds % 60
+    );
+    asset::Model {
+        created_at: Some(DateTimeWithTimeZone::parse_from_rfc3339(&created_at).unwrap()),
+        ..create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey()).asset
+    }
+}
+
+fn sorted(assets: &[asset::Model], key: CursorKey, direction: &Order) -> Vec<asset::Model> {
+    let mut assets = assets.to_vec();
+    assets.sort_by(|a, b| {
+        let ordering = match key {
+            CursorKey::Id => a.id.cmp(&b.id),
+            CursorKey::Created => a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)),
+        };
+        match direction {
+            Order::Desc => ordering.reverse(),
+            _ => ordering,
+        }
+    });
+    assets
+}
+
+/// A page of the list as the database would return it for `cursor`.
+fn page(
+    assets: &[asset::Model],
+    cursor: &Option<AssetCursor>,
+    key: CursorKey,
+    direction: &Order,
+    limit: usize,
+) -> Vec<asset::Model> {
+    sorted(assets, key, direction)
+        .into_iter()
+        .filter(|asset| {
+            cursor
+                .as_ref()
+                .map_or(true, |cursor| cursor.precedes(asset, key, direction))
+        })
+        .take(limit)
+        .collect()
+}
+
+#[test]
+fn cursors_roundtrip() {
+    let asset = asset_at(0);
+    let cursor = AssetCursor::of(&asset);
+    assert_eq!(AssetCursor::parse(&cursor.encode()).unwrap(), Some(cursor));
+
+    let without_time = AssetCursor {
+        id: asset.id,
+        created_at: None,
+    };
+    assert_eq!(
+        AssetCursor::parse(&without_time.encode()).unwrap(),
+        Some(without_time)
+    );
+}
+
+#[test]
+fn empty_cursors_start_at_the_first_page_and_garbage_is_rejected() {
+    assert_eq!(AssetCursor::parse("").unwrap(), None);
+    for cursor in ["0OIl", "2", &bs58::encode([40u8, 1, 2]).into_string()] {
+        assert!(AssetCursor::parse(cursor).is_err(), "{}", cursor);
+    }
+}
+
+#[test]
+fn cursors_only_page_by_immutable_keys() {
+    assert_eq!(CursorKey::for_sort(None).unwrap(), CursorKey::Id);
+    assert_eq!(
+        CursorKey::for_sort(Some(&asset::Column::CreatedAt)).unwrap(),
+        CursorKey::Created
+    );
+    assert!(CursorKey::for_sort(Some(&asset::Column::SlotUpdated)).is_err());
+}
+
+#[test]
+fn pages_neither_skip_nor_repeat_assets_under_concurrent_inserts() {
+    for key in [CursorKey::Id, CursorKey::Created] {
+        for direction in [Order::Asc, Order::Desc] {
+            // Several assets indexed in the same second, tied on created_at.
+            let original: Vec<_> = (0..40).map(|i| asset_at(i / 3)).collect();
+            let mut live = original.clone();
+            let mut seen = Vec::new();
+            let mut cursor = None;
+            for round in 0.. {
+                let page = page(&live, &cursor, key, &direction, 7);
+                if page.is_empty() {
+                    break;
+                }
+                cursor = page.last().map(AssetCursor::of);
+                seen.extend(page.into_iter().map(|a| a.id));
+                // Assets indexed between two page fetches, before and after the cursor.
+                live.push(asset_at(round));
+                live.push(asset_at(100 + round));
+            }
+
+            let mut unique = seen.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), seen.len(), "{:?} {:?}", key, direction);
+            for asset in &original {
+                assert!(seen.contains(&asset.id), "{:?} {:?}", key, direction);
+            }
+        }
+    }
+}
+
+fn empty_db() -> DatabaseConnection {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .append_query_results(vec![Vec::<asset_data::Model>::new()])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection()
+}
+
+#[tokio::test]
+async fn cursor_pages_resume_after_the_cursor_instead_of_an_offset() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey().to_bytes().to_vec();
+    let cursor = AssetCursor::of(&asset_at(0));
+    let db = empty_db();
+    let (assets, _) = scopes::asset::get_assets_by_owner(
+        &db,
+        owner,
+        Some(asset::Column::CreatedAt),
+        Order::Desc,
+        &Pagination::Cursor {
+            cursor: Some(cursor),
+        },
+        10,
+        false,
+        None,
+        &HiddenAssets::default(),
+    )
+    .await?;
+    assert!(assets.is_empty());
+
+    let sql = logged_sql(db).remove(0);
+    assert!(sql.contains(r#""asset"."created_at" < "#));
+    assert!(sql.contains(r#""asset"."id" < "#));
+    assert!(sql.contains(r#"ORDER BY "asset"."created_at" DESC, "asset"."id" DESC"#));
+    assert!(!sql.contains("OFFSET"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn cursors_reject_mutable_sort_keys() {
+    let owner = Keypair::new().pubkey().to_bytes().to_vec();
+    let result = scopes::asset::get_assets_by_owner(
+        &empty_db(),
+        owner,
+        Some(asset::Column::SlotUpdated),
+        Order::Desc,
+        &Pagination::Cursor { cursor: None },
+        10,
+        false,
+        None,
+        &HiddenAssets::default(),
+    )
+    .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::escrow_owners;
+use digital_asset_types::dapi::{add_escrow_treatment, common::asset_list_to_rpc};
+use digital_asset_types::rpc::{transform::AssetTransform, OwnershipTreatment};
+
+#[tokio::test]
+async fn escrowed_assets_are_tagged() -> Result<(), DbErr> {
+    let escrow = Keypair::new().pubkey();
+    let wallet = Keypair::new().pubkey();
+    let transform = AssetTransform::default();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            create_full_asset(Keypair::new().pubkey(), escrow),
+            create_full_asset(Keypair::new().pubkey(), wallet),
+        ],
+        &transform,
+    );
+    assert!(errors.is_empty());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![escrow_owners::Model {
+            owner: escrow.to_bytes().to_vec(),
+            marketplace: "Tensor".to_string(),
+            created_at: DateTime::from_timestamp(1_690_000_000, 0),
+        }]])
+        .into_connection();
+    add_escrow_treatment(&db, &mut assets).await?;
+
+    assert_eq!(
+        assets[0].ownership.treatment,
+        Some(OwnershipTreatment::Escrowed)
+    );
+    assert_eq!(assets[0].ownership.marketplace, Some("Tensor".to_string()));
+    assert_eq!(assets[1].ownership.treatment, None);
+    assert_eq!(assets[1].ownership.marketplace, None);
+
+    Ok(())
+}
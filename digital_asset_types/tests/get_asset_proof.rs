@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::{
+    dapi::{get_proof_for_asset, proof_matches_root},
+    rpc::filter::Commitment,
+};
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{keccak::hashv, signature::Keypair, signer::Signer};
+use spl_concurrent_merkle_tree::node::empty_node;
+
+fn path_row(
+    tree: &[u8],
+    leaf: &[u8; 32],
+    level: i64,
+    node_idx: i64,
+    hash: Option<[u8; 32]>,
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("tree", tree.to_vec().into()),
+        ("leaf_node_idx", 6i64.into()),
+        ("leaf_hash", leaf.to_vec().into()),
+        ("level", level.into()),
+        ("node_idx", node_idx.into()),
+        ("hash", hash.map(|h| h.to_vec()).into()),
+        ("seq", hash.map(|_| 1i64).into()),
+    ])
+}
+
+// The third leaf of a depth 2 tree, node index 6, whose sibling was never written.
+#[tokio::test]
+async fn proof_fills_unwritten_nodes_with_empty_nodes() -> Result<(), DbErr> {
+    let tree = Keypair::new().pubkey().to_bytes();
+    let leaf = [3; 32];
+    let left = [9; 32];
+    let right = hashv(&[&leaf, &empty_node(0)]).to_bytes();
+    let root = hashv(&[&left, &right]).to_bytes();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            path_row(&tree, &leaf, 0, 7, None),
+            path_row(&tree, &leaf, 1, 2, Some(left)),
+            path_row(&tree, &leaf, 2, 1, Some(root)),
+        ]])
+        .into_connection();
+
+    let proof = get_proof_for_asset(&db, vec![1; 32], Commitment::Confirmed).await?;
+
+    assert_eq!(proof.node_index, 6);
+    assert_eq!(proof.tree_id, bs58::encode(tree).into_string());
+    assert_eq!(proof.root, bs58::encode(root).into_string());
+    assert_eq!(
+        proof.proof,
+        vec![
+            bs58::encode(empty_node(0)).into_string(),
+            bs58::encode(left).into_string(),
+        ]
+    );
+    assert!(proof_matches_root(&proof));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn proof_of_unknown_asset_is_not_found() {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<BTreeMap<&'static str, Value>>::new()])
+        .into_connection();
+
+    let res = get_proof_for_asset(&db, vec![1; 32], Commitment::Confirmed).await;
+    assert!(matches!(res, Err(DbErr::RecordNotFound(_))));
+}
+
+// The leaf was overwritten after the finalized slot, the finalized proof is of its earlier
+// version, read from the node history.
+#[tokio::test]
+async fn finalized_proof_reads_nodes_as_of_the_finalized_slot() -> Result<(), DbErr> {
+    let tree = Keypair::new().pubkey().to_bytes();
+    let finalized_leaf = [4; 32];
+    let left = [9; 32];
+    let right = hashv(&[&finalized_leaf, &empty_node(0)]).to_bytes();
+    let root = hashv(&[&left, &right]).to_bytes();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            path_row(&tree, &finalized_leaf, 0, 7, None),
+            path_row(&tree, &finalized_leaf, 1, 2, Some(left)),
+            path_row(&tree, &finalized_leaf, 2, 1, Some(root)),
+        ]])
+        .into_connection();
+
+    let proof = get_proof_for_asset(&db, vec![1; 32], Commitment::Finalized).await?;
+    assert_eq!(proof.leaf, bs58::encode(finalized_leaf).into_string());
+    assert_eq!(proof.root, bs58::encode(root).into_string());
+    assert!(proof_matches_root(&proof));
+
+    let log = format!("{:?}", db.into_transaction_log()[0]);
+    assert!(log.contains("finalized_slot"));
+    assert!(log.contains("cl_items_history"));
+    Ok(())
+}
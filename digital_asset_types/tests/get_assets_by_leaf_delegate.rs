@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Order};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use blockbuster::token_metadata::state::*;
+use common::*;
+use digital_asset_types::dao::{
+    asset_authority, asset_creators, asset_grouping, scopes,
+    sea_orm_active_enums::{OwnerType, RoyaltyTargetType, SpecificationVersions},
+    Pagination,
+};
+use digital_asset_types::rpc::transform::HiddenAssets;
+
+#[tokio::test]
+async fn get_assets_by_leaf_delegate() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey();
+    let owner = Keypair::new().pubkey();
+    let delegate = Keypair::new().pubkey();
+    let tree = Keypair::new().pubkey();
+
+    let metadata = MockMetadataArgs {
+        name: String::from("Staked #1"),
+        symbol: String::from("BUBBLE"),
+        uri: Keypair::new().pubkey().to_string(),
+        primary_sale_happened: true,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        creators: vec![],
+        seller_fee_basis_points: 100,
+    };
+    let asset_data = create_asset_data(metadata.clone(), id.to_bytes().to_vec());
+    let asset = create_asset(
+        id.to_bytes().to_vec(),
+        owner.to_bytes().to_vec(),
+        OwnerType::Single,
+        Some(delegate.to_bytes().to_vec()),
+        false,
+        1,
+        None,
+        true,
+        false,
+        Some(tree.to_bytes().to_vec()),
+        Some(SpecificationVersions::V1),
+        Some(0_i64),
+        None,
+        RoyaltyTargetType::Creators,
+        None,
+        metadata.seller_fee_basis_points as i32,
+    );
+    assert_eq!(asset.1.leaf_delegate, Some(delegate.to_bytes().to_vec()));
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset.1.clone()]])
+        .append_query_results(vec![vec![asset_data.1.clone()]])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection();
+
+    let (assets, grand_total) = scopes::asset::get_by_leaf_delegate(
+        &db,
+        delegate.to_bytes().to_vec(),
+        None,
+        Order::Asc,
+        &Pagination::Page { page: 1 },
+        10,
+        false,
+        None,
+        &HiddenAssets::default(),
+    )
+    .await?;
+    assert_eq!(grand_total, None);
+    assert_eq!(assets.len(), 1);
+    assert_eq!(assets[0].asset, asset.1);
+    assert_eq!(assets[0].data, asset_data.1);
+
+    // The assets are found by their leaf delegate, not the delegate shared with token accounts.
+    let sql = logged_sql(db);
+    assert!(sql[0].contains(r#"WHERE "asset"."leaf_delegate" = "#));
+
+    Ok(())
+}
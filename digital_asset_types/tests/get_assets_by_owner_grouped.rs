@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod common;
+
+use std::collections::BTreeMap;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{asset_authority, asset_creators};
+use digital_asset_types::dapi::get_assets_by_owner_grouped;
+use digital_asset_types::rpc::transform::AssetTransform;
+
+fn collection_row(
+    collection: Option<String>,
+    count: i64,
+    sample: &[u8],
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("collection", collection.into()),
+        ("count", count.into()),
+        ("sample_id", sample.to_vec().into()),
+    ])
+}
+
+#[tokio::test]
+async fn assets_are_summarized_per_collection() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    let collection = Keypair::new().pubkey();
+    let collected = create_full_asset(Keypair::new().pubkey(), owner);
+    let uncollected = create_full_asset(Keypair::new().pubkey(), owner);
+    let (_, grouping) = create_asset_grouping(collected.asset.id.clone(), collection, 1);
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            collection_row(
+                Some(bs58::encode(collection).into_string()),
+                3,
+                &collected.asset.id,
+            ),
+            collection_row(None, 1, &uncollected.asset.id),
+        ]])
+        .append_query_results(vec![vec![
+            collected.asset.clone(),
+            uncollected.asset.clone(),
+        ]])
+        .append_query_results(vec![vec![collected.data, uncollected.data]])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![vec![grouping]])
+        .into_connection();
+
+    let transform = AssetTransform::default();
+    let list =
+        get_assets_by_owner_grouped(&db, owner.to_bytes().to_vec(), 10, 1, &transform).await?;
+
+    assert!(list.items.is_empty());
+    assert!(list.errors.is_empty());
+    assert_eq!(list.total, 2);
+    let collections = list.collections.unwrap();
+    assert_eq!(
+        collections[0].collection,
+        Some(bs58::encode(collection).into_string())
+    );
+    assert_eq!(collections[0].count, 3);
+    assert_eq!(
+        collections[0].sample.id,
+        bs58::encode(&collected.asset.id).into_string()
+    );
+    assert_eq!(collections[1].collection, None);
+    assert_eq!(collections[1].count, 1);
+    assert_eq!(
+        collections[1].sample.id,
+        bs58::encode(&uncollected.asset.id).into_string()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use digital_asset_types::dao::collection_stats;
+use digital_asset_types::dapi::get_collection_activity_stats;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn get_collection_activity_stats_from_counters() -> Result<(), DbErr> {
+    let collection = Keypair::new().pubkey().to_string();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![collection_stats::Model {
+            collection: collection.clone(),
+            mints: 10,
+            transfers: 4,
+            burns: 1,
+            last_activity_slot: 200,
+            updated_at: DateTime::from_timestamp(1_690_000_000, 0),
+        }]])
+        .into_connection();
+
+    let stats = get_collection_activity_stats(&db, collection.clone()).await?;
+
+    assert_eq!(stats.collection, collection);
+    assert_eq!(stats.mints, 10);
+    assert_eq!(stats.transfers, 4);
+    assert_eq!(stats.burns, 1);
+    assert_eq!(stats.last_activity_slot, Some(200));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn collections_without_activity_have_empty_stats() -> Result<(), DbErr> {
+    let collection = Keypair::new().pubkey().to_string();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<collection_stats::Model>::new()])
+        .into_connection();
+
+    let stats = get_collection_activity_stats(&db, collection.clone()).await?;
+
+    assert_eq!(stats.collection, collection);
+    assert_eq!((stats.mints, stats.transfers, stats.burns), (0, 0, 0));
+    assert_eq!(stats.last_activity_slot, None);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::dao::collection_traits;
+use digital_asset_types::dapi::get_collection_traits;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn get_collection_traits_with_frequencies() -> Result<(), DbErr> {
+    let collection = Keypair::new().pubkey().to_string();
+
+    let entry = |id: i64, trait_type: &str, value: &str, count: i64| collection_traits::Model {
+        id,
+        collection: collection.clone(),
+        trait_type: trait_type.to_string(),
+        value: value.to_string(),
+        count,
+    };
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "num_items",
+            Value::BigInt(Some(4)),
+        )])]])
+        .append_query_results(vec![vec![
+            entry(1, "Background", "Blue", 3),
+            entry(2, "Background", "Red", 1),
+        ]])
+        .into_connection();
+
+    let traits = get_collection_traits(&db, collection.clone(), None, 10, None).await?;
+
+    assert_eq!(traits.collection, collection);
+    assert_eq!(traits.asset_count, 4);
+    assert_eq!(traits.total, 2);
+    assert_eq!(traits.items[0].value, "Blue");
+    assert_eq!(traits.items[0].count, 3);
+    assert_eq!(traits.items[0].frequency, 0.75);
+    assert_eq!(traits.items[1].frequency, 0.25);
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use serde_json::json;
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::sea_orm_active_enums::*;
+use digital_asset_types::dao::{asset_editions, asset_v1_account_attachments};
+use digital_asset_types::dapi::get_nft_editions;
+
+#[tokio::test]
+async fn get_nft_editions_with_mints_and_owners() -> Result<(), DbErr> {
+    let master_edition = Keypair::new().pubkey();
+    let edition_1 = Keypair::new().pubkey();
+    let edition_2 = Keypair::new().pubkey();
+    let mint_1 = Keypair::new().pubkey();
+    let owner_1 = Keypair::new().pubkey();
+
+    let attachment =
+        |id: Vec<u8>,
+         asset_id: Option<Vec<u8>>,
+         attachment_type: V1AccountAttachments,
+         data: serde_json::Value| asset_v1_account_attachments::Model {
+            id,
+            asset_id,
+            attachment_type,
+            initialized: true,
+            data: Some(data),
+            slot_updated: 1,
+        };
+    let edition = |id: Vec<u8>, edition: i64| asset_editions::Model {
+        id,
+        parent: master_edition.to_bytes().to_vec(),
+        edition,
+        slot_updated: 1,
+    };
+
+    let (_, asset_1) = create_asset(
+        mint_1.to_bytes().to_vec(),
+        owner_1.to_bytes().to_vec(),
+        OwnerType::Single,
+        None,
+        false,
+        1,
+        None,
+        false,
+        false,
+        None,
+        Some(SpecificationVersions::V1),
+        None,
+        None,
+        RoyaltyTargetType::Creators,
+        None,
+        0,
+    );
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![attachment(
+            master_edition.to_bytes().to_vec(),
+            None,
+            V1AccountAttachments::MasterEditionV2,
+            json!({ "key": "MasterEditionV2", "supply": 2, "max_supply": 10 }),
+        )]])
+        .append_query_results(vec![vec![
+            edition(edition_1.to_bytes().to_vec(), 1),
+            edition(edition_2.to_bytes().to_vec(), 2),
+        ]])
+        // Only the first print's metadata account has been indexed.
+        .append_query_results(vec![vec![attachment(
+            edition_1.to_bytes().to_vec(),
+            Some(mint_1.to_bytes().to_vec()),
+            V1AccountAttachments::Edition,
+            json!({ "edition": 1 }),
+        )]])
+        .append_query_results(vec![vec![asset_1]])
+        .into_connection();
+
+    let editions = get_nft_editions(&db, master_edition.to_bytes().to_vec(), 10, None).await?;
+
+    assert_eq!(editions.master_edition_address, master_edition.to_string());
+    assert_eq!(editions.supply, 2);
+    assert_eq!(editions.max_supply, Some(10));
+    assert_eq!(editions.total, 2);
+    assert_eq!(editions.editions[0].edition, 1);
+    assert_eq!(editions.editions[0].mint, Some(mint_1.to_string()));
+    assert_eq!(editions.editions[0].owner, Some(owner_1.to_string()));
+    assert_eq!(editions.editions[1].edition_address, edition_2.to_string());
+    assert_eq!(editions.editions[1].mint, None);
+    assert_eq!(editions.editions[1].owner, None);
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod common;
+
+use std::collections::BTreeMap;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    sea_orm_active_enums::{OwnerType, RoyaltyTargetType, SpecificationVersions},
+    AssetLeafAddress,
+};
+use digital_asset_types::dapi::get_signatures_for_asset_batch;
+
+fn signature_row(tree: &[u8], leaf_idx: i64, tx: &str) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("tree", tree.to_vec().into()),
+        ("leaf_idx", leaf_idx.into()),
+        ("tx", tx.to_string().into()),
+        ("instruction", "Transfer".to_string().into()),
+    ])
+}
+
+#[tokio::test]
+async fn signatures_by_id_and_by_leaf() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey().to_bytes().to_vec();
+    let tree = Keypair::new().pubkey().to_bytes().to_vec();
+    let unindexed_tree = Keypair::new().pubkey().to_bytes().to_vec();
+    let (_, asset) = create_asset(
+        id.clone(),
+        Keypair::new().pubkey().to_bytes().to_vec(),
+        OwnerType::Single,
+        None,
+        false,
+        1,
+        None,
+        true,
+        false,
+        Some(tree.clone()),
+        Some(SpecificationVersions::V1),
+        Some(7),
+        None,
+        RoyaltyTargetType::Creators,
+        None,
+        100,
+    );
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset]])
+        .append_query_results(vec![vec![
+            signature_row(&tree, 7, "transfer"),
+            signature_row(&tree, 7, "mint"),
+            signature_row(&unindexed_tree, 0, "mint_to_collection"),
+        ]])
+        .into_connection();
+
+    let lists = get_signatures_for_asset_batch(
+        &db,
+        vec![
+            AssetLeafAddress::Id(id),
+            // Minted, but not indexed as an asset yet.
+            AssetLeafAddress::Leaf {
+                tree: unindexed_tree,
+                leaf_idx: 0,
+            },
+            AssetLeafAddress::Id(Keypair::new().pubkey().to_bytes().to_vec()),
+        ],
+        10,
+    )
+    .await?;
+
+    let txs: Vec<Vec<String>> = lists
+        .iter()
+        .map(|list| list.items.iter().map(|item| item[0].clone()).collect())
+        .collect();
+    assert_eq!(
+        txs,
+        vec![
+            vec!["transfer".to_string(), "mint".to_string()],
+            vec!["mint_to_collection".to_string()],
+            vec![],
+        ]
+    );
+    assert_eq!(lists[0].total, 2);
+    assert_eq!(lists[2].total, 0);
+    Ok(())
+}
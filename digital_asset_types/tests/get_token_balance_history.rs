@@ -0,0 +1,51 @@
+use digital_asset_types::dao::token_account_balances;
+use digital_asset_types::dapi::get_token_balance_history;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn get_token_balance_history_by_owner_and_mint() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    let mint = Keypair::new().pubkey();
+    let token_account = Keypair::new().pubkey();
+
+    let entry = |id: i64, amount: u64, slot: i64| token_account_balances::Model {
+        id,
+        token_account: token_account.to_bytes().to_vec(),
+        owner: owner.to_bytes().to_vec(),
+        mint: mint.to_bytes().to_vec(),
+        amount: amount.into(),
+        slot,
+    };
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            entry(3, u64::MAX, 300),
+            entry(2, 150, 200),
+            entry(1, 100, 100),
+        ]])
+        .into_connection();
+
+    let history = get_token_balance_history(
+        &db,
+        owner.to_bytes().to_vec(),
+        mint.to_bytes().to_vec(),
+        10,
+        None,
+        None,
+        None,
+    )
+    .await?;
+
+    assert_eq!(history.owner, owner.to_string());
+    assert_eq!(history.mint, mint.to_string());
+    assert_eq!(history.total, 3);
+    assert_eq!(history.items[0].token_account, token_account.to_string());
+    // Amounts above i64::MAX are read back as stored rather than wrapped.
+    assert_eq!(history.items[0].amount, u64::MAX);
+    assert_eq!(history.items[1].amount, 150);
+    assert_eq!(history.items[1].slot, 200);
+    assert_eq!(history.items[2].amount, 100);
+
+    Ok(())
+}
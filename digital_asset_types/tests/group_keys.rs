@@ -0,0 +1,29 @@
+#[cfg(test)]
+mod common;
+
+use common::create_asset_grouping;
+use digital_asset_types::dao::{asset_grouping, EDITION_PARENT_GROUP_KEY};
+use digital_asset_types::dapi::is_group_key_indexed;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn group_keys_are_indexed_once_an_asset_is_grouped_under_them() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey().to_bytes().to_vec();
+    let master = Keypair::new().pubkey();
+    let (_, mut grouping) = create_asset_grouping(id, master, 1);
+    grouping.group_key = EDITION_PARENT_GROUP_KEY.to_string();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![grouping], Vec::<asset_grouping::Model>::new()])
+        .into_connection();
+
+    assert!(is_group_key_indexed(&db, EDITION_PARENT_GROUP_KEY.to_string()).await?);
+    assert!(!is_group_key_indexed(&db, "mpl_core_collection".to_string()).await?);
+
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 2);
+    assert!(format!("{:?}", log[1]).contains("mpl_core_collection"));
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Order};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_grouping, hot_owner_assets, hot_owners,
+    scopes::hot_owner::get_hot_owner_assets, Pagination,
+};
+
+fn hot_owner(owner: Pubkey, ready: bool) -> hot_owners::Model {
+    hot_owners::Model {
+        owner: owner.to_bytes().to_vec(),
+        ready,
+        created_at: DateTimeWithTimeZone::parse_from_rfc3339("2023-08-26T00:00:00+00:00").unwrap(),
+    }
+}
+
+fn hot_asset(owner: Pubkey, id: Pubkey) -> hot_owner_assets::Model {
+    hot_owner_assets::Model {
+        owner: owner.to_bytes().to_vec(),
+        asset_id: id.to_bytes().to_vec(),
+        network: "mainnet".to_string(),
+        created_at: None,
+    }
+}
+
+#[tokio::test]
+async fn hot_owner_pages_follow_their_copy() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    let (first, second) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    let (first_asset, second_asset) = (
+        create_full_asset(first, owner),
+        create_full_asset(second, owner),
+    );
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![hot_owner(owner, true)]])
+        .append_query_results(vec![vec![
+            hot_asset(owner, second),
+            hot_asset(owner, first),
+        ]])
+        // The assets come back by id, not in the page's order.
+        .append_query_results(vec![vec![first_asset.asset, second_asset.asset]])
+        .append_query_results(vec![vec![first_asset.data, second_asset.data]])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection();
+
+    let (assets, grand_total) = get_hot_owner_assets(
+        &db,
+        owner.to_bytes().to_vec(),
+        Some(asset::Column::CreatedAt),
+        Order::Desc,
+        &Pagination::Page { page: 2 },
+        2,
+        false,
+        None,
+    )
+    .await?
+    .expect("served from the copy");
+
+    let ids: Vec<_> = assets.iter().map(|a| a.asset.id.clone()).collect();
+    assert_eq!(
+        ids,
+        vec![second.to_bytes().to_vec(), first.to_bytes().to_vec()]
+    );
+    assert_eq!(grand_total, None);
+
+    let sql = logged_sql(db).remove(1);
+    assert!(sql.contains(r#"FROM "hot_owner_assets""#));
+    assert!(sql.contains(r#"ORDER BY "hot_owner_assets"."created_at" DESC"#));
+    assert!(sql.contains("OFFSET"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn owners_not_ready_use_the_asset_table() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    for hot in [vec![], vec![hot_owner(owner, false)]] {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![hot])
+            .into_connection();
+        let page = get_hot_owner_assets(
+            &db,
+            owner.to_bytes().to_vec(),
+            None,
+            Order::Asc,
+            &Pagination::Page { page: 1 },
+            10,
+            false,
+            None,
+        )
+        .await?;
+        assert!(page.is_none());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn sorting_by_update_uses_the_asset_table() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    let page = get_hot_owner_assets(
+        &db,
+        Keypair::new().pubkey().to_bytes().to_vec(),
+        Some(asset::Column::SlotUpdated),
+        Order::Desc,
+        &Pagination::Page { page: 1 },
+        10,
+        false,
+        None,
+    )
+    .await?;
+
+    assert!(page.is_none());
+    assert!(db.into_transaction_log().is_empty());
+    Ok(())
+}
@@ -2,8 +2,11 @@
 use blockbuster::token_metadata::state::TokenStandard as TSBlockbuster;
 use digital_asset_types::dao::asset_data;
 use digital_asset_types::dao::sea_orm_active_enums::{ChainMutability, Mutability};
-use digital_asset_types::dapi::common::v1_content_from_json;
+use digital_asset_types::dapi::common::{
+    apply_probed_files, rewrite_content_urls, v1_content_from_json,
+};
 use digital_asset_types::json::ChainDataV1;
+use digital_asset_types::rpc::transform::{AssetTransform, UrlRewrite};
 use digital_asset_types::rpc::Content;
 use digital_asset_types::rpc::File;
 use solana_sdk::signature::Keypair;
@@ -40,6 +43,8 @@ pub async fn parse_onchain_json(
         reindex: None,
         raw_name: Some(String::from("Handalf  ").into_bytes().to_vec()),
         raw_symbol: Some(String::from("  ").into_bytes().to_vec()),
+        files: None,
+        metadata_blob: None,
     };
 
     v1_content_from_json(&asset_data, cdn_prefix, raw_data).unwrap()
@@ -56,6 +61,7 @@ async fn simple_content() {
             File {
                 uri: Some("https://madlads.s3.us-west-2.amazonaws.com/images/1.png".to_string()),
                 cdn_uri: None,
+                preview_uri: None,
                 mime: Some("image/png".to_string()),
                 quality: None,
                 contexts: None,
@@ -66,6 +72,7 @@ async fn simple_content() {
                         .to_string(),
                 ),
                 cdn_uri: None,
+                preview_uri: None,
                 mime: Some("image/png".to_string()),
                 quality: None,
                 contexts: None,
@@ -142,6 +149,7 @@ async fn simple_content_with_cdn() {
             File {
                 uri: Some("https://madlads.s3.us-west-2.amazonaws.com/images/1.png".to_string()),
                 cdn_uri: Some("https://cdn.foobar.blah//https://madlads.s3.us-west-2.amazonaws.com/images/1.png".to_string()),
+                preview_uri: None,
                 mime: Some("image/png".to_string()),
                 quality: None,
                 contexts: None,
@@ -152,6 +160,7 @@ async fn simple_content_with_cdn() {
                         .to_string(),
                 ),
                 cdn_uri: Some("https://cdn.foobar.blah//https://arweave.net/qJ5B6fx5hEt4P7XbicbJQRyTcbyLaV-OQNA1KjzdqOQ/1.png".to_string()),
+                preview_uri: None,
                 mime: Some("image/png".to_string()),
                 quality: None,
                 contexts: None,
@@ -174,6 +183,7 @@ async fn complex_content() {
                         .to_string(),
                 ),
                 cdn_uri: None,
+                preview_uri: None,
                 mime: Some("image/gif".to_string()),
                 quality: None,
                 contexts: None,
@@ -184,6 +194,7 @@ async fn complex_content() {
                         .to_string()
                 ),
                 cdn_uri: None,
+                preview_uri: None,
                 mime: Some("video/mp4".to_string()),
                 quality: None,
                 contexts: None,
@@ -231,6 +242,7 @@ async fn complex_content_with_cdn() {
                     "https://cdn.foobar.blah//https://arweave.net/_a4sXT6fOHI-5VHFOHLEF73wqKuZtJgE518Ciq9DGyI?ext=gif"
                         .to_string(),
                 ),
+                preview_uri: None,
                 mime: Some("image/gif".to_string()),
                 quality: None,
                 contexts: None,
@@ -238,6 +250,7 @@ async fn complex_content_with_cdn() {
             File {
                 uri: Some("https://arweave.net/HVOJ3bTpqMJJJtd5nW2575vPTekLa_SSDsQc7AqV_Ho?ext=mp4".to_string()),
                 cdn_uri: None,
+                preview_uri: None,
                 mime: Some("video/mp4".to_string()),
                 quality: None,
                 contexts: None,
@@ -245,3 +258,96 @@ async fn complex_content_with_cdn() {
         ])
     )
 }
+
+#[tokio::test]
+async fn complex_content_with_url_rewrites() {
+    let cdn_prefix = Some("https://cdn.foobar.blah".to_string());
+    let j = load_test_json("infinite_fungi.json").await;
+    let mut parsed = parse_onchain_json(j, cdn_prefix, None).await;
+    let transform = AssetTransform {
+        url_rewrites: vec![
+            UrlRewrite {
+                prefix: "https://ipfs.io/ipfs/".to_string(),
+                replacement: "https://media.foobar.blah/ipfs/".to_string(),
+            },
+            UrlRewrite {
+                prefix: "https://arweave.net/".to_string(),
+                replacement: "https://media.foobar.blah/arweave/".to_string(),
+            },
+        ],
+        ..Default::default()
+    };
+    rewrite_content_urls(&mut parsed, &transform);
+    assert_eq!(
+        parsed.files,
+        Some(vec![
+            File {
+                uri: Some(
+                    "https://media.foobar.blah/arweave/_a4sXT6fOHI-5VHFOHLEF73wqKuZtJgE518Ciq9DGyI?ext=gif"
+                        .to_string(),
+                ),
+                cdn_uri: Some(
+                    "https://cdn.foobar.blah//https://arweave.net/_a4sXT6fOHI-5VHFOHLEF73wqKuZtJgE518Ciq9DGyI?ext=gif"
+                        .to_string(),
+                ),
+                preview_uri: None,
+                mime: Some("image/gif".to_string()),
+                quality: None,
+                contexts: None,
+            },
+            File {
+                uri: Some("https://media.foobar.blah/arweave/HVOJ3bTpqMJJJtd5nW2575vPTekLa_SSDsQc7AqV_Ho?ext=mp4".to_string()),
+                cdn_uri: None,
+                preview_uri: None,
+                mime: Some("video/mp4".to_string()),
+                quality: None,
+                contexts: None,
+            },
+        ])
+    );
+    let links = parsed.links.unwrap();
+    assert_eq!(
+        links.get("image").unwrap().as_str().unwrap(),
+        "https://media.foobar.blah/arweave/_a4sXT6fOHI-5VHFOHLEF73wqKuZtJgE518Ciq9DGyI?ext=gif"
+    );
+    assert_eq!(
+        links.get("animation_url").unwrap().as_str().unwrap(),
+        "https://media.foobar.blah/arweave/HVOJ3bTpqMJJJtd5nW2575vPTekLa_SSDsQc7AqV_Ho?ext=mp4"
+    );
+}
+
+#[tokio::test]
+async fn probed_files_override_guessed_mimes() {
+    let mut files = vec![
+        File {
+            uri: Some("https://arweave.net/abc".to_string()),
+            cdn_uri: None,
+            preview_uri: None,
+            mime: Some("image/png".to_string()),
+            quality: None,
+            contexts: None,
+        },
+        File {
+            uri: Some("https://arweave.net/def".to_string()),
+            cdn_uri: None,
+            preview_uri: None,
+            mime: Some("video/mp4".to_string()),
+            quality: None,
+            contexts: None,
+        },
+    ];
+    let probed = serde_json::json!([
+        {"uri": "https://arweave.net/abc", "mime": "image/webp", "size": 1024, "preview_uri": "https://thumbs.foobar.blah/abc"},
+        {"uri": "https://arweave.net/def", "size": 2048},
+    ]);
+    apply_probed_files(&mut files, Some(&probed));
+    assert_eq!(files[0].mime.as_deref(), Some("image/webp"));
+    assert_eq!(
+        files[0].preview_uri.as_deref(),
+        Some("https://thumbs.foobar.blah/abc")
+    );
+    assert_eq!(files[0].cdn_uri, None);
+    // Without a probed mime the declared one stays.
+    assert_eq!(files[1].mime.as_deref(), Some("video/mp4"));
+    assert_eq!(files[1].preview_uri, None);
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod common;
+
+use blockbuster::token_metadata::pda::find_token_record_account;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::sea_orm_active_enums::*;
+use digital_asset_types::dao::{token_accounts, token_records};
+use digital_asset_types::dapi::{add_lock_state, common::asset_list_to_rpc};
+use digital_asset_types::rpc::transform::AssetTransform;
+
+fn token_account(pubkey: Pubkey, mint: Pubkey, owner: Pubkey) -> token_accounts::Model {
+    token_accounts::Model {
+        pubkey: pubkey.to_bytes().to_vec(),
+        mint: mint.to_bytes().to_vec(),
+        amount: 1.into(),
+        owner: owner.to_bytes().to_vec(),
+        frozen: true,
+        close_authority: None,
+        delegate: None,
+        delegated_amount: 0.into(),
+        slot_updated: 1,
+        token_program: Keypair::new().pubkey().to_bytes().to_vec(),
+    }
+}
+
+#[tokio::test]
+async fn locked_pnfts_show_their_delegate() -> Result<(), DbErr> {
+    let (locked, unlocked, legacy) = (
+        Keypair::new().pubkey(),
+        Keypair::new().pubkey(),
+        Keypair::new().pubkey(),
+    );
+    let owner = Keypair::new().pubkey();
+    let staking = Keypair::new().pubkey();
+    let (locked_token, unlocked_token) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    let [locked_pnft, unlocked_pnft] = [locked, unlocked].map(|id| {
+        let mut pnft = create_full_asset(id, owner);
+        pnft.asset.specification_asset_class = Some(SpecificationAssetClass::ProgrammableNft);
+        pnft
+    });
+    let transform = AssetTransform::default();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![locked_pnft, unlocked_pnft, create_full_asset(legacy, owner)],
+        &transform,
+    );
+    assert!(errors.is_empty());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            token_account(locked_token, locked, owner),
+            token_account(unlocked_token, unlocked, owner),
+        ]])
+        .append_query_results(vec![vec![
+            token_records::Model {
+                pubkey: find_token_record_account(&locked, &locked_token)
+                    .0
+                    .to_bytes()
+                    .to_vec(),
+                state: TokenRecordState::Locked,
+                delegate: Some(staking.to_bytes().to_vec()),
+                delegate_role: Some("Staking".to_string()),
+                locked_transfer: None,
+                slot_updated: 2,
+            },
+            token_records::Model {
+                pubkey: find_token_record_account(&unlocked, &unlocked_token)
+                    .0
+                    .to_bytes()
+                    .to_vec(),
+                state: TokenRecordState::Unlocked,
+                delegate: None,
+                delegate_role: None,
+                locked_transfer: None,
+                slot_updated: 2,
+            },
+        ]])
+        .into_connection();
+    add_lock_state(&db, &mut assets).await?;
+
+    assert_eq!(assets[0].ownership.locked, Some(true));
+    assert_eq!(assets[0].ownership.lock_delegate, Some(staking.to_string()));
+    assert_eq!(
+        assets[0].ownership.lock_delegate_role,
+        Some("Staking".to_string())
+    );
+    assert_eq!(assets[1].ownership.locked, Some(false));
+    assert_eq!(assets[1].ownership.lock_delegate, None);
+    assert_eq!(assets[2].ownership.locked, None);
+
+    Ok(())
+}
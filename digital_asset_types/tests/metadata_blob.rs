@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod common;
+
+use blockbuster::token_metadata::state::*;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use serde_json::json;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::metadata_blob;
+use digital_asset_types::dao::scopes::metadata_blob::{
+    content_hash, resolve_metadata_blobs, uri_hash,
+};
+
+fn asset_data(id: Pubkey, uri: &str) -> digital_asset_types::dao::asset_data::Model {
+    let args = MockMetadataArgs {
+        name: String::from("Test #1"),
+        symbol: String::from("BUBBLE"),
+        uri: uri.to_string(),
+        primary_sale_happened: true,
+        is_mutable: true,
+        edition_nonce: None,
+        token_standard: Some(TokenStandard::NonFungible),
+        collection: None,
+        uses: None,
+        creators: vec![],
+        seller_fee_basis_points: 100,
+    };
+    create_asset_data(args, id.to_bytes().to_vec()).1
+}
+
+#[test]
+fn blobs_are_keyed_by_uri() {
+    let uri = "https://example.com/collection.json";
+    assert_eq!(uri_hash(uri), uri_hash(uri));
+    assert_eq!(uri_hash(uri).len(), 32);
+    assert_ne!(uri_hash(uri), uri_hash("https://example.com/other.json"));
+    assert_eq!(
+        content_hash(&json!({"name": "Shared"})),
+        content_hash(&json!({"name": "Shared"}))
+    );
+    assert_ne!(
+        content_hash(&json!({"name": "Shared"})),
+        content_hash(&json!({"name": "Other"}))
+    );
+}
+
+#[tokio::test]
+async fn shared_metadata_is_read_from_its_blob() -> Result<(), DbErr> {
+    let uri = "https://example.com/collection.json";
+    let shared = json!({"name": "Shared", "image": "https://example.com/1.png"});
+    let mut linked = asset_data(Keypair::new().pubkey(), uri);
+    linked.metadata = serde_json::Value::Null;
+    linked.metadata_blob = Some(uri_hash(uri));
+    let mut other = linked.clone();
+    other.id = Keypair::new().pubkey().to_bytes().to_vec();
+    let mut own = asset_data(Keypair::new().pubkey(), "https://example.com/own.json");
+    own.metadata = json!({"name": "Own"});
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![metadata_blob::Model {
+            uri_hash: uri_hash(uri),
+            uri: uri.to_string(),
+            metadata: shared.clone(),
+            content_hash: content_hash(&shared),
+            files: None,
+            fetched_at: "2023-08-31T00:00:00+00:00".parse().unwrap(),
+        }]])
+        .into_connection();
+    let mut data = vec![linked, other, own];
+    resolve_metadata_blobs(&db, &mut data).await?;
+
+    assert_eq!(data[0].metadata, shared);
+    assert_eq!(data[1].metadata, shared);
+    assert_eq!(data[2].metadata, json!({"name": "Own"}));
+    // The blobs are read in a single query.
+    assert_eq!(db.into_transaction_log().len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn rows_without_a_blob_are_not_looked_up() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    let mut data = vec![asset_data(
+        Keypair::new().pubkey(),
+        "https://example.com/own.json",
+    )];
+    let metadata = data[0].metadata.clone();
+    resolve_metadata_blobs(&db, &mut data).await?;
+
+    assert_eq!(data[0].metadata, metadata);
+    assert!(db.into_transaction_log().is_empty());
+    Ok(())
+}
@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use serde_json::json;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{asset_data_full_metadata, FullAsset};
+use digital_asset_types::dapi::{add_full_metadata, common::asset_list_to_rpc};
+use digital_asset_types::json::{
+    truncate_metadata, truncation_marker, TruncationPolicy, TRUNCATED_KEY,
+};
+use digital_asset_types::rpc::transform::AssetTransform;
+
+fn large_metadata() -> serde_json::Value {
+    json!({
+        "name": "Test #1",
+        "symbol": "BUBBLE",
+        "description": "A test asset",
+        "image": "https://example.com/1.png",
+        "attributes": [{"trait_type": "Background", "value": "Blue"}],
+        "frames": vec!["a".repeat(100); 100],
+        "history": "b".repeat(2000),
+    })
+}
+
+fn full_asset(id: Pubkey, metadata: serde_json::Value) -> FullAsset {
+    let mut asset = create_full_asset(id, Keypair::new().pubkey());
+    asset.data.metadata = metadata;
+    asset
+}
+
+#[test]
+fn metadata_under_the_cap_is_kept() {
+    let metadata = large_metadata();
+    assert_eq!(
+        truncate_metadata(&metadata, 1 << 20, TruncationPolicy::DropLargest),
+        None
+    );
+}
+
+#[test]
+fn largest_fields_are_dropped_until_the_metadata_fits() {
+    let metadata = large_metadata();
+    let original_bytes = serde_json::to_vec(&metadata).unwrap().len();
+    let truncated = truncate_metadata(&metadata, 1000, TruncationPolicy::DropLargest).unwrap();
+
+    assert!(serde_json::to_vec(&truncated).unwrap().len() <= 1000);
+    let marker = truncation_marker(&truncated).unwrap();
+    assert_eq!(marker.original_bytes, original_bytes);
+    assert_eq!(marker.dropped_fields, vec!["frames", "history"]);
+    assert_eq!(truncated["name"], "Test #1");
+    assert_eq!(truncated["attributes"], metadata["attributes"]);
+
+    // Not an object, only the marker is left.
+    let blob = json!("c".repeat(2000));
+    let truncated = truncate_metadata(&blob, 1000, TruncationPolicy::DropLargest).unwrap();
+    assert_eq!(truncated.as_object().unwrap().len(), 1);
+    assert!(truncated.get(TRUNCATED_KEY).is_some());
+}
+
+#[test]
+fn essentials_keep_only_the_content_fields() {
+    let mut metadata = large_metadata();
+    metadata["seller_fee_basis_points"] = json!(500);
+    let truncated = truncate_metadata(&metadata, 1000, TruncationPolicy::Essentials).unwrap();
+
+    let marker = truncation_marker(&truncated).unwrap();
+    assert_eq!(
+        marker.dropped_fields,
+        vec!["frames", "history", "seller_fee_basis_points"]
+    );
+    assert_eq!(truncated["description"], "A test asset");
+    assert_eq!(truncated["image"], "https://example.com/1.png");
+}
+
+#[tokio::test]
+async fn full_metadata_replaces_the_truncated_content() -> Result<(), DbErr> {
+    let (truncated_id, small_id) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    let metadata = large_metadata();
+    let truncated = truncate_metadata(&metadata, 1000, TruncationPolicy::DropLargest).unwrap();
+    let transform = AssetTransform::default();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            full_asset(truncated_id, truncated.clone()),
+            full_asset(small_id, json!({"name": "Small"})),
+        ],
+        &transform,
+    );
+    assert!(errors.is_empty());
+    let content = assets[0].content.as_ref().unwrap();
+    assert_eq!(content.metadata_truncated, Some(true));
+    assert_eq!(assets[1].content.as_ref().unwrap().metadata_truncated, None);
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset_data_full_metadata::Model {
+            id: truncated_id.to_bytes().to_vec(),
+            metadata: metadata.clone(),
+            original_bytes: serde_json::to_vec(&metadata).unwrap().len() as i64,
+            updated_at: "2023-08-27T00:00:00+00:00".parse().unwrap(),
+        }]])
+        .append_query_results(vec![vec![full_asset(truncated_id, truncated).data]])
+        .into_connection();
+    add_full_metadata(&db, &mut assets, &transform).await?;
+
+    let content = assets[0].content.as_ref().unwrap();
+    assert_eq!(content.metadata_truncated, None);
+    assert_eq!(
+        content.links.as_ref().unwrap().get("image"),
+        Some(&json!("https://example.com/1.png"))
+    );
+
+    // Only the truncated asset is looked up.
+    let log = format!("{:?}", db.into_transaction_log()[0]);
+    assert!(log.contains(&format!("{:?}", truncated_id.to_bytes().to_vec())));
+    assert!(!log.contains(&format!("{:?}", small_id.to_bytes().to_vec())));
+    Ok(())
+}
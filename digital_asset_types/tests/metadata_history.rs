@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::dao::{asset_data_history, scopes};
+use digital_asset_types::dapi::get_asset_metadata_history;
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn metadata_history_lists_versions_oldest_first() -> Result<(), DbErr> {
+    let asset = Keypair::new().pubkey();
+
+    let version = |id: i64, name: &str, uri: &str, slot: i64| asset_data_history::Model {
+        id,
+        asset_id: asset.to_bytes().to_vec(),
+        name: name.to_string(),
+        symbol: "SYM".to_string(),
+        metadata_url: uri.to_string(),
+        slot_updated: slot,
+    };
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            version(1, "Before", "https://example.com/1.json", 100),
+            version(2, "After", "https://example.com/2.json", 250),
+        ]])
+        .into_connection();
+
+    let history = get_asset_metadata_history(&db, asset.to_bytes().to_vec(), 10, Some(1)).await?;
+
+    assert_eq!(history.id, asset.to_string());
+    assert_eq!(history.total, 2);
+    assert_eq!(history.page, Some(1));
+    assert_eq!(history.items[0].name, "Before");
+    assert_eq!(history.items[0].slot, 100);
+    assert_eq!(history.items[1].name, "After");
+    assert_eq!(history.items[1].uri, "https://example.com/2.json");
+    assert_eq!(history.items[1].slot, 250);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn assets_with_more_than_one_version_are_mutated() -> Result<(), DbErr> {
+    let mutated = Keypair::new().pubkey().to_bytes().to_vec();
+    let untouched = Keypair::new().pubkey().to_bytes().to_vec();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "asset_id",
+            Value::from(mutated.clone()),
+        )])]])
+        .into_connection();
+
+    let ids = scopes::data_history::get_mutated(&db, vec![mutated.clone(), untouched]).await?;
+    assert_eq!(ids, vec![mutated]);
+
+    // Nothing to look up, nothing is queried.
+    let empty = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    assert!(scopes::data_history::get_mutated(&empty, vec![])
+        .await?
+        .is_empty());
+
+    Ok(())
+}
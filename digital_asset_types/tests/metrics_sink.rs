@@ -0,0 +1,51 @@
+use cadence::MetricSink;
+use digital_asset_types::metrics_sink::FlushingMetricSink;
+use std::{net::UdpSocket, time::Duration};
+
+fn receiver() -> (UdpSocket, (String, u16)) {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+    (socket, ("127.0.0.1".to_string(), port))
+}
+
+fn received(socket: &UdpSocket) -> Vec<String> {
+    let mut metrics = vec![];
+    let mut buf = [0; 1024];
+    // The datagrams of a flush are on the loopback by the time it returns.
+    socket.set_nonblocking(true).unwrap();
+    while let Ok(len) = socket.recv(&mut buf) {
+        let datagram = String::from_utf8_lossy(&buf[..len]).to_string();
+        assert!(len <= 512, "datagram of {} bytes", len);
+        metrics.extend(datagram.lines().map(str::to_string));
+    }
+    metrics
+}
+
+#[test]
+fn a_flush_sends_the_queued_metrics() {
+    let (socket, host) = receiver();
+    let sink = FlushingMetricSink::start(host, "test", "|#env:test".to_string()).unwrap();
+    sink.emit("test.a:1|c").unwrap();
+    sink.emit("test.b:2|c").unwrap();
+
+    assert!(sink.flush(Duration::from_secs(5)));
+
+    assert_eq!(received(&socket), vec!["test.a:1|c", "test.b:2|c"]);
+}
+
+#[test]
+fn metrics_are_batched_into_datagrams() {
+    let (socket, host) = receiver();
+    let sink = FlushingMetricSink::start(host, "test", "|#env:test".to_string()).unwrap();
+    let metrics: Vec<String> = (0..100)
+        .map(|i| format!("test.metric_{}:{}|c", i, i))
+        .collect();
+    for metric in metrics.iter() {
+        sink.emit(metric).unwrap();
+    }
+
+    assert!(sink.flush(Duration::from_secs(5)));
+
+    // Every metric arrives, in order, across datagrams of at most 512 bytes.
+    assert_eq!(received(&socket), metrics);
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::dapi::{get_missing_proof, MissingProof};
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+fn proof_asset(
+    compressed: bool,
+    tree: Option<Vec<u8>>,
+    tree_indexed: bool,
+) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("compressed", Value::from(compressed)),
+        ("tree_id", Value::from(tree)),
+        ("tree_indexed", Value::from(tree_indexed)),
+    ])
+}
+
+#[tokio::test]
+async fn missing_proof_tells_apart_unindexed_trees_and_leaves() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey().to_bytes().to_vec();
+    let tree = Keypair::new().pubkey().to_bytes().to_vec();
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![
+            Vec::<BTreeMap<&str, Value>>::new(),
+            vec![proof_asset(false, None, false)],
+            vec![proof_asset(true, Some(tree.clone()), false)],
+            vec![proof_asset(true, Some(tree.clone()), true)],
+        ])
+        .into_connection();
+
+    assert_eq!(
+        get_missing_proof(&db, id.clone()).await?,
+        MissingProof::AssetNotFound
+    );
+    assert_eq!(
+        get_missing_proof(&db, id.clone()).await?,
+        MissingProof::NotCompressed
+    );
+    assert_eq!(
+        get_missing_proof(&db, id.clone()).await?,
+        MissingProof::TreeNotIndexed(tree.clone())
+    );
+    assert_eq!(
+        get_missing_proof(&db, id).await?,
+        MissingProof::LeafNotIndexed(tree)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, DatabaseConnection, MockDatabase, Order};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping, is_valid_network, scopes,
+    Pagination,
+};
+use digital_asset_types::rpc::transform::HiddenAssets;
+
+fn empty_db() -> DatabaseConnection {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .append_query_results(vec![Vec::<asset_data::Model>::new()])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection()
+}
+
+#[tokio::test]
+async fn assets_are_only_filtered_by_network_when_asked() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey().to_bytes().to_vec();
+    for network in [None, Some("devnet".to_string())] {
+        let db = empty_db();
+        let (assets, _) = scopes::asset::get_assets_by_owner(
+            &db,
+            owner.clone(),
+            None,
+            Order::Asc,
+            &Pagination::Page { page: 1 },
+            10,
+            false,
+            network.clone(),
+            &HiddenAssets::default(),
+        )
+        .await?;
+        assert!(assets.is_empty());
+
+        let sql = logged_sql(db).remove(0);
+        assert_eq!(
+            sql.contains(r#""asset"."network" = 'devnet'"#),
+            network.is_some()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn network_names_are_short_lowercase_identifiers() {
+    for network in ["mainnet", "devnet", "my-svm-l2", "eclipse_testnet"] {
+        assert!(is_valid_network(network), "{}", network);
+    }
+    for network in ["", "Devnet", "dev net", "devnet'; --", &"a".repeat(33)] {
+        assert!(!is_valid_network(network), "{}", network);
+    }
+}
@@ -0,0 +1,35 @@
+use digital_asset_types::dao::numeric::{
+    decimal_to_u64, saturating_to_i64, to_decimal, to_i64, to_u64,
+};
+use sea_orm::{entity::prelude::Decimal, DbErr};
+
+#[test]
+fn out_of_range_values_are_rejected_instead_of_wrapped() {
+    assert_eq!(to_i64(i64::MAX as u64, "seq"), Ok(i64::MAX));
+    let err = to_i64(i64::MAX as u64 + 1, "seq").unwrap_err();
+    assert_eq!(err.column, "seq");
+    assert_eq!(err.to_string(), "seq 9223372036854775808 is out of range");
+
+    assert_eq!(to_u64(0, "slot"), Ok(0));
+    assert!(to_u64(-1, "slot").is_err());
+
+    let err: DbErr = to_u64(-1, "slot").unwrap_err().into();
+    assert!(matches!(err, DbErr::Custom(msg) if msg == "slot -1 is out of range"));
+}
+
+#[test]
+fn token_amounts_keep_the_whole_u64_range() {
+    for amount in [0, 1, i64::MAX as u64 + 1, u64::MAX] {
+        assert_eq!(decimal_to_u64(to_decimal(amount), "amount"), Ok(amount));
+    }
+    assert!(decimal_to_u64(Decimal::from(-1), "amount").is_err());
+    assert!(decimal_to_u64(Decimal::new(15, 1), "amount").is_err());
+    assert!(decimal_to_u64(to_decimal(u64::MAX) + Decimal::ONE, "amount").is_err());
+}
+
+#[test]
+fn asset_supplies_are_capped() {
+    assert_eq!(saturating_to_i64(1, "supply"), 1);
+    assert_eq!(saturating_to_i64(i64::MAX as u64, "supply"), i64::MAX);
+    assert_eq!(saturating_to_i64(u64::MAX, "supply"), i64::MAX);
+}
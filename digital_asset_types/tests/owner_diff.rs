@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::{dapi::get_owner_diff, rpc::transform::HiddenAssets};
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+#[tokio::test]
+async fn owner_diff_splits_gained_and_lost_assets() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    let received = Keypair::new().pubkey();
+    let sent = Keypair::new().pubkey();
+
+    let change = |asset: &Pubkey, gained: bool, slot: i64| {
+        BTreeMap::from([
+            ("asset_id", Value::from(asset.to_bytes().to_vec())),
+            ("gained", Value::from(gained)),
+            ("slot_updated", Value::from(slot)),
+        ])
+    };
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            change(&received, true, 120),
+            change(&sent, false, 150),
+        ]])
+        .into_connection();
+
+    let diff = get_owner_diff(
+        &db,
+        owner.to_bytes().to_vec(),
+        100,
+        10,
+        Some(1),
+        &HiddenAssets::default(),
+    )
+    .await?;
+
+    assert_eq!(diff.owner, owner.to_string());
+    assert_eq!(diff.since_slot, 100);
+    assert_eq!(diff.total, 2);
+    assert_eq!(diff.gained.len(), 1);
+    assert_eq!(diff.gained[0].id, received.to_string());
+    assert_eq!(diff.gained[0].slot, 120);
+    assert_eq!(diff.lost.len(), 1);
+    assert_eq!(diff.lost[0].id, sent.to_string());
+    assert_eq!(diff.lost[0].slot, 150);
+
+    Ok(())
+}
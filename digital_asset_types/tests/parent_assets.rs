@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Order};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping, asset_owner_pdas, scopes,
+    Pagination,
+};
+use digital_asset_types::dapi::{add_parent_assets, common::asset_list_to_rpc};
+use digital_asset_types::rpc::{
+    transform::{AssetTransform, HiddenAssets},
+    OwnershipModel,
+};
+
+#[tokio::test]
+async fn assets_owned_by_an_asset_name_their_parent() -> Result<(), DbErr> {
+    let parent = Keypair::new().pubkey();
+    let escrow = Keypair::new().pubkey();
+    let wallet = Keypair::new().pubkey();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            create_full_asset(Keypair::new().pubkey(), escrow),
+            create_full_asset(Keypair::new().pubkey(), wallet),
+        ],
+        &AssetTransform::default(),
+    );
+    assert!(errors.is_empty());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![asset_owner_pdas::Model {
+            pda: escrow.to_bytes().to_vec(),
+            asset_id: parent.to_bytes().to_vec(),
+            program: "token_metadata_escrow".to_string(),
+            created_at: DateTime::from_timestamp(1_690_000_000, 0),
+        }]])
+        .into_connection();
+    add_parent_assets(&db, &mut assets).await?;
+
+    assert_eq!(assets[0].ownership.ownership_model, OwnershipModel::Asset);
+    assert_eq!(assets[0].ownership.parent_asset, Some(parent.to_string()));
+    // The owner is still the escrow, which signs for the asset.
+    assert_eq!(assets[0].ownership.owner, escrow.to_string());
+    assert_eq!(assets[1].ownership.ownership_model, OwnershipModel::Single);
+    assert_eq!(assets[1].ownership.parent_asset, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn assets_of_a_parent_are_the_ones_its_pdas_own() -> Result<(), DbErr> {
+    let parent = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .append_query_results(vec![Vec::<asset_data::Model>::new()])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection();
+
+    let (assets, _) = scopes::asset::get_by_parent_asset(
+        &db,
+        parent.to_bytes().to_vec(),
+        None,
+        Order::Asc,
+        &Pagination::Page { page: 1 },
+        10,
+        false,
+        None,
+        &HiddenAssets::default(),
+    )
+    .await?;
+    assert!(assets.is_empty());
+
+    let sql = logged_sql(db).remove(0);
+    assert!(sql.contains(
+        r#""asset"."owner" IN (SELECT "pda" FROM "asset_owner_pdas" WHERE "asset_owner_pdas"."asset_id" = "#
+    ));
+    Ok(())
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::asset_pricing;
+use digital_asset_types::dapi::{add_price_info, common::asset_list_to_rpc};
+use digital_asset_types::rpc::{transform::AssetTransform, PriceInfo};
+
+#[tokio::test]
+async fn priced_assets_get_price_info() -> Result<(), DbErr> {
+    let priced = Keypair::new().pubkey();
+    let unpriced = Keypair::new().pubkey();
+    let transform = AssetTransform::default();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            create_full_asset(priced, Keypair::new().pubkey()),
+            create_full_asset(unpriced, Keypair::new().pubkey()),
+        ],
+        &transform,
+    );
+    assert!(errors.is_empty());
+
+    let updated_at = DateTime::from_timestamp(1_690_000_000, 0);
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![
+            asset_pricing::Model {
+                id: 1,
+                asset_id: priced.to_bytes().to_vec(),
+                provider: "floor".to_string(),
+                price: 12.5,
+                currency: "USD".to_string(),
+                updated_at,
+            },
+            asset_pricing::Model {
+                id: 2,
+                asset_id: priced.to_bytes().to_vec(),
+                provider: "fixed".to_string(),
+                price: 0.1,
+                currency: "SOL".to_string(),
+                updated_at,
+            },
+        ]])
+        .into_connection();
+    add_price_info(&db, &mut assets).await?;
+
+    assert_eq!(
+        assets[0].price_info,
+        Some(vec![
+            PriceInfo {
+                provider: "floor".to_string(),
+                price: 12.5,
+                currency: "USD".to_string(),
+                updated_at: updated_at.to_string(),
+            },
+            PriceInfo {
+                provider: "fixed".to_string(),
+                price: 0.1,
+                currency: "SOL".to_string(),
+                updated_at: updated_at.to_string(),
+            },
+        ])
+    );
+    assert_eq!(assets[1].price_info, None);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, DatabaseConnection, MockDatabase, Order};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping, scopes, Pagination,
+};
+use digital_asset_types::dapi::is_hidden;
+use digital_asset_types::rpc::transform::HiddenAssets;
+
+fn empty_db() -> DatabaseConnection {
+    MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset::Model>::new()])
+        .append_query_results(vec![Vec::<asset_data::Model>::new()])
+        .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection()
+}
+
+#[tokio::test]
+async fn hidden_collections_and_trees_are_left_out_of_listings() -> Result<(), DbErr> {
+    let collection = Keypair::new().pubkey().to_string();
+    let owner = Keypair::new().pubkey().to_bytes().to_vec();
+    for hidden in [
+        HiddenAssets::default(),
+        HiddenAssets {
+            collections: vec![collection.clone()],
+            trees: vec![Keypair::new().pubkey().to_bytes().to_vec()],
+        },
+    ] {
+        let db = empty_db();
+        let (assets, _) = scopes::asset::get_assets_by_owner(
+            &db,
+            owner.clone(),
+            None,
+            Order::Asc,
+            &Pagination::Page { page: 1 },
+            10,
+            false,
+            None,
+            &hidden,
+        )
+        .await?;
+        assert!(assets.is_empty());
+
+        let sql = logged_sql(db).remove(0);
+        assert_eq!(
+            sql.contains(r#""asset"."id" NOT IN (SELECT "asset_id" FROM "asset_grouping""#),
+            !hidden.is_empty()
+        );
+        assert_eq!(
+            sql.contains(r#""asset"."tree_id" NOT IN"#),
+            !hidden.is_empty()
+        );
+        assert_eq!(sql.contains(&collection), !hidden.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn assets_of_hidden_collections_and_trees_are_hidden() {
+    let collection = Keypair::new().pubkey();
+    let tree = Keypair::new().pubkey().to_bytes().to_vec();
+    let hidden = HiddenAssets {
+        collections: vec![collection.to_string()],
+        trees: vec![tree.clone()],
+    };
+
+    let public = create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey());
+    assert!(!is_hidden(&public, &hidden));
+    assert!(!is_hidden(&public, &HiddenAssets::default()));
+
+    // Unverified groupings hide the asset too, a private asset isn't always verified yet.
+    let mut collected = create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey());
+    let (_, grouping) = create_asset_grouping(collected.asset.id.clone(), collection, 1);
+    assert_eq!(grouping.verified, Some(false));
+    collected.groups.push(grouping);
+    assert!(is_hidden(&collected, &hidden));
+
+    let mut compressed = create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey());
+    compressed.asset.tree_id = Some(tree);
+    assert!(is_hidden(&compressed, &hidden));
+}
@@ -0,0 +1,44 @@
+use digital_asset_types::dapi::proof_matches_root;
+use digital_asset_types::rpc::AssetProof;
+use solana_sdk::keccak::hashv;
+
+fn encode(node: &[u8]) -> String {
+    bs58::encode(node).into_string()
+}
+
+// A depth 2 tree and the proof of its third leaf, node index 6.
+fn third_leaf_proof() -> AssetProof {
+    let leaves: Vec<[u8; 32]> = (0..4u8).map(|i| [i + 1; 32]).collect();
+    let left = hashv(&[&leaves[0], &leaves[1]]).to_bytes();
+    let right = hashv(&[&leaves[2], &leaves[3]]).to_bytes();
+    let root = hashv(&[&left, &right]).to_bytes();
+    AssetProof {
+        root: encode(&root),
+        proof: vec![encode(&leaves[3]), encode(&left)],
+        node_index: 6,
+        leaf: encode(&leaves[2]),
+        tree_id: encode(&[0; 32]),
+        valid: None,
+        on_chain_root: None,
+    }
+}
+
+#[test]
+fn valid_proof_matches_root() {
+    assert!(proof_matches_root(&third_leaf_proof()));
+}
+
+#[test]
+fn stale_proof_does_not_match_root() {
+    let mut proof = third_leaf_proof();
+    proof.proof[1] = encode(&[9; 32]);
+    assert!(!proof_matches_root(&proof));
+
+    let mut proof = third_leaf_proof();
+    proof.node_index = 7;
+    assert!(!proof_matches_root(&proof));
+
+    let mut proof = third_leaf_proof();
+    proof.root = "not a hash".to_string();
+    assert!(!proof_matches_root(&proof));
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::dao::{raw_query::RawQuery, scopes::asset::get_signatures_for_leaves};
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Transaction, Value};
+
+#[test]
+fn raw_query_numbers_pushed_values_after_bound_ones() {
+    let query = RawQuery::new("SELECT * FROM asset WHERE owner = $1")
+        .bind(vec![1u8; 32])
+        .push(" AND supply > ")
+        .push_bind(0i64)
+        .push(" LIMIT ")
+        .push_bind(10i64);
+
+    assert_eq!(
+        query.sql(),
+        "SELECT * FROM asset WHERE owner = $1 AND supply > $2 LIMIT $3"
+    );
+    assert_eq!(
+        query.values(),
+        &[
+            Value::from(vec![1u8; 32]),
+            Value::from(0i64),
+            Value::from(10i64)
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "every placeholder")]
+fn raw_query_refuses_unbound_placeholders() {
+    RawQuery::new("SELECT * FROM asset WHERE owner = $1 LIMIT $2")
+        .bind(vec![1u8; 32])
+        .build();
+}
+
+fn statement_sql(log: &Transaction) -> String {
+    // The statement is only reachable through the log's debug output.
+    let debug = format!("{:?}", log);
+    debug[..debug.find("values:").unwrap_or(debug.len())].to_string()
+}
+
+#[tokio::test]
+async fn signatures_for_leaves_share_one_statement() -> Result<(), DbErr> {
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![
+            Vec::<BTreeMap<&str, Value>>::new(),
+            Vec::<BTreeMap<&str, Value>>::new(),
+        ])
+        .into_connection();
+
+    let tree = vec![7u8; 32];
+    get_signatures_for_leaves(&db, &[(tree.clone(), 0)], 10).await?;
+    get_signatures_for_leaves(&db, &[(tree.clone(), 0), (tree.clone(), 1), (tree, 2)], 10).await?;
+
+    let log = db.into_transaction_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!(statement_sql(&log[0]), statement_sql(&log[1]));
+    assert!(format!("{:?}", log[1]).contains("\"leaf_idx\": Number(2)"));
+    Ok(())
+}
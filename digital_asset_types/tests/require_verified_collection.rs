@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Order};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{
+    asset, asset_authority, asset_creators, asset_data, asset_grouping, scopes, FullAsset,
+    Pagination,
+};
+use digital_asset_types::dapi::common::asset_to_rpc;
+use digital_asset_types::rpc::transform::{AssetTransform, HiddenAssets};
+
+fn collection(id: Pubkey, collection: Pubkey, verified: Option<bool>) -> asset_grouping::Model {
+    let (_, mut grouping) = create_asset_grouping(id.to_bytes().to_vec(), collection, 1);
+    grouping.verified = verified;
+    grouping
+}
+
+#[test]
+fn grouping_shows_only_verified_collections() -> Result<(), DbErr> {
+    let id = Keypair::new().pubkey();
+    let verified = Keypair::new().pubkey();
+    let groups = vec![
+        collection(id, verified, Some(true)),
+        collection(id, Keypair::new().pubkey(), Some(false)),
+        // Indexed before the verified flag was.
+        collection(id, Keypair::new().pubkey(), None),
+    ];
+
+    let full_asset = FullAsset {
+        groups,
+        ..create_full_asset(id, Keypair::new().pubkey())
+    };
+
+    let asset = asset_to_rpc(full_asset.clone(), &AssetTransform::default(), None)?;
+    assert_eq!(asset.grouping.unwrap().len(), 3);
+
+    let transform = AssetTransform {
+        require_verified_collection: true,
+        ..Default::default()
+    };
+    let grouping = asset_to_rpc(full_asset, &transform, None)?
+        .grouping
+        .unwrap();
+    assert_eq!(grouping.len(), 1);
+    assert_eq!(grouping[0].group_value, Some(verified.to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn assets_by_group_require_verified_membership() -> Result<(), DbErr> {
+    let collection = Keypair::new().pubkey().to_string();
+    let legacy = r#""asset_grouping"."verified" IS NULL"#;
+
+    for require_verified in [false, true] {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<asset::Model>::new()])
+            .append_query_results(vec![Vec::<asset_data::Model>::new()])
+            .append_query_results(vec![Vec::<asset_authority::Model>::new()])
+            .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+            .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+            .into_connection();
+        let (assets, _) = scopes::asset::get_by_grouping(
+            &db,
+            "collection".to_string(),
+            collection.clone(),
+            require_verified,
+            None,
+            Order::Asc,
+            &Pagination::Page { page: 1 },
+            10,
+            false,
+            None,
+            &HiddenAssets::default(),
+        )
+        .await?;
+        assert!(assets.is_empty());
+
+        let sql = logged_sql(db);
+        assert_eq!(sql[0].contains(legacy), !require_verified);
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod common;
+
+use common::create_asset_creator;
+use digital_asset_types::{
+    dao::sea_orm_active_enums::RoyaltyTargetType,
+    dapi::common::{creator_shares_valid, hash_creators, royalty_breakdown},
+};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+#[test]
+fn royalty_is_split_by_share_rounding_down() {
+    let id = Keypair::new().pubkey().to_bytes().to_vec();
+    let creators = vec![
+        create_asset_creator(id.clone(), vec![1; 32], 33, true, 1).1,
+        create_asset_creator(id, vec![2; 32], 67, false, 2).1,
+    ];
+    assert!(creator_shares_valid(&creators, None));
+
+    let breakdown = royalty_breakdown(&RoyaltyTargetType::Creators, 500, &creators, true);
+    assert_eq!(breakdown.len(), 2);
+    assert_eq!(breakdown[0].basis_points, 165);
+    assert!(breakdown[0].verified);
+    assert_eq!(breakdown[1].basis_points, 335);
+    assert_eq!(breakdown[1].address, bs58::encode([2; 32]).into_string());
+
+    assert!(royalty_breakdown(&RoyaltyTargetType::Single, 500, &creators, true).is_empty());
+    assert!(royalty_breakdown(&RoyaltyTargetType::Creators, 500, &creators, false).is_empty());
+}
+
+#[test]
+fn creator_shares_must_add_up_and_match_the_leaf() {
+    let id = Keypair::new().pubkey().to_bytes().to_vec();
+    let creators = vec![
+        create_asset_creator(id.clone(), vec![1; 32], 40, true, 1).1,
+        create_asset_creator(id.clone(), vec![2; 32], 50, false, 2).1,
+    ];
+    assert!(!creator_shares_valid(&creators, None));
+
+    let duplicated = vec![
+        create_asset_creator(id.clone(), vec![1; 32], 50, true, 1).1,
+        create_asset_creator(id.clone(), vec![1; 32], 50, true, 2).1,
+    ];
+    assert!(!creator_shares_valid(&duplicated, None));
+
+    let creators = vec![
+        create_asset_creator(id.clone(), vec![1; 32], 60, true, 1).1,
+        create_asset_creator(id, vec![2; 32], 40, false, 2).1,
+    ];
+    let leaf_hash = bs58::encode(hash_creators(&creators)).into_string();
+    assert!(creator_shares_valid(&creators, Some(&leaf_hash)));
+    let other_hash = bs58::encode([0; 32]).into_string();
+    assert!(!creator_shares_valid(&creators, Some(&other_hash)));
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+
+use digital_asset_types::dao::schema::{
+    check_schema_version, is_valid_schema_name, SchemaCompatibility, SCHEMA_VERSION,
+};
+use sea_orm::{entity::prelude::*, DatabaseBackend, DatabaseConnection, MockDatabase};
+
+fn database(migrations_table: bool, applied: &[&str]) -> DatabaseConnection {
+    let mut db = MockDatabase::new(DatabaseBackend::Postgres).append_query_results(vec![vec![
+        BTreeMap::from([("exists", Value::Bool(Some(migrations_table)))]),
+    ]]);
+    if migrations_table {
+        db = db.append_query_results(vec![applied
+            .iter()
+            .map(|version| BTreeMap::from([("version", Value::from(version.to_string()))]))
+            .collect::<Vec<_>>()]);
+    }
+    db.into_connection()
+}
+
+#[tokio::test]
+async fn schema_version_current() -> Result<(), DbErr> {
+    let db = database(true, &["m20220101_000001_init", SCHEMA_VERSION]);
+    assert_eq!(
+        check_schema_version(&db).await?,
+        SchemaCompatibility::Current
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn schema_version_behind() -> Result<(), DbErr> {
+    let db = database(false, &[]);
+    assert_eq!(
+        check_schema_version(&db).await?,
+        SchemaCompatibility::Behind { applied: None }
+    );
+
+    let db = database(true, &["m20220101_000001_init"]);
+    assert_eq!(
+        check_schema_version(&db).await?,
+        SchemaCompatibility::Behind {
+            applied: Some("m20220101_000001_init".to_string())
+        }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn schema_version_ahead() -> Result<(), DbErr> {
+    let db = database(
+        true,
+        &[
+            "m20220101_000001_init",
+            SCHEMA_VERSION,
+            "m99990101_000001_next",
+        ],
+    );
+    assert_eq!(
+        check_schema_version(&db).await?,
+        SchemaCompatibility::Ahead {
+            applied: "m99990101_000001_next".to_string()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn schema_names_are_lowercase_identifiers() {
+    for schema in ["public", "das_devnet", "_tenant2"] {
+        assert!(is_valid_schema_name(schema), "{}", schema);
+    }
+    for schema in [
+        "",
+        "2das",
+        "Das",
+        "das-devnet",
+        "pg_catalog",
+        "das\"; --",
+        &"a".repeat(64),
+    ] {
+        assert!(!is_valid_schema_name(schema), "{}", schema);
+    }
+}
@@ -0,0 +1,61 @@
+use digital_asset_types::dao::{asset, SearchAssetsQuery};
+use sea_orm::{entity::prelude::*, DatabaseBackend, QueryTrait};
+
+fn search_sql(query: SearchAssetsQuery) -> String {
+    let (condition, _) = query.conditions().unwrap();
+    asset::Entity::find()
+        .filter(condition)
+        .build(DatabaseBackend::Postgres)
+        .to_string()
+}
+
+#[test]
+fn royalty_range_bounds_basis_points() {
+    let query = SearchAssetsQuery {
+        royalty_amount_min: Some(100),
+        royalty_amount_max: Some(500),
+        ..Default::default()
+    };
+    assert_eq!(query.count_conditions(), 2);
+    let sql = search_sql(query);
+    assert!(
+        sql.contains(r#""asset"."royalty_amount" >= 100"#),
+        "{}",
+        sql
+    );
+    assert!(
+        sql.contains(r#""asset"."royalty_amount" <= 500"#),
+        "{}",
+        sql
+    );
+}
+
+#[test]
+fn royalty_recipient_matches_target_or_creator_with_share() {
+    let sql = search_sql(SearchAssetsQuery {
+        royalty_recipient: Some(vec![1; 32]),
+        ..Default::default()
+    });
+    assert!(sql.contains(r#""asset"."royalty_target" = "#), "{}", sql);
+    assert!(sql.contains(r#" OR "asset"."id" IN (SELECT "#), "{}", sql);
+    assert!(sql.contains(r#""asset_creators"."share" > 0"#), "{}", sql);
+}
+
+#[test]
+fn creator_share_range_bounds_the_joined_creator() {
+    let query = SearchAssetsQuery {
+        creator_address: Some(vec![1; 32]),
+        creator_share_min: Some(50),
+        creator_share_max: Some(100),
+        ..Default::default()
+    };
+    let (_, joins) = query.conditions().unwrap();
+    assert_eq!(joins.len(), 1);
+    let sql = search_sql(query);
+    assert!(sql.contains(r#""asset_creators"."share" >= 50"#), "{}", sql);
+    assert!(
+        sql.contains(r#""asset_creators"."share" <= 100"#),
+        "{}",
+        sql
+    );
+}
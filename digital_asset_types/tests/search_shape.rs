@@ -0,0 +1,34 @@
+use digital_asset_types::dao::SearchAssetsQuery;
+use sea_orm::sea_query::ConditionType;
+
+#[test]
+fn shapes_name_the_filters_without_their_values() {
+    let query = SearchAssetsQuery {
+        owner_address: Some(vec![1; 32]),
+        compressed: Some(true),
+        json_uri: Some("https://example.com/1.json".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(query.shape(), "all(ownerAddress, compressed, jsonUri)");
+
+    let other_owner = SearchAssetsQuery {
+        owner_address: Some(vec![2; 32]),
+        compressed: Some(false),
+        json_uri: Some("https://example.com/2.json".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(query.shape(), other_owner.shape());
+}
+
+#[test]
+fn shapes_keep_the_condition_type_and_negation() {
+    let query = SearchAssetsQuery {
+        negate: Some(true),
+        condition_type: Some(ConditionType::Any),
+        creator_address: Some(vec![1; 32]),
+        grouping: Some(("collection".to_string(), "x".to_string())),
+        ..Default::default()
+    };
+    assert_eq!(query.shape(), "not any(creatorAddress, grouping)");
+    assert_eq!(SearchAssetsQuery::default().shape(), "all()");
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod common;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dao::{token_accounts, tokens};
+use digital_asset_types::dapi::{add_token_info, common::asset_list_to_rpc};
+use digital_asset_types::rpc::{transform::AssetTransform, TokenInfo};
+
+#[tokio::test]
+async fn indexed_mints_get_token_info() -> Result<(), DbErr> {
+    let owner = Keypair::new().pubkey();
+    let mint = Keypair::new().pubkey();
+    let unindexed = Keypair::new().pubkey();
+    let token_account = Keypair::new().pubkey();
+    let authority = Keypair::new().pubkey();
+    let token_program = Keypair::new().pubkey();
+    let transform = AssetTransform::default();
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            create_full_asset(mint, owner),
+            create_full_asset(unindexed, owner),
+        ],
+        &transform,
+    );
+    assert!(errors.is_empty());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![tokens::Model {
+            mint: mint.to_bytes().to_vec(),
+            supply: Decimal::from(1),
+            decimals: 0,
+            token_program: token_program.to_bytes().to_vec(),
+            mint_authority: Some(authority.to_bytes().to_vec()),
+            freeze_authority: None,
+            close_authority: None,
+            extension_data: None,
+            slot_updated: 1,
+        }]])
+        .append_query_results(vec![vec![token_accounts::Model {
+            pubkey: token_account.to_bytes().to_vec(),
+            mint: mint.to_bytes().to_vec(),
+            amount: Decimal::from(1),
+            owner: owner.to_bytes().to_vec(),
+            frozen: false,
+            close_authority: None,
+            delegate: None,
+            delegated_amount: Decimal::from(0),
+            slot_updated: 1,
+            token_program: token_program.to_bytes().to_vec(),
+        }]])
+        .into_connection();
+    add_token_info(&db, &mut assets).await?;
+
+    assert_eq!(
+        assets[0].token_info,
+        Some(TokenInfo {
+            balance: 1,
+            supply: 1,
+            decimals: 0,
+            token_program: token_program.to_string(),
+            associated_token_address: Some(token_account.to_string()),
+            mint_authority: Some(authority.to_string()),
+            freeze_authority: None,
+        })
+    );
+    assert_eq!(assets[1].token_info, None);
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod common;
+
+use std::collections::BTreeMap;
+
+use sea_orm::{entity::prelude::*, DatabaseBackend, MockDatabase, Value};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+use common::*;
+use digital_asset_types::dapi::{add_tree_freshness, common::asset_list_to_rpc};
+use digital_asset_types::rpc::transform::AssetTransform;
+
+fn freshness(tree_seq: i64, leaf_seq: i64, leaf_hash: &[u8]) -> BTreeMap<&'static str, Value> {
+    BTreeMap::from([
+        ("tree_seq", Value::from(tree_seq)),
+        ("leaf_seq", Value::from(leaf_seq)),
+        ("leaf_hash", Value::from(leaf_hash.to_vec())),
+    ])
+}
+
+#[tokio::test]
+async fn compressed_assets_show_how_far_the_tree_moved_past_them() -> Result<(), DbErr> {
+    let (leaf, other_leaf) = (vec![1u8; 32], vec![2u8; 32]);
+    let compressed = || {
+        let mut full_asset = create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey());
+        full_asset.asset.compressed = true;
+        full_asset.asset.tree_id = Some(Keypair::new().pubkey().to_bytes().to_vec());
+        full_asset.asset.nonce = Some(3);
+        full_asset.asset.leaf = Some(leaf.clone());
+        full_asset.asset.seq = Some(8);
+        full_asset
+    };
+    let (mut assets, errors) = asset_list_to_rpc(
+        vec![
+            compressed(),
+            compressed(),
+            create_full_asset(Keypair::new().pubkey(), Keypair::new().pubkey()),
+        ],
+        &AssetTransform::default(),
+    );
+    assert!(errors.is_empty());
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![freshness(12, 8, &leaf)]])
+        .append_query_results(vec![vec![freshness(12, 10, &other_leaf)]])
+        .into_connection();
+    add_tree_freshness(&db, &mut assets).await?;
+
+    let indexed = assets[0].compression.as_ref().unwrap();
+    assert_eq!(indexed.seq, 8);
+    assert_eq!(indexed.tree_seq, Some(12));
+    assert_eq!(indexed.leaf_seq, Some(8));
+    assert_eq!(indexed.leaf_consistent, Some(true));
+
+    let lagging = assets[1].compression.as_ref().unwrap();
+    assert_eq!(lagging.leaf_seq, Some(10));
+    assert_eq!(lagging.leaf_consistent, Some(false));
+
+    let uncompressed = assets[2].compression.as_ref().unwrap();
+    assert_eq!(uncompressed.tree_seq, None);
+    assert_eq!(uncompressed.leaf_consistent, None);
+
+    // Only the compressed assets are looked up.
+    assert_eq!(db.into_transaction_log().len(), 2);
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Compression of the flatbuffers the geyser plugin and the tools write to the messenger
+//! streams, shared by the writers and the ingester reading them.
+
+use std::{borrow::Cow, io};
+
+// Compressed payloads start with this header followed by a codec byte. A flatbuffer starts with
+// the offset of its root table, which can't be this large, so payloads from writers that don't
+// compress, like the geyser plugin, are told apart without any negotiation.
+const PAYLOAD_MAGIC: [u8; 4] = *b"DASP";
+const HEADER_SIZE: usize = PAYLOAD_MAGIC.len() + 1;
+
+const CODEC_ZSTD: u8 = 1;
+const ZSTD_LEVEL: i32 = 3;
+// Assumed when a compressed payload doesn't record its size.
+const ZSTD_RATIO: usize = 4;
+
+/// How serialized flatbuffers are compressed before they are sent to a messenger stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum PayloadCompression {
+    #[default]
+    None,
+    Zstd,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PayloadError {
+    #[error("Unknown payload codec: {0}")]
+    UnknownCodec(u8),
+    #[error("Failed to decompress payload: {0}")]
+    Decompress(#[from] io::Error),
+}
+
+pub fn encode_payload(data: &[u8], compression: PayloadCompression) -> io::Result<Cow<[u8]>> {
+    match compression {
+        PayloadCompression::None => Ok(Cow::Borrowed(data)),
+        PayloadCompression::Zstd => {
+            let mut payload = Vec::with_capacity(HEADER_SIZE + data.len() / 3);
+            payload.extend_from_slice(&PAYLOAD_MAGIC);
+            payload.push(CODEC_ZSTD);
+            // Unlike the streaming encoder, this records the decompressed size in the frame.
+            payload.extend(zstd::bulk::compress(data, ZSTD_LEVEL)?);
+            Ok(Cow::Owned(payload))
+        }
+    }
+}
+
+/// Returns the flatbuffer in a payload read from a messenger stream, decompressing it if it
+/// was compressed.
+pub fn decode_payload(payload: &[u8]) -> Result<Cow<[u8]>, PayloadError> {
+    if payload.len() < HEADER_SIZE || payload[..PAYLOAD_MAGIC.len()] != PAYLOAD_MAGIC {
+        return Ok(Cow::Borrowed(payload));
+    }
+    match payload[PAYLOAD_MAGIC.len()] {
+        CODEC_ZSTD => {
+            let data = zstd::stream::decode_all(&payload[HEADER_SIZE..])?;
+            Ok(Cow::Owned(data))
+        }
+        codec => Err(PayloadError::UnknownCodec(codec)),
+    }
+}
+
+/// The size of the flatbuffer in a payload without decompressing it. Compressed payloads from
+/// older writers don't record it and are estimated.
+pub fn decoded_len(payload: &[u8]) -> usize {
+    if payload.len() < HEADER_SIZE || payload[..PAYLOAD_MAGIC.len()] != PAYLOAD_MAGIC {
+        return payload.len();
+    }
+    let frame = &payload[HEADER_SIZE..];
+    match payload[PAYLOAD_MAGIC.len()] {
+        CODEC_ZSTD => match zstd::zstd_safe::get_frame_content_size(frame) {
+            Ok(Some(size)) => size as usize,
+            _ => frame.len() * ZSTD_RATIO,
+        },
+        _ => payload.len(),
+    }
+}
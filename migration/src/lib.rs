@@ -31,11 +31,52 @@ mod m20230720_120101_add_asset_grouping_verified;
 mod m20230720_130101_remove_asset_grouping_null_constraints;
 mod m20230724_120101_add_group_info_seq;
 mod m20230726_013107_remove_not_null_constraint_from_group_value;
+mod m20230801_120101_add_token_account_balances;
+mod m20230802_120101_add_pending_creator_verifications;
+mod m20230803_120101_add_asset_authority_history;
+mod m20230803_130101_add_task_leases;
+mod m20230803_140101_add_collection_traits;
+mod m20230803_150101_add_asset_editions;
+mod m20230804_120101_add_escrow_owners;
+mod m20230804_130101_partition_cl_items_by_tree;
+mod m20230805_120101_add_asset_pricing;
+mod m20230805_130101_add_merkle_tree;
+mod m20230806_120101_add_asset_data_history;
+mod m20230807_120101_add_cl_items_proof_path;
+mod m20230808_120101_add_asset_leaf_delegate;
+mod m20230809_120101_add_failed_tx_log;
+mod m20230810_120101_add_cl_audits_leaf_index;
+mod m20230811_120101_add_token_records;
+mod m20230812_120101_add_backfill_queue;
+mod m20230813_120101_add_asset_owner_history;
+mod m20230814_120101_add_imported_assets;
+mod m20230815_120101_add_asset_data_files;
+mod m20230816_120101_add_asset_network;
+mod m20230817_120101_add_index_suggestions;
+mod m20230818_120101_add_bootstrap_state;
+mod m20230819_120101_add_cl_items_history;
+mod m20230820_120101_add_asset_owner_pdas;
+mod m20230821_120101_widen_token_amounts;
+mod m20230822_120101_add_collection_stats;
+mod m20230823_120101_add_failed_slots;
+mod m20230824_120101_add_asset_state_history;
+mod m20230825_120101_add_merkle_tree_canopy;
+mod m20230826_120101_add_hot_owner_assets;
+mod m20230827_120101_add_asset_data_full_metadata;
+mod m20230828_120101_add_asset_change_outbox;
+mod m20230829_120101_add_asset_leaf_indexes;
+mod m20230830_120101_add_standby_checkpoints;
+mod m20230831_120101_add_metadata_blob;
+mod m20230901_120101_add_failed_slot_signatures;
+mod m20230902_120101_asset_network_follows_writer;
+mod m20230903_120101_add_tasks_unfinished_index;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
+    // The binaries check the database against `digital_asset_types::dao::schema::SCHEMA_VERSION`
+    // on startup, bump it to the name of every migration added here.
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
         vec![
             Box::new(m20220101_000001_init::Migration),
@@ -69,6 +110,45 @@ impl MigratorTrait for Migrator {
             Box::new(m20230720_120101_add_asset_grouping_verified::Migration),
             Box::new(m20230720_130101_remove_asset_grouping_null_constraints::Migration),
             Box::new(m20230724_120101_add_group_info_seq::Migration),
+            Box::new(m20230801_120101_add_token_account_balances::Migration),
+            Box::new(m20230802_120101_add_pending_creator_verifications::Migration),
+            Box::new(m20230803_120101_add_asset_authority_history::Migration),
+            Box::new(m20230803_130101_add_task_leases::Migration),
+            Box::new(m20230803_140101_add_collection_traits::Migration),
+            Box::new(m20230803_150101_add_asset_editions::Migration),
+            Box::new(m20230804_120101_add_escrow_owners::Migration),
+            Box::new(m20230804_130101_partition_cl_items_by_tree::Migration),
+            Box::new(m20230805_120101_add_asset_pricing::Migration),
+            Box::new(m20230805_130101_add_merkle_tree::Migration),
+            Box::new(m20230806_120101_add_asset_data_history::Migration),
+            Box::new(m20230807_120101_add_cl_items_proof_path::Migration),
+            Box::new(m20230808_120101_add_asset_leaf_delegate::Migration),
+            Box::new(m20230809_120101_add_failed_tx_log::Migration),
+            Box::new(m20230810_120101_add_cl_audits_leaf_index::Migration),
+            Box::new(m20230811_120101_add_token_records::Migration),
+            Box::new(m20230812_120101_add_backfill_queue::Migration),
+            Box::new(m20230813_120101_add_asset_owner_history::Migration),
+            Box::new(m20230814_120101_add_imported_assets::Migration),
+            Box::new(m20230815_120101_add_asset_data_files::Migration),
+            Box::new(m20230816_120101_add_asset_network::Migration),
+            Box::new(m20230817_120101_add_index_suggestions::Migration),
+            Box::new(m20230818_120101_add_bootstrap_state::Migration),
+            Box::new(m20230819_120101_add_cl_items_history::Migration),
+            Box::new(m20230820_120101_add_asset_owner_pdas::Migration),
+            Box::new(m20230821_120101_widen_token_amounts::Migration),
+            Box::new(m20230822_120101_add_collection_stats::Migration),
+            Box::new(m20230823_120101_add_failed_slots::Migration),
+            Box::new(m20230824_120101_add_asset_state_history::Migration),
+            Box::new(m20230825_120101_add_merkle_tree_canopy::Migration),
+            Box::new(m20230826_120101_add_hot_owner_assets::Migration),
+            Box::new(m20230827_120101_add_asset_data_full_metadata::Migration),
+            Box::new(m20230828_120101_add_asset_change_outbox::Migration),
+            Box::new(m20230829_120101_add_asset_leaf_indexes::Migration),
+            Box::new(m20230830_120101_add_standby_checkpoints::Migration),
+            Box::new(m20230831_120101_add_metadata_blob::Migration),
+            Box::new(m20230901_120101_add_failed_slot_signatures::Migration),
+            Box::new(m20230902_120101_asset_network_follows_writer::Migration),
+            Box::new(m20230903_120101_add_tasks_unfinished_index::Migration),
         ]
     }
 }
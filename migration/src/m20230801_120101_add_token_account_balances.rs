@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TokenAccountBalances::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::TokenAccount)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::Owner)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::Mint)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::Amount)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenAccountBalances::Slot)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("token_account_balances_account_slot")
+                    .table(TokenAccountBalances::Table)
+                    .col(TokenAccountBalances::TokenAccount)
+                    .col(TokenAccountBalances::Slot)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("token_account_balances_owner_mint_slot")
+                    .table(TokenAccountBalances::Table)
+                    .col(TokenAccountBalances::Owner)
+                    .col(TokenAccountBalances::Mint)
+                    .col(TokenAccountBalances::Slot)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TokenAccountBalances::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TokenAccountBalances {
+    Table,
+    Id,
+    TokenAccount,
+    Owner,
+    Mint,
+    Amount,
+    Slot,
+}
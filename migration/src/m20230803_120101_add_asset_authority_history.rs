@@ -0,0 +1,158 @@
+use enum_iterator::{all, Sequence};
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(AuthorityAccounts::AuthorityKind)
+                    .values(all::<AuthorityKind>().collect::<Vec<_>>())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetAuthorityHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AssetAuthorityHistory::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetAuthorityHistory::AssetId)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetAuthorityHistory::Authority)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetAuthorityHistory::SlotUpdated)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetAuthorityHistory::Seq)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_authority_history_asset_authority_slot")
+                    .table(AssetAuthorityHistory::Table)
+                    .col(AssetAuthorityHistory::AssetId)
+                    .col(AssetAuthorityHistory::Authority)
+                    .col(AssetAuthorityHistory::SlotUpdated)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_authority_history_asset_slot")
+                    .table(AssetAuthorityHistory::Table)
+                    .col(AssetAuthorityHistory::AssetId)
+                    .col(AssetAuthorityHistory::SlotUpdated)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthorityAccounts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuthorityAccounts::Authority)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorityAccounts::Kind)
+                            .enumeration(
+                                AuthorityAccounts::AuthorityKind,
+                                all::<AuthorityKind>().collect::<Vec<_>>(),
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AuthorityAccounts::Owner).binary().null())
+                    .col(
+                        ColumnDef::new(AuthorityAccounts::ResolvedAt)
+                            .date_time()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp))
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthorityAccounts::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(AssetAuthorityHistory::Table).to_owned())
+            .await?;
+        manager
+            .drop_type(
+                Type::drop()
+                    .name(AuthorityAccounts::AuthorityKind)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden, Debug, PartialEq, Sequence)]
+enum AuthorityKind {
+    Wallet,
+    Pda,
+    Multisig,
+    Program,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AssetAuthorityHistory {
+    Table,
+    Id,
+    AssetId,
+    Authority,
+    SlotUpdated,
+    Seq,
+}
+
+#[derive(Iden)]
+enum AuthorityAccounts {
+    AuthorityKind,
+    Table,
+    Authority,
+    Kind,
+    Owner,
+    ResolvedAt,
+}
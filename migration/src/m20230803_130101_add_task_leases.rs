@@ -0,0 +1,53 @@
+use digital_asset_types::dao::tasks;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(tasks::Entity)
+                    .add_column(
+                        ColumnDef::new(Alias::new("heartbeat_at"))
+                            .date_time()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("tasks_locked_until")
+                    .table(tasks::Entity)
+                    .col(tasks::Column::LockedUntil)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("tasks_locked_until")
+                    .table(tasks::Entity)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(tasks::Entity)
+                    .drop_column(Alias::new("heartbeat_at"))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
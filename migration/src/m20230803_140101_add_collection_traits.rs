@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CollectionTraits::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CollectionTraits::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(CollectionTraits::Collection)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CollectionTraits::TraitType)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CollectionTraits::Value).text().not_null())
+                    .col(
+                        ColumnDef::new(CollectionTraits::Count)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("collection_traits_collection_trait_value")
+                    .table(CollectionTraits::Table)
+                    .col(CollectionTraits::Collection)
+                    .col(CollectionTraits::TraitType)
+                    .col(CollectionTraits::Value)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CollectionTraits::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum CollectionTraits {
+    Table,
+    Id,
+    Collection,
+    TraitType,
+    Value,
+    Count,
+}
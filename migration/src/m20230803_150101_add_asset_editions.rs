@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetEditions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AssetEditions::Id)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AssetEditions::Parent).binary().not_null())
+                    .col(
+                        ColumnDef::new(AssetEditions::Edition)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetEditions::SlotUpdated)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_editions_parent_edition")
+                    .table(AssetEditions::Table)
+                    .col(AssetEditions::Parent)
+                    .col(AssetEditions::Edition)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AssetEditions::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AssetEditions {
+    Table,
+    Id,
+    Parent,
+    Edition,
+    SlotUpdated,
+}
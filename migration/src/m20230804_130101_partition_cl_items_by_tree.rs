@@ -0,0 +1,171 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Partitions are named after the md5 of the tree, the hex encoded tree is too long for a table name.
+const PARTITION_FUNCTIONS: [&str; 3] = [
+    "
+CREATE OR REPLACE FUNCTION tree_partition_name(parent text, tree bytea) RETURNS text
+LANGUAGE sql IMMUTABLE AS $$
+    SELECT parent || '_' || md5(tree)
+$$
+",
+    // The partition is created on its own and then attached, attaching doesn't block reads and
+    // writes of the other trees' partitions.
+    "
+CREATE OR REPLACE FUNCTION create_tree_partition(parent text, tree bytea) RETURNS void
+LANGUAGE plpgsql AS $$
+DECLARE
+    partition text := tree_partition_name(parent, tree);
+BEGIN
+    IF to_regclass(partition) IS NOT NULL THEN
+        RETURN;
+    END IF;
+    EXECUTE format('CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS)', partition, parent);
+    EXECUTE format(
+        'ALTER TABLE %I ATTACH PARTITION %I FOR VALUES IN (%L::bytea)',
+        parent, partition, tree
+    );
+EXCEPTION WHEN duplicate_table OR unique_violation THEN
+    -- Created concurrently by another writer.
+    RETURN;
+END;
+$$
+",
+    "
+CREATE OR REPLACE FUNCTION drop_tree_partitions(tree bytea) RETURNS void
+LANGUAGE plpgsql AS $$
+BEGIN
+    EXECUTE format(
+        'DROP TABLE IF EXISTS %I, %I',
+        tree_partition_name('cl_items', tree),
+        tree_partition_name('cl_audits', tree)
+    );
+END;
+$$
+",
+];
+
+const PARTITION_TABLES: [&str; 19] = [
+    "ALTER TABLE cl_items RENAME TO cl_items_unpartitioned",
+    "
+CREATE TABLE cl_items (
+    id       bigint NOT NULL DEFAULT nextval('cl_items_id_seq'),
+    tree     bytea  NOT NULL,
+    node_idx bigint NOT NULL,
+    leaf_idx bigint,
+    seq      bigint NOT NULL,
+    level    bigint NOT NULL,
+    hash     bytea  NOT NULL,
+    PRIMARY KEY (tree, id)
+) PARTITION BY LIST (tree)
+",
+    "ALTER SEQUENCE cl_items_id_seq OWNED BY cl_items.id",
+    "SELECT create_tree_partition('cl_items', tree) FROM (SELECT DISTINCT tree FROM cl_items_unpartitioned) t",
+    "INSERT INTO cl_items SELECT id, tree, node_idx, leaf_idx, seq, level, hash FROM cl_items_unpartitioned",
+    "DROP TABLE cl_items_unpartitioned",
+    // Indexes are created after the copy, on every partition at once. A per tree index is
+    // unnecessary now that each tree has its own partition.
+    "CREATE UNIQUE INDEX cl_items__tree_node ON cl_items (tree, node_idx)",
+    "CREATE INDEX cl_items_leaf_idx ON cl_items (leaf_idx)",
+    "CREATE INDEX cl_items_node_idx ON cl_items (node_idx)",
+    "CREATE INDEX cl_items_level ON cl_items (level)",
+    "CREATE INDEX cl_items_hash_idx ON cl_items (hash)",
+    "ALTER TABLE cl_audits RENAME TO cl_audits_unpartitioned",
+    "
+CREATE TABLE cl_audits (
+    id          bigint    NOT NULL DEFAULT nextval('cl_audits_id_seq'),
+    tree        bytea     NOT NULL,
+    node_idx    bigint    NOT NULL,
+    leaf_idx    bigint,
+    seq         bigint    NOT NULL,
+    level       bigint    NOT NULL,
+    hash        bytea     NOT NULL,
+    created_at  timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    tx          varchar   NOT NULL,
+    instruction varchar   NOT NULL
+) PARTITION BY LIST (tree)
+",
+    "ALTER SEQUENCE cl_audits_id_seq OWNED BY cl_audits.id",
+    "SELECT create_tree_partition('cl_audits', tree) FROM (SELECT DISTINCT tree FROM cl_audits_unpartitioned) t",
+    "INSERT INTO cl_audits SELECT id, tree, node_idx, leaf_idx, seq, level, hash, created_at, tx, instruction FROM cl_audits_unpartitioned",
+    "DROP TABLE cl_audits_unpartitioned",
+    "ALTER TABLE cl_audits ADD CONSTRAINT unique_tree_tx_nodeidx_seq UNIQUE (tree, node_idx, seq, hash, tx)",
+    "CREATE INDEX idx_cl_audits_leaf_id ON cl_audits (leaf_idx)",
+];
+
+const UNPARTITION_TABLES: [&str; 19] = [
+    "ALTER TABLE cl_items RENAME TO cl_items_partitioned",
+    "
+CREATE TABLE cl_items (
+    id       bigint NOT NULL DEFAULT nextval('cl_items_id_seq') PRIMARY KEY,
+    tree     bytea  NOT NULL,
+    node_idx bigint NOT NULL,
+    leaf_idx bigint,
+    seq      bigint NOT NULL,
+    level    bigint NOT NULL,
+    hash     bytea  NOT NULL
+)
+",
+    "ALTER SEQUENCE cl_items_id_seq OWNED BY cl_items.id",
+    "INSERT INTO cl_items SELECT id, tree, node_idx, leaf_idx, seq, level, hash FROM cl_items_partitioned",
+    "DROP TABLE cl_items_partitioned",
+    "CREATE UNIQUE INDEX cl_items__tree_node ON cl_items (tree, node_idx)",
+    "CREATE INDEX cl_items_leaf_idx ON cl_items (leaf_idx)",
+    "CREATE INDEX cl_items_node_idx ON cl_items (node_idx)",
+    "CREATE INDEX cl_items_level ON cl_items (level)",
+    "CREATE INDEX cl_items_hash_idx ON cl_items (hash)",
+    "ALTER TABLE cl_audits RENAME TO cl_audits_partitioned",
+    "
+CREATE TABLE cl_audits (
+    id          bigint    NOT NULL DEFAULT nextval('cl_audits_id_seq'),
+    tree        bytea     NOT NULL,
+    node_idx    bigint    NOT NULL,
+    leaf_idx    bigint,
+    seq         bigint    NOT NULL,
+    level       bigint    NOT NULL,
+    hash        bytea     NOT NULL,
+    created_at  timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    tx          varchar   NOT NULL,
+    instruction varchar   NOT NULL
+)
+",
+    "ALTER SEQUENCE cl_audits_id_seq OWNED BY cl_audits.id",
+    "INSERT INTO cl_audits SELECT id, tree, node_idx, leaf_idx, seq, level, hash, created_at, tx, instruction FROM cl_audits_partitioned",
+    "DROP TABLE cl_audits_partitioned",
+    "ALTER TABLE cl_audits ADD CONSTRAINT unique_tree_tx_nodeidx_seq UNIQUE (tree, node_idx, seq, hash, tx)",
+    "CREATE INDEX idx_cl_audits_tree ON cl_audits (tree)",
+    "CREATE INDEX idx_cl_audits_leaf_id ON cl_audits (leaf_idx)",
+    "DROP FUNCTION drop_tree_partitions(bytea), create_tree_partition(text, bytea), tree_partition_name(text, bytea)",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in PARTITION_FUNCTIONS.iter().chain(PARTITION_TABLES.iter()) {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in UNPARTITION_TABLES.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetPricing::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AssetPricing::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AssetPricing::AssetId).binary().not_null())
+                    .col(ColumnDef::new(AssetPricing::Provider).string().not_null())
+                    .col(ColumnDef::new(AssetPricing::Price).double().not_null())
+                    .col(ColumnDef::new(AssetPricing::Currency).string().not_null())
+                    .col(
+                        ColumnDef::new(AssetPricing::UpdatedAt)
+                            .date_time()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp))
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Each provider keeps one estimate per asset.
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_pricing_asset_provider")
+                    .table(AssetPricing::Table)
+                    .col(AssetPricing::AssetId)
+                    .col(AssetPricing::Provider)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AssetPricing::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AssetPricing {
+    Table,
+    Id,
+    AssetId,
+    Provider,
+    Price,
+    Currency,
+    UpdatedAt,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Columns are filled from different sources, the create_tree instruction, the tree
+        // config account and the tree account, so any of them may still be missing.
+        manager
+            .create_table(
+                Table::create()
+                    .table(MerkleTree::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MerkleTree::Tree)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MerkleTree::Config).binary().not_null())
+                    .col(ColumnDef::new(MerkleTree::Creator).binary())
+                    .col(ColumnDef::new(MerkleTree::Delegate).binary())
+                    .col(ColumnDef::new(MerkleTree::MaxDepth).integer())
+                    .col(ColumnDef::new(MerkleTree::MaxBufferSize).integer())
+                    .col(ColumnDef::new(MerkleTree::CanopyDepth).integer())
+                    .col(ColumnDef::new(MerkleTree::Public).boolean())
+                    .col(ColumnDef::new(MerkleTree::TotalMintCapacity).big_integer())
+                    .col(ColumnDef::new(MerkleTree::NumMinted).big_integer())
+                    .col(ColumnDef::new(MerkleTree::CreationSlot).big_integer())
+                    .col(ColumnDef::new(MerkleTree::SlotUpdated).big_integer())
+                    .col(
+                        ColumnDef::new(MerkleTree::CreatedAt)
+                            .date_time()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp))
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tree config account updates only know the config address.
+        manager
+            .create_index(
+                Index::create()
+                    .name("merkle_tree_config")
+                    .table(MerkleTree::Table)
+                    .col(MerkleTree::Config)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("merkle_tree_creator")
+                    .table(MerkleTree::Table)
+                    .col(MerkleTree::Creator)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MerkleTree::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MerkleTree {
+    Table,
+    Tree,
+    Config,
+    Creator,
+    Delegate,
+    MaxDepth,
+    MaxBufferSize,
+    CanopyDepth,
+    Public,
+    TotalMintCapacity,
+    NumMinted,
+    CreationSlot,
+    SlotUpdated,
+    CreatedAt,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetDataHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AssetDataHistory::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetDataHistory::AssetId)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AssetDataHistory::Name).text().not_null())
+                    .col(ColumnDef::new(AssetDataHistory::Symbol).text().not_null())
+                    .col(
+                        ColumnDef::new(AssetDataHistory::MetadataUrl)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetDataHistory::SlotUpdated)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_data_history_asset_slot")
+                    .table(AssetDataHistory::Table)
+                    .col(AssetDataHistory::AssetId)
+                    .col(AssetDataHistory::SlotUpdated)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // The current data of indexed assets is their first known version, so their next change
+        // shows up as a mutation.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "
+INSERT INTO asset_data_history (asset_id, name, symbol, metadata_url, slot_updated)
+SELECT id, COALESCE(chain_data->>'name', ''), COALESCE(chain_data->>'symbol', ''), metadata_url, slot_updated
+FROM asset_data
+ON CONFLICT DO NOTHING
+"
+                .to_string(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AssetDataHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AssetDataHistory {
+    Table,
+    Id,
+    AssetId,
+    Name,
+    Symbol,
+    MetadataUrl,
+    SlotUpdated,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ADD_PROOF_PATH: [&str; 5] = [
+    // The node indexes a proof of node `node_idx` is made of, its sibling on each level up to the
+    // root and then the root.
+    "
+CREATE OR REPLACE FUNCTION cl_proof_path(node_idx bigint) RETURNS bigint[]
+LANGUAGE sql IMMUTABLE PARALLEL SAFE AS $$
+    SELECT array_append(coalesce(array_agg((node_idx >> l) # 1 ORDER BY l), '{}'), 1::bigint)
+    FROM generate_series(0, 62) l
+    WHERE (node_idx >> (l + 1)) > 0
+$$
+",
+    // Only leaves need their path, so every other node stores a null.
+    "
+ALTER TABLE cl_items ADD COLUMN proof_path bigint[]
+GENERATED ALWAYS AS (CASE WHEN level = 0 THEN cl_proof_path(node_idx) END) STORED
+",
+    // A proof is read with an index only scan for the leaf and one for its path.
+    "CREATE INDEX cl_items_leaf_proof_path ON cl_items (tree, leaf_idx) INCLUDE (node_idx, hash, proof_path) WHERE level = 0",
+    "CREATE INDEX cl_items_tree_node_covering ON cl_items (tree, node_idx) INCLUDE (level, seq, hash)",
+    // New partitions have to generate the path like the parent does to be attached.
+    "
+CREATE OR REPLACE FUNCTION create_tree_partition(parent text, tree bytea) RETURNS void
+LANGUAGE plpgsql AS $$
+DECLARE
+    partition text := tree_partition_name(parent, tree);
+BEGIN
+    IF to_regclass(partition) IS NOT NULL THEN
+        RETURN;
+    END IF;
+    EXECUTE format(
+        'CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS INCLUDING GENERATED)',
+        partition, parent
+    );
+    EXECUTE format(
+        'ALTER TABLE %I ATTACH PARTITION %I FOR VALUES IN (%L::bytea)',
+        parent, partition, tree
+    );
+EXCEPTION WHEN duplicate_table OR unique_violation THEN
+    -- Created concurrently by another writer.
+    RETURN;
+END;
+$$
+",
+];
+
+const DROP_PROOF_PATH: [&str; 5] = [
+    "DROP INDEX IF EXISTS cl_items_tree_node_covering",
+    "DROP INDEX IF EXISTS cl_items_leaf_proof_path",
+    "ALTER TABLE cl_items DROP COLUMN IF EXISTS proof_path",
+    "DROP FUNCTION IF EXISTS cl_proof_path(bigint)",
+    "
+CREATE OR REPLACE FUNCTION create_tree_partition(parent text, tree bytea) RETURNS void
+LANGUAGE plpgsql AS $$
+DECLARE
+    partition text := tree_partition_name(parent, tree);
+BEGIN
+    IF to_regclass(partition) IS NOT NULL THEN
+        RETURN;
+    END IF;
+    EXECUTE format('CREATE TABLE %I (LIKE %I INCLUDING DEFAULTS)', partition, parent);
+    EXECUTE format(
+        'ALTER TABLE %I ATTACH PARTITION %I FOR VALUES IN (%L::bytea)',
+        parent, partition, tree
+    );
+EXCEPTION WHEN duplicate_table OR unique_violation THEN
+    -- Created concurrently by another writer.
+    RETURN;
+END;
+$$
+",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_PROOF_PATH.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in DROP_PROOF_PATH.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ADD_LEAF_DELEGATE: [&str; 3] = [
+    "ALTER TABLE asset ADD COLUMN leaf_delegate bytea",
+    // Compressed assets only ever had their leaf delegate in `delegate`.
+    "UPDATE asset SET leaf_delegate = delegate WHERE compressed AND delegate IS NOT NULL",
+    "CREATE INDEX asset_leaf_delegate ON asset (leaf_delegate) WHERE leaf_delegate IS NOT NULL",
+];
+
+const DROP_LEAF_DELEGATE: [&str; 2] = [
+    "DROP INDEX IF EXISTS asset_leaf_delegate",
+    "ALTER TABLE asset DROP COLUMN IF EXISTS leaf_delegate",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_LEAF_DELEGATE.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in DROP_LEAF_DELEGATE.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ADD_FAILED_TX_LOG: [&str; 2] = [
+    // A transaction touching several known trees has a row for each of them.
+    "
+CREATE TABLE IF NOT EXISTS failed_tx_log (
+    signature text NOT NULL,
+    tree bytea NOT NULL,
+    error text NOT NULL,
+    slot bigint NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (signature, tree)
+)
+",
+    "CREATE INDEX IF NOT EXISTS failed_tx_log_tree_slot ON failed_tx_log (tree, slot)",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_FAILED_TX_LOG.iter() {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS failed_tx_log".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Reads the latest transactions of a leaf without sorting its whole history. Only the leaf level
+// of a change log has a leaf index.
+const ADD_CL_AUDITS_LEAF_INDEX: &str = "
+CREATE INDEX IF NOT EXISTS cl_audits_tree_leaf_idx_created_at
+ON cl_audits (tree, leaf_idx, created_at)
+WHERE leaf_idx IS NOT NULL
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_CL_AUDITS_LEAF_INDEX.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP INDEX IF EXISTS cl_audits_tree_leaf_idx_created_at".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
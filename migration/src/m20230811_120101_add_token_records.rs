@@ -0,0 +1,87 @@
+use enum_iterator::{all, Sequence};
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TokenRecords::TokenRecordState)
+                    .values(all::<TokenRecordState>().collect::<Vec<_>>())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TokenRecords::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TokenRecords::Pubkey)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TokenRecords::State)
+                            .enumeration(
+                                TokenRecords::TokenRecordState,
+                                all::<TokenRecordState>().collect::<Vec<_>>(),
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TokenRecords::Delegate).binary().null())
+                    .col(ColumnDef::new(TokenRecords::DelegateRole).string().null())
+                    .col(ColumnDef::new(TokenRecords::LockedTransfer).binary().null())
+                    .col(
+                        ColumnDef::new(TokenRecords::SlotUpdated)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TokenRecords::Table).to_owned())
+            .await?;
+        manager
+            .drop_type(
+                Type::drop()
+                    .name(TokenRecords::TokenRecordState)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Iden, Debug, PartialEq, Sequence)]
+enum TokenRecordState {
+    Unlocked,
+    Locked,
+    Listed,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TokenRecords {
+    TokenRecordState,
+    Table,
+    Pubkey,
+    State,
+    Delegate,
+    DelegateRole,
+    LockedTransfer,
+    SlotUpdated,
+}
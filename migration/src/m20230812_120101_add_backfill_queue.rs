@@ -0,0 +1,79 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BackfillQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BackfillQueue::Tree)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BackfillQueue::ForceChk)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BackfillQueue::Slot).big_integer().not_null())
+                    .col(ColumnDef::new(BackfillQueue::LockedBy).string().null())
+                    .col(
+                        ColumnDef::new(BackfillQueue::LockedUntil)
+                            .date_time()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BackfillQueue::HeartbeatAt)
+                            .date_time()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BackfillQueue::CreatedAt)
+                            .date_time()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp))
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("backfill_queue_locked_until")
+                    .table(BackfillQueue::Table)
+                    .col(BackfillQueue::LockedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BackfillQueue::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum BackfillQueue {
+    Table,
+    Tree,
+    ForceChk,
+    Slot,
+    LockedBy,
+    LockedUntil,
+    HeartbeatAt,
+    CreatedAt,
+}
@@ -0,0 +1,113 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AssetOwnerHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AssetOwnerHistory::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetOwnerHistory::AssetId)
+                            .binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AssetOwnerHistory::Owner).binary().not_null())
+                    .col(
+                        ColumnDef::new(AssetOwnerHistory::SlotUpdated)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AssetOwnerHistory::Seq)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_owner_history_asset_owner_slot")
+                    .table(AssetOwnerHistory::Table)
+                    .col(AssetOwnerHistory::AssetId)
+                    .col(AssetOwnerHistory::Owner)
+                    .col(AssetOwnerHistory::SlotUpdated)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_owner_history_asset_slot")
+                    .table(AssetOwnerHistory::Table)
+                    .col(AssetOwnerHistory::AssetId)
+                    .col(AssetOwnerHistory::SlotUpdated)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("asset_owner_history_owner")
+                    .table(AssetOwnerHistory::Table)
+                    .col(AssetOwnerHistory::Owner)
+                    .to_owned(),
+            )
+            .await?;
+
+        // The current owner of indexed assets is their first known one, so the assets they hold
+        // now show up as lost once they are transferred.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "
+INSERT INTO asset_owner_history (asset_id, owner, slot_updated, seq)
+SELECT id, owner, COALESCE(slot_updated, 0), COALESCE(owner_delegate_seq, 0)
+FROM asset
+WHERE owner IS NOT NULL
+ON CONFLICT DO NOTHING
+"
+                .to_string(),
+            ))
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AssetOwnerHistory::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum AssetOwnerHistory {
+    Table,
+    Id,
+    AssetId,
+    Owner,
+    SlotUpdated,
+    Seq,
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ImportedAssets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ImportedAssets::AssetId)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ImportedAssets::Source).string().not_null())
+                    .col(
+                        ColumnDef::new(ImportedAssets::ImportedAt)
+                            .date_time()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp))
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ImportedAssets::VerifiedAt)
+                            .date_time()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ImportedAssets::VerifyError).text().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("imported_assets_verified_at")
+                    .table(ImportedAssets::Table)
+                    .col(ImportedAssets::VerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ImportedAssets::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ImportedAssets {
+    Table,
+    AssetId,
+    Source,
+    ImportedAt,
+    VerifiedAt,
+    VerifyError,
+}
@@ -0,0 +1,53 @@
+use digital_asset_types::dao::asset;
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A constant default fills the existing rows without rewriting the table.
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(asset::Entity)
+                    .add_column(
+                        ColumnDef::new(Alias::new("network"))
+                            .text()
+                            .not_null()
+                            .default("mainnet"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // New assets are on the network of the connection that inserts them, see the ingester's
+        // `network` setting.
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "
+ALTER TABLE asset ALTER COLUMN network
+SET DEFAULT COALESCE(NULLIF(current_setting('das.network', true), ''), 'mainnet')
+"
+                .to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(asset::Entity)
+                    .drop_column(Alias::new("network"))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Written by the API's index advisor, `calls` and `total_ms` add up over its reports.
+const ADD_INDEX_SUGGESTIONS: &str = "
+CREATE TABLE IF NOT EXISTS index_suggestions (
+    table_name text NOT NULL,
+    index_columns text NOT NULL,
+    suggested_index text NOT NULL,
+    example_query text NOT NULL,
+    calls bigint NOT NULL DEFAULT 0,
+    total_ms double precision NOT NULL DEFAULT 0,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (table_name, index_columns)
+)
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_INDEX_SUGGESTIONS.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS index_suggestions".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
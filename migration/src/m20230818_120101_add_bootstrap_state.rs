@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The ingester's bootstrap role resumes from these after a restart.
+const ADD_BOOTSTRAP_STATE: [&str; 2] = [
+    "
+CREATE TABLE IF NOT EXISTS bootstrap_phases (
+    phase text PRIMARY KEY,
+    position smallint NOT NULL,
+    status text NOT NULL DEFAULT 'pending',
+    total bigint NOT NULL DEFAULT 0,
+    done bigint NOT NULL DEFAULT 0,
+    error text,
+    started_at timestamptz,
+    finished_at timestamptz,
+    updated_at timestamptz NOT NULL DEFAULT now()
+)
+",
+    "
+CREATE TABLE IF NOT EXISTS bootstrap_trees (
+    tree bytea PRIMARY KEY,
+    creation_slot bigint NOT NULL,
+    seq bigint NOT NULL,
+    last_signature text,
+    transactions_sent bigint NOT NULL DEFAULT 0,
+    done bool NOT NULL DEFAULT false,
+    updated_at timestamptz NOT NULL DEFAULT now()
+)
+",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_BOOTSTRAP_STATE {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS bootstrap_trees, bootstrap_phases".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
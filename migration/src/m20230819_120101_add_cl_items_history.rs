@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ADD_CL_ITEMS_HISTORY: [&str; 4] = [
+    // Nodes written before this migration have no slot and count as finalized.
+    "ALTER TABLE cl_items ADD COLUMN IF NOT EXISTS slot bigint",
+    // The versions of nodes that were overwritten before the write replacing them was
+    // finalized, for proofs at finalized commitment.
+    "
+CREATE TABLE IF NOT EXISTS cl_items_history (
+    tree bytea NOT NULL,
+    node_idx bigint NOT NULL,
+    seq bigint NOT NULL,
+    slot bigint,
+    hash bytea NOT NULL,
+    replaced_slot bigint NOT NULL,
+    PRIMARY KEY (tree, node_idx, seq)
+)
+",
+    "CREATE INDEX IF NOT EXISTS cl_items_history_replaced_slot ON cl_items_history (replaced_slot)",
+    "
+CREATE TABLE IF NOT EXISTS finalized_slot (
+    id smallint PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    slot bigint NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now()
+)
+",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_CL_ITEMS_HISTORY {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in [
+            "DROP TABLE IF EXISTS finalized_slot, cl_items_history",
+            "ALTER TABLE cl_items DROP COLUMN IF EXISTS slot",
+        ] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Accounts derived from an asset that can own other assets, such as its token owned escrow.
+const ADD_ASSET_OWNER_PDAS: [&str; 2] = [
+    "
+CREATE TABLE IF NOT EXISTS asset_owner_pdas (
+    pda bytea PRIMARY KEY,
+    asset_id bytea NOT NULL,
+    program text NOT NULL,
+    created_at timestamp NOT NULL DEFAULT now()
+)
+",
+    "CREATE INDEX IF NOT EXISTS asset_owner_pdas_asset_id ON asset_owner_pdas (asset_id)",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_ASSET_OWNER_PDAS {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS asset_owner_pdas".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Token amounts use the whole range of a u64, which a bigint can't hold. The amounts above
+// i64::MAX were stored wrapped around into negative numbers, and are unwrapped on the way.
+const COLUMNS: [(&str, &str); 4] = [
+    ("token_accounts", "amount"),
+    ("token_accounts", "delegated_amount"),
+    ("tokens", "supply"),
+    ("token_account_balances", "amount"),
+];
+
+async fn alter(manager: &SchemaManager<'_>, using: &str, ty: &str) -> Result<(), DbErr> {
+    for (table, column) in COLUMNS {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                format!(
+                    "ALTER TABLE {table} ALTER COLUMN {column} TYPE {ty} USING {}",
+                    using.replace("{column}", column)
+                ),
+            ))
+            .await?;
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        alter(
+            manager,
+            "CASE WHEN {column} < 0 THEN {column}::numeric + 18446744073709551616 ELSE {column} END",
+            "numeric(20, 0)",
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        alter(
+            manager,
+            "(CASE WHEN {column} > 9223372036854775807 THEN {column} - 18446744073709551616 ELSE {column} END)::bigint",
+            "bigint",
+        )
+        .await
+    }
+}
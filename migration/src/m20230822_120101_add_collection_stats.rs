@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Activity counters of the verified collections, kept up to date by the ingester. The mints and
+// burns of the assets indexed so far are counted from their current state, their transfers
+// aren't and are counted from here on.
+const ADD_COLLECTION_STATS: [&str; 2] = [
+    "
+CREATE TABLE IF NOT EXISTS collection_stats (
+    collection text PRIMARY KEY,
+    mints bigint NOT NULL DEFAULT 0,
+    transfers bigint NOT NULL DEFAULT 0,
+    burns bigint NOT NULL DEFAULT 0,
+    last_activity_slot bigint NOT NULL DEFAULT 0,
+    updated_at timestamp NOT NULL DEFAULT now()
+)
+",
+    "
+INSERT INTO collection_stats (collection, mints, burns, last_activity_slot)
+SELECT g.group_value, count(*), count(*) FILTER (WHERE a.burnt), COALESCE(max(a.slot_updated), 0)
+FROM asset_grouping g
+JOIN asset a ON a.id = g.asset_id
+WHERE g.group_key = 'collection' AND g.group_value IS NOT NULL AND g.verified
+GROUP BY g.group_value
+ON CONFLICT (collection) DO NOTHING
+",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_COLLECTION_STATS {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS collection_stats".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Slots of a gap the backfiller couldn't read or parse, retried with a backoff until they go
+// through or `given_up` is set after too many attempts.
+const ADD_FAILED_SLOTS: [&str; 2] = [
+    "
+CREATE TABLE IF NOT EXISTS failed_slots (
+    tree bytea NOT NULL,
+    slot bigint NOT NULL,
+    error text NOT NULL,
+    attempts int NOT NULL DEFAULT 1,
+    next_retry_at timestamp NOT NULL DEFAULT now(),
+    given_up bool NOT NULL DEFAULT false,
+    created_at timestamp NOT NULL DEFAULT now(),
+    updated_at timestamp NOT NULL DEFAULT now(),
+    PRIMARY KEY (tree, slot)
+)
+",
+    "CREATE INDEX IF NOT EXISTS failed_slots_next_retry_at_idx ON failed_slots (next_retry_at) WHERE NOT given_up",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_FAILED_SLOTS {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS failed_slots".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The delegate changes and burns of assets, which with `asset_owner_history` give the state of
+// an asset as of a slot. Like the owner history, the current delegate of indexed assets is
+// their first known one, and the burns indexed before are dated by the asset's last update.
+const ADD_ASSET_STATE_HISTORY: [&str; 5] = [
+    "
+CREATE TABLE IF NOT EXISTS asset_delegate_history (
+    id bigserial PRIMARY KEY,
+    asset_id bytea NOT NULL,
+    delegate bytea,
+    slot_updated bigint NOT NULL,
+    seq bigint NOT NULL
+)
+",
+    "CREATE UNIQUE INDEX IF NOT EXISTS asset_delegate_history_asset_slot_seq ON asset_delegate_history (asset_id, slot_updated, seq)",
+    "
+CREATE TABLE IF NOT EXISTS asset_burns (
+    asset_id bytea PRIMARY KEY,
+    slot bigint NOT NULL,
+    seq bigint NOT NULL
+)
+",
+    "
+INSERT INTO asset_delegate_history (asset_id, delegate, slot_updated, seq)
+SELECT id, delegate, COALESCE(slot_updated, 0), COALESCE(owner_delegate_seq, 0)
+FROM asset
+WHERE delegate IS NOT NULL
+ON CONFLICT DO NOTHING
+",
+    "
+INSERT INTO asset_burns (asset_id, slot, seq)
+SELECT id, COALESCE(slot_updated, 0), COALESCE(seq, 0)
+FROM asset
+WHERE burnt
+ON CONFLICT DO NOTHING
+",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_ASSET_STATE_HISTORY {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in ["asset_burns", "asset_delegate_history"] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    format!("DROP TABLE IF EXISTS {}", table),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The canopy of each tree as last read from its account: the `2^(canopy_depth + 1) - 2` nodes
+// of the tree's top levels, from node index 2 on, 32 bytes each.
+const ADD_MERKLE_TREE_CANOPY: &str = "
+CREATE TABLE IF NOT EXISTS merkle_tree_canopy (
+    tree bytea PRIMARY KEY,
+    canopy_depth int NOT NULL,
+    nodes bytea NOT NULL,
+    slot_updated bigint NOT NULL
+)
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_MERKLE_TREE_CANOPY.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS merkle_tree_canopy".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
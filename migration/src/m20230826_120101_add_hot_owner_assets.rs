@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The owners with too many assets to page through `asset`, and a narrow copy of the assets each
+// of them holds, sorted as `getAssetsByOwner` pages them. An owner is `ready` once its assets
+// were copied over.
+const ADD_HOT_OWNER_ASSETS: [&str; 4] = [
+    "
+CREATE TABLE IF NOT EXISTS hot_owners (
+    owner bytea PRIMARY KEY,
+    ready bool NOT NULL DEFAULT false,
+    created_at timestamptz NOT NULL DEFAULT now()
+)
+",
+    "
+CREATE TABLE IF NOT EXISTS hot_owner_assets (
+    owner bytea NOT NULL,
+    asset_id bytea NOT NULL,
+    network text NOT NULL,
+    created_at timestamptz,
+    PRIMARY KEY (owner, asset_id)
+)
+",
+    "CREATE INDEX IF NOT EXISTS hot_owner_assets_asset ON hot_owner_assets (asset_id)",
+    "CREATE INDEX IF NOT EXISTS hot_owner_assets_owner_created ON hot_owner_assets (owner, created_at, asset_id)",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_HOT_OWNER_ASSETS {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in ["hot_owner_assets", "hot_owners"] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    format!("DROP TABLE IF EXISTS {}", table),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The off-chain metadata that was over the ingester's size cap, as downloaded. `asset_data`
+// keeps the truncated copy served by default.
+const ADD_ASSET_DATA_FULL_METADATA: &str = "
+CREATE TABLE IF NOT EXISTS asset_data_full_metadata (
+    id bytea PRIMARY KEY,
+    metadata jsonb NOT NULL,
+    original_bytes bigint NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now()
+)
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_ASSET_DATA_FULL_METADATA.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS asset_data_full_metadata".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Asset changes waiting to be exported, written before the stream message that caused them is
+// acknowledged and deleted once the sink took them.
+const ADD_ASSET_CHANGE_OUTBOX: &str = "
+CREATE TABLE IF NOT EXISTS asset_change_outbox (
+    id bigserial PRIMARY KEY,
+    asset_id bytea NOT NULL,
+    change text NOT NULL,
+    slot bigint NOT NULL,
+    created_at timestamptz NOT NULL DEFAULT now()
+)
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_ASSET_CHANGE_OUTBOX.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS asset_change_outbox".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Resolve a leaf, by its position in its tree or by its hash, to the compressed asset it holds.
+const ADD_ASSET_LEAF_INDEXES: [&str; 2] = [
+    "CREATE INDEX IF NOT EXISTS asset_tree_nonce ON asset (tree_id, nonce) WHERE tree_id IS NOT NULL",
+    "CREATE INDEX IF NOT EXISTS asset_leaf ON asset (leaf) WHERE leaf IS NOT NULL",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_ASSET_LEAF_INDEXES {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in [
+            "DROP INDEX IF EXISTS asset_tree_nonce",
+            "DROP INDEX IF EXISTS asset_leaf",
+        ] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// The last stream entry a standby ingester read, per stream and consumer group. A promoted
+// standby replays the streams from there.
+const ADD_STANDBY_CHECKPOINTS: &str = "
+CREATE TABLE IF NOT EXISTS standby_checkpoints (
+    stream text NOT NULL,
+    consumer_group text NOT NULL,
+    message_id text NOT NULL,
+    updated_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (stream, consumer_group)
+)
+";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                ADD_STANDBY_CHECKPOINTS.to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute(Statement::from_string(
+                DatabaseBackend::Postgres,
+                "DROP TABLE IF EXISTS standby_checkpoints".to_string(),
+            ))
+            .await?;
+        Ok(())
+    }
+}
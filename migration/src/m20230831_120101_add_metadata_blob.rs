@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Metadata JSON stored once per URI, keyed by the sha256 of the URI. The asset_data rows of the
+// URI reference it instead of holding a copy.
+const ADD_METADATA_BLOB: [&str; 3] = [
+    "
+CREATE TABLE IF NOT EXISTS metadata_blob (
+    uri_hash bytea PRIMARY KEY,
+    uri text NOT NULL,
+    metadata jsonb NOT NULL,
+    content_hash bytea NOT NULL,
+    files jsonb,
+    fetched_at timestamptz NOT NULL DEFAULT now()
+)
+",
+    "ALTER TABLE asset_data ADD COLUMN IF NOT EXISTS metadata_blob bytea",
+    "CREATE INDEX IF NOT EXISTS asset_data_metadata_blob ON asset_data (metadata_blob) WHERE metadata_blob IS NOT NULL",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for sql in ADD_METADATA_BLOB {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The metadata is copied back to the rows referencing a blob first.
+        for sql in [
+            "UPDATE asset_data SET metadata = b.metadata FROM metadata_blob b WHERE asset_data.metadata_blob = b.uri_hash",
+            "DROP INDEX IF EXISTS asset_data_metadata_blob",
+            "ALTER TABLE asset_data DROP COLUMN IF EXISTS metadata_blob",
+            "DROP TABLE IF EXISTS metadata_blob",
+        ] {
+            manager
+                .get_connection()
+                .execute(Statement::from_string(
+                    DatabaseBackend::Postgres,
+                    sql.to_string(),
+                ))
+                .await?;
+        }
+        Ok(())
+    }
+}
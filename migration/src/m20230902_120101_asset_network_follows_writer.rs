@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseBackend, Statement};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// An asset belongs to the network of the ingester that last wrote it, which also wrote the rest
+// of its rows, so an address in use on two networks is served from one of them only. The column
+// default only applied to inserts, leaving an asset overwritten from another network under the
+// network of its first writer.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "
+CREATE OR REPLACE FUNCTION asset_network_follows_writer() RETURNS trigger AS $$
+BEGIN
+    NEW.network := COALESCE(NULLIF(current_setting('das.network', true), ''), NEW.network);
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql
+"
+            .to_string(),
+        ))
+        .await?;
+        conn.execute(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "
+CREATE TRIGGER asset_network_follows_writer BEFORE UPDATE ON asset
+FOR EACH ROW EXECUTE FUNCTION asset_network_follows_writer()
+"
+            .to_string(),
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let conn = manager.get_connection();
+        conn.execute(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "DROP TRIGGER IF EXISTS asset_network_follows_writer ON asset".to_string(),
+        ))
+        .await?;
+        conn.execute(Statement::from_string(
+            DatabaseBackend::Postgres,
+            "DROP FUNCTION IF EXISTS asset_network_follows_writer()".to_string(),
+        ))
+        .await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,37 @@
-use sea_orm_migration::prelude::*;
+use digital_asset_types::dao::schema::is_valid_schema_name;
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, Database, Statement},
+};
+
+/// The cli migrates `DATABASE_SCHEMA` when it's set, but the schema has to exist first.
+async fn create_schema() {
+    let (url, schema) = match (
+        std::env::var("DATABASE_URL"),
+        std::env::var("DATABASE_SCHEMA"),
+    ) {
+        (Ok(url), Ok(schema)) if schema != "public" => (url, schema),
+        _ => return,
+    };
+    if !is_valid_schema_name(&schema) {
+        panic!(
+            "Invalid database schema {}, use lowercase letters, digits and _",
+            schema
+        );
+    }
+    let conn = Database::connect(&url)
+        .await
+        .expect("Failed to connect to the database");
+    conn.execute(Statement::from_string(
+        conn.get_database_backend(),
+        format!("CREATE SCHEMA IF NOT EXISTS {}", schema),
+    ))
+    .await
+    .expect("Failed to create the database schema");
+}
 
 #[async_std::main]
 async fn main() {
+    create_schema().await;
     cli::run_cli(migration::Migrator).await;
 }
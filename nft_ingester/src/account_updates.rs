@@ -1,11 +1,19 @@
-use std::sync::Arc;
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use crate::{
-    metric, metrics::capture_result, program_transformers::ProgramTransformer, tasks::TaskData,
+    admin::PayloadDumper,
+    asset_changes::ChangeNotifier,
+    memory_budget::MemoryBudget,
+    metric,
+    metrics::capture_result,
+    program_transformers::{Assertion, ProgramGroups, ProgramTransformer},
+    stream_control::{StreamConsumer, StreamControls},
+    tasks::TaskData,
 };
 use cadence_macros::{is_global_default_set, statsd_count, statsd_time};
 use chrono::Utc;
 use log::{debug, error};
+use messenger_payload::{decode_payload, PayloadError};
 use plerkle_messenger::{ConsumptionType, Messenger, MessengerConfig, RecvData, ACCOUNT_STREAM};
 use plerkle_serialization::root_as_account_info;
 use sqlx::{Pool, Postgres};
@@ -15,25 +23,56 @@ use tokio::{
     time::Instant,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn account_worker<T: Messenger>(
     pool: Pool<Postgres>,
     config: MessengerConfig,
     bg_task_sender: UnboundedSender<TaskData>,
     ack_channel: UnboundedSender<(&'static str, String)>,
     consumption_type: ConsumptionType,
+    dumper: PayloadDumper,
+    budget: Option<MemoryBudget>,
+    changes: ChangeNotifier,
+    controls: StreamControls,
+    assertion: Option<Assertion>,
+    groups: ProgramGroups,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
-        let source = T::new(config).await;
-        if let Ok(mut msg) = source {
-            let manager = Arc::new(ProgramTransformer::new(pool, bg_task_sender));
+        let source = StreamConsumer::<T>::new(ACCOUNT_STREAM, config, &controls).await;
+        if let Ok(mut msg) = source.map(|source| source.with_memory_budget(budget.clone())) {
+            let manager = Arc::new(
+                ProgramTransformer::new(pool, bg_task_sender)
+                    .with_change_notifier(changes)
+                    .with_assertion(assertion)
+                    .with_program_groups(groups),
+            );
             loop {
-                let e = msg.recv(ACCOUNT_STREAM, consumption_type.clone()).await;
+                let e = msg.recv(consumption_type.clone()).await;
                 let mut tasks = JoinSet::new();
                 match e {
                     Ok(data) => {
                         let len = data.len();
+                        let (data, stale) = compact_account_updates(data);
+                        if !stale.is_empty() {
+                            metric! {
+                                statsd_count!("ingester.stream.compacted", stale.len() as i64, "stream" => ACCOUNT_STREAM);
+                            }
+                        }
+                        for id in stale {
+                            if let Err(err) = ack_channel.send((ACCOUNT_STREAM, id)) {
+                                error!("Account stream ack error: {}", err);
+                            }
+                        }
                         for item in data {
-                            tasks.spawn(handle_account(Arc::clone(&manager), item));
+                            let permit = match (&budget, &item.data) {
+                                (Some(budget), Ok(data)) => Some(budget.reserve(data).await),
+                                _ => None,
+                            };
+                            let (manager, dumper) = (Arc::clone(&manager), dumper.clone());
+                            tasks.spawn(async move {
+                                let _permit = permit;
+                                handle_account(manager, item, dumper).await
+                            });
                         }
                         if len > 0 {
                             debug!("Processed {} accounts", len);
@@ -64,15 +103,93 @@ pub fn account_worker<T: Messenger>(
     })
 }
 
-async fn handle_account(manager: Arc<ProgramTransformer>, item: RecvData) -> Option<String> {
+/// An account update read from the stream, with its payload decoded.
+pub struct AccountUpdate {
+    pub id: String,
+    pub tries: usize,
+    pub data: Result<Vec<u8>, PayloadError>,
+}
+
+impl From<RecvData> for AccountUpdate {
+    fn from(item: RecvData) -> Self {
+        // Uncompressed payloads are kept as they are rather than copied.
+        let decoded = match decode_payload(&item.data) {
+            Ok(Cow::Owned(data)) => Ok(Some(data)),
+            Ok(Cow::Borrowed(_)) => Ok(None),
+            Err(e) => Err(e),
+        };
+        AccountUpdate {
+            id: item.id,
+            tries: item.tries,
+            data: decoded.map(|data| data.unwrap_or(item.data)),
+        }
+    }
+}
+
+/// Keeps only the latest update of each account in a batch, by slot and then write version, as a
+/// backlogged stream can hold many updates of a busy account. Returns the updates to process and
+/// the ids of the stale ones, which can be acked without processing since a newer update of the
+/// same account replaces them. Updates that can't be read are kept for `handle_account` to report.
+/// Payloads are decoded once, here, the batch size keeping the decoded batch within the budget.
+pub fn compact_account_updates(data: Vec<RecvData>) -> (Vec<AccountUpdate>, Vec<String>) {
+    let mut kept: Vec<AccountUpdate> = Vec::with_capacity(data.len());
+    let mut stale = Vec::new();
+    let mut latest: HashMap<Vec<u8>, (usize, (u64, u64))> = HashMap::new();
+    for item in data {
+        let item = AccountUpdate::from(item);
+        let key = item.data.as_ref().ok().and_then(|data| {
+            let account = root_as_account_info(data).ok()?;
+            let pubkey = account.pubkey()?.0.to_vec();
+            Some((pubkey, (account.slot(), account.write_version())))
+        });
+        match key {
+            Some((pubkey, version)) => match latest.get_mut(&pubkey) {
+                Some((idx, latest_version)) => {
+                    if version > *latest_version {
+                        let replaced = std::mem::replace(&mut kept[*idx], item);
+                        stale.push(replaced.id);
+                        *latest_version = version;
+                    } else {
+                        stale.push(item.id);
+                    }
+                }
+                None => {
+                    latest.insert(pubkey, (kept.len(), version));
+                    kept.push(item);
+                }
+            },
+            None => kept.push(item),
+        }
+    }
+    (kept, stale)
+}
+
+async fn handle_account(
+    manager: Arc<ProgramTransformer>,
+    item: AccountUpdate,
+    dumper: PayloadDumper,
+) -> Option<String> {
     let id = item.id;
     let mut ret_id = None;
-    let data = item.data;
     if item.tries > 0 {
         metric! {
             statsd_count!("ingester.account_stream_redelivery", 1);
         }
     }
+    let data = match item.data {
+        Ok(data) => data,
+        Err(e) => {
+            // Redelivery wouldn't decode it either.
+            error!(
+                "Failed to decode account payload {}, acknowledging it: {}",
+                id, e
+            );
+            metric! {
+                statsd_count!("ingester.payload.decode_error", 1, "stream" => ACCOUNT_STREAM);
+            }
+            return Some(id);
+        }
+    };
     // Get root of account info flatbuffers object.
     if let Ok(account_update) = root_as_account_info(&data) {
         let str_program_id =
@@ -91,6 +208,13 @@ async fn handle_account(manager: Arc<ProgramTransformer>, item: RecvData) -> Opt
         if let Some(pubkey) = account_update.pubkey() {
             account = Some(bs58::encode(pubkey.0.as_slice()).into_string());
         }
+        let owner = account_update.owner().map(|owner| owner.0.as_slice());
+        if let Some(program) = dumper.dumped_program(owner.into_iter()) {
+            let name = account.as_deref().unwrap_or("unknown");
+            dumper
+                .dump(program, "account", account_update.slot(), name, &data)
+                .await;
+        }
         let begin_processing = Instant::now();
         let res = manager.handle_account_update(account_update).await;
         let should_ack = capture_result(
@@ -1,53 +1,83 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+};
 
 use cadence_macros::{is_global_default_set, statsd_count};
 use log::error;
 use plerkle_messenger::{Messenger, MessengerConfig};
 use tokio::{
-    sync::mpsc::{unbounded_channel, UnboundedSender},
+    sync::{
+        mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        Mutex,
+    },
     task::JoinHandle,
     time::{interval, Duration},
 };
 
-use crate::metric;
+use crate::{
+    metric,
+    stream_control::{StreamConsumer, StreamControls},
+};
+
+/// The receiving end of the acks, which outlives the ack worker so a restarted worker keeps
+/// receiving from the senders the stream workers hold.
+pub type AckReceiver = Arc<Mutex<UnboundedReceiver<(&'static str, String)>>>;
 
+pub fn ack_channel() -> (UnboundedSender<(&'static str, String)>, AckReceiver) {
+    let (tx, rx) = unbounded_channel::<(&'static str, String)>();
+    (tx, Arc::new(Mutex::new(rx)))
+}
+
+/// Acks the messages sent on the channel in batches. The acks a worker that panicked was holding
+/// are lost, and their messages redelivered.
 pub fn ack_worker<T: Messenger>(
     config: MessengerConfig,
-) -> (JoinHandle<()>, UnboundedSender<(&'static str, String)>) {
-    let (tx, mut rx) = unbounded_channel::<(&'static str, String)>();
-    (
-        tokio::spawn(async move {
-            let mut interval = interval(Duration::from_millis(100));
-            let mut acks: HashMap<&str, Vec<String>> = HashMap::new();
-            let source = T::new(config).await;
-            if let Ok(mut msg) = source {
-                loop {
-                    tokio::select! {
-                        _ = interval.tick() => {
-                            if acks.is_empty() {
-                                continue;
-                            }
-                            let len = acks.len();
-                            for (stream, msgs)  in acks.iter_mut() {
-                                if let Err(e) = msg.ack_msg(&stream, &msgs).await {
-                                    error!("Error acking message: {}", e);
-                                }
-                                metric! {
-                                    statsd_count!("ingester.ack", len as i64, "stream" => stream);
+    controls: StreamControls,
+    receiver: AckReceiver,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut rx = receiver.lock().await;
+        let mut interval = interval(Duration::from_millis(100));
+        let mut acks: HashMap<&'static str, Vec<String>> = HashMap::new();
+        // A messenger per stream, so each stream is acked in the consumer group it's read from.
+        let mut ackers: HashMap<&'static str, StreamConsumer<T>> = HashMap::new();
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if acks.is_empty() {
+                        continue;
+                    }
+                    let len = acks.len();
+                    for (stream, msgs)  in acks.iter_mut() {
+                        let acker = match ackers.entry(stream) {
+                            Entry::Occupied(acker) => acker.into_mut(),
+                            Entry::Vacant(entry) => {
+                                match StreamConsumer::new(stream, config.clone(), &controls).await {
+                                    Ok(acker) => entry.insert(acker),
+                                    Err(e) => {
+                                        error!("Error connecting to ack stream {}: {}", stream, e);
+                                        continue;
+                                    }
                                 }
-                                msgs.clear();
                             }
-
+                        };
+                        if let Err(e) = acker.ack(msgs).await {
+                            error!("Error acking message: {}", e);
                         }
-                        Some(msg) = rx.recv() => {
-                            let (stream, msg) = msg;
-                            let ackstream = acks.entry(stream).or_insert_with(Vec::<String>::new);
-                            ackstream.push(msg);
+                        metric! {
+                            statsd_count!("ingester.ack", len as i64, "stream" => stream);
                         }
+                        msgs.clear();
                     }
+
+                }
+                Some(msg) = rx.recv() => {
+                    let (stream, msg) = msg;
+                    let ackstream = acks.entry(stream).or_insert_with(Vec::<String>::new);
+                    ackstream.push(msg);
                 }
             }
-        }),
-        tx,
-    )
+        }
+    })
 }
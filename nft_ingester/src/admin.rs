@@ -0,0 +1,417 @@
+use crate::{
+    bootstrap::bootstrap_status,
+    config::AdminConfig,
+    error::IngesterError,
+    profiling::{cpu_profile, ProfileFormat},
+    reindex::{ReindexRequest, Reindexer},
+    standby::Standby,
+    stream_control::{StreamControls, StreamSettings},
+    tasks::TaskManager,
+};
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{error, info, warn};
+use sea_orm::SqlxPostgresConnector;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::{Pool, Postgres};
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+use tokio::{fs, task::JoinHandle};
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Writes the raw stream payloads that involve the programs in dump mode to disk, so a
+/// transformer that misparses them can be reproduced offline. Accounts are dumped when the
+/// program owns them, transactions when the program is one of their account keys.
+#[derive(Clone)]
+pub struct PayloadDumper {
+    programs: Arc<RwLock<HashSet<Pubkey>>>,
+    dir: PathBuf,
+}
+
+impl PayloadDumper {
+    pub fn new(dir: PathBuf) -> Self {
+        PayloadDumper {
+            programs: Arc::new(RwLock::new(HashSet::new())),
+            dir,
+        }
+    }
+
+    pub fn enable(&self, program: Pubkey) {
+        self.programs.write().unwrap().insert(program);
+    }
+
+    pub fn disable(&self, program: &Pubkey) -> bool {
+        self.programs.write().unwrap().remove(program)
+    }
+
+    pub fn programs(&self) -> Vec<Pubkey> {
+        self.programs.read().unwrap().iter().copied().collect()
+    }
+
+    /// The first of `keys` in dump mode, if any.
+    pub fn dumped_program<'a>(&self, mut keys: impl Iterator<Item = &'a [u8]>) -> Option<Pubkey> {
+        let programs = self.programs.read().unwrap();
+        if programs.is_empty() {
+            return None;
+        }
+        keys.find_map(|key| {
+            Pubkey::try_from(key)
+                .ok()
+                .filter(|key| programs.contains(key))
+        })
+    }
+
+    /// Writes `payload` to `<dir>/<program>/<kind>-<slot>-<name>.bin`. Failures are only
+    /// logged, dumping never holds up ingestion.
+    pub async fn dump(&self, program: Pubkey, kind: &str, slot: u64, name: &str, payload: &[u8]) {
+        let dir = self.dir.join(program.to_string());
+        let path = dir.join(format!("{}-{}-{}.bin", kind, slot, name));
+        let res = match fs::create_dir_all(&dir).await {
+            Ok(()) => fs::write(&path, payload).await,
+            Err(e) => Err(e),
+        };
+        match res {
+            Ok(()) => info!("dumped {} payload to {}", kind, path.display()),
+            Err(e) => warn!(
+                "failed to dump {} payload to {}: {}",
+                kind,
+                path.display(),
+                e
+            ),
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
+
+fn respond_json(value: &impl serde::Serialize) -> Response<Body> {
+    match serde_json::to_string(value) {
+        Ok(json) => respond(StatusCode::OK, json),
+        Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn authorized(req: &Request<Body>, token: &str) -> bool {
+    req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map_or(false, |t| t == token)
+}
+
+async fn handle_stream(
+    req: Request<Body>,
+    controls: StreamControls,
+    stream: &str,
+    action: Option<&str>,
+) -> Response<Body> {
+    let method = req.method().clone();
+    let settings = match (method.clone(), action) {
+        (Method::GET, None) => controls.get(stream),
+        (Method::PUT, None) => {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(e) => return respond(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let next: StreamSettings = match serde_json::from_slice(&body) {
+                Ok(next) => next,
+                Err(e) => return respond(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            if next.batch_size == Some(0) {
+                return respond(StatusCode::BAD_REQUEST, "batch_size must be positive");
+            }
+            controls.update(stream, |settings| *settings = next)
+        }
+        (Method::PUT, Some(action @ ("pause" | "resume"))) => {
+            controls.update(stream, |settings| settings.paused = action == "pause")
+        }
+        (_, None) => return respond(StatusCode::METHOD_NOT_ALLOWED, "use GET or PUT"),
+        (Method::PUT, Some(_)) => return respond(StatusCode::NOT_FOUND, "not found"),
+        (_, Some(_)) => return respond(StatusCode::METHOD_NOT_ALLOWED, "use PUT"),
+    };
+    match settings {
+        Some(settings) => {
+            if method == Method::PUT {
+                info!("stream {} settings changed to {:?}", stream, settings);
+            }
+            respond_json(&settings)
+        }
+        None => respond(StatusCode::NOT_FOUND, "unknown stream"),
+    }
+}
+
+async fn handle_reindex(req: Request<Body>, reindexer: Reindexer) -> Response<Body> {
+    match req.method().clone() {
+        Method::GET => respond_json(&reindexer.status()),
+        Method::POST => {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(e) => return respond(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let request: ReindexRequest = match serde_json::from_slice(&body) {
+                Ok(request) => request,
+                Err(e) => return respond(StatusCode::BAD_REQUEST, e.to_string()),
+            };
+            let slots = request.slots;
+            match reindexer.start(request) {
+                Ok(()) => {
+                    info!("reindex of the last {} slots started", slots);
+                    respond(StatusCode::ACCEPTED, "reindex started")
+                }
+                Err(e) => respond(StatusCode::CONFLICT, e),
+            }
+        }
+        Method::DELETE => {
+            if reindexer.cancel() {
+                info!("reindex cancelled");
+                respond(StatusCode::OK, "reindex cancelled")
+            } else {
+                respond(StatusCode::NOT_FOUND, "no reindex running")
+            }
+        }
+        _ => respond(StatusCode::METHOD_NOT_ALLOWED, "use GET, POST or DELETE"),
+    }
+}
+
+async fn handle_profile(req: Request<Body>) -> Response<Body> {
+    let (mut seconds, mut format) = (30, ProfileFormat::Flamegraph);
+    let query = req.uri().query().unwrap_or_default();
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "seconds" => match value.parse() {
+                Ok(s) => seconds = s,
+                Err(_) => return respond(StatusCode::BAD_REQUEST, "invalid seconds"),
+            },
+            "format" => match ProfileFormat::parse(&value) {
+                Some(f) => format = f,
+                None => return respond(StatusCode::BAD_REQUEST, "use format=svg or format=proto"),
+            },
+            _ => {}
+        }
+    }
+    info!("capturing a {} second CPU profile", seconds);
+    match cpu_profile(seconds, format).await {
+        Some(Ok(profile)) => {
+            let mut response = respond(StatusCode::OK, profile);
+            if let Ok(content_type) = format.content_type().parse() {
+                response.headers_mut().insert(CONTENT_TYPE, content_type);
+            }
+            response
+        }
+        Some(Err(e)) => respond(StatusCode::INTERNAL_SERVER_ERROR, e),
+        None => respond(StatusCode::CONFLICT, "a profile is already running"),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle(
+    req: Request<Body>,
+    log_filter: LogFilterHandle,
+    dumper: PayloadDumper,
+    controls: StreamControls,
+    reindexer: Reindexer,
+    standby: Option<Standby>,
+    pool: Pool<Postgres>,
+    auth_token: Option<Arc<str>>,
+    profiling: bool,
+) -> Result<Response<Body>, Infallible> {
+    if let Some(token) = &auth_token {
+        if !authorized(&req, token) {
+            return Ok(respond(StatusCode::UNAUTHORIZED, "unauthorized"));
+        }
+    }
+    let path = req.uri().path().trim_end_matches('/').to_string();
+    let response = match (req.method().clone(), path.as_str()) {
+        (Method::GET, "/log-filter") => match log_filter.with_current(|f| f.to_string()) {
+            Ok(filter) => respond(StatusCode::OK, filter),
+            Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        },
+        (Method::PUT, "/log-filter") => {
+            let body = match hyper::body::to_bytes(req.into_body()).await {
+                Ok(body) => body,
+                Err(e) => return Ok(respond(StatusCode::BAD_REQUEST, e.to_string())),
+            };
+            let directives = String::from_utf8_lossy(&body).trim().to_string();
+            match EnvFilter::try_new(&directives) {
+                Ok(filter) => match log_filter.reload(filter) {
+                    Ok(()) => {
+                        info!("log filter changed to {}", directives);
+                        respond(StatusCode::OK, directives)
+                    }
+                    Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+                },
+                Err(e) => respond(StatusCode::BAD_REQUEST, e.to_string()),
+            }
+        }
+        (Method::GET, "/dump") => {
+            let programs = dumper
+                .programs()
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>();
+            respond(StatusCode::OK, programs.join("\n"))
+        }
+        (method, path) if path.starts_with("/dump/") => {
+            match Pubkey::from_str(&path["/dump/".len()..]) {
+                Ok(program) if method == Method::PUT => {
+                    dumper.enable(program);
+                    info!("dump mode enabled for {}", program);
+                    respond(StatusCode::OK, program.to_string())
+                }
+                Ok(program) if method == Method::DELETE => {
+                    if dumper.disable(&program) {
+                        info!("dump mode disabled for {}", program);
+                        respond(StatusCode::OK, program.to_string())
+                    } else {
+                        respond(StatusCode::NOT_FOUND, "program not in dump mode")
+                    }
+                }
+                Ok(_) => respond(StatusCode::METHOD_NOT_ALLOWED, "use PUT or DELETE"),
+                Err(e) => respond(StatusCode::BAD_REQUEST, e.to_string()),
+            }
+        }
+        (Method::GET, "/background-tasks") => {
+            let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+            match TaskManager::get_task_stats(&conn).await {
+                Ok(stats) => respond_json(&stats),
+                Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            }
+        }
+        (Method::GET, "/bootstrap") => {
+            let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+            match bootstrap_status(&conn).await {
+                Ok(status) => respond_json(&status),
+                Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            }
+        }
+        (_, path) if path.starts_with("/standby") && standby.is_none() => {
+            respond(StatusCode::NOT_FOUND, "not a standby")
+        }
+        (Method::GET, "/standby") => {
+            let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+            match standby.unwrap().status(&conn).await {
+                Ok(status) => respond_json(&status),
+                Err(e) => respond(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            }
+        }
+        (_, "/standby/promote") if auth_token.is_none() => respond(
+            StatusCode::FORBIDDEN,
+            "promoting the standby needs an admin auth token",
+        ),
+        (Method::POST, "/standby/promote") => {
+            if standby.unwrap().promote("admin") {
+                respond(StatusCode::ACCEPTED, "standby promoted")
+            } else {
+                respond(StatusCode::CONFLICT, "standby already promoted")
+            }
+        }
+        // Redirecting a stream's consumer or flooding it with a replay is only allowed to
+        // authenticated callers.
+        (_, path) if path.starts_with("/streams") && auth_token.is_none() => respond(
+            StatusCode::FORBIDDEN,
+            "stream controls need an admin auth token",
+        ),
+        (_, "/reindex") if auth_token.is_none() => respond(
+            StatusCode::FORBIDDEN,
+            "reindexing needs an admin auth token",
+        ),
+        (_, "/reindex") => handle_reindex(req, reindexer).await,
+        (Method::GET, "/debug/pprof/profile") if profiling => handle_profile(req).await,
+        (Method::GET, "/streams") => respond_json(&controls.all()),
+        (_, path) if path.starts_with("/streams/") => {
+            let mut parts = path["/streams/".len()..].splitn(2, '/');
+            let stream = parts.next().unwrap_or_default().to_string();
+            let action = parts.next().map(str::to_string);
+            handle_stream(req, controls, &stream, action.as_deref()).await
+        }
+        _ => respond(StatusCode::NOT_FOUND, "not found"),
+    };
+    Ok(response)
+}
+
+/// Serves the admin endpoints:
+/// - `GET /log-filter`, `PUT /log-filter` with `RUST_LOG` style directives as the body.
+/// - `GET /dump`, `PUT /dump/<program id>` and `DELETE /dump/<program id>` for dump mode.
+/// - `GET /streams`, `GET /streams/<stream>`, `PUT /streams/<stream>` with the stream's settings
+///   as JSON, and `PUT /streams/<stream>/pause` or `/resume`. Only served with an auth token.
+/// - `GET /reindex` for the progress of the last reindex, `POST /reindex` with
+///   `{"slots": <n>, "accounts": <bool>}` to replay the last slots and `DELETE /reindex` to stop
+///   it. Only served with an auth token.
+/// - `GET /background-tasks` for the pending, running and failed background tasks per type.
+/// - `GET /bootstrap` for the progress of each bootstrap phase and the trees replayed so far.
+/// - `GET /standby` for a standby's promotion and stream checkpoints, and `POST /standby/promote`
+///   to promote it. Only served by a standby, and promoting needs an auth token.
+/// - `GET /debug/pprof/profile?seconds=<n>&format=<svg|proto>` for a CPU profile, as a
+///   flamegraph by default. Only served when `profiling` is on.
+///
+/// With an `auth_token` every request needs an `Authorization: Bearer <token>` header.
+pub fn start_admin_server(
+    config: &AdminConfig,
+    log_filter: LogFilterHandle,
+    dumper: PayloadDumper,
+    controls: StreamControls,
+    reindexer: Reindexer,
+    standby: Option<Standby>,
+    pool: Pool<Postgres>,
+) -> Result<JoinHandle<()>, IngesterError> {
+    let addr =
+        SocketAddr::from_str(&format!("{}:{}", config.get_host(), config.port)).map_err(|e| {
+            IngesterError::ConfigurationError {
+                msg: format!("invalid admin address: {}", e),
+            }
+        })?;
+    let auth_token: Option<Arc<str>> = config.auth_token.as_deref().map(Arc::from);
+    let profiling = config.get_profiling();
+    let make_service = make_service_fn(move |_| {
+        let (log_filter, dumper, controls, reindexer, standby, pool, auth_token) = (
+            log_filter.clone(),
+            dumper.clone(),
+            controls.clone(),
+            reindexer.clone(),
+            standby.clone(),
+            pool.clone(),
+            auth_token.clone(),
+        );
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                handle(
+                    req,
+                    log_filter.clone(),
+                    dumper.clone(),
+                    controls.clone(),
+                    reindexer.clone(),
+                    standby.clone(),
+                    pool.clone(),
+                    auth_token.clone(),
+                    profiling,
+                )
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)
+        .map_err(|e| IngesterError::ConfigurationError {
+            msg: format!("failed to bind admin server to {}: {}", addr, e),
+        })?
+        .serve(make_service);
+    info!("Admin server listening on {}", addr);
+    Ok(tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("admin server error: {}", e);
+        }
+    }))
+}
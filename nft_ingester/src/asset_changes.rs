@@ -0,0 +1,178 @@
+use crate::metric;
+use cadence_macros::{is_global_default_set, statsd_count};
+use figment::value::Value;
+use log::error;
+use plerkle_messenger::{Messenger, MessengerConfig};
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, Statement};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    task::JoinHandle,
+};
+
+/// The outbound stream asset changes are published to.
+pub const ASSET_CHANGE_STREAM: &str = "ASSET_CHANGES";
+const DEFAULT_MAX_LEN: usize = 1_000_000;
+
+/// Publishes asset changes to `ASSET_CHANGES`, e.g.
+/// `INGESTER_CHANGE_NOTIFICATION_CONFIG='{max_len=1000000}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ChangeNotificationConfig {
+    /// Changes kept in the stream, older ones are trimmed.
+    pub max_len: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeType {
+    Mint,
+    Transfer,
+    Burn,
+    Delegate,
+    Freeze,
+    /// Redeemed, redeem cancelled or decompressed.
+    Compression,
+    /// A creator or the collection was verified or unverified.
+    Verification,
+    Metadata,
+}
+
+/// A committed change of an asset, published as JSON such as
+/// `{"id":"<asset>","change":"transfer","slot":200}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AssetChange {
+    pub id: String,
+    pub change: ChangeType,
+    pub slot: u64,
+}
+
+impl ChangeType {
+    /// The change's name as it is serialized, e.g. `transfer`.
+    pub fn name(&self) -> String {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(name)) => name,
+            _ => unreachable!("change types serialize to their name"),
+        }
+    }
+
+    /// The change type named `name`, unless it is one this build doesn't know.
+    pub fn from_name(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+}
+
+/// Hands committed changes to the publisher, doing nothing when notifications are off, and writes
+/// them to the export outbox when the delta export is on.
+#[derive(Debug, Clone, Default)]
+pub struct ChangeNotifier {
+    sender: Option<UnboundedSender<AssetChange>>,
+    export: bool,
+}
+
+impl ChangeNotifier {
+    /// Also writes the changes to `asset_change_outbox` for the delta exporter.
+    pub fn with_export(mut self) -> Self {
+        self.export = true;
+        self
+    }
+
+    /// Writes the change to the export outbox. Unlike notifications, it has to be written before
+    /// the message that caused it is acknowledged, from the transaction that detected it when a
+    /// replay of the message wouldn't detect it again.
+    pub async fn export<T: ConnectionTrait>(
+        &self,
+        conn: &T,
+        id: &[u8],
+        change: ChangeType,
+        slot: u64,
+    ) -> Result<(), DbErr> {
+        if !self.export {
+            return Ok(());
+        }
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "INSERT INTO asset_change_outbox (asset_id, change, slot) VALUES ($1, $2, $3)",
+            vec![
+                id.to_vec().into(),
+                change.name().into(),
+                (slot as i64).into(),
+            ],
+        ))
+        .await?;
+        Ok(())
+    }
+
+    pub fn notify(&self, id: &[u8], change: ChangeType, slot: u64) {
+        if let Some(sender) = &self.sender {
+            let change = AssetChange {
+                id: bs58::encode(id).into_string(),
+                change,
+                slot,
+            };
+            if sender.send(change).is_err() {
+                error!("Asset change publisher stopped");
+            }
+        }
+    }
+}
+
+/// Publishes the changes sent to the returned notifier. Publishing is best effort, a change that
+/// fails to publish is counted and dropped, it never holds up indexing.
+pub fn change_publisher<T: Messenger>(
+    mut config: MessengerConfig,
+    notification_config: ChangeNotificationConfig,
+) -> (JoinHandle<()>, ChangeNotifier) {
+    let (tx, mut rx) = unbounded_channel::<AssetChange>();
+    // Sends each change as it comes instead of waiting for the messenger's pipeline to fill.
+    config.connection_config.insert(
+        "pipeline_size_bytes".to_string(),
+        Value::from(1u128.to_string()),
+    );
+    (
+        tokio::spawn(async move {
+            let mut messenger = match T::new(config).await {
+                Ok(messenger) => messenger,
+                Err(e) => {
+                    error!("Failed to start the asset change publisher: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = messenger.add_stream(ASSET_CHANGE_STREAM).await {
+                error!("Failed to add the asset change stream: {}", e);
+                return;
+            }
+            messenger
+                .set_buffer_size(
+                    ASSET_CHANGE_STREAM,
+                    notification_config.max_len.unwrap_or(DEFAULT_MAX_LEN),
+                )
+                .await;
+            while let Some(change) = rx.recv().await {
+                let res = match serde_json::to_vec(&change) {
+                    Ok(bytes) => messenger
+                        .send(ASSET_CHANGE_STREAM, &bytes)
+                        .await
+                        .map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                };
+                match res {
+                    Ok(()) => {
+                        metric! {
+                            statsd_count!("ingester.changes.published", 1);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to publish asset change: {}", e);
+                        metric! {
+                            statsd_count!("ingester.changes.publish_error", 1);
+                        }
+                    }
+                }
+            }
+        }),
+        ChangeNotifier {
+            sender: Some(tx),
+            export: false,
+        },
+    )
+}
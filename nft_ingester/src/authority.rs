@@ -0,0 +1,192 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::{authority_accounts, sea_orm_active_enums::AuthorityKind};
+use log::{debug, error};
+use sea_orm::{
+    query::*, sea_query::OnConflict, ActiveValue::Set, ConnectionTrait, DatabaseConnection,
+    DbBackend, EntityTrait, FromQueryResult, SqlxPostgresConnector,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey, pubkey::Pubkey, system_program};
+use spl_token::state::Multisig;
+use sqlx::PgPool;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+// Max accounts per `getMultipleAccounts` request.
+const RESOLVE_BATCH_SIZE: u64 = 100;
+
+/// Programs whose accounts act as multisig wallets: Squads v3, Squads v4 and Goki.
+const MULTISIG_PROGRAMS: [Pubkey; 3] = [
+    pubkey!("SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu"),
+    pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf"),
+    pubkey!("GokivDYuQXPZCWRkwMhdH2h91KpDQXBEmpgBgs55bnpH"),
+];
+
+/// Appends `authority` to the authority history of `asset_id`, unless it is already the
+/// asset's authority as of `slot`, so repeated updates of an unchanged account are no-ops.
+pub async fn record_authority_change<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    authority: Vec<u8>,
+    slot: i64,
+    seq: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO asset_authority_history (asset_id, authority, slot_updated, seq)
+SELECT $1, $2, $3, $4
+WHERE NOT EXISTS (
+    SELECT 1 FROM (
+        SELECT authority FROM asset_authority_history
+        WHERE asset_id = $1 AND slot_updated <= $3
+        ORDER BY slot_updated DESC, seq DESC, id DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.authority = $2
+)
+ON CONFLICT (asset_id, authority, slot_updated) DO NOTHING
+",
+            vec![asset_id.into(), authority.into(), slot.into(), seq.into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() > 0 {
+        metric! {
+            statsd_count!("ingester.authority.change_recorded", 1);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, FromQueryResult)]
+struct UnresolvedAuthority {
+    authority: Vec<u8>,
+}
+
+/// Works out what kind of account an authority is. Accounts that don't exist on chain are
+/// wallets that were never funded, or PDAs when they are off the ed25519 curve.
+fn classify_authority(
+    authority: &Pubkey,
+    account: Option<&Account>,
+) -> (AuthorityKind, Option<Pubkey>) {
+    let off_curve = !authority.is_on_curve();
+    let account = match account {
+        Some(account) => account,
+        None if off_curve => return (AuthorityKind::Pda, None),
+        None => return (AuthorityKind::Wallet, None),
+    };
+    let owner = if account.owner == system_program::id() {
+        None
+    } else {
+        Some(account.owner)
+    };
+
+    if account.executable {
+        (AuthorityKind::Program, owner)
+    } else if (account.owner == spl_token::id() && account.data.len() == Multisig::LEN)
+        || MULTISIG_PROGRAMS.contains(&account.owner)
+    {
+        (AuthorityKind::Multisig, owner)
+    } else if off_curve {
+        (AuthorityKind::Pda, owner)
+    } else {
+        (AuthorityKind::Wallet, owner)
+    }
+}
+
+/// Resolves one batch of authorities that haven't been looked at yet, returning how many were.
+async fn resolve_authorities(
+    conn: &DatabaseConnection,
+    client: &RpcClient,
+) -> Result<usize, IngesterError> {
+    let unresolved = UnresolvedAuthority::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+SELECT DISTINCT h.authority
+FROM asset_authority_history h
+LEFT JOIN authority_accounts a ON a.authority = h.authority
+WHERE a.authority IS NULL
+LIMIT $1
+",
+        vec![(RESOLVE_BATCH_SIZE as i64).into()],
+    ))
+    .all(conn)
+    .await?;
+    let keys = unresolved
+        .iter()
+        .filter_map(|u| Pubkey::try_from(u.authority.as_slice()).ok())
+        .collect::<Vec<_>>();
+    if keys.is_empty() {
+        return Ok(0);
+    }
+
+    let accounts = client
+        .get_multiple_accounts(&keys)
+        .await
+        .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+    let models = keys
+        .iter()
+        .zip(accounts.iter())
+        .map(|(key, account)| {
+            let (kind, owner) = classify_authority(key, account.as_ref());
+            authority_accounts::ActiveModel {
+                authority: Set(key.to_bytes().to_vec()),
+                kind: Set(kind),
+                owner: Set(owner.map(|o| o.to_bytes().to_vec())),
+                ..Default::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    let query = authority_accounts::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([authority_accounts::Column::Authority])
+                .update_columns([
+                    authority_accounts::Column::Kind,
+                    authority_accounts::Column::Owner,
+                ])
+                .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+    conn.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    metric! {
+        statsd_count!("ingester.authority.resolved", keys.len() as i64);
+    }
+    Ok(keys.len())
+}
+
+/// Periodically resolves new update authorities through RPC, flagging multisigs, PDAs and
+/// programs, until none are left to resolve.
+pub fn authority_resolver(pool: PgPool, rpc_url: String, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let client = RpcClient::new(rpc_url);
+        let mut interval = time::interval(interval);
+        loop {
+            interval.tick().await; // ticks immediately
+            loop {
+                match resolve_authorities(&conn, &client).await {
+                    Ok(resolved) => {
+                        debug!("resolved {} authorities", resolved);
+                        if resolved < RESOLVE_BATCH_SIZE as usize {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("error resolving authorities: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
@@ -4,10 +4,11 @@
 use borsh::BorshDeserialize;
 use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
 use chrono::Utc;
-use digital_asset_types::dao::backfill_items;
+use das_tree_reader::{find_signature_slots, missing_lookups, LookupTableCache, RpcThrottle};
+use digital_asset_types::dao::{backfill_items, backfill_queue, failed_slots, numeric::to_u64};
 use flatbuffers::FlatBufferBuilder;
 use futures::{stream::FuturesUnordered, StreamExt};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use plerkle_messenger::{Messenger, TRANSACTION_STREAM};
 use plerkle_serialization::serializer::seralize_encoded_transaction_with_status;
 
@@ -18,7 +19,6 @@ use sea_orm::{
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
-    rpc_client::GetConfirmedSignaturesForAddress2Config,
     rpc_config::{RpcAccountInfoConfig, RpcBlockConfig, RpcProgramAccountsConfig},
     rpc_filter::{Memcmp, RpcFilterType},
 };
@@ -26,7 +26,6 @@ use solana_sdk::{
     account::Account,
     commitment_config::{CommitmentConfig, CommitmentLevel},
     pubkey::Pubkey,
-    signature::Signature,
     slot_history::Slot,
 };
 use solana_transaction_status::{
@@ -38,6 +37,7 @@ use spl_account_compression::state::{
 };
 use sqlx::{self, Pool, Postgres};
 use std::{
+    borrow::Cow,
     cmp,
     collections::{HashMap, HashSet},
     str::FromStr,
@@ -45,19 +45,29 @@ use std::{
 };
 use stretto::{AsyncCache, AsyncCacheBuilder};
 use tokio::{
-    sync::Semaphore,
+    sync::{oneshot, Semaphore},
     task::JoinSet,
     time::{self, sleep, Duration},
 };
 
 use crate::{
-    config::{IngesterConfig, DATABASE_LISTENER_CHANNEL_KEY, RPC_COMMITMENT_KEY, RPC_URL_KEY},
+    block_source::BlockSource,
+    config::{
+        rand_string, IngesterConfig, DATABASE_LISTENER_CHANNEL_KEY, RPC_COMMITMENT_KEY, RPC_URL_KEY,
+    },
     error::IngesterError,
+    failed_slots::{
+        claim_failed_slot, fail_slot_retry, quarantine_slot, release_failed_slot,
+        report_failed_slots, retried_signatures,
+    },
+    failed_transactions::record_failed_transaction,
+    merkle_tree::{save_tree_shapes, TreeShape},
     metric,
+    redis_failover::RedisEndpoints,
+    stream_control::StreamProducer,
 };
 // Number of tries to backfill a single tree before marking as "failed".
 const NUM_TRIES: i32 = 5;
-const TREE_SYNC_INTERVAL: u64 = 60;
 const MAX_BACKFILL_CHECK_WAIT: u64 = 1000;
 // Constants used for varying delays when failures occur.
 const INITIAL_FAILURE_DELAY: u64 = 100;
@@ -65,17 +75,123 @@ const MAX_FAILURE_DELAY_MS: u64 = 10_000;
 const BLOCK_CACHE_SIZE: usize = 300_000;
 const MAX_CACHE_COST: i64 = 32;
 const BLOCK_CACHE_DURATION: u64 = 172800;
+// Signatures found ahead of the slots collected from them.
+const SIGNATURE_BUFFER: usize = 1000;
+// Seconds between checks for quarantined slots due for a retry.
+const FAILED_SLOT_CHECK_INTERVAL: u64 = 10;
+
+/// The creation slot and the sequence number of a tree.
+pub struct SlotSeq(pub u64, pub u64);
+
+/// The Bubblegum trees of the account compression program that have changed at least once,
+/// saving the shape of every tree found to `merkle_tree`.
+pub async fn fetch_bubblegum_trees(
+    rpc_client: &RpcClient,
+    db: &DatabaseConnection,
+) -> Result<HashMap<Pubkey, SlotSeq>, IngesterError> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            vec![1u8],
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let results: Vec<(Pubkey, Account)> = rpc_client
+        .get_program_accounts_with_config(&spl_account_compression::id(), config)
+        .await
+        .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+    let mut list = HashMap::with_capacity(results.len());
+    let mut shapes = Vec::with_capacity(results.len());
+    for r in results.into_iter() {
+        let (pubkey, mut account) = r;
+        let (mut header_bytes, rest) = account
+            .data
+            .split_at_mut(CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1);
+        let header: ConcurrentMerkleTreeHeader =
+            ConcurrentMerkleTreeHeader::try_from_slice(&mut header_bytes)
+                .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+
+        let auth = Pubkey::find_program_address(&[pubkey.as_ref()], &mpl_bubblegum::id()).0;
 
-struct SlotSeq(u64, u64);
+        let merkle_tree_size = merkle_tree_get_size(&header)
+            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+        let (tree_bytes, canopy_bytes) = rest.split_at_mut(merkle_tree_size);
+        let seq_bytes = tree_bytes[0..8].try_into().map_err(|_e| {
+            IngesterError::RpcGetDataError("Failed to convert seq bytes to array".to_string())
+        })?;
+        let seq = u64::from_le_bytes(seq_bytes);
+
+        // Only Bubblegum trees are indexed, and a tree without any changes has nothing to
+        // backfill yet.  It will be picked up on a later scan once it has been used.
+        if header.assert_valid_authority(&auth).is_err() {
+            continue;
+        }
+        shapes.push(TreeShape::new(pubkey, &header, canopy_bytes.len()));
+        if seq == 0 {
+            continue;
+        }
+        list.insert(pubkey, SlotSeq(header.get_creation_slot(), seq));
+    }
+    // The canopy depth is only known from the account, so discovery keeps it for the
+    // `merkle_tree` table.
+    save_tree_shapes(db, &shapes).await?;
+    Ok(list)
+}
+
+/// Extends the lease on a claimed tree every half lease, and sends on `lost` once `instance_name`
+/// no longer holds it, i.e. the lease expired and another backfiller claimed the tree.
+async fn heartbeat_lease(
+    pool: Pool<Postgres>,
+    tree: Vec<u8>,
+    instance_name: String,
+    lease: chrono::Duration,
+    lost: oneshot::Sender<()>,
+) {
+    let db = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+    let beat = Duration::from_millis((lease.num_milliseconds() / 2).max(500) as u64);
+    let mut heartbeats = time::interval_at(time::Instant::now() + beat, beat);
+    loop {
+        heartbeats.tick().await;
+        let now = Utc::now().naive_utc();
+        let res = backfill_queue::Entity::update_many()
+            .col_expr(
+                backfill_queue::Column::LockedUntil,
+                Expr::value(now + lease),
+            )
+            .col_expr(backfill_queue::Column::HeartbeatAt, Expr::value(now))
+            .filter(backfill_queue::Column::Tree.eq(tree.as_slice()))
+            .filter(backfill_queue::Column::LockedBy.eq(instance_name.as_str()))
+            .exec(&db)
+            .await;
+        match res {
+            Ok(res) if res.rows_affected > 0 => {}
+            Ok(_) => {
+                let _ = lost.send(());
+                return;
+            }
+            Err(err) => error!(
+                "Error heartbeating tree {}: {}",
+                bs58::encode(&tree).into_string(),
+                err
+            ),
+        }
+    }
+}
 /// Main public entry point for backfiller task.
 pub fn setup_backfiller<T: Messenger>(
     pool: Pool<Postgres>,
     config: IngesterConfig,
+    endpoints: RedisEndpoints,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
             let pool_cloned = pool.clone();
             let config_cloned = config.clone();
+            let endpoints_cloned = endpoints.clone();
             let block_cache = Arc::new(
                 AsyncCacheBuilder::new(BLOCK_CACHE_SIZE, MAX_CACHE_COST)
                     .set_ignore_internal_cost(true)
@@ -86,17 +202,38 @@ pub fn setup_backfiller<T: Messenger>(
             let bc = Arc::clone(&block_cache);
             tasks.spawn(async move {
                 info!("Backfiller filler running");
-                let mut backfiller = Backfiller::<T>::new(pool_cloned, config_cloned, &bc).await;
+                let mut backfiller =
+                    Backfiller::<T>::new(pool_cloned, config_cloned, endpoints_cloned, &bc).await;
                 backfiller.run_filler().await;
             });
 
+            // Tree discovery can be turned off by setting the interval to 0, in which case
+            // trees have to be enrolled into `backfill_items` manually.
+            let tree_sync_interval = config.get_tree_discovery_interval();
+            if tree_sync_interval > 0 {
+                let pool_cloned = pool.clone();
+                let config_cloned = config.clone();
+                let endpoints_cloned = endpoints.clone();
+                let bc = Arc::clone(&block_cache);
+                tasks.spawn(async move {
+                    info!("Backfiller finder running");
+                    let mut backfiller =
+                        Backfiller::<T>::new(pool_cloned, config_cloned, endpoints_cloned, &bc)
+                            .await;
+                    backfiller.run_finder(tree_sync_interval).await;
+                });
+            }
+
             let pool_cloned = pool.clone();
             let config_cloned = config.clone();
+            let endpoints_cloned = endpoints.clone();
             let bc = Arc::clone(&block_cache);
             tasks.spawn(async move {
-                info!("Backfiller finder running");
-                let mut backfiller = Backfiller::<T>::new(pool_cloned, config_cloned, &bc).await;
-                backfiller.run_finder().await;
+                info!("Backfiller slot retrier running");
+                let retry_limit = config_cloned.get_backfiller_slot_retry_limit();
+                let mut backfiller =
+                    Backfiller::<T>::new(pool_cloned, config_cloned, endpoints_cloned, &bc).await;
+                backfiller.run_slot_retrier(retry_limit).await;
             });
 
             while let Some(task) = tasks.join_next().await {
@@ -125,13 +262,6 @@ struct UniqueTree {
     tree: Vec<u8>,
 }
 
-/// Struct used when querying for unique trees.
-#[derive(Debug, FromQueryResult)]
-struct TreeWithSlot {
-    tree: Vec<u8>,
-    slot: i64,
-}
-
 #[derive(Debug, Default, Clone)]
 struct MissingTree {
     tree: Pubkey,
@@ -181,12 +311,44 @@ impl GapInfo {
     }
 }
 
+/// The transactions of a slot the backfiller couldn't send to the transaction stream.
+#[derive(Debug, Default)]
+struct SkippedTransactions {
+    // Transactions without metadata.
+    signatures: Vec<String>,
+    // Transactions that couldn't be decoded, which have no signature to retry them by.
+    undecodable: usize,
+}
+
+impl SkippedTransactions {
+    fn error(&self) -> Option<String> {
+        match (self.signatures.len(), self.undecodable) {
+            (0, 0) => None,
+            (missing, 0) => Some(format!("{missing} transactions without metadata")),
+            (missing, undecodable) => Some(format!(
+                "{missing} transactions without metadata and {undecodable} that couldn't be decoded"
+            )),
+        }
+    }
+
+    // The transactions to retry, or none to retry the whole slot.
+    fn retried(&self) -> Option<&[String]> {
+        (self.undecodable == 0).then_some(self.signatures.as_slice())
+    }
+}
+
 /// Main struct used for backfiller task.
 struct Backfiller<'a, T: Messenger> {
     db: DatabaseConnection,
+    pool: Pool<Postgres>,
+    instance_name: String,
+    lease: chrono::Duration,
     rpc_client: RpcClient,
-    rpc_block_config: RpcBlockConfig,
-    messenger: T,
+    lookup_tables: LookupTableCache,
+    lookup_table_throttle: RpcThrottle,
+    block_source: BlockSource,
+    record_failed_transactions: bool,
+    messenger: StreamProducer<T>,
     failure_delay: u64,
     cache: &'a AsyncCache<String, EncodedConfirmedBlock>,
 }
@@ -196,6 +358,7 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
     async fn new(
         pool: Pool<Postgres>,
         config: IngesterConfig,
+        endpoints: RedisEndpoints,
         cache: &'a AsyncCache<String, EncodedConfirmedBlock>,
     ) -> Backfiller<'a, T> {
         // Create Sea ORM database connection used later for queries.
@@ -252,27 +415,49 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
         };
 
         // Instantiate RPC client.
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, rpc_commitment);
+        let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), rpc_commitment);
 
-        // Instantiate messenger.
-        let mut messenger = T::new(config.get_messneger_client_config()).await.unwrap();
-        messenger.add_stream(TRANSACTION_STREAM).await.unwrap();
+        // Blocks are read through their own client, from Bigtable when configured.
+        let block_source = BlockSource::new(
+            config.backfiller_block_source.as_ref(),
+            RpcClient::new_with_commitment(rpc_url, rpc_commitment),
+            rpc_block_config,
+        )
+        .await
+        .unwrap();
+
+        // Instantiate messenger, failing over along with the stream consumers.
+        let mut messenger = StreamProducer::<T>::new(
+            config.get_messneger_client_config(),
+            endpoints,
+            &[TRANSACTION_STREAM],
+        )
+        .await
+        .unwrap();
         messenger
             .set_buffer_size(TRANSACTION_STREAM, 10_000_000)
             .await;
 
         Self {
             db,
+            pool,
+            instance_name: rand_string(),
+            lease: chrono::Duration::seconds(config.get_backfiller_lease_duration() as i64),
             rpc_client,
-            rpc_block_config,
+            lookup_tables: LookupTableCache::new(),
+            lookup_table_throttle: RpcThrottle::new(1),
+            block_source,
+            record_failed_transactions: config.get_record_failed_transactions(),
             messenger,
             failure_delay: INITIAL_FAILURE_DELAY,
             cache,
         }
     }
 
-    async fn run_finder(&mut self) {
-        let mut interval = time::interval(tokio::time::Duration::from_secs(TREE_SYNC_INTERVAL));
+    /// Periodically scans the account compression program for trees that are not indexed yet
+    /// and enrolls them to be backfilled from seq 1.
+    async fn run_finder(&mut self, tree_sync_interval: u64) {
+        let mut interval = time::interval(tokio::time::Duration::from_secs(tree_sync_interval));
         let sem = Semaphore::new(1);
         loop {
             interval.tick().await;
@@ -295,7 +480,10 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
                         let res2 = txn.commit().await;
                         match (res, res2) {
                             (Ok(_), Ok(_)) => {
-                                debug!("Set {} trees to backfill from 0", len);
+                                metric! {
+                                    statsd_count!("ingester.backfiller.discovered_trees", len as i64);
+                                }
+                                info!("Set {} trees to backfill from 0", len);
                             }
                             (Err(e), _) => {
                                 error!("Error setting trees to backfill from 0: {}", e);
@@ -312,66 +500,189 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
             }
         }
     }
-    /// Run the backfiller task.
+    /// Run the backfiller task. Trees needing a backfill are queued in `backfill_queue`, and each
+    /// backfiller works through the queue one leased tree at a time, so several backfillers split
+    /// the trees among themselves without fetching the same ones.
     async fn run_filler(&mut self) {
         let mut interval =
             time::interval(tokio::time::Duration::from_millis(MAX_BACKFILL_CHECK_WAIT));
         loop {
             interval.tick().await;
-            match self.get_trees_to_backfill().await {
-                Ok(backfill_trees) => {
-                    if !backfill_trees.is_empty() {
-                        for backfill_tree in backfill_trees {
-                            for tries in 1..=NUM_TRIES {
-                                // Get the tree out of nested structs.
-                                let tree = &backfill_tree.unique_tree.tree;
-                                let tree_string = bs58::encode(&tree).into_string();
-                                info!("Backfilling tree: {tree_string}");
-                                // Call different methods based on whether tree needs to be backfilled
-                                // completely from seq number 1 or just have any gaps in seq number
-                                // filled.
-                                let result = if backfill_tree.backfill_from_seq_1 {
-                                    self.backfill_tree_from_seq_1(&backfill_tree).await
-                                } else {
-                                    self.fetch_and_plug_gaps(tree).await
-                                };
-
-                                match result {
-                                    Ok(opt_max_seq) => {
-                                        // Successfully backfilled the tree.  Now clean up database.
-                                        self.clean_up_backfilled_tree(
-                                            opt_max_seq,
-                                            tree,
-                                            &tree_string,
-                                            tries,
-                                        )
-                                        .await;
-                                        self.reset_delay();
-                                        break;
-                                    }
-                                    Err(err) => {
-                                        error!("Failed to fetch and plug gaps for {tree_string}, attempt {tries}");
-                                        error!("{err}");
-                                    }
-                                }
+            if let Err(err) = self.enqueue_trees_to_backfill().await {
+                // Print error but keep trying.
+                error!("Could not queue trees to backfill: {err}");
+                self.sleep_and_increase_delay().await;
+                continue;
+            }
+            // Keep claiming until the queue is empty or the other backfillers hold the rest.
+            loop {
+                match self.claim_tree().await {
+                    Ok(Some(claimed)) => self.backfill_claimed_tree(claimed).await,
+                    Ok(None) => break,
+                    Err(err) => {
+                        error!("Could not claim a tree to backfill: {err}");
+                        self.sleep_and_increase_delay().await;
+                        break;
+                    }
+                }
+            }
+        }
+    }
 
-                                if tries == NUM_TRIES {
-                                    if let Err(err) = self.mark_tree_as_failed(tree).await {
-                                        error!("Error marking tree as failed to backfill: {err}");
-                                    }
-                                } else {
-                                    self.sleep_and_increase_delay().await;
-                                }
-                            }
+    /// Retries the slots quarantined by `plug_gap` once their backoff has passed, until they go
+    /// through or have failed `retry_limit` times. Slots are leased like trees, so several
+    /// backfillers split the retries between them.
+    async fn run_slot_retrier(&mut self, retry_limit: u32) {
+        let mut interval = time::interval(Duration::from_secs(FAILED_SLOT_CHECK_INTERVAL));
+        loop {
+            interval.tick().await;
+            if let Err(err) = report_failed_slots(&self.db).await {
+                error!("Could not count failed slots: {err}");
+            }
+            loop {
+                match claim_failed_slot(&self.db, self.lease).await {
+                    Ok(Some(failed)) => self.retry_failed_slot(failed, retry_limit).await,
+                    Ok(None) => break,
+                    Err(err) => {
+                        error!("Could not claim a failed slot to retry: {err}");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn retry_failed_slot(&mut self, failed: failed_slots::Model, retry_limit: u32) {
+        let tree_string = bs58::encode(&failed.tree).into_string();
+        let only = retried_signatures(&failed);
+        let res = match to_u64(failed.slot, "slot") {
+            Ok(slot) => self
+                .backfill_slot(slot, &failed.tree, only.as_deref())
+                .await
+                .and_then(|skipped| match skipped.error() {
+                    Some(error) => Err(IngesterError::DeserializationError(error)),
+                    None => Ok(()),
+                }),
+            Err(err) => Err(err.into()),
+        };
+        match res {
+            Ok(()) => {
+                metric! {
+                    statsd_count!("ingester.backfiller.slot_retried", 1, "status" => "success");
+                }
+                info!("Backfilled slot {} of {tree_string} on retry", failed.slot);
+                if let Err(err) = release_failed_slot(&self.db, &failed).await {
+                    error!(
+                        "Error releasing slot {} of {tree_string}: {err}",
+                        failed.slot
+                    );
+                }
+            }
+            Err(err) => {
+                metric! {
+                    statsd_count!("ingester.backfiller.slot_retried", 1, "status" => "failed");
+                }
+                match fail_slot_retry(&self.db, &failed, &err.to_string(), retry_limit).await {
+                    Ok(true) => {
+                        metric! {
+                            statsd_count!("ingester.backfiller.slot_given_up", 1);
                         }
+                        error!(
+                            "Giving up on slot {} of {tree_string} after {} attempts: {err}",
+                            failed.slot,
+                            failed.attempts + 1
+                        );
+                    }
+                    Ok(false) => warn!(
+                        "Retry of slot {} of {tree_string} failed: {err}",
+                        failed.slot
+                    ),
+                    Err(db_err) => {
+                        error!(
+                            "Error recording failed retry of slot {}: {db_err}",
+                            failed.slot
+                        )
                     }
                 }
+            }
+        }
+    }
+
+    /// Backfills a claimed tree while heartbeating its lease, then takes it off the queue. A tree
+    /// whose lease is lost is left to the backfiller that claimed it since.
+    async fn backfill_claimed_tree(&mut self, claimed: backfill_queue::Model) {
+        let tree = claimed.tree.clone();
+        let tree_string = bs58::encode(&tree).into_string();
+        metric! {
+            statsd_count!("ingester.backfiller.tree_claimed", 1);
+        }
+        let (lost_tx, lost_rx) = oneshot::channel();
+        let heartbeat = tokio::spawn(heartbeat_lease(
+            self.pool.clone(),
+            tree.clone(),
+            self.instance_name.clone(),
+            self.lease,
+            lost_tx,
+        ));
+        let backfill_tree = BackfillTree::new(
+            UniqueTree { tree: claimed.tree },
+            claimed.force_chk,
+            claimed.slot as u64,
+        );
+        let lost = tokio::select! {
+            _ = self.backfill_tree(&backfill_tree) => false,
+            Ok(()) = lost_rx => true,
+        };
+        heartbeat.abort();
+        if lost {
+            metric! {
+                statsd_count!("ingester.backfiller.lease_lost", 1);
+            }
+            warn!("Lost the lease on tree {tree_string}, leaving it to its new holder");
+            return;
+        }
+        if let Err(err) = self.release_tree(&tree).await {
+            error!("Error releasing tree {tree_string}: {err}");
+        }
+    }
+
+    // Backfills a tree, marking it as failed after `NUM_TRIES` failed attempts.
+    async fn backfill_tree(&mut self, backfill_tree: &BackfillTree) {
+        for tries in 1..=NUM_TRIES {
+            // Get the tree out of nested structs.
+            let tree = &backfill_tree.unique_tree.tree;
+            let tree_string = bs58::encode(&tree).into_string();
+            info!("Backfilling tree: {tree_string}");
+            // Call different methods based on whether tree needs to be backfilled
+            // completely from seq number 1 or just have any gaps in seq number
+            // filled.
+            let result = if backfill_tree.backfill_from_seq_1 {
+                self.backfill_tree_from_seq_1(backfill_tree).await
+            } else {
+                self.fetch_and_plug_gaps(tree).await
+            };
+
+            match result {
+                Ok(opt_max_seq) => {
+                    // Successfully backfilled the tree.  Now clean up database.
+                    self.clean_up_backfilled_tree(opt_max_seq, tree, &tree_string, tries)
+                        .await;
+                    self.reset_delay();
+                    break;
+                }
                 Err(err) => {
-                    // Print error but keep trying.
-                    error!("Could not get trees to backfill from db: {err}");
-                    self.sleep_and_increase_delay().await;
+                    error!("Failed to fetch and plug gaps for {tree_string}, attempt {tries}");
+                    error!("{err}");
                 }
             }
+
+            if tries == NUM_TRIES {
+                if let Err(err) = self.mark_tree_as_failed(tree).await {
+                    error!("Error marking tree as failed to backfill: {err}");
+                }
+            } else {
+                self.sleep_and_increase_delay().await;
+            }
         }
     }
 
@@ -456,14 +767,14 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
     ) -> Result<Vec<MissingTree>, IngesterError> {
         let mut all_trees: HashMap<Pubkey, SlotSeq> = self.fetch_trees_by_gpa().await?;
         debug!("Number of Trees on Chain {}", all_trees.len());
-        let get_locked_or_failed_trees = Statement::from_string(
+        // Any tree already in `backfill_items` is either queued, being backfilled, locked or
+        // failed, so enrolling it again would only create duplicate work.
+        let get_enrolled_trees = Statement::from_string(
             DbBackend::Postgres,
-            "SELECT DISTINCT tree FROM backfill_items WHERE failed = true\n\
-             OR locked = true"
-                .to_string(),
+            "SELECT DISTINCT tree FROM backfill_items".to_string(),
         );
-        let locked_trees = cn.query_all(get_locked_or_failed_trees).await?;
-        for row in locked_trees.into_iter() {
+        let enrolled_trees = cn.query_all(get_enrolled_trees).await?;
+        for row in enrolled_trees.into_iter() {
             let tree = UniqueTree::from_query_result(&row, "")?;
             let key = &Pubkey::new(&tree.tree);
             if all_trees.contains_key(key) {
@@ -494,106 +805,68 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
         Ok(missing_trees)
     }
 
-    async fn get_trees_to_backfill(&self) -> Result<Vec<BackfillTree>, DbErr> {
-        // Start a db transaction.
-        let txn = self.db.begin().await?;
-
-        // Get trees with the `force_chk` flag set to true (that have not failed and are not locked).
-        let force_chk_trees = Statement::from_string(
-            DbBackend::Postgres,
-            "SELECT DISTINCT backfill_items.tree, backfill_items.slot FROM backfill_items\n\
-            WHERE backfill_items.force_chk = TRUE\n\
-            AND backfill_items.failed = FALSE\n\
-            AND backfill_items.locked = FALSE"
-                .to_string(),
-        );
-
-        let force_chk_trees: Vec<TreeWithSlot> =
-            txn.query_all(force_chk_trees).await.map(|qr| {
-                qr.iter()
-                    .map(|q| TreeWithSlot::from_query_result(q, "").unwrap())
-                    .collect()
-            })?;
-
-        debug!(
-            "Number of force check trees to backfill: {} {}",
-            force_chk_trees.len(),
-            Utc::now()
-        );
-
-        for tree in force_chk_trees.iter() {
-            let stmt = backfill_items::Entity::update_many()
-                .col_expr(backfill_items::Column::Locked, Expr::value(true))
-                .filter(backfill_items::Column::Tree.eq(&*tree.tree))
-                .build(DbBackend::Postgres);
-
-            if let Err(err) = txn.execute(stmt).await {
-                error!(
-                    "Error marking tree {} as locked: {}",
-                    bs58::encode(&tree.tree).into_string(),
-                    err
-                );
-                return Err(err);
-            }
-        }
-
-        // Get trees with multiple rows from `backfill_items` table (that have not failed and are not locked).
-        let multi_row_trees = Statement::from_string(
-            DbBackend::Postgres,
-            "SELECT backfill_items.tree, max(backfill_items.slot) as slot FROM backfill_items\n\
-            WHERE backfill_items.failed = FALSE
-            AND backfill_items.locked = FALSE\n\
-            GROUP BY backfill_items.tree\n\
-            HAVING COUNT(*) > 1"
+    /// Queues the trees that are enrolled to be backfilled from seq 1 or have gaps to plug, unless
+    /// they have failed. Trees that are queued already, claimed or not, are left as they are.
+    async fn enqueue_trees_to_backfill(&self) -> Result<(), DbErr> {
+        let res = self
+            .db
+            .execute(Statement::from_string(
+                DbBackend::Postgres,
+                "
+INSERT INTO backfill_queue (tree, force_chk, slot)
+SELECT tree, bool_or(force_chk), COALESCE(max(slot) FILTER (WHERE force_chk), max(slot))
+FROM backfill_items
+WHERE failed = FALSE
+GROUP BY tree
+HAVING bool_or(force_chk) OR COUNT(*) > 1
+ON CONFLICT (tree) DO NOTHING
+"
                 .to_string(),
-        );
-
-        let multi_row_trees: Vec<TreeWithSlot> =
-            txn.query_all(multi_row_trees).await.map(|qr| {
-                qr.iter()
-                    .map(|q| TreeWithSlot::from_query_result(q, "").unwrap())
-                    .collect()
-            })?;
-
-        debug!(
-            "Number of multi-row trees to backfill {}",
-            multi_row_trees.len()
-        );
-
-        for tree in multi_row_trees.iter() {
-            let stmt = backfill_items::Entity::update_many()
-                .col_expr(backfill_items::Column::Locked, Expr::value(true))
-                .filter(backfill_items::Column::Tree.eq(&*tree.tree))
-                .build(DbBackend::Postgres);
-
-            if let Err(err) = txn.execute(stmt).await {
-                error!(
-                    "Error marking tree {} as locked: {}",
-                    bs58::encode(&tree.tree).into_string(),
-                    err
-                );
-                return Err(err);
-            }
-        }
-
-        // Close out transaction and relinqish the lock.
-        txn.commit().await?;
-
-        // Convert force check trees Vec of `UniqueTree` to a Vec of `BackfillTree` (which contain extra info).
-        let mut trees: Vec<BackfillTree> = force_chk_trees
-            .into_iter()
-            .map(|tree| BackfillTree::new(UniqueTree { tree: tree.tree }, true, tree.slot as u64))
-            .collect();
-
-        // Convert multi-row trees Vec of `UniqueTree` to a Vec of `BackfillTree` (which contain extra info).
-        let mut multi_row_trees: Vec<BackfillTree> = multi_row_trees
-            .into_iter()
-            .map(|tree| BackfillTree::new(UniqueTree { tree: tree.tree }, false, tree.slot as u64))
-            .collect();
+            ))
+            .await?;
+        debug!("Queued {} trees to backfill", res.rows_affected());
+        Ok(())
+    }
 
-        trees.append(&mut multi_row_trees);
+    /// Claims the next queued tree for this backfiller, leasing it for `lease`. Trees enrolled to
+    /// be backfilled from seq 1 come first. A tree another backfiller is claiming at the same
+    /// moment is skipped rather than waited on, and a tree whose lease ran out without a
+    /// heartbeat can be claimed again.
+    async fn claim_tree(&self) -> Result<Option<backfill_queue::Model>, DbErr> {
+        let now = Utc::now().naive_utc();
+        backfill_queue::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "
+UPDATE backfill_queue
+SET locked_by = $1, locked_until = $2, heartbeat_at = $3
+WHERE tree = (
+    SELECT tree FROM backfill_queue
+    WHERE locked_until IS NULL OR locked_until <= $3
+    ORDER BY force_chk DESC, created_at ASC
+    LIMIT 1
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING *
+",
+                vec![
+                    self.instance_name.clone().into(),
+                    (now + self.lease).into(),
+                    now.into(),
+                ],
+            ))
+            .one(&self.db)
+            .await
+    }
 
-        Ok(trees)
+    /// Takes a tree this backfiller holds off the queue.
+    async fn release_tree(&self, tree: &[u8]) -> Result<(), DbErr> {
+        backfill_queue::Entity::delete_many()
+            .filter(backfill_queue::Column::Tree.eq(tree))
+            .filter(backfill_queue::Column::LockedBy.eq(self.instance_name.as_str()))
+            .exec(&self.db)
+            .await?;
+        Ok(())
     }
 
     async fn backfill_tree_from_seq_1(
@@ -620,47 +893,29 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
     }
 
     async fn find_slots_via_address(&self, address: &Pubkey) -> Result<Vec<Slot>, IngesterError> {
-        let mut last_sig = None;
         let mut slots = HashSet::new();
         // TODO: Any log running function like this should actually be run in a way that supports re-entry,
         // usually we woudl break the tasks into smaller parralel tasks and we woudl not worry about it, but in this we have several linearally dpendent async tasks
         // and if they fail, it causes a chain reaction of failures since the dependant nature of it affects the next task. Right now you are just naivley looping and
         // hoping for the best what needs to happen is to start saving the state opf each task with the last signature that was retuned iun durable storage.
         // Then if the task fails, you can restart it from the last signature that was returned.
-        loop {
-            let before = last_sig;
-            let sigs = self
-                .rpc_client
-                .get_signatures_for_address_with_config(
-                    address,
-                    GetConfirmedSignaturesForAddress2Config {
-                        before,
-                        until: None,
-                        ..GetConfirmedSignaturesForAddress2Config::default()
-                    },
-                )
-                .await
-                .map_err(|e| {
-                    IngesterError::RpcGetDataError(format!(
-                        "GetSignaturesForAddressWithConfig failed {}",
-                        e
-                    ))
-                })?;
-            for sig in sigs.iter() {
-                let slot = sig.slot;
-                let sig = Signature::from_str(&sig.signature).map_err(|e| {
-                    IngesterError::RpcDataUnsupportedFormat(format!(
-                        "Failed to parse signature {}",
-                        e
-                    ))
-                })?;
-
-                slots.insert(slot);
-                last_sig = Some(sig);
-            }
-            if sigs.is_empty() || sigs.len() < 1000 {
-                break;
-            }
+        let mut found = find_signature_slots(
+            *address,
+            RpcClient::new_with_commitment(self.rpc_client.url(), self.rpc_client.commitment()),
+            RpcThrottle::new(1),
+            None,
+            None,
+            SIGNATURE_BUFFER,
+            false,
+        );
+        while let Some(item) = found.recv().await {
+            let (_signature, slot) = item.map_err(|e| {
+                IngesterError::RpcGetDataError(format!(
+                    "GetSignaturesForAddressWithConfig failed {}",
+                    e
+                ))
+            })?;
+            slots.insert(slot);
         }
         Ok(Vec::from_iter(slots))
     }
@@ -688,48 +943,7 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
     }
 
     async fn fetch_trees_by_gpa(&self) -> Result<HashMap<Pubkey, SlotSeq>, IngesterError> {
-        let config = RpcProgramAccountsConfig {
-            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                0,
-                vec![1u8],
-            ))]),
-            account_config: RpcAccountInfoConfig {
-                encoding: Some(UiAccountEncoding::Base64),
-                ..RpcAccountInfoConfig::default()
-            },
-            ..RpcProgramAccountsConfig::default()
-        };
-        let results: Vec<(Pubkey, Account)> = self
-            .rpc_client
-            .get_program_accounts_with_config(&spl_account_compression::id(), config)
-            .await
-            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
-        let mut list = HashMap::with_capacity(results.len());
-        for r in results.into_iter() {
-            let (pubkey, mut account) = r;
-            let (mut header_bytes, rest) = account
-                .data
-                .split_at_mut(CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1);
-            let header: ConcurrentMerkleTreeHeader =
-                ConcurrentMerkleTreeHeader::try_from_slice(&mut header_bytes)
-                    .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
-
-            let auth = Pubkey::find_program_address(&[pubkey.as_ref()], &mpl_bubblegum::id()).0;
-
-            let merkle_tree_size = merkle_tree_get_size(&header)
-                .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
-            let (tree_bytes, _canopy_bytes) = rest.split_at_mut(merkle_tree_size);
-            let seq_bytes = tree_bytes[0..8].try_into().map_err(|_e| {
-                IngesterError::RpcGetDataError("Failed to convert seq bytes to array".to_string())
-            })?;
-            let seq = u64::from_le_bytes(seq_bytes);
-            list.insert(pubkey, SlotSeq(header.get_creation_slot(), seq));
-
-            if header.assert_valid_authority(&auth).is_err() {
-                continue;
-            }
-        }
-        Ok(list)
+        fetch_bubblegum_trees(&self.rpc_client, &self.db).await
     }
 
     // Similar to `fetchAndPlugGaps()` in `backfiller.ts`.
@@ -820,13 +1034,10 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
             num_iter = 1;
         }
         for _ in 0..num_iter {
-            get_confirmed_slot_tasks.push(self.rpc_client.get_blocks_with_commitment(
-                start_slot as u64,
-                Some(end_slot as u64),
-                CommitmentConfig {
-                    commitment: CommitmentLevel::Confirmed,
-                },
-            ));
+            get_confirmed_slot_tasks.push(
+                self.block_source
+                    .get_blocks(start_slot as u64, end_slot as u64),
+            );
             start_slot = end_slot;
             end_slot = cmp::min(end_slot + 500_000, gap.curr.slot);
         }
@@ -834,123 +1045,185 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
             .collect::<Vec<_>>()
             .await
             .into_iter()
-            .filter_map(|x| x.ok())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .flatten();
         for slot in result_slots {
-            let key = format!("block{}", slot);
-            let mut cached_block = self.cache.get(&key);
-            if cached_block.is_none() {
-                debug!("Fetching block {} from RPC", slot);
-                let block = EncodedConfirmedBlock::from(
-                    self.rpc_client
-                        .get_block_with_config(slot as u64, self.rpc_block_config)
-                        .await
-                        .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?,
-                );
-                let cost = cmp::min(32, block.transactions.len() as i64);
-                let write = self
-                    .cache
-                    .try_insert_with_ttl(
-                        key.clone(),
-                        block,
-                        cost,
-                        Duration::from_secs(BLOCK_CACHE_DURATION),
-                    )
-                    .await?;
-
-                if !write {
-                    return Err(IngesterError::CacheStorageWriteError(format!(
-                        "Cache Write Failed on {} is missing.",
-                        &key
-                    )));
+            // The rest of the gap is still plugged, the failed slot is retried on its own.
+            match self.backfill_slot(slot, tree, None).await {
+                Ok(skipped) => {
+                    if let Some(error) = skipped.error() {
+                        warn!(
+                            "Skipped {error} in slot {slot} of {}, quarantining them",
+                            bs58::encode(tree).into_string()
+                        );
+                        quarantine_slot(&self.db, tree, slot, &error, skipped.retried()).await?;
+                    }
+                }
+                Err(err) => {
+                    error!(
+                        "Failed to backfill slot {slot} of {}, quarantining it: {err}",
+                        bs58::encode(tree).into_string()
+                    );
+                    quarantine_slot(&self.db, tree, slot, &err.to_string(), None).await?;
                 }
-                self.cache.wait().await?;
-                cached_block = self.cache.get(&key);
             }
-            if cached_block.is_none() {
+        }
+
+        Ok(())
+    }
+
+    /// Sends the transactions of `slot` that touch `tree` or Bubblegum to the transaction stream.
+    /// Only the transactions `only` are sent when given. Transactions without metadata or that
+    /// can't be decoded are skipped and returned, for the slot to be quarantined without holding
+    /// up the rest of it.
+    async fn backfill_slot(
+        &mut self,
+        slot: u64,
+        tree: &[u8],
+        only: Option<&[String]>,
+    ) -> Result<SkippedTransactions, IngesterError> {
+        let key = format!("block{}", slot);
+        let mut cached_block = self.cache.get(&key);
+        if cached_block.is_none() {
+            debug!("Fetching block {}", slot);
+            let block = self.block_source.get_block(slot).await?;
+            let cost = cmp::min(32, block.transactions.len() as i64);
+            let write = self
+                .cache
+                .try_insert_with_ttl(
+                    key.clone(),
+                    block,
+                    cost,
+                    Duration::from_secs(BLOCK_CACHE_DURATION),
+                )
+                .await?;
+
+            if !write {
                 return Err(IngesterError::CacheStorageWriteError(format!(
-                    "Cache Procedure Failed {} is missing.",
+                    "Cache Write Failed on {} is missing.",
                     &key
                 )));
             }
-            let block_ref = cached_block.unwrap();
-            let block_data = block_ref.value();
-
-            for tx in block_data.transactions.iter() {
-                // See if transaction has an error.
-                let meta = if let Some(meta) = &tx.meta {
-                    if let Some(_err) = &meta.err {
-                        continue;
-                    }
-                    meta
-                } else {
-                    error!("Unexpected, EncodedTransactionWithStatusMeta struct has no metadata");
+            self.cache.wait().await?;
+            cached_block = self.cache.get(&key);
+        }
+        if cached_block.is_none() {
+            return Err(IngesterError::CacheStorageWriteError(format!(
+                "Cache Procedure Failed {} is missing.",
+                &key
+            )));
+        }
+        let block_ref = cached_block.unwrap();
+        let block_data = block_ref.value();
+        let tree_string = bs58::encode(tree).into_string();
+        let mut skipped = SkippedTransactions::default();
+
+        for tx in block_data.transactions.iter() {
+            // Addresses loaded from lookup tables the block's meta leaves out are filled in, for
+            // the filter below and for the ingester parsing the transaction. Without them the
+            // transaction could be missed, so the slot is quarantined when they can't be read.
+            let mut tx = Cow::Borrowed(tx);
+            if missing_lookups(&tx).is_some() {
+                self.lookup_tables
+                    .fill_loaded_addresses(
+                        &self.rpc_client,
+                        &self.lookup_table_throttle,
+                        tx.to_mut(),
+                    )
+                    .await
+                    .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+            }
+            let decoded_tx = match tx.transaction.decode() {
+                Some(decoded_tx) if !decoded_tx.signatures.is_empty() => decoded_tx,
+                _ => {
+                    error!("Unable to decode a transaction of slot {slot} of {tree_string}");
+                    skipped.undecodable += 1;
                     continue;
-                };
-                let decoded_tx = if let Some(decoded_tx) = tx.transaction.decode() {
-                    decoded_tx
-                } else {
-                    error!("Unable to decode transaction");
+                }
+            };
+            let sig = decoded_tx.signatures[0].to_string();
+            if matches!(only, Some(only) if !only.contains(&sig)) {
+                continue;
+            }
+            // Failed transactions are only recorded once they are known to touch the tree.
+            let meta = if let Some(meta) = &tx.meta {
+                if meta.err.is_some() && !self.record_failed_transactions {
                     continue;
-                };
-                let sig = decoded_tx.signatures[0].to_string();
-                let msg = decoded_tx.message;
-                let atl_keys = msg.address_table_lookups();
-                let tree = Pubkey::try_from(tree)
-                    .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
-                let account_keys = msg.static_account_keys();
-                let account_keys = {
-                    let mut account_keys_vec = vec![];
-                    for key in account_keys.iter() {
-                        account_keys_vec.push(key.to_bytes());
-                    }
-                    if atl_keys.is_some() {
-                        if let OptionSerializer::Some(ad) = &meta.loaded_addresses {
-                            for i in &ad.writable {
-                                let mut output: [u8; 32] = [0; 32];
-                                bs58::decode(i).into(&mut output).map_err(|e| {
-                                    IngesterError::DeserializationError(e.to_string())
-                                })?;
-                                account_keys_vec.push(output);
-                            }
+                }
+                meta
+            } else {
+                error!("Transaction {sig} of slot {slot} of {tree_string} has no metadata");
+                skipped.signatures.push(sig);
+                continue;
+            };
+            let msg = decoded_tx.message;
+            let atl_keys = msg.address_table_lookups();
+            let tree = Pubkey::try_from(tree)
+                .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+            let account_keys = msg.static_account_keys();
+            let account_keys = {
+                let mut account_keys_vec = vec![];
+                for key in account_keys.iter() {
+                    account_keys_vec.push(key.to_bytes());
+                }
+                if atl_keys.is_some() {
+                    if let OptionSerializer::Some(ad) = &meta.loaded_addresses {
+                        for i in &ad.writable {
+                            let mut output: [u8; 32] = [0; 32];
+                            bs58::decode(i)
+                                .into(&mut output)
+                                .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+                            account_keys_vec.push(output);
+                        }
 
-                            for i in &ad.readonly {
-                                let mut output: [u8; 32] = [0; 32];
-                                bs58::decode(i).into(&mut output).map_err(|e| {
-                                    IngesterError::DeserializationError(e.to_string())
-                                })?;
-                                account_keys_vec.push(output);
-                            }
+                        for i in &ad.readonly {
+                            let mut output: [u8; 32] = [0; 32];
+                            bs58::decode(i)
+                                .into(&mut output)
+                                .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+                            account_keys_vec.push(output);
                         }
                     }
-                    account_keys_vec
-                };
-
-                // Filter out transactions that don't have to do with the tree we are interested in or
-                // the Bubblegum program.
-                let tb = tree.to_bytes();
-                let bubblegum = blockbuster::programs::bubblegum::program_id().to_bytes();
-                if account_keys.iter().all(|pk| *pk != tb && *pk != bubblegum) {
-                    continue;
                 }
+                account_keys_vec
+            };
 
-                // Serialize data.
-                let builder = FlatBufferBuilder::new();
-                debug!("Serializing transaction in backfiller {}", sig);
-                let tx_wrap = EncodedConfirmedTransactionWithStatusMeta {
-                    transaction: tx.to_owned(),
-                    slot,
-                    block_time: block_data.block_time,
-                };
-                let builder = seralize_encoded_transaction_with_status(builder, tx_wrap)?;
-                self.messenger
-                    .send(TRANSACTION_STREAM, builder.finished_data())
-                    .await?;
+            // Filter out transactions that don't have to do with the tree we are interested in or
+            // the Bubblegum program.
+            let tb = tree.to_bytes();
+            let bubblegum = blockbuster::programs::bubblegum::program_id().to_bytes();
+            if account_keys.iter().all(|pk| *pk != tb && *pk != bubblegum) {
+                continue;
+            }
+
+            if let Some(err) = &meta.err {
+                let keys: Vec<Vec<u8>> = account_keys.iter().map(|k| k.to_vec()).collect();
+                record_failed_transaction(&self.db, &sig, &keys, &err.to_string(), slot).await?;
+                continue;
             }
-            drop(block_ref);
+
+            // Serialize data.
+            let builder = FlatBufferBuilder::new();
+            debug!("Serializing transaction in backfiller {}", sig);
+            let tx_wrap = EncodedConfirmedTransactionWithStatusMeta {
+                transaction: tx.into_owned(),
+                slot,
+                block_time: block_data.block_time,
+            };
+            let builder = seralize_encoded_transaction_with_status(builder, tx_wrap)?;
+            self.messenger
+                .send(TRANSACTION_STREAM, builder.finished_data())
+                .await?;
         }
+        drop(block_ref);
 
-        Ok(())
+        if skipped.error().is_some() {
+            metric! {
+                statsd_count!("ingester.backfiller.transaction_skipped", (skipped.signatures.len() + skipped.undecodable) as i64);
+            }
+        }
+        Ok(skipped)
     }
 
     async fn delete_extra_rows_and_mark_as_backfilled(
@@ -1001,9 +1274,6 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
         // Clear the `force_chk` flag if it was set.
         self.clear_force_chk_flag(tree).await?;
 
-        // Unlock tree.
-        self.unlock_tree(tree).await?;
-
         // Debug.
         let test_items = backfill_items::Entity::find()
             .filter(backfill_items::Column::Tree.eq(tree))
@@ -1032,14 +1302,4 @@ impl<'a, T: Messenger> Backfiller<'a, T> {
 
         Ok(())
     }
-
-    async fn unlock_tree(&self, tree: &[u8]) -> Result<(), DbErr> {
-        backfill_items::Entity::update_many()
-            .col_expr(backfill_items::Column::Locked, Expr::value(false))
-            .filter(backfill_items::Column::Tree.eq(tree))
-            .exec(&self.db)
-            .await?;
-
-        Ok(())
-    }
 }
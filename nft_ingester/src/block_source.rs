@@ -0,0 +1,180 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::warn;
+use serde::Deserialize;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcBlockConfig};
+use solana_sdk::{
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    slot_history::Slot,
+};
+use solana_storage_bigtable::{CredentialType, LedgerStorage, LedgerStorageConfig};
+use solana_transaction_status::{
+    BlockEncodingOptions, EncodedConfirmedBlock, TransactionDetails, UiTransactionEncoding,
+};
+use std::time::Duration;
+
+const DEFAULT_INSTANCE_NAME: &str = "solana-ledger";
+const DEFAULT_APP_PROFILE_ID: &str = "default";
+
+/// Where the backfiller reads historical blocks from, RPC unless configured otherwise, e.g.
+/// `'{type="Bigtable", credentials_path="/secrets/bigtable.json", instance_name="solana-ledger"}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum BlockSourceConfig {
+    Rpc,
+    /// A Bigtable block archive as written by `solana-ledger-tool bigtable upload`. Without
+    /// `credentials_path` the `GOOGLE_APPLICATION_CREDENTIALS` file is used. `timeout` is in
+    /// seconds.
+    Bigtable {
+        credentials_path: Option<String>,
+        instance_name: Option<String>,
+        app_profile_id: Option<String>,
+        timeout: Option<u64>,
+    },
+}
+
+/// Reads the confirmed slots and blocks the backfiller replays. Blocks the archive doesn't have
+/// yet, such as recent ones that aren't uploaded, are read from RPC instead.
+pub struct BlockSource {
+    rpc_client: RpcClient,
+    rpc_block_config: RpcBlockConfig,
+    bigtable: Option<LedgerStorage>,
+}
+
+impl BlockSource {
+    pub async fn new(
+        config: Option<&BlockSourceConfig>,
+        rpc_client: RpcClient,
+        rpc_block_config: RpcBlockConfig,
+    ) -> Result<Self, IngesterError> {
+        let bigtable = match config {
+            Some(BlockSourceConfig::Bigtable {
+                credentials_path,
+                instance_name,
+                app_profile_id,
+                timeout,
+            }) => {
+                let storage = LedgerStorage::new_with_config(LedgerStorageConfig {
+                    read_only: true,
+                    timeout: timeout.map(Duration::from_secs),
+                    credential_type: CredentialType::Filepath(credentials_path.clone()),
+                    instance_name: instance_name
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_INSTANCE_NAME.to_string()),
+                    app_profile_id: app_profile_id
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_APP_PROFILE_ID.to_string()),
+                })
+                .await
+                .map_err(|e| IngesterError::ConfigurationError {
+                    msg: format!("Failed to connect to Bigtable: {}", e),
+                })?;
+                Some(storage)
+            }
+            Some(BlockSourceConfig::Rpc) | None => None,
+        };
+        Ok(BlockSource {
+            rpc_client,
+            rpc_block_config,
+            bigtable,
+        })
+    }
+
+    /// The confirmed slots from `start_slot` to `end_slot` inclusive. The slots past the end of
+    /// the archive, which hasn't caught up with them yet, are listed from RPC.
+    pub async fn get_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<Slot>, IngesterError> {
+        if let Some(bigtable) = &self.bigtable {
+            let limit = end_slot.saturating_sub(start_slot) as usize + 1;
+            match bigtable.get_confirmed_blocks(start_slot, limit).await {
+                Ok(slots) => {
+                    // The archive has the whole range when it lists a slot past it or every slot
+                    // of it.
+                    let complete =
+                        slots.len() >= limit || slots.last().map_or(false, |s| *s > end_slot);
+                    let mut slots: Vec<Slot> =
+                        slots.into_iter().filter(|slot| *slot <= end_slot).collect();
+                    if !complete {
+                        let from = slots.last().map_or(start_slot, |slot| slot + 1);
+                        metric! {
+                            statsd_count!("ingester.backfiller.bigtable_fallback", 1, "call" => "get_blocks_tail");
+                        }
+                        slots.extend(self.rpc_blocks(from, end_slot).await?);
+                    }
+                    return Ok(slots);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to list slots {}-{} from Bigtable: {}",
+                        start_slot, end_slot, e
+                    );
+                    metric! {
+                        statsd_count!("ingester.backfiller.bigtable_fallback", 1, "call" => "get_blocks");
+                    }
+                }
+            }
+        }
+        self.rpc_blocks(start_slot, end_slot).await
+    }
+
+    async fn rpc_blocks(
+        &self,
+        start_slot: Slot,
+        end_slot: Slot,
+    ) -> Result<Vec<Slot>, IngesterError> {
+        if start_slot > end_slot {
+            return Ok(vec![]);
+        }
+        self.rpc_client
+            .get_blocks_with_commitment(
+                start_slot,
+                Some(end_slot),
+                CommitmentConfig {
+                    commitment: CommitmentLevel::Confirmed,
+                },
+            )
+            .await
+            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))
+    }
+
+    pub async fn get_block(&self, slot: Slot) -> Result<EncodedConfirmedBlock, IngesterError> {
+        if let Some(bigtable) = &self.bigtable {
+            match bigtable.get_confirmed_block(slot).await {
+                Ok(block) => {
+                    let block = block
+                        .encode_with_options(
+                            UiTransactionEncoding::Base64,
+                            BlockEncodingOptions {
+                                transaction_details: TransactionDetails::Full,
+                                show_rewards: false,
+                                max_supported_transaction_version: Some(0),
+                            },
+                        )
+                        .map_err(|e| IngesterError::RpcDataUnsupportedFormat(e.to_string()))?;
+                    metric! {
+                        statsd_count!("ingester.backfiller.block_fetched", 1, "source" => "bigtable");
+                    }
+                    return Ok(EncodedConfirmedBlock::from(block));
+                }
+                Err(e) => {
+                    warn!("Failed to get block {} from Bigtable: {}", slot, e);
+                    metric! {
+                        statsd_count!("ingester.backfiller.bigtable_fallback", 1, "call" => "get_block");
+                    }
+                }
+            }
+        }
+        let block = self
+            .rpc_client
+            .get_block_with_config(slot, self.rpc_block_config)
+            .await
+            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+        metric! {
+            statsd_count!("ingester.backfiller.block_fetched", 1, "source" => "rpc");
+        }
+        Ok(EncodedConfirmedBlock::from(block))
+    }
+}
@@ -0,0 +1,786 @@
+//! Bootstraps a new deployment from the chain's history: discovers the Bubblegum trees, replays
+//! each tree's transactions oldest first, sends the accounts the indexed assets need and waits
+//! for their metadata to download. The phases and each tree's last replayed signature are kept in
+//! `bootstrap_phases` and `bootstrap_trees`, so a restarted bootstrap resumes where it stopped.
+
+use crate::{
+    backfiller::fetch_bubblegum_trees, config::IngesterConfig, error::IngesterError, metric,
+    program_transformers::indexed_programs, redis_failover::RedisEndpoints, reindex::send_account,
+    stream_control::StreamProducer, tasks::TaskManager,
+};
+use blockbuster::{
+    program_handler::ProgramParser, programs::token_metadata::TokenMetadataParser,
+    token_metadata::state::Key,
+};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use das_tree_reader::{fetch_transaction, find_signatures, RpcThrottle};
+use digital_asset_types::dao::{numeric::to_i64, COLLECTION_GROUP_KEY};
+use flatbuffers::FlatBufferBuilder;
+use futures::StreamExt;
+use log::{error, info};
+use plerkle_messenger::{redis_messenger::RedisMessenger, ACCOUNT_STREAM, TRANSACTION_STREAM};
+use plerkle_serialization::serializer::seralize_encoded_transaction_with_status;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector,
+    Statement, Value,
+};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    signature::Signature,
+};
+use sqlx::{Pool, Postgres};
+use std::{collections::HashSet, str::FromStr, sync::Arc};
+use tokio::{
+    task::JoinHandle,
+    time::{self, sleep, Duration, MissedTickBehavior},
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+// Signatures found ahead of the transactions fetched for them.
+const SIGNATURE_BUFFER: usize = 2_000;
+// Transactions replayed between two saves of a tree's progress.
+const CHECKPOINT_EVERY: i64 = 500;
+// Accounts per `getMultipleAccounts` request, the RPC maximum.
+const ACCOUNT_BATCH_SIZE: usize = 100;
+// Trees inserted per statement.
+const TREE_BATCH_SIZE: usize = 1000;
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+// Where the mint is in a metadata account, after its key and update authority.
+const METADATA_MINT_OFFSET: usize = 33;
+
+/// The token-metadata accounts the ingesters index, by the key their data starts with.
+pub const SNAPSHOT_TOKEN_METADATA_KEYS: [Key; 5] = [
+    Key::MetadataV1,
+    Key::MasterEditionV1,
+    Key::MasterEditionV2,
+    Key::EditionV1,
+    Key::TokenRecord,
+];
+
+/// How hard the bootstrap may push the RPC and the streams, e.g.
+/// `'{concurrency=10, max_transactions_per_second=200, max_stream_size=100000}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct BootstrapConfig {
+    pub concurrency: Option<usize>,
+    pub max_transactions_per_second: Option<u32>,
+    pub max_stream_size: Option<u64>,
+    pub max_retries: Option<u8>,
+    /// Programs whose every account is sent, only sensible for programs with few accounts.
+    pub snapshot_programs: Option<Vec<String>>,
+    /// Whether every token-metadata account is sent, along with the mints of the metadata.
+    pub snapshot_token_metadata: Option<bool>,
+}
+
+impl BootstrapConfig {
+    /// Most RPC requests in flight.
+    pub fn get_concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(10).max(1)
+    }
+
+    /// Transactions sent per second, unlimited by default.
+    pub fn get_max_transactions_per_second(&self) -> Option<u32> {
+        self.max_transactions_per_second.filter(|tps| *tps > 0)
+    }
+
+    /// Entries the transaction stream may hold before the replay waits for the ingesters.
+    pub fn get_max_stream_size(&self) -> u64 {
+        self.max_stream_size.unwrap_or(100_000)
+    }
+
+    pub fn get_max_retries(&self) -> u8 {
+        self.max_retries.unwrap_or(5)
+    }
+
+    /// On by default, a deployment without it only knows the uncompressed NFTs of collections.
+    pub fn get_snapshot_token_metadata(&self) -> bool {
+        self.snapshot_token_metadata.unwrap_or(true)
+    }
+}
+
+/// The accounts a snapshot fetches, each once, in the order they were added.
+#[derive(Debug, Default)]
+pub struct SnapshotAccounts {
+    seen: HashSet<Pubkey>,
+    accounts: Vec<Pubkey>,
+}
+
+impl SnapshotAccounts {
+    pub fn add(&mut self, account: Pubkey) {
+        if self.seen.insert(account) {
+            self.accounts.push(account);
+        }
+    }
+
+    /// The mint, metadata and master edition of a collection.
+    pub fn add_collection(&mut self, mint: Pubkey) {
+        let token_metadata = TokenMetadataParser {}.key();
+        let seeds: &[&[u8]] = &[b"metadata", token_metadata.as_ref(), mint.as_ref()];
+        self.add(mint);
+        self.add(Pubkey::find_program_address(seeds, &token_metadata).0);
+        self.add(
+            Pubkey::find_program_address(
+                &[seeds[0], seeds[1], seeds[2], b"edition"],
+                &token_metadata,
+            )
+            .0,
+        );
+    }
+
+    /// A metadata account after its mint, read from the slice of its data at
+    /// `METADATA_MINT_OFFSET`.
+    pub fn add_metadata(&mut self, metadata: Pubkey, mint_slice: &[u8]) {
+        if let Ok(mint) = <[u8; 32]>::try_from(mint_slice) {
+            self.add(Pubkey::new_from_array(mint));
+        }
+        self.add(metadata);
+    }
+
+    pub fn accounts(&self) -> &[Pubkey] {
+        &self.accounts
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootstrapPhase {
+    DiscoverTrees,
+    ReplayTrees,
+    SnapshotAccounts,
+    DownloadMetadata,
+}
+
+impl BootstrapPhase {
+    /// The phases in the order they run.
+    pub const ALL: [BootstrapPhase; 4] = [
+        BootstrapPhase::DiscoverTrees,
+        BootstrapPhase::ReplayTrees,
+        BootstrapPhase::SnapshotAccounts,
+        BootstrapPhase::DownloadMetadata,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BootstrapPhase::DiscoverTrees => "discover_trees",
+            BootstrapPhase::ReplayTrees => "replay_trees",
+            BootstrapPhase::SnapshotAccounts => "snapshot_accounts",
+            BootstrapPhase::DownloadMetadata => "download_metadata",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, FromQueryResult)]
+pub struct PhaseStatus {
+    pub phase: String,
+    /// `pending`, `running`, `done` or `failed`.
+    pub status: String,
+    pub total: i64,
+    pub done: i64,
+    pub percent: f64,
+    pub error: Option<String>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromQueryResult)]
+pub struct TreeTotals {
+    pub trees: i64,
+    pub trees_done: i64,
+    pub transactions_sent: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BootstrapStatus {
+    pub phases: Vec<PhaseStatus>,
+    pub trees: TreeTotals,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct BootstrapTree {
+    tree: Vec<u8>,
+    last_signature: Option<String>,
+    transactions_sent: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CollectionMint {
+    group_value: String,
+}
+
+/// The progress of every phase, empty until a bootstrap started.
+pub async fn bootstrap_status(conn: &DatabaseConnection) -> Result<BootstrapStatus, IngesterError> {
+    let phases = PhaseStatus::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "
+SELECT phase, status, total, done,
+    CASE
+        WHEN status = 'done' THEN 100.0
+        WHEN total > 0 THEN round(100.0 * done / total, 1)::float8
+        ELSE 0.0
+    END AS percent,
+    error, started_at::text AS started_at, finished_at::text AS finished_at
+FROM bootstrap_phases
+ORDER BY position
+"
+        .to_string(),
+    ))
+    .all(conn)
+    .await?;
+    let trees = TreeTotals::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "
+SELECT count(*) AS trees,
+    count(*) FILTER (WHERE done) AS trees_done,
+    coalesce(sum(transactions_sent), 0)::bigint AS transactions_sent
+FROM bootstrap_trees
+"
+        .to_string(),
+    ))
+    .one(conn)
+    .await?
+    .unwrap_or(TreeTotals {
+        trees: 0,
+        trees_done: 0,
+        transactions_sent: 0,
+    });
+    Ok(BootstrapStatus { phases, trees })
+}
+
+struct Bootstrap {
+    conn: DatabaseConnection,
+    config: BootstrapConfig,
+    rpc_url: String,
+    rpc_client: RpcClient,
+    throttle: RpcThrottle,
+    messenger: StreamProducer<RedisMessenger>,
+}
+
+impl Bootstrap {
+    async fn execute(&self, sql: &str, values: Vec<Value>) -> Result<(), IngesterError> {
+        self.conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                sql,
+                values,
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn set_progress(
+        &self,
+        phase: BootstrapPhase,
+        total: i64,
+        done: i64,
+    ) -> Result<(), IngesterError> {
+        metric! {
+            let percent = if total > 0 { 100 * done / total } else { 0 };
+            statsd_gauge!("ingester.bootstrap.percent", percent as u64, "phase" => phase.name());
+        }
+        self.execute(
+            "UPDATE bootstrap_phases SET total = $2, done = $3, updated_at = now() WHERE phase = $1",
+            vec![phase.name().into(), total.into(), done.into()],
+        )
+        .await
+    }
+
+    /// Adds the phases a first run starts with, and returns whether `phase` still has to run.
+    async fn start_phase(&self, phase: BootstrapPhase) -> Result<bool, IngesterError> {
+        #[derive(FromQueryResult)]
+        struct Status {
+            status: String,
+        }
+        let status = Status::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT status FROM bootstrap_phases WHERE phase = $1",
+            vec![phase.name().into()],
+        ))
+        .one(&self.conn)
+        .await?;
+        if status.map_or(false, |s| s.status == "done") {
+            return Ok(false);
+        }
+        self.execute(
+            "
+UPDATE bootstrap_phases
+SET status = 'running', error = NULL, started_at = coalesce(started_at, now()), updated_at = now()
+WHERE phase = $1
+",
+            vec![phase.name().into()],
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn finish_phase(
+        &self,
+        phase: BootstrapPhase,
+        result: &Result<(), IngesterError>,
+    ) -> Result<(), IngesterError> {
+        let (status, error) = match result {
+            Ok(()) => ("done", None),
+            Err(e) => ("failed", Some(e.to_string())),
+        };
+        self.execute(
+            "
+UPDATE bootstrap_phases
+SET status = $2, error = $3, finished_at = CASE WHEN $2 = 'done' THEN now() END, updated_at = now()
+WHERE phase = $1
+",
+            vec![phase.name().into(), status.into(), error.into()],
+        )
+        .await
+    }
+
+    async fn run(&mut self) -> Result<(), IngesterError> {
+        for (position, phase) in BootstrapPhase::ALL.iter().enumerate() {
+            self.execute(
+                "
+INSERT INTO bootstrap_phases (phase, position, status) VALUES ($1, $2, 'pending')
+ON CONFLICT (phase) DO NOTHING
+",
+                vec![phase.name().into(), (position as i16).into()],
+            )
+            .await?;
+        }
+        for phase in BootstrapPhase::ALL {
+            if !self.start_phase(phase).await? {
+                continue;
+            }
+            info!("bootstrap phase {} started", phase.name());
+            let result = match phase {
+                BootstrapPhase::DiscoverTrees => self.discover_trees().await,
+                BootstrapPhase::ReplayTrees => self.replay_trees().await,
+                BootstrapPhase::SnapshotAccounts => self.snapshot_accounts().await,
+                BootstrapPhase::DownloadMetadata => self.download_metadata().await,
+            };
+            self.finish_phase(phase, &result).await?;
+            result?;
+            info!("bootstrap phase {} done", phase.name());
+        }
+        Ok(())
+    }
+
+    async fn discover_trees(&self) -> Result<(), IngesterError> {
+        let trees: Vec<(Pubkey, u64, u64)> = fetch_bubblegum_trees(&self.rpc_client, &self.conn)
+            .await?
+            .into_iter()
+            .map(|(tree, slot_seq)| (tree, slot_seq.0, slot_seq.1))
+            .collect();
+        for batch in trees.chunks(TREE_BATCH_SIZE) {
+            let mut sql =
+                "INSERT INTO bootstrap_trees (tree, creation_slot, seq) VALUES ".to_string();
+            let mut values: Vec<Value> = Vec::with_capacity(batch.len() * 3);
+            for (i, (tree, slot, seq)) in batch.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                sql.push_str(&format!("(${}, ${}, ${})", 3 * i + 1, 3 * i + 2, 3 * i + 3));
+                values.push(tree.to_bytes().to_vec().into());
+                values.push(to_i64(*slot, "creation_slot")?.into());
+                values.push(to_i64(*seq, "seq")?.into());
+            }
+            // A tree discovered again keeps its progress, it is replayed up to its new seq.
+            sql.push_str(" ON CONFLICT (tree) DO UPDATE SET seq = EXCLUDED.seq");
+            self.execute(&sql, values).await?;
+        }
+        let total = trees.len() as i64;
+        self.set_progress(BootstrapPhase::DiscoverTrees, total, total)
+            .await
+    }
+
+    async fn replay_trees(&mut self) -> Result<(), IngesterError> {
+        let trees = BootstrapTree::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            "
+SELECT tree, last_signature, transactions_sent
+FROM bootstrap_trees
+WHERE NOT done
+ORDER BY creation_slot, tree
+"
+            .to_string(),
+        ))
+        .all(&self.conn)
+        .await?;
+        let status = bootstrap_status(&self.conn).await?.trees;
+        let mut done = status.trees_done;
+        self.set_progress(BootstrapPhase::ReplayTrees, status.trees, done)
+            .await?;
+        for tree in trees {
+            self.replay_tree(&tree).await?;
+            done += 1;
+            self.set_progress(BootstrapPhase::ReplayTrees, status.trees, done)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the transactions of `tree` oldest first, from the one after its last replayed
+    /// signature. Each tree's transactions are fetched concurrently but sent in order.
+    async fn replay_tree(&mut self, tree: &BootstrapTree) -> Result<(), IngesterError> {
+        let address = Pubkey::new(&tree.tree);
+        let after = tree
+            .last_signature
+            .as_deref()
+            .map(Signature::from_str)
+            .transpose()
+            .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+        info!(
+            "bootstrap replaying tree {}, {} transactions sent so far",
+            address, tree.transactions_sent
+        );
+        let signatures = find_signatures(
+            address,
+            RpcClient::new(self.rpc_url.clone()),
+            self.throttle.clone(),
+            None,
+            after,
+            SIGNATURE_BUFFER,
+            true,
+        );
+        let client = Arc::new(RpcClient::new(self.rpc_url.clone()));
+        let (throttle, max_retries) = (self.throttle.clone(), self.config.get_max_retries());
+        let mut transactions = ReceiverStream::new(signatures)
+            .map(|signature| {
+                let (client, throttle) = (Arc::clone(&client), throttle.clone());
+                async move {
+                    let signature =
+                        signature.map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+                    let transaction = fetch_transaction(&client, &throttle, signature, max_retries)
+                        .await
+                        .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+                    Ok::<_, IngesterError>((signature, transaction))
+                }
+            })
+            .buffered(self.config.get_concurrency());
+
+        let mut rate = self.config.get_max_transactions_per_second().map(|tps| {
+            let mut rate = time::interval(Duration::from_secs_f64(1.0 / tps as f64));
+            rate.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            rate
+        });
+        let mut sent = tree.transactions_sent;
+        let mut last_signature = None;
+        while let Some(result) = transactions.next().await {
+            let (signature, transaction) = result?;
+            last_signature = Some(signature);
+            // Failed transactions changed nothing, the ingesters would drop them anyway.
+            if transaction
+                .transaction
+                .meta
+                .as_ref()
+                .map_or(true, |meta| meta.err.is_some())
+            {
+                continue;
+            }
+            if let Some(rate) = rate.as_mut() {
+                rate.tick().await;
+            }
+            if sent % CHECKPOINT_EVERY == 0 {
+                self.wait_for_stream(TRANSACTION_STREAM, self.config.get_max_stream_size())
+                    .await?;
+            }
+            let builder =
+                seralize_encoded_transaction_with_status(FlatBufferBuilder::new(), transaction)?;
+            self.messenger
+                .send(TRANSACTION_STREAM, builder.finished_data())
+                .await?;
+            metric! {
+                statsd_count!("ingester.bootstrap.transaction_sent", 1);
+            }
+            sent += 1;
+            if sent % CHECKPOINT_EVERY == 0 {
+                self.save_tree(&tree.tree, last_signature, sent, false)
+                    .await?;
+            }
+        }
+        self.save_tree(&tree.tree, last_signature, sent, true).await
+    }
+
+    async fn save_tree(
+        &self,
+        tree: &[u8],
+        last_signature: Option<Signature>,
+        transactions_sent: i64,
+        done: bool,
+    ) -> Result<(), IngesterError> {
+        self.execute(
+            "
+UPDATE bootstrap_trees
+SET last_signature = coalesce($2, last_signature), transactions_sent = $3, done = $4,
+    updated_at = now()
+WHERE tree = $1
+",
+            vec![
+                tree.to_vec().into(),
+                last_signature.map(|s| s.to_string()).into(),
+                transactions_sent.into(),
+                done.into(),
+            ],
+        )
+        .await
+    }
+
+    /// Waits until `stream` holds at most `max_size` entries.
+    async fn wait_for_stream(
+        &mut self,
+        stream: &'static str,
+        max_size: u64,
+    ) -> Result<(), IngesterError> {
+        loop {
+            let size = self.messenger.stream_size(stream).await?;
+            if size <= max_size {
+                return Ok(());
+            }
+            info!(
+                "bootstrap waiting for {} to drain, {} entries left",
+                stream, size
+            );
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Sends the mint, metadata and master edition accounts of the collections the replayed
+    /// assets belong to, every token-metadata account with the mints of the metadata, and every
+    /// account of the configured `snapshot_programs`. The phase starts over when interrupted,
+    /// accounts sent twice change nothing.
+    async fn snapshot_accounts(&mut self) -> Result<(), IngesterError> {
+        // The assets of the replayed trees are only known once the ingesters processed them.
+        self.wait_for_stream(TRANSACTION_STREAM, 0).await?;
+        let collections = CollectionMint::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+SELECT DISTINCT group_value FROM asset_grouping
+WHERE group_key = $1 AND group_value IS NOT NULL
+",
+            vec![COLLECTION_GROUP_KEY.into()],
+        ))
+        .all(&self.conn)
+        .await?;
+        let mut snapshot = SnapshotAccounts::default();
+        for collection in collections {
+            if let Ok(mint) = Pubkey::from_str(&collection.group_value) {
+                snapshot.add_collection(mint);
+            }
+        }
+        if self.config.get_snapshot_token_metadata() {
+            self.list_token_metadata(&mut snapshot).await?;
+        }
+        let accounts = snapshot.accounts();
+
+        let mut program_accounts = Vec::new();
+        for program in self.config.snapshot_programs.clone().unwrap_or_default() {
+            let program =
+                Pubkey::from_str(&program).map_err(|e| IngesterError::ConfigurationError {
+                    msg: format!("invalid snapshot program {}: {}", program, e),
+                })?;
+            let found = self
+                .rpc_client
+                .get_program_accounts_with_config(
+                    &program,
+                    RpcProgramAccountsConfig {
+                        account_config: RpcAccountInfoConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            ..RpcAccountInfoConfig::default()
+                        },
+                        ..RpcProgramAccountsConfig::default()
+                    },
+                )
+                .await
+                .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+            program_accounts.extend(found);
+        }
+
+        let total = (accounts.len() + program_accounts.len()) as i64;
+        let mut done = 0;
+        self.set_progress(BootstrapPhase::SnapshotAccounts, total, done)
+            .await?;
+        let programs: HashSet<Pubkey> = indexed_programs().into_iter().collect();
+        for batch in accounts.chunks(ACCOUNT_BATCH_SIZE) {
+            let response = self
+                .throttle
+                .run(|| {
+                    self.rpc_client
+                        .get_multiple_accounts_with_commitment(batch, self.rpc_client.commitment())
+                })
+                .await
+                .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+            let found: Vec<(Pubkey, Account)> = batch
+                .iter()
+                .zip(response.value)
+                .filter_map(|(pubkey, account)| account.map(|account| (*pubkey, account)))
+                .filter(|(_, account)| programs.contains(&account.owner))
+                .collect();
+            self.send_accounts(&found, response.context.slot).await?;
+            done += batch.len() as i64;
+            self.set_progress(BootstrapPhase::SnapshotAccounts, total, done)
+                .await?;
+        }
+        // Program accounts come without the slot they were read at.
+        let slot = self
+            .rpc_client
+            .get_slot()
+            .await
+            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+        for batch in program_accounts.chunks(ACCOUNT_BATCH_SIZE) {
+            self.send_accounts(batch, slot).await?;
+            done += batch.len() as i64;
+            self.set_progress(BootstrapPhase::SnapshotAccounts, total, done)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the token-metadata accounts of every kind the ingesters index. Only their addresses,
+    /// and the mint of the metadata, are listed, so the accounts are fetched in batches after.
+    async fn list_token_metadata(
+        &self,
+        snapshot: &mut SnapshotAccounts,
+    ) -> Result<(), IngesterError> {
+        let token_metadata = TokenMetadataParser {}.key();
+        for key in SNAPSHOT_TOKEN_METADATA_KEYS {
+            let data_slice = match key {
+                Key::MetadataV1 => UiDataSliceConfig {
+                    offset: METADATA_MINT_OFFSET,
+                    length: 32,
+                },
+                _ => UiDataSliceConfig {
+                    offset: 0,
+                    length: 0,
+                },
+            };
+            let found = self
+                .throttle
+                .run(|| {
+                    self.rpc_client.get_program_accounts_with_config(
+                        &token_metadata,
+                        RpcProgramAccountsConfig {
+                            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                                0,
+                                vec![key as u8],
+                            ))]),
+                            account_config: RpcAccountInfoConfig {
+                                encoding: Some(UiAccountEncoding::Base64),
+                                data_slice: Some(data_slice),
+                                ..RpcAccountInfoConfig::default()
+                            },
+                            ..RpcProgramAccountsConfig::default()
+                        },
+                    )
+                })
+                .await
+                .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+            info!(
+                "bootstrap found {} token-metadata accounts of kind {:?}",
+                found.len(),
+                key
+            );
+            for (pubkey, account) in found {
+                match key {
+                    Key::MetadataV1 => snapshot.add_metadata(pubkey, &account.data),
+                    _ => snapshot.add(pubkey),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_accounts(
+        &mut self,
+        accounts: &[(Pubkey, Account)],
+        slot: u64,
+    ) -> Result<(), IngesterError> {
+        self.wait_for_stream(ACCOUNT_STREAM, self.config.get_max_stream_size())
+            .await?;
+        for (pubkey, account) in accounts {
+            send_account(&mut self.messenger, pubkey, account, slot).await?;
+        }
+        metric! {
+            statsd_count!("ingester.bootstrap.account_sent", accounts.len() as i64);
+        }
+        Ok(())
+    }
+
+    /// The ingesters queue a metadata download for every asset they index, this waits for the
+    /// streams to drain and for the downloads to finish.
+    async fn download_metadata(&mut self) -> Result<(), IngesterError> {
+        self.wait_for_stream(TRANSACTION_STREAM, 0).await?;
+        self.wait_for_stream(ACCOUNT_STREAM, 0).await?;
+        loop {
+            let stats = TaskManager::get_task_stats(&self.conn).await?;
+            let (total, done) = stats
+                .iter()
+                .filter(|s| s.task_type == "DownloadMetadata")
+                .fold((0, 0), |(total, done), s| {
+                    (
+                        total + s.pending + s.running + s.succeeded + s.failed,
+                        done + s.succeeded + s.failed,
+                    )
+                });
+            self.set_progress(BootstrapPhase::DownloadMetadata, total, done)
+                .await?;
+            if done == total {
+                return Ok(());
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Runs the bootstrap phases that aren't done yet, then idles. A failed phase is recorded and
+/// ends the task, so the supervisor restarts it and the phase resumes. The streams are written on
+/// the Redis instance of `endpoints`.
+pub fn bootstrap(
+    pool: Pool<Postgres>,
+    config: IngesterConfig,
+    endpoints: RedisEndpoints,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let bootstrap_config = config.bootstrap_config.clone().unwrap_or_default();
+        let messenger = match StreamProducer::new(
+            config.get_messneger_client_config(),
+            endpoints,
+            &[TRANSACTION_STREAM, ACCOUNT_STREAM],
+        )
+        .await
+        {
+            Ok(messenger) => messenger,
+            Err(e) => {
+                error!("bootstrap failed to connect to the streams: {}", e);
+                return;
+            }
+        };
+        let mut bootstrap = Bootstrap {
+            conn: SqlxPostgresConnector::from_sqlx_postgres_pool(pool),
+            throttle: RpcThrottle::new(bootstrap_config.get_concurrency()),
+            config: bootstrap_config,
+            rpc_url: config.get_rpc_url(),
+            rpc_client: RpcClient::new_with_commitment(
+                config.get_rpc_url(),
+                CommitmentConfig {
+                    commitment: CommitmentLevel::Confirmed,
+                },
+            ),
+            messenger,
+        };
+        match bootstrap.run().await {
+            Ok(()) => {
+                info!("bootstrap complete");
+                futures::future::pending::<()>().await;
+            }
+            Err(e) => {
+                error!("bootstrap failed: {}", e);
+                metric! {
+                    statsd_count!("ingester.bootstrap.failed", 1);
+                }
+            }
+        }
+    })
+}
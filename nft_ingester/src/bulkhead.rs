@@ -0,0 +1,334 @@
+use crate::{
+    admin::PayloadDumper,
+    memory_budget::{BudgetPermit, MemoryBudget},
+    metric,
+    program_transformers::ProgramTransformer,
+    seq_buffer::SeqBuffer,
+    transaction_notifications::handle_transaction,
+};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use log::{error, warn};
+use messenger_payload::decode_payload;
+use plerkle_messenger::{RecvData, TRANSACTION_STREAM};
+use plerkle_serialization::root_as_transaction_info;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::VecDeque, str::FromStr, sync::Arc};
+use tokio::{
+    sync::{
+        mpsc::{self, UnboundedSender},
+        Mutex,
+    },
+    task::JoinSet,
+    time,
+};
+
+const DEFAULT_POOL: &str = "default";
+
+/// A pool of transaction workers with its own queue, for the transactions of `programs`, e.g.
+/// `'[{name="bubblegum", programs=["BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY"], workers=20}]'`.
+/// A transaction goes to the first pool with one of its programs among its account keys. A pool
+/// without programs takes the transactions no other pool does.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct BulkheadConfig {
+    pub name: String,
+    #[serde(default)]
+    pub programs: Vec<String>,
+    pub workers: Option<usize>,
+    pub queue_size: Option<usize>,
+}
+
+impl BulkheadConfig {
+    pub fn get_workers(&self) -> usize {
+        self.workers.unwrap_or(10).max(1)
+    }
+
+    pub fn get_queue_size(&self) -> usize {
+        self.queue_size.unwrap_or(1000).max(1)
+    }
+}
+
+/// Bubblegum and Token Metadata each get a pool, everything else shares the default one.
+pub fn default_bulkheads() -> Vec<BulkheadConfig> {
+    vec![
+        BulkheadConfig {
+            name: "bubblegum".to_string(),
+            programs: vec![mpl_bubblegum::id().to_string()],
+            workers: None,
+            queue_size: None,
+        },
+        BulkheadConfig {
+            name: "token_metadata".to_string(),
+            programs: vec![blockbuster::token_metadata::id().to_string()],
+            workers: None,
+            queue_size: None,
+        },
+    ]
+}
+
+pub struct Job {
+    id: String,
+    tries: usize,
+    tx_data: Vec<u8>,
+    // The trees the transaction changes and their seqs, only read with a seq buffer.
+    updates: Vec<(Pubkey, u64)>,
+    // The transaction's share of the memory budget, from when it's queued until it's applied.
+    _permit: Option<BudgetPermit>,
+}
+
+/// Reorders the transactions of each tree, shared by the transaction workers.
+pub type TransactionSeqBuffer = SeqBuffer<Job>;
+
+struct Pool {
+    name: String,
+    programs: Vec<Pubkey>,
+    queue_size: usize,
+    sender: mpsc::Sender<Job>,
+}
+
+/// Per program worker pools, so a program whose transactions are slow to transform has its own
+/// workers. A transaction whose pool's queue is full waits for room, which stops reading the
+/// stream until the pool catches up. Queued and held transactions count against the memory
+/// budget. With a seq buffer, a worker holds a transaction that's ahead of its tree instead of
+/// applying it, and applies the ones it releases after each transaction. Dropping the bulkheads
+/// stops their workers.
+pub struct Bulkheads {
+    pools: Vec<Pool>,
+    default: usize,
+    budget: Option<MemoryBudget>,
+    ack_channel: UnboundedSender<(&'static str, String)>,
+    _workers: JoinSet<()>,
+}
+
+impl Bulkheads {
+    pub fn start(
+        configs: Vec<BulkheadConfig>,
+        manager: Arc<ProgramTransformer>,
+        dumper: PayloadDumper,
+        budget: Option<MemoryBudget>,
+        ack_channel: UnboundedSender<(&'static str, String)>,
+        seq_buffer: Option<Arc<TransactionSeqBuffer>>,
+    ) -> Self {
+        let mut configs = configs;
+        if !configs.iter().any(|c| c.programs.is_empty()) {
+            configs.push(BulkheadConfig {
+                name: DEFAULT_POOL.to_string(),
+                programs: vec![],
+                workers: None,
+                queue_size: None,
+            });
+        }
+        let mut workers = JoinSet::new();
+        let mut pools = Vec::with_capacity(configs.len());
+        for config in configs.iter() {
+            let programs = config
+                .programs
+                .iter()
+                .filter_map(|program| match Pubkey::from_str(program) {
+                    Ok(program) => Some(program),
+                    Err(_) => {
+                        warn!(
+                            "Ignoring invalid program {} of pool {}",
+                            program, config.name
+                        );
+                        None
+                    }
+                })
+                .collect();
+            let (sender, receiver) = mpsc::channel(config.get_queue_size());
+            let receiver = Arc::new(Mutex::new(receiver));
+            let worker = Worker {
+                name: config.name.clone(),
+                manager: Arc::clone(&manager),
+                dumper: dumper.clone(),
+                ack_channel: ack_channel.clone(),
+                seq_buffer: seq_buffer.clone(),
+            };
+            for _ in 0..config.get_workers() {
+                workers.spawn(pool_worker(worker.clone(), Arc::clone(&receiver)));
+            }
+            pools.push(Pool {
+                name: config.name.clone(),
+                programs,
+                queue_size: config.get_queue_size(),
+                sender,
+            });
+        }
+        if let Some(seq_buffer) = seq_buffer {
+            workers.spawn(expiry_worker(Worker {
+                name: "seq_buffer".to_string(),
+                manager,
+                dumper,
+                ack_channel: ack_channel.clone(),
+                seq_buffer: Some(seq_buffer),
+            }));
+        }
+        let default = pools
+            .iter()
+            .position(|p| p.programs.is_empty())
+            .unwrap_or(pools.len() - 1);
+        Bulkheads {
+            pools,
+            default,
+            budget,
+            ack_channel,
+            _workers: workers,
+        }
+    }
+
+    /// Queues a transaction on its program's pool, waiting for its decoded size to fit the
+    /// memory budget and for room in the queue. A payload that can't be decoded never will be,
+    /// so it's acknowledged rather than redelivered.
+    pub async fn dispatch(&self, item: RecvData) {
+        let id = item.id.to_string();
+        let tx_data = match decode_payload(&item.data) {
+            Ok(data) => data.into_owned(),
+            Err(e) => {
+                error!(
+                    "Failed to decode txn payload {}, acknowledging it: {}",
+                    id, e
+                );
+                metric! {
+                    statsd_count!("ingester.payload.decode_error", 1, "stream" => TRANSACTION_STREAM);
+                }
+                if let Err(err) = self.ack_channel.send((TRANSACTION_STREAM, id)) {
+                    error!("Txn stream ack error: {}", err);
+                }
+                return;
+            }
+        };
+        let permit = match &self.budget {
+            Some(budget) => Some(budget.reserve(&item.data).await),
+            None => None,
+        };
+        let pool = &self.pools[self.route(&tx_data)];
+        let job = Job {
+            id,
+            tries: item.tries,
+            tx_data,
+            updates: vec![],
+            _permit: permit,
+        };
+        if pool.sender.capacity() == 0 {
+            metric! {
+                statsd_count!("ingester.bulkhead.queue_full", 1, "pool" => &pool.name);
+            }
+        }
+        match pool.sender.send(job).await {
+            Ok(()) => {
+                metric! {
+                    statsd_gauge!("ingester.bulkhead.queue_depth", (pool.queue_size - pool.sender.capacity()) as u64, "pool" => &pool.name);
+                }
+            }
+            Err(err) => {
+                error!("Pool {} is stopped, dropping {}", pool.name, err.0.id);
+            }
+        }
+    }
+
+    // The first pool with one of the transaction's programs, otherwise the default one.
+    fn route(&self, tx_data: &[u8]) -> usize {
+        let keys: Vec<Pubkey> = match root_as_transaction_info(tx_data) {
+            Ok(tx) => tx
+                .account_keys()
+                .map(|keys| keys.iter().map(|k| Pubkey::new_from_array(k.0)).collect())
+                .unwrap_or_default(),
+            Err(_) => return self.default,
+        };
+        self.pools
+            .iter()
+            .position(|pool| pool.programs.iter().any(|p| keys.contains(p)))
+            .unwrap_or(self.default)
+    }
+}
+
+#[derive(Clone)]
+struct Worker {
+    name: String,
+    manager: Arc<ProgramTransformer>,
+    dumper: PayloadDumper,
+    ack_channel: UnboundedSender<(&'static str, String)>,
+    seq_buffer: Option<Arc<TransactionSeqBuffer>>,
+}
+
+impl Worker {
+    // Holds the job in the seq buffer when it's ahead of its tree. Only transactions changing a
+    // single tree are held, the others are applied as they come.
+    async fn admit(&self, mut job: Job) -> Option<Job> {
+        let seq_buffer = match &self.seq_buffer {
+            Some(seq_buffer) => seq_buffer,
+            None => return Some(job),
+        };
+        job.updates = match root_as_transaction_info(&job.tx_data) {
+            Ok(tx) => self.manager.tree_updates(&tx),
+            Err(_) => return Some(job),
+        };
+        let (tree, seq) = match job.updates.first() {
+            Some(&(tree, _)) if job.updates.iter().all(|(t, _)| *t == tree) => {
+                (tree, job.updates.iter().map(|(_, seq)| *seq).min()?)
+            }
+            _ => return Some(job),
+        };
+        seq_buffer.admit(tree, seq, job.tx_data.len(), job).await
+    }
+
+    // Applies the job, then the held jobs it releases.
+    async fn run(&self, job: Job) {
+        let mut jobs = VecDeque::from([job]);
+        while let Some(job) = jobs.pop_front() {
+            let acked = handle_transaction(
+                &self.manager,
+                job.id.clone(),
+                job.tries,
+                &job.tx_data,
+                &self.dumper,
+            )
+            .await;
+            if let Some(id) = acked {
+                if let Err(err) = self.ack_channel.send((TRANSACTION_STREAM, id)) {
+                    error!("Txn stream ack error: {}", err);
+                    metric! {
+                        statsd_count!("ingester.stream.ack_error", 1, "stream" => TRANSACTION_STREAM);
+                    }
+                }
+                // A failed transaction leaves the ones behind it held until they expire.
+                if let Some(seq_buffer) = &self.seq_buffer {
+                    for (tree, seq) in job.updates.iter() {
+                        jobs.extend(seq_buffer.applied(*tree, *seq));
+                    }
+                }
+            }
+            metric! {
+                statsd_count!("ingester.bulkhead.processed", 1, "pool" => &self.name);
+            }
+        }
+    }
+}
+
+async fn pool_worker(worker: Worker, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) {
+    loop {
+        let job = receiver.lock().await.recv().await;
+        let job = match job {
+            Some(job) => job,
+            None => return,
+        };
+        if let Some(job) = worker.admit(job).await {
+            worker.run(job).await;
+        }
+    }
+}
+
+// Applies the jobs held past their wait.
+async fn expiry_worker(worker: Worker) {
+    let seq_buffer = match &worker.seq_buffer {
+        Some(seq_buffer) => Arc::clone(seq_buffer),
+        None => return,
+    };
+    let mut interval = time::interval(seq_buffer.poll_interval());
+    loop {
+        interval.tick().await;
+        for job in seq_buffer.expired() {
+            worker.run(job).await;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::{asset_grouping, COLLECTION_GROUP_KEY};
+use sea_orm::{query::*, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait};
+
+/// The activity counted in `collection_stats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionActivity {
+    Mint,
+    Transfer,
+    Burn,
+}
+
+impl CollectionActivity {
+    fn name(&self) -> &'static str {
+        match self {
+            CollectionActivity::Mint => "mint",
+            CollectionActivity::Transfer => "transfer",
+            CollectionActivity::Burn => "burn",
+        }
+    }
+}
+
+/// The verified collection of `asset_id`, if it has one. Read before and after an asset's
+/// collection grouping is written, see [record_collection_join].
+pub async fn verified_collection<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+) -> Result<Option<String>, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let grouping = asset_grouping::Entity::find()
+        .filter(asset_grouping::Column::AssetId.eq(asset_id))
+        .filter(asset_grouping::Column::GroupKey.eq(COLLECTION_GROUP_KEY))
+        .filter(asset_grouping::Column::Verified.eq(true))
+        .one(txn)
+        .await?;
+    Ok(grouping.and_then(|g| g.group_value))
+}
+
+/// Counts the mint of `asset_id` in its verified collection when the asset joined it at `slot`,
+/// i.e. its verified collection is no longer `before`, the one it had before its grouping was
+/// written. Assets minted unverified are counted when their collection is verified, and a
+/// replayed mint or verification, which leaves the collection as it was, isn't counted again.
+pub async fn record_collection_join<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    before: Option<String>,
+    slot: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let after = verified_collection(txn, asset_id.clone()).await?;
+    if after.is_some() && after != before {
+        record_collection_activity(txn, asset_id, CollectionActivity::Mint, slot).await?;
+    }
+    Ok(())
+}
+
+/// Counts `activity` of `asset_id` at `slot` in the stats of its verified collection, if it has
+/// one. Callers only report activity the first time they apply it, replays aren't counted again.
+pub async fn record_collection_activity<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    activity: CollectionActivity,
+    slot: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let count = |counted: CollectionActivity| i64::from(activity == counted);
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO collection_stats (collection, mints, transfers, burns, last_activity_slot, updated_at)
+SELECT group_value, $3, $4, $5, $6, now()
+FROM asset_grouping
+WHERE asset_id = $1 AND group_key = $2 AND group_value IS NOT NULL AND verified
+ON CONFLICT (collection) DO UPDATE SET
+    mints = collection_stats.mints + excluded.mints,
+    transfers = collection_stats.transfers + excluded.transfers,
+    burns = collection_stats.burns + excluded.burns,
+    last_activity_slot = GREATEST(collection_stats.last_activity_slot, excluded.last_activity_slot),
+    updated_at = excluded.updated_at
+",
+            vec![
+                asset_id.into(),
+                COLLECTION_GROUP_KEY.into(),
+                count(CollectionActivity::Mint).into(),
+                count(CollectionActivity::Transfer).into(),
+                count(CollectionActivity::Burn).into(),
+                slot.into(),
+            ],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() > 0 {
+        metric! {
+            statsd_count!("ingester.collection_stats.recorded", 1, "activity" => activity.name());
+        }
+    }
+    Ok(())
+}
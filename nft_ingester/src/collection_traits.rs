@@ -0,0 +1,254 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::{asset_data, asset_grouping, COLLECTION_GROUP_KEY};
+use log::{debug, error, info};
+use sea_orm::{
+    query::*, ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, JsonValue,
+    SqlxPostgresConnector, TransactionTrait,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+/// How often the trait counts of every collection are recounted from the assets, e.g.
+/// `'{interval=86400}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct CollectionTraitsConfig {
+    pub interval: Option<u64>,
+}
+
+impl CollectionTraitsConfig {
+    /// Seconds between the starts of two recounts.
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(86400).max(1)
+    }
+}
+
+// Counts the traits the way `metadata_traits` reads them, over the unburnt assets of the
+// collection, through the shared metadata blob when they have one.
+const RECOUNT_TRAITS: &str = "
+INSERT INTO collection_traits (collection, trait_type, value, count)
+SELECT $1, btrim(attribute->>'trait_type'),
+    CASE WHEN jsonb_typeof(attribute->'value') = 'string' THEN btrim(attribute->>'value')
+        ELSE (attribute->'value')::text END,
+    count(DISTINCT asset.id)
+FROM asset_grouping
+JOIN asset ON asset.id = asset_grouping.asset_id AND NOT asset.burnt
+JOIN asset_data ON asset_data.id = asset.asset_data
+LEFT JOIN metadata_blob ON metadata_blob.uri_hash = asset_data.metadata_blob
+CROSS JOIN LATERAL jsonb_array_elements(
+    CASE WHEN jsonb_typeof(COALESCE(metadata_blob.metadata, asset_data.metadata)->'attributes') = 'array'
+        THEN COALESCE(metadata_blob.metadata, asset_data.metadata)->'attributes'
+        ELSE '[]'::jsonb END
+) AS attribute
+WHERE asset_grouping.group_key = $2 AND asset_grouping.group_value = $1
+    AND asset_grouping.verified
+    AND jsonb_typeof(attribute->'trait_type') = 'string'
+    AND jsonb_typeof(attribute->'value') <> 'null'
+GROUP BY 2, 3
+";
+
+/// The distinct `(trait_type, value)` pairs of a metadata JSON's `attributes`. Values that
+/// aren't strings are kept in their JSON form, e.g. `3` or `true`.
+pub fn metadata_traits(metadata: &JsonValue) -> HashSet<(String, String)> {
+    metadata
+        .get("attributes")
+        .and_then(|a| a.as_array())
+        .map(|attributes| {
+            attributes
+                .iter()
+                .filter_map(|attribute| {
+                    let trait_type = attribute.get("trait_type")?.as_str()?.trim();
+                    let value = match attribute.get("value")? {
+                        JsonValue::Null => return None,
+                        JsonValue::String(value) => value.trim().to_string(),
+                        value => value.to_string(),
+                    };
+                    Some((trait_type.to_string(), value))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Moves the trait counts of `asset_id`'s verified collection from the traits in `old` to the
+/// ones in `new`, called when its metadata JSON is (re)downloaded.
+pub async fn update_collection_traits<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    old: &JsonValue,
+    new: &JsonValue,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let (old, new) = (metadata_traits(old), metadata_traits(new));
+    if old == new {
+        return Ok(());
+    }
+    let collection = asset_grouping::Entity::find()
+        .filter(asset_grouping::Column::AssetId.eq(asset_id))
+        .filter(asset_grouping::Column::GroupKey.eq(COLLECTION_GROUP_KEY))
+        .filter(asset_grouping::Column::Verified.eq(true))
+        .one(txn)
+        .await?
+        .and_then(|g| g.group_value);
+    let collection = match collection {
+        Some(collection) => collection,
+        None => return Ok(()),
+    };
+
+    let changes = new
+        .difference(&old)
+        .map(|t| (t, 1i64))
+        .chain(old.difference(&new).map(|t| (t, -1i64)));
+    for ((trait_type, value), delta) in changes {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO collection_traits (collection, trait_type, value, count)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (collection, trait_type, value)
+DO UPDATE SET count = collection_traits.count + excluded.count
+",
+            vec![
+                collection.clone().into(),
+                trait_type.clone().into(),
+                value.clone().into(),
+                delta.into(),
+            ],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    }
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM collection_traits WHERE collection = $1 AND count <= 0",
+        vec![collection.into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    metric! {
+        statsd_count!("ingester.collection_traits.updated", 1);
+    }
+    Ok(())
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SharingAsset {
+    id: Vec<u8>,
+}
+
+/// Moves the trait counts of the assets referencing the metadata blob `uri_hash`, but
+/// `except`, from the traits in `old` to the ones in `new`, called when a download of the uri
+/// changes the blob they see their metadata through.
+pub async fn update_shared_traits<T>(
+    txn: &T,
+    uri_hash: Vec<u8>,
+    except: Vec<u8>,
+    old: &JsonValue,
+    new: &JsonValue,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    if metadata_traits(old) == metadata_traits(new) {
+        return Ok(());
+    }
+    let sharing = asset_data::Entity::find()
+        .select_only()
+        .column(asset_data::Column::Id)
+        .filter(asset_data::Column::MetadataBlob.eq(uri_hash))
+        .filter(asset_data::Column::Id.ne(except))
+        .into_model::<SharingAsset>()
+        .all(txn)
+        .await?;
+    for asset in sharing {
+        update_collection_traits(txn, asset.id, old, new).await?;
+    }
+    Ok(())
+}
+
+/// Replaces the trait counts of `collection` with a count of its assets' traits. The counts
+/// kept up by `update_collection_traits` drift when two downloads of an asset's metadata race,
+/// and they keep the traits of burnt assets. Returns the number of distinct traits.
+pub async fn recount_collection_traits<T>(txn: &T, collection: String) -> Result<u64, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM collection_traits WHERE collection = $1",
+        vec![collection.clone().into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    let counted = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            RECOUNT_TRAITS,
+            vec![collection.into(), COLLECTION_GROUP_KEY.into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(counted.rows_affected())
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CountedCollection {
+    collection: String,
+}
+
+/// Recounts the traits of every collection that has counts, one collection per transaction.
+/// A download that commits while its collection is recounted may be counted twice or not at
+/// all, which the next recount corrects.
+pub fn collection_traits_reconciler(
+    pool: PgPool,
+    config: CollectionTraitsConfig,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let period = Duration::from_secs(config.get_interval());
+        // A period after starting, so restarts don't recount everything each time.
+        let mut interval = time::interval_at(time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            debug!("recounting collection traits");
+            let collections = CountedCollection::find_by_statement(Statement::from_string(
+                DbBackend::Postgres,
+                "SELECT DISTINCT collection FROM collection_traits".to_string(),
+            ))
+            .all(&conn)
+            .await;
+            let collections = match collections {
+                Ok(collections) => collections,
+                Err(e) => {
+                    error!("error listing collections to recount: {}", e);
+                    continue;
+                }
+            };
+            let mut recounted: i64 = 0;
+            for CountedCollection { collection } in collections {
+                let counted: Result<u64, IngesterError> = match conn.begin().await {
+                    Ok(txn) => match recount_collection_traits(&txn, collection.clone()).await {
+                        Ok(counted) => txn.commit().await.map(|_| counted).map_err(Into::into),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e.into()),
+                };
+                match counted {
+                    Ok(_) => recounted += 1,
+                    Err(e) => error!("error recounting traits of {}: {}", collection, e),
+                }
+            }
+            info!("recounted the traits of {} collections", recounted);
+            metric! {
+                statsd_count!("ingester.collection_traits.recounted", recounted);
+            }
+        }
+    })
+}
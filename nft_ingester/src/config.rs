@@ -1,13 +1,36 @@
 use std::fmt::{Display, Formatter};
 
-use figment::{providers::Env, value::Value, Figment};
+use digital_asset_types::dao::DEFAULT_NETWORK;
+use figment::{
+    providers::{Env, Format, Toml},
+    value::Value,
+    Figment,
+};
 use plerkle_messenger::MessengerConfig;
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use serde::Deserialize;
-use std::env;
-use tracing_subscriber::fmt;
+use std::{env, path::PathBuf};
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter};
 
-use crate::{error::IngesterError, tasks::BgTaskConfig};
+use crate::{
+    admin::LogFilterHandle,
+    asset_changes::ChangeNotificationConfig,
+    block_source::BlockSourceConfig,
+    bootstrap::BootstrapConfig,
+    bulkhead::{default_bulkheads, BulkheadConfig},
+    collection_traits::CollectionTraitsConfig,
+    delta_export::DeltaExportConfig,
+    error::IngesterError,
+    finality::FinalityConfig,
+    hot_owners::HotOwnersConfig,
+    ownership::OwnershipVerifierConfig,
+    pricing::PricingConfig,
+    program_transformers::{ProgramGroupConfig, TransformerAssertionConfig},
+    seq_buffer::SeqBufferConfig,
+    standby::StandbyConfig,
+    stream_start::StreamStartConfig,
+    tasks::BgTaskConfig,
+};
 
 #[derive(Deserialize, PartialEq, Debug, Clone)]
 pub struct IngesterConfig {
@@ -18,6 +41,9 @@ pub struct IngesterConfig {
     pub metrics_port: Option<u16>,
     pub metrics_host: Option<String>,
     pub backfiller: Option<bool>,
+    pub backfiller_block_source: Option<BlockSourceConfig>,
+    pub backfiller_lease_duration: Option<u64>,
+    pub backfiller_slot_retry_limit: Option<u32>,
     pub role: Option<IngesterRole>,
     pub max_postgres_connections: Option<u32>,
     pub account_stream_worker_count: Option<u32>,
@@ -25,6 +51,32 @@ pub struct IngesterConfig {
     pub code_version: Option<&'static str>,
     pub ipfs_gateway: Option<String>,
     pub bg_task_config: Option<BgTaskConfig>,
+    pub tree_discovery_interval: Option<u64>,
+    pub pending_operation_ttl: Option<u64>,
+    pub authority_resolver_interval: Option<u64>,
+    pub admin_config: Option<AdminConfig>,
+    pub stream_monitor_config: Option<StreamMonitorConfig>,
+    pub pricing_config: Option<PricingConfig>,
+    pub allow_schema_mismatch: Option<bool>,
+    pub account_stream_memory_budget_mb: Option<u64>,
+    pub transaction_stream_memory_budget_mb: Option<u64>,
+    pub change_notification_config: Option<ChangeNotificationConfig>,
+    pub record_failed_transactions: Option<bool>,
+    pub transaction_bulkheads: Option<Vec<BulkheadConfig>>,
+    pub ownership_verifier_config: Option<OwnershipVerifierConfig>,
+    pub network: Option<String>,
+    pub bootstrap_config: Option<BootstrapConfig>,
+    pub transformer_assertion_config: Option<TransformerAssertionConfig>,
+    pub finality_config: Option<FinalityConfig>,
+    pub hot_owners_config: Option<HotOwnersConfig>,
+    pub collection_traits_config: Option<CollectionTraitsConfig>,
+    pub program_groups: Option<Vec<ProgramGroupConfig>>,
+    pub delta_export_config: Option<DeltaExportConfig>,
+    /// Postgres schema the index lives in, `public` when unset.
+    pub database_schema: Option<String>,
+    pub standby_config: Option<StandbyConfig>,
+    pub seq_buffer_config: Option<SeqBufferConfig>,
+    pub stream_start_config: Option<StreamStartConfig>,
 }
 
 impl IngesterConfig {
@@ -64,6 +116,112 @@ impl IngesterConfig {
     pub fn get_transaction_stream_worker_count(&self) -> u32 {
         self.transaction_stream_worker_count.unwrap_or(2)
     }
+
+    /// Seconds between scans for new trees in the backfiller, 0 disables tree discovery.
+    pub fn get_tree_discovery_interval(&self) -> u64 {
+        self.tree_discovery_interval.unwrap_or(60)
+    }
+
+    /// Seconds a backfiller holds a claimed tree for without a heartbeat before another one can
+    /// claim it.
+    pub fn get_backfiller_lease_duration(&self) -> u64 {
+        self.backfiller_lease_duration.unwrap_or(300).max(1)
+    }
+
+    /// Attempts at a slot the backfiller couldn't read or parse before it is given up on.
+    pub fn get_backfiller_slot_retry_limit(&self) -> u32 {
+        self.backfiller_slot_retry_limit.unwrap_or(5).max(1)
+    }
+
+    /// Seconds an out of order update waits for its asset to be minted before it is dropped.
+    pub fn get_pending_operation_ttl(&self) -> u64 {
+        self.pending_operation_ttl.unwrap_or(3600)
+    }
+
+    /// Seconds between runs of the update authority resolver, 0 disables it.
+    pub fn get_authority_resolver_interval(&self) -> u64 {
+        self.authority_resolver_interval.unwrap_or(60)
+    }
+
+    /// The network this ingester indexes, which the assets it creates are recorded under.
+    pub fn get_network(&self) -> String {
+        self.network
+            .clone()
+            .unwrap_or_else(|| DEFAULT_NETWORK.to_string())
+    }
+
+    /// Whether failed transactions touching known trees are kept in `failed_tx_log`.
+    pub fn get_record_failed_transactions(&self) -> bool {
+        self.record_failed_transactions.unwrap_or(false)
+    }
+
+    /// The per program pools transaction workers process transactions in, Bubblegum and Token
+    /// Metadata apart from the rest unless configured.
+    pub fn get_transaction_bulkheads(&self) -> Vec<BulkheadConfig> {
+        self.transaction_bulkheads
+            .clone()
+            .unwrap_or_else(default_bulkheads)
+    }
+
+    /// Where dump mode writes payloads, the admin config's `dump_dir` or a temp directory.
+    pub fn get_dump_dir(&self) -> PathBuf {
+        self.admin_config
+            .as_ref()
+            .and_then(|c| c.dump_dir.clone())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| env::temp_dir().join("nft_ingester_dumps"))
+    }
+}
+
+/// The admin server, only started when configured, e.g. `'{port=8081}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct AdminConfig {
+    pub port: u16,
+    pub host: Option<String>,
+    pub dump_dir: Option<String>,
+    pub auth_token: Option<String>,
+    pub reindex_max_slots: Option<u64>,
+    pub profiling: Option<bool>,
+}
+
+impl AdminConfig {
+    /// Listens on localhost unless told otherwise, the endpoints are only authenticated when an
+    /// `auth_token` is set.
+    pub fn get_host(&self) -> String {
+        self.host.clone().unwrap_or_else(|| "127.0.0.1".to_string())
+    }
+
+    /// The most slots a reindex can replay, about an hour of slots by default.
+    pub fn get_reindex_max_slots(&self) -> u64 {
+        self.reindex_max_slots.unwrap_or(9000)
+    }
+
+    /// Whether CPU profiles are served, off by default since a profile slows the ingester down.
+    pub fn get_profiling(&self) -> bool {
+        self.profiling.unwrap_or(false)
+    }
+}
+
+/// Limits past which the stream monitor warns, `max_pending_age` and `interval` are in seconds.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct StreamMonitorConfig {
+    pub interval: Option<u64>,
+    pub max_lag: Option<u64>,
+    pub max_pending_age: Option<u64>,
+}
+
+impl StreamMonitorConfig {
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(30)
+    }
+
+    pub fn get_max_lag(&self) -> u64 {
+        self.max_lag.unwrap_or(100_000)
+    }
+
+    pub fn get_max_pending_age(&self) -> u64 {
+        self.max_pending_age.unwrap_or(300)
+    }
 }
 
 // Types and constants used for Figment configuration items.
@@ -77,6 +235,7 @@ pub type RpcConfig = figment::value::Dict;
 pub const RPC_URL_KEY: &str = "url";
 pub const RPC_COMMITMENT_KEY: &str = "commitment";
 pub const CODE_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const CONFIG_FILE_ENV: &str = "INGESTER_CONFIG_FILE";
 
 #[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
 pub enum IngesterRole {
@@ -84,6 +243,8 @@ pub enum IngesterRole {
     Backfiller,
     BackgroundTaskRunner,
     Ingester,
+    Bootstrap,
+    Standby,
 }
 
 impl Display for IngesterRole {
@@ -93,6 +254,8 @@ impl Display for IngesterRole {
             IngesterRole::Backfiller => write!(f, "Backfiller"),
             IngesterRole::BackgroundTaskRunner => write!(f, "BackgroundTaskRunner"),
             IngesterRole::Ingester => write!(f, "Ingester"),
+            IngesterRole::Bootstrap => write!(f, "Bootstrap"),
+            IngesterRole::Standby => write!(f, "Standby"),
         }
     }
 }
@@ -106,21 +269,41 @@ pub fn rand_string() -> String {
 }
 
 pub fn setup_config() -> IngesterConfig {
-    let mut config: IngesterConfig = Figment::new()
-        .join(Env::prefixed("INGESTER_"))
-        .extract()
-        .map_err(|config_error| IngesterError::ConfigurationError {
-            msg: format!("{}", config_error),
-        })
-        .unwrap();
+    load_config().unwrap()
+}
+
+/// Reads the `INGESTER_` env vars, overridden by the TOML file at `INGESTER_CONFIG_FILE` when
+/// set. Unlike the environment the file can change while running, which is what a reload on
+/// SIGHUP picks up.
+pub fn load_config() -> Result<IngesterConfig, IngesterError> {
+    let mut figment = Figment::new().join(Env::prefixed("INGESTER_"));
+    if let Ok(path) = env::var(CONFIG_FILE_ENV) {
+        figment = figment.merge(Toml::file(path));
+    }
+    load_config_from(figment)
+}
+
+/// Extracts the config from `figment`, for binaries that read it from their own sources.
+pub fn load_config_from(figment: Figment) -> Result<IngesterConfig, IngesterError> {
+    let mut config: IngesterConfig =
+        figment
+            .extract()
+            .map_err(|config_error| IngesterError::ConfigurationError {
+                msg: format!("{}", config_error),
+            })?;
     config.code_version = Some(CODE_VERSION);
-    config
+    Ok(config)
 }
 
-pub fn init_logger() {
+/// Sets up JSON logging filtered by `RUST_LOG`, returning a handle to change the filter with.
+pub fn init_logger() -> LogFilterHandle {
     let env_filter = env::var("RUST_LOG")
         .or::<Result<String, ()>>(Ok("info".to_string()))
         .unwrap();
-    let t = tracing_subscriber::fmt().with_env_filter(env_filter);
-    t.event_format(fmt::format::json()).init();
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(env_filter));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().json())
+        .init();
+    handle
 }
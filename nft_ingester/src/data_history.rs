@@ -0,0 +1,57 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use sea_orm::{query::*, ConnectionTrait, DbBackend};
+
+/// An asset's on-chain name, symbol and metadata uri as of a slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataVersion {
+    pub name: String,
+    pub symbol: String,
+    pub metadata_url: String,
+    pub slot: i64,
+}
+
+/// Appends `version` to the data history of `asset_id`, unless it is the same as the asset's
+/// data as of its slot, so account updates that don't touch the name, symbol or uri are no-ops.
+pub async fn record_data_change<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    version: DataVersion,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO asset_data_history (asset_id, name, symbol, metadata_url, slot_updated)
+SELECT $1, $2, $3, $4, $5
+WHERE NOT EXISTS (
+    SELECT 1 FROM (
+        SELECT name, symbol, metadata_url FROM asset_data_history
+        WHERE asset_id = $1 AND slot_updated <= $5
+        ORDER BY slot_updated DESC, id DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.name = $2 AND latest.symbol = $3 AND latest.metadata_url = $4
+)
+ON CONFLICT (asset_id, slot_updated) DO NOTHING
+",
+            vec![
+                asset_id.into(),
+                version.name.into(),
+                version.symbol.into(),
+                version.metadata_url.into(),
+                version.slot.into(),
+            ],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() > 0 {
+        metric! {
+            statsd_count!("ingester.data_history.change_recorded", 1);
+        }
+    }
+    Ok(())
+}
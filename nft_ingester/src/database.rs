@@ -2,12 +2,22 @@ use sqlx::{postgres::{PgPoolOptions, PgConnectOptions}, PgPool, ConnectOptions};
 
 use crate::{
     config::{IngesterConfig, IngesterRole},
+    error::IngesterError,
 };
+use digital_asset_types::dao::{
+    is_valid_network,
+    schema::{check_schema_version, is_valid_schema_name, SchemaCompatibility, SCHEMA_VERSION},
+};
+use log::warn;
+use sea_orm::SqlxPostgresConnector;
 const BARE_MINIMUM_CONNECTIONS: u32 = 5;
 const DEFAULT_MAX: u32 = 125;
 pub async fn setup_database(config: IngesterConfig) -> PgPool {
     let max = config.max_postgres_connections.unwrap_or(DEFAULT_MAX);
-    if config.role == Some(IngesterRole::All) || config.role == Some(IngesterRole::Ingester) {
+    if config.role == Some(IngesterRole::All)
+        || config.role == Some(IngesterRole::Ingester)
+        || config.role == Some(IngesterRole::Standby)
+    {
         let relative_max =
             config.get_account_stream_worker_count() + config.get_transaction_stream_worker_count();
         let should_be_at_least = relative_max * 5;
@@ -15,8 +25,30 @@ pub async fn setup_database(config: IngesterConfig) -> PgPool {
             panic!("Please increase max_postgres_connections to at least {}, at least 5 connections per worker process should be given", should_be_at_least);
         }
     }
+    let network = config.get_network();
+    if !is_valid_network(&network) {
+        panic!(
+            "Invalid network {}, use lowercase letters, digits, - and _",
+            network
+        );
+    }
     let url = config.get_database_url();
-    let mut options: PgConnectOptions = url.parse().unwrap();
+    // The assets inserted over these connections default to the network, see the `asset.network`
+    // migration.
+    let mut options: PgConnectOptions = url
+        .parse::<PgConnectOptions>()
+        .unwrap()
+        .options([("das.network", network)]);
+    // Every table is addressed unqualified, so the schema picks the instance's tables.
+    if let Some(schema) = &config.database_schema {
+        if !is_valid_schema_name(schema) {
+            panic!(
+                "Invalid database schema {}, use lowercase letters, digits and _",
+                schema
+            );
+        }
+        options = options.options([("search_path", schema)]);
+    }
     options.log_statements(log::LevelFilter::Trace);
 
     options.log_slow_statements(log::LevelFilter::Debug, std::time::Duration::from_millis(500));
@@ -29,3 +61,27 @@ pub async fn setup_database(config: IngesterConfig) -> PgPool {
         .unwrap();
     pool
 }
+
+/// Refuses to run against a database whose migrations don't match the ones this build was written
+/// for, since writing with a drifted schema can silently corrupt the index. With
+/// `allow_schema_mismatch` a mismatch is only logged.
+pub async fn check_schema(config: &IngesterConfig, pool: &PgPool) -> Result<(), IngesterError> {
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
+    let msg = match check_schema_version(&conn).await? {
+        SchemaCompatibility::Current => return Ok(()),
+        SchemaCompatibility::Behind { applied } => format!(
+            "database is at migration {}, this build needs {}, run the migrations first",
+            applied.as_deref().unwrap_or("none"),
+            SCHEMA_VERSION
+        ),
+        SchemaCompatibility::Ahead { applied } => format!(
+            "database is at migration {}, newer than {} this build was written for, upgrade the ingester",
+            applied, SCHEMA_VERSION
+        ),
+    };
+    if config.allow_schema_mismatch.unwrap_or(false) {
+        warn!("Schema mismatch allowed: {}", msg);
+        return Ok(());
+    }
+    Err(IngesterError::SchemaMismatch(msg))
+}
@@ -0,0 +1,233 @@
+use crate::{asset_changes::ChangeType, error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use futures::future::join_all;
+use log::{debug, error, warn};
+use rdkafka::{
+    config::ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+    util::Timeout,
+};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector,
+    Statement, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration, Instant},
+};
+
+const DEFAULT_TOPIC: &str = "asset_changes";
+// How often the outbox's length is reported, counting it is a scan of the outbox.
+const OUTBOX_GAUGE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Publishes the asset changes written to `asset_change_outbox` to Kafka, e.g.
+/// `'{brokers="kafka:9092", topics={transfer="asset_transfers"}, default_topic="asset_changes"}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct DeltaExportConfig {
+    pub brokers: String,
+    /// Topic per change type, the others go to `default_topic`.
+    #[serde(default)]
+    pub topics: HashMap<ChangeType, String>,
+    pub default_topic: Option<String>,
+    /// Milliseconds between two polls of an empty outbox.
+    pub interval: Option<u64>,
+    pub batch_size: Option<u64>,
+    /// Extra librdkafka producer settings, e.g. `{"security.protocol"="SSL"}`.
+    #[serde(default)]
+    pub producer: HashMap<String, String>,
+}
+
+impl DeltaExportConfig {
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(500).max(1)
+    }
+
+    /// Changes published per outbox transaction.
+    pub fn get_batch_size(&self) -> u64 {
+        self.batch_size.unwrap_or(500).max(1)
+    }
+
+    pub fn topic(&self, change: ChangeType) -> &str {
+        self.topics
+            .get(&change)
+            .or(self.default_topic.as_ref())
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_TOPIC)
+    }
+
+    /// The topic of the change written to the outbox as `change`. A change type this build
+    /// doesn't know, written by a newer ingester, goes to `default_topic`.
+    pub fn topic_of(&self, change: &str) -> &str {
+        match ChangeType::from_name(change) {
+            Some(change) => self.topic(change),
+            None => self.default_topic.as_deref().unwrap_or(DEFAULT_TOPIC),
+        }
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OutboxRow {
+    id: i64,
+    asset_id: Vec<u8>,
+    change: String,
+    slot: i64,
+}
+
+/// An exported change, keyed by `id` in Kafka. `event_id` grows with every change written, a
+/// change exported twice keeps its `event_id`, a replayed one gets a new one. `change` is exported
+/// as written, so the change types of a newer ingester pass through an older exporter.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedChange {
+    pub event_id: i64,
+    pub id: String,
+    pub change: String,
+    pub slot: u64,
+}
+
+impl ExportedChange {
+    pub fn new(event_id: i64, asset_id: &[u8], change: String, slot: i64) -> Self {
+        ExportedChange {
+            event_id,
+            id: bs58::encode(asset_id).into_string(),
+            change,
+            slot: slot as u64,
+        }
+    }
+}
+
+/// Publishes a batch of the outbox and deletes it, all or nothing: a batch that fails to publish
+/// is left in the outbox and published again, whole. Returns the changes published.
+async fn export_batch(
+    conn: &DatabaseConnection,
+    producer: &FutureProducer,
+    config: &DeltaExportConfig,
+) -> Result<usize, IngesterError> {
+    let txn = conn.begin().await?;
+    // Locked so several exporters share the outbox without publishing a change twice.
+    let rows = OutboxRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+SELECT id, asset_id, change, slot FROM asset_change_outbox
+ORDER BY id
+LIMIT $1
+FOR UPDATE SKIP LOCKED
+",
+        vec![(config.get_batch_size() as i64).into()],
+    ))
+    .all(&txn)
+    .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let mut events = Vec::with_capacity(rows.len());
+    for row in rows.iter() {
+        if ChangeType::from_name(&row.change).is_none() {
+            warn!("exporting unknown asset change {} as written", row.change);
+        }
+        let event = ExportedChange::new(row.id, &row.asset_id, row.change.clone(), row.slot);
+        let payload = serde_json::to_vec(&event)?;
+        events.push((event, payload));
+    }
+    // The whole batch is queued before waiting for its deliveries. The producer keeps the order
+    // they were queued in.
+    let deliveries = events.iter().map(|(event, payload)| {
+        let record = FutureRecord::to(config.topic_of(&event.change))
+            .key(&event.id)
+            .payload(payload);
+        producer.send(record, Timeout::After(Duration::from_secs(30)))
+    });
+    for delivery in join_all(deliveries).await {
+        delivery.map_err(|(e, _)| IngesterError::MessengerError(e.to_string()))?;
+    }
+    let ids: Vec<sea_orm::Value> = rows.iter().map(|row| row.id.into()).collect();
+    let placeholders = (1..=ids.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!(
+            "DELETE FROM asset_change_outbox WHERE id IN ({})",
+            placeholders
+        ),
+        ids,
+    ))
+    .await?;
+    txn.commit().await?;
+    Ok(rows.len())
+}
+
+/// Drains the outbox into Kafka, in the order the changes were written. Delivery is at least
+/// once: a change is only deleted once Kafka acknowledged it.
+pub fn delta_exporter(pool: PgPool, config: DeltaExportConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("enable.idempotence", "true")
+            .set("acks", "all");
+        for (key, value) in config.producer.iter() {
+            client.set(key, value);
+        }
+        let producer: FutureProducer = match client.create() {
+            Ok(producer) => producer,
+            Err(e) => {
+                error!("Failed to start the delta export producer: {}", e);
+                return;
+            }
+        };
+        let mut interval = time::interval(Duration::from_millis(config.get_interval()));
+        let mut gauged_at: Option<Instant> = None;
+        loop {
+            match export_batch(&conn, &producer, &config).await {
+                // A full batch means more are waiting.
+                Ok(exported) if exported as u64 == config.get_batch_size() => {
+                    metric! {
+                        statsd_count!("ingester.delta_export.exported", exported as i64);
+                    }
+                    continue;
+                }
+                Ok(exported) => {
+                    debug!("exported {} asset changes", exported);
+                    metric! {
+                        statsd_count!("ingester.delta_export.exported", exported as i64);
+                    }
+                }
+                Err(e) => {
+                    error!("error exporting asset changes: {}", e);
+                    metric! {
+                        statsd_count!("ingester.delta_export.error", 1);
+                    }
+                }
+            }
+            if gauged_at.map_or(true, |at| at.elapsed() >= OUTBOX_GAUGE_INTERVAL) {
+                gauged_at = Some(Instant::now());
+                if let Ok(Some(pending)) = outbox_len(&conn).await {
+                    metric! {
+                        statsd_gauge!("ingester.delta_export.outbox", pending);
+                    }
+                }
+            }
+            interval.tick().await;
+        }
+    })
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OutboxLen {
+    pending: i64,
+}
+
+async fn outbox_len(conn: &DatabaseConnection) -> Result<Option<u64>, IngesterError> {
+    Ok(OutboxLen::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "SELECT count(*) AS pending FROM asset_change_outbox".to_string(),
+    ))
+    .one(conn)
+    .await?
+    .map(|len| len.pending as u64))
+}
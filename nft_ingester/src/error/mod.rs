@@ -1,5 +1,6 @@
 use crate::tasks::TaskData;
 use blockbuster::error::BlockbusterError;
+use digital_asset_types::dao::numeric::NumericError;
 use plerkle_messenger::MessengerError;
 use plerkle_serialization::error::PlerkleSerializationError;
 use sea_orm::{DbErr, TransactionError};
@@ -52,6 +53,16 @@ pub enum IngesterError {
     HttpError { status_code: String },
     #[error("AssetIndex Error {0}")]
     AssetIndexError(String),
+    #[error("Schema Mismatch: {0}")]
+    SchemaMismatch(String),
+    #[error("Value out of range: {0}")]
+    ValueOutOfRange(String),
+}
+
+impl From<NumericError> for IngesterError {
+    fn from(err: NumericError) -> Self {
+        IngesterError::ValueOutOfRange(err.to_string())
+    }
 }
 
 impl From<reqwest::Error> for IngesterError {
@@ -0,0 +1,174 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use chrono::Utc;
+use digital_asset_types::dao::{failed_slots, numeric::to_i64};
+use sea_orm::{
+    entity::*, query::*, sea_query::Expr, ConnectionTrait, DbBackend, DbErr, FromQueryResult,
+};
+
+// Backoff before the first retry of a slot, doubled with every failed attempt.
+const INITIAL_RETRY_DELAY_SECS: i64 = 30;
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+/// How long a slot waits after its `attempts`th failure before it is retried.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let doublings = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    let secs = INITIAL_RETRY_DELAY_SECS.saturating_mul(1 << doublings);
+    chrono::Duration::seconds(secs.min(MAX_RETRY_DELAY_SECS))
+}
+
+/// Quarantines a slot of `tree` the backfiller couldn't read or parse, so it is retried rather
+/// than left as a gap. Only the transactions `signatures` are retried when given, the whole slot
+/// otherwise. A slot that is quarantined already keeps its attempts and only has its error and
+/// signatures updated, a slot quarantined whole stays so.
+pub async fn quarantine_slot<T>(
+    conn: &T,
+    tree: &[u8],
+    slot: u64,
+    error: &str,
+    signatures: Option<&[String]>,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+    let signatures = signatures
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| IngesterError::SerializatonError(e.to_string()))?;
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO failed_slots (tree, slot, error, next_retry_at, signatures)
+VALUES ($1, $2, $3, $4, $5)
+ON CONFLICT (tree, slot) DO UPDATE SET error = excluded.error, updated_at = now(),
+    signatures = CASE WHEN failed_slots.signatures IS NULL THEN NULL ELSE excluded.signatures END
+",
+        vec![
+            tree.into(),
+            to_i64(slot, "slot")?.into(),
+            error.into(),
+            (now + retry_delay(1)).into(),
+            signatures.into(),
+        ],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    metric! {
+        statsd_count!("ingester.backfiller.slot_quarantined", 1);
+    }
+    Ok(())
+}
+
+/// Claims the failed slot that has waited the longest past its retry time. Its retry time is
+/// pushed back by `lease` while it is retried, so other backfillers skip it, and a slot whose
+/// backfiller died is retried again once the lease runs out.
+pub async fn claim_failed_slot<T>(
+    conn: &T,
+    lease: chrono::Duration,
+) -> Result<Option<failed_slots::Model>, DbErr>
+where
+    T: ConnectionTrait,
+{
+    let now = Utc::now().naive_utc();
+    failed_slots::Entity::find()
+        .from_raw_sql(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+UPDATE failed_slots
+SET next_retry_at = $1
+WHERE (tree, slot) = (
+    SELECT tree, slot FROM failed_slots
+    WHERE NOT given_up AND next_retry_at <= $2
+    ORDER BY next_retry_at ASC
+    LIMIT 1
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING *
+",
+            vec![(now + lease).into(), now.into()],
+        ))
+        .one(conn)
+        .await
+}
+
+/// The transactions of a quarantined slot to retry, or none for all of them.
+pub fn retried_signatures(failed: &failed_slots::Model) -> Option<Vec<String>> {
+    failed
+        .signatures
+        .clone()
+        .and_then(|signatures| serde_json::from_value(signatures).ok())
+}
+
+/// Takes a slot that went through on retry out of quarantine.
+pub async fn release_failed_slot<T>(conn: &T, failed: &failed_slots::Model) -> Result<(), DbErr>
+where
+    T: ConnectionTrait,
+{
+    failed_slots::Entity::delete_many()
+        .filter(failed_slots::Column::Tree.eq(failed.tree.clone()))
+        .filter(failed_slots::Column::Slot.eq(failed.slot))
+        .exec(conn)
+        .await?;
+    Ok(())
+}
+
+/// Records another failed attempt at a quarantined slot, giving up on it once it has failed
+/// `retry_limit` times. Returns whether it was given up on.
+pub async fn fail_slot_retry<T>(
+    conn: &T,
+    failed: &failed_slots::Model,
+    error: &str,
+    retry_limit: u32,
+) -> Result<bool, DbErr>
+where
+    T: ConnectionTrait,
+{
+    let attempts = failed.attempts.saturating_add(1);
+    let given_up = attempts as u32 >= retry_limit;
+    let now = Utc::now().naive_utc();
+    failed_slots::Entity::update_many()
+        .col_expr(failed_slots::Column::Error, Expr::value(error))
+        .col_expr(failed_slots::Column::Attempts, Expr::value(attempts))
+        .col_expr(
+            failed_slots::Column::NextRetryAt,
+            Expr::value(now + retry_delay(attempts)),
+        )
+        .col_expr(failed_slots::Column::GivenUp, Expr::value(given_up))
+        .col_expr(failed_slots::Column::UpdatedAt, Expr::value(now))
+        .filter(failed_slots::Column::Tree.eq(failed.tree.clone()))
+        .filter(failed_slots::Column::Slot.eq(failed.slot))
+        .exec(conn)
+        .await?;
+    Ok(given_up)
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FailedSlotCounts {
+    pending: i64,
+    given_up: i64,
+}
+
+/// Reports the size of the quarantine, the slots still being retried and the ones given up on.
+pub async fn report_failed_slots<T>(conn: &T) -> Result<(), DbErr>
+where
+    T: ConnectionTrait,
+{
+    let counts = FailedSlotCounts::find_by_statement(Statement::from_string(
+        DbBackend::Postgres,
+        "
+SELECT count(*) FILTER (WHERE NOT given_up) AS pending, count(*) FILTER (WHERE given_up) AS given_up
+FROM failed_slots
+"
+        .to_string(),
+    ))
+    .one(conn)
+    .await?;
+    if let Some(counts) = counts {
+        metric! {
+            statsd_gauge!("ingester.backfiller.failed_slots", counts.pending as f64);
+            statsd_gauge!("ingester.backfiller.failed_slots_given_up", counts.given_up as f64);
+        }
+    }
+    Ok(())
+}
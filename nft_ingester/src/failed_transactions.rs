@@ -0,0 +1,73 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::numeric::to_i64;
+use plerkle_serialization::TransactionInfo;
+use sea_orm::{ConnectionTrait, DbBackend, Statement, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// The error of a failed transaction, from the runtime's `Program <id> failed: <error>` log of
+/// the program that failed. The plerkle messages don't carry the transaction's status, so this is
+/// the only sign of failure they have. Transactions whose logs were truncated before it can't be
+/// told apart from successful ones.
+pub fn transaction_error(tx: &TransactionInfo) -> Option<String> {
+    logged_failure(tx.log_messages()?.iter())
+}
+
+/// The error of the first `Program <id> failed: <error>` line of `logs`. Only lines the runtime
+/// writes match: programs' own output is prefixed `Program log:` or `Program data:`, which isn't
+/// a program id.
+pub fn logged_failure<'a>(logs: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    logs.into_iter().find_map(|log| {
+        let rest = log.strip_prefix("Program ")?;
+        let (program, error) = rest.split_once(' ')?;
+        let error = error.strip_prefix("failed: ")?;
+        Pubkey::from_str(program).ok()?;
+        Some(error.to_string())
+    })
+}
+
+/// Records a failed transaction in `failed_tx_log` for each known tree among `keys`, to debug
+/// updates users expected to happen. Nothing else is written for it. Returns how many trees it
+/// was recorded for.
+pub async fn record_failed_transaction<T>(
+    conn: &T,
+    signature: &str,
+    keys: &[Vec<u8>],
+    error: &str,
+    slot: u64,
+) -> Result<u64, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let placeholders = (0..keys.len())
+        .map(|i| format!("${}", i + 4))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut values: Vec<Value> = vec![signature.into(), error.into(), to_i64(slot, "slot")?.into()];
+    values.extend(keys.iter().map(|key| Value::from(key.clone())));
+    let res = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                "
+INSERT INTO failed_tx_log (signature, tree, error, slot)
+SELECT $1, tree, $2, $3 FROM merkle_tree WHERE tree IN ({})
+ON CONFLICT (signature, tree) DO NOTHING
+",
+                placeholders
+            ),
+            values,
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() > 0 {
+        metric! {
+            statsd_count!("ingester.failed_tx.recorded", res.rows_affected() as i64);
+        }
+    }
+    Ok(res.rows_affected())
+}
@@ -0,0 +1,90 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use digital_asset_types::dao::numeric::to_i64;
+use log::{debug, error};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, SqlxPostgresConnector, Statement};
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use sqlx::PgPool;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+/// How often the finality tracker reads the finalized slot, e.g. `'{interval_ms=1000}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct FinalityConfig {
+    pub interval_ms: Option<u64>,
+}
+
+impl FinalityConfig {
+    pub fn get_interval_ms(&self) -> u64 {
+        self.interval_ms.unwrap_or(1000).max(100)
+    }
+}
+
+/// Records `slot` as the finalized slot and drops the node versions that were replaced at or
+/// before it, returning how many were dropped.
+async fn finalize(conn: &DatabaseConnection, slot: u64) -> Result<u64, IngesterError> {
+    let slot = to_i64(slot, "slot")?;
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO finalized_slot (id, slot, updated_at) VALUES (1, $1, now())
+ON CONFLICT (id) DO UPDATE SET slot = excluded.slot, updated_at = excluded.updated_at
+WHERE excluded.slot > finalized_slot.slot
+",
+        vec![slot.into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    let pruned = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM cl_items_history WHERE replaced_slot <= $1",
+            vec![slot.into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(pruned.rows_affected())
+}
+
+/// Follows the cluster's finalized slot, which proofs at finalized commitment are served as of.
+/// While it runs the ingesters keep the tree nodes they overwrite until the overwrite is
+/// finalized.
+pub fn finality_tracker(pool: PgPool, rpc_url: String, config: FinalityConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::finalized());
+        let mut interval = time::interval(Duration::from_millis(config.get_interval_ms()));
+        loop {
+            interval.tick().await; // ticks immediately
+            let slot = match client.get_slot().await {
+                Ok(slot) => slot,
+                Err(e) => {
+                    error!("error reading the finalized slot: {}", e);
+                    metric! {
+                        statsd_count!("ingester.finality.error", 1);
+                    }
+                    continue;
+                }
+            };
+            match finalize(&conn, slot).await {
+                Ok(pruned) => {
+                    debug!("finalized slot {}, pruned {} node versions", slot, pruned);
+                    metric! {
+                        statsd_gauge!("ingester.finality.finalized_slot", slot);
+                        statsd_count!("ingester.finality.pruned", pruned as i64);
+                    }
+                }
+                Err(e) => {
+                    error!("error recording finalized slot {}: {}", slot, e);
+                    metric! {
+                        statsd_count!("ingester.finality.error", 1);
+                    }
+                }
+            }
+        }
+    })
+}
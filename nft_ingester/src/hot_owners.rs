@@ -0,0 +1,155 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::{debug, error, info};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, SqlxPostgresConnector, Statement, Value,
+};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::PgPool;
+use std::str::FromStr;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+/// The owners whose assets are kept in `hot_owner_assets` and how often the copy is reconciled
+/// with `asset`, e.g. `'{owners=["..."], interval=3600}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct HotOwnersConfig {
+    pub owners: Vec<String>,
+    pub interval: Option<u64>,
+}
+
+impl HotOwnersConfig {
+    /// Seconds between the starts of two reconciliations.
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(3600).max(1)
+    }
+}
+
+fn statement(sql: &str, values: Vec<Value>) -> Statement {
+    Statement::from_sql_and_values(DbBackend::Postgres, sql, values)
+}
+
+/// Registers `owners` as hot and drops the ones no longer configured along with their copies.
+/// New owners start without their assets, which the transformers add to from then on.
+async fn register_hot_owners(
+    conn: &DatabaseConnection,
+    owners: &[Vec<u8>],
+) -> Result<(), IngesterError> {
+    let owners: Vec<Value> = owners.iter().map(|o| o.clone().into()).collect();
+    for owner in owners.iter() {
+        conn.execute(statement(
+            "INSERT INTO hot_owners (owner) VALUES ($1) ON CONFLICT (owner) DO NOTHING",
+            vec![owner.clone()],
+        ))
+        .await?;
+    }
+    let dropped = if owners.is_empty() {
+        String::new()
+    } else {
+        let kept: Vec<_> = (1..=owners.len()).map(|i| format!("${}", i)).collect();
+        format!(" WHERE owner NOT IN ({})", kept.join(", "))
+    };
+    for table in ["hot_owner_assets", "hot_owners"] {
+        conn.execute(statement(
+            &format!("DELETE FROM {}{}", table, dropped),
+            owners.clone(),
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Copies the assets `owner` holds that its copy misses, and drops the ones it no longer holds or
+/// that were burnt, e.g. moved while the owner was first copied. Returns the assets added and
+/// removed. The owner is ready once its first copy is done.
+async fn reconcile_hot_owner(
+    conn: &DatabaseConnection,
+    owner: Vec<u8>,
+) -> Result<(u64, u64), IngesterError> {
+    let added = conn
+        .execute(statement(
+            "
+INSERT INTO hot_owner_assets (owner, asset_id, network, created_at)
+SELECT owner, id, network, created_at
+FROM asset
+WHERE owner = $1 AND supply > 0
+ON CONFLICT (owner, asset_id) DO NOTHING
+",
+            vec![owner.clone().into()],
+        ))
+        .await?
+        .rows_affected();
+    let removed = conn
+        .execute(statement(
+            "
+DELETE FROM hot_owner_assets hot
+USING asset
+WHERE hot.owner = $1 AND asset.id = hot.asset_id
+    AND (asset.owner IS DISTINCT FROM hot.owner OR asset.supply = 0)
+",
+            vec![owner.clone().into()],
+        ))
+        .await?
+        .rows_affected();
+    conn.execute(statement(
+        "UPDATE hot_owners SET ready = true WHERE owner = $1 AND NOT ready",
+        vec![owner.into()],
+    ))
+    .await?;
+    Ok((added, removed))
+}
+
+/// Keeps the copies of the hot owners' assets that serve their `getAssetsByOwner`. The
+/// transformers move assets in and out of the copies as they change owner, each round catches up
+/// with what they can't see, such as assets indexed before their owner was made hot.
+pub fn hot_owner_reconciler(pool: PgPool, config: HotOwnersConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let owners: Vec<Vec<u8>> = config
+            .owners
+            .iter()
+            .filter_map(|owner| match Pubkey::from_str(owner) {
+                Ok(owner) => Some(owner.to_bytes().to_vec()),
+                Err(_) => {
+                    error!("invalid hot owner {}", owner);
+                    None
+                }
+            })
+            .collect();
+        let mut interval = time::interval(Duration::from_secs(config.get_interval()));
+        loop {
+            interval.tick().await; // ticks immediately
+            debug!("reconciling hot owners");
+            if let Err(e) = register_hot_owners(&conn, &owners).await {
+                error!("error registering hot owners: {}", e);
+                continue;
+            }
+            for owner in owners.iter() {
+                match reconcile_hot_owner(&conn, owner.clone()).await {
+                    Ok((added, removed)) => {
+                        info!(
+                            "hot owner {} reconciled, {} assets added, {} removed",
+                            bs58::encode(owner).into_string(),
+                            added,
+                            removed
+                        );
+                        metric! {
+                            statsd_count!("ingester.hot_owners.added", added as i64);
+                            statsd_count!("ingester.hot_owners.removed", removed as i64);
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            "error reconciling hot owner {}: {}",
+                            bs58::encode(owner).into_string(),
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    })
+}
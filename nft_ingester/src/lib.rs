@@ -1,11 +1,40 @@
 pub mod account_updates;
 pub mod ack;
+pub mod admin;
+pub mod asset_changes;
+pub mod authority;
 pub mod backfiller;
+pub mod block_source;
+pub mod bootstrap;
+pub mod bulkhead;
+pub mod collection_stats;
+pub mod collection_traits;
 pub mod config;
+pub mod data_history;
 pub mod database;
+pub mod delta_export;
 pub mod error;
+pub mod failed_slots;
+pub mod failed_transactions;
+pub mod finality;
+pub mod hot_owners;
+pub mod memory_budget;
+pub mod merkle_tree;
 pub mod metrics;
+pub mod owner_history;
+pub mod ownership;
+pub mod pending;
+pub mod pricing;
+pub mod profiling;
+pub mod redis_failover;
 pub mod program_transformers;
+pub mod reindex;
+pub mod seq_buffer;
+pub mod service;
+pub mod standby;
 pub mod stream;
+pub mod stream_control;
+pub mod stream_start;
+pub mod supervisor;
 pub mod tasks;
 pub mod transaction_notifications;
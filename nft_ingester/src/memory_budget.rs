@@ -0,0 +1,90 @@
+use crate::metric;
+use cadence_macros::{is_global_default_set, statsd_count, statsd_time};
+use messenger_payload::decoded_len;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+// The budget is counted in KiB so large budgets fit the semaphore's permit counts.
+const UNIT: usize = 1024;
+
+/// Bounds the decoded payload bytes a stream's workers have in flight. Each message reserves
+/// its decoded size before it is processed, so the messages of a batch are only started as the
+/// budget allows and batches of large payloads, like the transactions of a big mint, are
+/// processed a few at a time instead of all at once. The stream's consumer sizes its batches
+/// to what the budget holds of the recent payloads, see `batch_size`.
+#[derive(Debug, Clone)]
+pub struct MemoryBudget {
+    stream: &'static str,
+    semaphore: Arc<Semaphore>,
+    units: u32,
+    // A moving average of the decoded payload sizes, 0 until the first payload.
+    average_len: Arc<AtomicUsize>,
+}
+
+/// Held while a message is processed, returns its bytes to the budget when dropped.
+pub struct BudgetPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl MemoryBudget {
+    pub fn new(stream: &'static str, megabytes: u64) -> Self {
+        let units = (megabytes.saturating_mul(1024)).clamp(1, u32::MAX as u64) as u32;
+        MemoryBudget {
+            stream,
+            semaphore: Arc::new(Semaphore::new(units as usize)),
+            units,
+            average_len: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    fn observe(&self, len: usize) {
+        // Weighs the latest payload an eighth, racing updates only lose a sample.
+        let average = self.average_len.load(Ordering::Relaxed);
+        let average = match average {
+            0 => len.max(1),
+            average => (average * 7 + len) / 8,
+        };
+        self.average_len.store(average.max(1), Ordering::Relaxed);
+    }
+
+    /// The number of messages of recent payload sizes the budget holds, at least 1 and at most
+    /// `max`. It is `max` until a payload was reserved.
+    pub fn batch_size(&self, max: usize) -> usize {
+        match self.average_len.load(Ordering::Relaxed) {
+            0 => max,
+            average => (self.units as usize * UNIT / average).clamp(1, max.max(1)),
+        }
+    }
+
+    /// Waits until the decoded size of `payload` fits the budget. A payload larger than the
+    /// whole budget waits for the budget to be free and is then processed alone.
+    pub async fn reserve(&self, payload: &[u8]) -> BudgetPermit {
+        let len = decoded_len(payload);
+        self.observe(len);
+        let units = (len / UNIT + 1).min(self.units as usize) as u32;
+        let permit = match Arc::clone(&self.semaphore).try_acquire_many_owned(units) {
+            Ok(permit) => permit,
+            Err(_) => {
+                let waiting = Instant::now();
+                metric! {
+                    statsd_count!("ingester.stream.memory_budget_wait", 1, "stream" => self.stream);
+                }
+                let permit = Arc::clone(&self.semaphore)
+                    .acquire_many_owned(units)
+                    .await
+                    .expect("memory budget semaphore is never closed");
+                metric! {
+                    statsd_time!("ingester.stream.memory_budget_wait_time", waiting.elapsed().as_millis() as u64, "stream" => self.stream);
+                }
+                permit
+            }
+        };
+        BudgetPermit { _permit: permit }
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{error::IngesterError, metric, program_transformers::writes_may_roll_back};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::numeric::to_i64;
+use lazy_static::lazy_static;
+use log::debug;
+use mpl_bubblegum::state::TreeConfig;
+use plerkle_serialization::AccountInfo;
+use sea_orm::{query::*, ConnectionTrait, DbBackend, Value};
+use solana_sdk::{
+    hash::{hash, Hash},
+    pubkey::Pubkey,
+};
+use spl_account_compression::state::{
+    merkle_tree_get_size, ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
+};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// Keeps a statement well under the bind parameter limit.
+const SHAPES_PER_STATEMENT: usize = 1000;
+// Every change to a tree changes its canopy, which is megabytes for deep trees, so a tree's
+// canopy is written at most this often.
+const CANOPY_WRITE_INTERVAL: Duration = Duration::from_secs(10);
+// Past this the canopy and shape are written again even when unchanged, in case they were
+// purged or never committed.
+const CANOPY_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+struct WrittenCanopy {
+    shape: TreeShape,
+    hash: Hash,
+    written_at: Instant,
+}
+
+lazy_static! {
+    // The canopy this process last wrote for each tree.
+    static ref WRITTEN_CANOPIES: Mutex<HashMap<Pubkey, WrittenCanopy>> = Mutex::new(HashMap::new());
+}
+
+/// A tree's settings as created, read from its `create_tree` instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeCreation {
+    pub tree: Pubkey,
+    pub creator: Pubkey,
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub public: bool,
+    pub slot: u64,
+}
+
+/// A tree's shape as stored in its account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeShape {
+    pub tree: Pubkey,
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub canopy_depth: u32,
+    pub creation_slot: u64,
+}
+
+impl TreeShape {
+    /// `canopy_bytes` is what follows the header and the tree itself in the tree account.
+    pub fn new(tree: Pubkey, header: &ConcurrentMerkleTreeHeader, canopy_bytes: usize) -> Self {
+        TreeShape {
+            tree,
+            max_depth: header.get_max_depth(),
+            max_buffer_size: header.get_max_buffer_size(),
+            canopy_depth: canopy_depth(canopy_bytes),
+            creation_slot: header.get_creation_slot(),
+        }
+    }
+}
+
+// A canopy of depth d holds the 2^(d+1) - 2 nodes of the tree's top d levels.
+fn canopy_depth(canopy_bytes: usize) -> u32 {
+    let nodes = canopy_bytes / 32;
+    (usize::BITS - (nodes + 2).leading_zeros()).saturating_sub(2)
+}
+
+fn canopy_nodes(canopy_depth: u32) -> usize {
+    (1usize << (canopy_depth + 1)) - 2
+}
+
+/// Bubblegum keeps a tree's config in a PDA of the tree.
+pub fn tree_config_address(tree: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[tree.as_ref()], &mpl_bubblegum::id()).0
+}
+
+pub async fn save_tree_creation<T>(txn: &T, creation: &TreeCreation) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    // The tree may already be known from its account, and its config from later updates
+    // which have the current delegate.
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO merkle_tree (tree, config, creator, delegate, max_depth, max_buffer_size, public, creation_slot)
+VALUES ($1, $2, $3, $3, $4, $5, $6, $7)
+ON CONFLICT (tree) DO UPDATE SET
+    creator = COALESCE(merkle_tree.creator, excluded.creator),
+    delegate = COALESCE(merkle_tree.delegate, excluded.delegate),
+    max_depth = excluded.max_depth,
+    max_buffer_size = excluded.max_buffer_size,
+    public = excluded.public,
+    creation_slot = excluded.creation_slot
+",
+        vec![
+            creation.tree.to_bytes().to_vec().into(),
+            tree_config_address(&creation.tree).to_bytes().to_vec().into(),
+            creation.creator.to_bytes().to_vec().into(),
+            (creation.max_depth as i32).into(),
+            (creation.max_buffer_size as i32).into(),
+            creation.public.into(),
+            to_i64(creation.slot, "creation_slot")?.into(),
+        ],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
+}
+
+/// Records the shape of trees read from their accounts, such as the canopy depth which the
+/// `create_tree` instruction doesn't carry. Trees whose shape is already recorded aren't
+/// written again.
+pub async fn save_tree_shapes<T>(conn: &T, shapes: &[TreeShape]) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    for chunk in shapes.chunks(SHAPES_PER_STATEMENT) {
+        let mut rows = Vec::with_capacity(chunk.len());
+        let mut values: Vec<Value> = Vec::with_capacity(chunk.len() * 6);
+        for (i, shape) in chunk.iter().enumerate() {
+            let p = i * 6;
+            rows.push(format!(
+                "(${}, ${}, ${}, ${}, ${}, ${})",
+                p + 1,
+                p + 2,
+                p + 3,
+                p + 4,
+                p + 5,
+                p + 6
+            ));
+            values.push(shape.tree.to_bytes().to_vec().into());
+            values.push(tree_config_address(&shape.tree).to_bytes().to_vec().into());
+            values.push((shape.max_depth as i32).into());
+            values.push((shape.max_buffer_size as i32).into());
+            values.push((shape.canopy_depth as i32).into());
+            values.push(to_i64(shape.creation_slot, "creation_slot")?.into());
+        }
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &format!(
+                "
+INSERT INTO merkle_tree (tree, config, max_depth, max_buffer_size, canopy_depth, creation_slot)
+VALUES {}
+ON CONFLICT (tree) DO UPDATE SET
+    max_depth = excluded.max_depth,
+    max_buffer_size = excluded.max_buffer_size,
+    canopy_depth = excluded.canopy_depth,
+    creation_slot = excluded.creation_slot
+WHERE merkle_tree.canopy_depth IS DISTINCT FROM excluded.canopy_depth
+OR merkle_tree.creation_slot IS DISTINCT FROM excluded.creation_slot
+",
+                rows.join(", ")
+            ),
+            values,
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Updates a tree's creator, delegate and mint counts from its Bubblegum tree config account.
+/// Other Bubblegum accounts are ignored. The config doesn't name its tree, so a config whose
+/// tree isn't known yet is skipped until the tree's creation or discovery records it.
+pub async fn handle_tree_config_account<T>(
+    account_update: &AccountInfo<'_>,
+    conn: &T,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let key = account_update
+        .pubkey()
+        .ok_or_else(|| IngesterError::DeserializationError("Missing pubkey".to_string()))?;
+    let data = account_update
+        .data()
+        .map(|d| d.bytes().to_vec())
+        .unwrap_or_default();
+    let config = match TreeConfig::try_deserialize(&mut data.as_slice()) {
+        Ok(config) => config,
+        Err(_) => return Ok(()),
+    };
+    let res = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+UPDATE merkle_tree SET
+    creator = $2,
+    delegate = $3,
+    total_mint_capacity = $4,
+    num_minted = $5,
+    slot_updated = $6
+WHERE config = $1 AND (slot_updated IS NULL OR slot_updated <= $6)
+",
+            vec![
+                key.0.to_vec().into(),
+                config.tree_creator.to_bytes().to_vec().into(),
+                config.tree_delegate.to_bytes().to_vec().into(),
+                to_i64(config.total_mint_capacity, "total_mint_capacity")?.into(),
+                to_i64(config.num_minted, "num_minted")?.into(),
+                to_i64(account_update.slot(), "slot")?.into(),
+            ],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() == 0 {
+        debug!(
+            "tree config {} has no known tree",
+            bs58::encode(key.0).into_string()
+        );
+    }
+    Ok(())
+}
+
+/// Records a tree's shape and canopy from its account compression account, so proofs can be
+/// trimmed and canopies checked without reading the account. Trees that aren't Bubblegum's
+/// aren't indexed and are skipped, as are accounts that aren't trees.
+pub async fn handle_tree_account<T>(
+    account_update: &AccountInfo<'_>,
+    conn: &T,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let key = account_update
+        .pubkey()
+        .ok_or_else(|| IngesterError::DeserializationError("Missing pubkey".to_string()))?;
+    let tree = Pubkey::new(key.0.as_slice());
+    let data = account_update
+        .data()
+        .map(|d| d.bytes().to_vec())
+        .unwrap_or_default();
+    if data.len() < CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1 {
+        return Ok(());
+    }
+    let (header_bytes, rest) = data.split_at(CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1);
+    let header = match ConcurrentMerkleTreeHeader::try_from_slice(header_bytes) {
+        Ok(header) => header,
+        Err(_) => return Ok(()),
+    };
+    if header
+        .assert_valid_authority(&tree_config_address(&tree))
+        .is_err()
+    {
+        return Ok(());
+    }
+    let tree_size = merkle_tree_get_size(&header)
+        .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+    let canopy = rest.get(tree_size..).ok_or_else(|| {
+        IngesterError::DeserializationError(format!("tree {} account is truncated", tree))
+    })?;
+    let shape = TreeShape::new(tree, &header, canopy.len());
+    let nodes = &canopy[..canopy_nodes(shape.canopy_depth) * 32];
+    let nodes_hash = hash(nodes);
+    // A rolled back write mustn't keep the one that counts from being written.
+    let cached = !writes_may_roll_back();
+    let mut shape_written = false;
+    if cached {
+        if let Some(written) = WRITTEN_CANOPIES.lock().unwrap().get(&tree) {
+            let age = written.written_at.elapsed();
+            shape_written = written.shape == shape && age < CANOPY_REFRESH_INTERVAL;
+            if shape_written && (written.hash == nodes_hash || age < CANOPY_WRITE_INTERVAL) {
+                metric! {
+                    statsd_count!("ingester.tree_canopy.skipped", 1);
+                }
+                return Ok(());
+            }
+        }
+    }
+    if !shape_written {
+        save_tree_shapes(conn, std::slice::from_ref(&shape)).await?;
+    }
+    save_tree_canopy(conn, &shape, nodes, account_update.slot()).await?;
+    if cached {
+        WRITTEN_CANOPIES.lock().unwrap().insert(
+            tree,
+            WrittenCanopy {
+                shape,
+                hash: nodes_hash,
+                written_at: Instant::now(),
+            },
+        );
+    }
+    Ok(())
+}
+
+/// Keeps the latest canopy read of a tree. Updates read out of order don't overwrite a later
+/// one.
+pub async fn save_tree_canopy<T>(
+    conn: &T,
+    shape: &TreeShape,
+    nodes: &[u8],
+    slot: u64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO merkle_tree_canopy (tree, canopy_depth, nodes, slot_updated)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT (tree) DO UPDATE SET
+    canopy_depth = excluded.canopy_depth,
+    nodes = excluded.nodes,
+    slot_updated = excluded.slot_updated
+WHERE merkle_tree_canopy.slot_updated <= excluded.slot_updated
+",
+        vec![
+            shape.tree.to_bytes().to_vec().into(),
+            (shape.canopy_depth as i32).into(),
+            nodes.to_vec().into(),
+            to_i64(slot, "slot")?.into(),
+        ],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
+}
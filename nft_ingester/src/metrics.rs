@@ -1,7 +1,8 @@
-use std::net::UdpSocket;
+use std::{panic::RefUnwindSafe, time::Duration};
 
-use cadence::{BufferedUdpMetricSink, QueuingMetricSink, StatsdClient};
+use cadence::{MetricSink, StatsdClient};
 use cadence_macros::{is_global_default_set, set_global_default, statsd_count, statsd_time};
+use digital_asset_types::metrics_sink::{self, FlushingMetricSink, LogMetricSink};
 use log::{error, warn};
 use tokio::time::Instant;
 
@@ -10,6 +11,9 @@ use crate::{
     error::IngesterError,
 };
 
+const METRICS_PREFIX: &str = "das_ingester";
+const METRICS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[macro_export]
 macro_rules! metric {
     {$($block:stmt;)*} => {
@@ -26,20 +30,38 @@ pub fn setup_metrics(config: &IngesterConfig) {
     let port = config.metrics_port;
     let env = config.env.clone().unwrap_or("dev".to_string());
     if uri.is_some() || port.is_some() {
-        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
-        socket.set_nonblocking(true).unwrap();
         let host = (uri.unwrap(), port.unwrap());
-        let udp_sink = BufferedUdpMetricSink::from(host, socket).unwrap();
-        let queuing_sink = QueuingMetricSink::from(udp_sink);
-        let builder = StatsdClient::builder("das_ingester", queuing_sink);
-        let client = builder
-            .with_tag("env", env)
-            .with_tag("version", CODE_VERSION)
-            .build();
+        let tags = format!("|#env:{},version:{}", env, CODE_VERSION);
+        let client = match FlushingMetricSink::start(host.clone(), METRICS_PREFIX, tags) {
+            Ok(sink) => statsd_client(sink, env),
+            Err(err) => {
+                warn!(
+                    "Metrics can't be sent to {}:{}, writing them to the log instead: {}",
+                    host.0, host.1, err
+                );
+                statsd_client(LogMetricSink, env)
+            }
+        };
         set_global_default(client);
     }
 }
 
+/// Sends the metrics still queued, waiting for at most a few seconds. Called on shutdown, as the
+/// sink's worker thread doesn't outlive the process.
+pub fn flush_metrics() {
+    metrics_sink::flush_metrics(METRICS_SHUTDOWN_TIMEOUT);
+}
+
+fn statsd_client<T>(sink: T, env: String) -> StatsdClient
+where
+    T: MetricSink + Sync + Send + RefUnwindSafe + 'static,
+{
+    StatsdClient::builder(METRICS_PREFIX, sink)
+        .with_tag("env", env)
+        .with_tag("version", CODE_VERSION)
+        .build()
+}
+
 // Returns a boolean indicating whether the redis message should be ACK'd.
 // If the message is not ACK'd, it will be retried as long as it is under the retry limit.
 pub fn capture_result(
@@ -0,0 +1,163 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use sea_orm::{query::*, ConnectionTrait, DbBackend};
+
+/// Appends `owner` to the owner history of `asset_id`, unless it is already the asset's owner
+/// as of `slot`, so updates that don't move the asset are no-ops. `seq` orders the changes of a
+/// slot: the tree's seq for compressed assets, the token account's write version otherwise.
+/// Returns whether it was appended.
+pub async fn record_owner_change<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    owner: Vec<u8>,
+    slot: i64,
+    seq: i64,
+) -> Result<bool, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO asset_owner_history (asset_id, owner, slot_updated, seq)
+SELECT $1, $2, $3, $4
+WHERE NOT EXISTS (
+    SELECT 1 FROM (
+        SELECT owner FROM asset_owner_history
+        WHERE asset_id = $1 AND slot_updated <= $3
+        ORDER BY slot_updated DESC, seq DESC, id DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.owner = $2
+)
+ON CONFLICT (asset_id, owner, slot_updated) DO NOTHING
+",
+            vec![
+                asset_id.clone().into(),
+                owner.into(),
+                slot.into(),
+                seq.into(),
+            ],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    let recorded = res.rows_affected() > 0;
+    if recorded {
+        metric! {
+            statsd_count!("ingester.owner_history.change_recorded", 1);
+        }
+        sync_hot_owner_asset(txn, asset_id).await?;
+    }
+    Ok(recorded)
+}
+
+/// Moves `asset_id` in `hot_owner_assets` to its latest owner in the history: away from the hot
+/// owner that held it, and to its owner if that one is hot. Changes recorded out of order leave
+/// the asset with whoever the history says holds it now.
+async fn sync_hot_owner_asset<T>(txn: &T, asset_id: Vec<u8>) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+WITH current AS (
+    SELECT owner FROM asset_owner_history
+    WHERE asset_id = $1
+    ORDER BY slot_updated DESC, seq DESC, id DESC
+    LIMIT 1
+), moved AS (
+    DELETE FROM hot_owner_assets
+    WHERE asset_id = $1 AND owner NOT IN (SELECT owner FROM current)
+)
+INSERT INTO hot_owner_assets (owner, asset_id, network, created_at)
+SELECT current.owner, asset.id, asset.network, asset.created_at
+FROM current
+JOIN hot_owners ON hot_owners.owner = current.owner
+JOIN asset ON asset.id = $1
+ON CONFLICT (owner, asset_id) DO NOTHING
+",
+        vec![asset_id.into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
+}
+
+/// Appends `delegate` to the delegate history of `asset_id`, unless it is already the asset's
+/// delegate as of `slot`. `None` records that the delegate was cleared, once the asset had one.
+pub async fn record_delegate_change<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    delegate: Option<Vec<u8>>,
+    slot: i64,
+    seq: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO asset_delegate_history (asset_id, delegate, slot_updated, seq)
+SELECT $1, $2, $3, $4
+WHERE NOT EXISTS (
+    SELECT 1 FROM (
+        SELECT delegate FROM asset_delegate_history
+        WHERE asset_id = $1 AND slot_updated <= $3
+        ORDER BY slot_updated DESC, seq DESC, id DESC
+        LIMIT 1
+    ) latest
+    WHERE latest.delegate IS NOT DISTINCT FROM $2
+)
+AND ($2 IS NOT NULL OR EXISTS (
+    SELECT 1 FROM asset_delegate_history WHERE asset_id = $1 AND slot_updated <= $3
+))
+ON CONFLICT (asset_id, slot_updated, seq) DO NOTHING
+",
+            vec![asset_id.into(), delegate.into(), slot.into(), seq.into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if res.rows_affected() > 0 {
+        metric! {
+            statsd_count!("ingester.owner_history.delegate_recorded", 1);
+        }
+    }
+    Ok(())
+}
+
+/// Records the slot `asset_id` was burnt at, and drops it from its hot owner's assets. A burn
+/// seen again, or out of order, keeps the earliest slot.
+pub async fn record_burn<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    slot: i64,
+    seq: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO asset_burns (asset_id, slot, seq)
+VALUES ($1, $2, $3)
+ON CONFLICT (asset_id) DO UPDATE SET slot = excluded.slot, seq = excluded.seq
+WHERE excluded.slot < asset_burns.slot
+",
+        vec![asset_id.clone().into(), slot.into(), seq.into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM hot_owner_assets WHERE asset_id = $1",
+        vec![asset_id.into()],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use crate::{
+    asset_changes::{ChangeNotifier, ChangeType},
+    error::IngesterError,
+    metric,
+    owner_history::record_owner_change,
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::{debug, error, info, warn};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector,
+    Statement, TransactionTrait,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+/// How often the ownership verifier scans the uncompressed NFTs and whether it fixes the
+/// conflicts it finds, e.g. `'{interval=3600, batch_size=1000, repair=true}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct OwnershipVerifierConfig {
+    pub interval: Option<u64>,
+    pub batch_size: Option<u64>,
+    pub repair: Option<bool>,
+}
+
+impl OwnershipVerifierConfig {
+    /// Seconds between the starts of two scans.
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(3600).max(1)
+    }
+
+    /// Assets checked per query.
+    pub fn get_batch_size(&self) -> u64 {
+        self.batch_size.unwrap_or(1000).max(1)
+    }
+
+    pub fn get_repair(&self) -> bool {
+        self.repair.unwrap_or(false)
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OwnershipCheck {
+    id: Vec<u8>,
+    owner: Option<Vec<u8>>,
+    holder: Option<Vec<u8>>,
+}
+
+/// The owner of each NFT of a batch next to the owner of its largest token account, by asset id
+/// after `after`. NFTs without a token account holding them have no holder.
+async fn ownership_batch(
+    conn: &DatabaseConnection,
+    after: &[u8],
+    batch_size: u64,
+) -> Result<Vec<OwnershipCheck>, IngesterError> {
+    Ok(
+        OwnershipCheck::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+SELECT a.id, a.owner, t.owner AS holder
+FROM (
+    SELECT id, owner FROM asset
+    WHERE id > $1 AND owner_type = 'single' AND supply = 1 AND compressed = false
+    ORDER BY id
+    LIMIT $2
+) a
+LEFT JOIN LATERAL (
+    SELECT owner FROM token_accounts
+    WHERE mint = a.id AND amount > 0
+    ORDER BY amount DESC, slot_updated DESC
+    LIMIT 1
+) t ON true
+ORDER BY a.id
+",
+            vec![after.to_vec().into(), (batch_size as i64).into()],
+        ))
+        .all(conn)
+        .await?,
+    )
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RepairedOwner {
+    slot_updated: i64,
+}
+
+/// Sets the asset's owner to `holder` while its token account still holds it, so a transfer
+/// indexed since the check isn't undone, and records and reports the change of owner like a
+/// transfer indexed from the account, at the slot the account was last updated. Returns whether
+/// the asset was changed.
+async fn repair_owner(
+    conn: &DatabaseConnection,
+    changes: &ChangeNotifier,
+    id: &[u8],
+    holder: &[u8],
+) -> Result<bool, IngesterError> {
+    let txn = conn.begin().await?;
+    let repaired = RepairedOwner::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+UPDATE asset SET owner = $2
+FROM (
+    SELECT max(slot_updated) AS slot_updated FROM token_accounts
+    WHERE mint = $1 AND owner = $2 AND amount > 0
+) t
+WHERE id = $1 AND owner IS DISTINCT FROM $2 AND t.slot_updated IS NOT NULL
+RETURNING t.slot_updated
+",
+        vec![id.to_vec().into(), holder.to_vec().into()],
+    ))
+    .one(&txn)
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    let slot = match repaired {
+        Some(repaired) => repaired.slot_updated,
+        None => return Ok(false),
+    };
+    // The holder's account as last updated in its slot, after the changes recorded in the slot.
+    record_owner_change(&txn, id.to_vec(), holder.to_vec(), slot, i64::MAX).await?;
+    changes
+        .export(&txn, id, ChangeType::Transfer, slot as u64)
+        .await?;
+    txn.commit().await?;
+    changes.notify(id, ChangeType::Transfer, slot as u64);
+    Ok(true)
+}
+
+/// Checks every uncompressed NFT once, returning the conflicts found and repaired.
+async fn verify_ownership(
+    conn: &DatabaseConnection,
+    config: &OwnershipVerifierConfig,
+    changes: &ChangeNotifier,
+) -> Result<(usize, usize), IngesterError> {
+    let (mut conflicts, mut repaired) = (0, 0);
+    let mut after = Vec::new();
+    loop {
+        let checks = ownership_batch(conn, &after, config.get_batch_size()).await?;
+        metric! {
+            statsd_count!("ingester.ownership.checked", checks.len() as i64);
+        }
+        for check in checks.iter() {
+            let holder = match &check.holder {
+                Some(holder) if check.owner.as_ref() != Some(holder) => holder,
+                _ => continue,
+            };
+            conflicts += 1;
+            warn!(
+                "Asset {} is owned by {:?} but held by {}",
+                bs58::encode(&check.id).into_string(),
+                check.owner.as_ref().map(|o| bs58::encode(o).into_string()),
+                bs58::encode(holder).into_string()
+            );
+            metric! {
+                statsd_count!("ingester.ownership.conflict", 1);
+            }
+            if config.get_repair() && repair_owner(conn, changes, &check.id, holder).await? {
+                repaired += 1;
+                metric! {
+                    statsd_count!("ingester.ownership.repaired", 1);
+                }
+            }
+        }
+        match checks.last() {
+            Some(last) if checks.len() as u64 == config.get_batch_size() => after = last.id.clone(),
+            _ => break,
+        }
+    }
+    Ok((conflicts, repaired))
+}
+
+/// Periodically cross-checks the owner of uncompressed NFTs against the owner of the token
+/// account holding them, to catch the ones a missed account update left with a stale owner.
+pub fn ownership_verifier(
+    pool: PgPool,
+    config: OwnershipVerifierConfig,
+    changes: ChangeNotifier,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let mut interval = time::interval(Duration::from_secs(config.get_interval()));
+        loop {
+            interval.tick().await; // ticks immediately
+            debug!("verifying asset ownership");
+            match verify_ownership(&conn, &config, &changes).await {
+                Ok((conflicts, repaired)) => {
+                    info!(
+                        "ownership verified, {} conflicts, {} repaired",
+                        conflicts, repaired
+                    );
+                }
+                Err(e) => {
+                    error!("error verifying ownership: {}", e);
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,177 @@
+use crate::{error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use digital_asset_types::dao::{asset_data, pending_creator_verifications};
+use log::{debug, error, info};
+use sea_orm::{
+    query::*, sea_query::OnConflict, ActiveValue::Set, ColumnTrait, ConnectionTrait, DbBackend,
+    EntityTrait, SqlxPostgresConnector,
+};
+use sqlx::PgPool;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration},
+};
+
+const SWEEP_INTERVAL: u64 = 60;
+
+/// Whether the mint of `asset_id` has been indexed yet. Creator rows only exist once it has.
+pub async fn asset_is_minted<T>(txn: &T, asset_id: Vec<u8>) -> Result<bool, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let found = asset_data::Entity::find_by_id(asset_id).count(txn).await?;
+    Ok(found > 0)
+}
+
+/// Holds a creator (un)verification that arrived before the mint of its asset, keeping only
+/// the latest one per asset and creator.
+pub async fn buffer_creator_verification<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    creator: Vec<u8>,
+    verified: bool,
+    seq: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let model = pending_creator_verifications::ActiveModel {
+        asset_id: Set(asset_id),
+        creator: Set(creator),
+        verified: Set(verified),
+        seq: Set(seq),
+        ..Default::default()
+    };
+
+    let mut query = pending_creator_verifications::Entity::insert(model)
+        .on_conflict(
+            OnConflict::columns([
+                pending_creator_verifications::Column::AssetId,
+                pending_creator_verifications::Column::Creator,
+            ])
+            .update_columns([
+                pending_creator_verifications::Column::Verified,
+                pending_creator_verifications::Column::Seq,
+            ])
+            .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+    query.sql = format!(
+        "{} WHERE excluded.seq > pending_creator_verifications.seq",
+        query.sql
+    );
+
+    txn.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    metric! {
+        statsd_count!("ingester.pending.creator_verification.buffered", 1);
+    }
+    Ok(())
+}
+
+/// Applies and removes the pending creator verifications of minted assets, only of `asset_id`
+/// when given. Returns how many were applied.
+pub async fn apply_pending_creator_verifications<T>(
+    txn: &T,
+    asset_id: Option<Vec<u8>>,
+) -> Result<u64, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let (filter, values): (&str, Vec<Value>) = match asset_id {
+        Some(id) => ("AND p.asset_id = $1", vec![id.into()]),
+        None => ("", vec![]),
+    };
+    let sql = format!(
+        "
+WITH applied AS (
+    DELETE FROM pending_creator_verifications p
+    USING asset_data d
+    WHERE p.asset_id = d.id {}
+    RETURNING p.asset_id, p.creator, p.verified, p.seq
+)
+UPDATE asset_creators c
+SET verified = a.verified, seq = a.seq
+FROM applied a
+WHERE c.asset_id = a.asset_id AND c.creator = a.creator AND (c.seq IS NULL OR a.seq >= c.seq)
+",
+        filter
+    );
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            &sql,
+            values,
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    let applied = res.rows_affected();
+    if applied > 0 {
+        debug!("Applied {} pending creator verifications", applied);
+        metric! {
+            statsd_count!("ingester.pending.creator_verification.applied", applied as i64);
+        }
+    }
+    Ok(applied)
+}
+
+/// Drops pending creator verifications older than `ttl`, their asset was never minted.
+pub async fn expire_pending_creator_verifications<T>(
+    txn: &T,
+    ttl: Duration,
+) -> Result<u64, IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM pending_creator_verifications WHERE created_at < NOW() - ($1 * INTERVAL '1 second')",
+            vec![(ttl.as_secs() as i64).into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(res.rows_affected())
+}
+
+/// Periodically applies pending operations whose asset has since been minted, which covers a
+/// mint committed concurrently with the buffering, and expires the ones past `ttl`.
+pub fn pending_operations_sweeper(pool: PgPool, ttl: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let mut interval = time::interval(Duration::from_secs(SWEEP_INTERVAL));
+        loop {
+            interval.tick().await; // ticks immediately
+            if let Err(e) = apply_pending_creator_verifications(&conn, None).await {
+                error!("error applying pending creator verifications: {}", e);
+            }
+            match expire_pending_creator_verifications(&conn, ttl).await {
+                Ok(expired) => {
+                    if expired > 0 {
+                        info!("expired {} pending creator verifications", expired);
+                    }
+                    metric! {
+                        statsd_count!("ingester.pending.creator_verification.expired", expired as i64);
+                    }
+                }
+                Err(e) => {
+                    error!("error expiring pending creator verifications: {}", e);
+                }
+            }
+            match pending_creator_verifications::Entity::find()
+                .count(&conn)
+                .await
+            {
+                Ok(count) => {
+                    metric! {
+                        statsd_gauge!("ingester.pending.creator_verification.count", count);
+                    }
+                }
+                Err(e) => {
+                    error!("error counting pending creator verifications: {}", e);
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,320 @@
+use crate::{error::IngesterError, metric};
+use async_trait::async_trait;
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::dao::{asset_grouping, asset_pricing, COLLECTION_GROUP_KEY};
+use log::{debug, error, warn};
+use sea_orm::{
+    sea_query::Query, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait,
+    QueryFilter, SqlxPostgresConnector, Statement, Value,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tokio::{
+    task::JoinHandle,
+    time::{self, Duration, Instant},
+};
+
+const DEFAULT_CURRENCY: &str = "USD";
+
+/// What a price estimate is for, a single asset or each verified asset of a collection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceTarget {
+    Asset(Vec<u8>),
+    Collection(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceEstimate {
+    pub target: PriceTarget,
+    pub price: f64,
+    pub currency: String,
+}
+
+/// A source of price estimates. Estimates of a provider are stored under its name, next to the
+/// other providers' estimates of the same asset, and replace its earlier ones.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn estimates(
+        &self,
+        conn: &DatabaseConnection,
+    ) -> Result<Vec<PriceEstimate>, IngesterError>;
+}
+
+/// The providers to run and how often, e.g.
+/// `'{interval=300, max_age=86400, providers=[{type="Feed", name="floor", url="https://..."}]}'`.
+#[derive(Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct PricingConfig {
+    pub interval: Option<u64>,
+    pub max_age: Option<u64>,
+    #[serde(default)]
+    pub providers: Vec<PriceProviderConfig>,
+}
+
+impl PricingConfig {
+    /// Seconds between runs of the providers.
+    pub fn get_interval(&self) -> u64 {
+        self.interval.unwrap_or(300).max(1)
+    }
+
+    /// Seconds a failing provider's estimates are kept since its last successful run.
+    pub fn get_max_age(&self) -> u64 {
+        self.max_age.unwrap_or(86400).max(self.get_interval())
+    }
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum PriceProviderConfig {
+    /// Polls `url` for a JSON object of collection addresses to prices.
+    Feed {
+        name: String,
+        url: String,
+        currency: Option<String>,
+    },
+    /// Fixed prices per collection address.
+    Static {
+        name: String,
+        currency: Option<String>,
+        prices: HashMap<String, f64>,
+    },
+}
+
+impl PriceProviderConfig {
+    pub fn build(&self) -> Box<dyn PriceProvider> {
+        match self.clone() {
+            PriceProviderConfig::Feed {
+                name,
+                url,
+                currency,
+            } => Box::new(FeedProvider {
+                name,
+                url,
+                currency: currency.unwrap_or_else(|| DEFAULT_CURRENCY.to_string()),
+                client: reqwest::Client::new(),
+            }),
+            PriceProviderConfig::Static {
+                name,
+                currency,
+                prices,
+            } => Box::new(StaticProvider {
+                name,
+                currency: currency.unwrap_or_else(|| DEFAULT_CURRENCY.to_string()),
+                prices,
+            }),
+        }
+    }
+}
+
+/// Collection prices from an external feed, such as a marketplace's floor prices.
+pub struct FeedProvider {
+    name: String,
+    url: String,
+    currency: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl PriceProvider for FeedProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn estimates(
+        &self,
+        _conn: &DatabaseConnection,
+    ) -> Result<Vec<PriceEstimate>, IngesterError> {
+        let response = self
+            .client
+            .get(&self.url)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|e| IngesterError::HttpError {
+                status_code: e.status().map(|s| s.to_string()).unwrap_or_default(),
+            })?;
+        if !response.status().is_success() {
+            return Err(IngesterError::HttpError {
+                status_code: response.status().to_string(),
+            });
+        }
+        let prices: HashMap<String, f64> = response
+            .json()
+            .await
+            .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
+        Ok(collection_estimates(prices, &self.currency))
+    }
+}
+
+pub struct StaticProvider {
+    name: String,
+    currency: String,
+    prices: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl PriceProvider for StaticProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn estimates(
+        &self,
+        _conn: &DatabaseConnection,
+    ) -> Result<Vec<PriceEstimate>, IngesterError> {
+        Ok(collection_estimates(self.prices.clone(), &self.currency))
+    }
+}
+
+fn collection_estimates(prices: HashMap<String, f64>, currency: &str) -> Vec<PriceEstimate> {
+    prices
+        .into_iter()
+        .filter(|(_, price)| price.is_finite() && *price >= 0.0)
+        .map(|(collection, price)| PriceEstimate {
+            target: PriceTarget::Collection(collection),
+            price,
+            currency: currency.to_string(),
+        })
+        .collect()
+}
+
+/// Runs the configured providers every interval and stores their estimates in `asset_pricing`.
+/// A provider that fails keeps its earlier estimates until its next run, or until `max_age`
+/// passed since its last successful one.
+pub fn price_updater(pool: PgPool, config: PricingConfig) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let providers: Vec<_> = config.providers.iter().map(|p| p.build()).collect();
+        let max_age = Duration::from_secs(config.get_max_age());
+        let mut last_success = vec![Instant::now(); providers.len()];
+        let mut interval = time::interval(Duration::from_secs(config.get_interval()));
+        loop {
+            interval.tick().await; // ticks immediately
+            for (provider, last_success) in providers.iter().zip(last_success.iter_mut()) {
+                match update_prices(&conn, provider.as_ref()).await {
+                    Ok((priced, expired)) => {
+                        *last_success = Instant::now();
+                        debug!(
+                            "{} priced {} assets and expired {}",
+                            provider.name(),
+                            priced,
+                            expired
+                        );
+                        metric! {
+                            statsd_count!("ingester.pricing.priced", priced as i64, "provider" => provider.name());
+                            statsd_count!("ingester.pricing.expired", expired as i64, "provider" => provider.name());
+                        }
+                    }
+                    Err(e) => {
+                        error!("error updating {} prices: {}", provider.name(), e);
+                        metric! {
+                            statsd_count!("ingester.pricing.error", 1, "provider" => provider.name());
+                        }
+                        if last_success.elapsed() >= max_age {
+                            expire_provider(&conn, provider.name()).await;
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+// Drops every estimate of a provider that hasn't run successfully for `max_age`.
+async fn expire_provider(conn: &DatabaseConnection, provider: &str) {
+    let res = conn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM asset_pricing WHERE provider = $1",
+            vec![provider.into()],
+        ))
+        .await;
+    match res {
+        Ok(res) if res.rows_affected() > 0 => {
+            warn!(
+                "expired {} estimates of failing provider {}",
+                res.rows_affected(),
+                provider
+            );
+            metric! {
+                statsd_count!("ingester.pricing.expired", res.rows_affected() as i64, "provider" => provider);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => error!("error expiring {} prices: {}", provider, e),
+    }
+}
+
+// Stores the estimates that changed and drops the provider's estimates of assets it no longer
+// prices. Returns how many assets were priced anew and how many estimates were dropped.
+async fn update_prices(
+    conn: &DatabaseConnection,
+    provider: &dyn PriceProvider,
+) -> Result<(u64, u64), IngesterError> {
+    let estimates = provider.estimates(conn).await?;
+    let mut assets: Vec<Vec<u8>> = Vec::new();
+    let mut collections: Vec<String> = Vec::new();
+    for estimate in estimates.iter() {
+        match &estimate.target {
+            PriceTarget::Asset(id) => assets.push(id.clone()),
+            PriceTarget::Collection(collection) => collections.push(collection.clone()),
+        }
+    }
+    let mut priced = 0;
+    for estimate in estimates {
+        let (source, target): (&str, Value) = match estimate.target {
+            PriceTarget::Asset(id) => ("SELECT $1::bytea AS asset_id", id.into()),
+            PriceTarget::Collection(collection) => (
+                "
+SELECT asset_id FROM asset_grouping
+WHERE group_key = 'collection' AND group_value = $1 AND verified = true
+",
+                collection.into(),
+            ),
+        };
+        let res = conn
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                &format!(
+                    "
+INSERT INTO asset_pricing (asset_id, provider, price, currency, updated_at)
+SELECT asset_id, $2, $3, $4, now() FROM ({}) targets
+ON CONFLICT (asset_id, provider) DO UPDATE SET
+    price = excluded.price,
+    currency = excluded.currency,
+    updated_at = excluded.updated_at
+WHERE asset_pricing.price != excluded.price OR asset_pricing.currency != excluded.currency
+",
+                    source
+                ),
+                vec![
+                    target,
+                    provider.name().into(),
+                    estimate.price.into(),
+                    estimate.currency.into(),
+                ],
+            ))
+            .await
+            .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+        priced += res.rows_affected();
+    }
+    // Assets the provider dropped, or that left a priced collection.
+    let priced_collections = Query::select()
+        .column(asset_grouping::Column::AssetId)
+        .from(asset_grouping::Entity)
+        .and_where(asset_grouping::Column::GroupKey.eq(COLLECTION_GROUP_KEY))
+        .and_where(asset_grouping::Column::GroupValue.is_in(collections))
+        .and_where(asset_grouping::Column::Verified.eq(true))
+        .to_owned();
+    let expired = asset_pricing::Entity::delete_many()
+        .filter(asset_pricing::Column::Provider.eq(provider.name()))
+        .filter(asset_pricing::Column::AssetId.is_not_in(assets))
+        .filter(asset_pricing::Column::AssetId.not_in_subquery(priced_collections))
+        .exec(conn)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?
+        .rows_affected;
+    Ok((priced, expired))
+}
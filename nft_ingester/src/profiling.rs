@@ -0,0 +1,74 @@
+use pprof::{protos::Message, ProfilerGuardBuilder};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+// Samples per second, a prime so sampling doesn't line up with periodic work.
+const FREQUENCY: i32 = 99;
+const MAX_SECONDS: u64 = 300;
+
+// pprof-rs profiles the whole process through a single signal handler.
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// A flamegraph, as SVG.
+    Flamegraph,
+    /// An uncompressed pprof protobuf, for `go tool pprof` and other viewers.
+    Proto,
+}
+
+impl ProfileFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "svg" | "flamegraph" => Some(ProfileFormat::Flamegraph),
+            "proto" | "pprof" => Some(ProfileFormat::Proto),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ProfileFormat::Flamegraph => "image/svg+xml",
+            ProfileFormat::Proto => "application/octet-stream",
+        }
+    }
+}
+
+/// Samples the CPU of every thread of the ingester for `seconds`, at most 5 minutes. Only one
+/// profile runs at a time, `None` means another one is running.
+pub async fn cpu_profile(seconds: u64, format: ProfileFormat) -> Option<Result<Vec<u8>, String>> {
+    if PROFILING.swap(true, Ordering::SeqCst) {
+        return None;
+    }
+    let duration = Duration::from_secs(seconds.clamp(1, MAX_SECONDS));
+    // Sampling blocks the thread that owns the profiler until it is done.
+    let profile = tokio::task::spawn_blocking(move || profile(duration, format))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|profile| profile);
+    PROFILING.store(false, Ordering::SeqCst);
+    Some(profile)
+}
+
+fn profile(duration: Duration, format: ProfileFormat) -> Result<Vec<u8>, String> {
+    let guard = ProfilerGuardBuilder::default()
+        .frequency(FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| e.to_string())?;
+    thread::sleep(duration);
+    let report = guard.report().build().map_err(|e| e.to_string())?;
+    let mut body = Vec::new();
+    match format {
+        ProfileFormat::Flamegraph => report.flamegraph(&mut body).map_err(|e| e.to_string())?,
+        ProfileFormat::Proto => report
+            .pprof()
+            .map_err(|e| e.to_string())?
+            .encode(&mut body)
+            .map_err(|e| e.to_string())?,
+    }
+    Ok(body)
+}
@@ -0,0 +1,241 @@
+//! Assertion mode runs a candidate transformer, such as a rewrite of the current one, on the same
+//! updates as the current transformer. The candidate writes inside a transaction that is rolled
+//! back, and the rows both of them wrote are compared and the differences logged, so a rewrite
+//! can be checked against live traffic before it replaces the current transformer.
+
+use crate::{error::IngesterError, metric, tasks::TaskData};
+use async_trait::async_trait;
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::warn;
+use plerkle_serialization::{AccountInfo, TransactionInfo};
+use rand::Rng;
+use sea_orm::{metric::Info, DatabaseTransaction};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::{cell::RefCell, future::Future, sync::Arc};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use super::ProgramTransformer;
+
+/// Runs assertions on a `sample_rate` share of the updates, e.g.
+/// `'{candidate="current", sample_rate=0.1, max_logged=20}'`.
+#[derive(Deserialize, PartialEq, Debug, Clone)]
+pub struct TransformerAssertionConfig {
+    /// The transformer compared with the current one, see [candidate_transformer].
+    pub candidate: String,
+    pub sample_rate: Option<f64>,
+    /// Differences logged per update, the rest are only counted.
+    pub max_logged: Option<usize>,
+}
+
+impl TransformerAssertionConfig {
+    pub fn get_sample_rate(&self) -> f64 {
+        self.sample_rate.unwrap_or(1.0).clamp(0.0, 1.0)
+    }
+
+    pub fn get_max_logged(&self) -> usize {
+        self.max_logged.unwrap_or(20)
+    }
+}
+
+/// A transformer writing its rows through the transaction it is given.
+#[async_trait]
+pub trait Transformer: Send + Sync {
+    async fn transaction(
+        &self,
+        txn: &DatabaseTransaction,
+        tx: &TransactionInfo<'_>,
+    ) -> Result<(), IngesterError>;
+
+    async fn account_update(
+        &self,
+        txn: &DatabaseTransaction,
+        acct: AccountInfo<'_>,
+    ) -> Result<(), IngesterError>;
+}
+
+#[async_trait]
+impl Transformer for ProgramTransformer {
+    async fn transaction(
+        &self,
+        txn: &DatabaseTransaction,
+        tx: &TransactionInfo<'_>,
+    ) -> Result<(), IngesterError> {
+        self.transform_transaction(txn, tx).await
+    }
+
+    async fn account_update(
+        &self,
+        txn: &DatabaseTransaction,
+        acct: AccountInfo<'_>,
+    ) -> Result<(), IngesterError> {
+        self.transform_account_update(txn, acct).await
+    }
+}
+
+/// The transformer a config names. A rewrite registers itself here under a new name, `current`
+/// compares the current transformer with itself, where every difference is a bug in the
+/// comparison or a read of rows another worker changed meanwhile.
+pub fn candidate_transformer(
+    name: &str,
+    pool: PgPool,
+) -> Result<Box<dyn Transformer>, IngesterError> {
+    match name {
+        "current" => Ok(Box::new(ProgramTransformer::new(pool, discarded_tasks()))),
+        _ => Err(IngesterError::ConfigurationError {
+            msg: format!("unknown candidate transformer {}", name),
+        }),
+    }
+}
+
+/// A sender for the background tasks of a candidate, which are dropped.
+fn discarded_tasks() -> UnboundedSender<TaskData> {
+    let (sender, mut receiver) = unbounded_channel();
+    tokio::spawn(async move { while receiver.recv().await.is_some() {} });
+    sender
+}
+
+/// A row write, told apart by its table and the values it binds so that statements written
+/// differently but writing the same row match.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mutation {
+    pub kind: &'static str,
+    pub table: String,
+    pub values: String,
+}
+
+impl Mutation {
+    fn parse(sql: &str, values: String) -> Option<Mutation> {
+        let mut words = sql.split_whitespace();
+        let (kind, table) = match words.next()?.to_ascii_uppercase().as_str() {
+            "INSERT" => ("insert", words.nth(1)?),
+            "UPDATE" => ("update", words.next()?),
+            "DELETE" => ("delete", words.nth(1)?),
+            _ => return None,
+        };
+        Some(Mutation {
+            kind,
+            table: table.split('(').next()?.trim_matches('"').to_string(),
+            values,
+        })
+    }
+}
+
+tokio::task_local! {
+    static MUTATIONS: RefCell<Vec<Mutation>>;
+}
+
+/// The metric callback of the transformers' connection, which records the writes of the
+/// transformer running in the current task. Writes outside of [record] are ignored.
+pub fn record_mutation(info: &Info<'_>) {
+    let _ = MUTATIONS.try_with(|mutations| {
+        let values = format!("{:?}", info.statement.values);
+        if let Some(mutation) = Mutation::parse(&info.statement.sql, values) {
+            mutations.borrow_mut().push(mutation);
+        }
+    });
+}
+
+/// Runs `future`, returning its result along with the writes it made.
+pub async fn record<F>(future: F) -> (Result<(), IngesterError>, Vec<Mutation>)
+where
+    F: Future<Output = Result<(), IngesterError>>,
+{
+    MUTATIONS
+        .scope(RefCell::new(Vec::new()), async move {
+            let result = future.await;
+            (result, MUTATIONS.with(|mutations| mutations.take()))
+        })
+        .await
+}
+
+#[derive(Clone)]
+pub struct Assertion {
+    name: String,
+    candidate: Arc<dyn Transformer>,
+    sample_rate: f64,
+    max_logged: usize,
+}
+
+impl Assertion {
+    pub fn new(config: &TransformerAssertionConfig, pool: PgPool) -> Result<Self, IngesterError> {
+        Ok(Assertion {
+            name: config.candidate.clone(),
+            candidate: Arc::from(candidate_transformer(&config.candidate, pool)?),
+            sample_rate: config.get_sample_rate(),
+            max_logged: config.get_max_logged(),
+        })
+    }
+
+    pub fn candidate(&self) -> &dyn Transformer {
+        self.candidate.as_ref()
+    }
+
+    pub fn sampled(&self) -> bool {
+        self.sample_rate >= 1.0 || rand::thread_rng().gen::<f64>() < self.sample_rate
+    }
+
+    /// Logs how the candidate's outcome for the update `id` differs from the current one's.
+    pub fn compare(
+        &self,
+        kind: &str,
+        id: &str,
+        current: &(Result<(), IngesterError>, Vec<Mutation>),
+        candidate: &(Result<(), IngesterError>, Vec<Mutation>),
+    ) {
+        let (only_current, only_candidate) = difference(&current.1, &candidate.1);
+        let results_match = match (&current.0, &candidate.0) {
+            (Ok(()), Ok(())) => true,
+            (Err(a), Err(b)) => a.to_string() == b.to_string(),
+            _ => false,
+        };
+        if results_match && only_current.is_empty() && only_candidate.is_empty() {
+            metric! {
+                statsd_count!("ingester.assertion.match", 1, "kind" => kind);
+            }
+            return;
+        }
+        metric! {
+            statsd_count!("ingester.assertion.mismatch", 1, "kind" => kind);
+        }
+        if !results_match {
+            warn!(
+                "candidate {} disagrees on the result of {} {}: current {:?}, candidate {:?}",
+                self.name, kind, id, current.0, candidate.0
+            );
+        }
+        for mutation in only_current.iter().take(self.max_logged) {
+            warn!(
+                "candidate {} missed a write of {} {}: {:?}",
+                self.name, kind, id, mutation
+            );
+        }
+        for mutation in only_candidate.iter().take(self.max_logged) {
+            warn!(
+                "candidate {} made an extra write for {} {}: {:?}",
+                self.name, kind, id, mutation
+            );
+        }
+    }
+}
+
+/// The mutations only in `a` and those only in `b`, counting repeated ones.
+fn difference(a: &[Mutation], b: &[Mutation]) -> (Vec<Mutation>, Vec<Mutation>) {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    a.sort();
+    b.sort();
+    let (mut only_a, mut only_b) = (Vec::new(), Vec::new());
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+    loop {
+        match (a.peek(), b.peek()) {
+            (Some(x), Some(y)) if x == y => {
+                a.next();
+                b.next();
+            }
+            (Some(x), Some(y)) if x < y => only_a.extend(a.next()),
+            (Some(_), Some(_)) | (None, Some(_)) => only_b.extend(b.next()),
+            (Some(_), None) => only_a.extend(a.next()),
+            (None, None) => return (only_a, only_b),
+        }
+    }
+}
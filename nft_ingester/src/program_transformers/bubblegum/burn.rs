@@ -1,12 +1,14 @@
 use crate::{
+    collection_stats::{record_collection_activity, CollectionActivity},
     error::IngesterError,
+    owner_history::record_burn,
     program_transformers::bubblegum::{
         save_changelog_event, u32_to_u8_array, upsert_asset_with_seq,
     },
 };
 use anchor_lang::prelude::Pubkey;
 use blockbuster::{instruction::InstructionBundle, programs::bubblegum::BubblegumInstruction};
-use digital_asset_types::dao::asset;
+use digital_asset_types::dao::{asset, numeric::to_i64};
 use log::debug;
 use sea_orm::{
     entity::*, query::*, sea_query::OnConflict, ConnectionTrait, DbBackend, EntityTrait,
@@ -42,7 +44,7 @@ where
         };
 
         // Upsert asset table `burnt` column.
-        let query = asset::Entity::insert(asset_model)
+        let mut query = asset::Entity::insert(asset_model)
             .on_conflict(
                 OnConflict::columns([asset::Column::Id])
                     .update_columns([
@@ -52,9 +54,15 @@ where
                     .to_owned(),
             )
             .build(DbBackend::Postgres);
-        txn.execute(query).await?;
+        query.sql = format!("{} WHERE NOT asset.burnt", query.sql);
+        let slot = to_i64(bundle.slot, "slot")?;
+        if txn.execute(query).await?.rows_affected() > 0 {
+            record_collection_activity(txn, id_bytes.to_vec(), CollectionActivity::Burn, slot)
+                .await?;
+        }
+        record_burn(txn, id_bytes.to_vec(), slot, seq).await?;
 
-        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await?;
+        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await?;
         return Ok(());
     }
     Err(IngesterError::ParsingError(
@@ -49,7 +49,7 @@ where
                     le.leaf_hash.to_vec(),
                     le.schema.data_hash(),
                     le.schema.creator_hash(),
-                    seq as i64,
+                    seq,
                     false,
                 )
                 .await?;
@@ -60,11 +60,11 @@ where
                     id_bytes.to_vec(),
                     owner_bytes,
                     delegate,
-                    seq as i64,
+                    seq,
                 )
                 .await?;
 
-                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await
+                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await
             }
         };
     }
@@ -8,7 +8,12 @@ use mpl_bubblegum::state::metaplex_adapter::Collection;
 use sea_orm::query::*;
 
 use super::{save_changelog_event, upsert_asset_with_leaf_info};
-use crate::error::IngesterError;
+use crate::{
+    collection_stats::{record_collection_join, verified_collection},
+    error::IngesterError,
+};
+use digital_asset_types::dao::numeric::to_i64;
+
 pub async fn process<'c, T>(
     parsing_result: &BubblegumInstruction,
     bundle: &InstructionBundle<'c>,
@@ -53,13 +58,15 @@ where
             le.leaf_hash.to_vec(),
             le.schema.data_hash(),
             le.schema.creator_hash(),
-            seq as i64,
+            seq,
             false,
         )
         .await?;
 
-        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await?;
+        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await?;
 
+        let slot = to_i64(bundle.slot, "slot")?;
+        let before = verified_collection(txn, id_bytes.to_vec()).await?;
         upsert_collection_info(
             txn,
             id_bytes.to_vec(),
@@ -67,10 +74,11 @@ where
                 key: collection.clone(),
                 verified: verify,
             }),
-            bundle.slot as i64,
-            seq as i64,
+            slot,
+            seq,
         )
         .await?;
+        record_collection_join(txn, id_bytes.to_vec(), before, slot).await?;
 
         return Ok(());
     };
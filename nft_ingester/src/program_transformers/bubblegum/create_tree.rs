@@ -0,0 +1,61 @@
+use crate::{
+    error::IngesterError,
+    merkle_tree::{save_tree_creation, TreeCreation},
+};
+use blockbuster::instruction::InstructionBundle;
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use solana_sdk::pubkey::Pubkey;
+
+// Anchor instruction discriminator.
+const DISCRIMINATOR_SIZE: usize = 8;
+
+pub async fn create_tree<'c, T>(
+    bundle: &InstructionBundle<'c>,
+    txn: &'c T,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait + TransactionTrait,
+{
+    let data = bundle
+        .instruction
+        .and_then(|ix| ix.data())
+        .map(|d| d.bytes().to_vec())
+        .unwrap_or_default();
+    let (max_depth, max_buffer_size, public) = parse_args(&data).ok_or_else(|| {
+        IngesterError::DeserializationError("Malformed create_tree instruction".to_string())
+    })?;
+    // Accounts: tree authority (the tree config), merkle tree, payer, tree creator, ...
+    let (tree, creator) = match (bundle.keys.get(1), bundle.keys.get(3)) {
+        (Some(tree), Some(creator)) => (Pubkey::new(&tree.0), Pubkey::new(&creator.0)),
+        _ => {
+            return Err(IngesterError::DeserializationError(
+                "Missing create_tree accounts".to_string(),
+            ))
+        }
+    };
+    save_tree_creation(
+        txn,
+        &TreeCreation {
+            tree,
+            creator,
+            max_depth,
+            max_buffer_size,
+            public,
+            slot: bundle.slot,
+        },
+    )
+    .await
+}
+
+// `max_depth: u32, max_buffer_size: u32, public: Option<bool>`, Bubblegum versions before public
+// trees don't have the last argument and only create private trees.
+fn parse_args(data: &[u8]) -> Option<(u32, u32, bool)> {
+    let args = data.get(DISCRIMINATOR_SIZE..)?;
+    let max_depth = u32::from_le_bytes(args.get(0..4)?.try_into().ok()?);
+    let max_buffer_size = u32::from_le_bytes(args.get(4..8)?.try_into().ok()?);
+    let public = match args.get(8..10) {
+        Some([1, public]) => *public == 1,
+        _ => false,
+    };
+    Some((max_depth, max_buffer_size, public))
+}
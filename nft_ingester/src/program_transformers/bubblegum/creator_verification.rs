@@ -1,14 +1,17 @@
 use crate::{
     error::IngesterError,
+    pending::{asset_is_minted, buffer_creator_verification},
     program_transformers::bubblegum::{
-        save_changelog_event, upsert_asset_with_leaf_info,
-        upsert_asset_with_owner_and_delegate_info, upsert_asset_with_seq, upsert_creator_verified,
+        save_changelog_event, update_creator_verified, upsert_asset_creators,
+        upsert_asset_with_leaf_info, upsert_asset_with_owner_and_delegate_info,
+        upsert_asset_with_seq,
     },
 };
 use blockbuster::{
     instruction::InstructionBundle,
     programs::bubblegum::{BubblegumInstruction, LeafSchema, Payload},
 };
+use digital_asset_types::dao::numeric::to_i64;
 use log::debug;
 use sea_orm::{ConnectionTrait, TransactionTrait};
 
@@ -27,10 +30,12 @@ where
         &parsing_result.tree_update,
         &parsing_result.payload,
     ) {
-        let (creator, verify) = match payload {
+        let (metadata, creator, verify) = match payload {
             Payload::CreatorVerification {
-                creator, verify, ..
-            } => (creator, verify),
+                metadata,
+                creator,
+                verify,
+            } => (metadata, creator, verify),
             _ => {
                 return Err(IngesterError::ParsingError(
                     "Ix not parsed correctly".to_string(),
@@ -69,7 +74,7 @@ where
                     le.leaf_hash.to_vec(),
                     le.schema.data_hash(),
                     le.schema.creator_hash(),
-                    seq as i64,
+                    seq,
                     false,
                 )
                 .await?;
@@ -80,24 +85,45 @@ where
                     id_bytes.to_vec(),
                     owner_bytes,
                     delegate,
-                    seq as i64,
+                    seq,
                 )
                 .await?;
 
-                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await?;
+                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await?;
 
                 id_bytes.to_vec()
             }
         };
 
-        upsert_creator_verified(
-            txn,
-            asset_id_bytes,
-            creator.to_bytes().to_vec(),
-            value,
-            seq as i64,
-        )
-        .await?;
+        if asset_is_minted(txn, asset_id_bytes.clone()).await? {
+            // The instruction carries the whole creator array it was executed against, so the
+            // stored creators are replaced by it rather than just flipping one flag. This drops
+            // creators that a metadata update removed since they were indexed. A stale array
+            // still sets this creator's flag, as the newer one may only be a single buffered
+            // verification rather than a full array.
+            let mut creators = metadata.creators.clone();
+            for c in creators.iter_mut() {
+                if c.address == *creator {
+                    c.verified = value;
+                }
+            }
+            let applied = upsert_asset_creators(
+                txn,
+                asset_id_bytes.clone(),
+                &creators,
+                to_i64(bundle.slot, "slot")?,
+                seq,
+            )
+            .await?;
+            if !applied {
+                let creator = creator.to_bytes().to_vec();
+                update_creator_verified(txn, asset_id_bytes, creator, value, seq).await?;
+            }
+        } else {
+            // The mint hasn't been indexed yet, hold the update until it is.
+            let creator = creator.to_bytes().to_vec();
+            buffer_creator_verification(txn, asset_id_bytes, creator, value, seq).await?;
+        }
 
         return Ok(());
     }
@@ -1,13 +1,51 @@
 use crate::error::IngesterError;
 use digital_asset_types::dao::{
-    asset, asset_creators, asset_grouping, backfill_items, cl_audits, cl_items,
+    asset, asset_creators, asset_grouping, backfill_items, cl_audits, cl_items, numeric::to_i64,
+    COLLECTION_GROUP_KEY,
 };
+use lazy_static::lazy_static;
 use log::{debug, error, info};
-use mpl_bubblegum::state::metaplex_adapter::Collection;
+use mpl_bubblegum::state::metaplex_adapter::{Collection, Creator};
 use sea_orm::{
-    query::*, sea_query::OnConflict, ActiveValue::Set, ColumnTrait, DbBackend, EntityTrait,
+    query::*,
+    sea_query::{Expr, OnConflict},
+    ActiveValue::Set,
+    ColumnTrait, DbBackend, EntityTrait,
 };
 use spl_account_compression::events::ChangeLogEventV1;
+use std::{cell::RefCell, collections::HashSet, future::Future, sync::Mutex};
+
+// Postgres' error for a row without a partition to go to.
+const MISSING_PARTITION: &str = "no partition of relation";
+
+lazy_static! {
+    // Trees whose `cl_items` and `cl_audits` partitions this process has already created.
+    static ref PARTITIONED_TREES: Mutex<HashSet<Vec<u8>>> = Mutex::new(HashSet::new());
+}
+
+tokio::task_local! {
+    // The partitions created by writes that may still be rolled back.
+    static UNCOMMITTED_PARTITIONS: RefCell<HashSet<Vec<u8>>>;
+}
+
+/// Runs `future`, whose writes are rolled back, without caching the partitions it creates for
+/// the rest of the process. They are rolled back with the writes.
+pub async fn without_partition_cache<F: Future>(future: F) -> F::Output {
+    UNCOMMITTED_PARTITIONS
+        .scope(RefCell::new(HashSet::new()), future)
+        .await
+}
+
+/// Whether the current task runs under [without_partition_cache], so its writes are rolled back
+/// and shouldn't be cached either.
+pub fn writes_may_roll_back() -> bool {
+    UNCOMMITTED_PARTITIONS.try_with(|_| ()).is_ok()
+}
+
+/// Forgets that the partitions of `tree` were created, for when they were dropped.
+pub fn forget_tree_partitions(tree: &[u8]) {
+    PARTITIONED_TREES.lock().unwrap().remove(tree);
+}
 
 pub async fn save_changelog_event<'c, T>(
     change_log_event: &ChangeLogEventV1,
@@ -15,18 +53,91 @@ pub async fn save_changelog_event<'c, T>(
     txn_id: &str,
     txn: &T,
     instruction: &str,
-) -> Result<u64, IngesterError>
+) -> Result<i64, IngesterError>
 where
     T: ConnectionTrait + TransactionTrait,
 {
     insert_change_log(change_log_event, slot, txn_id, txn, instruction).await?;
-    Ok(change_log_event.seq)
+    Ok(to_i64(change_log_event.seq, "seq")?)
+}
+
+/// Creates the `cl_items` and `cl_audits` partitions of `tree` on its first write. A partition
+/// dropped while the process runs, e.g. by `purge-tree`, stays cached until a write to it fails,
+/// see [insert_change_log].
+pub async fn ensure_tree_partitions<T>(txn: &T, tree: &[u8]) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let uncommitted = UNCOMMITTED_PARTITIONS
+        .try_with(|trees| trees.borrow().contains(tree))
+        .ok();
+    if uncommitted == Some(true) || PARTITIONED_TREES.lock().unwrap().contains(tree) {
+        return Ok(());
+    }
+    for table in ["cl_items", "cl_audits"] {
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT create_tree_partition($1, $2)",
+            vec![table.into(), tree.to_vec().into()],
+        ))
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    }
+    match uncommitted {
+        Some(_) => UNCOMMITTED_PARTITIONS.with(|trees| trees.borrow_mut().insert(tree.to_vec())),
+        None => PARTITIONED_TREES.lock().unwrap().insert(tree.to_vec()),
+    };
+    Ok(())
+}
+
+/// Copies the nodes of the path of `leaf_node_idx` the change log of `seq` is about to overwrite
+/// to `cl_items_history`, so proofs at finalized commitment can still read them until the change
+/// log's `slot` is finalized. Nothing is kept when the finality tracker doesn't run and
+/// `finalized_slot` is empty.
+async fn save_unfinalized_nodes<T>(
+    txn: &T,
+    tree: &[u8],
+    leaf_node_idx: i64,
+    depth: i32,
+    seq: i64,
+    slot: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO cl_items_history (tree, node_idx, seq, slot, hash, replaced_slot)
+SELECT tree, node_idx, seq, slot, hash, $5
+FROM cl_items
+WHERE tree = $1
+    AND node_idx IN (SELECT $2 >> l FROM generate_series(0, $3) l)
+    AND seq < $4
+    AND $5 > (SELECT slot FROM finalized_slot)
+ON CONFLICT DO NOTHING
+",
+        vec![
+            tree.to_vec().into(),
+            leaf_node_idx.into(),
+            depth.into(),
+            seq.into(),
+            slot.into(),
+        ],
+    ))
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
 }
 
 fn node_idx_to_leaf_idx(index: i64, tree_height: u32) -> i64 {
     index - 2i64.pow(tree_height)
 }
 
+/// Writes the change log's path to `cl_items` and `cl_audits`. When the tree's cached partitions
+/// turn out to be gone, they are created again and the write is retried once. In a transaction
+/// the failed write already aborted it, and the retry only succeeds once the transaction is
+/// retried.
 pub async fn insert_change_log<'c, T>(
     change_log_event: &ChangeLogEventV1,
     slot: u64,
@@ -34,12 +145,41 @@ pub async fn insert_change_log<'c, T>(
     txn: &T,
     instruction: &str,
 ) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait + TransactionTrait,
+{
+    match write_change_log(change_log_event, slot, txn_id, txn, instruction).await {
+        Err(IngesterError::StorageWriteError(msg)) if msg.contains(MISSING_PARTITION) => {
+            let tree = change_log_event.id.as_ref();
+            info!(
+                "Partitions of tree {} are gone, creating them again",
+                bs58::encode(tree).into_string()
+            );
+            forget_tree_partitions(tree);
+            write_change_log(change_log_event, slot, txn_id, txn, instruction).await
+        }
+        result => result,
+    }
+}
+
+async fn write_change_log<T>(
+    change_log_event: &ChangeLogEventV1,
+    slot: u64,
+    txn_id: &str,
+    txn: &T,
+    instruction: &str,
+) -> Result<(), IngesterError>
 where
     T: ConnectionTrait + TransactionTrait,
 {
     let mut i: i64 = 0;
     let depth = change_log_event.path.len() - 1;
+    let (seq, slot) = (to_i64(change_log_event.seq, "seq")?, to_i64(slot, "slot")?);
     let tree_id = change_log_event.id.as_ref();
+    ensure_tree_partitions(txn, tree_id).await?;
+    if let Some(leaf) = change_log_event.path.first() {
+        save_unfinalized_nodes(txn, tree_id, leaf.index as i64, depth as i32, seq, slot).await?;
+    }
     for p in change_log_event.path.iter() {
         let node_idx = p.index as i64;
         debug!(
@@ -62,8 +202,9 @@ where
             level: Set(i),
             node_idx: Set(node_idx),
             hash: Set(p.node.as_ref().to_vec()),
-            seq: Set(change_log_event.seq as i64),
+            seq: Set(seq),
             leaf_idx: Set(leaf_idx),
+            slot: Set(Some(slot)),
             ..Default::default()
         };
 
@@ -80,6 +221,7 @@ where
                         cl_items::Column::Seq,
                         cl_items::Column::LeafIdx,
                         cl_items::Column::Level,
+                        cl_items::Column::Slot,
                     ])
                     .to_owned(),
             )
@@ -224,6 +366,7 @@ where
         creator_hash: Set(None),
         tree_id: Set(None),
         seq: Set(Some(0)),
+        leaf_delegate: Set(None),
         ..Default::default()
     };
     let query = asset::Entity::insert(model)
@@ -237,6 +380,7 @@ where
                     asset::Column::CreatorHash,
                     asset::Column::TreeId,
                     asset::Column::Seq,
+                    asset::Column::LeafDelegate,
                 ])
                 .to_owned(),
         )
@@ -261,7 +405,8 @@ where
     let model = asset::ActiveModel {
         id: Set(id),
         owner: Set(Some(owner)),
-        delegate: Set(delegate),
+        delegate: Set(delegate.clone()),
+        leaf_delegate: Set(delegate),
         owner_delegate_seq: Set(Some(seq)), // gummyroll seq
         ..Default::default()
     };
@@ -272,6 +417,7 @@ where
                 .update_columns([
                     asset::Column::Owner,
                     asset::Column::Delegate,
+                    asset::Column::LeafDelegate,
                     asset::Column::OwnerDelegateSeq,
                 ])
                 .to_owned(),
@@ -363,40 +509,101 @@ where
     Ok(())
 }
 
-pub async fn upsert_creator_verified<T>(
+/// Replaces the creators of `asset_id` with `creators`, the full creator array an instruction
+/// was executed against. Rows of creators no longer in the array are removed.
+///
+/// Every write stamps `seq` on all of the asset's creator rows, so the highest `seq` among them
+/// is the last array that was applied. An older array is ignored as a whole: the newer one
+/// already reflects everything that happened before it. Callers hold the `asset` row lock
+/// taken by the preceding `asset` upserts, which serializes this per asset. Returns whether the
+/// array was applied.
+pub async fn upsert_asset_creators<T>(
     txn: &T,
     asset_id: Vec<u8>,
-    creator: Vec<u8>,
-    verified: bool,
+    creators: &[Creator],
+    slot_updated: i64,
     seq: i64,
-) -> Result<(), IngesterError>
+) -> Result<bool, IngesterError>
 where
     T: ConnectionTrait + TransactionTrait,
 {
-    let model = asset_creators::ActiveModel {
-        asset_id: Set(asset_id),
-        creator: Set(creator),
-        verified: Set(verified),
-        seq: Set(Some(seq)),
-        ..Default::default()
-    };
+    let latest = asset_creators::Entity::find()
+        .filter(asset_creators::Column::AssetId.eq(asset_id.clone()))
+        .filter(asset_creators::Column::Seq.is_not_null())
+        .order_by_desc(asset_creators::Column::Seq)
+        .one(txn)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if let Some(latest_seq) = latest.and_then(|c| c.seq) {
+        if seq < latest_seq {
+            debug!(
+                "Skipping creators of asset {} at seq {}, already at seq {}",
+                bs58::encode(&asset_id).into_string(),
+                seq,
+                latest_seq
+            );
+            return Ok(false);
+        }
+    }
+
+    let mut models = Vec::with_capacity(creators.len());
+    let mut creators_set = HashSet::new();
+    for (i, c) in creators.iter().enumerate() {
+        if !creators_set.insert(c.address) {
+            continue;
+        }
+        models.push(asset_creators::ActiveModel {
+            asset_id: Set(asset_id.clone()),
+            creator: Set(c.address.to_bytes().to_vec()),
+            position: Set(i as i16),
+            share: Set(c.share as i32),
+            verified: Set(c.verified),
+            seq: Set(Some(seq)),
+            slot_updated: Set(Some(slot_updated)),
+            ..Default::default()
+        });
+    }
+
+    let present: Vec<Vec<u8>> = creators_set
+        .iter()
+        .map(|address| address.to_bytes().to_vec())
+        .collect();
+    let removed = asset_creators::Entity::delete_many()
+        .filter(asset_creators::Column::AssetId.eq(asset_id.clone()))
+        .filter(asset_creators::Column::Creator.is_not_in(present))
+        .exec(txn)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    if removed.rows_affected > 0 {
+        debug!(
+            "Removed {} stale creators of asset {}",
+            removed.rows_affected,
+            bs58::encode(&asset_id).into_string()
+        );
+    }
 
-    let mut query = asset_creators::Entity::insert(model)
+    if models.is_empty() {
+        return Ok(true);
+    }
+
+    let mut query = asset_creators::Entity::insert_many(models)
         .on_conflict(
             OnConflict::columns([
                 asset_creators::Column::AssetId,
                 asset_creators::Column::Creator,
             ])
             .update_columns([
+                asset_creators::Column::Position,
+                asset_creators::Column::Share,
                 asset_creators::Column::Verified,
                 asset_creators::Column::Seq,
+                asset_creators::Column::SlotUpdated,
             ])
             .to_owned(),
         )
         .build(DbBackend::Postgres);
-
     query.sql = format!(
-        "{} WHERE excluded.seq >= asset_creators.seq OR asset_creators.seq is NULL",
+        "{} WHERE excluded.seq >= asset_creators.seq OR asset_creators.seq IS NULL",
         query.sql
     );
 
@@ -404,6 +611,35 @@ where
         .await
         .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
 
+    Ok(true)
+}
+
+/// Sets whether `creator` verified `asset_id`, unless a later `seq` already wrote its row. Only
+/// updates existing rows, so a late instruction can't bring back a creator removed since.
+pub async fn update_creator_verified<T>(
+    txn: &T,
+    asset_id: Vec<u8>,
+    creator: Vec<u8>,
+    verified: bool,
+    seq: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait + TransactionTrait,
+{
+    asset_creators::Entity::update_many()
+        .col_expr(asset_creators::Column::Verified, Expr::value(verified))
+        .col_expr(asset_creators::Column::Seq, Expr::value(seq))
+        .filter(asset_creators::Column::AssetId.eq(asset_id))
+        .filter(asset_creators::Column::Creator.eq(creator))
+        .filter(
+            Condition::any()
+                .add(asset_creators::Column::Seq.lte(seq))
+                .add(asset_creators::Column::Seq.is_null()),
+        )
+        .exec(txn)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+
     Ok(())
 }
 
@@ -424,7 +660,7 @@ where
 
     let model = asset_grouping::ActiveModel {
         asset_id: Set(asset_id),
-        group_key: Set("collection".to_string()),
+        group_key: Set(COLLECTION_GROUP_KEY.to_string()),
         group_value: Set(group_value),
         verified: Set(Some(verified)),
         slot_updated: Set(Some(slot_updated)),
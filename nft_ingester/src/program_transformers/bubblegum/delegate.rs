@@ -1,5 +1,6 @@
 use crate::{
     error::IngesterError,
+    owner_history::record_delegate_change,
     program_transformers::bubblegum::{
         save_changelog_event, upsert_asset_with_leaf_info,
         upsert_asset_with_owner_and_delegate_info, upsert_asset_with_seq,
@@ -9,6 +10,7 @@ use blockbuster::{
     instruction::InstructionBundle,
     programs::bubblegum::{BubblegumInstruction, LeafSchema},
 };
+use digital_asset_types::dao::numeric::to_i64;
 use sea_orm::{ConnectionTrait, TransactionTrait};
 
 pub async fn delegate<'c, T>(
@@ -47,7 +49,7 @@ where
                     le.leaf_hash.to_vec(),
                     le.schema.data_hash(),
                     le.schema.creator_hash(),
-                    seq as i64,
+                    seq,
                     false,
                 )
                 .await?;
@@ -57,12 +59,20 @@ where
                     txn,
                     id_bytes.to_vec(),
                     owner_bytes,
+                    delegate.clone(),
+                    seq,
+                )
+                .await?;
+                record_delegate_change(
+                    txn,
+                    id_bytes.to_vec(),
                     delegate,
-                    seq as i64,
+                    to_i64(bundle.slot, "slot")?,
+                    seq,
                 )
                 .await?;
 
-                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await
+                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await
             }
         };
     }
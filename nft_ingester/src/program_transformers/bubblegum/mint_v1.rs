@@ -1,8 +1,14 @@
 use crate::{
+    authority::record_authority_change,
+    collection_stats::{record_collection_join, verified_collection},
+    data_history::{record_data_change, DataVersion},
     error::IngesterError,
+    owner_history::{record_delegate_change, record_owner_change},
+    pending::apply_pending_creator_verifications,
     program_transformers::bubblegum::{
-        save_changelog_event, upsert_asset_with_compression_info, upsert_asset_with_leaf_info,
-        upsert_asset_with_owner_and_delegate_info, upsert_asset_with_seq, upsert_collection_info,
+        save_changelog_event, upsert_asset_creators, upsert_asset_with_compression_info,
+        upsert_asset_with_leaf_info, upsert_asset_with_owner_and_delegate_info,
+        upsert_asset_with_seq, upsert_collection_info,
     },
     tasks::{DownloadMetadata, IntoTaskData, TaskData},
 };
@@ -17,7 +23,8 @@ use blockbuster::{
 use chrono::Utc;
 use digital_asset_types::{
     dao::{
-        asset, asset_authority, asset_creators, asset_data, asset_v1_account_attachments,
+        asset, asset_authority, asset_data, asset_v1_account_attachments,
+        numeric::to_i64,
         sea_orm_active_enums::{ChainMutability, Mutability, OwnerType, RoyaltyTargetType},
     },
     json::ChainDataV1,
@@ -27,7 +34,6 @@ use num_traits::FromPrimitive;
 use sea_orm::{
     entity::*, query::*, sea_query::OnConflict, ConnectionTrait, DbBackend, EntityTrait, JsonValue,
 };
-use std::collections::HashSet;
 
 use digital_asset_types::dao::sea_orm_active_enums::{
     SpecificationAssetClass, SpecificationVersions, V1AccountAttachments,
@@ -63,7 +69,8 @@ where
             } => {
                 let (edition_attachment_address, _) = find_master_edition_account(&id);
                 let id_bytes = id.to_bytes();
-                let slot_i = bundle.slot as i64;
+                let slot_i = to_i64(bundle.slot, "slot")?;
+                let nonce = to_i64(nonce, "nonce")?;
                 let uri = metadata.uri.trim().replace('\0', "");
                 let name = metadata.name.clone().into_bytes();
                 let symbol = metadata.symbol.clone().into_bytes();
@@ -80,6 +87,12 @@ where
                     }),
                 };
                 chain_data.sanitize();
+                let version = DataVersion {
+                    name: chain_data.name.clone(),
+                    symbol: chain_data.symbol.clone(),
+                    metadata_url: uri.clone(),
+                    slot: slot_i,
+                };
                 let chain_data_json = serde_json::to_value(chain_data)
                     .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
                 let chain_mutability = match metadata.is_mutable {
@@ -126,6 +139,7 @@ where
                 txn.execute(query)
                     .await
                     .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+                record_data_change(txn, id_bytes.to_vec(), version).await?;
                 // Insert into `asset` table.
                 let delegate = if owner == delegate || delegate.to_bytes() == [0; 32] {
                     None
@@ -142,7 +156,7 @@ where
                     tree_id: Set(Some(tree_id.clone())),
                     specification_version: Set(Some(SpecificationVersions::V1)),
                     specification_asset_class: Set(Some(SpecificationAssetClass::Nft)),
-                    nonce: Set(Some(nonce as i64)),
+                    nonce: Set(Some(nonce)),
                     royalty_target_type: Set(RoyaltyTargetType::Creators),
                     royalty_target: Set(None),
                     royalty_amount: Set(metadata.seller_fee_basis_points as i32), //basis points
@@ -194,12 +208,12 @@ where
                 upsert_asset_with_leaf_info(
                     txn,
                     id_bytes.to_vec(),
-                    nonce as i64,
+                    nonce,
                     tree_id,
                     le.leaf_hash.to_vec(),
                     le.schema.data_hash(),
                     le.schema.creator_hash(),
-                    seq as i64,
+                    seq,
                     false,
                 )
                 .await?;
@@ -209,12 +223,21 @@ where
                     txn,
                     id_bytes.to_vec(),
                     owner.to_bytes().to_vec(),
-                    delegate,
-                    seq as i64,
+                    delegate.clone(),
+                    seq,
+                )
+                .await?;
+                record_owner_change(
+                    txn,
+                    id_bytes.to_vec(),
+                    owner.to_bytes().to_vec(),
+                    slot_i,
+                    seq,
                 )
                 .await?;
+                record_delegate_change(txn, id_bytes.to_vec(), delegate, slot_i, seq).await?;
 
-                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await?;
+                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await?;
 
                 let attachment = asset_v1_account_attachments::ActiveModel {
                     id: Set(edition_attachment_address.to_bytes().to_vec()),
@@ -237,84 +260,19 @@ where
                 // Insert into `asset_creators` table.
                 let creators = &metadata.creators;
                 if !creators.is_empty() {
-                    // Vec to hold base creator information.
-                    let mut db_creator_infos = Vec::with_capacity(creators.len());
-
-                    // Vec to hold info on whether a creator is verified.  This info is protected by `seq` number.
-                    let mut db_creator_verified_infos = Vec::with_capacity(creators.len());
-
-                    // Set to prevent duplicates.
-                    let mut creators_set = HashSet::new();
-
-                    for (i, c) in creators.iter().enumerate() {
-                        if creators_set.contains(&c.address) {
-                            continue;
-                        }
-                        db_creator_infos.push(asset_creators::ActiveModel {
-                            asset_id: Set(id_bytes.to_vec()),
-                            creator: Set(c.address.to_bytes().to_vec()),
-                            position: Set(i as i16),
-                            share: Set(c.share as i32),
-                            slot_updated: Set(Some(slot_i)),
-                            ..Default::default()
-                        });
-
-                        db_creator_verified_infos.push(asset_creators::ActiveModel {
-                            asset_id: Set(id_bytes.to_vec()),
-                            creator: Set(c.address.to_bytes().to_vec()),
-                            verified: Set(c.verified),
-                            seq: Set(Some(seq as i64)),
-                            ..Default::default()
-                        });
-
-                        creators_set.insert(c.address);
-                    }
-
-                    // This statement will update base information for each creator.
-                    let query = asset_creators::Entity::insert_many(db_creator_infos)
-                        .on_conflict(
-                            OnConflict::columns([
-                                asset_creators::Column::AssetId,
-                                asset_creators::Column::Creator,
-                            ])
-                            .update_columns([
-                                asset_creators::Column::Position,
-                                asset_creators::Column::Share,
-                                asset_creators::Column::SlotUpdated,
-                            ])
-                            .to_owned(),
-                        )
-                        .build(DbBackend::Postgres);
-                    txn.execute(query).await?;
+                    // `seq` protects the creator rows, allowing for `mint` and `verifyCreator`
+                    // to be processed out of order.
+                    upsert_asset_creators(txn, id_bytes.to_vec(), creators, slot_i, seq).await?;
 
-                    // This statement will update whether the creator is verified and the `seq`
-                    // number.  `seq` is used to protect the `verified` field, allowing for `mint`
-                    // and `verifyCreator` to be processed out of order.
-                    let mut query = asset_creators::Entity::insert_many(db_creator_verified_infos)
-                        .on_conflict(
-                            OnConflict::columns([
-                                asset_creators::Column::AssetId,
-                                asset_creators::Column::Creator,
-                            ])
-                            .update_columns([
-                                asset_creators::Column::Verified,
-                                asset_creators::Column::Seq,
-                            ])
-                            .to_owned(),
-                        )
-                        .build(DbBackend::Postgres);
-                    query.sql = format!(
-                        "{} WHERE excluded.seq > asset_creators.seq OR asset_creators.seq IS NULL",
-                        query.sql
-                    );
-                    txn.execute(query).await?;
+                    // Apply creator verifications that were processed before this mint.
+                    apply_pending_creator_verifications(txn, Some(id_bytes.to_vec())).await?;
                 }
 
                 // Insert into `asset_authority` table.
                 let model = asset_authority::ActiveModel {
                     asset_id: Set(id_bytes.to_vec()),
                     authority: Set(bundle.keys.get(0).unwrap().0.to_vec()), //TODO - we need to rem,ove the optional bubblegum signer logic
-                    seq: Set(seq as i64),
+                    seq: Set(seq),
                     slot_updated: Set(slot_i),
                     ..Default::default()
                 };
@@ -331,16 +289,26 @@ where
                 txn.execute(query)
                     .await
                     .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+                record_authority_change(
+                    txn,
+                    id_bytes.to_vec(),
+                    bundle.keys.get(0).unwrap().0.to_vec(),
+                    slot_i,
+                    seq,
+                )
+                .await?;
 
                 // Upsert into `asset_grouping` table with base collection info.
+                let before = verified_collection(txn, id_bytes.to_vec()).await?;
                 upsert_collection_info(
                     txn,
                     id_bytes.to_vec(),
                     metadata.collection.clone(),
                     slot_i,
-                    seq as i64,
+                    seq,
                 )
                 .await?;
+                record_collection_join(txn, id_bytes.to_vec(), before, slot_i).await?;
 
                 let mut task = DownloadMetadata {
                     asset_data_id: id_bytes.to_vec(),
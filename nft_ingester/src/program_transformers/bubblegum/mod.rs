@@ -1,7 +1,8 @@
+use anchor_lang::prelude::Pubkey;
 use blockbuster::{
     self,
     instruction::InstructionBundle,
-    programs::bubblegum::{BubblegumInstruction, InstructionName},
+    programs::bubblegum::{BubblegumInstruction, InstructionName, LeafSchema},
 };
 use log::{debug, info};
 use sea_orm::{ConnectionTrait, TransactionTrait};
@@ -10,6 +11,7 @@ use tokio::sync::mpsc::UnboundedSender;
 mod burn;
 mod cancel_redeem;
 mod collection_verification;
+mod create_tree;
 mod creator_verification;
 mod db;
 mod decompress;
@@ -20,7 +22,7 @@ mod transfer;
 
 pub use db::*;
 
-use crate::{error::IngesterError, tasks::TaskData};
+use crate::{asset_changes::ChangeType, error::IngesterError, tasks::TaskData};
 
 pub async fn handle_bubblegum_instruction<'c, T>(
     parsing_result: &'c BubblegumInstruction,
@@ -79,6 +81,9 @@ where
         InstructionName::DecompressV1 => {
             decompress::decompress(parsing_result, bundle, txn).await?;
         }
+        InstructionName::CreateTree => {
+            create_tree::create_tree(bundle, txn).await?;
+        }
         InstructionName::VerifyCreator => {
             creator_verification::process(parsing_result, bundle, txn, true, ix_str).await?;
         }
@@ -95,6 +100,51 @@ where
     Ok(())
 }
 
+/// The asset an instruction changed and how, for change notifications.
+pub fn asset_change(
+    parsing_result: &BubblegumInstruction,
+    bundle: &InstructionBundle,
+) -> Option<(Pubkey, ChangeType)> {
+    let change = match parsing_result.instruction {
+        InstructionName::MintV1 | InstructionName::MintToCollectionV1 => ChangeType::Mint,
+        InstructionName::Transfer => ChangeType::Transfer,
+        InstructionName::Burn => ChangeType::Burn,
+        InstructionName::Delegate => ChangeType::Delegate,
+        InstructionName::Redeem | InstructionName::CancelRedeem | InstructionName::DecompressV1 => {
+            ChangeType::Compression
+        }
+        InstructionName::VerifyCreator
+        | InstructionName::UnverifyCreator
+        | InstructionName::VerifyCollection
+        | InstructionName::UnverifyCollection
+        | InstructionName::SetAndVerifyCollection => ChangeType::Verification,
+        _ => return None,
+    };
+    // A decompressed asset is its mint, the leaf of a burnt or redeemed one is gone so its id is
+    // derived from the tree and leaf index.
+    if let InstructionName::DecompressV1 = parsing_result.instruction {
+        return Some((Pubkey::new(bundle.keys.get(3)?.0.as_slice()), change));
+    }
+    let id = match (&parsing_result.leaf_update, &parsing_result.tree_update) {
+        (Some(le), _) => match le.schema {
+            LeafSchema::V1 { id, .. } => id,
+        },
+        (None, Some(cl)) => {
+            Pubkey::find_program_address(
+                &[
+                    "asset".as_bytes(),
+                    cl.id.as_ref(),
+                    u32_to_u8_array(cl.index).as_ref(),
+                ],
+                &mpl_bubblegum::ID,
+            )
+            .0
+        }
+        (None, None) => return None,
+    };
+    Some((id, change))
+}
+
 // PDA lookup requires an 8-byte array.
 fn u32_to_u8_array(value: u32) -> [u8; 8] {
     let bytes: [u8; 4] = value.to_le_bytes();
@@ -44,12 +44,12 @@ where
             vec![0; 32],
             [0; 32],
             [0; 32],
-            seq as i64,
+            seq,
             false,
         )
         .await?;
 
-        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await?;
+        upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await?;
 
         return Ok(());
     }
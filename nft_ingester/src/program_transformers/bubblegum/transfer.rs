@@ -1,6 +1,8 @@
 use super::save_changelog_event;
 use crate::{
+    collection_stats::{record_collection_activity, CollectionActivity},
     error::IngesterError,
+    owner_history::{record_delegate_change, record_owner_change},
     program_transformers::bubblegum::{
         upsert_asset_with_leaf_info, upsert_asset_with_owner_and_delegate_info,
         upsert_asset_with_seq,
@@ -10,6 +12,7 @@ use blockbuster::{
     instruction::InstructionBundle,
     programs::bubblegum::{BubblegumInstruction, LeafSchema},
 };
+use digital_asset_types::dao::numeric::to_i64;
 use sea_orm::{ConnectionTrait, TransactionTrait};
 
 pub async fn transfer<'c, T>(
@@ -49,7 +52,7 @@ where
                     le.leaf_hash.to_vec(),
                     le.schema.data_hash(),
                     le.schema.creator_hash(),
-                    seq as i64,
+                    seq,
                     false,
                 )
                 .await?;
@@ -58,13 +61,24 @@ where
                 upsert_asset_with_owner_and_delegate_info(
                     txn,
                     id_bytes.to_vec(),
-                    owner_bytes,
-                    delegate,
-                    seq as i64,
+                    owner_bytes.clone(),
+                    delegate.clone(),
+                    seq,
                 )
                 .await?;
+                let slot = to_i64(bundle.slot, "slot")?;
+                record_delegate_change(txn, id_bytes.to_vec(), delegate, slot, seq).await?;
+                if record_owner_change(txn, id_bytes.to_vec(), owner_bytes, slot, seq).await? {
+                    record_collection_activity(
+                        txn,
+                        id_bytes.to_vec(),
+                        CollectionActivity::Transfer,
+                        slot,
+                    )
+                    .await?;
+                }
 
-                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq as i64).await
+                upsert_asset_with_seq(txn, id_bytes.to_vec(), seq).await
             }
         };
     }
@@ -1,4 +1,11 @@
-use crate::{error::IngesterError, tasks::TaskData};
+use crate::{
+    asset_changes::ChangeNotifier,
+    error::IngesterError,
+    failed_transactions::{record_failed_transaction, transaction_error},
+    merkle_tree::{handle_tree_account, handle_tree_config_account},
+    metric,
+    tasks::TaskData,
+};
 use blockbuster::{
     instruction::{order_instructions, InstructionBundle, IxPair},
     program_handler::ProgramParser,
@@ -7,28 +14,57 @@ use blockbuster::{
         token_metadata::TokenMetadataParser, ProgramParseResult,
     },
 };
+use borsh::BorshDeserialize;
+use cadence_macros::{is_global_default_set, statsd_count};
 use log::{debug, error, info};
 use plerkle_serialization::{AccountInfo, Pubkey as FBPubkey, TransactionInfo};
-use sea_orm::{DatabaseConnection, SqlxPostgresConnector};
+use sea_orm::{ConnectionTrait, DatabaseConnection, SqlxPostgresConnector, TransactionTrait};
 use solana_sdk::pubkey::Pubkey;
+use spl_account_compression::{AccountCompressionEvent, ChangeLogEvent};
 use sqlx::PgPool;
 use std::collections::{HashMap, HashSet, VecDeque};
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::program_transformers::{
-    bubblegum::handle_bubblegum_instruction, token::handle_token_program_account,
+    assertion::record,
+    bubblegum::{asset_change, handle_bubblegum_instruction},
+    token::handle_token_program_account,
     token_metadata::handle_token_metadata_account,
 };
 
+pub use assertion::{Assertion, Transformer, TransformerAssertionConfig};
+pub use bubblegum::{
+    ensure_tree_partitions, forget_tree_partitions, upsert_asset_creators, without_partition_cache,
+    writes_may_roll_back,
+};
+pub use program_groups::{
+    upsert_program_groupings, ProgramGroupConfig, ProgramGrouping, ProgramGroups, MPL_CORE_PROGRAM,
+};
+
+mod assertion;
 mod bubblegum;
+mod program_groups;
 mod token;
 mod token_metadata;
 
+/// The programs whose instructions and accounts are indexed.
+pub fn indexed_programs() -> [Pubkey; 3] {
+    [
+        BubblegumParser {}.key(),
+        TokenMetadataParser {}.key(),
+        TokenAccountParser {}.key(),
+    ]
+}
+
 pub struct ProgramTransformer {
     storage: DatabaseConnection,
     task_sender: UnboundedSender<TaskData>,
+    changes: ChangeNotifier,
+    record_failed_transactions: bool,
     matchers: HashMap<Pubkey, Box<dyn ProgramParser>>,
     key_set: HashSet<Pubkey>,
+    assertion: Option<Assertion>,
+    groups: ProgramGroups,
 }
 
 impl ProgramTransformer {
@@ -48,11 +84,51 @@ impl ProgramTransformer {
         ProgramTransformer {
             storage: SqlxPostgresConnector::from_sqlx_postgres_pool(pool),
             task_sender,
+            changes: ChangeNotifier::default(),
+            record_failed_transactions: false,
             matchers,
             key_set: hs,
+            assertion: None,
+            groups: ProgramGroups::default(),
         }
     }
 
+    /// Publishes the changes of the handled updates once they are committed.
+    pub fn with_change_notifier(mut self, changes: ChangeNotifier) -> Self {
+        self.changes = changes;
+        self
+    }
+
+    /// Records failed transactions touching known trees in `failed_tx_log`. They never change
+    /// any asset either way.
+    pub fn with_failed_transaction_log(mut self, record: bool) -> Self {
+        self.record_failed_transactions = record;
+        self
+    }
+
+    /// Runs the assertion's candidate transformer on the sampled updates and logs how its writes
+    /// differ from the ones of this transformer, which are the only ones kept.
+    pub fn with_assertion(mut self, assertion: Option<Assertion>) -> Self {
+        if assertion.is_some() {
+            self.storage.set_metric_callback(assertion::record_mutation);
+        }
+        self.assertion = assertion;
+        self
+    }
+
+    /// Groups the assets named by the accounts of the configured programs, besides the MPL Core
+    /// collections.
+    pub fn with_program_groups(mut self, groups: ProgramGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    fn sampled_assertion(&self) -> Option<&Assertion> {
+        self.assertion
+            .as_ref()
+            .filter(|assertion| assertion.sampled())
+    }
+
     pub fn break_transaction<'i>(
         &self,
         tx: &'i TransactionInfo<'i>,
@@ -65,10 +141,68 @@ impl ProgramTransformer {
         self.matchers.get(&Pubkey::new(key.0.as_slice()))
     }
 
+    /// The trees the transaction's Bubblegum instructions change, with the seq of each change,
+    /// in instruction order. Failed transactions change none. Only the change log events the
+    /// instructions logged through the noop program are read, the instructions themselves are
+    /// parsed once, when the transaction is transformed.
+    pub fn tree_updates(&self, tx: &TransactionInfo) -> Vec<(Pubkey, u64)> {
+        let bubblegum = mpl_bubblegum::id().to_bytes();
+        let calls_bubblegum = tx
+            .account_keys()
+            .map_or(false, |keys| keys.iter().any(|key| key.0 == bubblegum));
+        if !calls_bubblegum || transaction_error(tx).is_some() {
+            return vec![];
+        }
+        let noop = spl_noop::id().to_bytes();
+        let mut updates = vec![];
+        for ((program, _), inner_ix) in self.break_transaction(tx) {
+            if program.0 != bubblegum {
+                continue;
+            }
+            for (inner_program, inner) in inner_ix.iter().flatten() {
+                if inner_program.0 != noop {
+                    continue;
+                }
+                let data = inner.data().map(|data| data.bytes()).unwrap_or_default();
+                if let Ok(AccountCompressionEvent::ChangeLog(ChangeLogEvent::V1(cl))) =
+                    AccountCompressionEvent::try_from_slice(data)
+                {
+                    updates.push((Pubkey::new_from_array(cl.id.to_bytes()), cl.seq));
+                }
+            }
+        }
+        updates
+    }
+
     pub async fn handle_transaction<'a>(
         &self,
         tx: &'a TransactionInfo<'a>,
     ) -> Result<(), IngesterError> {
+        let assertion = match self.sampled_assertion() {
+            Some(assertion) => assertion,
+            None => return self.transform_transaction(&self.storage, tx).await,
+        };
+        // The candidate runs first so both read the same rows.
+        let txn = self.storage.begin().await?;
+        let candidate = record(without_partition_cache(
+            assertion.candidate().transaction(&txn, tx),
+        ))
+        .await;
+        txn.rollback().await?;
+        let current = record(self.transform_transaction(&self.storage, tx)).await;
+        let id = tx.signature().unwrap_or_default();
+        assertion.compare("transaction", id, &current, &candidate);
+        current.0
+    }
+
+    pub async fn transform_transaction<'a, T>(
+        &self,
+        conn: &T,
+        tx: &'a TransactionInfo<'a>,
+    ) -> Result<(), IngesterError>
+    where
+        T: ConnectionTrait + TransactionTrait,
+    {
         let sig: Option<&str> = tx.signature();
         info!("Handling Transaction: {:?}", sig);
         let instructions = self.break_transaction(&tx);
@@ -79,6 +213,17 @@ impl ProgramTransformer {
         for k in accounts.into_iter() {
             keys.push(*k);
         }
+        if let Some(error) = transaction_error(tx) {
+            debug!("Skipping failed txn {:?}: {}", sig, error);
+            metric! {
+                statsd_count!("ingester.failed_tx.skipped", 1);
+            }
+            if self.record_failed_transactions {
+                let keys: Vec<Vec<u8>> = keys.iter().map(|k| k.0.to_vec()).collect();
+                record_failed_transaction(conn, txn_id, &keys, &error, slot).await?;
+            }
+            return Ok(());
+        }
         let mut not_impl = 0;
         let ixlen = instructions.len();
         debug!("Instructions: {}", ixlen);
@@ -120,20 +265,20 @@ impl ProgramTransformer {
                 let concrete = result.result_type();
                 match concrete {
                     ProgramParseResult::Bubblegum(parsing_result) => {
-                        handle_bubblegum_instruction(
-                            parsing_result,
-                            &ix,
-                            &self.storage,
-                            &self.task_sender,
-                        )
-                        .await
-                        .map_err(|err| {
-                            error!(
-                                "Failed to handle bubblegum instruction for txn {:?}: {:?}",
-                                sig, err
-                            );
-                            return err;
-                        })?;
+                        handle_bubblegum_instruction(parsing_result, &ix, conn, &self.task_sender)
+                            .await
+                            .map_err(|err| {
+                                error!(
+                                    "Failed to handle bubblegum instruction for txn {:?}: {:?}",
+                                    sig, err
+                                );
+                                return err;
+                            })?;
+                        // A replay of the instruction exports the change again.
+                        if let Some((id, change)) = asset_change(parsing_result, &ix) {
+                            self.changes.export(conn, id.as_ref(), change, slot).await?;
+                            self.changes.notify(id.as_ref(), change, slot);
+                        }
                     }
                     _ => {
                         not_impl += 1;
@@ -153,7 +298,50 @@ impl ProgramTransformer {
         &self,
         acct: AccountInfo<'b>,
     ) -> Result<(), IngesterError> {
+        let assertion = match self.sampled_assertion() {
+            Some(assertion) => assertion,
+            None => return self.transform_account_update(&self.storage, acct).await,
+        };
+        let txn = self.storage.begin().await?;
+        let candidate = record(without_partition_cache(
+            assertion.candidate().account_update(&txn, acct),
+        ))
+        .await;
+        txn.rollback().await?;
+        let current = record(self.transform_account_update(&self.storage, acct)).await;
+        let id = acct
+            .pubkey()
+            .map(|key| bs58::encode(key.0).into_string())
+            .unwrap_or_default();
+        assertion.compare("account", &id, &current, &candidate);
+        current.0
+    }
+
+    pub async fn transform_account_update<'b, T>(
+        &self,
+        conn: &T,
+        acct: AccountInfo<'b>,
+    ) -> Result<(), IngesterError>
+    where
+        T: ConnectionTrait + TransactionTrait,
+    {
         let owner = acct.owner().unwrap();
+        // Bubblegum accounts aren't parsed by blockbuster, its tree configs are read here.
+        if owner.0 == mpl_bubblegum::id().to_bytes() {
+            return handle_tree_config_account(&acct, conn).await;
+        }
+        // Nor are trees, whose shape and canopy are kept for proofs.
+        if owner.0 == spl_account_compression::id().to_bytes() {
+            return handle_tree_account(&acct, conn).await;
+        }
+        // Nor are MPL Core and the programs of custom groups, only their groupings are kept.
+        if let Some(address) = acct.pubkey() {
+            let program = Pubkey::new(owner.0.as_slice());
+            let data = acct.data().map(|d| d.bytes()).unwrap_or_default();
+            if let Some(groupings) = self.groups.groupings(&program, &address.0, data) {
+                return upsert_program_groupings(conn, &groupings, acct.slot()).await;
+            }
+        }
         if let Some(program) = self.match_program(owner) {
             let result = program.handle_account(&acct)?;
             let concrete = result.result_type();
@@ -162,8 +350,9 @@ impl ProgramTransformer {
                     handle_token_metadata_account(
                         &acct,
                         parsing_result,
-                        &self.storage,
+                        conn,
                         &self.task_sender,
+                        &self.changes,
                     )
                     .await
                 }
@@ -171,8 +360,9 @@ impl ProgramTransformer {
                     handle_token_program_account(
                         &acct,
                         parsing_result,
-                        &self.storage,
+                        conn,
                         &self.task_sender,
+                        &self.changes,
                     )
                     .await
                 }
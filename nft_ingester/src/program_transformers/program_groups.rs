@@ -0,0 +1,191 @@
+use crate::{error::IngesterError, program_transformers::indexed_programs};
+use digital_asset_types::dao::{
+    COLLECTION_GROUP_KEY, EDITION_PARENT_GROUP_KEY, MPL_CORE_COLLECTION_GROUP_KEY,
+};
+use log::warn;
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use serde::Deserialize;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::{collections::HashMap, str::FromStr};
+
+pub const MPL_CORE_PROGRAM: Pubkey = pubkey!("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+
+// The account keys of MPL Core, the first byte of its accounts.
+const MPL_CORE_UNINITIALIZED: u8 = 0;
+const MPL_CORE_ASSET_V1: u8 = 1;
+// The `UpdateAuthority` variant of an asset that is part of a collection.
+const MPL_CORE_UPDATE_AUTHORITY_COLLECTION: u8 = 2;
+// The update authority follows the key and the owner.
+const MPL_CORE_UPDATE_AUTHORITY_OFFSET: usize = 33;
+
+/// Groups the assets named by the accounts of a program, e.g.
+/// `'[{program="...", group_key="guild", discriminator=[2], asset_offset=1, value_offset=33}]'`.
+/// An account of `program` starting with `discriminator` groups the asset whose id is the key at
+/// `asset_offset` under `group_key`, valued with the key at `value_offset`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone)]
+pub struct ProgramGroupConfig {
+    pub program: String,
+    pub group_key: String,
+    #[serde(default)]
+    pub discriminator: Vec<u8>,
+    pub asset_offset: usize,
+    pub value_offset: usize,
+}
+
+/// The group an account puts an asset in, or takes it out of when `group_value` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramGrouping {
+    pub asset_id: Vec<u8>,
+    pub group_key: String,
+    pub group_value: Option<String>,
+}
+
+/// The groups read from the accounts of MPL Core and of the configured programs.
+#[derive(Debug, Clone, Default)]
+pub struct ProgramGroups {
+    programs: HashMap<Pubkey, Vec<ProgramGroupConfig>>,
+}
+
+impl ProgramGroups {
+    /// Skips the groups of invalid or already indexed programs, and the ones under the keys the
+    /// transformers write.
+    pub fn new(configs: &[ProgramGroupConfig]) -> Self {
+        let mut programs: HashMap<Pubkey, Vec<ProgramGroupConfig>> = HashMap::new();
+        for config in configs {
+            let program = match Pubkey::from_str(&config.program) {
+                Ok(program) => program,
+                Err(_) => {
+                    warn!(
+                        "Ignoring invalid program {} of group {}",
+                        config.program, config.group_key
+                    );
+                    continue;
+                }
+            };
+            if program == MPL_CORE_PROGRAM || indexed_programs().contains(&program) {
+                warn!(
+                    "Ignoring group {} of indexed program {}",
+                    config.group_key, program
+                );
+                continue;
+            }
+            if [
+                COLLECTION_GROUP_KEY,
+                EDITION_PARENT_GROUP_KEY,
+                MPL_CORE_COLLECTION_GROUP_KEY,
+            ]
+            .contains(&config.group_key.as_str())
+            {
+                warn!(
+                    "Ignoring group {} of program {}, the key is reserved",
+                    config.group_key, program
+                );
+                continue;
+            }
+            programs.entry(program).or_default().push(config.clone());
+        }
+        ProgramGroups { programs }
+    }
+
+    /// The groupings of the account at `address`, `None` when `owner` has no groups.
+    pub fn groupings(
+        &self,
+        owner: &Pubkey,
+        address: &[u8],
+        data: &[u8],
+    ) -> Option<Vec<ProgramGrouping>> {
+        if *owner == MPL_CORE_PROGRAM {
+            return Some(mpl_core_groupings(address, data));
+        }
+        let groups = self.programs.get(owner)?;
+        Some(
+            groups
+                .iter()
+                .filter(|group| data.starts_with(&group.discriminator))
+                .filter_map(|group| {
+                    let asset_id = key_at(data, group.asset_offset)?;
+                    let group_value = key_at(data, group.value_offset)?;
+                    Some(ProgramGrouping {
+                        asset_id: asset_id.to_vec(),
+                        group_key: group.group_key.clone(),
+                        group_value: Some(bs58::encode(group_value).into_string()),
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+fn key_at(data: &[u8], offset: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(32)?)
+}
+
+// An MPL Core asset is in the collection its update authority names. The account of a burnt
+// asset is left with an uninitialized key, which takes the asset out of its collection.
+fn mpl_core_groupings(address: &[u8], data: &[u8]) -> Vec<ProgramGrouping> {
+    let group_value = match data.first() {
+        Some(&MPL_CORE_ASSET_V1) => match data.get(MPL_CORE_UPDATE_AUTHORITY_OFFSET) {
+            Some(&MPL_CORE_UPDATE_AUTHORITY_COLLECTION) => {
+                key_at(data, MPL_CORE_UPDATE_AUTHORITY_OFFSET + 1)
+                    .map(|collection| bs58::encode(collection).into_string())
+            }
+            _ => None,
+        },
+        Some(&MPL_CORE_UNINITIALIZED) => None,
+        // Collections and the compressed assets have no group.
+        _ => return vec![],
+    };
+    vec![ProgramGrouping {
+        asset_id: address.to_vec(),
+        group_key: MPL_CORE_COLLECTION_GROUP_KEY.to_string(),
+        group_value,
+    }]
+}
+
+/// Writes the groupings of an account update at `slot`, unless a later update wrote them first.
+/// Groupings read from accounts are verified by the program owning them.
+pub async fn upsert_program_groupings<T: ConnectionTrait>(
+    conn: &T,
+    groupings: &[ProgramGrouping],
+    slot: u64,
+) -> Result<(), IngesterError> {
+    let slot = slot as i64;
+    for grouping in groupings {
+        let statement = match &grouping.group_value {
+            Some(group_value) => Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "
+INSERT INTO asset_grouping (asset_id, group_key, group_value, verified, slot_updated)
+VALUES ($1, $2, $3, true, $4)
+ON CONFLICT (asset_id, group_key) DO UPDATE SET
+    group_value = excluded.group_value,
+    verified = excluded.verified,
+    slot_updated = excluded.slot_updated
+WHERE asset_grouping.slot_updated IS NULL OR excluded.slot_updated >= asset_grouping.slot_updated
+",
+                vec![
+                    grouping.asset_id.clone().into(),
+                    grouping.group_key.clone().into(),
+                    group_value.clone().into(),
+                    slot.into(),
+                ],
+            ),
+            None => Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "
+DELETE FROM asset_grouping
+WHERE asset_id = $1 AND group_key = $2 AND (slot_updated IS NULL OR slot_updated <= $3)
+",
+                vec![
+                    grouping.asset_id.clone().into(),
+                    grouping.group_key.clone().into(),
+                    slot.into(),
+                ],
+            ),
+        };
+        conn.execute(statement)
+            .await
+            .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    }
+    Ok(())
+}
@@ -1,25 +1,44 @@
-use crate::{error::IngesterError, metric, tasks::TaskData};
+use crate::{
+    asset_changes::{ChangeNotifier, ChangeType},
+    collection_stats::{record_collection_activity, CollectionActivity},
+    error::IngesterError,
+    metric,
+    owner_history::{record_delegate_change, record_owner_change},
+    tasks::TaskData,
+};
 use blockbuster::programs::token_account::TokenProgramAccount;
 use cadence_macros::{is_global_default_set, statsd_count};
-use digital_asset_types::dao::{asset, token_accounts, tokens};
+use digital_asset_types::dao::{
+    asset,
+    numeric::{saturating_to_i64, to_decimal, to_i64},
+    sea_orm_active_enums::OwnerType,
+    token_accounts, tokens,
+};
 use plerkle_serialization::AccountInfo;
 use sea_orm::{
-    entity::*, query::*, sea_query::OnConflict, ActiveValue::Set, ConnectionTrait,
-    DatabaseConnection, DbBackend, EntityTrait,
+    entity::*, prelude::Decimal, query::*, sea_query::OnConflict, ActiveValue::Set,
+    ConnectionTrait, DbBackend, EntityTrait, Statement, TransactionTrait,
 };
 use solana_sdk::program_option::COption;
 use spl_token::state::AccountState;
 use tokio::sync::mpsc::UnboundedSender;
 
-pub async fn handle_token_program_account<'a, 'b, 'c>(
+pub async fn handle_token_program_account<'a, 'b, 'c, T>(
     account_update: &'a AccountInfo<'a>,
     parsing_result: &'b TokenProgramAccount,
-    db: &'c DatabaseConnection,
+    db: &'c T,
     _task_manager: &UnboundedSender<TaskData>,
-) -> Result<(), IngesterError> {
+    changes: &ChangeNotifier,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait + TransactionTrait,
+{
     let key = *account_update.pubkey().unwrap();
     let key_bytes = key.0.to_vec();
     let spl_token_program = account_update.owner().unwrap().0.to_vec();
+    let slot_i = to_i64(account_update.slot(), "slot")?;
+    // Orders the owner and delegate changes of an asset within a slot.
+    let write_version = to_i64(account_update.write_version(), "write_version")?;
     match &parsing_result {
         TokenProgramAccount::TokenAccount(ta) => {
             let mint = ta.mint.to_bytes().to_vec();
@@ -33,15 +52,15 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
             };
             let owner = ta.owner.to_bytes().to_vec();
             let model = token_accounts::ActiveModel {
-                pubkey: Set(key_bytes),
+                pubkey: Set(key_bytes.clone()),
                 mint: Set(mint.clone()),
                 delegate: Set(delegate.clone()),
                 owner: Set(owner.clone()),
                 frozen: Set(frozen),
-                delegated_amount: Set(ta.delegated_amount as i64),
+                delegated_amount: Set(to_decimal(ta.delegated_amount)),
                 token_program: Set(spl_token_program),
-                slot_updated: Set(account_update.slot() as i64),
-                amount: Set(ta.amount as i64),
+                slot_updated: Set(slot_i),
+                amount: Set(to_decimal(ta.amount)),
                 close_authority: Set(None),
             };
 
@@ -72,10 +91,11 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
             let mut token_owner_update = false;
             let mut token_delegate_update = false;
             let mut token_freeze_update = false;
+            let mut is_nft = false;
 
             let txn = db.begin().await?;
-            let asset_update = asset::Entity::find_by_id(mint)
-                .filter(asset::Column::OwnerType.eq("single"))
+            let asset_update = asset::Entity::find_by_id(mint.clone())
+                .filter(asset::Column::OwnerType.eq(OwnerType::Single))
                 .one(&txn)
                 .await?;
 
@@ -84,6 +104,7 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
                 // TODO: Support fungible tokens
                 let asset_clone = asset.clone();
                 if asset_clone.supply == 1 {
+                    is_nft = true;
                     let mut save_required = false;
                     let mut active: asset::ActiveModel = asset.into();
 
@@ -113,22 +134,80 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
                     if save_required {
                         active.save(&txn).await?;
                     }
+                    if token_owner_update {
+                        let recorded = record_owner_change(
+                            &txn,
+                            mint.clone(),
+                            owner.clone(),
+                            slot_i,
+                            write_version,
+                        )
+                        .await?;
+                        // The first owner of an asset is its mint, not a transfer.
+                        if recorded && old_owner.is_some() {
+                            record_collection_activity(
+                                &txn,
+                                mint.clone(),
+                                CollectionActivity::Transfer,
+                                slot_i,
+                            )
+                            .await?;
+                        }
+                    }
+                    if token_delegate_update {
+                        record_delegate_change(
+                            &txn,
+                            mint.clone(),
+                            delegate.clone(),
+                            slot_i,
+                            write_version,
+                        )
+                        .await?;
+                    }
+                    // A replay of the update finds the asset changed already, so the changes are
+                    // exported with them.
+                    let slot = account_update.slot();
+                    for (updated, change) in [
+                        (token_owner_update, ChangeType::Transfer),
+                        (token_delegate_update, ChangeType::Delegate),
+                        (token_freeze_update, ChangeType::Freeze),
+                    ] {
+                        if updated {
+                            changes.export(&txn, &mint, change, slot).await?;
+                        }
+                    }
                 }
             }
             txn.commit().await?;
 
+            if !is_nft {
+                save_token_balance(
+                    db,
+                    key_bytes,
+                    owner,
+                    mint.clone(),
+                    to_decimal(ta.amount),
+                    slot_i,
+                )
+                .await?;
+            }
+
             // Publish metrics outside of the txn to reduce txn latency.
+            let slot = account_update.slot();
             if token_owner_update {
+                changes.notify(&mint, ChangeType::Transfer, slot);
                 metric! {
                     statsd_count!("token_account.owner_update", 1);
                 }
             }
             if token_delegate_update {
+                changes.notify(&mint, ChangeType::Delegate, slot);
                 metric! {
                     statsd_count!("token_account.delegate_update", 1);
                 }
             }
             if token_freeze_update {
+                changes.notify(&mint, ChangeType::Freeze, slot);
                 metric! {
                     statsd_count!("token_account.freeze_update", 1);
                 }
@@ -148,8 +227,8 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
             let model = tokens::ActiveModel {
                 mint: Set(key_bytes.clone()),
                 token_program: Set(spl_token_program),
-                slot_updated: Set(account_update.slot() as i64),
-                supply: Set(m.supply as i64),
+                slot_updated: Set(slot_i),
+                supply: Set(to_decimal(m.supply)),
                 decimals: Set(m.decimals as i32),
                 close_authority: Set(None),
                 extension_data: Set(None),
@@ -179,12 +258,12 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
             );
             db.execute(query).await?;
             let asset_update: Option<asset::Model> = asset::Entity::find_by_id(key_bytes.clone())
-                .filter(asset::Column::OwnerType.eq("single"))
+                .filter(asset::Column::OwnerType.eq(OwnerType::Single))
                 .one(db)
                 .await?;
             if let Some(asset) = asset_update {
                 let mut active: asset::ActiveModel = asset.into();
-                active.supply = Set(m.supply as i64);
+                active.supply = Set(saturating_to_i64(m.supply, "supply"));
                 active.supply_mint = Set(Some(key_bytes));
                 active.save(db).await?;
             }
@@ -194,3 +273,49 @@ pub async fn handle_token_program_account<'a, 'b, 'c>(
     }?;
     Ok(())
 }
+
+/// Appends the token account's balance to the `token_account_balances` ledger. A row is only
+/// written when the amount or owner differs from the latest earlier entry, so account updates
+/// that don't move the balance (delegations, freezes) don't grow the ledger.
+async fn save_token_balance<T>(
+    db: &T,
+    token_account: Vec<u8>,
+    owner: Vec<u8>,
+    mint: Vec<u8>,
+    amount: Decimal,
+    slot: i64,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait,
+{
+    let query = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO token_account_balances (token_account, owner, mint, amount, slot)
+SELECT $1, $2, $3, $4, $5
+WHERE NOT EXISTS (
+    SELECT 1 FROM (
+        SELECT amount, owner FROM token_account_balances
+        WHERE token_account = $1 AND slot < $5
+        ORDER BY slot DESC
+        LIMIT 1
+    ) prev
+    WHERE prev.amount = $4 AND prev.owner = $2
+)
+ON CONFLICT (token_account, slot)
+DO UPDATE SET amount = excluded.amount, owner = excluded.owner
+",
+        vec![
+            token_account.into(),
+            owner.into(),
+            mint.into(),
+            amount.into(),
+            slot.into(),
+        ],
+    );
+    db.execute(query).await?;
+    metric! {
+        statsd_count!("token_account.balance_update", 1);
+    }
+    Ok(())
+}
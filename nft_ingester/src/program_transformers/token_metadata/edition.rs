@@ -0,0 +1,142 @@
+use crate::error::IngesterError;
+use blockbuster::token_metadata::state::Edition;
+use digital_asset_types::dao::{
+    asset_editions, asset_grouping, asset_v1_account_attachments, numeric::to_i64,
+    sea_orm_active_enums::V1AccountAttachments, EDITION_PARENT_GROUP_KEY,
+};
+use plerkle_serialization::Pubkey as FBPubkey;
+use sea_orm::{
+    entity::*, query::*, sea_query::OnConflict, ActiveValue::Set, ConnectionTrait,
+    DatabaseTransaction, DbBackend, EntityTrait, FromQueryResult, Statement,
+};
+
+#[derive(Debug, FromQueryResult)]
+struct PrintParent {
+    asset_id: Vec<u8>,
+    parent_id: Vec<u8>,
+    slot_updated: i64,
+}
+
+/// Groups print editions under the mint of their master edition once both mints are known.
+/// `edition_address` is either a print's edition account or a master edition account, so
+/// whichever of the edition and the two metadata accounts is indexed last links them.
+pub async fn upsert_edition_parent_groupings<T: ConnectionTrait>(
+    txn: &T,
+    edition_address: &[u8],
+) -> Result<(), IngesterError> {
+    let prints = PrintParent::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+SELECT print.asset_id, master.asset_id AS parent_id, e.slot_updated
+FROM asset_editions e
+JOIN asset_v1_account_attachments print ON print.id = e.id
+JOIN asset_v1_account_attachments master ON master.id = e.parent
+WHERE (e.id = $1 OR e.parent = $1)
+    AND print.asset_id IS NOT NULL AND master.asset_id IS NOT NULL
+    AND NOT EXISTS (
+        SELECT 1 FROM asset_grouping g WHERE g.asset_id = print.asset_id AND g.group_key = $2
+    )
+",
+        vec![
+            edition_address.to_vec().into(),
+            EDITION_PARENT_GROUP_KEY.into(),
+        ],
+    ))
+    .all(txn)
+    .await
+    .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+
+    for print in prints {
+        // A print never changes master edition, the first grouping written stays.
+        let model = asset_grouping::ActiveModel {
+            asset_id: Set(print.asset_id),
+            group_key: Set(EDITION_PARENT_GROUP_KEY.to_string()),
+            group_value: Set(Some(bs58::encode(print.parent_id).into_string())),
+            verified: Set(Some(true)),
+            seq: Set(None),
+            slot_updated: Set(Some(print.slot_updated)),
+            ..Default::default()
+        };
+        let query = asset_grouping::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    asset_grouping::Column::AssetId,
+                    asset_grouping::Column::GroupKey,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+        txn.execute(query)
+            .await
+            .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Saves a print edition under its master edition. The edition account shares its address with
+/// the attachment the metadata account links to the mint.
+pub async fn save_v1_edition(
+    id: FBPubkey,
+    slot: u64,
+    edition: &Edition,
+    txn: &DatabaseTransaction,
+) -> Result<(), IngesterError> {
+    let id_bytes = id.0.to_vec();
+    let slot_i = to_i64(slot, "slot")?;
+
+    let model = asset_editions::ActiveModel {
+        id: Set(id_bytes.clone()),
+        parent: Set(edition.parent.to_bytes().to_vec()),
+        edition: Set(to_i64(edition.edition, "edition")?),
+        slot_updated: Set(slot_i),
+    };
+    let mut query = asset_editions::Entity::insert(model)
+        .on_conflict(
+            OnConflict::columns([asset_editions::Column::Id])
+                .update_columns([
+                    asset_editions::Column::Parent,
+                    asset_editions::Column::Edition,
+                    asset_editions::Column::SlotUpdated,
+                ])
+                .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+    query.sql = format!(
+        "{} WHERE excluded.slot_updated > asset_editions.slot_updated",
+        query.sql
+    );
+    txn.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+
+    let ser = serde_json::to_value(edition)
+        .map_err(|e| IngesterError::SerializatonError(e.to_string()))?;
+    let attachment = asset_v1_account_attachments::ActiveModel {
+        id: Set(id_bytes.clone()),
+        attachment_type: Set(V1AccountAttachments::Edition),
+        data: Set(Some(ser)),
+        slot_updated: Set(slot_i),
+        ..Default::default()
+    };
+    let mut query = asset_v1_account_attachments::Entity::insert(attachment)
+        .on_conflict(
+            OnConflict::columns([asset_v1_account_attachments::Column::Id])
+                .update_columns([
+                    asset_v1_account_attachments::Column::AttachmentType,
+                    asset_v1_account_attachments::Column::Data,
+                    asset_v1_account_attachments::Column::SlotUpdated,
+                ])
+                .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+    // The metadata account links the attachment to its mint without its data, at its own slot.
+    query.sql = format!(
+        "{} WHERE excluded.slot_updated > asset_v1_account_attachments.slot_updated OR asset_v1_account_attachments.data IS NULL",
+        query.sql
+    );
+    txn.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    upsert_edition_parent_groupings(txn, &id_bytes).await
+}
@@ -2,6 +2,7 @@ use crate::error::IngesterError;
 use blockbuster::token_metadata::state::{Key, MasterEditionV1, MasterEditionV2};
 use digital_asset_types::dao::{
     asset, asset_v1_account_attachments,
+    numeric::to_i64,
     sea_orm_active_enums::{SpecificationAssetClass, V1AccountAttachments},
 };
 use plerkle_serialization::Pubkey as FBPubkey;
@@ -67,7 +68,7 @@ pub async fn save_master_edition(
         id: Set(id_bytes),
         attachment_type: Set(V1AccountAttachments::MasterEditionV1),
         data: Set(Some(ser)),
-        slot_updated: Set(slot as i64),
+        slot_updated: Set(to_i64(slot, "slot")?),
         ..Default::default()
     };
 
@@ -78,7 +79,7 @@ pub async fn save_master_edition(
         updatable.update(txn).await?;
     }
 
-    let query = asset_v1_account_attachments::Entity::insert(model)
+    let mut query = asset_v1_account_attachments::Entity::insert(model)
         .on_conflict(
             OnConflict::columns([asset_v1_account_attachments::Column::Id])
                 .update_columns([
@@ -89,6 +90,11 @@ pub async fn save_master_edition(
                 .to_owned(),
         )
         .build(DbBackend::Postgres);
+    // The metadata account links the attachment to its mint without its data, at its own slot.
+    query.sql = format!(
+        "{} WHERE excluded.slot_updated > asset_v1_account_attachments.slot_updated OR asset_v1_account_attachments.data IS NULL",
+        query.sql
+    );
     txn.execute(query).await?;
     Ok(())
 }
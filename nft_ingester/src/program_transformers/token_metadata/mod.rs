@@ -1,25 +1,34 @@
+mod edition;
 mod master_edition;
+mod token_record;
 mod v1_asset;
 
 use crate::{
+    asset_changes::{ChangeNotifier, ChangeType},
     error::IngesterError,
     program_transformers::token_metadata::{
+        edition::save_v1_edition,
         master_edition::{save_v1_master_edition, save_v2_master_edition},
+        token_record::save_token_record,
         v1_asset::{burn_v1_asset, save_v1_asset},
     },
     tasks::TaskData,
 };
 use blockbuster::programs::token_metadata::{TokenMetadataAccountData, TokenMetadataAccountState};
 use plerkle_serialization::AccountInfo;
-use sea_orm::{DatabaseConnection, TransactionTrait};
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use tokio::sync::mpsc::UnboundedSender;
 
-pub async fn handle_token_metadata_account<'a, 'b, 'c>(
+pub async fn handle_token_metadata_account<'a, 'b, 'c, T>(
     account_update: &'a AccountInfo<'a>,
     parsing_result: &'b TokenMetadataAccountState,
-    db: &'c DatabaseConnection,
+    db: &'c T,
     task_manager: &UnboundedSender<TaskData>,
-) -> Result<(), IngesterError> {
+    changes: &ChangeNotifier,
+) -> Result<(), IngesterError>
+where
+    T: ConnectionTrait + TransactionTrait,
+{
     let key = *account_update.pubkey().unwrap();
     match &parsing_result.data {
         TokenMetadataAccountData::EmptyAccount => {
@@ -34,6 +43,15 @@ pub async fn handle_token_metadata_account<'a, 'b, 'c>(
         }
         TokenMetadataAccountData::MetadataV1(m) => {
             let task = save_v1_asset(db, m.mint.as_ref().into(), account_update.slot(), m).await?;
+            changes
+                .export(
+                    db,
+                    m.mint.as_ref(),
+                    ChangeType::Metadata,
+                    account_update.slot(),
+                )
+                .await?;
+            changes.notify(m.mint.as_ref(), ChangeType::Metadata, account_update.slot());
             if let Some(task) = task {
                 task_manager.send(task)?;
             }
@@ -45,6 +63,16 @@ pub async fn handle_token_metadata_account<'a, 'b, 'c>(
             txn.commit().await?;
             Ok(())
         }
+        TokenMetadataAccountData::EditionV1(e) => {
+            let txn = db.begin().await?;
+            save_v1_edition(key, account_update.slot(), e, &txn).await?;
+            txn.commit().await?;
+            Ok(())
+        }
+        TokenMetadataAccountData::TokenRecord(r) => {
+            save_token_record(key, account_update.slot(), r, db).await?;
+            Ok(())
+        }
         // TokenMetadataAccountData::EditionMarker(_) => {}
         // TokenMetadataAccountData::UseAuthorityRecord(_) => {}
         // TokenMetadataAccountData::CollectionAuthorityRecord(_) => {}
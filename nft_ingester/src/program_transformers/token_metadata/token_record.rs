@@ -0,0 +1,53 @@
+use crate::error::IngesterError;
+use blockbuster::token_metadata::state::{TokenRecord, TokenState};
+use digital_asset_types::dao::{
+    numeric::to_i64, sea_orm_active_enums::TokenRecordState, token_records,
+};
+use plerkle_serialization::Pubkey as FBPubkey;
+use sea_orm::{
+    query::*, sea_query::OnConflict, ActiveValue::Set, ConnectionTrait, DbBackend, EntityTrait,
+};
+
+/// Saves the lock state of a programmable NFT's token account. The record doesn't name its mint
+/// or token account, readers find it at its address derived from them.
+pub async fn save_token_record<T: ConnectionTrait>(
+    id: FBPubkey,
+    slot: u64,
+    record: &TokenRecord,
+    db: &T,
+) -> Result<(), IngesterError> {
+    let state = match record.state {
+        TokenState::Unlocked => TokenRecordState::Unlocked,
+        TokenState::Locked => TokenRecordState::Locked,
+        TokenState::Listed => TokenRecordState::Listed,
+    };
+    let model = token_records::ActiveModel {
+        pubkey: Set(id.0.to_vec()),
+        state: Set(state),
+        delegate: Set(record.delegate.map(|d| d.to_bytes().to_vec())),
+        delegate_role: Set(record.delegate_role.as_ref().map(|r| format!("{:?}", r))),
+        locked_transfer: Set(record.locked_transfer.map(|l| l.to_bytes().to_vec())),
+        slot_updated: Set(to_i64(slot, "slot")?),
+    };
+    let mut query = token_records::Entity::insert(model)
+        .on_conflict(
+            OnConflict::columns([token_records::Column::Pubkey])
+                .update_columns([
+                    token_records::Column::State,
+                    token_records::Column::Delegate,
+                    token_records::Column::DelegateRole,
+                    token_records::Column::LockedTransfer,
+                    token_records::Column::SlotUpdated,
+                ])
+                .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+    query.sql = format!(
+        "{} WHERE excluded.slot_updated > token_records.slot_updated",
+        query.sql
+    );
+    db.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::StorageWriteError(db_err.to_string()))?;
+    Ok(())
+}
@@ -1,22 +1,34 @@
-use crate::{error::IngesterError, tasks::TaskData};
+use crate::{
+    authority::record_authority_change,
+    collection_stats::{
+        record_collection_activity, record_collection_join, verified_collection, CollectionActivity,
+    },
+    data_history::{record_data_change, DataVersion},
+    error::IngesterError,
+    owner_history::{record_burn, record_owner_change},
+    program_transformers::token_metadata::edition::upsert_edition_parent_groupings,
+    tasks::TaskData,
+};
 use blockbuster::token_metadata::{
-    pda::find_master_edition_account,
-    state::{Metadata, TokenStandard, UseMethod, Uses},
+    pda::{find_escrow_account, find_master_edition_account},
+    state::{EscrowAuthority, Metadata, TokenStandard, UseMethod, Uses},
 };
 use chrono::Utc;
 use digital_asset_types::{
     dao::{
-        asset, asset_authority, asset_creators, asset_data, asset_grouping,
+        asset, asset_authority, asset_creators, asset_data, asset_grouping, asset_owner_pdas,
         asset_v1_account_attachments,
+        numeric::{decimal_to_u64, saturating_to_i64, to_i64},
         sea_orm_active_enums::{
             ChainMutability, Mutability, OwnerType, RoyaltyTargetType, SpecificationAssetClass,
             SpecificationVersions, V1AccountAttachments,
         },
-        token_accounts, tokens,
+        token_accounts, tokens, COLLECTION_GROUP_KEY,
     },
     json::ChainDataV1,
 };
 
+use lazy_static::lazy_static;
 use log::warn;
 use num_traits::FromPrimitive;
 use plerkle_serialization::Pubkey as FBPubkey;
@@ -24,26 +36,58 @@ use sea_orm::{
     entity::*, query::*, sea_query::OnConflict, ActiveValue::Set, ConnectionTrait, DbBackend,
     DbErr, EntityTrait, JsonValue,
 };
+use solana_sdk::pubkey::Pubkey;
+use std::{collections::HashMap, sync::Mutex};
 
 use crate::tasks::{DownloadMetadata, IntoTaskData};
 
+/// The `asset_owner_pdas` program of Token Metadata's token owned escrows.
+const TOKEN_OWNED_ESCROW: &str = "token_metadata_escrow";
+// Mints whose PDAs are cached, the cache is emptied when it grows past them.
+const MAX_CACHED_MINTS: usize = 100_000;
+
+lazy_static! {
+    // The PDAs of the mints of recent metadata updates, which are rederived on every update of
+    // a mint otherwise, each derivation hashing until it finds an address off the curve.
+    static ref MINT_PDAS: Mutex<HashMap<Pubkey, (Pubkey, Pubkey)>> = Mutex::new(HashMap::new());
+}
+
+// The master edition and token owned escrow accounts of `mint`.
+fn mint_pdas(mint: &Pubkey) -> (Pubkey, Pubkey) {
+    if let Some(pdas) = MINT_PDAS.lock().unwrap().get(mint) {
+        return *pdas;
+    }
+    let (edition, _) = find_master_edition_account(mint);
+    let (escrow, _) = find_escrow_account(mint, &EscrowAuthority::TokenOwner);
+    let mut cache = MINT_PDAS.lock().unwrap();
+    if cache.len() >= MAX_CACHED_MINTS {
+        cache.clear();
+    }
+    cache.insert(*mint, (edition, escrow));
+    (edition, escrow)
+}
+
 pub async fn burn_v1_asset<T: ConnectionTrait + TransactionTrait>(
     conn: &T,
     id: FBPubkey,
     slot: u64,
 ) -> Result<(), IngesterError> {
-    let (id, slot_i) = (id.0, slot as i64);
+    let (id, slot_i) = (id.0, to_i64(slot, "slot")?);
     let model = asset::ActiveModel {
         id: Set(id.to_vec()),
         slot_updated: Set(Some(slot_i)),
         burnt: Set(true),
         ..Default::default()
     };
-    // If the asset hasn't been indexed yet, we don't do anything.
+    // If the asset hasn't been indexed yet, or was already burnt, we don't do anything.
     let query = asset::Entity::update(model)
         .filter(asset::Column::SlotUpdated.lt(slot_i))
+        .filter(asset::Column::Burnt.eq(false))
         .build(DbBackend::Postgres);
-    conn.execute(query).await?;
+    if conn.execute(query).await?.rows_affected() > 0 {
+        record_collection_activity(conn, id.to_vec(), CollectionActivity::Burn, slot_i).await?;
+        record_burn(conn, id.to_vec(), slot_i, 0).await?;
+    }
     Ok(())
 }
 
@@ -56,11 +100,11 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
     let metadata = metadata.clone();
     let data = metadata.data;
     let meta_mint_pubkey = metadata.mint;
-    let (edition_attachment_address, _) = find_master_edition_account(&meta_mint_pubkey);
+    let (edition_attachment_address, escrow) = mint_pdas(&meta_mint_pubkey);
     let mint = metadata.mint.to_bytes().to_vec();
     let authority = metadata.update_authority.to_bytes().to_vec();
     let id = id.0;
-    let slot_i = slot as i64;
+    let slot_i = to_i64(slot, "slot")?;
     let uri = data.uri.trim().replace('\0', "");
     let _spec = SpecificationVersions::V1;
     let class = match metadata.token_standard {
@@ -102,13 +146,19 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
 
     // get supply of token, default to 1 since most cases will be NFTs. Token mint ingester will properly set supply if token_result is None
     let (supply, supply_mint) = match token {
-        Some(t) => (Set(t.supply), Set(Some(t.mint))),
+        Some(t) => (
+            Set(saturating_to_i64(
+                decimal_to_u64(t.supply, "supply")?,
+                "supply",
+            )),
+            Set(Some(t.mint)),
+        ),
         None => (Set(1), NotSet),
     };
 
     // owner and delegate should be from the token account with the mint
-    let (owner, delegate) = match token_account {
-        Some(ta) => (Set(Some(ta.owner)), Set(ta.delegate)),
+    let (owner, delegate) = match &token_account {
+        Some(ta) => (Set(Some(ta.owner.clone())), Set(ta.delegate.clone())),
         None => (NotSet, NotSet),
     };
 
@@ -127,6 +177,12 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
         }),
     };
     chain_data.sanitize();
+    let version = DataVersion {
+        name: chain_data.name.clone(),
+        symbol: chain_data.symbol.clone(),
+        metadata_url: uri.clone(),
+        slot: slot_i,
+    };
     let chain_data_json = serde_json::to_value(chain_data)
         .map_err(|e| IngesterError::DeserializationError(e.to_string()))?;
     let chain_mutability = match metadata.is_mutable {
@@ -144,6 +200,7 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
         id: Set(id.to_vec()),
         raw_name: Set(Some(name.to_vec())),
         raw_symbol: Set(Some(symbol.to_vec())),
+        ..Default::default()
     };
     let txn = conn.begin().await?;
     let mut query = asset_data::Entity::insert(asset_data_model)
@@ -167,6 +224,7 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
     txn.execute(query)
         .await
         .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+    record_data_change(&txn, id.to_vec(), version).await?;
     let model = asset::ActiveModel {
         id: Set(id.to_vec()),
         owner,
@@ -226,8 +284,14 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
     txn.execute(query)
         .await
         .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+    if let Some(ta) = token_account {
+        record_owner_change(&txn, id.to_vec(), ta.owner, ta.slot_updated, 0).await?;
+    }
+    // The edition account doesn't name its mint, linking it here lets print editions be listed
+    // by mint.
     let attachment = asset_v1_account_attachments::ActiveModel {
         id: Set(edition_attachment_address.to_bytes().to_vec()),
+        asset_id: Set(Some(id.to_vec())),
         slot_updated: Set(slot_i),
         attachment_type: Set(V1AccountAttachments::MasterEditionV2),
         ..Default::default()
@@ -235,13 +299,31 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
     let query = asset_v1_account_attachments::Entity::insert(attachment)
         .on_conflict(
             OnConflict::columns([asset_v1_account_attachments::Column::Id])
-                .do_nothing()
+                .update_columns([asset_v1_account_attachments::Column::AssetId])
                 .to_owned(),
         )
         .build(DbBackend::Postgres);
     txn.execute(query)
         .await
         .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+    upsert_edition_parent_groupings(&txn, &edition_attachment_address.to_bytes()).await?;
+    // Assets sent to the token owned escrow of this one are owned by it.
+    let query = asset_owner_pdas::Entity::insert(asset_owner_pdas::ActiveModel {
+        pda: Set(escrow.to_bytes().to_vec()),
+        asset_id: Set(id.to_vec()),
+        program: Set(TOKEN_OWNED_ESCROW.to_string()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([asset_owner_pdas::Column::Pda])
+            .do_nothing()
+            .to_owned(),
+    )
+    .build(DbBackend::Postgres);
+    txn.execute(query)
+        .await
+        .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+    record_authority_change(&txn, id.to_vec(), authority.clone(), slot_i, 0).await?;
     let model = asset_authority::ActiveModel {
         asset_id: Set(id.to_vec()),
         authority: Set(authority),
@@ -268,9 +350,10 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
         .await
         .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
     if let Some(c) = &metadata.collection {
+        let before = verified_collection(&txn, id.to_vec()).await?;
         let model = asset_grouping::ActiveModel {
             asset_id: Set(id.to_vec()),
-            group_key: Set("collection".to_string()),
+            group_key: Set(COLLECTION_GROUP_KEY.to_string()),
             group_value: Set(Some(c.key.to_string())),
             verified: Set(Some(c.verified)),
             seq: Set(None),
@@ -286,19 +369,23 @@ pub async fn save_v1_asset<T: ConnectionTrait + TransactionTrait>(
                 .update_columns([
                     asset_grouping::Column::GroupKey,
                     asset_grouping::Column::GroupValue,
+                    asset_grouping::Column::Verified,
                     asset_grouping::Column::Seq,
                     asset_grouping::Column::SlotUpdated,
                 ])
                 .to_owned(),
             )
             .build(DbBackend::Postgres);
+        // Metadata accounts have no seq, so a later verification of the collection is applied by
+        // slot alone.
         query.sql = format!(
-                "{} WHERE excluded.slot_updated > asset_grouping.slot_updated AND excluded.seq >= asset_grouping.seq",
+                "{} WHERE excluded.slot_updated > asset_grouping.slot_updated AND (asset_grouping.seq IS NULL OR excluded.seq >= asset_grouping.seq)",
                 query.sql
             );
         txn.execute(query)
             .await
             .map_err(|db_err| IngesterError::AssetIndexError(db_err.to_string()))?;
+        record_collection_join(&txn, id.to_vec(), before, slot_i).await?;
     }
 
     // check if we need to index a newer update. This assumes that all creator rows with same AssetId have the same SlotUpdated
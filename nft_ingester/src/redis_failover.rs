@@ -0,0 +1,273 @@
+use crate::metric;
+use cadence_macros::{is_global_default_set, statsd_count};
+use figment::value::Value;
+use log::warn;
+use plerkle_messenger::MessengerConfig;
+use redis::{
+    streams::{StreamInfoGroupsReply, StreamPendingReply},
+    AsyncCommands, RedisResult,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+const URL_KEY: &str = "redis_connection_str";
+const BACKUPS_KEY: &str = "redis_backup_connection_strs";
+const FAILOVER_AFTER_KEY: &str = "redis_failover_after_errors";
+const CONSUMER_GROUP_KEY: &str = "consumer_group_name";
+const DEFAULT_CONSUMER_GROUP: &str = "plerkle";
+// Records the offset a consumer group was last started or reset at.
+const RESET_MARKER_PREFIX: &str = "das:group_start";
+
+/// What starting a consumer group at an offset did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupStart {
+    Created,
+    /// The group existed and was moved to the offset.
+    Moved,
+    /// The group existed and was left where it is.
+    Kept,
+}
+
+/// Where a consumer group stands on a stream: the last entry delivered to its consumers and the
+/// oldest of those they haven't acknowledged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupProgress {
+    pub last_delivered: String,
+    pub oldest_pending: Option<String>,
+}
+
+/// The Redis instances the streams can be read from: the messenger config's
+/// `redis_connection_str` and the backups under `redis_backup_connection_strs`, tried in that
+/// order. The stream consumers and producers share it, so they all move to the next instance
+/// together.
+#[derive(Clone, Debug, Default)]
+pub struct RedisEndpoints {
+    urls: Arc<Vec<String>>,
+    // Counts failovers, the instance in use is this modulo the number of instances.
+    generation: Arc<AtomicUsize>,
+    failover_after: u32,
+}
+
+impl RedisEndpoints {
+    pub fn from_config(config: &MessengerConfig) -> Self {
+        let setting = |key: &str| config.connection_config.get(key);
+        let urls = setting(URL_KEY)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .into_iter()
+            .chain(
+                setting(BACKUPS_KEY)
+                    .and_then(|v| v.deserialize::<Vec<String>>().ok())
+                    .unwrap_or_default(),
+            )
+            .collect();
+        RedisEndpoints {
+            urls: Arc::new(urls),
+            generation: Arc::new(AtomicUsize::new(0)),
+            failover_after: setting(FAILOVER_AFTER_KEY)
+                .and_then(|v| v.deserialize::<u32>().ok())
+                .unwrap_or(3)
+                .max(1),
+        }
+    }
+
+    /// Changes every time the consumers fail over.
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Consecutive errors of a consumer or producer before it fails over.
+    pub fn failover_after(&self) -> u32 {
+        self.failover_after
+    }
+
+    /// The number of instances, the primary and its backups.
+    pub fn instances(&self) -> usize {
+        self.urls.len().max(1)
+    }
+
+    fn index(&self, generation: usize) -> usize {
+        generation % self.urls.len().max(1)
+    }
+
+    /// `config` pointed at the instance of `generation`.
+    pub fn apply(&self, config: &MessengerConfig, generation: usize) -> MessengerConfig {
+        let mut config = config.clone();
+        if let Some(url) = self.urls.get(self.index(generation)) {
+            config
+                .connection_config
+                .insert(URL_KEY.to_string(), Value::from(url.clone()));
+        }
+        config
+    }
+
+    /// Moves the consumers past the instance of `generation` after it failed, unless one of them
+    /// already did. Without backups they stay on the primary.
+    pub fn fail_over(&self, generation: usize) {
+        if self.urls.len() < 2 {
+            return;
+        }
+        let moved = self.generation.compare_exchange(
+            generation,
+            generation + 1,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        );
+        if moved.is_ok() {
+            let (from, to) = (self.index(generation), self.index(generation + 1));
+            warn!(
+                "Redis instance {} is failing, failing over to instance {}",
+                from, to
+            );
+            metric! {
+                statsd_count!("ingester.redis.failover", 1, "instance" => &to.to_string());
+            }
+        }
+    }
+
+    /// Gets `stream` ready to be read in the consumer group of `config` on the instance of
+    /// `generation`. A backup the consumers never read from has no group yet, which is created to
+    /// deliver the stream from its start. The entries another instance already delivered are
+    /// redelivered rather than skipped, and the transformers drop the updates that are older
+    /// than what they already indexed.
+    pub async fn resync(
+        &self,
+        config: &MessengerConfig,
+        generation: usize,
+        stream: &str,
+    ) -> RedisResult<()> {
+        let url = match self.urls.get(self.index(generation)) {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+        let group = config
+            .connection_config
+            .get(CONSUMER_GROUP_KEY)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_CONSUMER_GROUP);
+        let mut conn = redis::Client::open(url.as_str())?
+            .get_async_connection()
+            .await?;
+        let created: RedisResult<()> = conn.xgroup_create_mkstream(stream, group, "0").await;
+        match created {
+            // The group was replicated from the previous instance, with its offsets.
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            created => created,
+        }
+    }
+
+    /// Creates the consumer group of `config` on `stream` at the entry `id`, on the instance of
+    /// `generation`. With `reset`, a group that exists is moved there once per `id`: the id it
+    /// was moved to is recorded next to the group, so restarts with the same offset leave it
+    /// where its consumers got to.
+    pub async fn create_group(
+        &self,
+        config: &MessengerConfig,
+        generation: usize,
+        stream: &str,
+        id: &str,
+        reset: bool,
+    ) -> RedisResult<GroupStart> {
+        let url = match self.urls.get(self.index(generation)) {
+            Some(url) => url,
+            None => return Ok(GroupStart::Kept),
+        };
+        let group = config
+            .connection_config
+            .get(CONSUMER_GROUP_KEY)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_CONSUMER_GROUP);
+        let mut conn = redis::Client::open(url.as_str())?
+            .get_async_connection()
+            .await?;
+        let created: RedisResult<()> = conn.xgroup_create_mkstream(stream, group, id).await;
+        let marker = format!("{}:{}:{}", RESET_MARKER_PREFIX, stream, group);
+        match created {
+            Ok(()) => {
+                conn.set::<_, _, ()>(&marker, id).await?;
+                Ok(GroupStart::Created)
+            }
+            Err(e) if e.code() == Some("BUSYGROUP") => {
+                if !reset {
+                    return Ok(GroupStart::Kept);
+                }
+                let applied: Option<String> = conn.get(&marker).await?;
+                if applied.as_deref() == Some(id) {
+                    return Ok(GroupStart::Kept);
+                }
+                conn.xgroup_setid::<_, _, _, ()>(stream, group, id).await?;
+                conn.set::<_, _, ()>(&marker, id).await?;
+                Ok(GroupStart::Moved)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Moves the consumer group of `config` on `stream` to the entry `id`, on the instance of
+    /// `generation`. Its consumers are then delivered the entries after `id`, read or not.
+    pub async fn set_group_id(
+        &self,
+        config: &MessengerConfig,
+        generation: usize,
+        stream: &str,
+        id: &str,
+    ) -> RedisResult<()> {
+        let url = match self.urls.get(self.index(generation)) {
+            Some(url) => url,
+            None => return Ok(()),
+        };
+        let group = config
+            .connection_config
+            .get(CONSUMER_GROUP_KEY)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_CONSUMER_GROUP);
+        let mut conn = redis::Client::open(url.as_str())?
+            .get_async_connection()
+            .await?;
+        redis::cmd("XGROUP")
+            .arg("SETID")
+            .arg(stream)
+            .arg(group)
+            .arg(id)
+            .query_async(&mut conn)
+            .await
+    }
+
+    /// Where the consumer group of `config` stands on `stream`, on the instance of `generation`,
+    /// or none when it has no such group.
+    pub async fn group_progress(
+        &self,
+        config: &MessengerConfig,
+        generation: usize,
+        stream: &str,
+    ) -> RedisResult<Option<GroupProgress>> {
+        let url = match self.urls.get(self.index(generation)) {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+        let group = config
+            .connection_config
+            .get(CONSUMER_GROUP_KEY)
+            .and_then(Value::as_str)
+            .unwrap_or(DEFAULT_CONSUMER_GROUP);
+        let mut conn = redis::Client::open(url.as_str())?
+            .get_async_connection()
+            .await?;
+        let groups: StreamInfoGroupsReply = conn.xinfo_groups(stream).await?;
+        let last_delivered = match groups.groups.into_iter().find(|g| g.name == group) {
+            Some(info) => info.last_delivered_id,
+            None => return Ok(None),
+        };
+        let pending: StreamPendingReply = conn.xpending(stream, group).await?;
+        let oldest_pending = match pending {
+            StreamPendingReply::Data(data) => Some(data.start_id),
+            StreamPendingReply::Empty => None,
+        };
+        Ok(Some(GroupProgress {
+            last_delivered,
+            oldest_pending,
+        }))
+    }
+}
@@ -0,0 +1,341 @@
+//! Replays the last slots of the chain into the streams, to recover from a short outage of the
+//! Geyser plugin or of Redis without a gap analysis per tree.
+
+use crate::{
+    block_source::BlockSource, config::IngesterConfig, error::IngesterError, metric,
+    program_transformers::indexed_programs, redis_failover::RedisEndpoints,
+    stream_control::StreamProducer,
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use das_tree_reader::{missing_lookups, LookupTableCache, RpcThrottle};
+use flatbuffers::FlatBufferBuilder;
+use log::{info, warn};
+use plerkle_messenger::{
+    redis_messenger::RedisMessenger, Messenger, ACCOUNT_STREAM, TRANSACTION_STREAM,
+};
+use plerkle_serialization::{
+    serializer::{seralize_encoded_transaction_with_status, serialize_account},
+    solana_geyser_plugin_interface_shims::ReplicaAccountInfoV2,
+};
+use serde::{Deserialize, Serialize};
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcBlockConfig};
+use solana_sdk::{
+    account::Account,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    pubkey::Pubkey,
+    slot_history::Slot,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
+    EncodedTransactionWithStatusMeta, UiTransactionEncoding,
+};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+use tokio::task::JoinHandle;
+
+// Accounts per `getMultipleAccounts` request, the RPC maximum.
+const ACCOUNT_BATCH_SIZE: usize = 100;
+// Slots listed in `failed_slots`, past which they are only counted.
+const MAX_FAILED_SLOTS: usize = 100;
+
+/// A request to replay the last `slots` slots. Unless `accounts` is false, the current state of
+/// the indexed accounts the replayed transactions wrote to is sent as well.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReindexRequest {
+    pub slots: u64,
+    pub accounts: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReindexStatus {
+    pub running: bool,
+    pub start_slot: Slot,
+    pub end_slot: Slot,
+    pub blocks_total: usize,
+    pub blocks_done: usize,
+    pub transactions_sent: u64,
+    pub accounts_sent: u64,
+    pub failed_slots: Vec<Slot>,
+    pub failed_slot_count: usize,
+    pub error: Option<String>,
+}
+
+/// Runs one replay at a time, started and followed through the admin server.
+#[derive(Clone)]
+pub struct Reindexer {
+    config: Arc<IngesterConfig>,
+    endpoints: RedisEndpoints,
+    max_slots: u64,
+    status: Arc<Mutex<Option<ReindexStatus>>>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl Reindexer {
+    /// Replays write the streams on the Redis instance of `endpoints`.
+    pub fn new(config: &IngesterConfig, endpoints: RedisEndpoints, max_slots: u64) -> Self {
+        Reindexer {
+            config: Arc::new(config.clone()),
+            endpoints,
+            max_slots,
+            status: Arc::new(Mutex::new(None)),
+            task: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The running or last replay.
+    pub fn status(&self) -> Option<ReindexStatus> {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn start(&self, request: ReindexRequest) -> Result<(), String> {
+        if request.slots == 0 || request.slots > self.max_slots {
+            return Err(format!("slots must be between 1 and {}", self.max_slots));
+        }
+        let mut task = self.task.lock().unwrap();
+        if task.as_ref().map_or(false, |t| !t.is_finished()) {
+            return Err("a reindex is already running".to_string());
+        }
+        *self.status.lock().unwrap() = Some(ReindexStatus {
+            running: true,
+            ..Default::default()
+        });
+        let (config, endpoints, status) = (
+            self.config.clone(),
+            self.endpoints.clone(),
+            self.status.clone(),
+        );
+        *task = Some(tokio::spawn(async move {
+            let result = replay(&config, endpoints, &request, &status).await;
+            let mut status = status.lock().unwrap();
+            if let Some(status) = status.as_mut() {
+                status.running = false;
+                if let Err(e) = result {
+                    warn!("reindex failed: {}", e);
+                    status.error = Some(e.to_string());
+                } else {
+                    info!(
+                        "reindexed slots {}-{}, {} transactions and {} accounts sent",
+                        status.start_slot,
+                        status.end_slot,
+                        status.transactions_sent,
+                        status.accounts_sent
+                    );
+                }
+            }
+        }));
+        Ok(())
+    }
+
+    /// Stops the running replay, what was already sent stays in the streams.
+    pub fn cancel(&self) -> bool {
+        let task = self.task.lock().unwrap().take();
+        match task {
+            Some(task) if !task.is_finished() => {
+                task.abort();
+                if let Some(status) = self.status.lock().unwrap().as_mut() {
+                    status.running = false;
+                    status.error = Some("cancelled".to_string());
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn update(status: &Mutex<Option<ReindexStatus>>, f: impl FnOnce(&mut ReindexStatus)) {
+    if let Some(status) = status.lock().unwrap().as_mut() {
+        f(status);
+    }
+}
+
+/// The account keys of a transaction, including the ones loaded from lookup tables.
+fn account_keys(tx: &EncodedTransactionWithStatusMeta) -> Option<Vec<Pubkey>> {
+    let decoded = tx.transaction.decode()?;
+    let mut keys = decoded.message.static_account_keys().to_vec();
+    if let Some(meta) = &tx.meta {
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for key in loaded.writable.iter().chain(loaded.readonly.iter()) {
+                keys.push(Pubkey::from_str(key).ok()?);
+            }
+        }
+    }
+    Some(keys)
+}
+
+/// Sends the state of `account` at `slot` to the account stream, like the Geyser plugin would.
+pub async fn send_account<T: Messenger>(
+    messenger: &mut StreamProducer<T>,
+    pubkey: &Pubkey,
+    account: &Account,
+    slot: Slot,
+) -> Result<(), IngesterError> {
+    let account_info = ReplicaAccountInfoV2 {
+        pubkey: &pubkey.to_bytes(),
+        lamports: account.lamports,
+        owner: &account.owner.to_bytes(),
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: &account.data,
+        write_version: 0,
+        txn_signature: None,
+    };
+    let builder = serialize_account(FlatBufferBuilder::new(), &account_info, slot, false);
+    messenger
+        .send(ACCOUNT_STREAM, builder.finished_data())
+        .await?;
+    Ok(())
+}
+
+async fn replay(
+    config: &IngesterConfig,
+    endpoints: RedisEndpoints,
+    request: &ReindexRequest,
+    status: &Mutex<Option<ReindexStatus>>,
+) -> Result<(), IngesterError> {
+    let commitment = CommitmentConfig {
+        commitment: CommitmentLevel::Confirmed,
+    };
+    let rpc_url = config.get_rpc_url();
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment);
+    let block_source = BlockSource::new(
+        config.backfiller_block_source.as_ref(),
+        RpcClient::new_with_commitment(rpc_url, commitment),
+        RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+            ..RpcBlockConfig::default()
+        },
+    )
+    .await?;
+    let mut messenger = StreamProducer::<RedisMessenger>::new(
+        config.get_messneger_client_config(),
+        endpoints,
+        &[TRANSACTION_STREAM, ACCOUNT_STREAM],
+    )
+    .await?;
+
+    let end_slot = rpc_client
+        .get_slot()
+        .await
+        .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+    let start_slot = end_slot.saturating_sub(request.slots - 1);
+    let slots = block_source.get_blocks(start_slot, end_slot).await?;
+    info!(
+        "reindexing {} blocks in slots {}-{}",
+        slots.len(),
+        start_slot,
+        end_slot
+    );
+    update(status, |s| {
+        s.start_slot = start_slot;
+        s.end_slot = end_slot;
+        s.blocks_total = slots.len();
+    });
+
+    let programs: HashSet<Pubkey> = indexed_programs().into_iter().collect();
+    let lookup_tables = LookupTableCache::new();
+    let throttle = RpcThrottle::new(1);
+    let mut accounts = HashSet::new();
+    for slot in slots {
+        let block = match block_source.get_block(slot).await {
+            Ok(block) => block,
+            Err(e) => {
+                warn!("reindex skipped slot {}: {}", slot, e);
+                metric! {
+                    statsd_count!("ingester.reindex.failed_slot", 1);
+                }
+                update(status, |s| {
+                    if s.failed_slots.len() < MAX_FAILED_SLOTS {
+                        s.failed_slots.push(slot);
+                    }
+                    s.failed_slot_count += 1;
+                    s.blocks_done += 1;
+                });
+                continue;
+            }
+        };
+        let mut sent = 0;
+        for tx in block.transactions.iter() {
+            // Failed transactions never change an asset.
+            if tx.meta.as_ref().map_or(true, |meta| meta.err.is_some()) {
+                continue;
+            }
+            // Addresses loaded from lookup tables the block's meta leaves out are filled in, for
+            // the filter below and for the ingester parsing the transaction.
+            let mut tx = Cow::Borrowed(tx);
+            if missing_lookups(&tx).is_some() {
+                if let Err(e) = lookup_tables
+                    .fill_loaded_addresses(&rpc_client, &throttle, tx.to_mut())
+                    .await
+                {
+                    warn!(
+                        "reindex couldn't resolve lookup tables in slot {}: {}",
+                        slot, e
+                    );
+                }
+            }
+            let keys = match account_keys(&tx) {
+                Some(keys) => keys,
+                None => continue,
+            };
+            if !keys.iter().any(|key| programs.contains(key)) {
+                continue;
+            }
+            let builder = seralize_encoded_transaction_with_status(
+                FlatBufferBuilder::new(),
+                EncodedConfirmedTransactionWithStatusMeta {
+                    transaction: tx.into_owned(),
+                    slot,
+                    block_time: block.block_time,
+                },
+            )?;
+            messenger
+                .send(TRANSACTION_STREAM, builder.finished_data())
+                .await?;
+            sent += 1;
+            if request.accounts.unwrap_or(true) {
+                accounts.extend(keys);
+            }
+        }
+        metric! {
+            statsd_count!("ingester.reindex.transaction_sent", sent as i64);
+        }
+        update(status, |s| {
+            s.blocks_done += 1;
+            s.transactions_sent += sent;
+        });
+    }
+
+    // Accounts only change through transactions, so the ones the replayed transactions touched
+    // are all that can be stale. Only the accounts of the indexed programs are sent.
+    let accounts: Vec<Pubkey> = accounts
+        .into_iter()
+        .filter(|key| !programs.contains(key))
+        .collect();
+    for batch in accounts.chunks(ACCOUNT_BATCH_SIZE) {
+        let response = rpc_client
+            .get_multiple_accounts_with_commitment(batch, commitment)
+            .await
+            .map_err(|e| IngesterError::RpcGetDataError(e.to_string()))?;
+        let mut sent = 0;
+        for (pubkey, account) in batch.iter().zip(response.value) {
+            let account = match account {
+                Some(account) if programs.contains(&account.owner) => account,
+                _ => continue,
+            };
+            send_account(&mut messenger, pubkey, &account, response.context.slot).await?;
+            sent += 1;
+        }
+        metric! {
+            statsd_count!("ingester.reindex.account_sent", sent as i64);
+        }
+        update(status, |s| s.accounts_sent += sent);
+    }
+    Ok(())
+}
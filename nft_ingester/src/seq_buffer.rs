@@ -0,0 +1,330 @@
+use crate::metric;
+use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
+use log::{debug, error};
+use sea_orm::{DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector, Statement};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::PgPool;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+use tokio::time::{Duration, Instant};
+
+// Trees without held transactions are forgotten after this long unseen, their applied seq is
+// read from `cl_items` again when they're seen next.
+const IDLE_TREE_TTL: Duration = Duration::from_secs(600);
+
+/// Holds the transactions of a tree that arrive ahead of their predecessor, e.g.
+/// `'{max_gap=50, max_wait=5000}'`.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct SeqBufferConfig {
+    /// Transactions further ahead of the tree's last applied seq are applied right away, their
+    /// predecessors are likely lost and left to the backfiller.
+    pub max_gap: Option<u64>,
+    /// Milliseconds a transaction waits for its predecessor before it's applied anyway.
+    pub max_wait: Option<u64>,
+    /// Transactions held across every tree, the ones past it are applied right away.
+    pub max_held: Option<usize>,
+    /// Megabytes of transactions held across every tree, the ones past it are applied right
+    /// away. Defaults to half the transaction stream's memory budget, when it has one.
+    pub max_held_mb: Option<u64>,
+}
+
+impl SeqBufferConfig {
+    pub fn get_max_gap(&self) -> u64 {
+        self.max_gap.unwrap_or(50)
+    }
+
+    pub fn get_max_wait(&self) -> Duration {
+        Duration::from_millis(self.max_wait.unwrap_or(5000))
+    }
+
+    pub fn get_max_held(&self) -> usize {
+        self.max_held.unwrap_or(10_000)
+    }
+
+    /// The bytes held transactions may take, given the stream's memory budget in megabytes.
+    /// Held transactions keep their share of the budget, so they're kept to part of it and
+    /// can't stall reading the stream until they expire.
+    pub fn get_max_held_bytes(&self, budget_mb: Option<u64>) -> Option<usize> {
+        self.max_held_mb
+            .or_else(|| budget_mb.map(|mb| mb / 2))
+            .map(|mb| (mb as usize).saturating_mul(1024 * 1024))
+    }
+}
+
+struct Held<J> {
+    since: Instant,
+    size: usize,
+    job: J,
+}
+
+struct TreeQueue<J> {
+    // The last seq applied to the tree, as far as this buffer knows.
+    applied: Option<u64>,
+    // Keyed by the first seq of the transaction.
+    held: BTreeMap<u64, Held<J>>,
+    last_seen: Instant,
+}
+
+impl<J> TreeQueue<J> {
+    fn new(now: Instant) -> Self {
+        TreeQueue {
+            applied: None,
+            held: BTreeMap::new(),
+            last_seen: now,
+        }
+    }
+}
+
+/// The transactions held for each tree, in seq order, and the seq each tree was applied up to.
+/// Trees without held transactions are forgotten once they weren't seen for a while.
+pub struct SeqQueues<J> {
+    config: SeqBufferConfig,
+    max_held_bytes: Option<usize>,
+    trees: HashMap<Pubkey, TreeQueue<J>>,
+    held: usize,
+    held_bytes: usize,
+}
+
+impl<J> SeqQueues<J> {
+    pub fn new(config: SeqBufferConfig, max_held_bytes: Option<usize>) -> Self {
+        SeqQueues {
+            config,
+            max_held_bytes,
+            trees: HashMap::new(),
+            held: 0,
+            held_bytes: 0,
+        }
+    }
+
+    /// Transactions held across every tree.
+    pub fn held(&self) -> usize {
+        self.held
+    }
+
+    /// Decoded bytes of the transactions held across every tree.
+    pub fn held_bytes(&self) -> usize {
+        self.held_bytes
+    }
+
+    /// Trees the buffer keeps the applied seq or held transactions of.
+    pub fn trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Whether `seq` is ahead of the next seq the buffer knows `tree` expects.
+    pub fn ahead(&self, tree: &Pubkey, seq: u64) -> bool {
+        match self.trees.get(tree).and_then(|queue| queue.applied) {
+            Some(applied) => seq > applied + 1,
+            None => true,
+        }
+    }
+
+    /// Records that `tree` was applied up to `seq`.
+    pub fn advance(&mut self, tree: Pubkey, seq: u64, now: Instant) {
+        let queue = self
+            .trees
+            .entry(tree)
+            .or_insert_with(|| TreeQueue::new(now));
+        queue.applied = Some(queue.applied.map_or(seq, |applied| applied.max(seq)));
+        queue.last_seen = now;
+    }
+
+    /// Holds `job`, `size` bytes, until the transaction of `seq - 1` on `tree` is applied.
+    /// Returns it when it can be applied now: its predecessor was applied, the tree's seq is
+    /// unknown, it's more than `max_gap` ahead or the buffer is full.
+    pub fn hold(&mut self, tree: Pubkey, seq: u64, size: usize, job: J, now: Instant) -> Option<J> {
+        let full = self.held >= self.config.get_max_held()
+            || self
+                .max_held_bytes
+                .map_or(false, |max| self.held_bytes + size > max);
+        let queue = self
+            .trees
+            .entry(tree)
+            .or_insert_with(|| TreeQueue::new(now));
+        queue.last_seen = now;
+        let gap = match queue.applied {
+            Some(applied) if seq > applied + 1 => seq - applied - 1,
+            // Applied already, or a tree never indexed whose first seqs can't be waited for.
+            _ => return Some(job),
+        };
+        if gap > self.config.get_max_gap() || full {
+            metric! {
+                statsd_count!("ingester.seq_buffer.skipped", 1);
+            }
+            return Some(job);
+        }
+        debug!("Holding seq {} of tree {}, {} seqs behind", seq, tree, gap);
+        // A redelivered transaction replaces the one held, which stays unacknowledged.
+        let replaced = queue.held.insert(
+            seq,
+            Held {
+                since: now,
+                size,
+                job,
+            },
+        );
+        self.held += 1;
+        self.held_bytes += size;
+        if let Some(replaced) = replaced {
+            self.held -= 1;
+            self.held_bytes -= replaced.size;
+        }
+        metric! {
+            statsd_count!("ingester.seq_buffer.buffered", 1);
+        }
+        None
+    }
+
+    /// Records that `tree` was applied up to `seq`, returning the held transactions that are
+    /// next, in seq order.
+    pub fn release(&mut self, tree: Pubkey, seq: u64, now: Instant) -> Vec<J> {
+        self.advance(tree, seq, now);
+        let queue = match self.trees.get_mut(&tree) {
+            Some(queue) => queue,
+            None => return vec![],
+        };
+        let next = queue.applied.unwrap_or(seq) + 1;
+        let rest = queue.held.split_off(&(next + 1));
+        let released = std::mem::replace(&mut queue.held, rest);
+        let released = self.take(released);
+        if !released.is_empty() {
+            metric! {
+                statsd_count!("ingester.seq_buffer.released", released.len() as i64);
+            }
+        }
+        released
+    }
+
+    /// Takes the transactions held for longer than `max_wait`, their predecessors are given up
+    /// on. They're applied in seq order, and release the ones behind them once applied. Trees
+    /// without held transactions that weren't seen for `IDLE_TREE_TTL` are forgotten.
+    pub fn expire(&mut self, now: Instant) -> Vec<J> {
+        let max_wait = self.config.get_max_wait();
+        let mut taken = vec![];
+        for queue in self.trees.values_mut() {
+            let waited = queue
+                .held
+                .iter()
+                .find(|(_, held)| now.saturating_duration_since(held.since) >= max_wait);
+            if let Some((&seq, _)) = waited {
+                // Everything before it goes too, to keep the tree's order.
+                let rest = queue.held.split_off(&(seq + 1));
+                taken.push(std::mem::replace(&mut queue.held, rest));
+            }
+        }
+        let expired: Vec<J> = taken.into_iter().flat_map(|held| self.take(held)).collect();
+        self.trees.retain(|_, queue| {
+            !queue.held.is_empty() || now.saturating_duration_since(queue.last_seen) < IDLE_TREE_TTL
+        });
+        if !expired.is_empty() {
+            metric! {
+                statsd_count!("ingester.seq_buffer.expired", expired.len() as i64);
+            }
+        }
+        expired
+    }
+
+    fn take(&mut self, held: BTreeMap<u64, Held<J>>) -> Vec<J> {
+        held.into_values()
+            .map(|held| {
+                self.held -= 1;
+                self.held_bytes -= held.size;
+                held.job
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TreeSeq {
+    seq: i64,
+}
+
+/// Reorders the transactions of each tree by seq. A transaction whose predecessor hasn't been
+/// applied yet is held until it is, or until it waited `max_wait`. The buffer is shared by the
+/// transaction workers, and the seqs applied by other ingesters are read from the tree's root
+/// node in `cl_items` before a transaction is held.
+pub struct SeqBuffer<J> {
+    poll_interval: Duration,
+    conn: DatabaseConnection,
+    queues: Mutex<SeqQueues<J>>,
+}
+
+impl<J> SeqBuffer<J> {
+    /// `budget_mb` is the transaction stream's memory budget, which held transactions count
+    /// against.
+    pub fn new(config: SeqBufferConfig, pool: PgPool, budget_mb: Option<u64>) -> Self {
+        let max_held_bytes = config.get_max_held_bytes(budget_mb);
+        SeqBuffer {
+            poll_interval: (config.get_max_wait() / 4).max(Duration::from_millis(10)),
+            conn: SqlxPostgresConnector::from_sqlx_postgres_pool(pool),
+            queues: Mutex::new(SeqQueues::new(config, max_held_bytes)),
+        }
+    }
+
+    /// How often the held transactions are checked for expiry.
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Returns `job`, `size` bytes, when it can be applied now, holds it when the transaction of
+    /// `seq - 1` on `tree` hasn't been.
+    pub async fn admit(&self, tree: Pubkey, seq: u64, size: usize, job: J) -> Option<J> {
+        if !self.queues.lock().unwrap().ahead(&tree, seq) {
+            return Some(job);
+        }
+        // Another ingester may have applied the predecessors.
+        match self.applied_in_db(&tree).await {
+            Ok(Some(applied)) => self
+                .queues
+                .lock()
+                .unwrap()
+                .advance(tree, applied, Instant::now()),
+            Ok(None) => {}
+            Err(e) => {
+                error!("Error reading the seq of tree {}: {}", tree, e);
+                return Some(job);
+            }
+        }
+        self.queues
+            .lock()
+            .unwrap()
+            .hold(tree, seq, size, job, Instant::now())
+    }
+
+    /// Records that `tree` was applied up to `seq`, returning the held transactions that are
+    /// next, in seq order.
+    pub fn applied(&self, tree: Pubkey, seq: u64) -> Vec<J> {
+        self.queues
+            .lock()
+            .unwrap()
+            .release(tree, seq, Instant::now())
+    }
+
+    /// Takes the transactions held for longer than `max_wait`, in seq order per tree.
+    pub fn expired(&self) -> Vec<J> {
+        let mut queues = self.queues.lock().unwrap();
+        let expired = queues.expire(Instant::now());
+        metric! {
+            statsd_gauge!("ingester.seq_buffer.held", queues.held() as u64);
+            statsd_gauge!("ingester.seq_buffer.held_bytes", queues.held_bytes() as u64);
+            statsd_gauge!("ingester.seq_buffer.trees", queues.trees() as u64);
+        }
+        expired
+    }
+
+    // The seq of the tree's root node, which every change log overwrites.
+    async fn applied_in_db(&self, tree: &Pubkey) -> Result<Option<u64>, sea_orm::DbErr> {
+        Ok(TreeSeq::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT seq FROM cl_items WHERE tree = $1 AND node_idx = 1",
+            vec![tree.to_bytes().to_vec().into()],
+        ))
+        .one(&self.conn)
+        .await?
+        .map(|row| row.seq.max(0) as u64))
+    }
+}
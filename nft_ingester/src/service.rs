@@ -0,0 +1,479 @@
+use crate::{
+    account_updates::account_worker,
+    ack::{ack_channel, ack_worker},
+    admin::{start_admin_server, LogFilterHandle, PayloadDumper},
+    asset_changes::{change_publisher, ChangeNotifier},
+    authority::authority_resolver,
+    backfiller::setup_backfiller,
+    bootstrap::bootstrap,
+    collection_traits::collection_traits_reconciler,
+    config::{rand_string, IngesterConfig, IngesterRole},
+    database::{check_schema, setup_database},
+    delta_export::delta_exporter,
+    error::IngesterError,
+    finality::finality_tracker,
+    hot_owners::hot_owner_reconciler,
+    memory_budget::MemoryBudget,
+    metric,
+    ownership::ownership_verifier,
+    pending::pending_operations_sweeper,
+    pricing::price_updater,
+    program_transformers::{Assertion, ProgramGroups},
+    redis_failover::RedisEndpoints,
+    reindex::Reindexer,
+    seq_buffer::SeqBuffer,
+    standby::{
+        hold_leader_lock, rewind_to_checkpoints, standby_follower, watch_leader_lock, Standby,
+    },
+    stream::{StreamMonitor, StreamSizeTimer},
+    stream_control::StreamControls,
+    stream_start::start_streams,
+    supervisor::Supervisor,
+    tasks::{BgTask, DownloadMetadataTask, MediaProber, MetadataFetcher, TaskManager},
+    transaction_notifications::transaction_worker,
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use chrono::Duration;
+use log::{error, info};
+use plerkle_messenger::{
+    redis_messenger::RedisMessenger, ConsumptionType, ACCOUNT_STREAM, TRANSACTION_STREAM,
+};
+use std::{future::Future, sync::Arc, time};
+use tokio::{
+    signal::{
+        self,
+        unix::{signal as unix_signal, SignalKind},
+    },
+    task::JoinSet,
+};
+
+/// Completes on Ctrl-C or SIGTERM, which is how container runtimes stop the process, or when
+/// neither can be listened for.
+pub async fn shutdown_signal() {
+    let mut terminate = match unix_signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            error!("Unable to listen for SIGTERM: {}", err);
+            return;
+        }
+    };
+    tokio::select! {
+        res = signal::ctrl_c() => {
+            if let Err(err) = res {
+                error!("Unable to listen for shutdown signal: {}", err);
+            }
+        }
+        _ = terminate.recv() => info!("Got SIGTERM, shutting down"),
+    }
+}
+
+/// Runs the processes of the configured role until `shutdown` completes. The config is replaced
+/// with what `reload` returns when the process gets SIGHUP, which is how the binaries running the
+/// ingester pick their config source.
+pub async fn run<F>(
+    config: IngesterConfig,
+    log_filter: LogFilterHandle,
+    reload: fn() -> Result<IngesterConfig, IngesterError>,
+    shutdown: F,
+) -> Result<(), IngesterError>
+where
+    F: Future<Output = ()>,
+{
+    // One pool many clones, this thing is thread safe and send sync
+    let database_pool = setup_database(config.clone()).await;
+    check_schema(&config, &database_pool).await?;
+
+    // The role determines the processes that get run.
+    let role = config.clone().role.unwrap_or(IngesterRole::All);
+
+    info!("Starting Program with Role {}", role);
+    // Tasks Setup -----------------------------------------------
+    // This joinSet manages all the tasks that are spawned.
+    let mut tasks = JoinSet::new();
+    // The supervisor restarts the role tasks when they fail or the config is reloaded.
+    let mut supervisor = Supervisor::new(role.to_string(), config.clone());
+    supervisor.reload_on_hangup(reload)?;
+    let stream_metrics_timer = Duration::seconds(30).to_std().unwrap();
+
+    // BACKGROUND TASKS --------------------------------------------
+    //Setup definitions for background tasks
+    let task_runner_config = config.bg_task_config.clone().unwrap_or_default();
+    let fetcher = MetadataFetcher::new(&task_runner_config, config.ipfs_gateway.clone())?;
+    let bg_task_definitions: Vec<Box<dyn BgTask>> = vec![Box::new(DownloadMetadataTask {
+        lock_duration: task_runner_config.lock_duration,
+        max_attempts: task_runner_config.max_attempts,
+        timeout: Some(time::Duration::from_secs(
+            task_runner_config.timeout.unwrap_or(3),
+        )),
+        prober: MediaProber::new(&task_runner_config, fetcher.clone()),
+        fetcher,
+        metadata_max_bytes: task_runner_config.metadata_max_bytes,
+        truncation_policy: task_runner_config.metadata_truncation.unwrap_or_default(),
+        blob_ttl: time::Duration::from_secs(task_runner_config.metadata_blob_ttl.unwrap_or(600)),
+    })];
+
+    let mut background_task_manager = TaskManager::new(
+        rand_string(),
+        database_pool.clone(),
+        bg_task_definitions,
+        config.ipfs_gateway.clone(),
+    );
+    // This is how we send new bg tasks
+    let bg_task_listener = background_task_manager
+        .start_listener(role == IngesterRole::BackgroundTaskRunner || role == IngesterRole::All);
+    let bg_task_sender = background_task_manager.get_sender().unwrap();
+    // Always listen for background tasks unless we are the bg task runner
+    if role != IngesterRole::BackgroundTaskRunner {
+        tasks.spawn(bg_task_listener);
+    }
+    let mut timer_acc = StreamSizeTimer::new(
+        stream_metrics_timer,
+        config.messenger_config.clone(),
+        ACCOUNT_STREAM,
+    )?;
+    let mut timer_txn = StreamSizeTimer::new(
+        stream_metrics_timer,
+        config.messenger_config.clone(),
+        TRANSACTION_STREAM,
+    )?;
+
+    if let Some(t) = timer_acc.start::<RedisMessenger>().await {
+        tasks.spawn(t);
+    }
+    if let Some(t) = timer_txn.start::<RedisMessenger>().await {
+        tasks.spawn(t);
+    }
+    let stream_monitor = StreamMonitor::new(
+        config.stream_monitor_config.clone().unwrap_or_default(),
+        &config.messenger_config,
+        vec![ACCOUNT_STREAM, TRANSACTION_STREAM],
+    )?;
+    tasks.spawn(stream_monitor.start());
+
+    // Admin Setup -----------------------------------------------
+    let dumper = PayloadDumper::new(config.get_dump_dir());
+    // Shared by everything reading or writing the streams, so they fail over together.
+    let redis_endpoints = RedisEndpoints::from_config(&config.messenger_config);
+    let stream_controls = StreamControls::new(&[ACCOUNT_STREAM, TRANSACTION_STREAM])
+        .with_redis_endpoints(redis_endpoints.clone());
+    let standby = (role == IngesterRole::Standby).then(Standby::default);
+    if let Some(admin_config) = &config.admin_config {
+        tasks.spawn(start_admin_server(
+            admin_config,
+            log_filter,
+            dumper.clone(),
+            stream_controls.clone(),
+            Reindexer::new(
+                &config,
+                redis_endpoints.clone(),
+                admin_config.get_reindex_max_slots(),
+            ),
+            standby.clone(),
+            database_pool.clone(),
+        )?);
+    }
+
+    // Standby Setup ---------------------------------------------
+    // A standby follows the streams in a consumer group of its own, only writing checkpoints.
+    // Once promoted it replays the streams from its checkpoints and carries on as an ingester.
+    tokio::pin!(shutdown);
+    let standby_config = config.standby_config.clone().unwrap_or_default();
+    let stream_start_config = config.stream_start_config.clone().unwrap_or_default();
+    if let Some(standby) = &standby {
+        for stream in [ACCOUNT_STREAM, TRANSACTION_STREAM] {
+            stream_controls.update(stream, |settings| {
+                settings.consumer_group = Some(standby_config.get_consumer_group())
+            });
+        }
+        start_streams(
+            &stream_start_config,
+            &[ACCOUNT_STREAM, TRANSACTION_STREAM],
+            &config.messenger_config,
+            &stream_controls,
+        )
+        .await?;
+        let mut followers = vec![];
+        for stream in [ACCOUNT_STREAM, TRANSACTION_STREAM] {
+            followers.push(standby_follower::<RedisMessenger>(
+                database_pool.clone(),
+                stream,
+                config.get_messneger_client_config(),
+                stream_controls.clone(),
+                standby_config.clone(),
+                standby.clone(),
+            ));
+        }
+        if standby_config.leader_lock {
+            tasks.spawn(watch_leader_lock(database_pool.clone(), standby.clone()));
+        }
+        let reason = tokio::select! {
+            reason = standby.promoted() => reason,
+            _ = &mut shutdown => {
+                followers.iter().for_each(|follower| follower.abort());
+                supervisor.shutdown().await;
+                tasks.shutdown().await;
+                return Ok(());
+            }
+        };
+        info!(
+            "Standby promoted ({}), replaying the streams from its checkpoints",
+            reason
+        );
+        for follower in followers {
+            let _ = follower.await;
+        }
+        rewind_to_checkpoints(
+            &database_pool,
+            &[ACCOUNT_STREAM, TRANSACTION_STREAM],
+            &config.messenger_config,
+            &stream_controls,
+            &standby_config,
+        )
+        .await?;
+    } else if standby_config.leader_lock
+        && (role == IngesterRole::Ingester || role == IngesterRole::All)
+    {
+        tasks.spawn(hold_leader_lock(database_pool.clone()));
+    }
+
+    // The stream workers and the ownership verifier report the changes they make.
+    let changes = match &config.change_notification_config {
+        Some(notification_config) => {
+            let (publisher, changes) = change_publisher::<RedisMessenger>(
+                config.messenger_config.clone(),
+                notification_config.clone(),
+            );
+            tasks.spawn(publisher);
+            changes
+        }
+        None => ChangeNotifier::default(),
+    };
+    let changes = match &config.delta_export_config {
+        Some(_) => changes.with_export(),
+        None => changes,
+    };
+
+    // Stream Consumers Setup -------------------------------------
+    if role == IngesterRole::Ingester || role == IngesterRole::All {
+        // A promoted standby already replays its group from its checkpoints.
+        start_streams(
+            &stream_start_config,
+            &[ACCOUNT_STREAM, TRANSACTION_STREAM],
+            &config.messenger_config,
+            &stream_controls,
+        )
+        .await?;
+    }
+    if role == IngesterRole::Ingester || role == IngesterRole::All || standby.is_some() {
+        let (ack_sender, ack_receiver) = ack_channel();
+        let controls = stream_controls.clone();
+        supervisor.supervise("ack_worker", move |config| {
+            ack_worker::<RedisMessenger>(
+                config.get_messneger_client_config(),
+                controls.clone(),
+                ack_receiver.clone(),
+            )
+        });
+        // The budgets are shared by the workers of a stream and kept across config reloads.
+        let account_budget = config
+            .account_stream_memory_budget_mb
+            .map(|mb| MemoryBudget::new(ACCOUNT_STREAM, mb));
+        let transaction_budget = config
+            .transaction_stream_memory_budget_mb
+            .map(|mb| MemoryBudget::new(TRANSACTION_STREAM, mb));
+        // One buffer for every transaction worker, so a tree's transactions are ordered whichever
+        // worker reads them.
+        let seq_buffer = config.seq_buffer_config.clone().map(|seq_config| {
+            Arc::new(SeqBuffer::new(
+                seq_config,
+                database_pool.clone(),
+                config.transaction_stream_memory_budget_mb,
+            ))
+        });
+        let assertion = config
+            .transformer_assertion_config
+            .as_ref()
+            .map(|assertion_config| Assertion::new(assertion_config, database_pool.clone()))
+            .transpose()?;
+        for i in 0..config.get_account_stream_worker_count() {
+            // The first worker also picks up messages left pending by a crashed consumer.
+            let redeliver = i == 0;
+            let (pool, bg_task_sender, ack_sender, dumper, budget, changes, controls, assertion) = (
+                database_pool.clone(),
+                bg_task_sender.clone(),
+                ack_sender.clone(),
+                dumper.clone(),
+                account_budget.clone(),
+                changes.clone(),
+                stream_controls.clone(),
+                assertion.clone(),
+            );
+            supervisor.supervise("account_worker", move |config| {
+                account_worker::<RedisMessenger>(
+                    pool.clone(),
+                    config.get_messneger_client_config(),
+                    bg_task_sender.clone(),
+                    ack_sender.clone(),
+                    if redeliver {
+                        ConsumptionType::Redeliver
+                    } else {
+                        ConsumptionType::New
+                    },
+                    dumper.clone(),
+                    budget.clone(),
+                    changes.clone(),
+                    controls.clone(),
+                    assertion.clone(),
+                    ProgramGroups::new(&config.program_groups.clone().unwrap_or_default()),
+                )
+            });
+        }
+        for i in 0..config.get_transaction_stream_worker_count() {
+            let redeliver = i == 0;
+            let (pool, bg_task_sender, ack_sender, dumper, budget, changes, controls, assertion) = (
+                database_pool.clone(),
+                bg_task_sender.clone(),
+                ack_sender.clone(),
+                dumper.clone(),
+                transaction_budget.clone(),
+                changes.clone(),
+                stream_controls.clone(),
+                assertion.clone(),
+            );
+            let seq_buffer = seq_buffer.clone();
+            supervisor.supervise("transaction_worker", move |config| {
+                transaction_worker::<RedisMessenger>(
+                    pool.clone(),
+                    config.get_messneger_client_config(),
+                    bg_task_sender.clone(),
+                    ack_sender.clone(),
+                    if redeliver {
+                        ConsumptionType::Redeliver
+                    } else {
+                        ConsumptionType::New
+                    },
+                    dumper.clone(),
+                    budget.clone(),
+                    changes.clone(),
+                    config.get_record_failed_transactions(),
+                    config.get_transaction_bulkheads(),
+                    controls.clone(),
+                    assertion.clone(),
+                    seq_buffer.clone(),
+                )
+            });
+        }
+    }
+    // Pending operations are swept by a single role so the ingesters don't race on expiry.
+    if role == IngesterRole::BackgroundTaskRunner || role == IngesterRole::All {
+        let pool = database_pool.clone();
+        supervisor.supervise("pending_operations_sweeper", move |config| {
+            pending_operations_sweeper(
+                pool.clone(),
+                time::Duration::from_secs(config.get_pending_operation_ttl()),
+            )
+        });
+        if config.get_authority_resolver_interval() > 0 {
+            let pool = database_pool.clone();
+            supervisor.supervise("authority_resolver", move |config| {
+                authority_resolver(
+                    pool.clone(),
+                    config.get_rpc_url(),
+                    time::Duration::from_secs(config.get_authority_resolver_interval().max(1)),
+                )
+            });
+        }
+        if config.pricing_config.is_some() {
+            let pool = database_pool.clone();
+            supervisor.supervise("price_updater", move |config| {
+                price_updater(
+                    pool.clone(),
+                    config.pricing_config.clone().unwrap_or_default(),
+                )
+            });
+        }
+        if config.ownership_verifier_config.is_some() {
+            let (pool, changes) = (database_pool.clone(), changes.clone());
+            supervisor.supervise("ownership_verifier", move |config| {
+                ownership_verifier(
+                    pool.clone(),
+                    config.ownership_verifier_config.clone().unwrap_or_default(),
+                    changes.clone(),
+                )
+            });
+        }
+        if config.finality_config.is_some() {
+            let pool = database_pool.clone();
+            supervisor.supervise("finality_tracker", move |config| {
+                finality_tracker(
+                    pool.clone(),
+                    config.get_rpc_url(),
+                    config.finality_config.clone().unwrap_or_default(),
+                )
+            });
+        }
+        if config.hot_owners_config.is_some() {
+            let pool = database_pool.clone();
+            supervisor.supervise("hot_owner_reconciler", move |config| {
+                hot_owner_reconciler(
+                    pool.clone(),
+                    config.hot_owners_config.clone().unwrap_or_default(),
+                )
+            });
+        }
+        if config.collection_traits_config.is_some() {
+            let pool = database_pool.clone();
+            supervisor.supervise("collection_traits_reconciler", move |config| {
+                collection_traits_reconciler(
+                    pool.clone(),
+                    config.collection_traits_config.clone().unwrap_or_default(),
+                )
+            });
+        }
+        if config.delta_export_config.is_some() {
+            let pool = database_pool.clone();
+            supervisor.supervise("delta_exporter", move |config| {
+                delta_exporter(
+                    pool.clone(),
+                    config.delta_export_config.clone().unwrap_or_default(),
+                )
+            });
+        }
+    }
+    // Stream Size Timers ----------------------------------------
+    // Setup Stream Size Timers, these are small processes that run every 60 seconds and farm metrics for the size of the streams.
+    // If metrics are disabled, these will not run.
+    if role == IngesterRole::BackgroundTaskRunner || role == IngesterRole::All {
+        supervisor.supervise("background_task_runner", move |config| {
+            background_task_manager.start_runner(config.bg_task_config.clone())
+        });
+    }
+    // Backfiller Setup ------------------------------------------
+    if role == IngesterRole::Backfiller || role == IngesterRole::All {
+        let pool = database_pool.clone();
+        let endpoints = redis_endpoints.clone();
+        supervisor.supervise("backfiller", move |config| {
+            setup_backfiller::<RedisMessenger>(pool.clone(), config.clone(), endpoints.clone())
+        });
+    }
+    // Bootstrap Setup -------------------------------------------
+    // Feeds the streams the ingesters consume, it runs next to the other roles.
+    if role == IngesterRole::Bootstrap {
+        let pool = database_pool.clone();
+        let endpoints = redis_endpoints.clone();
+        supervisor.supervise("bootstrap", move |config| {
+            bootstrap(pool.clone(), config.clone(), endpoints.clone())
+        });
+    }
+
+    let roles_str = role.to_string();
+    metric! {
+        statsd_count!("ingester.startup", 1, "role" => &roles_str);
+    }
+    shutdown.await;
+
+    supervisor.shutdown().await;
+    tasks.shutdown().await;
+
+    Ok(())
+}
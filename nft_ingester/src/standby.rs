@@ -0,0 +1,429 @@
+use crate::{
+    error::IngesterError,
+    metric,
+    redis_failover::GroupProgress,
+    stream_control::{StreamConsumer, StreamControls},
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::{error, info, warn};
+use plerkle_messenger::{ConsumptionType, Messenger, MessengerConfig};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector,
+    Statement,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::sync::Arc;
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{self, Duration, Instant},
+};
+
+const DEFAULT_CONSUMER_GROUP: &str = "ingester-standby";
+// The advisory lock the primaries hold, "das_lead" in ASCII.
+const LEADER_LOCK_KEY: i64 = 0x6461_735f_6c65_6164;
+
+/// A warm standby ingester, e.g. `'{consumer_group="ingester-standby", replay_window=30,
+/// leader_lock=true}'`. The same config on the primaries makes them hold the leader lock.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct StandbyConfig {
+    /// The standby's own consumer group, which leaves the primary's offsets alone.
+    pub consumer_group: Option<String>,
+    /// Seconds of the streams before the checkpoints replayed on promotion, for the messages the
+    /// primary read but hadn't written yet.
+    pub replay_window: Option<u64>,
+    /// Seconds between two checkpoints of a stream.
+    pub checkpoint_interval: Option<u64>,
+    /// The primaries hold a Postgres advisory lock and the standby is promoted once it gets it,
+    /// i.e. once no primary holds it.
+    #[serde(default)]
+    pub leader_lock: bool,
+}
+
+impl StandbyConfig {
+    pub fn get_consumer_group(&self) -> String {
+        self.consumer_group
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CONSUMER_GROUP.to_string())
+    }
+
+    pub fn get_replay_window(&self) -> u64 {
+        self.replay_window.unwrap_or(30)
+    }
+
+    pub fn get_checkpoint_interval(&self) -> u64 {
+        self.checkpoint_interval.unwrap_or(5).max(1)
+    }
+}
+
+/// Whether the standby was promoted, and why. Shared by the admin server, the leader lock
+/// watcher and the stream followers.
+#[derive(Clone)]
+pub struct Standby {
+    promotion: Arc<watch::Sender<Option<String>>>,
+}
+
+impl Default for Standby {
+    fn default() -> Self {
+        Standby {
+            promotion: Arc::new(watch::channel(None).0),
+        }
+    }
+}
+
+impl Standby {
+    /// Promotes the standby, returning false if it already was.
+    pub fn promote(&self, reason: &str) -> bool {
+        let promoted = self.promotion.send_if_modified(|promotion| {
+            if promotion.is_some() {
+                return false;
+            }
+            *promotion = Some(reason.to_string());
+            true
+        });
+        if promoted {
+            warn!("Standby promoted: {}", reason);
+            metric! {
+                statsd_count!("ingester.standby.promoted", 1, "reason" => reason);
+            }
+        }
+        promoted
+    }
+
+    /// Why the standby was promoted, none while it stands by.
+    pub fn promotion(&self) -> Option<String> {
+        self.promotion.borrow().clone()
+    }
+
+    /// Waits for the promotion, returning its reason.
+    pub async fn promoted(&self) -> String {
+        let mut promotion = self.promotion.subscribe();
+        loop {
+            if let Some(reason) = promotion.borrow_and_update().clone() {
+                return reason;
+            }
+            // The sender lives as long as `self`.
+            let _ = promotion.changed().await;
+        }
+    }
+
+    pub async fn status(&self, conn: &DatabaseConnection) -> Result<StandbyStatus, IngesterError> {
+        let checkpoints = Checkpoint::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            "
+SELECT stream, consumer_group, message_id, updated_at::text AS updated_at
+FROM standby_checkpoints
+ORDER BY stream, consumer_group
+"
+            .to_string(),
+        ))
+        .all(conn)
+        .await?;
+        Ok(StandbyStatus {
+            promotion: self.promotion(),
+            checkpoints,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct Checkpoint {
+    pub stream: String,
+    pub consumer_group: String,
+    pub message_id: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StandbyStatus {
+    pub promotion: Option<String>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+async fn write_checkpoint(
+    conn: &DatabaseConnection,
+    stream: &str,
+    group: &str,
+    id: &str,
+) -> Result<(), IngesterError> {
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO standby_checkpoints (stream, consumer_group, message_id, updated_at)
+VALUES ($1, $2, $3, now())
+ON CONFLICT (stream, consumer_group) DO UPDATE
+SET message_id = EXCLUDED.message_id, updated_at = EXCLUDED.updated_at
+",
+        vec![stream.into(), group.into(), id.into()],
+    ))
+    .await?;
+    Ok(())
+}
+
+async fn read_checkpoint(
+    conn: &DatabaseConnection,
+    stream: &str,
+    group: &str,
+) -> Result<Option<String>, IngesterError> {
+    Ok(
+        Checkpoint::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+SELECT stream, consumer_group, message_id, updated_at::text AS updated_at
+FROM standby_checkpoints
+WHERE stream = $1 AND consumer_group = $2
+",
+            vec![stream.into(), group.into()],
+        ))
+        .one(conn)
+        .await?
+        .map(|checkpoint| checkpoint.message_id),
+    )
+}
+
+/// The stream entry id `window` before the entry `checkpoint`. Entry ids start with the
+/// millisecond they were added at.
+pub fn replay_from(checkpoint: &str, window: Duration) -> Option<String> {
+    let millis: u64 = checkpoint.split('-').next()?.parse().ok()?;
+    Some(format!(
+        "{}-0",
+        millis.saturating_sub(window.as_millis() as u64)
+    ))
+}
+
+// The millisecond and sequence number of a stream entry id.
+fn parse_entry_id(id: &str) -> Option<(u64, u64)> {
+    let (millis, seq) = id.split_once('-').unwrap_or((id, "0"));
+    Some((millis.parse().ok()?, seq.parse().ok()?))
+}
+
+/// The entry a consumer group has to be moved to for the entries it hasn't acknowledged to be
+/// delivered again: the one right before its oldest pending entry, or its last delivered one.
+pub fn unacknowledged_from(progress: &GroupProgress) -> Option<String> {
+    match &progress.oldest_pending {
+        Some(pending) => match parse_entry_id(pending)? {
+            (0, 0) => Some("0-0".to_string()),
+            (millis, 0) => Some(format!("{}-{}", millis - 1, u64::MAX)),
+            (millis, seq) => Some(format!("{}-{}", millis, seq - 1)),
+        },
+        None => Some(progress.last_delivered.clone()),
+    }
+}
+
+/// Where a promoted standby replays a stream from: `window` before its checkpoint, or earlier
+/// where the primaries' consumer group still has entries it didn't acknowledge, which the
+/// primaries may have read without writing however long ago.
+pub fn replay_start(
+    checkpoint: &str,
+    window: Duration,
+    primary: Option<&GroupProgress>,
+) -> Option<String> {
+    let from = replay_from(checkpoint, window)?;
+    let primary = match primary.and_then(unacknowledged_from) {
+        Some(primary) => primary,
+        None => return Some(from),
+    };
+    if parse_entry_id(&primary)? < parse_entry_id(&from)? {
+        Some(primary)
+    } else {
+        Some(from)
+    }
+}
+
+/// Reads `stream` in the standby's consumer group without transforming it, acking what it reads
+/// and checkpointing the last entry read, until the standby is promoted. The stream's consumer
+/// group has to be switched to the standby's in `controls` beforehand.
+pub fn standby_follower<T: Messenger>(
+    pool: PgPool,
+    stream: &'static str,
+    messenger_config: MessengerConfig,
+    controls: StreamControls,
+    config: StandbyConfig,
+    standby: Standby,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+        let group = config.get_consumer_group();
+        let interval = Duration::from_secs(config.get_checkpoint_interval());
+        let mut consumer = match StreamConsumer::<T>::new(stream, messenger_config, &controls).await
+        {
+            Ok(consumer) => consumer,
+            Err(e) => {
+                error!("Error connecting standby to stream {}: {}", stream, e);
+                return;
+            }
+        };
+        let (mut last, mut checkpointed_at) = (None::<String>, Instant::now());
+        loop {
+            // Entries read by a cancelled receive are replayed anyway, they're newer than the
+            // checkpoint.
+            let received = tokio::select! {
+                _ = standby.promoted() => break,
+                received = consumer.recv(ConsumptionType::New) => received,
+            };
+            match received {
+                Ok(data) => {
+                    let ids: Vec<String> = data.into_iter().map(|item| item.id).collect();
+                    if let Some(id) = ids.last() {
+                        last = Some(id.clone());
+                        if let Err(e) = consumer.ack(&ids).await {
+                            error!("Error acking standby stream {}: {}", stream, e);
+                        }
+                        metric! {
+                            statsd_count!("ingester.standby.followed", ids.len() as i64, "stream" => stream);
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Error receiving from standby stream {}: {}", stream, e);
+                    metric! {
+                        statsd_count!("ingester.stream.receive_error", 1, "stream" => stream);
+                    }
+                }
+            }
+            if checkpointed_at.elapsed() >= interval {
+                if let Some(id) = last.take() {
+                    if let Err(e) = write_checkpoint(&conn, stream, &group, &id).await {
+                        error!("Error checkpointing standby stream {}: {}", stream, e);
+                        last = Some(id);
+                    }
+                }
+                checkpointed_at = Instant::now();
+            }
+        }
+        // The promotion replays from the checkpoint, so it has to be the last entry read.
+        if let Some(id) = last {
+            if let Err(e) = write_checkpoint(&conn, stream, &group, &id).await {
+                error!("Error checkpointing standby stream {}: {}", stream, e);
+            }
+        }
+    })
+}
+
+/// Rewinds the standby's consumer group on each of `streams` to the replay window before its
+/// checkpoint, so the ingesters started on promotion write what the primary may have missed.
+/// The transformers skip the updates that are already indexed.
+pub async fn rewind_to_checkpoints(
+    pool: &PgPool,
+    streams: &[&'static str],
+    messenger_config: &MessengerConfig,
+    controls: &StreamControls,
+    config: &StandbyConfig,
+) -> Result<(), IngesterError> {
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
+    let group = config.get_consumer_group();
+    let window = Duration::from_secs(config.get_replay_window());
+    for stream in streams {
+        let checkpoint = match read_checkpoint(&conn, stream, &group).await? {
+            Some(checkpoint) => checkpoint,
+            None => {
+                warn!(
+                    "No standby checkpoint for stream {}, consuming it from where the standby is",
+                    stream
+                );
+                continue;
+            }
+        };
+        // The primaries' group only tells more when they read from the same Redis instance.
+        let primary = match controls
+            .config_group_progress(stream, messenger_config)
+            .await
+        {
+            Ok(progress) => progress,
+            Err(e) => {
+                warn!(
+                    "Error reading the primaries' progress on stream {}, replaying from the checkpoint: {}",
+                    stream, e
+                );
+                None
+            }
+        };
+        let from = replay_start(&checkpoint, window, primary.as_ref()).ok_or_else(|| {
+            IngesterError::MessengerError(format!("invalid checkpoint {}", checkpoint))
+        })?;
+        controls
+            .rewind(stream, messenger_config, &from)
+            .await
+            .map_err(|e| IngesterError::MessengerError(e.to_string()))?;
+        info!(
+            "Replaying stream {} from {}, checkpointed at {}",
+            stream, from, checkpoint
+        );
+    }
+    Ok(())
+}
+
+/// Holds the leader lock while the primary runs, on a connection taken out of the pool. The lock
+/// is released with the connection when the primary dies, which promotes the standby. A primary
+/// that loses its connection takes the lock again, or waits for whoever took it meanwhile.
+pub fn hold_leader_lock(pool: PgPool) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(10));
+        let mut held = None;
+        loop {
+            interval.tick().await;
+            if let Some(conn) = held.as_mut() {
+                if sqlx::query("SELECT 1").execute(conn).await.is_ok() {
+                    continue;
+                }
+                warn!("Lost the connection holding the ingester leader lock");
+                held = None;
+            }
+            let mut conn = match pool.acquire().await {
+                Ok(conn) => conn.detach(),
+                Err(e) => {
+                    error!("Error connecting to take the ingester leader lock: {}", e);
+                    continue;
+                }
+            };
+            match sqlx::query("SELECT pg_advisory_lock($1)")
+                .bind(LEADER_LOCK_KEY)
+                .execute(&mut conn)
+                .await
+            {
+                Ok(_) => {
+                    info!("Holding the ingester leader lock");
+                    held = Some(conn);
+                }
+                Err(e) => error!("Error taking the ingester leader lock: {}", e),
+            }
+        }
+    })
+}
+
+/// Promotes the standby once it gets the leader lock, which it then keeps as the new primary.
+pub fn watch_leader_lock(pool: PgPool, standby: Standby) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = time::interval(Duration::from_secs(1));
+        let mut conn = None;
+        loop {
+            interval.tick().await;
+            if conn.is_none() {
+                match pool.acquire().await {
+                    Ok(c) => conn = Some(c.detach()),
+                    Err(e) => {
+                        error!("Error connecting to watch the ingester leader lock: {}", e);
+                        continue;
+                    }
+                }
+            }
+            let locked: Result<bool, _> = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+                .bind(LEADER_LOCK_KEY)
+                .fetch_one(conn.as_mut().unwrap())
+                .await;
+            match locked {
+                Ok(true) => break,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Error watching the ingester leader lock: {}", e);
+                    conn = None;
+                }
+            }
+        }
+        standby.promote("leader lock lost");
+        // Held until the process exits.
+        let _conn = conn;
+        std::future::pending::<()>().await;
+    })
+}
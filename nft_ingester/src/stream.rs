@@ -1,9 +1,17 @@
 
-use crate::{error::IngesterError, metric};
+use crate::{config::StreamMonitorConfig, error::IngesterError, metric};
 use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge};
 
-use log::{error};
+use log::{debug, error, warn};
 use plerkle_messenger::{Messenger, MessengerConfig};
+use redis::{
+    aio::Connection, streams::StreamPendingReply, AsyncCommands, FromRedisValue, RedisResult,
+    Value as RedisValue,
+};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::{
     task::{JoinHandle},
     time::{self, Duration},
@@ -59,3 +67,151 @@ impl StreamSizeTimer {
         None
     }
 }
+
+const CONSUMER_GROUP_NAME: &str = "plerkle";
+
+/// Reads the consumer group state of the streams on an interval and reports stream length,
+/// consumer lag and the age of the oldest pending (delivered but unacked) entry, warning when
+/// the backlog grows past the configured limits.
+pub struct StreamMonitor {
+    config: StreamMonitorConfig,
+    redis_url: String,
+    streams: Vec<&'static str>,
+}
+
+impl StreamMonitor {
+    pub fn new(
+        config: StreamMonitorConfig,
+        messenger_config: &MessengerConfig,
+        streams: Vec<&'static str>,
+    ) -> Result<Self, IngesterError> {
+        let redis_url = messenger_config
+            .connection_config
+            .get("redis_connection_str")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| IngesterError::ConfigurationError {
+                msg: "redis_connection_str is required to monitor streams".to_string(),
+            })?;
+        Ok(Self {
+            config,
+            redis_url,
+            streams,
+        })
+    }
+
+    pub fn start(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(self.config.get_interval()));
+            let mut connection: Option<Connection> = None;
+            loop {
+                interval.tick().await;
+                if connection.is_none() {
+                    let client = redis::Client::open(self.redis_url.as_str());
+                    match client {
+                        Ok(client) => match client.get_async_connection().await {
+                            Ok(c) => connection = Some(c),
+                            Err(e) => error!("Error connecting to redis to monitor streams: {}", e),
+                        },
+                        Err(e) => error!("Invalid redis url to monitor streams: {}", e),
+                    }
+                }
+                let conn = match connection.as_mut() {
+                    Some(conn) => conn,
+                    None => continue,
+                };
+                let mut failed = false;
+                for stream in self.streams.iter() {
+                    if let Err(e) = self.check_stream(conn, stream).await {
+                        metric! {
+                            statsd_count!("ingester.stream_monitor_error", 1, "stream" => stream);
+                        }
+                        error!("Error monitoring stream {}: {}", stream, e);
+                        failed = true;
+                        break;
+                    }
+                }
+                // Reconnect on the next tick in case the connection is gone.
+                if failed {
+                    connection = None;
+                }
+            }
+        })
+    }
+
+    async fn check_stream(&self, conn: &mut Connection, stream: &str) -> RedisResult<()> {
+        let length: u64 = conn.xlen(stream).await?;
+        metric! {
+            statsd_gauge!("ingester.stream.length", length, "stream" => stream);
+        }
+
+        let groups: Vec<HashMap<String, RedisValue>> = redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(stream)
+            .query_async(conn)
+            .await?;
+        let group = groups.iter().find(|g| {
+            g.get("name")
+                .and_then(|v| String::from_redis_value(v).ok())
+                .map_or(false, |name| name == CONSUMER_GROUP_NAME)
+        });
+        let group = match group {
+            Some(group) => group,
+            None => {
+                debug!(
+                    "Stream {} has no {} consumer group yet",
+                    stream, CONSUMER_GROUP_NAME
+                );
+                return Ok(());
+            }
+        };
+
+        // `lag` is only reported by Redis 7 and is nil when Redis can't tell.
+        let lag = group.get("lag").and_then(|v| u64::from_redis_value(v).ok());
+        if let Some(lag) = lag {
+            metric! {
+                statsd_gauge!("ingester.stream.lag", lag, "stream" => stream);
+            }
+            if lag > self.config.get_max_lag() {
+                warn!(
+                    "Stream {} consumers are {} entries behind (limit {})",
+                    stream,
+                    lag,
+                    self.config.get_max_lag()
+                );
+            }
+        }
+
+        let pending: StreamPendingReply = conn.xpending(stream, CONSUMER_GROUP_NAME).await?;
+        metric! {
+            statsd_gauge!("ingester.stream.pending", pending.count() as u64, "stream" => stream);
+        }
+        if let StreamPendingReply::Data(data) = pending {
+            if let Some(age) = entry_age(&data.start_id) {
+                metric! {
+                    statsd_gauge!("ingester.stream.oldest_pending_age_ms", age.as_millis() as u64, "stream" => stream);
+                }
+                if age.as_secs() > self.config.get_max_pending_age() {
+                    warn!(
+                        "Stream {} has {} pending entries, the oldest was added {:?} ago (limit {}s)",
+                        stream,
+                        data.count,
+                        age,
+                        self.config.get_max_pending_age()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Stream entry ids start with the millisecond timestamp they were added at.
+fn entry_age(id: &str) -> Option<Duration> {
+    let added_ms: u64 = id.split('-').next()?.parse().ok()?;
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some(Duration::from_millis(now_ms.saturating_sub(added_ms)))
+}
@@ -0,0 +1,483 @@
+use crate::{
+    memory_budget::MemoryBudget,
+    metric,
+    redis_failover::{GroupProgress, GroupStart, RedisEndpoints},
+};
+use cadence_macros::{is_global_default_set, statsd_gauge};
+use figment::value::Value;
+use log::{info, warn};
+use plerkle_messenger::{ConsumptionType, Messenger, MessengerConfig, MessengerError, RecvData};
+use redis::RedisResult;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{sync::watch, time::sleep};
+
+const BATCH_SIZE_KEY: &str = "batch_size";
+const CONSUMER_GROUP_KEY: &str = "consumer_group_name";
+// The largest batch a consumer with a memory budget reads when neither the stream's settings nor
+// the messenger config set a batch size.
+const DEFAULT_BATCH_SIZE: usize = 100;
+const PRODUCER_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How a stream is consumed, changed at runtime through the admin API. The batch size and
+/// consumer group override the messenger config's until the ingester restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamSettings {
+    #[serde(default)]
+    pub paused: bool,
+    pub batch_size: Option<usize>,
+    pub consumer_group: Option<String>,
+}
+
+impl StreamSettings {
+    /// `config` with the batch size and consumer group overrides applied.
+    pub fn apply(&self, config: &MessengerConfig) -> MessengerConfig {
+        let mut config = config.clone();
+        if let Some(batch_size) = self.batch_size {
+            config
+                .connection_config
+                .insert(BATCH_SIZE_KEY.to_string(), Value::from(batch_size as u64));
+        }
+        if let Some(group) = &self.consumer_group {
+            config
+                .connection_config
+                .insert(CONSUMER_GROUP_KEY.to_string(), Value::from(group.clone()));
+        }
+        config
+    }
+
+    // Whether going from `self` to `next` needs a new messenger.
+    fn reconnects(&self, next: &StreamSettings) -> bool {
+        self.batch_size != next.batch_size || self.consumer_group != next.consumer_group
+    }
+}
+
+/// The settings of each stream, shared by the admin server and the stream workers. They outlive
+/// the workers, so a restarted worker picks them up again.
+#[derive(Clone)]
+pub struct StreamControls {
+    streams: Arc<BTreeMap<&'static str, watch::Sender<StreamSettings>>>,
+    endpoints: RedisEndpoints,
+}
+
+impl StreamControls {
+    pub fn new(streams: &[&'static str]) -> Self {
+        StreamControls {
+            streams: Arc::new(
+                streams
+                    .iter()
+                    .map(|stream| (*stream, watch::channel(StreamSettings::default()).0))
+                    .collect(),
+            ),
+            endpoints: RedisEndpoints::default(),
+        }
+    }
+
+    /// Lets the streams fail over between `endpoints`.
+    pub fn with_redis_endpoints(mut self, endpoints: RedisEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn all(&self) -> BTreeMap<&'static str, StreamSettings> {
+        self.streams
+            .iter()
+            .map(|(stream, settings)| (*stream, settings.borrow().clone()))
+            .collect()
+    }
+
+    pub fn get(&self, stream: &str) -> Option<StreamSettings> {
+        self.streams.get(stream).map(|s| s.borrow().clone())
+    }
+
+    /// Changes a stream's settings, returning them or none for an unknown stream.
+    pub fn update(
+        &self,
+        stream: &str,
+        f: impl FnOnce(&mut StreamSettings),
+    ) -> Option<StreamSettings> {
+        let settings = self.streams.get(stream)?;
+        settings.send_modify(f);
+        Some(settings.borrow().clone())
+    }
+
+    /// Moves `stream`'s consumer group back or forth to the entry `id`, on the Redis instance the
+    /// consumers read from.
+    pub async fn rewind(
+        &self,
+        stream: &str,
+        config: &MessengerConfig,
+        id: &str,
+    ) -> RedisResult<()> {
+        let settings = self.get(stream).unwrap_or_default();
+        let generation = self.endpoints.generation();
+        let config = settings.apply(&self.endpoints.apply(config, generation));
+        self.endpoints
+            .set_group_id(&config, generation, stream, id)
+            .await
+    }
+
+    /// Creates `stream`'s consumer group at the entry `id`, or moves it there once with `reset`
+    /// when it exists, on the Redis instance the consumers read from.
+    pub async fn start_at(
+        &self,
+        stream: &str,
+        config: &MessengerConfig,
+        id: &str,
+        reset: bool,
+    ) -> RedisResult<GroupStart> {
+        let settings = self.get(stream).unwrap_or_default();
+        let generation = self.endpoints.generation();
+        let config = settings.apply(&self.endpoints.apply(config, generation));
+        self.endpoints
+            .create_group(&config, generation, stream, id, reset)
+            .await
+    }
+
+    /// Where the consumer group of `config` itself, not the one the stream's settings switch to,
+    /// stands on `stream`, on the Redis instance the consumers read from.
+    pub async fn config_group_progress(
+        &self,
+        stream: &str,
+        config: &MessengerConfig,
+    ) -> RedisResult<Option<GroupProgress>> {
+        let generation = self.endpoints.generation();
+        let config = self.endpoints.apply(config, generation);
+        self.endpoints
+            .group_progress(&config, generation, stream)
+            .await
+    }
+
+    fn subscribe(&self, stream: &str) -> watch::Receiver<StreamSettings> {
+        match self.streams.get(stream) {
+            Some(settings) => settings.subscribe(),
+            // A stream without controls keeps its defaults.
+            None => watch::channel(StreamSettings::default()).1,
+        }
+    }
+}
+
+/// A messenger for one stream that follows the stream's settings: it waits while the stream is
+/// paused and reconnects when the batch size or consumer group changes. Messages read before a
+/// consumer group change stay pending in the old group, for its other consumers to redeliver.
+/// After enough consecutive errors it fails over to the next Redis instance, along with the other
+/// consumers. With a memory budget, its batches shrink to what the budget holds of the recent
+/// payloads and grow back up to the configured batch size.
+pub struct StreamConsumer<T: Messenger> {
+    stream: &'static str,
+    config: MessengerConfig,
+    settings: watch::Receiver<StreamSettings>,
+    current: StreamSettings,
+    endpoints: RedisEndpoints,
+    // The failover generation of the Redis instance read from, and whether the messenger is
+    // still connected to a previous one.
+    generation: usize,
+    failed_over: bool,
+    errors: u32,
+    budget: Option<MemoryBudget>,
+    messenger: T,
+}
+
+impl<T: Messenger> StreamConsumer<T> {
+    pub async fn new(
+        stream: &'static str,
+        config: MessengerConfig,
+        controls: &StreamControls,
+    ) -> Result<Self, MessengerError> {
+        let mut settings = controls.subscribe(stream);
+        let current = settings.borrow_and_update().clone();
+        let endpoints = controls.endpoints.clone();
+        let generation = endpoints.generation();
+        let endpoint_config = endpoints.apply(&config, generation);
+        if generation != 0 {
+            if let Err(e) = endpoints
+                .resync(&current.apply(&endpoint_config), generation, stream)
+                .await
+            {
+                warn!("Failed to resync stream {}: {}", stream, e);
+            }
+        }
+        // The worker is restarted, and connects to the next instance then.
+        let messenger = match Self::connect(stream, &endpoint_config, &current).await {
+            Ok(messenger) => messenger,
+            Err(e) => {
+                endpoints.fail_over(generation);
+                return Err(e);
+            }
+        };
+        Ok(StreamConsumer {
+            stream,
+            config,
+            settings,
+            current,
+            endpoints,
+            generation,
+            failed_over: false,
+            errors: 0,
+            budget: None,
+            messenger,
+        })
+    }
+
+    /// Adapts the batch size to `budget`, the budget of the stream's workers.
+    pub fn with_memory_budget(mut self, budget: Option<MemoryBudget>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    // `settings` with the batch size the memory budget allows. The batch size only changes once
+    // it is off by half or more, so small swings of the payload sizes don't reconnect.
+    fn adapt(&self, mut settings: StreamSettings) -> StreamSettings {
+        let budget = match &self.budget {
+            Some(budget) => budget,
+            None => return settings,
+        };
+        let max = settings
+            .batch_size
+            .or_else(|| {
+                self.config
+                    .connection_config
+                    .get(BATCH_SIZE_KEY)
+                    .and_then(Value::to_u128)
+                    .map(|size| size as usize)
+            })
+            .unwrap_or(DEFAULT_BATCH_SIZE)
+            .max(1);
+        let current = self.current.batch_size.unwrap_or(max).min(max);
+        let target = budget.batch_size(max);
+        let size = if target * 2 > current && target < current * 2 {
+            current
+        } else {
+            target
+        };
+        // Until it first adapts, the messenger config's batch size is left to apply.
+        if size != max || self.current.batch_size.is_some() {
+            settings.batch_size = Some(size);
+        }
+        settings
+    }
+
+    async fn connect(
+        stream: &'static str,
+        config: &MessengerConfig,
+        settings: &StreamSettings,
+    ) -> Result<T, MessengerError> {
+        let mut messenger = T::new(settings.apply(config)).await?;
+        if settings.consumer_group.is_some() {
+            // Creates the group when it doesn't exist yet.
+            if let Err(e) = messenger.add_stream(stream).await {
+                warn!("Failed to add stream {}: {}", stream, e);
+            }
+        }
+        Ok(messenger)
+    }
+
+    // Applies the latest settings and Redis instance. A failed reconnect is retried on the next
+    // call.
+    async fn refresh(&mut self) -> Result<(), MessengerError> {
+        let next = self.adapt(self.settings.borrow_and_update().clone());
+        let generation = self.endpoints.generation();
+        if generation != self.generation {
+            self.generation = generation;
+            self.failed_over = true;
+        }
+        if self.failed_over {
+            let config = self.endpoints.apply(&self.config, generation);
+            if let Err(e) = self
+                .endpoints
+                .resync(&next.apply(&config), generation, self.stream)
+                .await
+            {
+                warn!("Failed to resync stream {}: {}", self.stream, e);
+            }
+            self.messenger = Self::connect(self.stream, &config, &next).await?;
+            info!(
+                "Stream {} failed over to another Redis instance",
+                self.stream
+            );
+            self.failed_over = false;
+        } else if next == self.current {
+            return Ok(());
+        } else if self.current.reconnects(&next) {
+            let config = self.endpoints.apply(&self.config, self.generation);
+            self.messenger = Self::connect(self.stream, &config, &next).await?;
+            info!(
+                "Reconnected to stream {} with batch size {:?} and consumer group {:?}",
+                self.stream, next.batch_size, next.consumer_group
+            );
+            if let Some(batch_size) = next.batch_size {
+                metric! {
+                    statsd_gauge!("ingester.stream.batch_size", batch_size as u64, "stream" => self.stream);
+                }
+            }
+        }
+        self.current = next;
+        Ok(())
+    }
+
+    // Counts consecutive errors, failing over once there are too many.
+    fn observe<R>(&mut self, result: Result<R, MessengerError>) -> Result<R, MessengerError> {
+        match &result {
+            Ok(_) => self.errors = 0,
+            Err(_) => {
+                self.errors += 1;
+                if self.errors >= self.endpoints.failover_after() {
+                    self.errors = 0;
+                    self.endpoints.fail_over(self.generation);
+                }
+            }
+        }
+        result
+    }
+
+    /// Receives the next batch, once the stream isn't paused.
+    pub async fn recv(
+        &mut self,
+        consumption_type: ConsumptionType,
+    ) -> Result<Vec<RecvData>, MessengerError> {
+        let received = self.recv_unpaused(consumption_type).await;
+        self.observe(received)
+    }
+
+    async fn recv_unpaused(
+        &mut self,
+        consumption_type: ConsumptionType,
+    ) -> Result<Vec<RecvData>, MessengerError> {
+        self.refresh().await?;
+        while self.current.paused {
+            if self.settings.changed().await.is_err() {
+                break;
+            }
+            self.refresh().await?;
+        }
+        self.messenger.recv(self.stream, consumption_type).await
+    }
+
+    pub async fn ack(&mut self, ids: &[String]) -> Result<(), MessengerError> {
+        let acked = match self.refresh().await {
+            Ok(()) => self.messenger.ack_msg(self.stream, ids).await,
+            Err(e) => Err(e),
+        };
+        self.observe(acked)
+    }
+}
+
+/// A messenger writing to the streams that fails over along with the consumers sharing its
+/// `endpoints`, so that what it writes goes to the instance they read. A failed call is retried,
+/// reconnecting first, and after enough consecutive errors on the next instance. It fails once
+/// every instance did.
+pub struct StreamProducer<T: Messenger> {
+    config: MessengerConfig,
+    streams: Vec<&'static str>,
+    buffer_sizes: Vec<(&'static str, usize)>,
+    endpoints: RedisEndpoints,
+    // The failover generation of the Redis instance written to.
+    generation: usize,
+    // Dropped after an error, connected again on the next call.
+    messenger: Option<T>,
+}
+
+impl<T: Messenger> StreamProducer<T> {
+    pub async fn new(
+        config: MessengerConfig,
+        endpoints: RedisEndpoints,
+        streams: &[&'static str],
+    ) -> Result<Self, MessengerError> {
+        let mut producer = StreamProducer {
+            config,
+            streams: streams.to_vec(),
+            buffer_sizes: Vec::new(),
+            generation: endpoints.generation(),
+            endpoints,
+            messenger: None,
+        };
+        let mut attempts = 0;
+        while let Err(e) = producer.connected().await {
+            producer.failed(e, &mut attempts).await?;
+        }
+        Ok(producer)
+    }
+
+    /// Sets the buffer size of `stream`, on this and every later connection.
+    pub async fn set_buffer_size(&mut self, stream: &'static str, size: usize) {
+        self.buffer_sizes.push((stream, size));
+        if let Some(messenger) = self.messenger.as_mut() {
+            messenger.set_buffer_size(stream, size).await;
+        }
+    }
+
+    // The messenger connected to the instance of the current generation.
+    async fn connected(&mut self) -> Result<&mut T, MessengerError> {
+        let generation = self.endpoints.generation();
+        if generation != self.generation {
+            self.generation = generation;
+            self.messenger = None;
+            info!("Stream producer failed over to another Redis instance");
+        }
+        let messenger = match self.messenger.take() {
+            Some(messenger) => messenger,
+            None => {
+                let mut messenger = T::new(self.endpoints.apply(&self.config, generation)).await?;
+                for &stream in self.streams.iter() {
+                    messenger.add_stream(stream).await?;
+                }
+                for &(stream, size) in self.buffer_sizes.iter() {
+                    messenger.set_buffer_size(stream, size).await;
+                }
+                messenger
+            }
+        };
+        Ok(self.messenger.insert(messenger))
+    }
+
+    // Counts a failed attempt at a call, failing over after `failover_after` of them. Returns
+    // the error once every instance failed, otherwise waits before the call is retried.
+    async fn failed(
+        &mut self,
+        e: MessengerError,
+        attempts: &mut u32,
+    ) -> Result<(), MessengerError> {
+        self.messenger = None;
+        *attempts += 1;
+        let failover_after = self.endpoints.failover_after();
+        if *attempts >= failover_after * self.endpoints.instances() as u32 {
+            return Err(e);
+        }
+        warn!("Failed to write to the streams, retrying: {}", e);
+        if *attempts % failover_after == 0 {
+            self.endpoints.fail_over(self.generation);
+        }
+        sleep(PRODUCER_RETRY_INTERVAL).await;
+        Ok(())
+    }
+
+    pub async fn send(&mut self, stream: &'static str, bytes: &[u8]) -> Result<(), MessengerError> {
+        let mut attempts = 0;
+        loop {
+            let sent = match self.connected().await {
+                Ok(messenger) => messenger.send(stream, bytes).await,
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(()) => return Ok(()),
+                Err(e) => self.failed(e, &mut attempts).await?,
+            }
+        }
+    }
+
+    /// The number of entries in `stream`, on the instance written to.
+    pub async fn stream_size(&mut self, stream: &'static str) -> Result<u64, MessengerError> {
+        let mut attempts = 0;
+        loop {
+            let size = match self.connected().await {
+                Ok(messenger) => messenger.stream_size(stream).await,
+                Err(e) => Err(e),
+            };
+            match size {
+                Ok(size) => return Ok(size),
+                Err(e) => self.failed(e, &mut attempts).await?,
+            }
+        }
+    }
+}
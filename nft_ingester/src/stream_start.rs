@@ -0,0 +1,112 @@
+use crate::{error::IngesterError, redis_failover::GroupStart, stream_control::StreamControls};
+use log::info;
+use plerkle_messenger::MessengerConfig;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// Where a consumer group that doesn't exist yet starts reading a stream from. Without it the
+/// messenger creates the group at whatever its default is when the first consumer connects.
+/// Groups created on a backup Redis instance when the consumers fail over ignore it, they start
+/// from the beginning of the stream so that nothing is lost.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct StreamStartConfig {
+    /// `beginning`, `latest` or the id of a stream entry, e.g. `1690000000000-0`, to start after.
+    pub offset: Option<String>,
+    /// Moves consumer groups that already exist to the offset too, once per offset, instead of
+    /// leaving them where they are.
+    #[serde(default)]
+    pub reset_existing_group: bool,
+}
+
+impl StreamStartConfig {
+    pub fn get_offset(&self) -> Result<Option<StreamOffset>, IngesterError> {
+        self.offset.as_deref().map(str::parse).transpose()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamOffset {
+    /// Every entry still in the stream.
+    Beginning,
+    /// Only the entries added after the group is created.
+    Latest,
+    /// The entries after this id.
+    Id(String),
+}
+
+impl StreamOffset {
+    /// The id `XGROUP` takes for the offset.
+    pub fn id(&self) -> &str {
+        match self {
+            StreamOffset::Beginning => "0",
+            StreamOffset::Latest => "$",
+            StreamOffset::Id(id) => id,
+        }
+    }
+}
+
+impl FromStr for StreamOffset {
+    type Err = IngesterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beginning" | "0" => Ok(StreamOffset::Beginning),
+            "latest" | "$" => Ok(StreamOffset::Latest),
+            id if is_stream_id(id) => Ok(StreamOffset::Id(id.to_string())),
+            _ => Err(IngesterError::ConfigurationError {
+                msg: format!(
+                    "stream offset {} isn't beginning, latest or a stream entry id",
+                    s
+                ),
+            }),
+        }
+    }
+}
+
+/// A millisecond timestamp, optionally followed by a dash and a sequence number.
+pub fn is_stream_id(id: &str) -> bool {
+    let mut parts = id.splitn(2, '-');
+    let is_number = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    parts.next().map_or(false, is_number) && parts.next().map_or(true, is_number)
+}
+
+/// Creates the consumer group of each of `streams` at the configured offset before the consumers
+/// connect, or moves it there the first time it starts with `reset_existing_group` and that
+/// offset. Groups that exist are otherwise left at their offsets, so restarting an ingester
+/// doesn't skip or replay anything.
+pub async fn start_streams(
+    config: &StreamStartConfig,
+    streams: &[&'static str],
+    messenger_config: &MessengerConfig,
+    controls: &StreamControls,
+) -> Result<(), IngesterError> {
+    let offset = match config.get_offset()? {
+        Some(offset) => offset,
+        None => return Ok(()),
+    };
+    for stream in streams {
+        let start = controls
+            .start_at(
+                stream,
+                messenger_config,
+                offset.id(),
+                config.reset_existing_group,
+            )
+            .await
+            .map_err(|e| IngesterError::MessengerError(e.to_string()))?;
+        match start {
+            GroupStart::Created => info!(
+                "Consumer group of stream {} created at {:?}",
+                stream, offset
+            ),
+            GroupStart::Moved => {
+                info!("Consumer group of stream {} moved to {:?}", stream, offset)
+            }
+            GroupStart::Kept => info!(
+                "Consumer group of stream {} already exists, consuming it from where it is",
+                stream
+            ),
+        }
+    }
+    Ok(())
+}
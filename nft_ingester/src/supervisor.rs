@@ -0,0 +1,133 @@
+use crate::{config::IngesterConfig, error::IngesterError, metric};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::{error, info, warn};
+use std::sync::Arc;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+    task::{JoinHandle, JoinSet},
+    time::{sleep, Duration, Instant},
+};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// A task that stayed up this long before failing starts over from the shortest backoff.
+const HEALTHY_AFTER: Duration = Duration::from_secs(300);
+
+/// Owns the long running tasks of the ingester's roles. A task that panics or returns is
+/// restarted with exponential backoff, and every task is restarted with the new config when it
+/// is reloaded on SIGHUP, e.g. to switch RPC or Redis without restarting the process. Which
+/// tasks run is decided at startup, so changing the role still needs a restart.
+pub struct Supervisor {
+    role: String,
+    config: Arc<watch::Sender<IngesterConfig>>,
+    tasks: JoinSet<()>,
+}
+
+impl Supervisor {
+    pub fn new(role: String, config: IngesterConfig) -> Self {
+        let (config, _) = watch::channel(config);
+        Supervisor {
+            role,
+            config: Arc::new(config),
+            tasks: JoinSet::new(),
+        }
+    }
+
+    /// Runs the task `start` spawns, spawning it again whenever it stops or the config changes.
+    pub fn supervise<F>(&mut self, name: &'static str, start: F)
+    where
+        F: Fn(&IngesterConfig) -> JoinHandle<()> + Send + 'static,
+    {
+        self.tasks.spawn(supervise_task(
+            self.role.clone(),
+            name,
+            self.config.subscribe(),
+            start,
+        ));
+    }
+
+    /// Reloads the config with `load` when the process gets SIGHUP. A config that fails to load
+    /// is logged and the tasks keep running with the current one.
+    pub fn reload_on_hangup(
+        &mut self,
+        load: fn() -> Result<IngesterConfig, IngesterError>,
+    ) -> Result<(), std::io::Error> {
+        let mut hangups = signal(SignalKind::hangup())?;
+        let config = Arc::clone(&self.config);
+        self.tasks.spawn(async move {
+            while hangups.recv().await.is_some() {
+                match load() {
+                    Ok(new_config) => {
+                        info!("Reloaded config, restarting supervised tasks");
+                        config.send_replace(new_config);
+                    }
+                    Err(e) => error!("Failed to reload config, keeping the current one: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub async fn shutdown(mut self) {
+        self.tasks.shutdown().await;
+    }
+}
+
+// Aborts the supervised task when the supervisor's own task is aborted on shutdown.
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn supervise_task<F>(
+    role: String,
+    name: &'static str,
+    mut config: watch::Receiver<IngesterConfig>,
+    start: F,
+) where
+    F: Fn(&IngesterConfig) -> JoinHandle<()>,
+{
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let started = Instant::now();
+        let current = config.borrow_and_update().clone();
+        let mut task = AbortOnDrop(start(&current));
+        let reason = tokio::select! {
+            res = &mut task.0 => match res {
+                Ok(()) => "exited",
+                Err(e) if e.is_panic() => "panic",
+                Err(_) => "cancelled",
+            },
+            changed = config.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+                "reload"
+            }
+        };
+        metric! {
+            statsd_count!("ingester.supervisor.restart", 1, "role" => &role, "task" => name, "reason" => reason);
+        }
+
+        if reason == "reload" {
+            info!("Restarting {} with the reloaded config", name);
+            task.0.abort();
+            let _ = (&mut task.0).await;
+            continue;
+        }
+
+        if started.elapsed() >= HEALTHY_AFTER {
+            backoff = MIN_BACKOFF;
+        }
+        warn!(
+            "Supervised task {} stopped ({}), restarting in {:?}",
+            name, reason, backoff
+        );
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{
+    error::IngesterError,
+    metric,
+    tasks::{BgTaskConfig, HostLimiter, MediaHead},
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::{debug, warn};
+use reqwest::{header::RANGE, Client, ClientBuilder, Proxy, StatusCode};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use url::Url;
+
+const GATEWAY_FAILURE_THRESHOLD: u32 = 5;
+const GATEWAY_COOLDOWN: u64 = 30;
+const ARWEAVE_HOSTS: [&str; 2] = ["arweave.net", "www.arweave.net"];
+
+/// A content addressed location that can be served by any gateway of the matching kind.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ContentPath {
+    /// `<cid>[/path]`
+    Ipfs(String),
+    /// `<tx id>[/path]`
+    Arweave(String),
+}
+
+impl ContentPath {
+    /// The content addressed path of `uri`, `None` when no gateway can serve it.
+    pub fn parse(uri: &str) -> Option<ContentPath> {
+        let url = Url::parse(uri).ok()?;
+        let mut path = url.path().trim_start_matches('/').to_string();
+        if let Some(query) = url.query() {
+            path = format!("{}?{}", path, query);
+        }
+        match url.scheme() {
+            "ipfs" => {
+                // `ipfs://<cid>/path` puts the cid in the host, `ipfs://ipfs/<cid>` is also seen in the wild.
+                let host = url.host_str().unwrap_or_default();
+                let full = if host.is_empty() || host == "ipfs" {
+                    path
+                } else if path.is_empty() {
+                    host.to_string()
+                } else {
+                    format!("{}/{}", host, path)
+                };
+                (!full.is_empty()).then(|| ContentPath::Ipfs(full))
+            }
+            "ar" => {
+                let host = url.host_str().unwrap_or_default();
+                let full = if path.is_empty() {
+                    host.to_string()
+                } else {
+                    format!("{}/{}", host, path)
+                };
+                (!full.is_empty()).then(|| ContentPath::Arweave(full))
+            }
+            "http" | "https" => {
+                let host = url.host_str()?;
+                if let Some(rest) = path.strip_prefix("ipfs/") {
+                    return (!rest.is_empty()).then(|| ContentPath::Ipfs(rest.to_string()));
+                }
+                // Subdomain style gateways, e.g. `https://<cid>.ipfs.nftstorage.link/path`.
+                if let Some((cid, _)) = host.split_once(".ipfs.") {
+                    return Some(ContentPath::Ipfs(if path.is_empty() {
+                        cid.to_string()
+                    } else {
+                        format!("{}/{}", cid, path)
+                    }));
+                }
+                if ARWEAVE_HOSTS.contains(&host) && !path.is_empty() {
+                    return Some(ContentPath::Arweave(path));
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    // Set while the single request probing a half open gateway is out, until when another
+    // one may probe it should that request never report back.
+    probe_until: Option<Instant>,
+}
+
+/// A single gateway with its own circuit breaker. After `failure_threshold` consecutive
+/// failures the gateway is skipped until `cooldown` has elapsed, at which point a single
+/// request is let through to probe it again. The gateway closes when the probe succeeds and
+/// opens for another `cooldown` when it fails.
+#[derive(Debug)]
+pub struct Gateway {
+    base: String,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl Gateway {
+    pub fn new(base: &str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Gateway {
+            base: base.trim_end_matches('/').to_string(),
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a request may go to the gateway. Once the cooldown of an open gateway elapsed,
+    /// only the request this returns true for probes it.
+    pub fn is_available(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match state.open_until {
+            Some(until) if now < until => false,
+            Some(_) => match state.probe_until {
+                Some(until) if now < until => false,
+                _ => {
+                    // Half open, let this request probe the gateway.
+                    state.probe_until = Some(now + self.cooldown);
+                    true
+                }
+            },
+            None => true,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures = 0;
+        state.open_until = None;
+        state.probe_until = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        metric! {
+            statsd_count!("ingester.bgtask.gateway_failure", 1, "gateway" => &self.base);
+        }
+        if state.probe_until.take().is_some() {
+            // The probe failed, open the gateway again.
+            state.open_until = Some(Instant::now() + self.cooldown);
+            return;
+        }
+        if state.consecutive_failures >= self.failure_threshold && state.open_until.is_none() {
+            warn!(
+                "Gateway {} failed {} times in a row, skipping it for {:?}",
+                self.base, state.consecutive_failures, self.cooldown
+            );
+            metric! {
+                statsd_count!("ingester.bgtask.gateway_open", 1, "gateway" => &self.base);
+            }
+            state.open_until = Some(Instant::now() + self.cooldown);
+        }
+    }
+}
+
+/// Fetches off-chain metadata, optionally through an outbound proxy, falling back across an
+/// ordered list of IPFS and Arweave gateways for content addressed URIs. Requests are rate
+/// limited per host.
+#[derive(Debug, Clone)]
+pub struct MetadataFetcher {
+    client: Client,
+    ipfs_gateways: Arc<Vec<Gateway>>,
+    arweave_gateways: Arc<Vec<Gateway>>,
+    limiter: Arc<HostLimiter>,
+}
+
+impl MetadataFetcher {
+    /// `ipfs_gateway` is the legacy single gateway setting, it is tried before any gateway in
+    /// `config.ipfs_gateways`.
+    pub fn new(config: &BgTaskConfig, ipfs_gateway: Option<String>) -> Result<Self, IngesterError> {
+        let mut builder = ClientBuilder::new();
+        if let Some(proxy) = &config.http_proxy {
+            let proxy = Proxy::all(proxy).map_err(|e| IngesterError::ConfigurationError {
+                msg: format!("Invalid metadata http proxy {}: {}", proxy, e),
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder.build()?;
+
+        let threshold = config
+            .gateway_failure_threshold
+            .unwrap_or(GATEWAY_FAILURE_THRESHOLD)
+            .max(1);
+        let cooldown = Duration::from_secs(config.gateway_cooldown.unwrap_or(GATEWAY_COOLDOWN));
+        let build = |gateways: Vec<String>| -> Vec<Gateway> {
+            let mut out: Vec<Gateway> = Vec::new();
+            for g in gateways {
+                let gateway = Gateway::new(&g, threshold, cooldown);
+                if !out.iter().any(|o| o.base == gateway.base) {
+                    out.push(gateway);
+                }
+            }
+            out
+        };
+
+        let ipfs = ipfs_gateway
+            .into_iter()
+            .chain(config.ipfs_gateways.clone().unwrap_or_default())
+            .collect();
+        Ok(MetadataFetcher {
+            client,
+            ipfs_gateways: Arc::new(build(ipfs)),
+            arweave_gateways: Arc::new(build(config.arweave_gateways.clone().unwrap_or_default())),
+            limiter: Arc::new(HostLimiter::new(config)?),
+        })
+    }
+
+    /// Builds the ordered list of `(url, gateway)` pairs to try for `uri`. The original uri is
+    /// always tried last when it is itself fetchable.
+    fn candidates<'a>(&'a self, uri: &str) -> Vec<(String, Option<&'a Gateway>)> {
+        let mut candidates = Vec::new();
+        match ContentPath::parse(uri) {
+            Some(ContentPath::Ipfs(path)) => {
+                for g in self.ipfs_gateways.iter() {
+                    candidates.push((format!("{}/ipfs/{}", g.base, path), Some(g)));
+                }
+            }
+            Some(ContentPath::Arweave(path)) => {
+                for g in self.arweave_gateways.iter() {
+                    candidates.push((format!("{}/{}", g.base, path), Some(g)));
+                }
+            }
+            None => {}
+        }
+        if (uri.starts_with("http://") || uri.starts_with("https://"))
+            && !candidates.iter().any(|(c, _)| c == uri)
+        {
+            candidates.push((uri.to_string(), None));
+        }
+        candidates
+    }
+
+    pub async fn fetch(
+        &self,
+        uri: &str,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, IngesterError> {
+        let mut last_err =
+            IngesterError::BatchInitNetworkingError(format!("No gateway available for {}", uri));
+        for (url, gateway) in self.candidates(uri) {
+            if let Some(g) = gateway {
+                if !g.is_available() {
+                    debug!("Skipping open gateway {} for {}", g.base, uri);
+                    continue;
+                }
+            }
+            let _permit = self.limiter.acquire(&url).await;
+            let response = self.client.get(&url).timeout(timeout).send().await;
+            let err = match response {
+                Ok(response) if response.status() == StatusCode::OK => {
+                    match response.json::<serde_json::Value>().await {
+                        Ok(val) => {
+                            if let Some(g) = gateway {
+                                g.record_success();
+                            }
+                            return Ok(val);
+                        }
+                        // The gateway answered, the content itself is bad so don't blame it.
+                        Err(e) => {
+                            if let Some(g) = gateway {
+                                g.record_success();
+                            }
+                            return Err(e.into());
+                        }
+                    }
+                }
+                Ok(response) => status_error(gateway, response.status()),
+                Err(e) => {
+                    if let Some(g) = gateway {
+                        g.record_failure();
+                    }
+                    e.into()
+                }
+            };
+            debug!("Fetching {} failed: {}", url, err);
+            last_err = err;
+        }
+        Err(last_err)
+    }
+
+    /// The content type and size of the file at `uri`, without downloading it. Hosts that refuse
+    /// `HEAD` requests get a `GET` of its first byte instead.
+    pub async fn probe(&self, uri: &str, timeout: Duration) -> Result<MediaHead, IngesterError> {
+        let mut last_err =
+            IngesterError::BatchInitNetworkingError(format!("No gateway available for {}", uri));
+        for (url, gateway) in self.candidates(uri) {
+            if let Some(g) = gateway {
+                if !g.is_available() {
+                    debug!("Skipping open gateway {} for {}", g.base, uri);
+                    continue;
+                }
+            }
+            let _permit = self.limiter.acquire(&url).await;
+            let mut response = self.client.head(&url).timeout(timeout).send().await;
+            if let Ok(r) = &response {
+                if r.status() == StatusCode::METHOD_NOT_ALLOWED
+                    || r.status() == StatusCode::FORBIDDEN
+                {
+                    response = self
+                        .client
+                        .get(&url)
+                        .header(RANGE, "bytes=0-0")
+                        .timeout(timeout)
+                        .send()
+                        .await;
+                }
+            }
+            let err = match response {
+                Ok(response) if response.status().is_success() => {
+                    if let Some(g) = gateway {
+                        g.record_success();
+                    }
+                    return Ok(MediaHead::from_headers(
+                        response.status(),
+                        response.headers(),
+                    ));
+                }
+                Ok(response) => status_error(gateway, response.status()),
+                Err(e) => {
+                    if let Some(g) = gateway {
+                        g.record_failure();
+                    }
+                    e.into()
+                }
+            };
+            debug!("Probing {} failed: {}", url, err);
+            last_err = err;
+        }
+        Err(last_err)
+    }
+}
+
+fn status_error(gateway: Option<&Gateway>, status: StatusCode) -> IngesterError {
+    // Only server side trouble counts against the gateway, a 404 from one gateway may simply
+    // mean the content isn't pinned there and shows the gateway is up.
+    if let Some(g) = gateway {
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            g.record_failure();
+        } else {
+            g.record_success();
+        }
+    }
+    IngesterError::HttpError {
+        status_code: status.as_str().to_string(),
+    }
+}
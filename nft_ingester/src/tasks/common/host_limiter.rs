@@ -0,0 +1,257 @@
+use crate::{error::IngesterError, metric, tasks::BgTaskConfig};
+use cadence_macros::{is_global_default_set, statsd_count};
+use log::warn;
+use redis::aio::MultiplexedConnection;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{Mutex as AsyncMutex, OwnedSemaphorePermit, Semaphore},
+    time::sleep,
+};
+use url::Url;
+
+const DEFAULT_HOST_CONCURRENCY: usize = 20;
+const RPS_KEY_PREFIX: &str = "das:bgtask:host_rps";
+// Hosts without requests for this long are forgotten, metadata points at countless hosts.
+const IDLE_HOST_TTL: Duration = Duration::from_secs(600);
+// How long requests count locally after the shared Redis failed, before reconnecting.
+const REDIS_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Limits on the metadata requests to a single host. Concurrency is limited per runner, the
+/// requests per second across all runners when they share a Redis.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct HostLimit {
+    pub max_concurrency: Option<usize>,
+    pub max_rps: Option<u32>,
+}
+
+struct HostState {
+    concurrency: Option<Arc<Semaphore>>,
+    max_rps: Option<u32>,
+    // Requests made in the current second, when limiting locally.
+    window: Mutex<(u64, u32)>,
+}
+
+impl HostState {
+    // Whether no request holds or waits for the state, so it can be dropped without letting
+    // more requests through than the limit.
+    fn is_unused(self: &Arc<Self>) -> bool {
+        Arc::strong_count(self) == 1
+            && self
+                .concurrency
+                .as_ref()
+                .map_or(true, |semaphore| Arc::strong_count(semaphore) == 1)
+    }
+}
+
+struct Hosts {
+    states: HashMap<String, (Arc<HostState>, Instant)>,
+    last_sweep: Instant,
+}
+
+impl Hosts {
+    // Drops the hosts unused for `IDLE_HOST_TTL`, at most once per that long.
+    fn evict_idle(&mut self, now: Instant) {
+        if now.duration_since(self.last_sweep) < IDLE_HOST_TTL {
+            return;
+        }
+        self.states.retain(|_, (state, last_used)| {
+            now.duration_since(*last_used) < IDLE_HOST_TTL || !state.is_unused()
+        });
+        self.last_sweep = now;
+    }
+}
+
+// The shared Redis connection, reused by every request, or when the last attempt failed.
+enum RedisConnection {
+    Connected(MultiplexedConnection),
+    Failed(Instant),
+}
+
+/// Held for the duration of a request to a limited host.
+pub struct HostPermit {
+    _concurrency: Option<OwnedSemaphorePermit>,
+}
+
+/// Keeps a collection mint from flooding its metadata host with downloads. A host without its
+/// own limit gets the default one, and a host's subdomains share its limit so subdomain style
+/// gateways are limited as a whole.
+pub struct HostLimiter {
+    default_limit: HostLimit,
+    limits: HashMap<String, HostLimit>,
+    hosts: Mutex<Hosts>,
+    redis: Option<redis::Client>,
+    connection: AsyncMutex<Option<RedisConnection>>,
+}
+
+impl fmt::Debug for HostLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostLimiter")
+            .field("default_limit", &self.default_limit)
+            .field("limits", &self.limits)
+            .field("shared", &self.redis.is_some())
+            .finish()
+    }
+}
+
+impl HostLimiter {
+    pub fn new(config: &BgTaskConfig) -> Result<Self, IngesterError> {
+        let default_limit = config.default_host_limit.unwrap_or(HostLimit {
+            max_concurrency: Some(DEFAULT_HOST_CONCURRENCY),
+            max_rps: None,
+        });
+        let limits = config
+            .host_limits
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(host, limit)| (host.to_lowercase(), limit))
+            .collect();
+        let redis = config
+            .host_limit_redis_url
+            .as_ref()
+            .map(|url| {
+                redis::Client::open(url.as_str()).map_err(|e| IngesterError::ConfigurationError {
+                    msg: format!("Invalid host limit redis url: {}", e),
+                })
+            })
+            .transpose()?;
+        Ok(HostLimiter {
+            default_limit,
+            limits,
+            hosts: Mutex::new(Hosts {
+                states: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+            redis,
+            connection: AsyncMutex::new(None),
+        })
+    }
+
+    /// Waits until a request to the host of `url` is allowed. Returns `None` for urls without a
+    /// host, which aren't limited.
+    pub async fn acquire(&self, url: &str) -> Option<HostPermit> {
+        let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+        let (key, state) = self.state(&host);
+
+        let concurrency = match &state.concurrency {
+            Some(semaphore) => {
+                if semaphore.available_permits() == 0 {
+                    metric! {
+                        statsd_count!("ingester.bgtask.host_throttled", 1, "host" => &key, "reason" => "concurrency");
+                    }
+                }
+                semaphore.clone().acquire_owned().await.ok()
+            }
+            None => None,
+        };
+        if let Some(max_rps) = state.max_rps {
+            loop {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                if self
+                    .take_request(&key, &state, now.as_secs(), max_rps)
+                    .await
+                {
+                    break;
+                }
+                metric! {
+                    statsd_count!("ingester.bgtask.host_throttled", 1, "host" => &key, "reason" => "rps");
+                }
+                sleep(Duration::from_nanos(
+                    1_000_000_000 - now.subsec_nanos() as u64,
+                ))
+                .await;
+            }
+        }
+        Some(HostPermit {
+            _concurrency: concurrency,
+        })
+    }
+
+    // The configured host `host` falls under, or the host itself, with its state.
+    fn state(&self, host: &str) -> (String, Arc<HostState>) {
+        let (key, limit) = self
+            .limits
+            .iter()
+            .filter(|(key, _)| host == key.as_str() || host.ends_with(&format!(".{}", key)))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(key, limit)| (key.clone(), *limit))
+            .unwrap_or_else(|| (host.to_string(), self.default_limit));
+        let now = Instant::now();
+        let mut hosts = self.hosts.lock().unwrap();
+        hosts.evict_idle(now);
+        let (state, last_used) = hosts.states.entry(key.clone()).or_insert_with(|| {
+            let state = Arc::new(HostState {
+                concurrency: limit
+                    .max_concurrency
+                    .map(|n| Arc::new(Semaphore::new(n.max(1)))),
+                max_rps: limit.max_rps,
+                window: Mutex::new((0, 0)),
+            });
+            (state, now)
+        });
+        *last_used = now;
+        (key, Arc::clone(state))
+    }
+
+    // Counts a request against the host's budget for second `now`, in Redis when it is shared.
+    // A Redis failure falls back to the local count rather than stalling downloads.
+    async fn take_request(&self, key: &str, state: &HostState, now: u64, max_rps: u32) -> bool {
+        if let Some(mut connection) = self.connection().await {
+            let window = format!("{}:{}:{}", RPS_KEY_PREFIX, key, now);
+            let res: Result<(u32,), _> = redis::pipe()
+                .atomic()
+                .incr(&window, 1)
+                .expire(&window, 2)
+                .ignore()
+                .query_async(&mut connection)
+                .await;
+            match res {
+                Ok((count,)) => return count <= max_rps,
+                Err(e) => {
+                    warn!("Failed to count request in host limit redis: {}", e);
+                    *self.connection.lock().await = Some(RedisConnection::Failed(Instant::now()));
+                }
+            }
+        }
+
+        let mut window = state.window.lock().unwrap();
+        if window.0 != now {
+            *window = (now, 0);
+        }
+        window.1 += 1;
+        window.1 <= max_rps
+    }
+
+    // The shared connection, connecting on first use and again `REDIS_RETRY_INTERVAL` after a
+    // failure, so an unreachable Redis isn't dialed on every request.
+    async fn connection(&self) -> Option<MultiplexedConnection> {
+        let client = self.redis.as_ref()?;
+        let mut connection = self.connection.lock().await;
+        match &*connection {
+            Some(RedisConnection::Connected(c)) => return Some(c.clone()),
+            Some(RedisConnection::Failed(at)) if at.elapsed() < REDIS_RETRY_INTERVAL => {
+                return None
+            }
+            _ => {}
+        }
+        match client.get_multiplexed_async_connection().await {
+            Ok(c) => {
+                *connection = Some(RedisConnection::Connected(c.clone()));
+                Some(c)
+            }
+            Err(e) => {
+                warn!("Failed to connect to host limit redis: {}", e);
+                *connection = Some(RedisConnection::Failed(Instant::now()));
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    metric,
+    tasks::{BgTaskConfig, MetadataFetcher},
+};
+use cadence_macros::{is_global_default_set, statsd_count};
+use digital_asset_types::json::ProbedFile;
+use futures::future::join_all;
+use log::debug;
+use reqwest::{
+    header::{HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE},
+    StatusCode,
+};
+use serde_json::Value;
+use std::time::Duration;
+
+const PROBE_MAX_FILES: usize = 4;
+// Content types that say nothing about the file, the guess from its extension is better.
+const GENERIC_MIMES: [&str; 3] = [
+    "application/octet-stream",
+    "binary/octet-stream",
+    "application/unknown",
+];
+
+/// What the response headers of a file tell about it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MediaHead {
+    pub mime: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl MediaHead {
+    /// Reads the headers of a `HEAD` response or of a ranged `GET`, whose `Content-Range` has
+    /// the full size where `Content-Length` only counts the range.
+    pub fn from_headers(status: StatusCode, headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        let mime = header(CONTENT_TYPE)
+            .and_then(|t| t.split(';').next())
+            .map(|t| t.trim().to_ascii_lowercase())
+            .filter(|t| t.contains('/') && !GENERIC_MIMES.contains(&t.as_str()));
+        let size = if status == StatusCode::PARTIAL_CONTENT {
+            header(CONTENT_RANGE)
+                .and_then(|r| r.rsplit_once('/'))
+                .and_then(|(_, total)| total.trim().parse().ok())
+        } else {
+            header(CONTENT_LENGTH).and_then(|l| l.trim().parse().ok())
+        };
+        MediaHead { mime, size }
+    }
+}
+
+/// The image, the animation and the other files of off-chain metadata, in that order and
+/// without duplicates. Only URIs the fetcher can request are kept.
+pub fn primary_file_uris(metadata: &Value, max_files: usize) -> Vec<String> {
+    let files = metadata
+        .pointer("/properties/files")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|f| match f {
+            Value::String(uri) => Some(uri.as_str()),
+            // Some assets specify `url` instead of `uri`.
+            Value::Object(f) => f
+                .get("uri")
+                .or_else(|| f.get("url"))
+                .and_then(Value::as_str),
+            _ => None,
+        });
+    let mut uris: Vec<String> = Vec::new();
+    for uri in metadata
+        .get("image")
+        .and_then(Value::as_str)
+        .into_iter()
+        .chain(metadata.get("animation_url").and_then(Value::as_str))
+        .chain(files)
+    {
+        let fetchable = ["http://", "https://", "ipfs://", "ar://"]
+            .iter()
+            .any(|scheme| uri.starts_with(scheme));
+        if fetchable && !uris.iter().any(|u| u == uri) {
+            uris.push(uri.to_string());
+        }
+        if uris.len() == max_files {
+            break;
+        }
+    }
+    uris
+}
+
+/// Requests the primary files of downloaded metadata to record the mime type and size their host
+/// serves, and optionally has a thumbnailer render their previews.
+#[derive(Debug, Clone)]
+pub struct MediaProber {
+    fetcher: MetadataFetcher,
+    max_files: usize,
+    preview_url_template: Option<String>,
+}
+
+impl MediaProber {
+    /// `None` unless `probe_files` is set.
+    pub fn new(config: &BgTaskConfig, fetcher: MetadataFetcher) -> Option<Self> {
+        if !config.probe_files.unwrap_or(false) {
+            return None;
+        }
+        Some(MediaProber {
+            fetcher,
+            max_files: config.probe_max_files.unwrap_or(PROBE_MAX_FILES).max(1),
+            preview_url_template: config.preview_url_template.clone(),
+        })
+    }
+
+    fn preview_url(&self, uri: &str, mime: Option<&str>) -> Option<String> {
+        let template = self.preview_url_template.as_ref()?;
+        let mime = mime?;
+        if !mime.starts_with("image/") && !mime.starts_with("video/") {
+            return None;
+        }
+        let encoded: String = url::form_urlencoded::byte_serialize(uri.as_bytes()).collect();
+        Some(template.replace("{uri}", &encoded))
+    }
+
+    async fn probe_file(&self, uri: String, timeout: Duration) -> Option<ProbedFile> {
+        let head = match self.fetcher.probe(&uri, timeout).await {
+            Ok(head) => head,
+            Err(e) => {
+                debug!("Probing {} failed: {}", uri, e);
+                metric! {
+                    statsd_count!("ingester.bgtask.file_probe", 1, "status" => "failed");
+                }
+                return None;
+            }
+        };
+        metric! {
+            statsd_count!("ingester.bgtask.file_probe", 1, "status" => "ok");
+        }
+        let mut preview_uri = None;
+        if let Some(preview) = self.preview_url(&uri, head.mime.as_deref()) {
+            // Requesting the preview has the thumbnailer render it before the first client asks.
+            match self.fetcher.probe(&preview, timeout).await {
+                Ok(_) => preview_uri = Some(preview),
+                Err(e) => {
+                    debug!("Rendering the preview of {} failed: {}", uri, e);
+                    metric! {
+                        statsd_count!("ingester.bgtask.file_preview_failed", 1);
+                    }
+                }
+            }
+        }
+        Some(ProbedFile {
+            uri,
+            mime: head.mime,
+            size: head.size,
+            preview_uri,
+        })
+    }
+
+    /// The probed files of `metadata` for `asset_data.files`, `None` when none could be probed.
+    pub async fn probe_files(&self, metadata: &Value, timeout: Duration) -> Option<Value> {
+        let uris = primary_file_uris(metadata, self.max_files);
+        let probed: Vec<ProbedFile> =
+            join_all(uris.into_iter().map(|uri| self.probe_file(uri, timeout)))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+        if probed.is_empty() {
+            return None;
+        }
+        serde_json::to_value(probed).ok()
+    }
+}
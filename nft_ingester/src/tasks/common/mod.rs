@@ -1,10 +1,22 @@
 use super::{BgTask, FromTaskData, IngesterError, IntoTaskData, TaskData};
+use crate::{
+    collection_traits::{update_collection_traits, update_shared_traits},
+    metric,
+};
 use async_trait::async_trait;
-use chrono::NaiveDateTime;
-use digital_asset_types::dao::asset_data;
+use cadence_macros::{is_global_default_set, statsd_count};
+use chrono::{NaiveDateTime, Utc};
+use digital_asset_types::{
+    dao::{
+        asset_data, asset_data_full_metadata, metadata_blob,
+        scopes::metadata_blob::{
+            content_hash, get_metadata_blob, resolve_metadata_blobs, uri_hash,
+        },
+    },
+    json::{truncate_metadata, truncation_marker, TruncationPolicy},
+};
 use log::debug;
-use reqwest::{Client, ClientBuilder};
-use sea_orm::*;
+use sea_orm::{sea_query::OnConflict, *};
 use serde::{Deserialize, Serialize};
 use std::{
     fmt::{Display, Formatter},
@@ -12,6 +24,13 @@ use std::{
 };
 use url::Url;
 
+mod gateway;
+mod host_limiter;
+mod media;
+pub use gateway::*;
+pub use host_limiter::*;
+pub use media::*;
+
 const TASK_NAME: &str = "DownloadMetadata";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,41 +66,19 @@ impl FromTaskData<DownloadMetadata> for DownloadMetadata {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct DownloadMetadataTask {
     pub lock_duration: Option<i64>,
     pub max_attempts: Option<i16>,
     pub timeout: Option<Duration>,
-}
-
-impl DownloadMetadataTask {
-    async fn request_metadata(
-        uri: String,
-        timeout: Duration,
-        ipfs_gateway: Option<String>,
-    ) -> Result<serde_json::Value, IngesterError> {
-        let new_uri = if uri.starts_with("https://nftstorage.link") {
-            if let Some(gateway) = ipfs_gateway {
-                uri.replace("https://nftstorage.link", &gateway)
-            } else {
-                uri
-            }
-        } else {
-            uri
-        };
-
-        let client = ClientBuilder::new().timeout(timeout).build()?;
-        let response = Client::get(&client, new_uri).send().await?;
-
-        if response.status() != reqwest::StatusCode::OK {
-            Err(IngesterError::HttpError {
-                status_code: response.status().as_str().to_string(),
-            })
-        } else {
-            let val: serde_json::Value = response.json().await?;
-            Ok(val)
-        }
-    }
+    pub fetcher: MetadataFetcher,
+    pub prober: Option<MediaProber>,
+    /// Metadata over this many bytes is stored truncated, and whole in
+    /// `asset_data_full_metadata`.
+    pub metadata_max_bytes: Option<usize>,
+    pub truncation_policy: TruncationPolicy,
+    /// How long a download of a uri is reused for the other assets of the uri.
+    pub blob_ttl: Duration,
 }
 
 #[async_trait]
@@ -98,47 +95,199 @@ impl BgTask for DownloadMetadataTask {
         self.max_attempts.unwrap_or(3)
     }
 
+    // A uri downloaded recently for another asset only needs linking, which is cheaper than a
+    // task row per asset of a collection sharing it.
+    async fn apply_unqueued(
+        &self,
+        db: &DatabaseConnection,
+        data: &serde_json::Value,
+    ) -> Result<bool, IngesterError> {
+        let download_metadata: DownloadMetadata = serde_json::from_value(data.clone())?;
+        if Url::parse(&download_metadata.uri).is_err() {
+            return Ok(false);
+        }
+        let fresh = get_metadata_blob(db, uri_hash(&download_metadata.uri))
+            .await?
+            .filter(|blob| self.is_reusable(blob));
+        if fresh.is_none() {
+            return Ok(false);
+        }
+        self.task(db, data.clone(), None).await?;
+        Ok(true)
+    }
+
     async fn task(
         &self,
         db: &DatabaseConnection,
         data: serde_json::Value,
-        ipfs_gateway: Option<String>,
+        // The gateway is already part of the fetcher's fallback chain.
+        _ipfs_gateway: Option<String>,
     ) -> Result<(), IngesterError> {
         let download_metadata: DownloadMetadata = serde_json::from_value(data)?;
         let meta_url = Url::parse(&download_metadata.uri);
-        let body = match meta_url {
-            Ok(_) => {
-                DownloadMetadataTask::request_metadata(
-                    download_metadata.uri.clone(),
-                    self.timeout.unwrap_or(Duration::from_secs(3)),
-                    ipfs_gateway,
-                )
-                .await?
+        let timeout = self.timeout.unwrap_or(Duration::from_secs(3));
+        let db_error = |db: DbErr| {
+            IngesterError::TaskManagerError(format!(
+                "Database error with {}, error: {}",
+                self.name(),
+                db
+            ))
+        };
+        // Invalid uris aren't shared, their asset keeps its own copy of the error.
+        let blob_key = meta_url.is_ok().then(|| uri_hash(&download_metadata.uri));
+        // Another asset of the uri downloaded it recently.
+        let reused = match &blob_key {
+            Some(key) => get_metadata_blob(db, key.clone())
+                .await
+                .map_err(db_error)?
+                .filter(|blob| self.is_reusable(blob)),
+            None => None,
+        };
+        let (body, files) = match &reused {
+            Some(blob) => {
+                metric! {
+                    statsd_count!("ingester.metadata_blob.reused", 1);
+                }
+                let files = match &blob.files {
+                    Some(files) => Set(Some(files.clone())),
+                    None => NotSet,
+                };
+                (blob.metadata.clone(), files)
+            }
+            None => {
+                let body = match meta_url {
+                    Ok(_) => self.fetcher.fetch(&download_metadata.uri, timeout).await?,
+                    _ => serde_json::Value::String("Invalid Uri".to_string()), //TODO -> enumize this.
+                };
+                let files = match &self.prober {
+                    Some(prober) => Set(prober.probe_files(&body, timeout).await),
+                    None => NotSet,
+                };
+                (body, files)
+            }
+        };
+        let truncated = self
+            .metadata_max_bytes
+            .and_then(|max_bytes| truncate_metadata(&body, max_bytes, self.truncation_policy));
+        // Traits are counted from the stored metadata, the previous metadata is read back from it.
+        let stored = truncated.clone().unwrap_or_else(|| body.clone());
+        debug!(
+            "download metadata for {:?}",
+            bs58::encode(download_metadata.asset_data_id.clone()).into_string()
+        );
+        let txn = db.begin().await.map_err(db_error)?;
+        // Locks the row so concurrent downloads of the same asset count its traits only once.
+        let previous = asset_data::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "SELECT * FROM asset_data WHERE id = $1 FOR UPDATE",
+                vec![download_metadata.asset_data_id.clone().into()],
+            ))
+            .one(&txn)
+            .await
+            .map_err(db_error)?;
+        // Locks the blob so the traits of the assets sharing it move only once per change.
+        let current = match &blob_key {
+            Some(key) => metadata_blob::Entity::find()
+                .from_raw_sql(Statement::from_sql_and_values(
+                    DbBackend::Postgres,
+                    "SELECT * FROM metadata_blob WHERE uri_hash = $1 FOR UPDATE",
+                    vec![key.clone().into()],
+                ))
+                .one(&txn)
+                .await
+                .map_err(db_error)?,
+            None => None,
+        };
+        // Read once the blob is locked, a concurrent download of the uri may have changed it.
+        let previous = match previous {
+            Some(mut previous) => {
+                resolve_metadata_blobs(&txn, std::slice::from_mut(&mut previous))
+                    .await
+                    .map_err(db_error)?;
+                Some(previous)
             }
-            _ => serde_json::Value::String("Invalid Uri".to_string()), //TODO -> enumize this.
+            None => None,
         };
+        // The reused blob may have been downloaded again since it was read.
+        let stored = match (&reused, &current) {
+            (Some(reused), Some(current)) if current.content_hash != reused.content_hash => {
+                current.metadata.clone()
+            }
+            _ => stored,
+        };
+        if let (Some(key), None) = (&blob_key, &reused) {
+            self.store_blob(
+                &txn,
+                key.clone(),
+                &download_metadata,
+                current,
+                &stored,
+                &files,
+            )
+            .await?;
+        }
+        // An asset referencing a blob doesn't keep a copy of the metadata.
         let model = asset_data::ActiveModel {
             id: Unchanged(download_metadata.asset_data_id.clone()),
-            metadata: Set(body),
+            metadata: Set(match blob_key {
+                Some(_) => serde_json::Value::Null,
+                None => stored.clone(),
+            }),
+            metadata_blob: Set(blob_key.clone()),
             reindex: Set(Some(false)),
+            files,
             ..Default::default()
         };
-        debug!(
-            "download metadata for {:?}",
-            bs58::encode(download_metadata.asset_data_id.clone()).into_string()
-        );
         asset_data::Entity::update(model)
             .filter(asset_data::Column::Id.eq(download_metadata.asset_data_id.clone()))
-            .exec(db)
+            .exec(&txn)
             .await
-            .map(|_| ())
-            .map_err(|db| {
-                IngesterError::TaskManagerError(format!(
-                    "Database error with {}, error: {}",
-                    self.name(),
-                    db
-                ))
-            })?;
+            .map_err(db_error)?;
+        match truncated.as_ref().and_then(truncation_marker) {
+            Some(marker) => {
+                let full = asset_data_full_metadata::ActiveModel {
+                    id: Set(download_metadata.asset_data_id.clone()),
+                    metadata: Set(body.clone()),
+                    original_bytes: Set(marker.original_bytes as i64),
+                    updated_at: Set(Utc::now().into()),
+                };
+                asset_data_full_metadata::Entity::insert(full)
+                    .on_conflict(
+                        OnConflict::columns([asset_data_full_metadata::Column::Id])
+                            .update_columns([
+                                asset_data_full_metadata::Column::Metadata,
+                                asset_data_full_metadata::Column::OriginalBytes,
+                                asset_data_full_metadata::Column::UpdatedAt,
+                            ])
+                            .to_owned(),
+                    )
+                    .exec(&txn)
+                    .await
+                    .map_err(db_error)?;
+            }
+            // The metadata fits now, the API would serve a stale blob otherwise.
+            None => {
+                asset_data_full_metadata::Entity::delete_many()
+                    .filter(
+                        asset_data_full_metadata::Column::Id
+                            .eq(download_metadata.asset_data_id.clone()),
+                    )
+                    .exec(&txn)
+                    .await
+                    .map_err(db_error)?;
+            }
+        }
+        if let Some(previous) = previous {
+            update_collection_traits(
+                &txn,
+                download_metadata.asset_data_id.clone(),
+                &previous.metadata,
+                &stored,
+            )
+            .await?;
+        }
+        txn.commit().await.map_err(db_error)?;
 
         if meta_url.is_err() {
             return Err(IngesterError::UnrecoverableTaskError(format!(
@@ -150,6 +299,69 @@ impl BgTask for DownloadMetadataTask {
     }
 }
 
+impl DownloadMetadataTask {
+    // Only whole metadata is reused, the full JSON of truncated metadata is kept per asset.
+    fn is_reusable(&self, blob: &metadata_blob::Model) -> bool {
+        let age = Utc::now().signed_duration_since(blob.fetched_at);
+        truncation_marker(&blob.metadata).is_none()
+            && age.to_std().map_or(true, |age| age < self.blob_ttl)
+    }
+
+    // Writes the downloaded metadata of the uri. The other assets sharing it see the change
+    // through the blob, their trait counts are moved here.
+    async fn store_blob(
+        &self,
+        txn: &DatabaseTransaction,
+        key: Vec<u8>,
+        download_metadata: &DownloadMetadata,
+        current: Option<metadata_blob::Model>,
+        stored: &serde_json::Value,
+        files: &ActiveValue<Option<serde_json::Value>>,
+    ) -> Result<(), IngesterError> {
+        let hash = content_hash(stored);
+        let files = match files {
+            ActiveValue::Set(files) => files.clone(),
+            _ => current.as_ref().and_then(|c| c.files.clone()),
+        };
+        let blob = metadata_blob::ActiveModel {
+            uri_hash: Set(key.clone()),
+            uri: Set(download_metadata.uri.clone()),
+            metadata: Set(stored.clone()),
+            content_hash: Set(hash.clone()),
+            files: Set(files),
+            fetched_at: Set(Utc::now().into()),
+        };
+        metadata_blob::Entity::insert(blob)
+            .on_conflict(
+                OnConflict::columns([metadata_blob::Column::UriHash])
+                    .update_columns([
+                        metadata_blob::Column::Metadata,
+                        metadata_blob::Column::ContentHash,
+                        metadata_blob::Column::Files,
+                        metadata_blob::Column::FetchedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(txn)
+            .await?;
+        metric! {
+            statsd_count!("ingester.metadata_blob.stored", 1);
+        }
+        let current = match current {
+            Some(current) if current.content_hash != hash => current,
+            _ => return Ok(()),
+        };
+        update_shared_traits(
+            txn,
+            key,
+            download_metadata.asset_data_id.clone(),
+            &current.metadata,
+            stored,
+        )
+        .await
+    }
+}
+
 impl Display for DownloadMetadata {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
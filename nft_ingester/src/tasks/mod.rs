@@ -3,18 +3,21 @@ use async_trait::async_trait;
 use cadence_macros::{is_global_default_set, statsd_count, statsd_gauge, statsd_histogram};
 use chrono::{Duration, NaiveDateTime, Utc};
 use crypto::{digest::Digest, sha2::Sha256};
-use digital_asset_types::dao::{sea_orm_active_enums::TaskStatus, tasks};
+use digital_asset_types::{
+    dao::{sea_orm_active_enums::TaskStatus, tasks},
+    json::TruncationPolicy,
+};
 use log::{debug, error, info, warn};
 use sea_orm::{
     entity::*, query::*, sea_query::Expr, ActiveValue::Set, ColumnTrait, DatabaseConnection,
-    DeleteResult, SqlxPostgresConnector,
+    DbBackend, DbErr, DeleteResult, FromQueryResult, SqlxPostgresConnector,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
 use std::{collections::HashMap, sync::Arc};
 use tokio::{
     sync::mpsc::{self, UnboundedSender},
-    task::JoinHandle,
+    task::{JoinHandle, JoinSet},
     time,
 };
 
@@ -32,10 +35,21 @@ pub trait BgTask: Send + Sync {
         data: serde_json::Value,
         ipfs_gateway: Option<String>,
     ) -> Result<(), IngesterError>;
+    /// Runs the task in place of queueing it when that's cheaper, returning whether it did.
+    async fn apply_unqueued(
+        &self,
+        _db: &DatabaseConnection,
+        _data: &serde_json::Value,
+    ) -> Result<bool, IngesterError> {
+        Ok(false)
+    }
 }
 
 const RETRY_INTERVAL: u64 = 1000;
 const QUEUE_DEPTH_INTERVAL: u64 = 2500;
+// The failure rates count the finished tasks, every task not purged yet, so they're reported
+// once per this many queue depth reports.
+const FAILURE_RATE_EVERY: u64 = 24;
 const DELETE_INTERVAL: u64 = 30000;
 const MAX_TASK_BATCH_SIZE: u64 = 100;
 const PURGE_TIME: u64 = 3600;
@@ -49,6 +63,37 @@ pub struct BgTaskConfig {
     pub lock_duration: Option<i64>,
     pub max_attempts: Option<i16>,
     pub timeout: Option<u64>,
+    /// Outbound proxy used for metadata requests, e.g. `http://proxy:3128`.
+    pub http_proxy: Option<String>,
+    /// Ordered IPFS gateways, tried in turn for `ipfs://` and `/ipfs/` uris.
+    pub ipfs_gateways: Option<Vec<String>>,
+    /// Ordered Arweave gateways, tried in turn for `ar://` and arweave.net uris.
+    pub arweave_gateways: Option<Vec<String>>,
+    /// Consecutive failures before a gateway is skipped.
+    pub gateway_failure_threshold: Option<u32>,
+    /// Seconds a failing gateway is skipped for.
+    pub gateway_cooldown: Option<u64>,
+    /// Limits for metadata hosts without an entry in `host_limits`.
+    pub default_host_limit: Option<HostLimit>,
+    /// Limits per metadata host, e.g. `{"arweave.net"={max_concurrency=10, max_rps=50}}`.
+    pub host_limits: Option<HashMap<String, HostLimit>>,
+    /// Redis shared by the runners to enforce `max_rps` across all of them.
+    pub host_limit_redis_url: Option<String>,
+    /// Requests the image, animation and files of downloaded metadata to record their mime type
+    /// and size in `asset_data.files`.
+    pub probe_files: Option<bool>,
+    /// Files probed per asset.
+    pub probe_max_files: Option<usize>,
+    /// Thumbnailer URL with `{uri}` in place of the encoded file URI, e.g.
+    /// `https://thumbs.example.com/?url={uri}&width=400`, requested for probed images and videos.
+    pub preview_url_template: Option<String>,
+    /// Metadata over this many bytes of JSON is stored truncated, following
+    /// `metadata_truncation`, and kept whole aside for `fullMetadata` requests.
+    pub metadata_max_bytes: Option<usize>,
+    pub metadata_truncation: Option<TruncationPolicy>,
+    /// Seconds a download of a uri shared by several assets is reused for the others, instead
+    /// of downloading it again for each.
+    pub metadata_blob_ttl: Option<u64>,
 }
 
 impl Default for BgTaskConfig {
@@ -61,6 +106,20 @@ impl Default for BgTaskConfig {
             lock_duration: Some(5),
             max_attempts: Some(3),
             timeout: Some(3),
+            http_proxy: None,
+            ipfs_gateways: None,
+            arweave_gateways: None,
+            gateway_failure_threshold: None,
+            gateway_cooldown: None,
+            default_host_limit: None,
+            host_limits: None,
+            host_limit_redis_url: None,
+            probe_files: None,
+            probe_max_files: None,
+            preview_url_template: None,
+            metadata_max_bytes: None,
+            metadata_truncation: None,
+            metadata_blob_ttl: None,
         }
     }
 }
@@ -93,6 +152,31 @@ pub trait IntoTaskData: Sized {
     fn into_task_data(self) -> Result<TaskData, IngesterError>;
 }
 
+/// The unfinished tasks of one task type. Pending tasks are the ones a runner could claim.
+#[derive(Debug, Clone, FromQueryResult, Serialize)]
+pub struct TaskBacklog {
+    pub task_type: String,
+    pub pending: i64,
+    /// Seconds since the oldest pending task was created.
+    pub oldest_pending_age: i64,
+    pub running: i64,
+}
+
+/// The state of the queue for one task type, [TaskBacklog] and the finished tasks. Failed tasks
+/// ran out of attempts. `failure_rate` is the share of failed tasks among those that finished and
+/// haven't been purged yet.
+#[derive(Debug, Clone, FromQueryResult, Serialize)]
+pub struct TaskStats {
+    pub task_type: String,
+    pub pending: i64,
+    /// Seconds since the oldest pending task was created.
+    pub oldest_pending_age: i64,
+    pub running: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub failure_rate: f64,
+}
+
 pub struct TaskManager {
     instance_name: String,
     pool: Pool<Postgres>,
@@ -220,6 +304,117 @@ impl TaskManager {
             .map_err(|e| e.into())
     }
 
+    /// Claims up to `batch_size` runnable tasks for `instance_name`, leasing them for `lease`.
+    /// Rows another runner is claiming at the same moment are skipped rather than waited on, so
+    /// concurrent runners never get the same task. A task whose lease ran out without a
+    /// heartbeat is runnable again.
+    pub async fn claim_tasks(
+        conn: &DatabaseConnection,
+        instance_name: &str,
+        batch_size: u64,
+        lease: Duration,
+    ) -> Result<Vec<tasks::Model>, IngesterError> {
+        let now = Utc::now().naive_utc();
+        tasks::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "
+UPDATE tasks
+SET status = 'running', locked_until = $3, locked_by = $2, heartbeat_at = $4
+WHERE id IN (
+    SELECT id FROM tasks
+    WHERE status != 'success'
+        AND (locked_until IS NULL OR locked_until <= $4)
+        AND attempts < max_attempts
+    ORDER BY attempts ASC, created_at DESC
+    LIMIT $1
+    FOR UPDATE SKIP LOCKED
+)
+RETURNING *
+",
+                vec![
+                    (batch_size as i64).into(),
+                    instance_name.into(),
+                    (now + lease).into(),
+                    now.into(),
+                ],
+            ))
+            .all(conn)
+            .await
+            .map_err(|e| e.into())
+    }
+
+    /// Extends the lease on a running task. Returns false when `instance_name` no longer holds
+    /// it, i.e. the lease expired and another runner claimed the task.
+    pub async fn heartbeat(
+        conn: &DatabaseConnection,
+        id: &str,
+        instance_name: &str,
+        lease: Duration,
+    ) -> Result<bool, IngesterError> {
+        let now = Utc::now().naive_utc();
+        let res = tasks::Entity::update_many()
+            .col_expr(tasks::Column::LockedUntil, Expr::value(now + lease))
+            .col_expr(tasks::Column::HeartbeatAt, Expr::value(now))
+            .filter(tasks::Column::Id.eq(id))
+            .filter(tasks::Column::LockedBy.eq(instance_name))
+            .filter(tasks::Column::Status.eq(TaskStatus::Running))
+            .exec(conn)
+            .await?;
+        Ok(res.rows_affected > 0)
+    }
+
+    // Runs a claimed task, heartbeating its lease until it's done.
+    async fn run_claimed_task(
+        conn: &DatabaseConnection,
+        task_executor: &Box<dyn BgTask>,
+        task: tasks::Model,
+        instance_name: &str,
+        lease: Duration,
+        ipfs_gateway: Option<String>,
+    ) -> Result<(), IngesterError> {
+        let id = task.id.clone();
+        let execution = TaskManager::execute_task(conn, task_executor, task.into(), ipfs_gateway);
+        tokio::pin!(execution);
+        let beat = time::Duration::from_millis((lease.num_milliseconds() / 2).max(500) as u64);
+        let mut heartbeats = time::interval_at(time::Instant::now() + beat, beat);
+        let model = loop {
+            tokio::select! {
+                model = &mut execution => break model?,
+                _ = heartbeats.tick() => {
+                    match TaskManager::heartbeat(conn, &id, instance_name, lease).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            metric! {
+                                statsd_count!("ingester.bgtask.lease_lost", 1, "type" => task_executor.name());
+                            }
+                            warn!("Lost the lease on task {}, dropping it", id);
+                            return Ok(());
+                        }
+                        Err(e) => error!("error heartbeating task {}: {}", id, e),
+                    }
+                }
+            }
+        };
+
+        // Only the lease holder may record the outcome.
+        match tasks::Entity::update(model)
+            .filter(tasks::Column::LockedBy.eq(instance_name))
+            .exec(conn)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(DbErr::RecordNotFound(_)) => {
+                metric! {
+                    statsd_count!("ingester.bgtask.lease_lost", 1, "type" => task_executor.name());
+                }
+                warn!("Lost the lease on task {} before saving its result", id);
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
     pub async fn get_task_queue_depth(conn: &DatabaseConnection) -> Result<u64, IngesterError> {
         tasks::Entity::find()
             .filter(tasks::Column::Status.eq(TaskStatus::Pending))
@@ -228,6 +423,71 @@ impl TaskManager {
             .map_err(|e| e.into())
     }
 
+    /// The backlog of each task type, read from the unfinished tasks only, see the
+    /// `tasks_unfinished` index.
+    pub async fn get_task_backlog(
+        conn: &DatabaseConnection,
+    ) -> Result<Vec<TaskBacklog>, IngesterError> {
+        TaskBacklog::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+SELECT
+    task_type,
+    COUNT(*) FILTER (WHERE pending) AS pending,
+    COALESCE(EXTRACT(EPOCH FROM $1 - MIN(created_at) FILTER (WHERE pending)), 0)::bigint
+        AS oldest_pending_age,
+    COUNT(*) FILTER (WHERE status = 'running' AND locked_until > $1) AS running
+FROM (
+    SELECT task_type, created_at, status, locked_until, attempts < max_attempts
+        AND (locked_until IS NULL OR locked_until <= $1) AS pending
+    FROM tasks
+    WHERE status != 'success'
+) t
+GROUP BY task_type
+ORDER BY task_type
+",
+            vec![Utc::now().naive_utc().into()],
+        ))
+        .all(conn)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    /// The backlog and the finished tasks of each task type. Counting the finished tasks reads
+    /// every task not purged yet.
+    pub async fn get_task_stats(
+        conn: &DatabaseConnection,
+    ) -> Result<Vec<TaskStats>, IngesterError> {
+        TaskStats::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+WITH counts AS (
+    SELECT
+        task_type,
+        COUNT(*) FILTER (WHERE pending) AS pending,
+        COALESCE(EXTRACT(EPOCH FROM $1 - MIN(created_at) FILTER (WHERE pending)), 0)::bigint
+            AS oldest_pending_age,
+        COUNT(*) FILTER (WHERE status = 'running' AND locked_until > $1) AS running,
+        COUNT(*) FILTER (WHERE status = 'success') AS succeeded,
+        COUNT(*) FILTER (WHERE status = 'failed' AND attempts >= max_attempts) AS failed
+    FROM (
+        SELECT *, status != 'success' AND attempts < max_attempts
+            AND (locked_until IS NULL OR locked_until <= $1) AS pending
+        FROM tasks
+    ) t
+    GROUP BY task_type
+)
+SELECT *, COALESCE(failed::float8 / NULLIF(succeeded + failed, 0), 0) AS failure_rate
+FROM counts
+ORDER BY task_type
+",
+            vec![Utc::now().naive_utc().into()],
+        ))
+        .all(conn)
+        .await
+        .map_err(|e| e.into())
+    }
+
     pub fn get_sender(&self) -> Result<UnboundedSender<TaskData>, IngesterError> {
         self.producer
             .clone()
@@ -238,6 +498,7 @@ impl TaskManager {
         task.status = Set(TaskStatus::Running);
         task.locked_until = Set(Some((Utc::now() + duration).naive_utc()));
         task.locked_by = Set(Some(instance_name));
+        task.heartbeat_at = Set(Some(Utc::now().naive_utc()));
     }
 
     pub fn new(
@@ -270,6 +531,19 @@ impl TaskManager {
         let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
         tokio::task::spawn(async move {
             if let Some(task_executor) = tasks_def.get(task.name) {
+                match task_executor.apply_unqueued(&conn, &task.data).await {
+                    Ok(true) => {
+                        metric! {
+                            statsd_count!("ingester.bgtask.unqueued", 1, "type" => task.name);
+                        }
+                        return Ok(());
+                    }
+                    Ok(false) => {}
+                    Err(e) => warn!(
+                        "Queueing {} task after failing to apply it: {}",
+                        task.name, e
+                    ),
+                }
                 let mut model = tasks::ActiveModel {
                     id: Set(task.hash()?),
                     task_type: Set(task.name.to_string()),
@@ -282,6 +556,7 @@ impl TaskManager {
                     attempts: Set(0),
                     duration: Set(None),
                     errors: Set(None),
+                    heartbeat_at: Set(None),
                 };
                 let duration = Duration::seconds(task_executor.lock_duration());
                 if process_now {
@@ -314,16 +589,6 @@ impl TaskManager {
             .map_err(|e| e.into())
     }
 
-    async fn save_task<A>(
-        txn: &A,
-        task: tasks::ActiveModel,
-    ) -> Result<tasks::ActiveModel, IngesterError>
-    where
-        A: ConnectionTrait,
-    {
-        let act: tasks::ActiveModel = task;
-        act.save(txn).await.map_err(|e| e.into())
-    }
     pub fn start_listener(&mut self, process_on_receive: bool) -> JoinHandle<()> {
         let (producer, mut receiver) = mpsc::unbounded_channel::<TaskData>();
         self.producer = Some(producer);
@@ -394,13 +659,22 @@ impl TaskManager {
             .batch_size
             .unwrap_or(BgTaskConfig::default().batch_size.unwrap());
 
+        let lease = Duration::seconds(
+            config
+                .lock_duration
+                .unwrap_or(BgTaskConfig::default().lock_duration.unwrap()),
+        );
+
         info!(
-            "Background runner config: delete_interval: {:?}, retry_interval: {:?}, purge_time: {:?}, batch_size:{:?}",
-            delete_interval, retry_interval, purge_time, batch_size
+            "Background runner config: delete_interval: {:?}, retry_interval: {:?}, purge_time: {:?}, batch_size:{:?}, lease: {:?}",
+            delete_interval, retry_interval, purge_time, batch_size, lease
         );
 
+        // The loops run in one set so aborting the returned handle, e.g. when the supervisor
+        // restarts the runner, stops all of them.
+        let mut runner = JoinSet::new();
         let pool = self.pool.clone();
-        tokio::spawn(async move {
+        runner.spawn(async move {
             let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
             let mut interval = time::interval(delete_interval);
             loop {
@@ -424,12 +698,14 @@ impl TaskManager {
         });
 
         let pool = self.pool.clone();
-        tokio::spawn(async move {
+        runner.spawn(async move {
             let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
             let mut interval =
                 time::interval(tokio::time::Duration::from_millis(QUEUE_DEPTH_INTERVAL));
+            let mut reports: u64 = 0;
             loop {
                 interval.tick().await; // ticks immediately
+                reports += 1;
                 let res = TaskManager::get_task_queue_depth(&conn).await;
                 match res {
                     Ok(depth) => {
@@ -442,6 +718,35 @@ impl TaskManager {
                         error!("error getting queue depth: {}", e);
                     }
                 };
+                match TaskManager::get_task_backlog(&conn).await {
+                    Ok(backlog) => {
+                        for b in backlog {
+                            metric! {
+                                statsd_gauge!("ingester.bgtask.pending", b.pending as u64, "type" => &b.task_type);
+                                statsd_gauge!("ingester.bgtask.oldest_pending_age", b.oldest_pending_age as u64, "type" => &b.task_type);
+                                statsd_gauge!("ingester.bgtask.running", b.running as u64, "type" => &b.task_type);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!("error getting task backlog: {}", e);
+                    }
+                }
+                if reports % FAILURE_RATE_EVERY != 1 {
+                    continue;
+                }
+                match TaskManager::get_task_stats(&conn).await {
+                    Ok(stats) => {
+                        for s in stats {
+                            metric! {
+                                statsd_gauge!("ingester.bgtask.failure_rate", s.failure_rate, "type" => &s.task_type);
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        error!("error getting task stats: {}", e);
+                    }
+                }
             }
         });
 
@@ -449,17 +754,19 @@ impl TaskManager {
         let ipfs_gateway = self.ipfs_gateway.clone();
         let task_map = self.registered_task_types.clone();
         let instance_name = self.instance_name.clone();
-        tokio::spawn(async move {
+        runner.spawn(async move {
             let mut interval = time::interval(retry_interval);
             let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
             loop {
                 interval.tick().await; // ticks immediately
-                let tasks_res = TaskManager::get_pending_tasks(&conn, batch_size).await;
+                let tasks_res =
+                    TaskManager::claim_tasks(&conn, &instance_name, batch_size, lease).await;
                 match tasks_res {
                     Ok(tasks) => {
-                        debug!("tasks that need to be executed: {}", tasks.len());
-                        let _task_map_clone = task_map.clone();
-                        let instance_name = instance_name.clone();
+                        debug!("tasks claimed for execution: {}", tasks.len());
+                        metric! {
+                            statsd_count!("ingester.bgtask.claimed", tasks.len() as i64);
+                        }
                         for task in tasks {
                             let task_map_clone = task_map.clone();
                             let instance_name_clone = instance_name.clone();
@@ -470,24 +777,15 @@ impl TaskManager {
                                     task_map_clone.clone().get(&*task.task_type)
                                 {
                                     let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
-                                    let mut active_model: tasks::ActiveModel = task.into();
-                                    TaskManager::lock_task(
-                                        &mut active_model,
-                                        Duration::seconds(task_executor.lock_duration()),
-                                        instance_name_clone,
-                                    );
-                                    // can ignore as txn will bubble up errors
-                                    let active_model =
-                                        TaskManager::save_task(&conn, active_model).await?;
-                                    let model = TaskManager::execute_task(
+                                    return TaskManager::run_claimed_task(
                                         &conn,
                                         task_executor,
-                                        active_model,
+                                        task,
+                                        &instance_name_clone,
+                                        lease,
                                         ipfs_gateway,
                                     )
-                                    .await?;
-                                    TaskManager::save_task(&conn, model).await?;
-                                    return Ok(());
+                                    .await;
                                 }
                                 Err(IngesterError::TaskManagerError(format!(
                                     "{} not a valid task type",
@@ -497,10 +795,19 @@ impl TaskManager {
                         }
                     }
                     Err(e) => {
-                        error!("Error getting pending tasks: {}", e);
+                        error!("Error claiming pending tasks: {}", e);
                     }
                 }
             }
+        });
+
+        tokio::spawn(async move {
+            // None of the loops return, so the first one to stop has panicked.
+            if let Some(Err(e)) = runner.join_next().await {
+                if e.is_panic() {
+                    std::panic::resume_unwind(e.into_panic());
+                }
+            }
         })
     }
 }
@@ -1,45 +1,62 @@
 use std::sync::Arc;
 
 use crate::{
-    metric, metrics::capture_result, program_transformers::ProgramTransformer, tasks::TaskData,
+    admin::PayloadDumper,
+    asset_changes::ChangeNotifier,
+    bulkhead::{BulkheadConfig, Bulkheads, TransactionSeqBuffer},
+    memory_budget::MemoryBudget,
+    metric,
+    metrics::capture_result,
+    program_transformers::{Assertion, ProgramTransformer},
+    stream_control::{StreamConsumer, StreamControls},
+    tasks::TaskData,
 };
 use cadence_macros::{is_global_default_set, statsd_count, statsd_time};
 use chrono::Utc;
 use log::{debug, error};
-use plerkle_messenger::{
-    ConsumptionType, Messenger, MessengerConfig, RecvData, TRANSACTION_STREAM,
-};
+use plerkle_messenger::{ConsumptionType, Messenger, MessengerConfig, TRANSACTION_STREAM};
 use plerkle_serialization::root_as_transaction_info;
 
 use sqlx::{Pool, Postgres};
-use tokio::{
-    sync::mpsc::UnboundedSender,
-    task::{JoinHandle, JoinSet},
-    time::Instant,
-};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle, time::Instant};
 
+#[allow(clippy::too_many_arguments)]
 pub fn transaction_worker<T: Messenger>(
     pool: Pool<Postgres>,
     config: MessengerConfig,
     bg_task_sender: UnboundedSender<TaskData>,
     ack_channel: UnboundedSender<(&'static str, String)>,
     consumption_type: ConsumptionType,
+    dumper: PayloadDumper,
+    budget: Option<MemoryBudget>,
+    changes: ChangeNotifier,
+    record_failed_transactions: bool,
+    bulkheads: Vec<BulkheadConfig>,
+    controls: StreamControls,
+    assertion: Option<Assertion>,
+    seq_buffer: Option<Arc<TransactionSeqBuffer>>,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
-        let source = T::new(config).await;
-        if let Ok(mut msg) = source {
-            let manager = Arc::new(ProgramTransformer::new(pool, bg_task_sender));
+        let source = StreamConsumer::<T>::new(TRANSACTION_STREAM, config, &controls).await;
+        if let Ok(mut msg) = source.map(|source| source.with_memory_budget(budget.clone())) {
+            let manager = Arc::new(
+                ProgramTransformer::new(pool, bg_task_sender)
+                    .with_change_notifier(changes)
+                    .with_failed_transaction_log(record_failed_transactions)
+                    .with_assertion(assertion),
+            );
+            let bulkheads =
+                Bulkheads::start(bulkheads, manager, dumper, budget, ack_channel, seq_buffer);
             loop {
-                let e = msg.recv(TRANSACTION_STREAM, consumption_type.clone()).await;
-                let mut tasks = JoinSet::new();
+                let e = msg.recv(consumption_type.clone()).await;
                 match e {
                     Ok(data) => {
                         let len = data.len();
                         for item in data {
-                            tasks.spawn(handle_transaction(Arc::clone(&manager), item));
+                            bulkheads.dispatch(item).await;
                         }
                         if len > 0 {
-                            debug!("Processed {} txns", len);
+                            debug!("Dispatched {} txns", len);
                         }
                     }
                     Err(e) => {
@@ -49,34 +66,26 @@ pub fn transaction_worker<T: Messenger>(
                         }
                     }
                 }
-                while let Some(res) = tasks.join_next().await {
-                    if let Ok(id) = res {
-                        if let Some(id) = id {
-                            let send = ack_channel.send((TRANSACTION_STREAM, id));
-                            if let Err(err) = send {
-                                metric! {
-                                    error!("Txn stream ack error: {}", err);
-                                    statsd_count!("ingester.stream.ack_error", 1, "stream" => TRANSACTION_STREAM);
-                                }
-                            }
-                        }
-                    }
-                }
             }
         }
     })
 }
 
-async fn handle_transaction(manager: Arc<ProgramTransformer>, item: RecvData) -> Option<String> {
+/// Transforms a decoded transaction, returning its message id if it should be acknowledged.
+pub(crate) async fn handle_transaction(
+    manager: &ProgramTransformer,
+    id: String,
+    tries: usize,
+    tx_data: &[u8],
+    dumper: &PayloadDumper,
+) -> Option<String> {
     let mut ret_id = None;
-    if item.tries > 0 {
+    if tries > 0 {
         metric! {
             statsd_count!("ingester.stream_redelivery", 1, "stream" => TRANSACTION_STREAM);
         }
     }
-    let id = item.id.to_string();
-    let tx_data = item.data;
-    if let Ok(tx) = root_as_transaction_info(&tx_data) {
+    if let Ok(tx) = root_as_transaction_info(tx_data) {
         let signature = tx.signature().unwrap_or("NO SIG");
         debug!("Received transaction: {}", signature);
         metric! {
@@ -91,13 +100,20 @@ async fn handle_transaction(manager: Arc<ProgramTransformer>, item: RecvData) ->
             );
         }
 
+        let keys = tx.account_keys().unwrap_or_default();
+        if let Some(program) = dumper.dumped_program(keys.iter().map(|k| k.0.as_slice())) {
+            dumper
+                .dump(program, "transaction", tx.slot(), signature, tx_data)
+                .await;
+        }
+
         let begin = Instant::now();
         let res = manager.handle_transaction(&tx).await;
         let should_ack = capture_result(
             id.clone(),
             TRANSACTION_STREAM,
             ("txn", "txn"),
-            item.tries,
+            tries,
             res,
             begin,
             tx.signature(),
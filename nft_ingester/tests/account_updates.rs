@@ -0,0 +1,74 @@
+use flatbuffers::FlatBufferBuilder;
+use messenger_payload::{encode_payload, PayloadCompression};
+use nft_ingester::account_updates::compact_account_updates;
+use plerkle_messenger::RecvData;
+use plerkle_serialization::{
+    serializer::serialize_account, solana_geyser_plugin_interface_shims::ReplicaAccountInfoV2,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn account(pubkey: &Pubkey, slot: u64, write_version: u64) -> Vec<u8> {
+    let account_info = ReplicaAccountInfoV2 {
+        pubkey: &pubkey.to_bytes(),
+        lamports: 1,
+        owner: &Pubkey::new_unique().to_bytes(),
+        executable: false,
+        rent_epoch: 0,
+        data: &[],
+        write_version,
+        txn_signature: None,
+    };
+    serialize_account(FlatBufferBuilder::new(), &account_info, slot, false)
+        .finished_data()
+        .to_vec()
+}
+
+fn item(id: &str, data: Vec<u8>) -> RecvData {
+    RecvData {
+        id: id.to_string(),
+        tries: 0,
+        data,
+    }
+}
+
+#[test]
+fn only_the_latest_update_of_an_account_is_kept() {
+    let (busy, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let latest = account(&busy, 7, 1);
+    let compressed = encode_payload(&latest, PayloadCompression::Zstd)
+        .unwrap()
+        .into_owned();
+
+    let (kept, stale) = compact_account_updates(vec![
+        item("1", account(&busy, 5, 9)),
+        item("2", account(&other, 5, 1)),
+        item("3", compressed),
+        // Same slot, earlier write.
+        item("4", account(&busy, 7, 0)),
+    ]);
+
+    let ids: Vec<_> = kept.iter().map(|update| update.id.as_str()).collect();
+    assert_eq!(ids, vec!["3", "2"]);
+    assert_eq!(stale, vec!["1", "4"]);
+    // The kept updates hold their decoded flatbuffer.
+    assert_eq!(kept[0].data.as_ref().unwrap(), &latest);
+}
+
+#[test]
+fn updates_that_cant_be_read_are_kept() {
+    let pubkey = Pubkey::new_unique();
+    // A compressed payload of an unknown codec.
+    let unknown = b"DASP\x09payload".to_vec();
+
+    let (kept, stale) = compact_account_updates(vec![
+        item("1", unknown),
+        item("2", vec![0; 3]),
+        item("3", account(&pubkey, 1, 1)),
+    ]);
+
+    assert!(stale.is_empty());
+    assert_eq!(kept.len(), 3);
+    assert!(kept[0].data.is_err());
+    // Not a flatbuffer, but only the transformer tells.
+    assert_eq!(kept[1].data.as_ref().unwrap(), &vec![0; 3]);
+}
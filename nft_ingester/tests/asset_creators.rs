@@ -0,0 +1,139 @@
+use digital_asset_types::dao::asset_creators;
+use mpl_bubblegum::state::metaplex_adapter::Creator;
+use nft_ingester::{
+    pending::{apply_pending_creator_verifications, buffer_creator_verification},
+    program_transformers::upsert_asset_creators,
+};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+fn written(rows_affected: u64) -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected,
+    }
+}
+
+fn creator(address: Pubkey, verified: bool) -> Creator {
+    Creator {
+        address,
+        verified,
+        share: 50,
+    }
+}
+
+fn stored(asset_id: &[u8], address: Pubkey, seq: i64) -> asset_creators::Model {
+    asset_creators::Model {
+        id: 1,
+        asset_id: asset_id.to_vec(),
+        creator: address.to_bytes().to_vec(),
+        share: 50,
+        verified: false,
+        seq: Some(seq),
+        slot_updated: Some(1),
+        position: 0,
+    }
+}
+
+#[tokio::test]
+async fn a_stale_array_is_skipped() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let first = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![stored(&asset_id, first, 10)]])
+        .into_connection();
+
+    let applied = upsert_asset_creators(&db, asset_id, &[creator(first, true)], 1, 5)
+        .await
+        .unwrap();
+
+    // Only the latest seq was read, nothing was written.
+    assert!(!applied);
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("DELETE"));
+    assert!(!log.contains("INSERT"));
+}
+
+#[tokio::test]
+async fn a_removed_creator_is_deleted() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let (kept, removed) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![stored(&asset_id, removed, 3)]])
+        .append_exec_results(vec![written(1), written(1)])
+        .into_connection();
+
+    let applied = upsert_asset_creators(&db, asset_id, &[creator(kept, false)], 1, 5)
+        .await
+        .unwrap();
+
+    assert!(applied);
+    let log = format!("{:?}", db.into_transaction_log());
+    // The rows of creators missing from the array are deleted, then the array is written,
+    // unless a later seq wrote a row first.
+    let delete = log.find("DELETE FROM \\\"asset_creators\\\"").unwrap();
+    let insert = log.find("INSERT INTO \\\"asset_creators\\\"").unwrap();
+    assert!(delete < insert);
+    assert!(log.contains("NOT IN"));
+    assert!(log.contains("WHERE excluded.seq >= asset_creators.seq"));
+    let kept_bytes = format!("{:?}", kept.to_bytes().to_vec());
+    assert!(log[delete..insert].contains(&kept_bytes));
+}
+
+#[tokio::test]
+async fn a_duplicate_creator_is_written_once() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let address = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_exec_results(vec![written(0), written(1)])
+        .into_connection();
+
+    let creators = [creator(address, true), creator(address, false)];
+    upsert_asset_creators(&db, asset_id, &creators, 1, 1)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    // One row of seven values.
+    let insert = log.find("INSERT INTO \\\"asset_creators\\\"").unwrap();
+    assert!(log[insert..].contains("$7"));
+    assert!(!log[insert..].contains("$8"));
+}
+
+#[tokio::test]
+async fn a_verify_before_the_mint_is_applied_once_the_creators_are_written() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let address = Keypair::new().pubkey();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset_creators::Model>::new()])
+        .append_exec_results(vec![written(1), written(0), written(1), written(1)])
+        .into_connection();
+
+    // The verify is held, keeping only the latest one per creator.
+    buffer_creator_verification(&db, asset_id.clone(), address.to_bytes().to_vec(), true, 7)
+        .await
+        .unwrap();
+    // The mint writes the creators at its own, earlier seq, then applies what was held.
+    let applied = upsert_asset_creators(&db, asset_id.clone(), &[creator(address, false)], 1, 2)
+        .await
+        .unwrap();
+    assert!(applied);
+    let verified = apply_pending_creator_verifications(&db, Some(asset_id))
+        .await
+        .unwrap();
+    assert_eq!(verified, 1);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    let buffered = log
+        .find("INSERT INTO \\\"pending_creator_verifications\\\"")
+        .unwrap();
+    let minted = log.find("INSERT INTO \\\"asset_creators\\\"").unwrap();
+    let pending = log
+        .find("DELETE FROM pending_creator_verifications")
+        .unwrap();
+    assert!(buffered < minted && minted < pending);
+    assert!(log.contains("WHERE excluded.seq > pending_creator_verifications.seq"));
+    // The held verify wins over the mint's row only when it is at least as recent.
+    assert!(log[pending..].contains("a.seq >= c.seq"));
+}
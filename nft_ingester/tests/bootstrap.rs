@@ -0,0 +1,114 @@
+use blockbuster::token_metadata::state::Key;
+use nft_ingester::bootstrap::{
+    BootstrapConfig, BootstrapPhase, SnapshotAccounts, SNAPSHOT_TOKEN_METADATA_KEYS,
+};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+const TOKEN_METADATA: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+fn metadata_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA.as_ref(), mint.as_ref()],
+        &TOKEN_METADATA,
+    )
+    .0
+}
+
+fn edition_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA.as_ref(),
+            mint.as_ref(),
+            b"edition",
+        ],
+        &TOKEN_METADATA,
+    )
+    .0
+}
+
+#[test]
+fn a_collection_is_sent_as_its_mint_metadata_and_edition() {
+    let mint = Pubkey::new_unique();
+    let mut snapshot = SnapshotAccounts::default();
+    snapshot.add_collection(mint);
+
+    assert_eq!(
+        snapshot.accounts(),
+        &[mint, metadata_pda(&mint), edition_pda(&mint)]
+    );
+}
+
+#[test]
+fn every_metadata_is_sent_after_its_mint_and_once() {
+    let (collection, nft) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut snapshot = SnapshotAccounts::default();
+    snapshot.add_collection(collection);
+
+    // The token-metadata scan finds the collection's accounts again, and an ordinary NFT.
+    snapshot.add_metadata(metadata_pda(&collection), collection.as_ref());
+    snapshot.add_metadata(metadata_pda(&nft), nft.as_ref());
+    snapshot.add(edition_pda(&collection));
+    snapshot.add(edition_pda(&nft));
+
+    assert_eq!(
+        snapshot.accounts(),
+        &[
+            collection,
+            metadata_pda(&collection),
+            edition_pda(&collection),
+            nft,
+            metadata_pda(&nft),
+            edition_pda(&nft),
+        ]
+    );
+}
+
+#[test]
+fn a_metadata_without_its_mint_is_still_sent() {
+    let metadata = Pubkey::new_unique();
+    let mut snapshot = SnapshotAccounts::default();
+    snapshot.add_metadata(metadata, &[]);
+
+    assert_eq!(snapshot.accounts(), &[metadata]);
+}
+
+#[test]
+fn the_snapshot_covers_ordinary_and_programmable_nfts() {
+    assert!(BootstrapConfig::default().get_snapshot_token_metadata());
+    for key in [
+        Key::MetadataV1,
+        Key::MasterEditionV2,
+        Key::EditionV1,
+        Key::TokenRecord,
+    ] {
+        assert!(SNAPSHOT_TOKEN_METADATA_KEYS.contains(&key));
+    }
+    let config = BootstrapConfig {
+        snapshot_token_metadata: Some(false),
+        ..Default::default()
+    };
+    assert!(!config.get_snapshot_token_metadata());
+}
+
+#[test]
+fn phases_run_in_order() {
+    let names: Vec<_> = BootstrapPhase::ALL.iter().map(|p| p.name()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "discover_trees",
+            "replay_trees",
+            "snapshot_accounts",
+            "download_metadata"
+        ]
+    );
+    let config = BootstrapConfig {
+        concurrency: Some(0),
+        max_transactions_per_second: Some(0),
+        ..Default::default()
+    };
+    // Neither can stop the bootstrap.
+    assert_eq!(config.get_concurrency(), 1);
+    assert_eq!(config.get_max_transactions_per_second(), None);
+}
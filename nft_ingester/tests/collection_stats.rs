@@ -0,0 +1,69 @@
+use digital_asset_types::dao::asset_grouping;
+use nft_ingester::collection_stats::record_collection_join;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+fn verified_grouping(asset_id: Vec<u8>, collection: &str) -> asset_grouping::Model {
+    asset_grouping::Model {
+        id: 1,
+        asset_id,
+        group_key: "collection".to_string(),
+        group_value: Some(collection.to_string()),
+        seq: None,
+        slot_updated: Some(1),
+        verified: Some(true),
+        group_info_seq: None,
+    }
+}
+
+#[tokio::test]
+async fn a_later_verification_counts_the_mint() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let collection = Keypair::new().pubkey().to_string();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![verified_grouping(asset_id.clone(), &collection)]])
+        .append_exec_results(vec![MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }])
+        .into_connection();
+
+    // Minted unverified, so it had no verified collection before.
+    record_collection_join(&db, asset_id, None, 10)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert_eq!(log.matches("INSERT INTO collection_stats").count(), 1);
+}
+
+#[tokio::test]
+async fn a_replayed_verification_isnt_counted_again() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let collection = Keypair::new().pubkey().to_string();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![verified_grouping(asset_id.clone(), &collection)]])
+        .into_connection();
+
+    record_collection_join(&db, asset_id, Some(collection), 10)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("collection_stats"));
+}
+
+#[tokio::test]
+async fn an_unverified_asset_isnt_counted() {
+    let asset_id = Keypair::new().pubkey().to_bytes().to_vec();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![Vec::<asset_grouping::Model>::new()])
+        .into_connection();
+
+    record_collection_join(&db, asset_id, None, 10)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    assert!(!log.contains("collection_stats"));
+}
@@ -0,0 +1,93 @@
+use digital_asset_types::dao::asset_grouping;
+use nft_ingester::collection_traits::{recount_collection_traits, update_shared_traits};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+use serde_json::json;
+use solana_sdk::{signature::Keypair, signer::Signer};
+use std::collections::BTreeMap;
+
+fn written() -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected: 1,
+    }
+}
+
+#[tokio::test]
+async fn a_changed_blob_moves_the_traits_of_the_assets_sharing_it() {
+    let downloaded = Keypair::new().pubkey().to_bytes().to_vec();
+    let sharing = Keypair::new().pubkey().to_bytes().to_vec();
+    let collection = Keypair::new().pubkey().to_string();
+    let old = json!({"attributes": [{"trait_type": "Eyes", "value": "Blue"}]});
+    let new = json!({"attributes": [{"trait_type": "Eyes", "value": "Green"}]});
+
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(vec![vec![BTreeMap::from([(
+            "id",
+            Value::from(sharing.clone()),
+        )])]])
+        .append_query_results(vec![vec![asset_grouping::Model {
+            id: 1,
+            asset_id: sharing.clone(),
+            group_key: "collection".to_string(),
+            group_value: Some(collection.clone()),
+            seq: None,
+            slot_updated: Some(1),
+            verified: Some(true),
+            group_info_seq: None,
+        }]])
+        .append_exec_results(vec![written(), written(), written()])
+        .into_connection();
+
+    update_shared_traits(&db, vec![1; 32], downloaded, &old, &new)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    // The sharing asset's collection gains the new trait and loses the old one.
+    assert!(log.contains("Green"));
+    assert!(log.contains("Blue"));
+    assert_eq!(log.matches("INSERT INTO collection_traits").count(), 2);
+    assert_eq!(log.matches("DELETE FROM collection_traits").count(), 1);
+}
+
+#[tokio::test]
+async fn an_unchanged_blob_leaves_the_trait_counts_alone() {
+    let traits = json!({"attributes": [{"trait_type": "Eyes", "value": "Blue"}]});
+    let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+    update_shared_traits(&db, vec![1; 32], vec![2; 32], &traits, &traits)
+        .await
+        .unwrap();
+
+    assert!(db.into_transaction_log().is_empty());
+}
+
+#[tokio::test]
+async fn a_recount_replaces_the_counts_with_those_of_the_unburnt_assets() {
+    let collection = Keypair::new().pubkey().to_string();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results(vec![
+            written(),
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 4,
+            },
+        ])
+        .into_connection();
+
+    let counted = recount_collection_traits(&db, collection.clone())
+        .await
+        .unwrap();
+    assert_eq!(counted, 4);
+
+    let log = format!("{:?}", db.into_transaction_log());
+    let deleted = log.find("DELETE FROM collection_traits").unwrap();
+    let counted = log.find("INSERT INTO collection_traits").unwrap();
+    assert!(deleted < counted);
+    assert!(log.contains(&collection));
+    // Only the verified members that aren't burnt are counted.
+    assert!(log[counted..].contains("NOT asset.burnt"));
+    assert!(log[counted..].contains("asset_grouping.verified"));
+    // Shared metadata is read through its blob.
+    assert!(log[counted..].contains("COALESCE(metadata_blob.metadata, asset_data.metadata)"));
+}
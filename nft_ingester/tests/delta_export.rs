@@ -0,0 +1,57 @@
+use nft_ingester::{
+    asset_changes::ChangeType,
+    delta_export::{DeltaExportConfig, ExportedChange},
+};
+use std::collections::HashMap;
+
+fn config() -> DeltaExportConfig {
+    DeltaExportConfig {
+        brokers: "kafka:9092".to_string(),
+        topics: HashMap::from([(ChangeType::Transfer, "asset_transfers".to_string())]),
+        default_topic: Some("changes".to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn change_names_round_trip() {
+    for change in [
+        ChangeType::Mint,
+        ChangeType::Transfer,
+        ChangeType::Burn,
+        ChangeType::Delegate,
+        ChangeType::Freeze,
+        ChangeType::Compression,
+        ChangeType::Verification,
+        ChangeType::Metadata,
+    ] {
+        assert_eq!(ChangeType::from_name(&change.name()), Some(change));
+    }
+    assert_eq!(ChangeType::Transfer.name(), "transfer");
+}
+
+#[test]
+fn changes_go_to_their_topic() {
+    let config = config();
+    assert_eq!(config.topic_of("transfer"), "asset_transfers");
+    assert_eq!(config.topic_of("burn"), "changes");
+}
+
+#[test]
+fn unknown_changes_go_to_the_default_topic() {
+    assert_eq!(config().topic_of("royalty"), "changes");
+    assert_eq!(
+        DeltaExportConfig::default().topic_of("royalty"),
+        "asset_changes"
+    );
+}
+
+#[test]
+fn unknown_changes_are_exported_as_written() {
+    let event = ExportedChange::new(42, &[1; 32], "royalty".to_string(), 200);
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(json["change"], "royalty");
+    assert_eq!(json["event_id"], 42);
+    assert_eq!(json["slot"], 200);
+    assert_eq!(json["id"], bs58::encode([1; 32]).into_string());
+}
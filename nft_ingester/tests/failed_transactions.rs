@@ -0,0 +1,38 @@
+use nft_ingester::failed_transactions::logged_failure;
+
+const BUBBLEGUM: &str = "BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY";
+
+#[test]
+fn program_logs_mentioning_failure_are_not_failures() {
+    let logs = [
+        format!("Program {} invoke [1]", BUBBLEGUM),
+        "Program log: Instruction: Transfer".to_string(),
+        "Program log: retry after lookup failed: stale".to_string(),
+        "Program data: failed: AAAA".to_string(),
+        format!("Program {} success", BUBBLEGUM),
+    ];
+    assert_eq!(logged_failure(logs.iter().map(String::as_str)), None);
+}
+
+#[test]
+fn the_runtime_failure_line_is_the_error() {
+    let logs = [
+        format!("Program {} invoke [1]", BUBBLEGUM),
+        "Program log: Instruction: Transfer".to_string(),
+        format!("Program {} failed: custom program error: 0x1771", BUBBLEGUM),
+    ];
+    assert_eq!(
+        logged_failure(logs.iter().map(String::as_str)),
+        Some("custom program error: 0x1771".to_string())
+    );
+}
+
+#[test]
+fn truncated_logs_read_as_successful() {
+    let logs = [
+        format!("Program {} invoke [1]", BUBBLEGUM),
+        "Program log: Instruction: Transfer".to_string(),
+        "Log truncated".to_string(),
+    ];
+    assert_eq!(logged_failure(logs.iter().map(String::as_str)), None);
+}
@@ -0,0 +1,109 @@
+use std::{thread::sleep, time::Duration};
+
+use nft_ingester::tasks::{ContentPath, Gateway};
+
+const COOLDOWN: Duration = Duration::from_millis(50);
+
+fn ipfs(path: &str) -> Option<ContentPath> {
+    Some(ContentPath::Ipfs(path.to_string()))
+}
+
+fn arweave(path: &str) -> Option<ContentPath> {
+    Some(ContentPath::Arweave(path.to_string()))
+}
+
+#[test]
+fn content_paths_parse_from_ipfs_uris() {
+    assert_eq!(ContentPath::parse("ipfs://bafy123"), ipfs("bafy123"));
+    assert_eq!(
+        ContentPath::parse("ipfs://bafy123/1.json"),
+        ipfs("bafy123/1.json")
+    );
+    assert_eq!(
+        ContentPath::parse("ipfs://ipfs/bafy123/1.json"),
+        ipfs("bafy123/1.json")
+    );
+    assert_eq!(
+        ContentPath::parse("https://ipfs.io/ipfs/bafy123/1.json?ext=png"),
+        ipfs("bafy123/1.json?ext=png")
+    );
+    assert_eq!(
+        ContentPath::parse("https://bafy123.ipfs.nftstorage.link/1.json"),
+        ipfs("bafy123/1.json")
+    );
+    assert_eq!(
+        ContentPath::parse("https://bafy123.ipfs.nftstorage.link"),
+        ipfs("bafy123")
+    );
+    assert_eq!(ContentPath::parse("https://ipfs.io/ipfs/"), None);
+}
+
+#[test]
+fn content_paths_parse_from_arweave_uris() {
+    assert_eq!(ContentPath::parse("ar://txid"), arweave("txid"));
+    assert_eq!(
+        ContentPath::parse("ar://txid/0.json"),
+        arweave("txid/0.json")
+    );
+    assert_eq!(
+        ContentPath::parse("https://arweave.net/txid?ext=png"),
+        arweave("txid?ext=png")
+    );
+    assert_eq!(
+        ContentPath::parse("https://www.arweave.net/txid"),
+        arweave("txid")
+    );
+    assert_eq!(ContentPath::parse("https://arweave.net/"), None);
+}
+
+#[test]
+fn other_uris_have_no_content_path() {
+    assert_eq!(ContentPath::parse("https://example.com/1.json"), None);
+    assert_eq!(ContentPath::parse("data:application/json,{}"), None);
+    assert_eq!(ContentPath::parse("not a uri"), None);
+}
+
+#[test]
+fn gateway_opens_after_consecutive_failures() {
+    let gateway = Gateway::new("https://ipfs.io/", 2, COOLDOWN);
+    gateway.record_failure();
+    assert!(gateway.is_available());
+    gateway.record_success();
+    gateway.record_failure();
+    assert!(gateway.is_available());
+    gateway.record_failure();
+    assert!(!gateway.is_available());
+}
+
+#[test]
+fn half_open_gateway_lets_a_single_probe_through() {
+    let gateway = Gateway::new("https://ipfs.io", 1, COOLDOWN);
+    gateway.record_failure();
+    assert!(!gateway.is_available());
+
+    sleep(COOLDOWN);
+    assert!(gateway.is_available());
+    assert!(!gateway.is_available());
+    assert!(!gateway.is_available());
+
+    gateway.record_success();
+    assert!(gateway.is_available());
+    assert!(gateway.is_available());
+}
+
+#[test]
+fn failed_probe_opens_the_gateway_again() {
+    let gateway = Gateway::new("https://ipfs.io", 3, COOLDOWN);
+    for _ in 0..3 {
+        gateway.record_failure();
+    }
+    sleep(COOLDOWN);
+    assert!(gateway.is_available());
+    gateway.record_failure();
+    assert!(!gateway.is_available());
+
+    // Another probe once the new cooldown elapsed.
+    sleep(COOLDOWN);
+    assert!(gateway.is_available());
+    assert!(!gateway.is_available());
+}
@@ -0,0 +1,29 @@
+use nft_ingester::memory_budget::MemoryBudget;
+
+#[tokio::test]
+async fn batches_shrink_to_what_the_budget_holds() {
+    let budget = MemoryBudget::new("TXN", 1);
+    // Nothing was read yet, the configured batch size applies.
+    assert_eq!(budget.batch_size(100), 100);
+
+    drop(budget.reserve(&vec![0; 64 * 1024]).await);
+    // A MiB holds 16 payloads of 64 KiB.
+    assert_eq!(budget.batch_size(100), 16);
+    assert_eq!(budget.batch_size(10), 10);
+
+    // Payloads as large as the budget are read one at a time.
+    drop(budget.reserve(&vec![0; 4 * 1024 * 1024]).await);
+    assert_eq!(budget.batch_size(100), 1);
+}
+
+#[tokio::test]
+async fn batches_grow_back_as_payloads_get_smaller() {
+    let budget = MemoryBudget::new("ACC", 1);
+    drop(budget.reserve(&vec![0; 1024 * 1024]).await);
+    assert_eq!(budget.batch_size(100), 1);
+
+    for _ in 0..64 {
+        drop(budget.reserve(&[0; 1024]).await);
+    }
+    assert_eq!(budget.batch_size(100), 100);
+}
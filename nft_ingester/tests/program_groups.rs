@@ -0,0 +1,125 @@
+use digital_asset_types::dao::MPL_CORE_COLLECTION_GROUP_KEY;
+use nft_ingester::program_transformers::{
+    upsert_program_groupings, ProgramGroupConfig, ProgramGrouping, ProgramGroups, MPL_CORE_PROGRAM,
+};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use solana_sdk::pubkey::Pubkey;
+
+// The start of an MPL Core `AssetV1`: its key, owner and update authority.
+fn core_asset(update_authority: u8, authority: Pubkey) -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.push(update_authority);
+    data.extend_from_slice(authority.as_ref());
+    data
+}
+
+fn guild(program: Pubkey) -> ProgramGroupConfig {
+    ProgramGroupConfig {
+        program: program.to_string(),
+        group_key: "guild".to_string(),
+        discriminator: vec![2],
+        asset_offset: 1,
+        value_offset: 33,
+    }
+}
+
+#[test]
+fn a_core_asset_is_grouped_under_its_collection() {
+    let (asset, collection) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let groups = ProgramGroups::default();
+
+    let grouping = |data: &[u8]| groups.groupings(&MPL_CORE_PROGRAM, asset.as_ref(), data);
+    assert_eq!(
+        grouping(&core_asset(2, collection)),
+        Some(vec![ProgramGrouping {
+            asset_id: asset.to_bytes().to_vec(),
+            group_key: MPL_CORE_COLLECTION_GROUP_KEY.to_string(),
+            group_value: Some(collection.to_string()),
+        }])
+    );
+    // An asset with an address as update authority, or burnt, is in no collection.
+    for data in [core_asset(1, collection), vec![0]] {
+        let groupings = grouping(&data).unwrap();
+        assert_eq!(groupings.len(), 1);
+        assert_eq!(groupings[0].group_value, None);
+    }
+    // Collection accounts group nothing.
+    assert_eq!(grouping(&[5]), Some(vec![]));
+}
+
+#[test]
+fn configured_programs_group_the_accounts_with_their_discriminator() {
+    let program = Pubkey::new_unique();
+    let (asset, guild_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let groups = ProgramGroups::new(&[guild(program)]);
+    let mut data = vec![2];
+    data.extend_from_slice(asset.as_ref());
+    data.extend_from_slice(guild_key.as_ref());
+
+    let groupings = groups.groupings(&program, &[], &data).unwrap();
+    assert_eq!(
+        groupings,
+        vec![ProgramGrouping {
+            asset_id: asset.to_bytes().to_vec(),
+            group_key: "guild".to_string(),
+            group_value: Some(guild_key.to_string()),
+        }]
+    );
+    // Other accounts of the program, and the ones too short, group nothing.
+    data[0] = 3;
+    assert_eq!(groups.groupings(&program, &[], &data), Some(vec![]));
+    assert_eq!(groups.groupings(&program, &[], &[2, 0]), Some(vec![]));
+    assert_eq!(groups.groupings(&Pubkey::new_unique(), &[], &data), None);
+}
+
+#[test]
+fn groups_under_reserved_keys_or_of_indexed_programs_are_ignored() {
+    let program = Pubkey::new_unique();
+    let reserved = ProgramGroupConfig {
+        group_key: "collection".to_string(),
+        ..guild(program)
+    };
+    let indexed = guild(mpl_bubblegum::id());
+    let groups = ProgramGroups::new(&[reserved, indexed]);
+
+    assert_eq!(groups.groupings(&program, &[], &[2; 65]), None);
+    assert_eq!(groups.groupings(&mpl_bubblegum::id(), &[], &[2; 65]), None);
+}
+
+#[tokio::test]
+async fn groupings_are_written_unless_a_later_update_was() {
+    let asset_id = Pubkey::new_unique().to_bytes().to_vec();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results(vec![
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+            MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            },
+        ])
+        .into_connection();
+    let grouping = ProgramGrouping {
+        asset_id,
+        group_key: MPL_CORE_COLLECTION_GROUP_KEY.to_string(),
+        group_value: Some(Pubkey::new_unique().to_string()),
+    };
+    let removed = ProgramGrouping {
+        group_value: None,
+        ..grouping.clone()
+    };
+
+    upsert_program_groupings(&db, &[grouping, removed], 10)
+        .await
+        .unwrap();
+
+    let log = format!("{:?}", db.into_transaction_log());
+    let insert = log.find("INSERT INTO asset_grouping").unwrap();
+    let delete = log.find("DELETE FROM asset_grouping").unwrap();
+    assert!(insert < delete);
+    assert!(log.contains("excluded.slot_updated >= asset_grouping.slot_updated"));
+    assert!(log[delete..].contains("slot_updated <= $3"));
+}
@@ -0,0 +1,91 @@
+use figment::value::{Dict, Value};
+use nft_ingester::{redis_failover::RedisEndpoints, stream_control::StreamProducer};
+use plerkle_messenger::{
+    redis_messenger::RedisMessenger, MessengerConfig, MessengerType, TRANSACTION_STREAM,
+};
+
+// Nothing listens on these ports, connecting fails right away.
+const PRIMARY: &str = "redis://127.0.0.1:1";
+const BACKUP: &str = "redis://127.0.0.1:2";
+
+fn config(backups: &[&str], failover_after: Option<u32>) -> MessengerConfig {
+    let mut connection_config = Dict::new();
+    connection_config.insert("redis_connection_str".to_string(), Value::from(PRIMARY));
+    if !backups.is_empty() {
+        connection_config.insert(
+            "redis_backup_connection_strs".to_string(),
+            Value::from(backups.to_vec()),
+        );
+    }
+    if let Some(errors) = failover_after {
+        connection_config.insert(
+            "redis_failover_after_errors".to_string(),
+            Value::from(errors),
+        );
+    }
+    MessengerConfig {
+        messenger_type: MessengerType::Redis,
+        connection_config,
+    }
+}
+
+fn url(config: &MessengerConfig) -> Option<&str> {
+    config
+        .connection_config
+        .get("redis_connection_str")
+        .and_then(Value::as_str)
+}
+
+#[test]
+fn instances_are_tried_in_order_and_wrap_around() {
+    let config = config(&[BACKUP], None);
+    let endpoints = RedisEndpoints::from_config(&config);
+    assert_eq!(endpoints.instances(), 2);
+    assert_eq!(url(&endpoints.apply(&config, 0)), Some(PRIMARY));
+    assert_eq!(url(&endpoints.apply(&config, 1)), Some(BACKUP));
+    assert_eq!(url(&endpoints.apply(&config, 2)), Some(PRIMARY));
+}
+
+#[test]
+fn a_failover_is_applied_once_per_generation() {
+    let endpoints = RedisEndpoints::from_config(&config(&[BACKUP], None));
+    let other = endpoints.clone();
+    // Two consumers seeing the primary fail move to the backup, not past it.
+    endpoints.fail_over(0);
+    other.fail_over(0);
+    assert_eq!(endpoints.generation(), 1);
+    assert_eq!(other.generation(), 1);
+    other.fail_over(1);
+    assert_eq!(endpoints.generation(), 2);
+}
+
+#[test]
+fn without_backups_the_primary_is_kept() {
+    let endpoints = RedisEndpoints::from_config(&config(&[], None));
+    endpoints.fail_over(0);
+    assert_eq!(endpoints.generation(), 0);
+    assert_eq!(endpoints.instances(), 1);
+}
+
+#[test]
+fn failover_after_defaults_to_three_and_is_at_least_one() {
+    let endpoints = RedisEndpoints::from_config(&config(&[], None));
+    assert_eq!(endpoints.failover_after(), 3);
+    let endpoints = RedisEndpoints::from_config(&config(&[], Some(0)));
+    assert_eq!(endpoints.failover_after(), 1);
+    let endpoints = RedisEndpoints::from_config(&config(&[], Some(5)));
+    assert_eq!(endpoints.failover_after(), 5);
+}
+
+#[tokio::test]
+async fn a_producer_fails_over_with_the_consumers() {
+    let config = config(&[BACKUP], Some(1));
+    let endpoints = RedisEndpoints::from_config(&config);
+    let consumers = endpoints.clone();
+
+    // Every instance is down: the producer moves to the backup, then gives up.
+    let producer =
+        StreamProducer::<RedisMessenger>::new(config, endpoints, &[TRANSACTION_STREAM]).await;
+    assert!(producer.is_err());
+    assert_eq!(consumers.generation(), 1);
+}
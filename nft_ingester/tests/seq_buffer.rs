@@ -0,0 +1,115 @@
+use nft_ingester::seq_buffer::{SeqBufferConfig, SeqQueues};
+use solana_sdk::pubkey::Pubkey;
+use tokio::time::{Duration, Instant};
+
+fn config() -> SeqBufferConfig {
+    SeqBufferConfig {
+        max_gap: Some(5),
+        max_wait: Some(1000),
+        max_held: Some(10),
+        max_held_mb: None,
+    }
+}
+
+#[test]
+fn transactions_ahead_of_their_tree_are_held_until_their_predecessor_applies() {
+    let (tree, now) = (Pubkey::new_unique(), Instant::now());
+    let mut queues = SeqQueues::new(config(), None);
+    queues.advance(tree, 10, now);
+
+    assert_eq!(queues.hold(tree, 11, 1, "11", now), Some("11"));
+    assert_eq!(queues.hold(tree, 13, 1, "13", now), None);
+    assert_eq!(queues.hold(tree, 12, 1, "12", now), None);
+    assert_eq!(queues.hold(tree, 15, 1, "15", now), None);
+    assert_eq!(queues.held(), 3);
+
+    // 11 releases 12, which releases 13, and 15 waits for 14.
+    assert_eq!(queues.release(tree, 11, now), vec!["12"]);
+    assert_eq!(queues.release(tree, 12, now), vec!["13"]);
+    assert!(queues.release(tree, 13, now).is_empty());
+    assert_eq!(queues.release(tree, 14, now), vec!["15"]);
+    assert_eq!(queues.held(), 0);
+}
+
+#[test]
+fn transactions_of_unknown_trees_or_too_far_ahead_apply_right_away() {
+    let (tree, now) = (Pubkey::new_unique(), Instant::now());
+    let mut queues = SeqQueues::new(config(), None);
+    assert_eq!(queues.hold(tree, 7, 1, "7", now), Some("7"));
+
+    queues.advance(tree, 10, now);
+    assert_eq!(queues.hold(tree, 17, 1, "17", now), Some("17"));
+    assert_eq!(queues.hold(tree, 9, 1, "9", now), Some("9"));
+    assert_eq!(queues.hold(tree, 16, 1, "16", now), None);
+}
+
+#[test]
+fn held_transactions_are_bounded_by_count_and_bytes() {
+    let (tree, now) = (Pubkey::new_unique(), Instant::now());
+    let mut queues = SeqQueues::new(
+        SeqBufferConfig {
+            max_held: Some(2),
+            ..config()
+        },
+        None,
+    );
+    queues.advance(tree, 10, now);
+    assert_eq!(queues.hold(tree, 12, 1, 12, now), None);
+    assert_eq!(queues.hold(tree, 13, 1, 13, now), None);
+    assert_eq!(queues.hold(tree, 14, 1, 14, now), Some(14));
+
+    let mut queues = SeqQueues::new(config(), Some(100));
+    queues.advance(tree, 10, now);
+    assert_eq!(queues.hold(tree, 12, 60, 12, now), None);
+    assert_eq!(queues.hold(tree, 13, 60, 13, now), Some(13));
+    assert_eq!(queues.held_bytes(), 60);
+    // A redelivery replaces the held transaction and its size.
+    assert_eq!(queues.hold(tree, 12, 40, 120, now), None);
+    assert_eq!(queues.held_bytes(), 40);
+    assert_eq!(queues.hold(tree, 13, 60, 13, now), None);
+    assert_eq!(queues.held_bytes(), 100);
+    assert_eq!(queues.release(tree, 11, now), vec![120]);
+    assert_eq!(queues.held_bytes(), 60);
+}
+
+#[test]
+fn expired_transactions_take_the_ones_before_them_in_seq_order() {
+    let (tree, now) = (Pubkey::new_unique(), Instant::now());
+    let mut queues = SeqQueues::new(config(), None);
+    queues.advance(tree, 10, now);
+    assert_eq!(queues.hold(tree, 13, 1, "13", now), None);
+    let later = now + Duration::from_millis(500);
+    assert_eq!(queues.hold(tree, 12, 1, "12", later), None);
+    assert_eq!(queues.hold(tree, 15, 1, "15", later), None);
+
+    assert!(queues.expire(now + Duration::from_millis(999)).is_empty());
+    // 13 waited long enough and takes 12 along, 15 waits on.
+    assert_eq!(
+        queues.expire(now + Duration::from_millis(1000)),
+        vec!["12", "13"]
+    );
+    assert_eq!(queues.held(), 1);
+    assert_eq!(
+        queues.expire(later + Duration::from_millis(1000)),
+        vec!["15"]
+    );
+    assert_eq!(queues.held(), 0);
+}
+
+#[test]
+fn idle_trees_are_forgotten() {
+    let (idle, busy, now) = (Pubkey::new_unique(), Pubkey::new_unique(), Instant::now());
+    let mut queues = SeqQueues::<()>::new(config(), None);
+    queues.advance(idle, 10, now);
+    queues.advance(busy, 10, now);
+
+    let later = now + Duration::from_secs(600);
+    queues.advance(busy, 11, later);
+    queues.expire(now + Duration::from_secs(599));
+    assert_eq!(queues.trees(), 2);
+    queues.expire(later);
+    assert_eq!(queues.trees(), 1);
+    // A forgotten tree's seq is unknown again.
+    assert!(queues.ahead(&idle, 11));
+    assert!(!queues.ahead(&busy, 12));
+}
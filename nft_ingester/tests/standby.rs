@@ -0,0 +1,61 @@
+use nft_ingester::{
+    redis_failover::GroupProgress,
+    standby::{replay_from, replay_start, unacknowledged_from},
+};
+use std::time::Duration;
+
+fn progress(last_delivered: &str, oldest_pending: Option<&str>) -> GroupProgress {
+    GroupProgress {
+        last_delivered: last_delivered.to_string(),
+        oldest_pending: oldest_pending.map(str::to_string),
+    }
+}
+
+#[test]
+fn replay_goes_back_the_window_from_the_checkpoint() {
+    let window = Duration::from_secs(30);
+    assert_eq!(
+        replay_from("1690000030000-7", window).as_deref(),
+        Some("1690000000000-0")
+    );
+    // A window longer than the stream goes back to its start.
+    assert_eq!(replay_from("1000-0", window).as_deref(), Some("0-0"));
+    assert_eq!(replay_from("not-an-id", window), None);
+}
+
+#[test]
+fn unacknowledged_entries_are_delivered_again() {
+    assert_eq!(
+        unacknowledged_from(&progress("1690000050000-0", Some("1690000020000-3"))).as_deref(),
+        Some("1690000020000-2")
+    );
+    assert_eq!(
+        unacknowledged_from(&progress("1690000050000-0", Some("1690000020000-0"))).as_deref(),
+        Some("1690000019999-18446744073709551615")
+    );
+    assert_eq!(
+        unacknowledged_from(&progress("1690000050000-0", None)).as_deref(),
+        Some("1690000050000-0")
+    );
+}
+
+#[test]
+fn replay_starts_at_the_earlier_of_the_checkpoint_and_the_primaries() {
+    let window = Duration::from_secs(30);
+    // The primaries stalled on an entry from long before the checkpoint.
+    let stalled = progress("1690000090000-0", Some("1689990000000-5"));
+    assert_eq!(
+        replay_start("1690000100000-0", window, Some(&stalled)).as_deref(),
+        Some("1689990000000-4")
+    );
+    // Primaries that are caught up leave the checkpoint's window.
+    let caught_up = progress("1690000100000-0", None);
+    assert_eq!(
+        replay_start("1690000100000-0", window, Some(&caught_up)).as_deref(),
+        Some("1690000070000-0")
+    );
+    assert_eq!(
+        replay_start("1690000100000-0", window, None).as_deref(),
+        Some("1690000070000-0")
+    );
+}
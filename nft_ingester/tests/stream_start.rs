@@ -0,0 +1,49 @@
+use nft_ingester::stream_start::{is_stream_id, StreamOffset, StreamStartConfig};
+
+#[test]
+fn stream_offsets_parse_from_names_and_ids() {
+    assert_eq!(
+        "beginning".parse::<StreamOffset>().unwrap(),
+        StreamOffset::Beginning
+    );
+    assert_eq!(
+        "0".parse::<StreamOffset>().unwrap(),
+        StreamOffset::Beginning
+    );
+    assert_eq!(
+        "latest".parse::<StreamOffset>().unwrap(),
+        StreamOffset::Latest
+    );
+    assert_eq!("$".parse::<StreamOffset>().unwrap(), StreamOffset::Latest);
+    assert_eq!(
+        "1690000000000-3".parse::<StreamOffset>().unwrap(),
+        StreamOffset::Id("1690000000000-3".to_string())
+    );
+    assert_eq!(
+        "1690000000000".parse::<StreamOffset>().unwrap().id(),
+        "1690000000000"
+    );
+    assert!("newest".parse::<StreamOffset>().is_err());
+    assert!("".parse::<StreamOffset>().is_err());
+}
+
+#[test]
+fn stream_ids_are_a_timestamp_and_an_optional_sequence() {
+    assert!(is_stream_id("1690000000000"));
+    assert!(is_stream_id("1690000000000-0"));
+    assert!(!is_stream_id("1690000000000-"));
+    assert!(!is_stream_id("-1"));
+    assert!(!is_stream_id("1690000000000-0-1"));
+    assert!(!is_stream_id("abc-1"));
+}
+
+#[test]
+fn no_offset_leaves_the_groups_to_the_messenger() {
+    let config = StreamStartConfig::default();
+    assert_eq!(config.get_offset().unwrap(), None);
+    let config = StreamStartConfig {
+        offset: Some("latest".to_string()),
+        reset_existing_group: true,
+    };
+    assert_eq!(config.get_offset().unwrap(), Some(StreamOffset::Latest));
+}
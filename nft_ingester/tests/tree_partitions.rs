@@ -0,0 +1,32 @@
+use nft_ingester::program_transformers::{ensure_tree_partitions, without_partition_cache};
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use solana_sdk::{signature::Keypair, signer::Signer};
+
+fn created() -> MockExecResult {
+    MockExecResult {
+        last_insert_id: 0,
+        rows_affected: 0,
+    }
+}
+
+#[tokio::test]
+async fn partitions_created_by_a_sampled_candidate_are_created_again() {
+    // A tree first seen by a transaction whose assertion is sampled: the candidate creates the
+    // partitions in a transaction that is rolled back, then the ingester writes the tree.
+    let tree = Keypair::new().pubkey().to_bytes().to_vec();
+    let db = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_exec_results(vec![created(), created(), created(), created()])
+        .into_connection();
+
+    without_partition_cache(async {
+        ensure_tree_partitions(&db, &tree).await.unwrap();
+        // The candidate's later writes of the tree in the same transaction don't create them again.
+        ensure_tree_partitions(&db, &tree).await.unwrap();
+    })
+    .await;
+    ensure_tree_partitions(&db, &tree).await.unwrap();
+    ensure_tree_partitions(&db, &tree).await.unwrap();
+
+    // cl_items and cl_audits, once by the candidate and once by the ingester.
+    assert_eq!(db.into_transaction_log().len(), 4);
+}
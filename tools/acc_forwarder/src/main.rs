@@ -1,6 +1,7 @@
 use {
     anyhow::Context,
     clap::Parser,
+    das_tree_reader::{find_signatures, rpc_tx_with_retries, RpcThrottle},
     figment::{map, value::Value},
     futures::{future::try_join_all, stream::StreamExt},
     log::{info, warn},
@@ -28,8 +29,8 @@ use {
         UiParsedInstruction, UiTransactionEncoding,
     },
     std::{collections::HashSet, env, str::FromStr, sync::Arc},
-    tokio::sync::Mutex,
-    txn_forwarder::{find_signatures, read_lines, rpc_tx_with_retries},
+    tokio::{sync::Mutex, time::Duration},
+    txn_forwarder::read_lines,
 };
 
 #[derive(Parser)]
@@ -108,12 +109,14 @@ async fn main() -> anyhow::Result<()> {
     let messenger = Arc::new(Mutex::new(messenger));
 
     let client = RpcClient::new(args.rpc_url.clone());
+    // The account actions make one request at a time, the collection crawl has its own throttle.
+    let throttle = RpcThrottle::new(1);
 
     match args.action {
         Action::Single { account } => {
             let pubkey = Pubkey::from_str(&account)
                 .with_context(|| format!("failed to parse account {account}"))?;
-            fetch_and_send_account(pubkey, &client, &messenger).await?;
+            fetch_and_send_account(pubkey, &client, &throttle, &messenger).await?;
         }
         Action::Scenario { scenario_file } => {
             let mut accounts = read_lines(&scenario_file).await?;
@@ -122,13 +125,16 @@ async fn main() -> anyhow::Result<()> {
                     Ok(account) => match account.parse() {
                         Ok(mint) => {
                             let metadata_account = find_metadata_account(&mint).0;
-                            let token_account = get_token_largest_account(&client, mint).await;
+                            let token_account =
+                                get_token_largest_account(&client, &throttle, mint).await;
 
                             match token_account {
                                 Ok(token_account) => {
                                     for pubkey in &[mint, metadata_account, token_account] {
-                                        match fetch_and_send_account(*pubkey, &client, &messenger)
-                                            .await
+                                        match fetch_and_send_account(
+                                            *pubkey, &client, &throttle, &messenger,
+                                        )
+                                        .await
                                         {
                                             Ok(_) => {}
                                             Err(e) => {
@@ -158,12 +164,12 @@ async fn main() -> anyhow::Result<()> {
             let mint =
                 Pubkey::from_str(&mint).with_context(|| format!("failed to parse mint {mint}"))?;
             let metadata_account = find_metadata_account(&mint).0;
-            let token_account = get_token_largest_account(&client, mint).await;
+            let token_account = get_token_largest_account(&client, &throttle, mint).await;
 
             match token_account {
                 Ok(token_account) => {
                     for pubkey in &[mint, metadata_account, token_account] {
-                        fetch_and_send_account(*pubkey, &client, &messenger).await?;
+                        fetch_and_send_account(*pubkey, &client, &throttle, &messenger).await?;
                     }
                 }
                 Err(e) => warn!("Failed to find mint account: {:?}", e),
@@ -177,8 +183,16 @@ async fn main() -> anyhow::Result<()> {
 
             let collection = Pubkey::from_str(&collection)
                 .with_context(|| format!("failed to parse collection {collection}"))?;
+            let throttle = RpcThrottle::new(concurrency);
+            throttle.spawn_reporter(Duration::from_secs(10));
             let stream = Arc::new(Mutex::new(find_signatures(
-                collection, client, None, None, 2_000, false,
+                collection,
+                client,
+                throttle.clone(),
+                None,
+                None,
+                2_000,
+                false,
             )));
 
             try_join_all((0..concurrency).map(|_| {
@@ -186,6 +200,7 @@ async fn main() -> anyhow::Result<()> {
                 let stream = Arc::clone(&stream);
                 let client = RpcClient::new(args.rpc_url.clone());
                 let messenger = Arc::clone(&messenger);
+                let throttle = throttle.clone();
                 async move {
                     loop {
                         let mut locked = stream.lock().await;
@@ -194,7 +209,8 @@ async fn main() -> anyhow::Result<()> {
 
                         let mut txinfo = match maybe_signature {
                             Some(signature) => {
-                                match collection_get_tx_info(&client, signature?).await? {
+                                match collection_get_tx_info(&client, &throttle, signature?).await?
+                                {
                                     Some(txinfo) => txinfo,
                                     None => continue,
                                 }
@@ -211,8 +227,10 @@ async fn main() -> anyhow::Result<()> {
                         drop(locked);
 
                         if inserted {
-                            match fetch_metadata_and_send_accounts(account, &client, &messenger)
-                                .await
+                            match fetch_metadata_and_send_accounts(
+                                account, &client, &throttle, &messenger,
+                            )
+                            .await
                             {
                                 Ok(_) => info!("Uploaded {:?}", account),
                                 Err(e) => warn!("Could not insert {:?}: {:?}", account, e),
@@ -232,6 +250,7 @@ async fn main() -> anyhow::Result<()> {
 // fetch tx and filter
 async fn collection_get_tx_info(
     client: &RpcClient,
+    throttle: &RpcThrottle,
     signature: Signature,
 ) -> anyhow::Result<Option<CollectionTransactionInfo>> {
     const CONFIG: RpcTransactionConfig = RpcTransactionConfig {
@@ -244,6 +263,7 @@ async fn collection_get_tx_info(
 
     let tx: EncodedConfirmedTransactionWithStatusMeta = rpc_tx_with_retries(
         client,
+        throttle,
         RpcRequest::GetTransaction,
         serde_json::json!([signature.to_string(), CONFIG]),
         3,
@@ -321,25 +341,31 @@ async fn collection_get_tx_info(
 async fn fetch_metadata_and_send_accounts(
     pubkey: Pubkey,
     client: &RpcClient,
+    throttle: &RpcThrottle,
     messenger: &Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
 ) -> anyhow::Result<()> {
-    let (account, _slot) = fetch_account(pubkey, client).await?;
+    let (account, _slot) = fetch_account(pubkey, client, throttle).await?;
     let metadata: Metadata = try_from_slice_unchecked(&account.data)
         .with_context(|| anyhow::anyhow!("failed to parse data for metadata account {pubkey}"))?;
 
     info!("Fetching token largest accounts: {:?}", metadata.mint);
-    let token_account = get_token_largest_account(client, metadata.mint).await?;
+    let token_account = get_token_largest_account(client, throttle, metadata.mint).await?;
 
     for pubkey in &[metadata.mint, pubkey, token_account] {
-        fetch_and_send_account(*pubkey, client, messenger).await?;
+        fetch_and_send_account(*pubkey, client, throttle, messenger).await?;
     }
     Ok(())
 }
 
 // returns largest (NFT related) token account belonging to mint
-async fn get_token_largest_account(client: &RpcClient, mint: Pubkey) -> anyhow::Result<Pubkey> {
+async fn get_token_largest_account(
+    client: &RpcClient,
+    throttle: &RpcThrottle,
+    mint: Pubkey,
+) -> anyhow::Result<Pubkey> {
     let response: RpcResponse<Vec<RpcTokenAccountBalance>> = rpc_tx_with_retries(
         client,
+        throttle,
         RpcRequest::Custom {
             method: "getTokenLargestAccounts",
         },
@@ -357,7 +383,11 @@ async fn get_token_largest_account(client: &RpcClient, mint: Pubkey) -> anyhow::
 }
 
 // fetch account and slot with retries
-async fn fetch_account(pubkey: Pubkey, client: &RpcClient) -> anyhow::Result<(Account, u64)> {
+async fn fetch_account(
+    pubkey: Pubkey,
+    client: &RpcClient,
+    throttle: &RpcThrottle,
+) -> anyhow::Result<(Account, u64)> {
     const CONFIG: RpcAccountInfoConfig = RpcAccountInfoConfig {
         encoding: Some(UiAccountEncoding::Base64Zstd),
         commitment: Some(CommitmentConfig {
@@ -369,6 +399,7 @@ async fn fetch_account(pubkey: Pubkey, client: &RpcClient) -> anyhow::Result<(Ac
 
     let response: RpcResponse<Option<UiAccount>> = rpc_tx_with_retries(
         client,
+        throttle,
         RpcRequest::GetAccountInfo,
         serde_json::json!([pubkey.to_string(), CONFIG]),
         3,
@@ -390,9 +421,10 @@ async fn fetch_account(pubkey: Pubkey, client: &RpcClient) -> anyhow::Result<(Ac
 async fn fetch_and_send_account(
     pubkey: Pubkey,
     client: &RpcClient,
+    throttle: &RpcThrottle,
     messenger: &Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
 ) -> anyhow::Result<()> {
-    let (account, slot) = fetch_account(pubkey, client).await?;
+    let (account, slot) = fetch_account(pubkey, client, throttle).await?;
     send_account(pubkey, account, slot, messenger).await
 }
 
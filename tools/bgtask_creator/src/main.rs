@@ -1,6 +1,6 @@
 use digital_asset_types::dao::{
     asset, asset_authority, asset_creators, asset_data, asset_grouping,
-    sea_orm_active_enums::TaskStatus, tasks, tokens,
+    scopes::metadata_blob::resolve_metadata_blobs, sea_orm_active_enums::TaskStatus, tasks, tokens,
 };
 
 use log::{debug, error, info};
@@ -12,7 +12,8 @@ use nft_ingester::{
     error::IngesterError,
     metrics::setup_metrics,
     tasks::{
-        BgTask, BgTaskConfig, DownloadMetadata, DownloadMetadataTask, IntoTaskData, TaskManager,
+        BgTask, BgTaskConfig, DownloadMetadata, DownloadMetadataTask, IntoTaskData,
+        MetadataFetcher, TaskManager,
     },
 };
 
@@ -159,6 +160,11 @@ pub async fn main() {
         timeout: Some(time::Duration::from_secs(
             task_runner_config.timeout.unwrap_or(3),
         )),
+        fetcher: MetadataFetcher::new(&task_runner_config, config.ipfs_gateway.clone()).unwrap(),
+        prober: None,
+        metadata_max_bytes: task_runner_config.metadata_max_bytes,
+        truncation_policy: task_runner_config.metadata_truncation.unwrap_or_default(),
+        blob_ttl: time::Duration::from_secs(task_runner_config.metadata_blob_ttl.unwrap_or(600)),
     })];
     let mut bg_tasks = HashMap::new();
     for task in bg_task_definitions {
@@ -186,11 +192,14 @@ pub async fn main() {
             // Re-using this field for simplicity.
             let asset_id = Pubkey::from_str(mint.unwrap()).unwrap();
             let asset_id_bytes = asset_id.clone().to_bytes().to_vec();
-            let asset_data = asset_data::Entity::find_by_id(asset_id_bytes.clone())
+            let mut asset_data = asset_data::Entity::find_by_id(asset_id_bytes.clone())
                 .one(&conn)
                 .await
                 .unwrap()
                 .unwrap();
+            resolve_metadata_blobs(&conn, std::slice::from_mut(&mut asset_data))
+                .await
+                .unwrap();
 
             println!("off-chain data for asset: {:?}", asset_data.metadata);
 
@@ -0,0 +1,460 @@
+use {
+    crate::client::{Client, INVALID_PARAMS, METHOD_NOT_FOUND},
+    anyhow::{ensure, Context},
+    serde_json::{json, Value},
+    solana_sdk::keccak,
+    std::collections::HashSet,
+};
+
+// The largest page the DAS spec requires endpoints to serve.
+const MAX_LIMIT: u64 = 1000;
+
+const ASSET_FIELDS: &[&str] = &[
+    "interface",
+    "id",
+    "content",
+    "authorities",
+    "compression",
+    "grouping",
+    "royalty",
+    "creators",
+    "ownership",
+    "supply",
+    "mutable",
+    "burnt",
+];
+const OWNERSHIP_FIELDS: &[&str] = &[
+    "frozen",
+    "delegated",
+    "delegate",
+    "ownership_model",
+    "owner",
+];
+const COMPRESSION_FIELDS: &[&str] = &[
+    "eligible",
+    "compressed",
+    "data_hash",
+    "creator_hash",
+    "asset_hash",
+    "tree",
+    "seq",
+    "leaf_id",
+];
+const ROYALTY_FIELDS: &[&str] = &[
+    "royalty_model",
+    "target",
+    "percent",
+    "basis_points",
+    "primary_sale_happened",
+    "locked",
+];
+const PAGE_FIELDS: &[&str] = &["total", "limit", "items"];
+
+/// The addresses the checks query, given on the command line or found from the owner's assets.
+#[derive(Debug, Clone)]
+pub struct Fixtures {
+    pub owner: String,
+    pub asset: Option<String>,
+    pub compressed_asset: Option<String>,
+    pub uncompressed_asset: Option<String>,
+    pub collection: Option<String>,
+    pub creator: Option<String>,
+    pub authority: Option<String>,
+}
+
+impl Fixtures {
+    /// Fills in what wasn't given from the first page of the owner's assets.
+    pub async fn discover(mut self, client: &Client) -> anyhow::Result<Self> {
+        let page = client
+            .call(
+                "getAssetsByOwner",
+                json!({"ownerAddress": self.owner, "page": 1, "limit": 100}),
+            )
+            .await?
+            .result()
+            .context("failed to list the owner's assets")?;
+        let items = items(&page)?;
+        if self.asset.is_none() {
+            self.asset = items.first().and_then(|a| str_at(a, "/id"));
+        }
+        if self.compressed_asset.is_none() {
+            self.compressed_asset = items
+                .iter()
+                .find(|a| a.pointer("/compression/compressed") == Some(&json!(true)))
+                .and_then(|a| str_at(a, "/id"));
+        }
+        if self.uncompressed_asset.is_none() {
+            self.uncompressed_asset = items
+                .iter()
+                .find(|a| a.pointer("/compression/compressed") == Some(&json!(false)))
+                .and_then(|a| str_at(a, "/id"));
+        }
+        if self.collection.is_none() {
+            self.collection = items.iter().find_map(|a| {
+                a.get("grouping")?
+                    .as_array()?
+                    .iter()
+                    .find(|g| g.get("group_key") == Some(&json!("collection")))
+                    .and_then(|g| str_at(g, "/group_value"))
+            });
+        }
+        if self.creator.is_none() {
+            self.creator = items.iter().find_map(|a| str_at(a, "/creators/0/address"));
+        }
+        if self.authority.is_none() {
+            self.authority = items
+                .iter()
+                .find_map(|a| str_at(a, "/authorities/0/address"));
+        }
+        Ok(self)
+    }
+}
+
+fn str_at(value: &Value, pointer: &str) -> Option<String> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+fn items(page: &Value) -> anyhow::Result<&Vec<Value>> {
+    page.get("items")
+        .and_then(Value::as_array)
+        .context("page without items")
+}
+
+fn ids(page: &Value) -> anyhow::Result<Vec<String>> {
+    items(page)?
+        .iter()
+        .map(|a| str_at(a, "/id").context("asset without an id"))
+        .collect()
+}
+
+fn has_fields(value: &Value, fields: &[&str], what: &str) -> anyhow::Result<()> {
+    let missing: Vec<_> = fields
+        .iter()
+        .filter(|field| value.get(**field).is_none())
+        .collect();
+    ensure!(missing.is_empty(), "{} is missing {:?}", what, missing);
+    Ok(())
+}
+
+pub async fn asset_fields(client: &Client, asset: &str) -> anyhow::Result<()> {
+    let result = client
+        .call("getAsset", json!({ "id": asset }))
+        .await?
+        .result()?;
+    has_fields(&result, ASSET_FIELDS, "asset")?;
+    has_fields(&result["ownership"], OWNERSHIP_FIELDS, "ownership")?;
+    has_fields(&result["compression"], COMPRESSION_FIELDS, "compression")?;
+    has_fields(&result["royalty"], ROYALTY_FIELDS, "royalty")?;
+    ensure!(
+        result["id"] == json!(asset),
+        "returned asset {} instead of {}",
+        result["id"],
+        asset
+    );
+    Ok(())
+}
+
+pub async fn invalid_id(client: &Client) -> anyhow::Result<()> {
+    client
+        .call("getAsset", json!({"id": "not-a-pubkey"}))
+        .await?
+        .error_code()?;
+    Ok(())
+}
+
+pub async fn unknown_param(client: &Client, asset: &str) -> anyhow::Result<()> {
+    let code = client
+        .call("getAsset", json!({"id": asset, "notAParam": true}))
+        .await?
+        .error_code()?;
+    ensure!(
+        code == INVALID_PARAMS,
+        "expected invalid params ({}), got {}",
+        INVALID_PARAMS,
+        code
+    );
+    Ok(())
+}
+
+pub async fn unknown_method(client: &Client) -> anyhow::Result<()> {
+    let code = client
+        .call("getNotAMethod", json!({}))
+        .await?
+        .error_code()?;
+    ensure!(
+        code == METHOD_NOT_FOUND,
+        "expected method not found ({}), got {}",
+        METHOD_NOT_FOUND,
+        code
+    );
+    Ok(())
+}
+
+pub async fn page_shape(client: &Client, owner: &str) -> anyhow::Result<()> {
+    let page = client
+        .call(
+            "getAssetsByOwner",
+            json!({"ownerAddress": owner, "page": 1, "limit": 10}),
+        )
+        .await?
+        .result()?;
+    has_fields(&page, PAGE_FIELDS, "page")?;
+    let count = items(&page)?.len() as u64;
+    ensure!(page["limit"] == json!(10), "limit is {}", page["limit"]);
+    ensure!(page["page"] == json!(1), "page is {}", page["page"]);
+    ensure!(count <= 10, "{} items in a page of 10", count);
+    ensure!(
+        page["total"] == json!(count),
+        "total {} for {} items",
+        page["total"],
+        count
+    );
+    Ok(())
+}
+
+/// Two half pages return the assets of the full page, in the same order, and a page read twice
+/// returns the same assets.
+pub async fn page_stability(client: &Client, owner: &str) -> anyhow::Result<()> {
+    let page = |number: u64, limit: u64| {
+        client.call(
+            "getAssetsByOwner",
+            json!({
+                "ownerAddress": owner,
+                "page": number,
+                "limit": limit,
+                "sortBy": {"sortBy": "created", "sortDirection": "asc"},
+            }),
+        )
+    };
+    let full = ids(&page(1, 10).await?.result()?)?;
+    let mut halves = ids(&page(1, 5).await?.result()?)?;
+    halves.extend(ids(&page(2, 5).await?.result()?)?);
+    ensure!(
+        halves == full,
+        "pages 1 and 2 of 5 {:?} differ from page 1 of 10 {:?}",
+        halves,
+        full
+    );
+    let unique: HashSet<_> = full.iter().collect();
+    ensure!(unique.len() == full.len(), "a page repeats assets");
+    let again = ids(&page(1, 10).await?.result()?)?;
+    ensure!(again == full, "the same page returned different assets");
+    Ok(())
+}
+
+pub async fn limit_above_max(client: &Client, owner: &str) -> anyhow::Result<()> {
+    client
+        .call(
+            "getAssetsByOwner",
+            json!({"ownerAddress": owner, "page": 1, "limit": MAX_LIMIT + 1}),
+        )
+        .await?
+        .error_code()?;
+    Ok(())
+}
+
+pub async fn page_and_cursor(client: &Client, owner: &str, asset: &str) -> anyhow::Result<()> {
+    client
+        .call(
+            "getAssetsByOwner",
+            json!({"ownerAddress": owner, "page": 1, "before": asset}),
+        )
+        .await?
+        .error_code()?;
+    Ok(())
+}
+
+pub async fn sort_directions(client: &Client, owner: &str) -> anyhow::Result<()> {
+    let sorted = |direction: &'static str| {
+        client.call(
+            "getAssetsByOwner",
+            json!({
+                "ownerAddress": owner,
+                "page": 1,
+                "limit": MAX_LIMIT,
+                "sortBy": {"sortBy": "created", "sortDirection": direction},
+            }),
+        )
+    };
+    let asc = ids(&sorted("asc").await?.result()?)?;
+    let desc = ids(&sorted("desc").await?.result()?)?;
+    if asc.len() as u64 == MAX_LIMIT {
+        // Only a whole result set can be compared.
+        return Ok(());
+    }
+    let asc: HashSet<_> = asc.into_iter().collect();
+    let desc: HashSet<_> = desc.into_iter().collect();
+    ensure!(
+        asc == desc,
+        "ascending and descending return different assets"
+    );
+    Ok(())
+}
+
+/// Every asset of a listing matches the listing's filter.
+pub async fn listing_matches(
+    client: &Client,
+    method: &str,
+    params: Value,
+    matches: impl Fn(&Value) -> bool,
+) -> anyhow::Result<()> {
+    let mut params = params;
+    params["page"] = json!(1);
+    params["limit"] = json!(100);
+    let page = client.call(method, params).await?.result()?;
+    has_fields(&page, PAGE_FIELDS, "page")?;
+    let items = items(&page)?;
+    ensure!(!items.is_empty(), "no assets returned");
+    let mismatched: Vec<_> = items
+        .iter()
+        .filter(|a| !matches(a))
+        .filter_map(|a| str_at(a, "/id"))
+        .collect();
+    ensure!(
+        mismatched.is_empty(),
+        "assets not matching the filter: {:?}",
+        mismatched
+    );
+    Ok(())
+}
+
+pub fn owned_by(owner: &str) -> impl Fn(&Value) -> bool + '_ {
+    move |asset| asset.pointer("/ownership/owner") == Some(&json!(owner))
+}
+
+pub fn in_collection(collection: &str) -> impl Fn(&Value) -> bool + '_ {
+    move |asset| {
+        asset["grouping"].as_array().map_or(false, |groups| {
+            groups.iter().any(|g| {
+                g["group_key"] == json!("collection") && g["group_value"] == json!(collection)
+            })
+        })
+    }
+}
+
+pub fn created_by(creator: &str) -> impl Fn(&Value) -> bool + '_ {
+    move |asset| {
+        asset["creators"].as_array().map_or(false, |creators| {
+            creators.iter().any(|c| c["address"] == json!(creator))
+        })
+    }
+}
+
+pub fn with_authority(authority: &str) -> impl Fn(&Value) -> bool + '_ {
+    move |asset| {
+        asset["authorities"]
+            .as_array()
+            .map_or(false, |authorities| {
+                authorities.iter().any(|a| a["address"] == json!(authority))
+            })
+    }
+}
+
+pub async fn search_matches_owner(client: &Client, owner: &str) -> anyhow::Result<()> {
+    let params = json!({"ownerAddress": owner, "page": 1, "limit": MAX_LIMIT});
+    let by_owner = client
+        .call("getAssetsByOwner", params.clone())
+        .await?
+        .result()?;
+    let search = client.call("searchAssets", params).await?.result()?;
+    ensure!(
+        by_owner["total"] == search["total"],
+        "searchAssets found {} assets, getAssetsByOwner {}",
+        search["total"],
+        by_owner["total"]
+    );
+    Ok(())
+}
+
+pub async fn grouping_size(client: &Client, collection: &str) -> anyhow::Result<()> {
+    let result = client
+        .call(
+            "getGrouping",
+            json!({"groupKey": "collection", "groupValue": collection}),
+        )
+        .await?
+        .result()?;
+    has_fields(
+        &result,
+        &["group_key", "group_name", "group_size"],
+        "grouping",
+    )?;
+    ensure!(
+        result["group_size"].as_u64().map_or(false, |size| size > 0),
+        "group_size is {} for a collection with assets",
+        result["group_size"]
+    );
+    Ok(())
+}
+
+/// The proof hashes from the leaf up to the returned root.
+pub async fn proof_root(client: &Client, asset: &str) -> anyhow::Result<()> {
+    let proof = client
+        .call("getAssetProof", json!({ "id": asset }))
+        .await?
+        .result()?;
+    has_fields(
+        &proof,
+        &["root", "proof", "node_index", "leaf", "tree_id"],
+        "proof",
+    )?;
+    let decode = |value: &Value| -> anyhow::Result<[u8; 32]> {
+        let bytes = bs58::decode(value.as_str().context("node isn't a string")?).into_vec()?;
+        bytes
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("node isn't 32 bytes"))
+    };
+    let mut node = decode(&proof["leaf"])?;
+    let mut index = proof["node_index"].as_u64().context("invalid node_index")?;
+    for sibling in proof["proof"].as_array().context("invalid proof")? {
+        let sibling = decode(sibling)?;
+        node = if index % 2 == 0 {
+            keccak::hashv(&[node.as_ref(), sibling.as_ref()]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling.as_ref(), node.as_ref()]).to_bytes()
+        };
+        index /= 2;
+    }
+    ensure!(index == 1, "node_index doesn't match the proof length");
+    ensure!(
+        node == decode(&proof["root"])?,
+        "the proof doesn't hash to the root"
+    );
+    Ok(())
+}
+
+pub async fn proof_of_uncompressed(client: &Client, asset: &str) -> anyhow::Result<()> {
+    client
+        .call("getAssetProof", json!({ "id": asset }))
+        .await?
+        .error_code()?;
+    Ok(())
+}
+
+pub async fn signatures_shape(client: &Client, asset: &str) -> anyhow::Result<()> {
+    let page = client
+        .call(
+            "getSignaturesForAsset",
+            json!({"id": asset, "page": 1, "limit": 10}),
+        )
+        .await?
+        .result()?;
+    has_fields(&page, PAGE_FIELDS, "page")?;
+    let malformed = items(&page)?
+        .iter()
+        .filter(|item| {
+            item.as_array().map_or(true, |pair| {
+                pair.len() != 2 || pair.iter().any(|v| !v.is_string())
+            })
+        })
+        .count();
+    ensure!(
+        malformed == 0,
+        "{} items aren't [signature, type] pairs",
+        malformed
+    );
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use {
+    anyhow::Context,
+    serde_json::{json, Value},
+    std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    },
+};
+
+/// JSON-RPC error codes the checks expect.
+pub const INVALID_PARAMS: i64 = -32602;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// What a method call returned, a result or a JSON-RPC error.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Result(Value),
+    Error { code: i64, message: String },
+}
+
+impl Reply {
+    /// The result, or an error naming the JSON-RPC error returned instead.
+    pub fn result(self) -> anyhow::Result<Value> {
+        match self {
+            Reply::Result(value) => Ok(value),
+            Reply::Error { code, message } => {
+                anyhow::bail!("expected a result, got error {}: {}", code, message)
+            }
+        }
+    }
+
+    /// The error code, or an error when the call succeeded.
+    pub fn error_code(self) -> anyhow::Result<i64> {
+        match self {
+            Reply::Result(_) => anyhow::bail!("expected an error, got a result"),
+            Reply::Error { code, .. } => Ok(code),
+        }
+    }
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    url: String,
+    id: AtomicU64,
+}
+
+impl Client {
+    pub fn new(url: String, timeout: Duration) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(Client {
+            http,
+            url,
+            id: AtomicU64::new(0),
+        })
+    }
+
+    /// Calls `method` with named `params`. Transport failures and responses that aren't
+    /// JSON-RPC are errors, JSON-RPC errors are replies.
+    pub async fn call(&self, method: &str, params: Value) -> anyhow::Result<Reply> {
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await
+            .with_context(|| format!("failed to call {}", method))?
+            .json()
+            .await
+            .with_context(|| format!("{} returned a response that isn't JSON", method))?;
+        if response.get("id") != Some(&json!(id)) {
+            anyhow::bail!("{} returned a response with the wrong id", method);
+        }
+        if let Some(error) = response.get("error") {
+            let code = error
+                .get("code")
+                .and_then(Value::as_i64)
+                .context("error without a code")?;
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            return Ok(Reply::Error { code, message });
+        }
+        response
+            .get("result")
+            .cloned()
+            .map(Reply::Result)
+            .with_context(|| format!("{} returned neither a result nor an error", method))
+    }
+}
@@ -0,0 +1,361 @@
+mod checks;
+mod client;
+
+use {
+    checks::Fixtures,
+    clap::Parser,
+    client::Client,
+    log::{error, info, warn},
+    serde::Serialize,
+    serde_json::json,
+    std::{
+        env,
+        future::Future,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// DAS API endpoint.
+    #[arg(long, short)]
+    url: String,
+
+    /// Owner whose assets the checks query. The assets below default to ones of this owner.
+    #[arg(long)]
+    owner: String,
+
+    /// Asset for the getAsset checks.
+    #[arg(long)]
+    asset: Option<String>,
+
+    /// Compressed asset for the proof and signature checks.
+    #[arg(long)]
+    compressed_asset: Option<String>,
+
+    /// Uncompressed asset, which has no proof.
+    #[arg(long)]
+    uncompressed_asset: Option<String>,
+
+    /// Collection for the getAssetsByGroup and getGrouping checks.
+    #[arg(long)]
+    collection: Option<String>,
+
+    /// Creator for the getAssetsByCreator checks.
+    #[arg(long)]
+    creator: Option<String>,
+
+    /// Authority for the getAssetsByAuthority checks.
+    #[arg(long)]
+    authority: Option<String>,
+
+    /// Seconds before a call times out.
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Write the report as JSON to this file.
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Debug, Serialize)]
+struct CheckResult {
+    name: &'static str,
+    method: &'static str,
+    status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    elapsed_ms: u64,
+}
+
+#[derive(Default)]
+struct Report {
+    results: Vec<CheckResult>,
+}
+
+impl Report {
+    async fn check<F>(&mut self, name: &'static str, method: &'static str, check: F)
+    where
+        F: Future<Output = anyhow::Result<()>>,
+    {
+        let start = Instant::now();
+        let res = check.await;
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        let (status, detail) = match res {
+            Ok(()) => {
+                info!("PASS {} ({})", name, method);
+                (Status::Pass, None)
+            }
+            Err(e) => {
+                error!("FAIL {} ({}): {:#}", name, method, e);
+                (Status::Fail, Some(format!("{:#}", e)))
+            }
+        };
+        self.results.push(CheckResult {
+            name,
+            method,
+            status,
+            detail,
+            elapsed_ms,
+        });
+    }
+
+    /// Runs the check when its fixture is known, otherwise records it as skipped.
+    async fn check_with<'a, F, Fut>(
+        &mut self,
+        name: &'static str,
+        method: &'static str,
+        fixture: &'a Option<String>,
+        what: &str,
+        check: F,
+    ) where
+        F: FnOnce(&'a str) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>>,
+    {
+        match fixture {
+            Some(fixture) => self.check(name, method, check(fixture)).await,
+            None => {
+                let detail = format!("no {} given or found among the owner's assets", what);
+                warn!("SKIP {} ({}): {}", name, method, detail);
+                self.results.push(CheckResult {
+                    name,
+                    method,
+                    status: Status::Skip,
+                    detail: Some(detail),
+                    elapsed_ms: 0,
+                });
+            }
+        }
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let client = Client::new(args.url.clone(), Duration::from_secs(args.timeout))?;
+    let fixtures = Fixtures {
+        owner: args.owner,
+        asset: args.asset,
+        compressed_asset: args.compressed_asset,
+        uncompressed_asset: args.uncompressed_asset,
+        collection: args.collection,
+        creator: args.creator,
+        authority: args.authority,
+    }
+    .discover(&client)
+    .await?;
+    info!("Checking {} with {:?}", args.url, fixtures);
+
+    let c = &client;
+    let owner = fixtures.owner.as_str();
+    let mut report = Report::default();
+
+    // Protocol and parameter validation.
+    report
+        .check("unknown-method", "rpc", checks::unknown_method(c))
+        .await;
+    report
+        .check("invalid-id", "getAsset", checks::invalid_id(c))
+        .await;
+    report
+        .check_with("unknown-param", "getAsset", &fixtures.asset, "asset", |a| {
+            checks::unknown_param(c, a)
+        })
+        .await;
+    report
+        .check(
+            "limit-above-max",
+            "getAssetsByOwner",
+            checks::limit_above_max(c, owner),
+        )
+        .await;
+    report
+        .check_with(
+            "page-and-cursor",
+            "getAssetsByOwner",
+            &fixtures.asset,
+            "asset",
+            |a| checks::page_and_cursor(c, owner, a),
+        )
+        .await;
+
+    // Field presence.
+    report
+        .check_with("asset-fields", "getAsset", &fixtures.asset, "asset", |a| {
+            checks::asset_fields(c, a)
+        })
+        .await;
+    report
+        .check(
+            "page-shape",
+            "getAssetsByOwner",
+            checks::page_shape(c, owner),
+        )
+        .await;
+    report
+        .check_with(
+            "signatures-shape",
+            "getSignaturesForAsset",
+            &fixtures.compressed_asset,
+            "compressed asset",
+            |a| checks::signatures_shape(c, a),
+        )
+        .await;
+
+    // Pagination.
+    report
+        .check(
+            "page-stability",
+            "getAssetsByOwner",
+            checks::page_stability(c, owner),
+        )
+        .await;
+    report
+        .check(
+            "sort-directions",
+            "getAssetsByOwner",
+            checks::sort_directions(c, owner),
+        )
+        .await;
+
+    // Semantics.
+    report
+        .check(
+            "owner-filter",
+            "getAssetsByOwner",
+            checks::listing_matches(
+                c,
+                "getAssetsByOwner",
+                json!({ "ownerAddress": owner }),
+                checks::owned_by(owner),
+            ),
+        )
+        .await;
+    report
+        .check(
+            "search-matches-owner",
+            "searchAssets",
+            checks::search_matches_owner(c, owner),
+        )
+        .await;
+    report
+        .check_with(
+            "group-filter",
+            "getAssetsByGroup",
+            &fixtures.collection,
+            "collection",
+            |collection| {
+                checks::listing_matches(
+                    c,
+                    "getAssetsByGroup",
+                    json!({"groupKey": "collection", "groupValue": collection}),
+                    checks::in_collection(collection),
+                )
+            },
+        )
+        .await;
+    report
+        .check_with(
+            "grouping-size",
+            "getGrouping",
+            &fixtures.collection,
+            "collection",
+            |collection| checks::grouping_size(c, collection),
+        )
+        .await;
+    report
+        .check_with(
+            "creator-filter",
+            "getAssetsByCreator",
+            &fixtures.creator,
+            "creator",
+            |creator| {
+                checks::listing_matches(
+                    c,
+                    "getAssetsByCreator",
+                    json!({ "creatorAddress": creator }),
+                    checks::created_by(creator),
+                )
+            },
+        )
+        .await;
+    report
+        .check_with(
+            "authority-filter",
+            "getAssetsByAuthority",
+            &fixtures.authority,
+            "authority",
+            |authority| {
+                checks::listing_matches(
+                    c,
+                    "getAssetsByAuthority",
+                    json!({ "authorityAddress": authority }),
+                    checks::with_authority(authority),
+                )
+            },
+        )
+        .await;
+    report
+        .check_with(
+            "proof-root",
+            "getAssetProof",
+            &fixtures.compressed_asset,
+            "compressed asset",
+            |a| checks::proof_root(c, a),
+        )
+        .await;
+    report
+        .check_with(
+            "proof-of-uncompressed",
+            "getAssetProof",
+            &fixtures.uncompressed_asset,
+            "uncompressed asset",
+            |a| checks::proof_of_uncompressed(c, a),
+        )
+        .await;
+
+    let (passed, failed, skipped) = (
+        report.count(Status::Pass),
+        report.count(Status::Fail),
+        report.count(Status::Skip),
+    );
+    info!(
+        "{} checks passed, {} failed, {} skipped",
+        passed, failed, skipped
+    );
+    if let Some(path) = args.report {
+        let json = serde_json::to_vec_pretty(&json!({
+            "endpoint": args.url,
+            "passed": passed,
+            "failed": failed,
+            "skipped": skipped,
+            "results": report.results,
+        }))?;
+        tokio::fs::write(&path, json).await?;
+        info!("Wrote the report to {}", path.display());
+    }
+    if failed > 0 {
+        anyhow::bail!("{} checks failed", failed);
+    }
+    Ok(())
+}
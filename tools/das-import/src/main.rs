@@ -0,0 +1,240 @@
+mod snapshot;
+mod verify;
+
+use {
+    anyhow::Context,
+    clap::{Parser, Subcommand},
+    digital_asset_types::dao::{
+        asset, asset_authority, asset_creators, asset_data, asset_grouping, imported_assets,
+    },
+    log::{info, warn},
+    sea_orm::{
+        entity::*, query::*, sea_query::OnConflict, ConnectionTrait, DatabaseConnection, DbBackend,
+        JsonValue, SqlxPostgresConnector, Statement, TransactionTrait,
+    },
+    snapshot::{parse_snapshot, AssetRows},
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+    std::{collections::BTreeSet, env, path::PathBuf},
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Postgres connection string.
+    #[arg(long, short)]
+    pg_url: String,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Seeds the asset tables from snapshot files, skipping the assets already indexed.
+    Import {
+        /// The provider the snapshot comes from, kept with each imported asset.
+        #[arg(long, short)]
+        source: String,
+
+        /// Assets written per transaction.
+        #[arg(long, short, default_value_t = 500)]
+        batch_size: usize,
+
+        /// Don't queue the trees of compressed assets for a backfill.
+        #[arg(long)]
+        no_backfill: bool,
+
+        /// `getAssetsByGroup` responses, one page or an array of pages per file.
+        files: Vec<PathBuf>,
+    },
+    /// Checks the unverified imported assets on chain.
+    Verify {
+        /// Solana RPC URL.
+        #[arg(long, short)]
+        rpc_url: String,
+
+        /// Assets checked per query.
+        #[arg(long, short, default_value_t = 100)]
+        batch_size: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let options: PgConnectOptions = args.pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with(options)
+        .await?;
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+
+    match args.action {
+        Action::Import {
+            source,
+            batch_size,
+            no_backfill,
+            files,
+        } => import(&conn, &source, batch_size.max(1), !no_backfill, files).await,
+        Action::Verify {
+            rpc_url,
+            batch_size,
+        } => {
+            let counts = verify::verify_imported(&conn, rpc_url, batch_size.clamp(1, 100)).await?;
+            println!(
+                "{} verified, {} mismatched, {} waiting for their tree's backfill",
+                counts.verified, counts.mismatched, counts.pending
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn import(
+    conn: &DatabaseConnection,
+    source: &str,
+    batch_size: usize,
+    backfill: bool,
+    files: Vec<PathBuf>,
+) -> anyhow::Result<()> {
+    let (mut imported, mut skipped) = (0, 0);
+    let mut trees = BTreeSet::new();
+    for file in files {
+        let json: JsonValue = serde_json::from_slice(&tokio::fs::read(&file).await?)
+            .with_context(|| format!("invalid JSON in {}", file.display()))?;
+        let assets =
+            parse_snapshot(json).with_context(|| format!("invalid snapshot {}", file.display()))?;
+        info!("Importing {} assets from {}", assets.len(), file.display());
+        let mut rows = Vec::with_capacity(batch_size);
+        for asset in assets {
+            let id = asset.id.clone();
+            match asset.into_rows() {
+                Ok(asset_rows) => rows.push(asset_rows),
+                Err(e) => warn!("Skipping asset {}: {:#}", id, e),
+            }
+            if rows.len() == batch_size {
+                let batch = std::mem::take(&mut rows);
+                import_batch(conn, source, batch, &mut trees, &mut imported, &mut skipped).await?;
+            }
+        }
+        import_batch(conn, source, rows, &mut trees, &mut imported, &mut skipped).await?;
+        info!("Imported {}, skipped {} already indexed", imported, skipped);
+    }
+    if backfill {
+        for tree in trees.iter() {
+            queue_backfill(conn, tree).await?;
+        }
+        info!("Queued {} trees for a backfill", trees.len());
+    }
+    println!("{} assets imported, {} already indexed", imported, skipped);
+    Ok(())
+}
+
+/// Writes a batch in one transaction. An asset already indexed is left untouched, the snapshot
+/// is only there to fill in what the index lacks.
+async fn import_batch(
+    conn: &DatabaseConnection,
+    source: &str,
+    batch: Vec<AssetRows>,
+    trees: &mut BTreeSet<Vec<u8>>,
+    imported: &mut usize,
+    skipped: &mut usize,
+) -> anyhow::Result<()> {
+    let txn = conn.begin().await?;
+    for rows in batch {
+        let query = asset::Entity::insert(rows.asset)
+            .on_conflict(
+                OnConflict::columns([asset::Column::Id])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+        if txn.execute(query).await?.rows_affected() == 0 {
+            *skipped += 1;
+            continue;
+        }
+        let query = asset_data::Entity::insert(rows.data)
+            .on_conflict(
+                OnConflict::columns([asset_data::Column::Id])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .build(DbBackend::Postgres);
+        txn.execute(query).await?;
+        for authority in rows.authorities {
+            let query = asset_authority::Entity::insert(authority)
+                .on_conflict(
+                    OnConflict::columns([asset_authority::Column::AssetId])
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .build(DbBackend::Postgres);
+            txn.execute(query).await?;
+        }
+        if !rows.creators.is_empty() {
+            let query = asset_creators::Entity::insert_many(rows.creators)
+                .on_conflict(
+                    OnConflict::columns([
+                        asset_creators::Column::AssetId,
+                        asset_creators::Column::Position,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .build(DbBackend::Postgres);
+            txn.execute(query).await?;
+        }
+        for grouping in rows.groupings {
+            let query = asset_grouping::Entity::insert(grouping)
+                .on_conflict(
+                    OnConflict::columns([
+                        asset_grouping::Column::AssetId,
+                        asset_grouping::Column::GroupKey,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .build(DbBackend::Postgres);
+            txn.execute(query).await?;
+        }
+        let query = imported_assets::Entity::insert(imported_assets::ActiveModel {
+            asset_id: Set(rows.id),
+            source: Set(source.to_string()),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::columns([imported_assets::Column::AssetId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .build(DbBackend::Postgres);
+        txn.execute(query).await?;
+        if let Some(tree) = rows.tree {
+            trees.insert(tree);
+        }
+        *imported += 1;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Has the backfiller rebuild the tree from chain, which is what confirms its imported assets.
+async fn queue_backfill(conn: &DatabaseConnection, tree: &[u8]) -> anyhow::Result<()> {
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+INSERT INTO backfill_items (tree, seq, slot, force_chk, backfilled, failed, locked)
+SELECT $1, 0, 0, true, false, false, false
+WHERE NOT EXISTS (SELECT 1 FROM backfill_items WHERE tree = $1 AND backfilled = false)
+",
+        vec![tree.to_vec().into()],
+    ))
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use {
+    anyhow::Context,
+    digital_asset_types::{
+        dao::{
+            asset, asset_authority, asset_creators, asset_data, asset_grouping,
+            sea_orm_active_enums::{
+                ChainMutability, Mutability, OwnerType, RoyaltyTargetType, SpecificationAssetClass,
+                SpecificationVersions,
+            },
+        },
+        rpc::{Interface, OwnershipModel, RoyaltyModel},
+    },
+    sea_orm::{entity::*, JsonValue},
+    serde::Deserialize,
+    serde_json::json,
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+/// The parts of a DAS asset the import keeps. Providers add their own fields and disagree on
+/// which ones are optional, so everything but the id and ownership may be missing.
+#[derive(Debug, Deserialize)]
+pub struct SnapshotAsset {
+    pub id: String,
+    #[serde(default)]
+    pub interface: Option<String>,
+    #[serde(default)]
+    pub content: Option<SnapshotContent>,
+    #[serde(default)]
+    pub authorities: Vec<SnapshotAuthority>,
+    #[serde(default)]
+    pub compression: Option<SnapshotCompression>,
+    #[serde(default)]
+    pub grouping: Vec<SnapshotGroup>,
+    #[serde(default)]
+    pub royalty: Option<SnapshotRoyalty>,
+    #[serde(default)]
+    pub creators: Vec<SnapshotCreator>,
+    pub ownership: SnapshotOwnership,
+    #[serde(default)]
+    pub supply: Option<JsonValue>,
+    #[serde(default)]
+    pub mutable: Option<bool>,
+    #[serde(default)]
+    pub burnt: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SnapshotContent {
+    #[serde(default)]
+    pub json_uri: String,
+    #[serde(default)]
+    pub files: Vec<JsonValue>,
+    #[serde(default)]
+    pub metadata: serde_json::Map<String, JsonValue>,
+    #[serde(default)]
+    pub links: serde_json::Map<String, JsonValue>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotAuthority {
+    pub address: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotCompression {
+    pub compressed: bool,
+    #[serde(default)]
+    pub data_hash: String,
+    #[serde(default)]
+    pub creator_hash: String,
+    #[serde(default)]
+    pub asset_hash: String,
+    #[serde(default)]
+    pub tree: String,
+    #[serde(default)]
+    pub leaf_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotGroup {
+    pub group_key: String,
+    pub group_value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotRoyalty {
+    pub royalty_model: String,
+    pub target: Option<String>,
+    #[serde(default)]
+    pub basis_points: u32,
+    #[serde(default)]
+    pub primary_sale_happened: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotCreator {
+    pub address: String,
+    pub share: i32,
+    pub verified: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnapshotOwnership {
+    #[serde(default)]
+    pub frozen: bool,
+    pub delegate: Option<String>,
+    pub ownership_model: String,
+    pub owner: String,
+}
+
+/// The assets of a snapshot file: a `getAssetsByGroup` response, its `result`, or an array of
+/// either for a file holding every page.
+pub fn parse_snapshot(json: JsonValue) -> anyhow::Result<Vec<SnapshotAsset>> {
+    match json {
+        JsonValue::Array(pages) => {
+            let mut assets = Vec::new();
+            for page in pages {
+                assets.extend(parse_snapshot(page)?);
+            }
+            Ok(assets)
+        }
+        JsonValue::Object(mut page) => {
+            if let Some(result) = page.remove("result") {
+                return parse_snapshot(result);
+            }
+            let items = page.remove("items").context("page has no items")?;
+            Ok(serde_json::from_value(items)?)
+        }
+        _ => anyhow::bail!("expected a page or an array of pages"),
+    }
+}
+
+/// The rows indexing would have written for an asset, as imported at slot 0 so that anything
+/// the ingester indexes for it later takes precedence.
+pub struct AssetRows {
+    pub id: Vec<u8>,
+    /// The tree of a compressed asset.
+    pub tree: Option<Vec<u8>>,
+    pub asset: asset::ActiveModel,
+    pub data: asset_data::ActiveModel,
+    pub authorities: Vec<asset_authority::ActiveModel>,
+    pub creators: Vec<asset_creators::ActiveModel>,
+    pub groupings: Vec<asset_grouping::ActiveModel>,
+}
+
+fn pubkey(address: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(Pubkey::from_str(address)
+        .with_context(|| format!("invalid address {}", address))?
+        .to_bytes()
+        .to_vec())
+}
+
+fn optional_pubkey(address: Option<&str>) -> anyhow::Result<Option<Vec<u8>>> {
+    address.filter(|a| !a.is_empty()).map(pubkey).transpose()
+}
+
+fn hash(hash: &str) -> Option<String> {
+    let hash = hash.trim();
+    (!hash.is_empty()).then(|| hash.to_string())
+}
+
+impl SnapshotAsset {
+    pub fn into_rows(self) -> anyhow::Result<AssetRows> {
+        let id = pubkey(&self.id)?;
+        let interface = self
+            .interface
+            .and_then(|i| serde_json::from_value(JsonValue::String(i)).ok())
+            .unwrap_or(Interface::Custom);
+        let (specification_version, specification_asset_class): (
+            SpecificationVersions,
+            SpecificationAssetClass,
+        ) = interface.into();
+        let content = self.content.unwrap_or_default();
+        let compression = self.compression.filter(|c| c.compressed);
+        let tree = compression.as_ref().map(|c| pubkey(&c.tree)).transpose()?;
+        let (royalty_target_type, royalty_target, royalty_amount, primary_sale_happened) =
+            match &self.royalty {
+                Some(royalty) => (
+                    RoyaltyTargetType::from(RoyaltyModel::from(royalty.royalty_model.clone())),
+                    optional_pubkey(royalty.target.as_deref())?,
+                    royalty.basis_points as i32,
+                    royalty.primary_sale_happened,
+                ),
+                None => (RoyaltyTargetType::Creators, None, 0, false),
+            };
+        let edition_nonce = self
+            .supply
+            .as_ref()
+            .and_then(|s| s.get("edition_nonce"))
+            .and_then(JsonValue::as_u64);
+
+        let text = |key: &str| {
+            content
+                .metadata
+                .get(key)
+                .and_then(JsonValue::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let chain_data = json!({
+            "name": text("name"),
+            "symbol": text("symbol"),
+            "edition_nonce": edition_nonce,
+            "primary_sale_happened": primary_sale_happened,
+        });
+        // The off-chain JSON isn't part of the snapshot, this rebuilds the fields the API reads
+        // from it.
+        let mut metadata = content.metadata.clone();
+        if let Some(image) = content.links.get("image") {
+            metadata.insert("image".to_string(), image.clone());
+        }
+        if let Some(external_url) = content.links.get("external_url") {
+            metadata.insert("external_url".to_string(), external_url.clone());
+        }
+        if !content.files.is_empty() {
+            metadata.insert("properties".to_string(), json!({ "files": content.files }));
+        }
+
+        let asset = asset::ActiveModel {
+            id: Set(id.clone()),
+            alt_id: Set(None),
+            specification_version: Set(Some(specification_version)),
+            specification_asset_class: Set(Some(specification_asset_class)),
+            owner: Set(optional_pubkey(Some(&self.ownership.owner))?),
+            owner_type: Set(OwnerType::from(OwnershipModel::from(
+                self.ownership.ownership_model.clone(),
+            ))),
+            delegate: Set(optional_pubkey(self.ownership.delegate.as_deref())?),
+            frozen: Set(self.ownership.frozen),
+            supply: Set(1),
+            supply_mint: Set(compression.is_none().then(|| id.clone())),
+            compressed: Set(compression.is_some()),
+            compressible: Set(false),
+            // The provider's seqs are left out, so the first update the ingester indexes for the
+            // asset wins whatever its seq.
+            seq: Set(None),
+            tree_id: Set(tree.clone()),
+            leaf: Set(compression
+                .as_ref()
+                .and_then(|c| bs58::decode(&c.asset_hash).into_vec().ok())),
+            nonce: Set(compression.as_ref().map(|c| c.leaf_id)),
+            royalty_target_type: Set(royalty_target_type),
+            royalty_target: Set(royalty_target),
+            royalty_amount: Set(royalty_amount),
+            asset_data: Set(Some(id.clone())),
+            burnt: Set(self.burnt),
+            slot_updated: Set(Some(0)),
+            data_hash: Set(compression.as_ref().and_then(|c| hash(&c.data_hash))),
+            creator_hash: Set(compression.as_ref().and_then(|c| hash(&c.creator_hash))),
+            owner_delegate_seq: Set(None),
+            was_decompressed: Set(false),
+            leaf_seq: Set(None),
+            ..Default::default()
+        };
+        let data = asset_data::ActiveModel {
+            id: Set(id.clone()),
+            chain_data_mutability: Set(match self.mutable {
+                Some(true) => ChainMutability::Mutable,
+                Some(false) => ChainMutability::Immutable,
+                None => ChainMutability::Unknown,
+            }),
+            chain_data: Set(chain_data),
+            metadata_url: Set(content.json_uri.trim().to_string()),
+            metadata_mutability: Set(Mutability::Mutable),
+            metadata: Set(JsonValue::Object(metadata)),
+            slot_updated: Set(0),
+            reindex: Set(Some(false)),
+            raw_name: Set(None),
+            raw_symbol: Set(None),
+            files: Set(None),
+            metadata_blob: Set(None),
+        };
+        let authorities = self
+            .authorities
+            .iter()
+            .take(1)
+            .map(|a| {
+                Ok(asset_authority::ActiveModel {
+                    asset_id: Set(id.clone()),
+                    authority: Set(pubkey(&a.address)?),
+                    seq: Set(0),
+                    slot_updated: Set(0),
+                    ..Default::default()
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let creators = self
+            .creators
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                Ok(asset_creators::ActiveModel {
+                    asset_id: Set(id.clone()),
+                    creator: Set(pubkey(&c.address)?),
+                    share: Set(c.share),
+                    verified: Set(c.verified),
+                    seq: Set(None),
+                    slot_updated: Set(Some(0)),
+                    position: Set(i as i16),
+                    ..Default::default()
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let groupings = self
+            .grouping
+            .into_iter()
+            .map(|g| asset_grouping::ActiveModel {
+                asset_id: Set(id.clone()),
+                group_key: Set(g.group_key),
+                group_value: Set(g.group_value),
+                // Whether the collection is verified isn't part of the DAS output.
+                verified: Set(None),
+                seq: Set(None),
+                slot_updated: Set(Some(0)),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(AssetRows {
+            id,
+            tree,
+            asset,
+            data,
+            authorities,
+            creators,
+            groupings,
+        })
+    }
+}
@@ -0,0 +1,191 @@
+use {
+    log::{info, warn},
+    mpl_token_metadata::{pda::find_metadata_account, state::Metadata},
+    sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, Statement},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{borsh::try_from_slice_unchecked, pubkey::Pubkey},
+};
+
+#[derive(Debug, Default)]
+pub struct VerifyCounts {
+    pub verified: usize,
+    pub mismatched: usize,
+    pub pending: usize,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ImportedAsset {
+    asset_id: Vec<u8>,
+    compressed: bool,
+    tree_id: Option<Vec<u8>>,
+    nonce: Option<i64>,
+    leaf: Option<Vec<u8>>,
+    metadata_url: Option<String>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct LeafHash {
+    hash: Vec<u8>,
+}
+
+enum Check {
+    Verified,
+    Mismatch(String),
+    /// The chain's state isn't indexed yet, e.g. the tree hasn't been backfilled.
+    Pending,
+}
+
+async fn unverified_batch(
+    conn: &DatabaseConnection,
+    after: &[u8],
+    batch_size: u64,
+) -> anyhow::Result<Vec<ImportedAsset>> {
+    Ok(
+        ImportedAsset::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+SELECT i.asset_id, a.compressed, a.tree_id, a.nonce, a.leaf, d.metadata_url
+FROM imported_assets i
+JOIN asset a ON a.id = i.asset_id
+LEFT JOIN asset_data d ON d.id = a.asset_data
+WHERE i.verified_at IS NULL AND i.asset_id > $1
+ORDER BY i.asset_id
+LIMIT $2
+",
+            vec![after.to_vec().into(), (batch_size as i64).into()],
+        ))
+        .all(conn)
+        .await?,
+    )
+}
+
+/// A compressed asset is confirmed once the backfiller indexed its tree from chain and the
+/// leaf at its index hashes to the imported leaf.
+async fn check_compressed(
+    conn: &DatabaseConnection,
+    asset: &ImportedAsset,
+) -> anyhow::Result<Check> {
+    let (tree, nonce) = match (&asset.tree_id, asset.nonce) {
+        (Some(tree), Some(nonce)) => (tree, nonce),
+        _ => return Ok(Check::Mismatch("no tree or leaf index".to_string())),
+    };
+    let leaf = LeafHash::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT hash FROM cl_items WHERE tree = $1 AND leaf_idx = $2 ORDER BY seq DESC LIMIT 1",
+        vec![tree.clone().into(), nonce.into()],
+    ))
+    .one(conn)
+    .await?;
+    Ok(match leaf {
+        None => Check::Pending,
+        Some(leaf) if Some(&leaf.hash) == asset.leaf.as_ref() => Check::Verified,
+        Some(_) => Check::Mismatch("leaf hash differs from the tree's".to_string()),
+    })
+}
+
+/// An uncompressed asset is confirmed when its metadata account exists and points to the
+/// imported metadata uri.
+async fn check_uncompressed(
+    client: &RpcClient,
+    assets: &[&ImportedAsset],
+) -> anyhow::Result<Vec<Check>> {
+    let mints: Vec<Pubkey> = assets
+        .iter()
+        .map(|a| Pubkey::try_from(a.asset_id.as_slice()).unwrap_or_default())
+        .collect();
+    let metadata_accounts: Vec<Pubkey> = mints
+        .iter()
+        .map(|mint| find_metadata_account(mint).0)
+        .collect();
+    let accounts = client.get_multiple_accounts(&metadata_accounts).await?;
+    Ok(assets
+        .iter()
+        .zip(mints.iter())
+        .zip(accounts.into_iter())
+        .map(|((asset, mint), account)| {
+            let account = match account {
+                Some(account) => account,
+                None => return Check::Mismatch("no metadata account".to_string()),
+            };
+            let metadata: Metadata = match try_from_slice_unchecked(&account.data) {
+                Ok(metadata) => metadata,
+                Err(e) => return Check::Mismatch(format!("invalid metadata account: {}", e)),
+            };
+            let uri = metadata.data.uri.trim().trim_matches('\0');
+            if metadata.mint != *mint {
+                Check::Mismatch("metadata account of another mint".to_string())
+            } else if asset.metadata_url.as_deref() != Some(uri) {
+                Check::Mismatch(format!("metadata uri is {} on chain", uri))
+            } else {
+                Check::Verified
+            }
+        })
+        .collect())
+}
+
+async fn record(conn: &DatabaseConnection, asset_id: &[u8], check: &Check) -> anyhow::Result<()> {
+    let statement = match check {
+        Check::Verified => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "UPDATE imported_assets SET verified_at = now(), verify_error = NULL WHERE asset_id = $1",
+            vec![asset_id.to_vec().into()],
+        ),
+        Check::Mismatch(error) => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "UPDATE imported_assets SET verify_error = $2 WHERE asset_id = $1",
+            vec![asset_id.to_vec().into(), error.clone().into()],
+        ),
+        Check::Pending => return Ok(()),
+    };
+    conn.execute(statement).await?;
+    Ok(())
+}
+
+/// Checks every imported asset that isn't verified yet. Mismatches keep the asset unverified
+/// with the reason in `verify_error`, so a later run checks them again.
+pub async fn verify_imported(
+    conn: &DatabaseConnection,
+    rpc_url: String,
+    batch_size: u64,
+) -> anyhow::Result<VerifyCounts> {
+    let client = RpcClient::new(rpc_url);
+    let mut counts = VerifyCounts::default();
+    let mut after = Vec::new();
+    loop {
+        let batch = unverified_batch(conn, &after, batch_size).await?;
+        let (compressed, uncompressed): (Vec<_>, Vec<_>) =
+            batch.iter().partition(|asset| asset.compressed);
+        let mut checks = Vec::with_capacity(batch.len());
+        for asset in compressed {
+            checks.push((asset, check_compressed(conn, asset).await?));
+        }
+        if !uncompressed.is_empty() {
+            let results = check_uncompressed(&client, &uncompressed).await?;
+            checks.extend(uncompressed.into_iter().zip(results));
+        }
+        for (asset, check) in checks.iter() {
+            match check {
+                Check::Verified => counts.verified += 1,
+                Check::Mismatch(error) => {
+                    warn!(
+                        "Imported asset {} doesn't match the chain: {}",
+                        bs58::encode(&asset.asset_id).into_string(),
+                        error
+                    );
+                    counts.mismatched += 1;
+                }
+                Check::Pending => counts.pending += 1,
+            }
+            record(conn, &asset.asset_id, check).await?;
+        }
+        info!(
+            "{} verified, {} mismatched, {} pending",
+            counts.verified, counts.mismatched, counts.pending
+        );
+        match batch.last() {
+            Some(last) if batch.len() as u64 == batch_size => after = last.asset_id.clone(),
+            _ => break,
+        }
+    }
+    Ok(counts)
+}
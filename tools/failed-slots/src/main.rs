@@ -0,0 +1,126 @@
+use {
+    anyhow::Context,
+    chrono::Utc,
+    clap::{Parser, Subcommand},
+    digital_asset_types::dao::failed_slots,
+    sea_orm::{entity::*, query::*, sea_query::Expr, DatabaseConnection, SqlxPostgresConnector},
+    solana_sdk::pubkey::Pubkey,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Postgres connection string.
+    #[arg(long, short)]
+    pg_url: String,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Lists quarantined slots, the next ones to be retried first.
+    List {
+        /// Only the slots of this tree.
+        #[arg(long, short)]
+        tree: Option<Pubkey>,
+        /// Only the slots the backfiller gave up on.
+        #[arg(long)]
+        given_up: bool,
+        #[arg(long, short, default_value_t = 100)]
+        limit: u64,
+    },
+    /// Makes quarantined slots due for a retry now, with their attempts reset, e.g. after an RPC
+    /// outage made the backfiller give up on them.
+    Requeue {
+        /// Only the slots of this tree.
+        #[arg(long, short)]
+        tree: Option<Pubkey>,
+        /// Only this slot.
+        #[arg(long, short)]
+        slot: Option<i64>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let options: PgConnectOptions = args.pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+
+    match args.action {
+        Action::List {
+            tree,
+            given_up,
+            limit,
+        } => list(&conn, tree, given_up, limit).await,
+        Action::Requeue { tree, slot } => requeue(&conn, tree, slot).await,
+    }
+}
+
+async fn list(
+    conn: &DatabaseConnection,
+    tree: Option<Pubkey>,
+    given_up: bool,
+    limit: u64,
+) -> anyhow::Result<()> {
+    let mut query = failed_slots::Entity::find();
+    if let Some(tree) = tree {
+        query = query.filter(failed_slots::Column::Tree.eq(tree.to_bytes().to_vec()));
+    }
+    if given_up {
+        query = query.filter(failed_slots::Column::GivenUp.eq(true));
+    }
+    let slots = query
+        .order_by_asc(failed_slots::Column::GivenUp)
+        .order_by_asc(failed_slots::Column::NextRetryAt)
+        .limit(limit)
+        .all(conn)
+        .await?;
+    for slot in &slots {
+        let status = if slot.given_up {
+            "given up".to_string()
+        } else {
+            format!("retry at {}", slot.next_retry_at)
+        };
+        println!(
+            "{} {} attempts={} {}: {}",
+            bs58::encode(&slot.tree).into_string(),
+            slot.slot,
+            slot.attempts,
+            status,
+            slot.error
+        );
+    }
+    println!("{} slots", slots.len());
+    Ok(())
+}
+
+async fn requeue(
+    conn: &DatabaseConnection,
+    tree: Option<Pubkey>,
+    slot: Option<i64>,
+) -> anyhow::Result<()> {
+    let mut update = failed_slots::Entity::update_many()
+        .col_expr(failed_slots::Column::Attempts, Expr::value(0))
+        .col_expr(failed_slots::Column::GivenUp, Expr::value(false))
+        .col_expr(
+            failed_slots::Column::NextRetryAt,
+            Expr::value(Utc::now().naive_utc()),
+        );
+    if let Some(tree) = tree {
+        update = update.filter(failed_slots::Column::Tree.eq(tree.to_bytes().to_vec()));
+    }
+    if let Some(slot) = slot {
+        update = update.filter(failed_slots::Column::Slot.eq(slot));
+    }
+    let res = update.exec(conn).await?;
+    println!("Requeued {} slots", res.rows_affected);
+    Ok(())
+}
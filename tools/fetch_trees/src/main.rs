@@ -0,0 +1,362 @@
+use {
+    anchor_lang::{AccountDeserialize, AnchorDeserialize},
+    anyhow::Context,
+    clap::{Parser, ValueEnum},
+    digital_asset_types::dao::numeric::{to_i64, to_u64},
+    log::{debug, info},
+    mpl_bubblegum::state::TreeConfig,
+    serde::Serialize,
+    solana_account_decoder::UiAccountEncoding,
+    solana_client::{
+        nonblocking::rpc_client::RpcClient,
+        rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
+    solana_sdk::{account::Account, pubkey::Pubkey},
+    spl_account_compression::state::{
+        merkle_tree_get_size, ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
+    },
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+    std::{env, pin::Pin},
+    tokio::{
+        fs::File,
+        io::{stdout, AsyncWrite, AsyncWriteExt},
+    },
+};
+
+// Max accounts per `getMultipleAccounts` request.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Solana RPC endpoint.
+    #[arg(long, short, alias = "rpc-url", required_unless_present = "pg_url")]
+    rpc: Option<String>,
+
+    /// Read the trees indexed in the `merkle_tree` table of this database instead of the chain.
+    #[arg(long, conflicts_with = "rpc")]
+    pg_url: Option<String>,
+
+    /// Only trees created by this address (the Bubblegum tree config creator).
+    #[arg(long)]
+    creator: Option<Pubkey>,
+
+    /// Only trees whose tree authority (the Bubblegum tree config delegate) is this address.
+    #[arg(long)]
+    authority: Option<Pubkey>,
+
+    /// Only trees with at least this seq.
+    #[arg(long, default_value_t = 0)]
+    min_seq: u64,
+
+    /// Only trees created after this slot.
+    #[arg(long)]
+    created_after_slot: Option<u64>,
+
+    /// Output format, `text` is one tree per line as read by tree-status.
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Output file, `-` for stdout.
+    #[arg(long, short, default_value = "-")]
+    output: String,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeInfo {
+    tree: String,
+    creator: String,
+    authority: String,
+    creation_slot: u64,
+    seq: u64,
+    max_depth: u32,
+    max_buffer_size: u32,
+    num_minted: u64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct IndexedTree {
+    tree: Vec<u8>,
+    creator: Option<Vec<u8>>,
+    delegate: Option<Vec<u8>>,
+    creation_slot: Option<i64>,
+    seq: i64,
+    max_depth: Option<i32>,
+    max_buffer_size: Option<i32>,
+    num_minted: Option<i64>,
+}
+
+#[derive(Debug)]
+struct TreeHeader {
+    tree: Pubkey,
+    config: Pubkey,
+    creation_slot: u64,
+    seq: u64,
+    max_depth: u32,
+    max_buffer_size: u32,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let mut trees = match (&args.pg_url, &args.rpc) {
+        (Some(pg_url), _) => fetch_indexed_trees(pg_url, &args).await?,
+        (None, Some(rpc)) => {
+            let client = RpcClient::new(rpc.clone());
+            let headers = fetch_tree_headers(&client, &args).await?;
+            info!(
+                "Found {} Bubblegum trees matching the header filters",
+                headers.len()
+            );
+            fetch_tree_configs(&client, headers, &args).await?
+        }
+        (None, None) => anyhow::bail!("either --rpc or --pg-url is required"),
+    };
+    trees.sort_by(|a, b| {
+        a.creation_slot
+            .cmp(&b.creation_slot)
+            .then_with(|| a.tree.cmp(&b.tree))
+    });
+    info!("Found {} trees", trees.len());
+
+    let mut output: Pin<Box<dyn AsyncWrite>> = if args.output == "-" {
+        Box::pin(stdout())
+    } else {
+        Box::pin(
+            File::create(&args.output)
+                .await
+                .with_context(|| format!("failed to create {}", args.output))?,
+        )
+    };
+    write_trees(&mut output, &trees, args.format).await?;
+    output.flush().await?;
+
+    Ok(())
+}
+
+// Reads the trees the ingester indexed from their creation, config and discovery, with the
+// latest seq in the tree's change log.
+async fn fetch_indexed_trees(pg_url: &str, args: &Args) -> anyhow::Result<Vec<TreeInfo>> {
+    let options: PgConnectOptions = pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    let rows: Vec<IndexedTree> = sqlx::query_as(
+        "
+SELECT * FROM (
+    SELECT t.tree, t.creator, t.delegate, t.creation_slot, t.max_depth, t.max_buffer_size,
+        t.num_minted, COALESCE((SELECT max(c.seq) FROM cl_items c WHERE c.tree = t.tree), 0) AS seq
+    FROM merkle_tree t
+    WHERE ($1::bytea IS NULL OR t.creator = $1)
+    AND ($2::bytea IS NULL OR t.delegate = $2)
+    AND ($3::bigint IS NULL OR t.creation_slot > $3)
+) trees
+WHERE seq >= $4
+",
+    )
+    .bind(args.creator.map(|c| c.to_bytes().to_vec()))
+    .bind(args.authority.map(|a| a.to_bytes().to_vec()))
+    .bind(
+        args.created_after_slot
+            .map(|s| to_i64(s, "created_after_slot"))
+            .transpose()?,
+    )
+    .bind(to_i64(args.min_seq, "min_seq")?)
+    .fetch_all(&pool)
+    .await
+    .context("failed to read merkle_tree")?;
+
+    let address = |key: Option<Vec<u8>>| {
+        key.map(|k| bs58::encode(k).into_string())
+            .unwrap_or_default()
+    };
+    rows.into_iter()
+        .map(|row| -> anyhow::Result<TreeInfo> {
+            Ok(TreeInfo {
+                tree: bs58::encode(row.tree).into_string(),
+                creator: address(row.creator),
+                authority: address(row.delegate),
+                creation_slot: to_u64(row.creation_slot.unwrap_or_default(), "creation_slot")?,
+                seq: to_u64(row.seq, "seq")?,
+                max_depth: row.max_depth.unwrap_or_default() as u32,
+                max_buffer_size: row.max_buffer_size.unwrap_or_default() as u32,
+                num_minted: to_u64(row.num_minted.unwrap_or_default(), "num_minted")?,
+            })
+        })
+        .collect()
+}
+
+// Reads every merkle tree account, keeping the Bubblegum trees that pass the seq and slot filters.
+async fn fetch_tree_headers(client: &RpcClient, args: &Args) -> anyhow::Result<Vec<TreeHeader>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            vec![1u8],
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    };
+    let accounts: Vec<(Pubkey, Account)> = client
+        .get_program_accounts_with_config(&spl_account_compression::id(), config)
+        .await
+        .context("failed to fetch merkle tree accounts")?;
+    info!("Fetched {} merkle tree accounts", accounts.len());
+
+    let mut headers = Vec::new();
+    for (tree, account) in accounts.into_iter() {
+        match parse_tree_header(tree, &account.data) {
+            Ok(Some(header)) => {
+                if header.seq < args.min_seq {
+                    continue;
+                }
+                if let Some(slot) = args.created_after_slot {
+                    if header.creation_slot <= slot {
+                        continue;
+                    }
+                }
+                headers.push(header);
+            }
+            Ok(None) => {}
+            Err(error) => debug!("skipping tree {}: {:?}", tree, error),
+        }
+    }
+    Ok(headers)
+}
+
+// Returns `None` for trees that aren't owned by Bubblegum.
+fn parse_tree_header(tree: Pubkey, data: &[u8]) -> anyhow::Result<Option<TreeHeader>> {
+    anyhow::ensure!(
+        data.len() >= CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
+        "account too small"
+    );
+    let (mut header_bytes, rest) = data.split_at(CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1);
+    let header = ConcurrentMerkleTreeHeader::deserialize(&mut header_bytes)?;
+
+    let config = Pubkey::find_program_address(&[tree.as_ref()], &mpl_bubblegum::id()).0;
+    if header.assert_valid_authority(&config).is_err() {
+        return Ok(None);
+    }
+
+    let merkle_tree_size = merkle_tree_get_size(&header).map_err(|e| anyhow::anyhow!("{:?}", e))?;
+    anyhow::ensure!(rest.len() >= merkle_tree_size, "account too small");
+    let seq = u64::from_le_bytes(rest[0..8].try_into()?);
+
+    Ok(Some(TreeHeader {
+        tree,
+        config,
+        creation_slot: header.get_creation_slot(),
+        seq,
+        max_depth: header.get_max_depth(),
+        max_buffer_size: header.get_max_buffer_size(),
+    }))
+}
+
+// Fetches the Bubblegum tree configs to apply the creator and authority filters.
+async fn fetch_tree_configs(
+    client: &RpcClient,
+    headers: Vec<TreeHeader>,
+    args: &Args,
+) -> anyhow::Result<Vec<TreeInfo>> {
+    let mut trees = Vec::with_capacity(headers.len());
+    for chunk in headers.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let keys = chunk.iter().map(|h| h.config).collect::<Vec<_>>();
+        let accounts = client
+            .get_multiple_accounts(&keys)
+            .await
+            .context("failed to fetch tree configs")?;
+        for (header, account) in chunk.iter().zip(accounts.into_iter()) {
+            let account = match account {
+                Some(account) => account,
+                None => {
+                    debug!("skipping tree {}: tree config not found", header.tree);
+                    continue;
+                }
+            };
+            let config = match TreeConfig::try_deserialize(&mut account.data.as_slice()) {
+                Ok(config) => config,
+                Err(error) => {
+                    debug!("skipping tree {}: {:?}", header.tree, error);
+                    continue;
+                }
+            };
+            if args.creator.map_or(false, |c| c != config.tree_creator)
+                || args.authority.map_or(false, |a| a != config.tree_delegate)
+            {
+                continue;
+            }
+            trees.push(TreeInfo {
+                tree: header.tree.to_string(),
+                creator: config.tree_creator.to_string(),
+                authority: config.tree_delegate.to_string(),
+                creation_slot: header.creation_slot,
+                seq: header.seq,
+                max_depth: header.max_depth,
+                max_buffer_size: header.max_buffer_size,
+                num_minted: config.num_minted,
+            });
+        }
+    }
+    Ok(trees)
+}
+
+async fn write_trees(
+    output: &mut Pin<Box<dyn AsyncWrite>>,
+    trees: &[TreeInfo],
+    format: Format,
+) -> anyhow::Result<()> {
+    match format {
+        Format::Text => {
+            for tree in trees.iter() {
+                output
+                    .write_all(format!("{}\n", tree.tree).as_bytes())
+                    .await?;
+            }
+        }
+        Format::Json => {
+            let mut json = serde_json::to_vec_pretty(trees)?;
+            json.push(b'\n');
+            output.write_all(&json).await?;
+        }
+        Format::Csv => {
+            output
+                .write_all(
+                    b"tree,creator,authority,creation_slot,seq,max_depth,max_buffer_size,num_minted\n",
+                )
+                .await?;
+            for tree in trees.iter() {
+                let line = format!(
+                    "{},{},{},{},{},{},{},{}\n",
+                    tree.tree,
+                    tree.creator,
+                    tree.authority,
+                    tree.creation_slot,
+                    tree.seq,
+                    tree.max_depth,
+                    tree.max_buffer_size,
+                    tree.num_minted
+                );
+                output.write_all(line.as_bytes()).await?;
+            }
+        }
+    }
+    Ok(())
+}
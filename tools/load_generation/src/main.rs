@@ -1,3 +1,5 @@
+mod verify;
+
 use mpl_token_metadata::state::Creator;
 use solana_client::client_error::ClientError;
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -13,9 +15,10 @@ use solana_sdk::transaction::Transaction;
 use spl_token::solana_program::program_pack::Pack;
 use std::env;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
 use tokio::time::{sleep, Duration as td};
+use verify::GeneratedNft;
 
 #[tokio::main]
 async fn main() {
@@ -26,6 +29,16 @@ async fn main() {
         env::var("RPC_URL").unwrap_or_else(|_| "http://solana:8899".to_string());
     let network = env::var("NETWORK").unwrap_or_else(|_| "local".to_string());
     let carnage = env::var("AMOUNT_OF_CHAOS").map(|chaos_str| chaos_str.parse::<usize>().expect("How can you mess that up? Okay okay, your AMOUNT OF CHAOS variable is super messed up.")).unwrap_or_else(|_| 64);
+    // With a DAS endpoint to verify, the chaos stops after `ROUNDS` rounds (1 by default) and the
+    // endpoint is asked for every NFT it made.
+    let das_url = env::var("DAS_URL").ok();
+    let rounds = env::var("ROUNDS")
+        .map(|rounds| rounds.parse::<usize>().expect("ROUNDS must be a number of rounds"))
+        .ok()
+        .or_else(|| das_url.as_ref().map(|_| 1));
+    let verify_timeout = env::var("VERIFY_TIMEOUT_SECS")
+        .map(|secs| secs.parse::<u64>().expect("VERIFY_TIMEOUT_SECS must be a number of seconds"))
+        .unwrap_or(120);
     let le_blockchain = Arc::new(RpcClient::new_with_timeout_and_commitment(
         le_blockchain_url,
         Duration::from_secs(45),
@@ -43,7 +56,10 @@ async fn main() {
     let nft_collection_thing = make_a_nft_thing(le_blockchain.clone(), kp.clone(), kp.clone(), None)
         .await
         .unwrap();
-    println!("NFT Collection Thing: {:?}", nft_collection_thing);
+    println!("NFT Collection Thing: {:?}", nft_collection_thing.mint);
+    let collection_mint = nft_collection_thing.mint;
+    let mut generated = vec![];
+    let mut round = 0;
     loop {
         let mut tasks = vec![];
         for _ in (0..carnage) {
@@ -55,13 +71,16 @@ async fn main() {
                 let _permit = semaphore.acquire().await.unwrap(); //wait for le government to allow le action
                                                                   // MINT A MASTER EDITION:
                 sleep(Duration::from_millis(1000)).await;
-                make_a_nft_thing(le_clone, kp, kp_new, Some(nft_collection_thing.clone())).await
+                make_a_nft_thing(le_clone, kp, kp_new, Some(collection_mint)).await
             }));
         }
         for task in tasks {
             match task.await.unwrap() {
-                Ok(e) => {
+                Ok(nft) => {
                     println!("Lo! and Behold ! Successfully minted a NFT");
+                    if das_url.is_some() {
+                        generated.push(nft);
+                    }
                     continue;
                 }
                 Err(e) => {
@@ -71,6 +90,25 @@ async fn main() {
             }
         }
         check_balance(le_blockchain.clone(), kp.clone(), network != "mainnet").await;
+        round += 1;
+        if rounds.map_or(false, |rounds| round >= rounds) {
+            break;
+        }
+    }
+    if let Some(das_url) = das_url {
+        println!("Verifying {} NFTs against {}", generated.len(), das_url);
+        let report = verify::verify(
+            das_url,
+            generated,
+            Duration::from_secs(verify_timeout),
+            Duration::from_millis(500),
+            carnage,
+        )
+        .await;
+        report.print();
+        if !report.passed() {
+            std::process::exit(1);
+        }
     }
 }
 
@@ -151,7 +189,7 @@ pub async fn make_a_nft_thing(
     payer: Arc<Keypair>,
     owner: Arc<Keypair>,
     collection_mint: Option<Pubkey>,
-) -> Result<Pubkey, ClientError> {
+) -> Result<GeneratedNft, ClientError> {
     let (mint, token_account) = make_a_token_thing(solana_client.clone(), payer.clone(), owner.clone(), 1).await?;
     let prg_uid = mpl_token_metadata::id();
     let metadata_seeds = &[
@@ -200,6 +238,7 @@ pub async fn make_a_nft_thing(
         solana_client.get_latest_blockhash().await?,
     );
     solana_client.send_and_confirm_transaction(&tx).await?;
+    let minted_at = Instant::now();
     let mut ix = vec![
         mpl_token_metadata::instruction::update_metadata_accounts_v2(
             prg_uid,
@@ -236,5 +275,10 @@ pub async fn make_a_nft_thing(
         solana_client.get_latest_blockhash().await?,
     );
     solana_client.send_and_confirm_transaction(&tx).await?;
-    Ok(mint)
+    Ok(GeneratedNft {
+        mint,
+        owner: owner.pubkey(),
+        minted_at,
+        collection: collection_mint.map(|collection| (collection, Instant::now())),
+    })
 }
@@ -0,0 +1,228 @@
+use serde_json::{json, Value};
+use solana_program::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::time::sleep;
+
+/// What a round of chaos left on chain, and when each operation was confirmed.
+#[derive(Debug, Clone)]
+pub struct GeneratedNft {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub minted_at: Instant,
+    pub collection: Option<(Pubkey, Instant)>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    /// How long after its confirmation each operation showed up in the API, by operation: until
+    /// the request that found it was sent.
+    pub latencies: BTreeMap<&'static str, Vec<Duration>>,
+    /// How long each `getAsset` request took, from sending it to its response.
+    pub requests: Vec<Duration>,
+    pub missing: Vec<Pubkey>,
+    pub wrong_owner: Vec<(Pubkey, String)>,
+    pub missing_collection: Vec<Pubkey>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.wrong_owner.is_empty() && self.missing_collection.is_empty()
+    }
+
+    pub fn print(&self) {
+        for (operation, latencies) in self.latencies.iter() {
+            println!("{}: {} indexed, {}", operation, latencies.len(), summary(latencies));
+        }
+        println!(
+            "getAsset: {} requests, {}",
+            self.requests.len(),
+            summary(&self.requests)
+        );
+        for mint in self.missing.iter() {
+            println!("Alas, {} never appeared", mint);
+        }
+        for (mint, owner) in self.wrong_owner.iter() {
+            println!("Alas, {} is owned by {} in the index", mint, owner);
+        }
+        for mint in self.missing_collection.iter() {
+            println!("Alas, {} never joined its collection", mint);
+        }
+    }
+}
+
+fn summary(durations: &[Duration]) -> String {
+    let mut sorted = durations.to_vec();
+    sorted.sort();
+    format!(
+        "p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+        percentile(&sorted, 0.5),
+        percentile(&sorted, 0.9),
+        percentile(&sorted, 0.99),
+        sorted.last().copied().unwrap_or_default()
+    )
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default();
+    }
+    let rank = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len());
+    sorted[rank - 1]
+}
+
+enum Check {
+    Missing,
+    WrongOwner(String),
+    Indexed { collection: bool },
+}
+
+async fn get_asset(
+    client: &reqwest::Client,
+    das_url: &str,
+    mint: &Pubkey,
+) -> Result<Option<Value>, reqwest::Error> {
+    let response: Value = client
+        .post(das_url)
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getAsset",
+            "params": { "id": mint.to_string() },
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    Ok(response.get("result").cloned().filter(|r| !r.is_null()))
+}
+
+async fn check(client: &reqwest::Client, das_url: &str, nft: &GeneratedNft) -> Check {
+    let asset = match get_asset(client, das_url, &nft.mint).await {
+        Ok(Some(asset)) => asset,
+        _ => return Check::Missing,
+    };
+    let owner = asset["ownership"]["owner"].as_str().unwrap_or_default();
+    if owner != nft.owner.to_string() {
+        return Check::WrongOwner(owner.to_string());
+    }
+    let collection = match &nft.collection {
+        Some((collection, _)) => asset["grouping"]
+            .as_array()
+            .map(|groups| {
+                groups.iter().any(|g| {
+                    g["group_key"] == "collection" && g["group_value"] == collection.to_string()
+                })
+            })
+            .unwrap_or(false),
+        None => true,
+    };
+    Check::Indexed { collection }
+}
+
+struct Watched {
+    nft: GeneratedNft,
+    minted: Option<Duration>,
+    collected: Option<Duration>,
+    requests: Vec<Duration>,
+    check: Check,
+}
+
+/// Polls the DAS API until the NFT shows up with its owner and collection, or `timeout` after
+/// its last operation. Latencies run until the request that found an operation was sent, so
+/// neither waiting for a slot of the concurrency limit nor the request itself counts against
+/// them; the requests are timed on their own.
+async fn watch(
+    client: reqwest::Client,
+    das_url: Arc<String>,
+    semaphore: Arc<Semaphore>,
+    nft: GeneratedNft,
+    timeout: Duration,
+    poll_interval: Duration,
+) -> Watched {
+    let deadline = nft.collection.map_or(nft.minted_at, |(_, at)| at) + timeout;
+    let (mut minted, mut collected) = (None, None);
+    let mut requests = vec![];
+    loop {
+        let (sent_at, check) = {
+            let _permit = semaphore.acquire().await.unwrap();
+            let sent_at = Instant::now();
+            let check = check(&client, &das_url, &nft).await;
+            requests.push(sent_at.elapsed());
+            (sent_at, check)
+        };
+        let mut done = false;
+        if let Check::Indexed { collection } = check {
+            minted.get_or_insert_with(|| sent_at.saturating_duration_since(nft.minted_at));
+            if collection {
+                if let Some((_, at)) = nft.collection {
+                    collected.get_or_insert_with(|| sent_at.saturating_duration_since(at));
+                }
+                done = true;
+            }
+        }
+        if done || Instant::now() >= deadline {
+            return Watched {
+                nft,
+                minted,
+                collected,
+                requests,
+                check,
+            };
+        }
+        sleep(poll_interval).await;
+    }
+}
+
+pub async fn verify(
+    das_url: String,
+    nfts: Vec<GeneratedNft>,
+    timeout: Duration,
+    poll_interval: Duration,
+    concurrency: usize,
+) -> Report {
+    let client = reqwest::Client::new();
+    let das_url = Arc::new(das_url);
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = vec![];
+    for nft in nfts {
+        tasks.push(tokio::spawn(watch(
+            client.clone(),
+            das_url.clone(),
+            semaphore.clone(),
+            nft,
+            timeout,
+            poll_interval,
+        )));
+    }
+    let mut report = Report::default();
+    for task in tasks {
+        let Watched {
+            nft,
+            minted,
+            collected,
+            requests,
+            check,
+        } = task.await.unwrap();
+        report.requests.extend(requests);
+        if let Some(latency) = minted {
+            report.latencies.entry("mint").or_default().push(latency);
+        }
+        if let Some(latency) = collected {
+            report
+                .latencies
+                .entry("verify_collection")
+                .or_default()
+                .push(latency);
+        }
+        match check {
+            Check::Missing => report.missing.push(nft.mint),
+            Check::WrongOwner(owner) => report.wrong_owner.push((nft.mint, owner)),
+            Check::Indexed { collection: false } => report.missing_collection.push(nft.mint),
+            Check::Indexed { collection: true } => {}
+        }
+    }
+    report
+}
@@ -0,0 +1,232 @@
+use {
+    anyhow::Context,
+    clap::Parser,
+    digital_asset_types::dao::{
+        asset, asset_authority, asset_authority_history, asset_creators, asset_data,
+        asset_data_history, asset_grouping, asset_v1_account_attachments, backfill_items,
+        failed_slots, pending_creator_verifications, scopes::metadata_blob::resolve_metadata_blobs,
+    },
+    log::info,
+    nft_ingester::collection_traits::{metadata_traits, update_collection_traits},
+    sea_orm::{
+        entity::*, query::*, DatabaseConnection, DbBackend, FromQueryResult, JsonValue,
+        SqlxPostgresConnector, Statement,
+    },
+    solana_sdk::pubkey::Pubkey,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
+    std::env,
+    tokio::io::{stdin, AsyncBufReadExt, BufReader},
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Postgres connection string.
+    #[arg(long, short)]
+    pg_url: String,
+
+    /// The tree to remove.
+    #[arg(long, short)]
+    tree: Pubkey,
+
+    /// Assets removed per transaction.
+    #[arg(long, short, default_value_t = 1000)]
+    batch_size: u64,
+
+    /// Only count what would be removed.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Don't ask for confirmation.
+    #[arg(long, short)]
+    yes: bool,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Count {
+    count: i64,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let options: PgConnectOptions = args.pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect_with(options)
+        .await?;
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool);
+    let tree = args.tree.to_bytes().to_vec();
+
+    let assets = asset::Entity::find()
+        .filter(asset::Column::TreeId.eq(tree.clone()))
+        .count(&conn)
+        .await?;
+    let cl_items = count_tree_rows(&conn, "cl_items", &tree).await?;
+    let cl_audits = count_tree_rows(&conn, "cl_audits", &tree).await?;
+    let backfill_items = backfill_items::Entity::find()
+        .filter(backfill_items::Column::Tree.eq(tree.clone()))
+        .count(&conn)
+        .await?;
+    println!(
+        "Tree {} has {} assets, {} cl_items, {} cl_audits and {} backfill_items",
+        args.tree, assets, cl_items, cl_audits, backfill_items
+    );
+    if args.dry_run {
+        return Ok(());
+    }
+    if !args.yes && !confirm(&args.tree).await? {
+        println!("Aborted");
+        return Ok(());
+    }
+
+    let mut removed = 0;
+    loop {
+        let batch = purge_assets(&conn, &tree, args.batch_size).await?;
+        if batch == 0 {
+            break;
+        }
+        removed += batch;
+        info!("Removed {} of {} assets", removed, assets);
+    }
+
+    // Each tree has its own change log partitions, so they are dropped rather than deleted from.
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT drop_tree_partitions($1)",
+        vec![tree.clone().into()],
+    ))
+    .await?;
+    conn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "DELETE FROM failed_tx_log WHERE tree = $1",
+        vec![tree.clone().into()],
+    ))
+    .await?;
+    failed_slots::Entity::delete_many()
+        .filter(failed_slots::Column::Tree.eq(tree.clone()))
+        .exec(&conn)
+        .await?;
+    backfill_items::Entity::delete_many()
+        .filter(backfill_items::Column::Tree.eq(tree))
+        .exec(&conn)
+        .await?;
+    info!("Removed tree {}", args.tree);
+
+    Ok(())
+}
+
+async fn count_tree_rows(
+    conn: &DatabaseConnection,
+    table: &str,
+    tree: &[u8],
+) -> anyhow::Result<i64> {
+    let count = Count::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        &format!("SELECT count(*) AS count FROM {} WHERE tree = $1", table),
+        vec![tree.to_vec().into()],
+    ))
+    .one(conn)
+    .await?
+    .map(|c| c.count)
+    .unwrap_or_default();
+    Ok(count)
+}
+
+async fn confirm(tree: &Pubkey) -> anyhow::Result<bool> {
+    println!("Type the tree address to remove everything indexed for it:");
+    let mut line = String::new();
+    BufReader::new(stdin()).read_line(&mut line).await?;
+    Ok(line.trim() == tree.to_string())
+}
+
+// Removes up to `limit` assets of the tree along with the rows that hang off them, in one
+// transaction. Returns how many assets were removed.
+async fn purge_assets(conn: &DatabaseConnection, tree: &[u8], limit: u64) -> anyhow::Result<u64> {
+    let txn = conn.begin().await?;
+    let ids: Vec<Vec<u8>> = asset::Entity::find()
+        .filter(asset::Column::TreeId.eq(tree.to_vec()))
+        .order_by_asc(asset::Column::Id)
+        .limit(limit)
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|a| a.id)
+        .collect();
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    // Take the assets' traits out of their collections' trait counts while the groupings are
+    // still there. Assets sharing a URI's metadata only reference its blob.
+    let mut data = asset_data::Entity::find()
+        .filter(asset_data::Column::Id.is_in(ids.clone()))
+        .all(&txn)
+        .await?;
+    resolve_metadata_blobs(&txn, &mut data).await?;
+    for d in data.iter() {
+        if !metadata_traits(&d.metadata).is_empty() {
+            update_collection_traits(&txn, d.id.clone(), &d.metadata, &JsonValue::Null).await?;
+        }
+    }
+
+    // Metadata download tasks only reference their asset in their JSON data.
+    let task_asset_ids = serde_json::to_value(&ids)?;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+DELETE FROM tasks
+WHERE task_type = 'DownloadMetadata'
+AND (data->'asset_data_id')::jsonb IN (SELECT jsonb_array_elements($1::jsonb))
+",
+        vec![task_asset_ids.into()],
+    ))
+    .await?;
+
+    asset_creators::Entity::delete_many()
+        .filter(asset_creators::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset_grouping::Entity::delete_many()
+        .filter(asset_grouping::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset_authority::Entity::delete_many()
+        .filter(asset_authority::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset_authority_history::Entity::delete_many()
+        .filter(asset_authority_history::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset_data_history::Entity::delete_many()
+        .filter(asset_data_history::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    pending_creator_verifications::Entity::delete_many()
+        .filter(pending_creator_verifications::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset_v1_account_attachments::Entity::delete_many()
+        .filter(asset_v1_account_attachments::Column::AssetId.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    asset::Entity::delete_many()
+        .filter(asset::Column::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+    // Compressed assets have their data keyed by the asset id.
+    asset_data::Entity::delete_many()
+        .filter(asset_data::Column::Id.is_in(ids.clone()))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+    Ok(ids.len() as u64)
+}
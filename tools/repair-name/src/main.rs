@@ -14,7 +14,7 @@ use crate::{
     config::{init_logger, setup_config, IngesterRole},
     database::setup_database,
     error::IngesterError,
-    metrics::setup_metrics,
+    metrics::{flush_metrics, setup_metrics},
     stream::StreamSizeTimer,
     transaction_notifications::transaction_worker,
 };
@@ -108,6 +108,7 @@ pub async fn main() -> Result<(), IngesterError> {
     }
 
     tasks.shutdown().await;
+    flush_metrics();
 
     Ok(())
 }
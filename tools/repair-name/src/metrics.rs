@@ -1,7 +1,8 @@
-use std::net::UdpSocket;
+use std::time::Duration;
 
-use cadence::{BufferedUdpMetricSink, QueuingMetricSink, StatsdClient};
+use cadence::StatsdClient;
 use cadence_macros::{is_global_default_set, set_global_default, statsd_count, statsd_time};
+use digital_asset_types::metrics_sink::{self, FlushingMetricSink, LogMetricSink};
 use log::{error, warn};
 use tokio::time::Instant;
 
@@ -26,12 +27,18 @@ pub fn setup_metrics(config: &IngesterConfig) {
     let port = config.metrics_port;
     let env = config.env.clone().unwrap_or("dev".to_string());
     if uri.is_some() || port.is_some() {
-        let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
-        socket.set_nonblocking(true).unwrap();
         let host = (uri.unwrap(), port.unwrap());
-        let udp_sink = BufferedUdpMetricSink::from(host, socket).unwrap();
-        let queuing_sink = QueuingMetricSink::from(udp_sink);
-        let builder = StatsdClient::builder("das_ingester", queuing_sink);
+        let tags = format!("|#env:{},version:{}", env, CODE_VERSION);
+        let builder = match FlushingMetricSink::start(host.clone(), "das_ingester", tags) {
+            Ok(sink) => StatsdClient::builder("das_ingester", sink),
+            Err(err) => {
+                warn!(
+                    "Metrics can't be sent to {}:{}, writing them to the log instead: {}",
+                    host.0, host.1, err
+                );
+                StatsdClient::builder("das_ingester", LogMetricSink)
+            }
+        };
         let client = builder
             .with_tag("env", env)
             .with_tag("version", CODE_VERSION)
@@ -40,6 +47,11 @@ pub fn setup_metrics(config: &IngesterConfig) {
     }
 }
 
+/// Sends the metrics still queued, waiting for at most a few seconds. Called on shutdown.
+pub fn flush_metrics() {
+    metrics_sink::flush_metrics(Duration::from_secs(5));
+}
+
 pub fn capture_result(
     id: String,
     stream: &str,
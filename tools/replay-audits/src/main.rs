@@ -0,0 +1,237 @@
+use {
+    anyhow::Context,
+    clap::Parser,
+    digital_asset_types::dao::cl_items,
+    log::{error, info, warn},
+    nft_ingester::program_transformers::ProgramTransformer,
+    plerkle_serialization::root_as_transaction_info,
+    sea_orm::{
+        entity::*, query::*, DatabaseConnection, DbBackend, FromQueryResult, SqlxPostgresConnector,
+        Statement,
+    },
+    solana_sdk::pubkey::Pubkey,
+    sqlx::{
+        postgres::{PgConnectOptions, PgPoolOptions},
+        PgPool,
+    },
+    std::{
+        collections::HashMap,
+        env, fs,
+        path::{Path, PathBuf},
+    },
+    tokio::sync::mpsc,
+};
+
+#[derive(Parser)]
+#[command(next_line_help = true, author, version, about)]
+struct Args {
+    /// Postgres connection string.
+    #[arg(long, short)]
+    pg_url: String,
+
+    /// The tree to rebuild.
+    #[arg(long, short)]
+    tree: Pubkey,
+
+    /// Delete the tree's cl_items before rebuilding them, so nodes without audits are dropped.
+    #[arg(long)]
+    reset: bool,
+
+    /// Directory of transaction payloads dumped by the ingester's admin server. The tree's
+    /// audited transactions found there are replayed through the transformers.
+    #[arg(long)]
+    payload_dir: Option<PathBuf>,
+
+    /// Write the signatures of audited transactions without a dumped payload to this file.
+    #[arg(long)]
+    missing_out: Option<PathBuf>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AuditedTransaction {
+    tx: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env::set_var(
+        env_logger::DEFAULT_FILTER_ENV,
+        env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info,sqlx=warn".into()),
+    );
+    env_logger::init();
+
+    let args = Args::parse();
+    let options: PgConnectOptions = args.pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?;
+    let conn = SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone());
+    let tree = args.tree.to_bytes().to_vec();
+
+    let rebuilt = rebuild_cl_items(&conn, &tree, args.reset).await?;
+    info!("Rebuilt {} cl_items of tree {}", rebuilt, args.tree);
+
+    if let Some(dir) = args.payload_dir {
+        let missing = replay_transactions(pool, &conn, &tree, &dir).await?;
+        if !missing.is_empty() {
+            warn!(
+                "{} audited transactions have no dumped payload",
+                missing.len()
+            );
+        }
+        if let Some(path) = args.missing_out {
+            fs::write(&path, missing.join("\n"))
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+// The latest audit of each node is the node's current state.
+async fn rebuild_cl_items(
+    conn: &DatabaseConnection,
+    tree: &[u8],
+    reset: bool,
+) -> anyhow::Result<u64> {
+    let txn = conn.begin().await?;
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT create_tree_partition('cl_items', $1)",
+        vec![tree.to_vec().into()],
+    ))
+    .await?;
+    if reset {
+        cl_items::Entity::delete_many()
+            .filter(cl_items::Column::Tree.eq(tree.to_vec()))
+            .exec(&txn)
+            .await?;
+    }
+    let res = txn
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "
+INSERT INTO cl_items (tree, node_idx, leaf_idx, seq, level, hash)
+SELECT DISTINCT ON (node_idx) tree, node_idx, leaf_idx, seq, level, hash
+FROM cl_audits
+WHERE tree = $1
+ORDER BY node_idx, seq DESC
+ON CONFLICT (tree, node_idx) DO UPDATE SET
+    leaf_idx = excluded.leaf_idx,
+    seq = excluded.seq,
+    level = excluded.level,
+    hash = excluded.hash
+WHERE excluded.seq >= cl_items.seq
+",
+            vec![tree.to_vec().into()],
+        ))
+        .await?;
+    txn.commit().await?;
+    Ok(res.rows_affected())
+}
+
+// Replays the tree's audited transactions in seq order. Every update is guarded by its seq, so
+// replaying what was indexed already leaves each asset with the output of the current
+// transformers for its latest updates. Returns the signatures without a dumped payload.
+async fn replay_transactions(
+    pool: PgPool,
+    conn: &DatabaseConnection,
+    tree: &[u8],
+    dir: &Path,
+) -> anyhow::Result<Vec<String>> {
+    let payloads = find_payloads(dir)?;
+    info!(
+        "Found {} dumped transactions in {}",
+        payloads.len(),
+        dir.display()
+    );
+
+    let audited = AuditedTransaction::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT tx FROM cl_audits WHERE tree = $1 GROUP BY tx ORDER BY min(seq)",
+        vec![tree.to_vec().into()],
+    ))
+    .all(conn)
+    .await?;
+
+    // Metadata downloads can't run without the network, they are only counted.
+    let (task_sender, mut task_receiver) = mpsc::unbounded_channel();
+    let transformer = ProgramTransformer::new(pool, task_sender);
+    let (mut replayed, mut failed) = (0, 0);
+    let mut missing = Vec::new();
+    for AuditedTransaction { tx: signature } in audited {
+        let path = match payloads.get(&signature) {
+            Some(path) => path,
+            None => {
+                missing.push(signature);
+                continue;
+            }
+        };
+        let data = tokio::fs::read(path)
+            .await
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let res = match root_as_transaction_info(&data) {
+            Ok(tx) => transformer
+                .handle_transaction(&tx)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match res {
+            Ok(()) => replayed += 1,
+            Err(e) => {
+                error!("Failed to replay {}: {}", signature, e);
+                failed += 1;
+            }
+        }
+    }
+    drop(transformer);
+
+    let mut tasks = 0;
+    while task_receiver.recv().await.is_some() {
+        tasks += 1;
+    }
+    info!(
+        "Replayed {} transactions, {} failed, {} missing",
+        replayed,
+        failed,
+        missing.len()
+    );
+    if tasks > 0 {
+        warn!(
+            "Skipped {} metadata download tasks, create them with bgtask_creator",
+            tasks
+        );
+    }
+    Ok(missing)
+}
+
+// Dumped transactions are named `transaction-<slot>-<signature>.bin`, in `dir` or in its per
+// program directories.
+fn find_payloads(dir: &Path) -> anyhow::Result<HashMap<String, PathBuf>> {
+    let mut payloads = HashMap::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries =
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let signature = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("transaction-"))
+                .and_then(|name| name.strip_suffix(".bin"))
+                .and_then(|name| name.split_once('-'))
+                .map(|(_slot, signature)| signature.to_string());
+            if let Some(signature) = signature {
+                payloads.insert(signature, path);
+            }
+        }
+    }
+    Ok(payloads)
+}
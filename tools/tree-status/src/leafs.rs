@@ -0,0 +1,227 @@
+use {
+    anyhow::Context,
+    sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, Statement, Value},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{
+        cmp::Reverse,
+        collections::BinaryHeap,
+        env,
+        fs::{self, File},
+        io::{BufRead, BufReader, BufWriter, Lines, Write},
+        mem,
+        path::PathBuf,
+        process,
+        str::FromStr,
+    },
+};
+
+#[derive(Debug, FromQueryResult)]
+pub struct AssetMaxSeq {
+    pub leaf_idx: i64,
+    pub seq: i64,
+}
+
+/// Latest update seen on chain for a leaf index.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainLeaf {
+    pub leaf_idx: i64,
+    pub seq: u64,
+    pub signature: Signature,
+}
+
+/// Fetches the max asset seq per leaf index for `tree`, `limit` leaf indexes at a time starting
+/// right after `after`, so the whole tree never has to be held in memory.
+pub async fn db_leafs_page(
+    conn: &DatabaseConnection,
+    tree: &Pubkey,
+    after: i64,
+    limit: u64,
+) -> anyhow::Result<Vec<AssetMaxSeq>> {
+    let query = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "
+SELECT
+    cl_items.leaf_idx, MAX(asset.seq) AS seq
+FROM
+    asset
+INNER JOIN
+    cl_items ON
+        cl_items.tree = asset.tree_id AND
+        cl_items.seq = asset.seq
+WHERE
+    asset.tree_id = $1 AND
+    cl_items.leaf_idx IS NOT NULL AND
+    cl_items.leaf_idx > $2
+GROUP BY
+    cl_items.leaf_idx
+ORDER BY
+    cl_items.leaf_idx
+LIMIT $3
+",
+        [
+            Value::Bytes(Some(Box::new(tree.as_ref().to_vec()))),
+            Value::BigInt(Some(after)),
+            Value::BigInt(Some(limit as i64)),
+        ],
+    );
+    conn.query_all(query)
+        .await?
+        .iter()
+        .map(|row| AssetMaxSeq::from_query_result(row, "").map_err(Into::into))
+        .collect()
+}
+
+/// External sort of the leaf updates read from a tree's transactions, which arrive in no
+/// particular order. Updates are buffered up to `run_size`, then sorted by leaf index and
+/// spilled to a temporary run file; the runs are merged back in leaf index order when reading.
+pub struct ChainLeafSorter {
+    prefix: String,
+    run_size: usize,
+    buffer: Vec<ChainLeaf>,
+    runs: Vec<PathBuf>,
+}
+
+impl ChainLeafSorter {
+    pub fn new(tree: &Pubkey, run_size: usize) -> Self {
+        Self {
+            prefix: format!("tree-status-{}-{}", tree, process::id()),
+            run_size: run_size.max(1),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, leaf: ChainLeaf) -> anyhow::Result<()> {
+        self.buffer.push(leaf);
+        if self.buffer.len() >= self.run_size {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    // Sorts by leaf index, keeping only the highest seq for each index.
+    fn sort_dedup(buffer: &mut Vec<ChainLeaf>) {
+        buffer.sort_unstable_by(|a, b| a.leaf_idx.cmp(&b.leaf_idx).then(b.seq.cmp(&a.seq)));
+        buffer.dedup_by_key(|leaf| leaf.leaf_idx);
+    }
+
+    fn spill(&mut self) -> anyhow::Result<()> {
+        Self::sort_dedup(&mut self.buffer);
+        let path = env::temp_dir().join(format!("{}-{}.run", self.prefix, self.runs.len()));
+        let file = File::create(&path)
+            .with_context(|| format!("failed to create run file {}", path.display()))?;
+        self.runs.push(path);
+        let mut writer = BufWriter::new(file);
+        for leaf in self.buffer.drain(..) {
+            writeln!(writer, "{} {} {}", leaf.leaf_idx, leaf.seq, leaf.signature)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> anyhow::Result<SortedChainLeafs> {
+        if self.runs.is_empty() {
+            let mut buffer = mem::take(&mut self.buffer);
+            Self::sort_dedup(&mut buffer);
+            return Ok(SortedChainLeafs {
+                memory: buffer.into_iter(),
+                readers: Vec::new(),
+                heap: BinaryHeap::new(),
+                runs: Vec::new(),
+            });
+        }
+
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+        let mut sorted = SortedChainLeafs {
+            memory: Vec::new().into_iter(),
+            readers: Vec::with_capacity(self.runs.len()),
+            heap: BinaryHeap::with_capacity(self.runs.len()),
+            runs: mem::take(&mut self.runs),
+        };
+        for path in sorted.runs.iter() {
+            let file = File::open(path)
+                .with_context(|| format!("failed to open run file {}", path.display()))?;
+            sorted.readers.push(BufReader::new(file).lines());
+        }
+        for run in 0..sorted.readers.len() {
+            sorted.refill(run)?;
+        }
+        Ok(sorted)
+    }
+}
+
+impl Drop for ChainLeafSorter {
+    fn drop(&mut self) {
+        for path in self.runs.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+type RunHead = Reverse<(i64, Reverse<u64>, usize, Signature)>;
+
+/// Chain leaves in leaf index order, one entry per leaf index.
+pub struct SortedChainLeafs {
+    memory: std::vec::IntoIter<ChainLeaf>,
+    readers: Vec<Lines<BufReader<File>>>,
+    // Smallest leaf index first, highest seq first within the same index.
+    heap: BinaryHeap<RunHead>,
+    runs: Vec<PathBuf>,
+}
+
+impl SortedChainLeafs {
+    fn refill(&mut self, run: usize) -> anyhow::Result<()> {
+        if let Some(line) = self.readers[run].next() {
+            let line = line?;
+            let mut parts = line.split(' ');
+            let mut next = || {
+                parts
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("malformed run line: {}", line))
+            };
+            let leaf_idx = next()?.parse()?;
+            let seq = next()?.parse()?;
+            let signature = Signature::from_str(next()?)?;
+            self.heap
+                .push(Reverse((leaf_idx, Reverse(seq), run, signature)));
+        }
+        Ok(())
+    }
+
+    pub fn next(&mut self) -> anyhow::Result<Option<ChainLeaf>> {
+        if self.readers.is_empty() {
+            return Ok(self.memory.next());
+        }
+
+        let mut best: Option<ChainLeaf> = None;
+        loop {
+            match (self.heap.peek(), best) {
+                (Some(Reverse((leaf_idx, ..))), Some(leaf)) if *leaf_idx != leaf.leaf_idx => break,
+                (None, _) => break,
+                _ => {}
+            }
+            let Reverse((leaf_idx, Reverse(seq), run, signature)) = self.heap.pop().unwrap();
+            self.refill(run)?;
+            // The first entry popped for an index carries its highest seq, the rest are older
+            // updates of the same leaf from other runs.
+            if best.is_none() {
+                best = Some(ChainLeaf {
+                    leaf_idx,
+                    seq,
+                    signature,
+                });
+            }
+        }
+        Ok(best)
+    }
+}
+
+impl Drop for SortedChainLeafs {
+    fn drop(&mut self) {
+        for path in self.runs.iter() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -1,5 +1,12 @@
 use crossbeam::channel::{unbounded, Sender};
-use digital_asset_types::dao::cl_audits;
+use digital_asset_types::{
+    dao::{
+        cl_audits,
+        numeric::{to_i64, to_u64},
+        raw_query::RawQuery,
+    },
+    dapi::check_canopy,
+};
 use log::{trace, warn};
 use plerkle_messenger::{MessengerConfig, TRANSACTION_STREAM};
 use plerkle_serialization::serializer::seralize_encoded_transaction_with_status;
@@ -7,88 +14,68 @@ use sea_orm::{QueryOrder, Value};
 use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
 use tokio::runtime::Builder;
 
+mod leafs;
+mod progress;
+mod summary;
+
 use {
     anchor_client::anchor_lang::AnchorDeserialize,
     anyhow::Context,
     clap::{arg, Parser, Subcommand},
+    das_tree_reader::{
+        fetch_transaction, parse_tx_sequence, read_tree, LeafNode, LookupTableCache, RpcThrottle,
+        SeqUpdate, TreeTransaction,
+    },
     figment::util::map,
     futures::{
-        future::{try_join, try_join_all, BoxFuture, FutureExt, TryFutureExt},
+        future::try_join,
         stream::{self, StreamExt},
     },
+    indicatif::{MultiProgress, ProgressBar},
+    leafs::{db_leafs_page, AssetMaxSeq, ChainLeaf, ChainLeafSorter, SortedChainLeafs},
     log::{debug, error, info},
+    progress::{tree_progress, ProgressLogWriter},
     sea_orm::{
         sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr,
         EntityTrait, FromQueryResult, QueryFilter, QuerySelect, QueryTrait, SqlxPostgresConnector,
-        Statement,
     },
     // plerkle_serialization::serializer::seralize_encoded_transaction_with_status,
     // solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config,
-    solana_client::{
-        nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig,
-        rpc_request::RpcRequest,
-    },
-    solana_sdk::{
-        commitment_config::{CommitmentConfig, CommitmentLevel},
-        pubkey::{ParsePubkeyError, Pubkey},
-        signature::Signature,
-        transaction::VersionedTransaction,
-    },
-    solana_transaction_status::{
-        option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta,
-        UiTransactionEncoding, UiTransactionStatusMeta,
-    },
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature},
     // solana_sdk::signature::Signature,
     // solana_transaction_status::UiTransactionEncoding,
-    spl_account_compression::{
-        state::{
-            merkle_tree_get_size, ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
-        },
-        AccountCompressionEvent, ChangeLogEvent,
+    spl_account_compression::state::{
+        merkle_tree_get_size, ConcurrentMerkleTreeHeader, CONCURRENT_MERKLE_TREE_HEADER_SIZE_V1,
     },
     sqlx::postgres::{PgConnectOptions, PgPoolOptions},
     std::{
         cmp,
-        collections::HashMap,
+        collections::{HashMap, HashSet},
         env,
         num::NonZeroUsize,
+        path::PathBuf,
         pin::Pin,
+        process::ExitCode,
         str::FromStr,
         sync::{
             atomic::{AtomicUsize, Ordering},
             Arc,
         },
     },
+    summary::{CanopyCounts, LeafCounts, Summary, TreeHealth, TreeReport},
     tokio::{
         fs::OpenOptions,
         io::{stdout, AsyncWrite, AsyncWriteExt},
-        sync::{mpsc, Mutex},
+        sync::Mutex,
+        time::Duration,
     },
-    txn_forwarder::{find_signatures, read_lines, rpc_tx_with_retries},
+    txn_forwarder::{encode_payload, read_lines, PayloadCompression},
 };
 
 const RPC_GET_TXN_RETRIES: u8 = 5;
-const RPC_TXN_CONFIG: RpcTransactionConfig = RpcTransactionConfig {
-    encoding: Some(UiTransactionEncoding::Base64),
-    commitment: Some(CommitmentConfig {
-        commitment: CommitmentLevel::Finalized,
-    }),
-    max_supported_transaction_version: Some(0),
-};
 
-#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
-pub enum ParseError {
-    #[error("failed to load Transaction Meta")]
-    TransactionMeta,
-    #[error("failed to decode Transaction")]
-    Transaction,
-    #[error("failed to decode instruction data: {0}")]
-    Instruction(#[from] bs58::decode::Error),
-    #[error("failed to parse pubkey: {0}")]
-    Pubkey(#[from] ParsePubkeyError),
-}
-
-#[derive(Debug, FromQueryResult, Clone)]
+#[derive(Debug, Clone)]
 struct MaxSeqItem {
     max_seq: i64,
     cnt_seq: i64,
@@ -100,20 +87,6 @@ struct MissingSeq {
     missing_seq: i64,
 }
 
-#[derive(Debug, FromQueryResult)]
-struct AssetMaxSeq {
-    leaf_idx: i64,
-    seq: i64,
-}
-
-#[derive(Debug)]
-struct LeafNode {
-    leaf: Vec<u8>,
-    index: i64,
-}
-
-type MaybeLeafNode = Option<LeafNode>;
-
 #[derive(Parser)]
 #[command(next_line_help = true, author, version, about)]
 struct Args {
@@ -121,7 +94,8 @@ struct Args {
     #[arg(long, short, alias = "rpc-url")]
     rpc: String,
 
-    /// Number of concurrent requests for fetching transactions.
+    /// Number of concurrent requests for fetching transactions, lowered automatically while the
+    /// RPC rate limits.
     #[arg(long, short, default_value_t = 25)]
     concurrency: usize,
 
@@ -129,6 +103,16 @@ struct Args {
     #[arg(long, short, default_value_t = 3)]
     max_retries: u8,
 
+    /// Show the transactions read, their rate and an ETA for each tree, for show-tree(s) and
+    /// check-tree(s)-leafs.
+    #[arg(long)]
+    progress: bool,
+
+    /// Write a JSON summary of the checked trees and their health to this file, for
+    /// check-tree(s), check-tree(s)-leafs and check-canop(y|ies).
+    #[arg(long)]
+    summary_file: Option<PathBuf>,
+
     #[command(subcommand)]
     action: Action,
 }
@@ -140,6 +124,8 @@ impl Args {
             | Action::CheckTrees { pg_url, .. }
             | Action::CheckTreeLeafs { pg_url, .. }
             | Action::CheckTreesLeafs { pg_url, .. }
+            | Action::CheckCanopy { pg_url, .. }
+            | Action::CheckCanopies { pg_url, .. }
             | Action::FixTree { pg_url, .. } => {
                 let options: PgConnectOptions = pg_url.parse().unwrap();
 
@@ -202,6 +188,18 @@ enum Action {
         pg_url: String,
         #[arg(short, long)]
         output: Option<String>,
+        #[arg(
+            long,
+            default_value_t = 10_000,
+            help = "Leaf indexes read from the database per page"
+        )]
+        page_size: u64,
+        #[arg(
+            long,
+            default_value_t = 1_000_000,
+            help = "Chain leaf updates kept in memory before spilling a sorted run to disk"
+        )]
+        sort_buffer: usize,
         #[arg(short, long, help = "Tree pubkey")]
         tree: String,
     },
@@ -211,6 +209,32 @@ enum Action {
         pg_url: String,
         #[arg(short, long)]
         output: Option<String>,
+        #[arg(
+            long,
+            default_value_t = 10_000,
+            help = "Leaf indexes read from the database per page"
+        )]
+        page_size: u64,
+        #[arg(
+            long,
+            default_value_t = 1_000_000,
+            help = "Chain leaf updates kept in memory before spilling a sorted run to disk"
+        )]
+        sort_buffer: usize,
+        #[arg(short, long, help = "Path to file with trees pubkeys")]
+        file: String,
+    },
+    /// Checks the indexed canopy of a merkle tree against its indexed nodes, without RPC calls
+    CheckCanopy {
+        #[arg(short, long)]
+        pg_url: String,
+        #[arg(short, long, help = "Tree pubkey")]
+        tree: String,
+    },
+    /// Checks the indexed canopies of merkle trees from a file against their indexed nodes
+    CheckCanopies {
+        #[arg(short, long)]
+        pg_url: String,
         #[arg(short, long, help = "Path to file with trees pubkeys")]
         file: String,
     },
@@ -238,27 +262,39 @@ enum Action {
             help = "Concurrency for fetching signatures for sequence batches"
         )]
         get_sigs_concurrency: Option<usize>,
+        /// Compression of the transactions sent to the stream, the ingester reads either.
+        #[arg(long, value_enum, default_value_t = PayloadCompression::None)]
+        compression: PayloadCompression,
     },
 }
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> anyhow::Result<ExitCode> {
     // RUST_LOG=info,sqlx=warn,tree_status=debug
     env::set_var(
         env_logger::DEFAULT_FILTER_ENV,
         env::var_os(env_logger::DEFAULT_FILTER_ENV).unwrap_or_else(|| "info,sqlx=warn".into()),
     );
-    env_logger::init();
-
     let args = Args::parse();
+    let progress = args.progress.then(MultiProgress::new);
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(progress) = &progress {
+        logger.target(env_logger::Target::Pipe(Box::new(ProgressLogWriter(
+            progress.clone(),
+        ))));
+    }
+    logger.init();
 
     let concurrency = NonZeroUsize::new(args.concurrency)
         .ok_or_else(|| anyhow::anyhow!("invalid concurrency: {}", args.concurrency))?;
+    let throttle = RpcThrottle::new(args.concurrency);
+    throttle.spawn_reporter(Duration::from_secs(10));
 
     // Set up RPC interface
     let pubkeys_str = match &args.action {
         Action::CheckTree { tree, .. }
         | Action::CheckTreeLeafs { tree, .. }
+        | Action::CheckCanopy { tree, .. }
         | Action::FixTree { tree, .. }
         | Action::ShowTree { tree } => {
             let tree = tree.to_string();
@@ -266,6 +302,7 @@ async fn main() -> anyhow::Result<()> {
         }
         Action::CheckTrees { file, .. }
         | Action::CheckTreesLeafs { file, .. }
+        | Action::CheckCanopies { file, .. }
         | Action::ShowTrees { file } => read_lines(file).await?.boxed(),
     };
 
@@ -277,6 +314,7 @@ async fn main() -> anyhow::Result<()> {
         })
     });
 
+    let mut summary = Summary::default();
     match &args.action {
         Action::CheckTree { .. } | Action::CheckTrees { .. } => {
             let client = RpcClient::new(args.rpc.clone());
@@ -284,12 +322,28 @@ async fn main() -> anyhow::Result<()> {
             while let Some(maybe_pubkey) = pubkeys.next().await {
                 let pubkey = maybe_pubkey?;
                 info!("checking tree {pubkey}, hex: {}", hex::encode(pubkey));
-                if let Err(error) = check_tree(pubkey, &client, &conn).await {
-                    error!("{:?}", error);
-                }
+                let report = match check_tree(pubkey, &client, &conn).await {
+                    Ok(report) => report,
+                    Err(error) => {
+                        error!("{:?}", error);
+                        TreeReport::failed(pubkey, &error)
+                    }
+                };
+                summary.push(report);
             }
         }
-        Action::CheckTreeLeafs { output, .. } | Action::CheckTreesLeafs { output, .. } => {
+        Action::CheckTreeLeafs {
+            output,
+            page_size,
+            sort_buffer,
+            ..
+        }
+        | Action::CheckTreesLeafs {
+            output,
+            page_size,
+            sort_buffer,
+            ..
+        } => {
             let conn = args.get_pg_conn().await?;
             let mut output: Option<Pin<Box<dyn AsyncWrite>>> = if let Some(output) = output {
                 Some(if output == "-" {
@@ -310,36 +364,83 @@ async fn main() -> anyhow::Result<()> {
             while let Some(maybe_pubkey) = pubkeys.next().await {
                 let pubkey = maybe_pubkey?;
                 info!("checking tree leafs {pubkey}, hex: {}", hex::encode(pubkey));
-                if let Err(error) = check_tree_leafs(
+                let bar = progress
+                    .as_ref()
+                    .map(|progress| tree_progress(progress, pubkey, &args.rpc, &throttle));
+                let report = match check_tree_leafs(
                     pubkey,
                     &args.rpc,
+                    &throttle,
                     concurrency,
                     args.max_retries,
                     &conn,
+                    *page_size,
+                    *sort_buffer,
                     output.as_mut(),
+                    bar.clone(),
                 )
                 .await
                 {
-                    error!("{:?}", error);
+                    Ok(counts) => TreeReport::with_leafs(pubkey, counts),
+                    Err(error) => {
+                        error!("{:?}", error);
+                        TreeReport::failed(pubkey, &error)
+                    }
+                };
+                summary.push(report);
+                if let Some(bar) = bar {
+                    bar.finish();
                 }
             }
             if let Some(mut output) = output {
                 output.flush().await?;
             }
         }
+        Action::CheckCanopy { .. } | Action::CheckCanopies { .. } => {
+            let conn = args.get_pg_conn().await?;
+            while let Some(maybe_pubkey) = pubkeys.next().await {
+                let pubkey = maybe_pubkey?;
+                info!(
+                    "checking tree canopy {pubkey}, hex: {}",
+                    hex::encode(pubkey)
+                );
+                let report = match check_tree_canopy(pubkey, &conn).await {
+                    Ok(report) => report,
+                    Err(error) => {
+                        error!("{:?}", error);
+                        TreeReport::failed(pubkey, &error)
+                    }
+                };
+                summary.push(report);
+            }
+        }
         Action::ShowTree { .. } | Action::ShowTrees { .. } => {
             while let Some(maybe_pubkey) = pubkeys.next().await {
                 let pubkey = maybe_pubkey?;
                 info!("showing tree {pubkey}, hex: {}", hex::encode(pubkey));
-                if let Err(error) =
-                    read_tree(pubkey, &args.rpc, concurrency, args.max_retries).await
+                let bar = progress
+                    .as_ref()
+                    .map(|progress| tree_progress(progress, pubkey, &args.rpc, &throttle));
+                if let Err(error) = print_tree(
+                    pubkey,
+                    &args.rpc,
+                    &throttle,
+                    concurrency,
+                    args.max_retries,
+                    bar.clone(),
+                )
+                .await
                 {
                     error!("{:?}", error);
                 }
+                if let Some(bar) = bar {
+                    bar.finish();
+                }
             }
         }
         Action::FixTree {
             get_sigs_concurrency,
+            compression,
             pg_url: _,
             redis_url: _,
             tree: _,
@@ -353,10 +454,12 @@ async fn main() -> anyhow::Result<()> {
                 if let Err(error) = fix_tree(
                     pubkey,
                     client,
+                    throttle.clone(),
                     conn,
                     messenger_config,
                     Some(args.concurrency),
                     get_sigs_concurrency.to_owned(),
+                    *compression,
                 )
                 .await
                 {
@@ -366,31 +469,50 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    Ok(())
+    if let Some(path) = &args.summary_file {
+        summary
+            .write(path)
+            .await
+            .with_context(|| format!("failed to write summary to {}", path.display()))?;
+    }
+    Ok(summary.exit_code())
 }
 
 async fn check_tree(
     pubkey: Pubkey,
     client: &RpcClient,
     conn: &DatabaseConnection,
-) -> anyhow::Result<()> {
-    let onchain_seq: i64 = get_onchain_tree_seq(pubkey, client)
+) -> anyhow::Result<TreeReport> {
+    let onchain_seq = get_onchain_tree_seq(pubkey, client)
         .await
-        .with_context(|| format!("[{pubkey}] tree is missing from chain or error occured"))?
-        .try_into()
-        .unwrap();
+        .with_context(|| format!("[{pubkey}] tree is missing from chain or error occured"))?;
+    let onchain_seq = to_i64(onchain_seq, "seq")?;
 
-    let indexed_seq = get_tree_max_seq(pubkey, conn)
+    let MaxSeqItem { max_seq, cnt_seq } = match get_tree_max_seq(pubkey, conn)
         .await
         .with_context(|| format!("[{pubkey:?}] counldn't query tree from index"))?
-        .ok_or_else(|| anyhow::anyhow!("[{pubkey}] tree missing from index"))?;
+    {
+        Some(indexed_seq) => indexed_seq,
+        None => {
+            error!("[{pubkey}] tree missing from index");
+            return Ok(TreeReport {
+                onchain_seq: Some(onchain_seq),
+                ..TreeReport::new(pubkey, TreeHealth::MissingFromIndex)
+            });
+        }
+    };
+    let mut report = TreeReport {
+        onchain_seq: Some(onchain_seq),
+        indexed_seq: Some(max_seq),
+        ..TreeReport::new(pubkey, TreeHealth::Healthy)
+    };
 
     // Check tip
-    match indexed_seq.max_seq.cmp(&onchain_seq) {
+    match max_seq.cmp(&onchain_seq) {
         cmp::Ordering::Less => {
             warn!(
                 "[{pubkey}] Tree not fully indexed. On-chain seq: {}. Indexed seq: {}",
-                onchain_seq, indexed_seq.max_seq
+                onchain_seq, max_seq
             );
         }
         cmp::Ordering::Equal => {
@@ -399,41 +521,86 @@ async fn check_tree(
         cmp::Ordering::Greater => {
             error!(
                 "[{pubkey}] Something went wrong. Indexer is ahead of the chain? On-chain seq: {}. Indexed seq: {}",
-                onchain_seq, indexed_seq.max_seq
+                onchain_seq, max_seq
             );
+            report.health = TreeHealth::Error;
+            report.error = Some("indexed seq is ahead of the chain".to_string());
         }
     }
 
     // Check completeness
-    if indexed_seq.max_seq != indexed_seq.cnt_seq {
+    if max_seq != cnt_seq {
         warn!(
             "[{pubkey}] Tree has gaps. Max indexed seq: {}. Distinct seqs: {}",
-            indexed_seq.max_seq, indexed_seq.cnt_seq
+            max_seq, cnt_seq
         );
         let missing_seqs = get_missing_seq(pubkey, onchain_seq, conn).await?;
-        warn!(
-            "[{pubkey}] missing seq ranges: {:?}",
-            build_seq_ranges(missing_seqs)
-        );
+        let ranges = build_seq_ranges(missing_seqs);
+        warn!("[{pubkey}] missing seq ranges: {:?}", ranges);
+        // A tree indexed ahead of the chain stays an error, its gaps are only listed.
+        if report.health == TreeHealth::Healthy {
+            report.health = TreeHealth::Gaps;
+        }
+        report.missing_seq_ranges = ranges;
     } else {
         info!("[{:?}] Tree has no gaps!", pubkey)
     }
-    Ok(())
+    Ok(report)
+}
+
+async fn check_tree_canopy(
+    pubkey: Pubkey,
+    conn: &DatabaseConnection,
+) -> anyhow::Result<TreeReport> {
+    let check = match check_canopy(conn, pubkey.to_bytes().to_vec())
+        .await
+        .with_context(|| format!("[{pubkey}] couldn't query canopy from index"))?
+    {
+        Some(check) => check,
+        None => {
+            error!("[{pubkey}] canopy missing from index");
+            return Ok(TreeReport::new(pubkey, TreeHealth::MissingFromIndex));
+        }
+    };
+    // The canopy is from the last account update the ingester read, the nodes from the last
+    // change log. They may differ while the tree is being written to.
+    if check.mismatched.is_empty() {
+        info!(
+            "[{pubkey}] Canopy of depth {} matches the indexed nodes as of slot {}",
+            check.canopy_depth, check.slot_updated
+        );
+    } else {
+        warn!(
+            "[{pubkey}] Canopy as of slot {} differs from the indexed nodes at node indexes {:?}",
+            check.slot_updated, check.mismatched
+        );
+    }
+    Ok(TreeReport::with_canopy(
+        pubkey,
+        CanopyCounts {
+            depth: check.canopy_depth,
+            slot_updated: check.slot_updated,
+            nodes: check.nodes,
+            mismatched: check.mismatched.len(),
+        },
+    ))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn fix_tree(
     pubkey: Pubkey,
     client: RpcClient,
+    throttle: RpcThrottle,
     conn: DatabaseConnection,
     messenger_config: MessengerConfig,
     get_txn_concurrency: Option<usize>,
     get_sigs_concurrency: Option<usize>,
+    compression: PayloadCompression,
 ) -> anyhow::Result<()> {
-    let onchain_seq: i64 = get_onchain_tree_seq(pubkey, &client)
+    let onchain_seq = get_onchain_tree_seq(pubkey, &client)
         .await
-        .with_context(|| format!("[{pubkey}] tree is missing from chain or error occured"))?
-        .try_into()
-        .unwrap();
+        .with_context(|| format!("[{pubkey}] tree is missing from chain or error occured"))?;
+    let onchain_seq = to_i64(onchain_seq, "seq")?;
 
     let indexed_seq = get_tree_max_seq(pubkey, &conn)
         .await
@@ -470,10 +637,12 @@ async fn fix_tree(
             pubkey,
             missing_seqs,
             client,
+            throttle,
             conn,
             messenger_config,
             get_txn_concurrency,
             get_sigs_concurrency,
+            compression,
         )
         .await?;
     } else {
@@ -485,14 +654,17 @@ async fn fix_tree(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn find_and_forward_txns_for_missing_seqs(
     tree: Pubkey,
     seqs: Vec<i64>,
     client: RpcClient,
+    throttle: RpcThrottle,
     conn: DatabaseConnection,
     messenger_config: MessengerConfig,
     get_txn_concurrency: Option<usize>,
     get_sigs_concurrency: Option<usize>,
+    compression: PayloadCompression,
 ) -> anyhow::Result<()> {
     // Concurrency config
     let get_txn_concurrency: usize = get_txn_concurrency.unwrap_or(20);
@@ -504,6 +676,13 @@ async fn find_and_forward_txns_for_missing_seqs(
     let client = Arc::new(client);
     let conn = Arc::new(conn);
     let messenger = init_redis_messenger(messenger_config).await?;
+    let missing = seqs
+        .iter()
+        .map(|seq| to_u64(*seq, "seq"))
+        .collect::<Result<HashSet<u64>, _>>()?;
+    let forwarded = AtomicUsize::new(0);
+    let skipped = AtomicUsize::new(0);
+    let lookup_tables = LookupTableCache::new();
 
     crossbeam::scope(|s| {
         let runtime = Arc::new(
@@ -526,6 +705,7 @@ async fn find_and_forward_txns_for_missing_seqs(
         for _ in 0..get_sigs_concurrency {
             let (s_sender, r_recv) = (s_sender.clone(), r_recv.clone());
             let client = client.clone();
+            let throttle = throttle.clone();
             let conn = conn.clone();
             let runtime = runtime.clone();
             // Spawn workers in separate threads
@@ -533,7 +713,7 @@ async fn find_and_forward_txns_for_missing_seqs(
                 for range in r_recv.iter() {
                     info!("Processing seq range: {:?}", range);
                     match runtime.block_on(find_signatures_for_missing_seq_range(
-                        tree, range, &client, &conn, &s_sender,
+                        tree, range, &client, &throttle, &conn, &s_sender,
                     )) {
                         Ok(_) => {}
                         Err(err) => {
@@ -548,20 +728,42 @@ async fn find_and_forward_txns_for_missing_seqs(
         for _ in 0..get_txn_concurrency {
             let s_recv = s_recv.clone();
             let client = client.clone();
+            let throttle = throttle.clone();
             let messenger = messenger.clone();
             let runtime = runtime.clone();
+            let (missing, forwarded, skipped) = (&missing, &forwarded, &skipped);
+            let lookup_tables = &lookup_tables;
             s.spawn(move |_| {
                 for sig in s_recv.iter() {
                     trace!("Attempting to send signature to redis: {:?}", sig);
-                    runtime
-                        .block_on(send_txn(sig, &client, &messenger))
+                    let sent = runtime
+                        .block_on(send_txn(
+                            sig,
+                            tree,
+                            missing,
+                            &client,
+                            &throttle,
+                            lookup_tables,
+                            &messenger,
+                            compression,
+                        ))
                         .unwrap();
+                    if sent {
+                        forwarded.fetch_add(1, Ordering::Relaxed);
+                    } else {
+                        skipped.fetch_add(1, Ordering::Relaxed);
+                    }
                 }
             });
         }
     })
     .unwrap();
 
+    info!(
+        "[{tree}] forwarded {} transactions with missing seqs, skipped {}",
+        forwarded.load(Ordering::Relaxed),
+        skipped.load(Ordering::Relaxed)
+    );
     anyhow::Ok(())
 }
 
@@ -576,37 +778,55 @@ async fn init_redis_messenger(
     anyhow::Ok(Arc::new(Mutex::new(messenger)))
 }
 
+// Forwards the transaction only when it carries one of the `missing` seqs of `tree`, as most
+// transactions in a gap's signature window were indexed already. Returns whether it was sent.
 async fn send_txn(
     signature: Signature,
+    tree: Pubkey,
+    missing: &HashSet<u64>,
     client: &RpcClient,
+    throttle: &RpcThrottle,
+    lookup_tables: &LookupTableCache,
     messenger: &Mutex<Box<dyn plerkle_messenger::Messenger>>,
-) -> anyhow::Result<()> {
-    let txn: EncodedConfirmedTransactionWithStatusMeta = rpc_tx_with_retries(
-        &client,
-        RpcRequest::GetTransaction,
-        serde_json::json!([signature.to_string(), RPC_TXN_CONFIG,]),
-        RPC_GET_TXN_RETRIES,
-        signature,
-    )
-    .await?;
+    compression: PayloadCompression,
+) -> anyhow::Result<bool> {
+    let mut txn = fetch_transaction(client, throttle, signature, RPC_GET_TXN_RETRIES).await?;
+    // Filled in for the seqs parsed here and for the ingester parsing the forwarded transaction.
+    lookup_tables
+        .fill_loaded_addresses(client, throttle, &mut txn.transaction)
+        .await
+        .with_context(|| format!("failed to resolve lookup tables of {}", signature))?;
 
     // Ignore if tx failed or meta is missed
     let meta = txn.transaction.meta.as_ref();
     if meta.map(|meta| meta.status.is_err()).unwrap_or(true) {
         info!("Dropping failed transaction: {:?}", signature);
-        return Ok(());
+        return Ok(false);
+    }
+
+    let seqs = parse_tx_sequence(txn.clone())
+        .with_context(|| format!("failed to parse seqs from {}", signature))?
+        .remove(&tree)
+        .unwrap_or_default();
+    if !seqs.iter().any(|update| missing.contains(&update.seq)) {
+        trace!(
+            "Skipping transaction without missing seqs: {:?}, seqs: {:?}",
+            signature,
+            seqs.iter().map(|update| update.seq).collect::<Vec<_>>()
+        );
+        return Ok(false);
     }
 
     let fbb = flatbuffers::FlatBufferBuilder::new();
     let fbb = seralize_encoded_transaction_with_status(fbb, txn)
         .with_context(|| format!("failed to serialize transaction with {}", signature))?;
-    let bytes = fbb.finished_data();
+    let bytes = encode_payload(fbb.finished_data(), compression)?;
 
     let mut locked = messenger.lock().await;
-    locked.send(TRANSACTION_STREAM, bytes).await?;
+    locked.send(TRANSACTION_STREAM, &bytes).await?;
     drop(locked);
     info!("Successfully pushed transaction to redis: {:?}", signature);
-    Ok(())
+    Ok(true)
 }
 
 fn build_seq_ranges(seqs: Vec<i64>) -> Vec<(i64, i64)> {
@@ -647,14 +867,14 @@ fn build_seq_ranges(seqs: Vec<i64>) -> Vec<(i64, i64)> {
     joined_ranges
 }
 
-// TODO: Txns submitted not be the right ones! We need a more complex search algo.
-// Add the following:
-//   1 – Keep searching until finding a successful transaction.
-//   2 – Parse txns and extract seq, keep searching until the seq is found (can use Helius for this).
+// Sends every signature between the indexed txns around the range, `send_txn` then drops the
+// ones that don't carry a missing seq.
+// TODO: Keep searching past the window when its bounding txns failed.
 async fn find_signatures_for_missing_seq_range(
     tree: Pubkey,
     range: (i64, i64),
     client: &RpcClient,
+    throttle: &RpcThrottle,
     conn: &DatabaseConnection,
     sender: &Sender<Signature>,
 ) -> anyhow::Result<()> {
@@ -702,14 +922,16 @@ async fn find_signatures_for_missing_seq_range(
         .flatten();
     let limit: usize = 1000;
     loop {
-        let config = GetConfirmedSignaturesForAddress2Config {
-            before: before,
-            until: until,
-            limit: Some(limit),
-            ..Default::default()
-        };
-        let sigs = client
-            .get_signatures_for_address_with_config(&tree, config)
+        let sigs = throttle
+            .run(|| {
+                let config = GetConfirmedSignaturesForAddress2Config {
+                    before: before,
+                    until: until,
+                    limit: Some(limit),
+                    ..Default::default()
+                };
+                client.get_signatures_for_address_with_config(&tree, config)
+            })
             .await?;
         for sig in sigs.clone() {
             let o = Signature::from_str(&sig.signature)?;
@@ -759,7 +981,20 @@ async fn get_tree_max_seq(
         .column_as(Expr::cust("count(distinct seq)"), "cnt_seq")
         .build(DbBackend::Postgres);
 
-    MaxSeqItem::find_by_statement(query).one(conn).await
+    // The aggregate returns a row with a NULL max for a tree without any `cl_audits` rows, which
+    // is reported as missing from the index.
+    #[derive(FromQueryResult)]
+    struct MaxSeqRow {
+        max_seq: Option<i64>,
+        cnt_seq: i64,
+    }
+    let row = MaxSeqRow::find_by_statement(query).one(conn).await?;
+    Ok(row.and_then(|row| {
+        Some(MaxSeqItem {
+            max_seq: row.max_seq?,
+            cnt_seq: row.cnt_seq,
+        })
+    }))
 }
 
 // TODO: Break checks into batches for larger trees.
@@ -768,22 +1003,20 @@ async fn get_missing_seq(
     max_seq: i64,
     conn: &DatabaseConnection,
 ) -> Result<Vec<i64>, DbErr> {
-    let query = Statement::from_string(
-        DbBackend::Postgres,
-        format!(
-            "
+    let query = RawQuery::new(
+        "
 SELECT
     s.seq AS missing_seq
 FROM
-    generate_series(1::bigint, {}::bigint) s(seq)
+    generate_series(1::bigint, $1::bigint) s(seq)
 WHERE
     NOT EXISTS (
-        SELECT 1 FROM cl_audits WHERE seq = s.seq AND tree='\\x{}'
+        SELECT 1 FROM cl_audits WHERE seq = s.seq AND tree = $2
     )",
-            max_seq,
-            hex::encode(tree.as_ref())
-        ),
-    );
+    )
+    .bind(max_seq)
+    .bind(tree.as_ref().to_vec())
+    .build();
 
     let res: Vec<MissingSeq> = conn
         .query_all(query)
@@ -794,276 +1027,178 @@ WHERE
     Ok(res.iter().map(|m| m.missing_seq).collect::<Vec<i64>>())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn check_tree_leafs(
     pubkey: Pubkey,
     client_url: &str,
+    throttle: &RpcThrottle,
     concurrency: NonZeroUsize,
     max_retries: u8,
     conn: &DatabaseConnection,
+    page_size: u64,
+    sort_buffer: usize,
     mut output: Option<&mut Pin<Box<dyn AsyncWrite>>>,
-) -> anyhow::Result<()> {
-    let (fetch_fut, mut leafs_rx) = read_tree_start(pubkey, client_url, concurrency, max_retries);
-    try_join(fetch_fut, async move {
-        // collect max seq per leaf index from transactions
-        let mut leafs = HashMap::new();
-        while let Some((_id, signature, vec)) = leafs_rx.recv().await {
-            for (seq, maybe_leaf) in vec.unwrap_or_default() {
-                if let Some(LeafNode {
-                    index: leaf_idx,
-                    leaf: _leaf,
-                }) = maybe_leaf
-                {
-                    let entry = leafs.entry(leaf_idx).or_insert((signature, seq));
-                    if entry.1 < seq {
-                        *entry = (signature, seq);
-                    }
-                }
+    progress: Option<ProgressBar>,
+) -> anyhow::Result<LeafCounts> {
+    // collect max seq per leaf index from transactions, sorted by leaf index
+    let mut sorter = ChainLeafSorter::new(&pubkey, sort_buffer);
+    let mut transactions = read_tree(pubkey, client_url, throttle, concurrency, max_retries);
+    while let Some(transaction) = transactions.next().await {
+        let TreeTransaction {
+            signature, updates, ..
+        } = transaction?;
+        if let Some(progress) = &progress {
+            progress.inc(1);
+        }
+        for SeqUpdate { seq, leaf } in updates {
+            if let Some(LeafNode {
+                index: leaf_idx, ..
+            }) = leaf
+            {
+                sorter.push(ChainLeaf {
+                    leaf_idx,
+                    seq,
+                    signature,
+                })?;
             }
         }
+    }
+    let mut chain = sorter.finish()?;
+    let mut chain_leaf = chain.next()?;
+
+    // merge-join with the database in leaf index order, fetching the next page while the
+    // current one is compared
+    let page_size = page_size.max(1);
+    debug!("send query to database...");
+    let mut page = db_leafs_page(conn, &pubkey, -1, page_size).await?;
+    let mut counts = LeafCounts::default();
+    while !page.is_empty() {
+        let next_page = async {
+            match page.last() {
+                Some(last) if page.len() as u64 == page_size => {
+                    db_leafs_page(conn, &pubkey, last.leaf_idx, page_size).await
+                }
+                _ => Ok(Vec::new()),
+            }
+        };
+        let compare = compare_leafs_page(
+            &page,
+            &mut chain,
+            &mut chain_leaf,
+            &mut counts,
+            output.as_deref_mut(),
+        );
+        let (next_page, _) = try_join(next_page, compare).await?;
+        page = next_page;
+    }
+    while let Some(leaf) = chain_leaf {
+        report_missing_in_db(leaf, &mut counts, output.as_deref_mut()).await?;
+        chain_leaf = chain.next()?;
+    }
 
-        info!("Found {:?} leaves", leafs.len());
+    info!(
+        "Found {} leaves, {} in db, {} mismatched seq, {} not found in blockchain, {} not found in db",
+        counts.chain, counts.db, counts.mismatched, counts.missing_in_chain, counts.missing_in_db
+    );
 
-        // fetch from database in chunks
-        let query = Statement::from_sql_and_values(
-            DbBackend::Postgres,
-            "
-SELECT
-    cl_items.leaf_idx, MAX(asset.seq) AS seq
-FROM
-    asset
-INNER JOIN
-    cl_items ON
-        cl_items.tree = asset.tree_id AND
-        cl_items.seq = asset.seq
-WHERE
-    asset.tree_id = $1 AND
-    cl_items.leaf_idx IS NOT NULL
-GROUP BY
-    cl_items.leaf_idx
-",
-            [Value::Bytes(Some(Box::new(pubkey.as_ref().to_vec())))],
-        );
+    Ok(counts)
+}
 
-        debug!("send query to database...");
-        let leafs_db = conn.query_all(query).await?;
-
-        for leaf_db in leafs_db.iter() {
-            let leaf_db = AssetMaxSeq::from_query_result(leaf_db, "").unwrap();
-            match leafs.remove(&leaf_db.leaf_idx) {
-                Some((signature, seq)) => {
-                    if leaf_db.seq != seq as i64 {
-                        error!(
-                            "leaf index {}: invalid seq {} vs {} (db vs blockchain, tx={:?})",
-                            leaf_db.leaf_idx, leaf_db.seq, seq, signature
-                        );
-                    }
-                }
-                None => {
-                    error!("leaf index {}: not found in blockchain", leaf_db.leaf_idx);
+// Compares a page of database leaves with the chain leaves up to the last index in the page.
+async fn compare_leafs_page(
+    page: &[AssetMaxSeq],
+    chain: &mut SortedChainLeafs,
+    chain_leaf: &mut Option<ChainLeaf>,
+    counts: &mut LeafCounts,
+    mut output: Option<&mut Pin<Box<dyn AsyncWrite>>>,
+) -> anyhow::Result<()> {
+    for leaf_db in page.iter() {
+        counts.db += 1;
+        while let Some(leaf) = chain_leaf.filter(|leaf| leaf.leaf_idx < leaf_db.leaf_idx) {
+            report_missing_in_db(leaf, counts, output.as_deref_mut()).await?;
+            *chain_leaf = chain.next()?;
+        }
+        match *chain_leaf {
+            Some(leaf) if leaf.leaf_idx == leaf_db.leaf_idx => {
+                counts.chain += 1;
+                if to_u64(leaf_db.seq, "seq").ok() != Some(leaf.seq) {
+                    counts.mismatched += 1;
+                    error!(
+                        "leaf index {}: invalid seq {} vs {} (db vs blockchain, tx={:?})",
+                        leaf_db.leaf_idx, leaf_db.seq, leaf.seq, leaf.signature
+                    );
                 }
+                *chain_leaf = chain.next()?;
             }
-        }
-        for (leaf_idx, (signature, seq)) in leafs.into_iter() {
-            error!("leaf index {leaf_idx}: not found in db, seq {seq} tx={signature:?}");
-            if let Some(output) = output.as_mut() {
-                let _ = output.write(format!("{signature}\n").as_bytes()).await?;
+            _ => {
+                counts.missing_in_chain += 1;
+                error!("leaf index {}: not found in blockchain", leaf_db.leaf_idx);
             }
         }
+    }
+    Ok(())
+}
 
-        Ok(())
-    })
-    .await
-    .map(|_| ())
+async fn report_missing_in_db(
+    leaf: ChainLeaf,
+    counts: &mut LeafCounts,
+    output: Option<&mut Pin<Box<dyn AsyncWrite>>>,
+) -> anyhow::Result<()> {
+    let ChainLeaf {
+        leaf_idx,
+        seq,
+        signature,
+    } = leaf;
+    counts.chain += 1;
+    counts.missing_in_db += 1;
+    error!("leaf index {leaf_idx}: not found in db, seq {seq} tx={signature:?}");
+    if let Some(output) = output {
+        let _ = output.write(format!("{signature}\n").as_bytes()).await?;
+    }
+    Ok(())
 }
 
-// Fetches all the transactions referencing a specific trees
-async fn read_tree(
+// Prints the updates of all the transactions of a tree, in signature order
+async fn print_tree(
     pubkey: Pubkey,
     client_url: &str,
+    throttle: &RpcThrottle,
     concurrency: NonZeroUsize,
     max_retries: u8,
+    progress: Option<ProgressBar>,
 ) -> anyhow::Result<()> {
-    fn print_seqs(id: usize, sig: Signature, seqs: Option<Vec<(u64, MaybeLeafNode)>>) {
-        for (seq, leaf_idx) in seqs.unwrap_or_default() {
-            let leaf_idx = leaf_idx.map(|v| v.index.to_string()).unwrap_or_default();
+    fn print_seqs(id: usize, sig: Signature, seqs: Vec<SeqUpdate>) {
+        for SeqUpdate { seq, leaf } in seqs {
+            let leaf_idx = leaf.map(|v| v.index.to_string()).unwrap_or_default();
             info!("{seq} {leaf_idx} {sig} {id}");
         }
     }
 
-    let (fetch_fut, mut print_rx) = read_tree_start(pubkey, client_url, concurrency, max_retries);
-    try_join(fetch_fut, async move {
-        let mut next_id = 0;
-        let mut map = HashMap::new();
-
-        while let Some((id, sig, seqs)) = print_rx.recv().await {
-            map.insert(id, (sig, seqs));
-
-            if let Some((sig, seqs)) = map.remove(&next_id) {
-                print_seqs(next_id, sig, seqs);
-                next_id += 1;
-            }
+    let mut transactions = read_tree(pubkey, client_url, throttle, concurrency, max_retries);
+    let mut next_id = 0;
+    let mut map = HashMap::new();
+    while let Some(transaction) = transactions.next().await {
+        let TreeTransaction {
+            id,
+            signature,
+            updates,
+        } = transaction?;
+        if let Some(progress) = &progress {
+            progress.inc(1);
         }
+        map.insert(id, (signature, updates));
 
-        let mut vec = map.into_iter().collect::<Vec<_>>();
-        vec.sort_by_key(|(id, _)| *id);
-        for (id, (sig, seqs)) in vec.into_iter() {
-            print_seqs(id, sig, seqs);
+        while let Some((sig, seqs)) = map.remove(&next_id) {
+            print_seqs(next_id, sig, seqs);
+            next_id += 1;
         }
-
-        Ok(())
-    })
-    .await
-    .map(|_| ())
-}
-
-#[allow(clippy::type_complexity)]
-fn read_tree_start(
-    pubkey: Pubkey,
-    client_url: &str,
-    concurrency: NonZeroUsize,
-    max_retries: u8,
-) -> (
-    BoxFuture<'static, anyhow::Result<()>>,
-    mpsc::UnboundedReceiver<(usize, Signature, Option<Vec<(u64, MaybeLeafNode)>>)>,
-) {
-    let sig_id = Arc::new(AtomicUsize::new(0));
-    let rx_sig = Arc::new(Mutex::new(find_signatures(
-        pubkey,
-        RpcClient::new(client_url.to_owned()),
-        None,
-        None,
-        2_000,
-        false,
-    )));
-
-    let (tx, rx) = mpsc::unbounded_channel();
-    let tx = Arc::new(tx);
-
-    let fetch_futs = (0..concurrency.get())
-        .map(|_| {
-            let sig_id = Arc::clone(&sig_id);
-            let rx_sig = Arc::clone(&rx_sig);
-            let client = RpcClient::new(client_url.to_owned());
-            let tx = Arc::clone(&tx);
-            async move {
-                loop {
-                    let mut lock = rx_sig.lock().await;
-                    let maybe_msg = lock.recv().await;
-                    let id = sig_id.fetch_add(1, Ordering::SeqCst);
-                    if id > 0 && id % 10 == 0 {
-                        debug!("received {} transactions", id);
-                    }
-                    drop(lock);
-                    match maybe_msg {
-                        Some(maybe_sig) => {
-                            let signature = maybe_sig?;
-                            let mut map = process_tx(signature, &client, max_retries).await?;
-                            let _ = tx.send((id, signature, map.remove(&pubkey)));
-                        }
-                        None => return Ok::<(), anyhow::Error>(()),
-                    }
-                }
-            }
-        })
-        .collect::<Vec<_>>();
-    drop(tx);
-
-    (try_join_all(fetch_futs).map_ok(|_| ()).boxed(), rx)
-}
-
-// Process and individual transaction, fetching it and reading out the sequence numbers
-async fn process_tx(
-    signature: Signature,
-    client: &RpcClient,
-    max_retries: u8,
-) -> anyhow::Result<HashMap<Pubkey, Vec<(u64, MaybeLeafNode)>>> {
-    const CONFIG: RpcTransactionConfig = RpcTransactionConfig {
-        encoding: Some(UiTransactionEncoding::Base64),
-        commitment: Some(CommitmentConfig {
-            commitment: CommitmentLevel::Finalized,
-        }),
-        max_supported_transaction_version: Some(0),
-    };
-
-    let tx: EncodedConfirmedTransactionWithStatusMeta = rpc_tx_with_retries(
-        client,
-        RpcRequest::GetTransaction,
-        serde_json::json!([signature.to_string(), CONFIG]),
-        max_retries,
-        signature,
-    )
-    .await?;
-    parse_tx_sequence(tx).map_err(Into::into)
-}
-
-// Parse the trasnaction data
-fn parse_tx_sequence(
-    tx: EncodedConfirmedTransactionWithStatusMeta,
-) -> Result<HashMap<Pubkey, Vec<(u64, MaybeLeafNode)>>, ParseError> {
-    let mut seq_updates = HashMap::<Pubkey, Vec<(u64, MaybeLeafNode)>>::new();
-
-    // ignore if tx failed or meta is missed
-    let meta = tx.transaction.meta.as_ref();
-    if meta.map(|meta| meta.status.is_err()).unwrap_or(true) {
-        return Ok(seq_updates);
     }
 
-    // Get `UiTransaction` out of `EncodedTransactionWithStatusMeta`.
-    let meta: UiTransactionStatusMeta = tx.transaction.meta.ok_or(ParseError::TransactionMeta)?;
-
-    // See https://github.com/ngundotra/spl-ac-seq-parse/blob/main/src/main.rs
-    if let OptionSerializer::Some(inner_instructions_vec) = meta.inner_instructions.as_ref() {
-        let transaction: VersionedTransaction = tx
-            .transaction
-            .transaction
-            .decode()
-            .ok_or(ParseError::Transaction)?;
-
-        // Add the account lookup stuff
-        let mut account_keys = transaction.message.static_account_keys().to_vec();
-        if let OptionSerializer::Some(loaded_addresses) = meta.loaded_addresses {
-            for pubkey in loaded_addresses.writable.iter() {
-                account_keys.push(Pubkey::from_str(pubkey)?);
-            }
-            for pubkey in loaded_addresses.readonly.iter() {
-                account_keys.push(Pubkey::from_str(pubkey)?);
-            }
-        }
-
-        for inner_ixs in inner_instructions_vec.iter() {
-            for inner_ix in inner_ixs.instructions.iter() {
-                if let solana_transaction_status::UiInstruction::Compiled(instr) = inner_ix {
-                    if let Some(program) = account_keys.get(instr.program_id_index as usize) {
-                        if *program == spl_noop::id() {
-                            let data = bs58::decode(&instr.data)
-                                .into_vec()
-                                .map_err(ParseError::Instruction)?;
-
-                            if let Ok(AccountCompressionEvent::ChangeLog(cl_data)) =
-                                AccountCompressionEvent::try_from_slice(&data)
-                            {
-                                let ChangeLogEvent::V1(cl_data) = cl_data;
-                                let leaf = cl_data.path.get(0).map(|node| LeafNode {
-                                    leaf: node.node.to_vec(),
-                                    index: node_idx_to_leaf_idx(
-                                        node.index as i64,
-                                        cl_data.path.len() as u32 - 1,
-                                    ),
-                                });
-                                seq_updates
-                                    .entry(cl_data.id)
-                                    .or_default()
-                                    .push((cl_data.seq, leaf));
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    let mut vec = map.into_iter().collect::<Vec<_>>();
+    vec.sort_by_key(|(id, _)| *id);
+    for (id, (sig, seqs)) in vec.into_iter() {
+        print_seqs(id, sig, seqs);
     }
-    Ok(seq_updates)
-}
 
-fn node_idx_to_leaf_idx(index: i64, tree_height: u32) -> i64 {
-    index - 2i64.pow(tree_height)
+    Ok(())
 }
@@ -0,0 +1,59 @@
+use {
+    das_tree_reader::{find_signatures, RpcThrottle},
+    indicatif::{MultiProgress, ProgressBar, ProgressStyle},
+    solana_client::nonblocking::rpc_client::RpcClient,
+    solana_sdk::pubkey::Pubkey,
+    std::io::{self, Write},
+};
+
+const TEMPLATE: &str =
+    "{prefix} [{elapsed_precise}] {wide_bar} {pos}/{len} signatures ({per_sec}, ETA {eta})";
+
+/// Writes log records above the progress bars, so the workers logging concurrently don't tear
+/// the bars.
+pub struct ProgressLogWriter(pub MultiProgress);
+
+impl Write for ProgressLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.suspend(|| io::stderr().write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.suspend(|| io::stderr().flush())
+    }
+}
+
+/// Adds a bar for the transactions of `tree` read so far. Its length is the number of the tree's
+/// signatures discovered so far, counted by a second signature scan that runs ahead of the
+/// transaction fetches, so the ETA settles once the scan is through the tree's history.
+pub fn tree_progress(
+    progress: &MultiProgress,
+    tree: Pubkey,
+    client_url: &str,
+    throttle: &RpcThrottle,
+) -> ProgressBar {
+    let bar = progress.add(ProgressBar::new(0));
+    bar.set_style(
+        ProgressStyle::with_template(TEMPLATE)
+            .expect("valid progress template")
+            .progress_chars("=> "),
+    );
+    bar.set_prefix(tree.to_string());
+
+    let mut signatures = find_signatures(
+        tree,
+        RpcClient::new(client_url.to_owned()),
+        throttle.clone(),
+        None,
+        None,
+        2_000,
+        false,
+    );
+    let counter = bar.clone();
+    tokio::spawn(async move {
+        while let Some(Ok(_)) = signatures.recv().await {
+            counter.inc_length(1);
+        }
+    });
+    bar
+}
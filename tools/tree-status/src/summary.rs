@@ -0,0 +1,161 @@
+use {
+    serde::Serialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{path::Path, process::ExitCode},
+};
+
+/// How a check found a tree, in the order of the exit code they lead to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeHealth {
+    Healthy,
+    /// The check failed before it could tell, e.g. on an RPC error.
+    Error,
+    /// Seqs or leaves are missing or differ from the chain's.
+    Gaps,
+    MissingFromIndex,
+}
+
+impl TreeHealth {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            TreeHealth::Healthy => 0,
+            TreeHealth::Error => 1,
+            TreeHealth::Gaps => 2,
+            TreeHealth::MissingFromIndex => 3,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LeafCounts {
+    pub chain: usize,
+    pub db: usize,
+    pub mismatched: usize,
+    pub missing_in_chain: usize,
+    pub missing_in_db: usize,
+}
+
+impl LeafCounts {
+    pub fn health(&self) -> TreeHealth {
+        if self.db == 0 && self.chain > 0 {
+            TreeHealth::MissingFromIndex
+        } else if self.mismatched + self.missing_in_chain + self.missing_in_db > 0 {
+            TreeHealth::Gaps
+        } else {
+            TreeHealth::Healthy
+        }
+    }
+}
+
+/// The indexed canopy of a tree against its indexed nodes.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CanopyCounts {
+    pub depth: u32,
+    pub slot_updated: i64,
+    pub nodes: usize,
+    pub mismatched: usize,
+}
+
+impl CanopyCounts {
+    pub fn health(&self) -> TreeHealth {
+        if self.mismatched > 0 {
+            TreeHealth::Gaps
+        } else {
+            TreeHealth::Healthy
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeReport {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub tree: Pubkey,
+    pub health: TreeHealth,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_seq: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indexed_seq: Option<i64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_seq_ranges: Vec<(i64, i64)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leafs: Option<LeafCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canopy: Option<CanopyCounts>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TreeReport {
+    pub fn new(tree: Pubkey, health: TreeHealth) -> Self {
+        TreeReport {
+            tree,
+            health,
+            onchain_seq: None,
+            indexed_seq: None,
+            missing_seq_ranges: Vec::new(),
+            leafs: None,
+            canopy: None,
+            error: None,
+        }
+    }
+
+    pub fn failed(tree: Pubkey, error: &anyhow::Error) -> Self {
+        TreeReport {
+            error: Some(format!("{:#}", error)),
+            ..TreeReport::new(tree, TreeHealth::Error)
+        }
+    }
+
+    pub fn with_leafs(tree: Pubkey, leafs: LeafCounts) -> Self {
+        TreeReport {
+            leafs: Some(leafs.clone()),
+            ..TreeReport::new(tree, leafs.health())
+        }
+    }
+
+    pub fn with_canopy(tree: Pubkey, canopy: CanopyCounts) -> Self {
+        TreeReport {
+            canopy: Some(canopy.clone()),
+            ..TreeReport::new(tree, canopy.health())
+        }
+    }
+}
+
+fn serialize_pubkey<S: serde::Serializer>(pubkey: &Pubkey, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&pubkey.to_string())
+}
+
+/// The reports of the trees checked in a run, for CI jobs to gate on.
+#[derive(Debug, Default, Serialize)]
+pub struct Summary {
+    pub exit_code: u8,
+    pub healthy: usize,
+    pub gaps: usize,
+    pub missing_from_index: usize,
+    pub errors: usize,
+    pub trees: Vec<TreeReport>,
+}
+
+impl Summary {
+    pub fn push(&mut self, report: TreeReport) {
+        match report.health {
+            TreeHealth::Healthy => self.healthy += 1,
+            TreeHealth::Error => self.errors += 1,
+            TreeHealth::Gaps => self.gaps += 1,
+            TreeHealth::MissingFromIndex => self.missing_from_index += 1,
+        }
+        self.exit_code = self.exit_code.max(report.health.exit_code());
+        self.trees.push(report);
+    }
+
+    pub async fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        tokio::fs::write(path, json).await?;
+        Ok(())
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.exit_code)
+    }
+}
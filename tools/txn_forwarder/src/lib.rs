@@ -1,164 +1,60 @@
+pub use messenger_payload::{
+    decode_payload, decoded_len, encode_payload, PayloadCompression, PayloadError,
+};
+
 use {
     anyhow::Context,
+    das_tree_reader::RpcThrottle,
     futures::stream::{BoxStream, StreamExt},
-    log::{debug, error, info},
+    log::{error, info},
+    nft_ingester::program_transformers::ProgramTransformer,
     plerkle_messenger::TRANSACTION_STREAM,
-    plerkle_serialization::serializer::seralize_encoded_transaction_with_status,
-    serde::de::DeserializeOwned,
-    solana_client::client_error::Result as RpcClientResult,
+    plerkle_serialization::{
+        root_as_transaction_info, serializer::seralize_encoded_transaction_with_status,
+    },
     solana_client::{
         client_error::ClientError, nonblocking::rpc_client::RpcClient,
-        rpc_client::GetConfirmedSignaturesForAddress2Config,
         rpc_request::RpcError::RpcRequestError, rpc_request::RpcRequest,
     },
-    solana_sdk::{
-        pubkey::Pubkey,
-        signature::{ParseSignatureError, Signature},
-    },
+    solana_sdk::signature::Signature,
     solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta,
+    std::io::Result as IoResult,
     std::sync::Arc,
-    std::{fmt, io::Result as IoResult, str::FromStr},
     tokio::sync::Mutex,
     tokio::{
         fs::File,
         io::{stdin, AsyncBufReadExt, BufReader},
-        sync::mpsc,
         time::{sleep, Duration},
     },
     tokio_stream::wrappers::LinesStream,
 };
 
-#[derive(Debug, thiserror::Error)]
-pub enum FindSignaturesError {
-    #[error("Failed to fetch signatures: {0}")]
-    Fetch(#[from] ClientError),
-    #[error("Failed to parse signature: {0}")]
-    Parse(#[from] ParseSignatureError),
-}
-
-pub fn find_signatures(
-    address: Pubkey,
-    client: RpcClient,
-    before: Option<Signature>,
-    after: Option<Signature>,
-    buffer: usize,
-    replay_forward: bool,
-) -> mpsc::Receiver<Result<Signature, FindSignaturesError>> {
-    let (chan, rx) = mpsc::channel(buffer);
-    tokio::spawn(async move {
-        let mut last_signature = before;
-        let mut all_signatures: Vec<Signature> = Vec::new();
-
-        loop {
-            debug!(
-                "fetching signatures for {} before {:?}",
-                address, last_signature
-            );
-            let config = GetConfirmedSignaturesForAddress2Config {
-                before: last_signature,
-                until: after,
-                ..Default::default()
-            };
-
-            let batch = match client
-                .get_signatures_for_address_with_config(&address, config)
-                .await
-            {
-                Ok(vec) => {
-                    info!(
-                        "fetched {} signatures for address {:?} before {:?}",
-                        vec.len(),
-                        address,
-                        last_signature
-                    );
-                    vec
-                }
-                Err(error) => {
-                    chan.send(Err(error.into())).await.map_err(|_| ())?;
-                    break;
-                }
-            };
-
-            // Collect all the signatures in the batch
-            let signatures: Vec<Signature> = batch
-                .into_iter()
-                .filter_map(|tx| Signature::from_str(&tx.signature).ok())
-                .collect();
-
-            if signatures.is_empty() {
-                break;
-            }
-
-            last_signature = signatures.last().cloned();
-            if replay_forward {
-                all_signatures.extend(signatures);
-            } else {
-                for signature in signatures.into_iter() {
-                    chan.send(Ok(signature)).await.map_err(|_| ())?;
-                }
-            }
-        }
-        info!(
-            "sending {} signatures for address {:?}",
-            all_signatures.len(),
-            address
-        );
-
-        if replay_forward {
-            for signature in all_signatures.into_iter().rev() {
-                chan.send(Ok(signature)).await.map_err(|_| ())?;
-            }
-        }
-
-        Ok::<(), ()>(())
-    });
-
-    rx
-}
-
-pub async fn rpc_tx_with_retries<T, E>(
-    client: &RpcClient,
-    request: RpcRequest,
-    value: serde_json::Value,
-    max_retries: u8,
-    error_key: E,
-) -> RpcClientResult<T>
-where
-    T: DeserializeOwned,
-    E: fmt::Debug,
-{
-    let mut retries = 0;
-    let mut delay = Duration::from_millis(500);
-    loop {
-        match client.send(request, value.clone()).await {
-            Ok(value) => return Ok(value),
-            Err(error) => {
-                if retries < max_retries {
-                    error!("retrying {request} {error_key:?}: {error}");
-                    sleep(delay).await;
-                    delay *= 2;
-                    retries += 1;
-                } else {
-                    return Err(error);
-                }
-            }
-        }
-    }
+/// Where fetched transactions are sent.
+#[derive(Clone)]
+pub enum TransactionSink {
+    /// The transaction stream, for a running ingester to index.
+    Stream {
+        messenger: Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
+        compression: PayloadCompression,
+    },
+    /// The ingester's transformers, run in-process against its database.
+    Direct(Arc<ProgramTransformer>),
 }
 
 pub async fn rpc_send_with_retries(
     client: &RpcClient,
+    throttle: &RpcThrottle,
     request: RpcRequest,
     value: serde_json::Value,
     max_retries: u8,
-    messenger: Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
+    sink: &TransactionSink,
     signature: Signature,
 ) -> Result<(), ClientError> {
     let mut retries = 0;
     let mut delay = Duration::from_millis(500);
 
     loop {
-        let response = client.send(request.clone(), value.clone()).await;
+        let response = throttle.run(|| client.send(request, value.clone())).await;
 
         if let Err(error) = response {
             if retries < max_retries {
@@ -173,7 +69,7 @@ pub async fn rpc_send_with_retries(
         }
         let value = response.unwrap();
         let tx: EncodedConfirmedTransactionWithStatusMeta = value;
-        match send(signature, tx, Arc::clone(&messenger)).await {
+        match send(signature, tx, sink).await {
             Ok(_) => return Ok(()),
             Err(e) => {
                 if retries < max_retries {
@@ -199,7 +95,7 @@ pub async fn rpc_send_with_retries(
 async fn send(
     signature: Signature,
     tx: EncodedConfirmedTransactionWithStatusMeta,
-    messenger: Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
+    sink: &TransactionSink,
 ) -> anyhow::Result<()> {
     // Ignore if tx failed or meta is missed
     let meta = tx.transaction.meta.as_ref();
@@ -210,11 +106,26 @@ async fn send(
     let fbb = flatbuffers::FlatBufferBuilder::new();
     let fbb = seralize_encoded_transaction_with_status(fbb, tx)
         .with_context(|| format!("failed to serialize transaction with {}", signature))?;
-    let bytes = fbb.finished_data();
-
-    let mut locked = messenger.lock().await;
-    locked.send(TRANSACTION_STREAM, bytes).await?;
-    info!("Sent transaction to stream {}", signature);
+    match sink {
+        TransactionSink::Stream {
+            messenger,
+            compression,
+        } => {
+            let bytes = encode_payload(fbb.finished_data(), *compression)?;
+            let mut locked = messenger.lock().await;
+            locked.send(TRANSACTION_STREAM, &bytes).await?;
+            info!("Sent transaction to stream {}", signature);
+        }
+        TransactionSink::Direct(transformer) => {
+            let tx = root_as_transaction_info(fbb.finished_data())
+                .with_context(|| format!("failed to read transaction {}", signature))?;
+            transformer
+                .handle_transaction(&tx)
+                .await
+                .with_context(|| format!("failed to transform transaction {}", signature))?;
+            info!("Transformed transaction {}", signature);
+        }
+    }
 
     Ok(())
 }
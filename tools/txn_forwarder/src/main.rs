@@ -1,11 +1,14 @@
 use {
     anyhow::Context,
     clap::Parser,
+    das_tree_reader::{find_signatures_in_window, RpcThrottle, SignatureWindow},
     figment::{util::map, value::Value},
     futures::{
         future::{try_join_all, BoxFuture, FutureExt},
         stream::StreamExt,
     },
+    log::warn,
+    nft_ingester::program_transformers::ProgramTransformer,
     plerkle_messenger::{MessengerConfig, ACCOUNT_STREAM, TRANSACTION_STREAM},
     solana_client::{
         nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig,
@@ -17,18 +20,31 @@ use {
         signature::Signature,
     },
     solana_transaction_status::UiTransactionEncoding,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions},
     std::{env, str::FromStr, sync::Arc},
-    tokio::sync::{mpsc, Mutex},
-    txn_forwarder::{find_signatures, read_lines, rpc_send_with_retries},
+    tokio::{
+        sync::{mpsc, Mutex},
+        task::JoinHandle,
+        time::Duration,
+    },
+    txn_forwarder::{read_lines, rpc_send_with_retries, PayloadCompression, TransactionSink},
 };
 
 #[derive(Parser)]
 #[command(next_line_help = true)]
 struct Cli {
+    #[arg(long, required_unless_present = "direct")]
+    redis_url: Option<String>,
+    /// Run the transactions through the ingester's transformers in-process and write them to
+    /// `--pg-url`, instead of sending them to Redis for a running ingester.
+    #[arg(long, requires = "pg_url")]
+    direct: bool,
+    /// Postgres connection string of the index, for `--direct`.
     #[arg(long)]
-    redis_url: String,
+    pg_url: Option<String>,
     #[arg(long)]
     rpc_url: String,
+    /// Most transactions fetched at once, lowered automatically while the RPC rate limits.
     #[arg(long, short, default_value_t = 25)]
     concurrency: usize,
     #[arg(long, short, default_value_t = 5)]
@@ -41,6 +57,21 @@ struct Cli {
     before: Option<String>,
     #[arg(long)]
     after: Option<String>,
+    /// Only forwards the transactions of an address older than this slot.
+    #[arg(long)]
+    before_slot: Option<u64>,
+    /// Only forwards the transactions of an address newer than this slot.
+    #[arg(long)]
+    after_slot: Option<u64>,
+    /// Only forwards the transactions of an address older than this unix timestamp.
+    #[arg(long)]
+    before_time: Option<i64>,
+    /// Only forwards the transactions of an address newer than this unix timestamp.
+    #[arg(long)]
+    after_time: Option<i64>,
+    /// Compression of the transactions sent to the stream, the ingester reads either.
+    #[arg(long, value_enum, default_value_t = PayloadCompression::None)]
+    compression: PayloadCompression,
 }
 
 #[derive(clap::Subcommand, Clone)]
@@ -74,29 +105,26 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
 
     let cli = Cli::parse();
-    let config_wrapper = Value::from(map! {
-        "redis_connection_str" => cli.redis_url,
-        "pipeline_size_bytes" => 1u128.to_string(),
-    });
-    let config = config_wrapper.into_dict().unwrap();
-
-    let messenenger_config = MessengerConfig {
-        messenger_type: plerkle_messenger::MessengerType::Redis,
-        connection_config: config,
+    let (sink, skipped_tasks) = match (cli.direct, &cli.pg_url, &cli.redis_url) {
+        (true, Some(pg_url), _) => direct_sink(pg_url, cli.concurrency).await?,
+        (_, _, Some(redis_url)) => (stream_sink(redis_url, cli.compression).await?, None),
+        _ => anyhow::bail!("either --redis-url or --direct with --pg-url is required"),
     };
-    let mut messenger = plerkle_messenger::select_messenger(messenenger_config).await?;
-    messenger.add_stream(TRANSACTION_STREAM).await?;
-    messenger.add_stream(ACCOUNT_STREAM).await?;
-    messenger
-        .set_buffer_size(TRANSACTION_STREAM, 10000000000000000)
-        .await;
-    let messenger = Arc::new(Mutex::new(messenger));
+
+    let throttle = RpcThrottle::new(cli.concurrency);
+    throttle.spawn_reporter(Duration::from_secs(10));
 
     let (tx, rx) = mpsc::unbounded_channel();
     let replay_forward = cli.replay_forward;
 
     let before = cli.before.map(|x| Signature::from_str(&x).unwrap());
     let after = cli.after.map(|x| Signature::from_str(&x).unwrap());
+    let window = SignatureWindow {
+        before_slot: cli.before_slot,
+        after_slot: cli.after_slot,
+        before_time: cli.before_time,
+        after_time: cli.after_time,
+    };
 
     match cli.action {
         Action::Address {
@@ -108,10 +136,12 @@ async fn main() -> anyhow::Result<()> {
                 send_address(
                     pubkey,
                     cli.rpc_url,
-                    messenger,
+                    throttle.clone(),
+                    sink.clone(),
                     cli.max_retries,
                     before,
                     after,
+                    window,
                     tx.clone(),
                     replay_forward,
                 )
@@ -125,15 +155,16 @@ async fn main() -> anyhow::Result<()> {
                 let line = maybe_line?;
                 let pubkey = Pubkey::from_str(&line).context("failed to parse address")?;
                 let rpc_url = cli.rpc_url.clone();
-                let messenger = Arc::clone(&messenger);
                 tx.send(
                     send_address(
                         pubkey,
                         rpc_url,
-                        messenger,
+                        throttle.clone(),
+                        sink.clone(),
                         cli.max_retries,
                         before,
                         after,
+                        window,
                         tx.clone(),
                         replay_forward,
                     )
@@ -144,8 +175,17 @@ async fn main() -> anyhow::Result<()> {
         }
         Action::Single { txn } => {
             let sig = Signature::from_str(&txn).context("failed to parse signature")?;
-            tx.send(send_tx(sig, cli.rpc_url, cli.max_retries, messenger).boxed())
-                .map_err(|_| anyhow::anyhow!("failed to send job"))?;
+            tx.send(
+                send_tx(
+                    sig,
+                    cli.rpc_url,
+                    throttle.clone(),
+                    cli.max_retries,
+                    sink.clone(),
+                )
+                .boxed(),
+            )
+            .map_err(|_| anyhow::anyhow!("failed to send job"))?;
         }
         Action::Scenario { scenario_file } => {
             let mut lines = read_lines(&scenario_file).await?;
@@ -153,13 +193,22 @@ async fn main() -> anyhow::Result<()> {
                 let line = maybe_line?;
                 let sig = Signature::from_str(&line).context("failed to parse signature")?;
                 let rpc_url = cli.rpc_url.clone();
-                let messenger = Arc::clone(&messenger);
-                tx.send(send_tx(sig, rpc_url, cli.max_retries, messenger).boxed())
-                    .map_err(|_| anyhow::anyhow!("failed to send job"))?;
+                tx.send(
+                    send_tx(
+                        sig,
+                        rpc_url,
+                        throttle.clone(),
+                        cli.max_retries,
+                        sink.clone(),
+                    )
+                    .boxed(),
+                )
+                .map_err(|_| anyhow::anyhow!("failed to send job"))?;
             }
         }
     }
     drop(tx);
+    drop(sink);
 
     let rx = Arc::new(Mutex::new(rx));
     try_join_all((0..cli.concurrency).map(|_| {
@@ -177,27 +226,100 @@ async fn main() -> anyhow::Result<()> {
             }
         }
     }))
-    .await
-    .map(|_| ())
+    .await?;
+
+    if let Some(skipped_tasks) = skipped_tasks {
+        let skipped = skipped_tasks.await?;
+        if skipped > 0 {
+            warn!(
+                "Skipped {} metadata download tasks, create them with bgtask_creator",
+                skipped
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn stream_sink(
+    redis_url: &str,
+    compression: PayloadCompression,
+) -> anyhow::Result<TransactionSink> {
+    let config_wrapper = Value::from(map! {
+        "redis_connection_str" => redis_url.to_string(),
+        "pipeline_size_bytes" => 1u128.to_string(),
+    });
+    let config = config_wrapper.into_dict().unwrap();
+
+    let messenenger_config = MessengerConfig {
+        messenger_type: plerkle_messenger::MessengerType::Redis,
+        connection_config: config,
+    };
+    let mut messenger = plerkle_messenger::select_messenger(messenenger_config).await?;
+    messenger.add_stream(TRANSACTION_STREAM).await?;
+    messenger.add_stream(ACCOUNT_STREAM).await?;
+    messenger
+        .set_buffer_size(TRANSACTION_STREAM, 10000000000000000)
+        .await;
+    Ok(TransactionSink::Stream {
+        messenger: Arc::new(Mutex::new(messenger)),
+        compression,
+    })
+}
+
+// Metadata downloads are left to the background task runner of an ingester, the tasks the
+// transformers create are only counted.
+async fn direct_sink(
+    pg_url: &str,
+    concurrency: usize,
+) -> anyhow::Result<(TransactionSink, Option<JoinHandle<u64>>)> {
+    let options: PgConnectOptions = pg_url.parse().context("invalid pg url")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(concurrency.max(1) as u32)
+        .connect_with(options)
+        .await?;
+    let (task_sender, mut task_receiver) = mpsc::unbounded_channel();
+    let skipped_tasks = tokio::spawn(async move {
+        let mut skipped = 0;
+        while task_receiver.recv().await.is_some() {
+            skipped += 1;
+        }
+        skipped
+    });
+    let transformer = ProgramTransformer::new(pool, task_sender);
+    Ok((
+        TransactionSink::Direct(Arc::new(transformer)),
+        Some(skipped_tasks),
+    ))
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn send_address(
     pubkey: Pubkey,
     rpc_url: String,
-    messenger: Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
+    throttle: RpcThrottle,
+    sink: TransactionSink,
     max_retries: u8,
     before: Option<Signature>,
     after: Option<Signature>,
+    window: SignatureWindow,
     tasks_tx: mpsc::UnboundedSender<BoxFuture<'static, anyhow::Result<()>>>,
     replay_forward: bool,
 ) -> anyhow::Result<()> {
     let client = RpcClient::new(rpc_url.clone());
-    let mut all_sig = find_signatures(pubkey, client, before, after, 2_000, replay_forward);
+    let mut all_sig = find_signatures_in_window(
+        pubkey,
+        client,
+        throttle.clone(),
+        before,
+        after,
+        window,
+        2_000,
+        replay_forward,
+    );
     while let Some(sig) = all_sig.recv().await {
         let rpc_url = rpc_url.clone();
-        let messenger = Arc::clone(&messenger);
         tasks_tx
-            .send(send_tx(sig?, rpc_url, max_retries, messenger).boxed())
+            .send(send_tx(sig?, rpc_url, throttle.clone(), max_retries, sink.clone()).boxed())
             .map_err(|_| anyhow::anyhow!("failed to send job"))?;
     }
     Ok(())
@@ -206,8 +328,9 @@ async fn send_address(
 async fn send_tx(
     signature: Signature,
     rpc_url: String,
+    throttle: RpcThrottle,
     max_retries: u8,
-    messenger: Arc<Mutex<Box<dyn plerkle_messenger::Messenger>>>,
+    sink: TransactionSink,
 ) -> anyhow::Result<()> {
     const CONFIG: RpcTransactionConfig = RpcTransactionConfig {
         encoding: Some(UiTransactionEncoding::Base64),
@@ -220,10 +343,11 @@ async fn send_tx(
     let client = RpcClient::new(rpc_url);
     rpc_send_with_retries(
         &client,
+        &throttle,
         RpcRequest::GetTransaction,
         serde_json::json!([signature.to_string(), CONFIG,]),
         max_retries,
-        Arc::clone(&messenger),
+        &sink,
         signature,
     )
     .await